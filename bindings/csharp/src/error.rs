@@ -62,6 +62,18 @@ pub enum IronBaseErrorCode {
     /// WAL corruption detected
     WalCorruption = -15,
 
+    /// Another compaction is already running
+    CompactionInProgress = -16,
+
+    /// Another transaction holds a lock on the document
+    WriteConflict = -17,
+
+    /// Insert would exceed the collection's document quota
+    QuotaExceeded = -18,
+
+    /// Existing documents violate a unique index being created
+    DuplicateKey = -19,
+
     /// Unknown/internal error
     Unknown = -99,
 }
@@ -83,6 +95,10 @@ impl From<&MongoLiteError> for IronBaseErrorCode {
             MongoLiteError::TransactionCommitted => IronBaseErrorCode::TransactionCommitted,
             MongoLiteError::TransactionAborted(_) => IronBaseErrorCode::TransactionAborted,
             MongoLiteError::WALCorruption => IronBaseErrorCode::WalCorruption,
+            MongoLiteError::CompactionInProgress => IronBaseErrorCode::CompactionInProgress,
+            MongoLiteError::WriteConflict(_) => IronBaseErrorCode::WriteConflict,
+            MongoLiteError::QuotaExceeded(_) => IronBaseErrorCode::QuotaExceeded,
+            MongoLiteError::DuplicateKey(_) => IronBaseErrorCode::DuplicateKey,
             MongoLiteError::Unknown(_) => IronBaseErrorCode::Unknown,
         }
     }
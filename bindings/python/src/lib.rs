@@ -2,29 +2,54 @@
 // PyO3 0.24 wrapper for ironbase-core
 
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3::types::{PyDict, PyInt, PyList, PyTuple};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use ironbase_core::{CollectionCore, DatabaseCore, DocumentId, DurabilityMode, StorageEngine};
+use ironbase_core::{
+    CollectionCore, DatabaseCore, DocumentId, DurabilityMode, MongoLiteError, OnConflict, PatchOp,
+    StorageEngine, TextSearchMode, Tokenizer,
+};
+
+// Specific error types; all derive from RuntimeError so `except RuntimeError` keeps working
+pyo3::create_exception!(
+    ironbase,
+    DuplicateKeyError,
+    pyo3::exceptions::PyRuntimeError
+);
+pyo3::create_exception!(ironbase, ValidationError, pyo3::exceptions::PyRuntimeError);
+pyo3::create_exception!(
+    ironbase,
+    DocumentNotFoundError,
+    pyo3::exceptions::PyRuntimeError
+);
+pyo3::create_exception!(ironbase, TransactionError, pyo3::exceptions::PyRuntimeError);
 
 /// IronBase Database - Python wrapper
 #[pyclass]
 pub struct IronBase {
     db: Arc<DatabaseCore<StorageEngine>>,
+    exact_numbers: bool,
 }
 
 #[pymethods]
 impl IronBase {
     /// Create or open a database
+    ///
+    /// With `exact_numbers=True`, ints beyond 64 bits and `decimal.Decimal`
+    /// values are stored as `{"$numberDecimal": "<text>"}` and read back as
+    /// `int` / `Decimal` instead of being rounded to a float. They compare,
+    /// sort and match exactly but take no part in arithmetic (`$inc`,
+    /// `$sum`, ...), and queries filtering on them don't use index scans.
     #[new]
-    #[pyo3(signature = (path, durability="safe", batch_size=100, auto_checkpoint=None))]
+    #[pyo3(signature = (path, durability="safe", batch_size=100, auto_checkpoint=None, exact_numbers=false))]
     fn new(
         path: String,
         durability: &str,
         batch_size: usize,
         auto_checkpoint: Option<usize>,
+        exact_numbers: bool,
     ) -> PyResult<Self> {
         let mode = match durability {
             "safe" => DurabilityMode::Safe,
@@ -47,21 +72,37 @@ impl IronBase {
         let db = DatabaseCore::open_with_durability(&path, mode)
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
 
-        Ok(IronBase { db: Arc::new(db) })
+        Ok(IronBase {
+            db: Arc::new(db),
+            exact_numbers,
+        })
     }
 
     /// Get or create a collection
-    fn collection(&self, name: String) -> PyResult<Collection> {
-        let coll_core = self
-            .db
-            .collection(&name)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+    ///
+    /// With `create=False` a missing collection raises instead of being
+    /// created, so a misspelled name doesn't leave an empty collection.
+    #[pyo3(signature = (name, create=true))]
+    fn collection(&self, name: String, create: bool) -> PyResult<Collection> {
+        let coll_core = if create {
+            self.db.collection(&name)
+        } else {
+            self.db.existing_collection(&name)
+        }
+        .map_err(to_py_err)?;
 
-        Ok(Collection {
-            core: coll_core,
-            db: Arc::clone(&self.db),
-            name: name.clone(),
-        })
+        Ok(self.wrap_collection(coll_core, name))
+    }
+
+    /// True if the collection exists
+    fn collection_exists(&self, name: String) -> bool {
+        self.db.collection_exists(&name)
+    }
+
+    /// Create a collection; raises if it already exists
+    fn create_collection(&self, name: String) -> PyResult<Collection> {
+        let coll_core = self.db.create_collection(&name).map_err(to_py_err)?;
+        Ok(self.wrap_collection(coll_core, name))
     }
 
     /// List all collections
@@ -69,6 +110,31 @@ impl IronBase {
         Ok(self.db.list_collections())
     }
 
+    /// List collections with their document counts, indexes and on-disk size
+    fn collections_info<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let mut names = self.db.list_collections();
+        names.sort();
+
+        let result = PyList::empty(py);
+        for name in names {
+            let coll = self.db.collection(&name).map_err(to_py_err)?;
+            let count = coll
+                .count_documents(&serde_json::json!({}))
+                .map_err(to_py_err)?;
+            let size_bytes = self.db.collection_size_bytes(&name).map_err(to_py_err)?;
+
+            let info = PyDict::new(py);
+            info.set_item("name", &name)?;
+            info.set_item("count", count)?;
+            info.set_item("indexes", coll.list_indexes())?;
+            info.set_item("has_schema", coll.get_schema().is_some())?;
+            info.set_item("size_bytes", size_bytes)?;
+            result.append(info)?;
+        }
+
+        Ok(result)
+    }
+
     /// Set or clear JSON schema for a collection
     fn set_collection_schema(
         &self,
@@ -77,20 +143,18 @@ impl IronBase {
         schema: Option<Bound<'_, PyDict>>,
     ) -> PyResult<()> {
         let schema_json = match schema {
-            Some(dict) => Some(python_dict_to_json_value(py, &dict)?),
+            Some(dict) => Some(python_dict_to_json_value(py, &dict, self.exact_numbers)?),
             None => None,
         };
 
         self.db
             .set_collection_schema(&name, schema_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+            .map_err(to_py_err)
     }
 
     /// Drop a collection
     fn drop_collection(&self, name: String) -> PyResult<()> {
-        self.db
-            .drop_collection(&name)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.db.drop_collection(&name).map_err(to_py_err)
     }
 
     /// Close and flush database
@@ -107,6 +171,17 @@ impl IronBase {
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
     }
 
+    /// Delete documents past their TTL index expiry; returns the count
+    fn run_ttl_sweep_now(&self) -> PyResult<u64> {
+        self.db.run_ttl_sweep_now().map_err(to_py_err)
+    }
+
+    /// Sweep expired TTL documents in the background every `secs` seconds
+    /// (0 stops the sweeper)
+    fn set_ttl_sweep_interval(&self, secs: u64) {
+        self.db.set_ttl_sweep_interval(secs);
+    }
+
     /// Get database statistics
     fn stats(&self) -> PyResult<String> {
         Ok(serde_json::to_string_pretty(&self.db.stats()).unwrap())
@@ -135,21 +210,16 @@ impl IronBase {
 
     /// Storage compaction
     fn compact<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
-        let stats = self
-            .db
-            .compact()
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let stats = self.db.compact().map_err(to_py_err)?;
+        compaction_stats_dict(py, &stats)
+    }
 
-        let dict = PyDict::new(py);
-        dict.set_item("size_before", stats.size_before)?;
-        dict.set_item("size_after", stats.size_after)?;
-        dict.set_item("space_saved", stats.space_saved())?;
-        dict.set_item("documents_scanned", stats.documents_scanned)?;
-        dict.set_item("documents_kept", stats.documents_kept)?;
-        dict.set_item("tombstones_removed", stats.tombstones_removed)?;
-        dict.set_item("peak_memory_mb", stats.peak_memory_mb)?;
-        dict.set_item("compression_ratio", stats.compression_ratio())?;
-        Ok(dict)
+    /// Storage compaction that verifies the compacted file before swapping it in
+    ///
+    /// Raises RuntimeError (keeping the original file) if verification fails.
+    fn compact_verified<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = self.db.compact_verified().map_err(to_py_err)?;
+        compaction_stats_dict(py, &stats)
     }
 
     fn __repr__(&self) -> String {
@@ -158,6 +228,22 @@ impl IronBase {
 
     // ========== ACD TRANSACTION API ==========
 
+    /// Begin a transaction scoped to a `with` block
+    ///
+    /// The block's transaction commits when it exits normally and rolls
+    /// back when it raises:
+    ///
+    ///     with db.transaction() as tx:
+    ///         tx.insert_one("users", {"name": "Alice"})
+    fn transaction(slf: Bound<'_, Self>) -> Transaction {
+        let tx_id = slf.borrow().db.begin_transaction();
+        Transaction {
+            db: slf.unbind(),
+            tx_id,
+            finished: false,
+        }
+    }
+
     /// Begin a new transaction
     fn begin_transaction(&self) -> PyResult<u64> {
         Ok(self.db.begin_transaction())
@@ -165,16 +251,12 @@ impl IronBase {
 
     /// Commit a transaction
     fn commit_transaction(&self, tx_id: u64) -> PyResult<()> {
-        self.db
-            .commit_transaction(tx_id)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.db.commit_transaction(tx_id).map_err(to_py_err)
     }
 
     /// Rollback a transaction
     fn rollback_transaction(&self, tx_id: u64) -> PyResult<()> {
-        self.db
-            .rollback_transaction(tx_id)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.db.rollback_transaction(tx_id).map_err(to_py_err)
     }
 
     /// Insert one document within a transaction
@@ -188,14 +270,14 @@ impl IronBase {
         let mut doc_map: HashMap<String, Value> = HashMap::new();
         for (key, value) in document.iter() {
             let key_str: String = key.extract()?;
-            let json_value = python_to_json(py, &value)?;
+            let json_value = python_to_json(py, &value, self.exact_numbers)?;
             doc_map.insert(key_str, json_value);
         }
 
         let inserted_id = self
             .db
             .insert_one_tx(&collection_name, doc_map, tx_id)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -213,13 +295,13 @@ impl IronBase {
         new_doc: Bound<'_, PyDict>,
         tx_id: u64,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
-        let new_doc_json = python_dict_to_json_value(py, &new_doc)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
+        let new_doc_json = python_dict_to_json_value(py, &new_doc, self.exact_numbers)?;
 
         let (matched_count, modified_count) = self
             .db
             .update_one_tx(&collection_name, &query_json, new_doc_json, tx_id)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -228,6 +310,26 @@ impl IronBase {
         Ok(result)
     }
 
+    /// Lock a document for update until the transaction ends
+    ///
+    /// Raises TransactionError if another transaction holds the lock.
+    /// Writes outside a transaction do not check the lock.
+    fn lock_for_update(
+        &self,
+        py: Python<'_>,
+        collection_name: String,
+        id: Bound<'_, PyAny>,
+        tx_id: u64,
+    ) -> PyResult<()> {
+        let id: DocumentId = serde_json::from_value(python_to_json(py, &id, self.exact_numbers)?)
+            .map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>("id must be an int or str")
+        })?;
+        self.db
+            .lock_for_update(tx_id, &collection_name, &id)
+            .map_err(to_py_err)
+    }
+
     /// Delete one document within a transaction
     fn delete_one_tx<'py>(
         &self,
@@ -236,12 +338,12 @@ impl IronBase {
         query: Bound<'_, PyDict>,
         tx_id: u64,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
 
         let deleted_count = self
             .db
             .delete_one_tx(&collection_name, &query_json, tx_id)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -250,12 +352,148 @@ impl IronBase {
     }
 }
 
+impl IronBase {
+    fn wrap_collection(&self, core: CollectionCore<StorageEngine>, name: String) -> Collection {
+        Collection {
+            core,
+            db: Arc::clone(&self.db),
+            name,
+            exact_numbers: self.exact_numbers,
+        }
+    }
+}
+
+/// Transaction handle returned by `IronBase.transaction()`
+///
+/// Wraps the raw transaction id; commits or rolls back at most once.
+#[pyclass]
+pub struct Transaction {
+    db: Py<IronBase>,
+    tx_id: u64,
+    finished: bool,
+}
+
+impl Transaction {
+    /// Fail if the transaction was already committed or rolled back
+    fn active_id(&self) -> PyResult<u64> {
+        if self.finished {
+            return Err(TransactionError::new_err(format!(
+                "Transaction {} already finished",
+                self.tx_id
+            )));
+        }
+        Ok(self.tx_id)
+    }
+}
+
+#[pymethods]
+impl Transaction {
+    /// The underlying transaction id
+    #[getter]
+    fn id(&self) -> u64 {
+        self.tx_id
+    }
+
+    /// Insert one document within the transaction
+    fn insert_one<'py>(
+        &self,
+        py: Python<'py>,
+        collection_name: String,
+        document: Bound<'_, PyDict>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let tx_id = self.active_id()?;
+        self.db
+            .borrow(py)
+            .insert_one_tx(py, collection_name, document, tx_id)
+    }
+
+    /// Update one document within the transaction
+    fn update_one<'py>(
+        &self,
+        py: Python<'py>,
+        collection_name: String,
+        query: Bound<'_, PyDict>,
+        new_doc: Bound<'_, PyDict>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let tx_id = self.active_id()?;
+        self.db
+            .borrow(py)
+            .update_one_tx(py, collection_name, query, new_doc, tx_id)
+    }
+
+    /// Delete one document within the transaction
+    fn delete_one<'py>(
+        &self,
+        py: Python<'py>,
+        collection_name: String,
+        query: Bound<'_, PyDict>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let tx_id = self.active_id()?;
+        self.db
+            .borrow(py)
+            .delete_one_tx(py, collection_name, query, tx_id)
+    }
+
+    /// Commit the transaction
+    fn commit(&mut self, py: Python<'_>) -> PyResult<()> {
+        let tx_id = self.active_id()?;
+        self.finished = true;
+        self.db.borrow(py).commit_transaction(tx_id)
+    }
+
+    /// Roll the transaction back
+    fn rollback(&mut self, py: Python<'_>) -> PyResult<()> {
+        let tx_id = self.active_id()?;
+        self.finished = true;
+        self.db.borrow(py).rollback_transaction(tx_id)
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Commit on a clean exit, roll back if the block raised
+    ///
+    /// Returns False so the block's exception propagates.
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        exc_type: Option<Bound<'_, PyAny>>,
+        _exc_value: Option<Bound<'_, PyAny>>,
+        _traceback: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        if !self.finished {
+            if exc_type.is_some_and(|t| !t.is_none()) {
+                self.rollback(py)?;
+            } else {
+                self.commit(py)?;
+            }
+        }
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Transaction({})", self.tx_id)
+    }
+}
+
 /// Collection wrapper
 #[pyclass]
 pub struct Collection {
     core: CollectionCore<StorageEngine>,
     db: Arc<DatabaseCore<StorageEngine>>,
     name: String,
+    exact_numbers: bool,
+}
+
+impl Collection {
+    /// A new handle on this collection
+    ///
+    /// Text index postings live in each handle's memory, so this handle's
+    /// copy misses writes made through the database since it was opened.
+    fn fresh_core(&self) -> PyResult<CollectionCore<StorageEngine>> {
+        self.db.collection(&self.name).map_err(to_py_err)
+    }
 }
 
 #[pymethods]
@@ -263,13 +501,11 @@ impl Collection {
     /// Set or clear JSON schema
     fn set_schema(&self, py: Python<'_>, schema: Option<Bound<'_, PyDict>>) -> PyResult<()> {
         let schema_json = match schema {
-            Some(dict) => Some(python_dict_to_json_value(py, &dict)?),
+            Some(dict) => Some(python_dict_to_json_value(py, &dict, self.exact_numbers)?),
             None => None,
         };
 
-        self.core
-            .set_schema(schema_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.core.set_schema(schema_json).map_err(to_py_err)
     }
 
     /// Get current JSON schema
@@ -281,24 +517,56 @@ impl Collection {
         }
     }
 
+    /// Set or clear (None) the maximum number of documents the collection may hold
+    ///
+    /// Inserts past the quota raise RuntimeError.
+    #[pyo3(signature = (max_documents=None))]
+    fn set_max_documents(&self, max_documents: Option<u64>) -> PyResult<()> {
+        self.core
+            .set_max_documents(max_documents)
+            .map_err(to_py_err)
+    }
+
+    /// Get the collection's document quota (None when unlimited)
+    fn max_documents(&self) -> Option<u64> {
+        self.core.max_documents()
+    }
+
     /// Insert one document
+    ///
+    /// `on_conflict` decides what a duplicate `_id` does: "error" (default)
+    /// raises, "replace" overwrites the stored document, "ignore" keeps it.
+    #[pyo3(signature = (document, on_conflict="error"))]
     fn insert_one<'py>(
         &self,
         py: Python<'py>,
         document: Bound<'_, PyDict>,
+        on_conflict: &str,
     ) -> PyResult<Bound<'py, PyDict>> {
+        let policy = match on_conflict {
+            "error" => OnConflict::Error,
+            "replace" => OnConflict::Replace,
+            "ignore" => OnConflict::Ignore,
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid on_conflict '{}'. Must be 'error', 'replace', or 'ignore'",
+                    on_conflict
+                )));
+            }
+        };
+
         let mut doc_map: HashMap<String, Value> = HashMap::new();
 
         for (key, value) in document.iter() {
             let key_str: String = key.extract()?;
-            let json_value = python_to_json(py, &value)?;
+            let json_value = python_to_json(py, &value, self.exact_numbers)?;
             doc_map.insert(key_str, json_value);
         }
 
         let inserted_id = self
             .db
-            .insert_one(&self.name, doc_map)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .insert_one_with_conflict(&self.name, doc_map, policy)
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -307,6 +575,34 @@ impl Collection {
         Ok(result)
     }
 
+    /// Insert `document` only if no document matches `query`
+    ///
+    /// Returns {"inserted": bool, "id": ...}; `id` is the new document's
+    /// `_id`, or the existing match's when nothing was inserted.
+    fn insert_if_absent<'py>(
+        &self,
+        py: Python<'py>,
+        query: Bound<'_, PyDict>,
+        document: Bound<'_, PyDict>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
+        let mut doc_map: HashMap<String, Value> = HashMap::new();
+        for (key, value) in document.iter() {
+            let key_str: String = key.extract()?;
+            doc_map.insert(key_str, python_to_json(py, &value, self.exact_numbers)?);
+        }
+
+        let (inserted, doc_id) = self
+            .db
+            .insert_if_absent(&self.name, &query_json, doc_map)
+            .map_err(to_py_err)?;
+
+        let result = PyDict::new(py);
+        result.set_item("inserted", inserted)?;
+        result.set_item("id", doc_id_to_py(py, &doc_id)?)?;
+        Ok(result)
+    }
+
     /// Insert many documents
     fn insert_many<'py>(
         &self,
@@ -320,17 +616,14 @@ impl Collection {
 
             for (key, value) in doc_dict.iter() {
                 let key_str: String = key.extract()?;
-                let value_json = python_to_json(py, &value)?;
+                let value_json = python_to_json(py, &value, self.exact_numbers)?;
                 fields.insert(key_str, value_json);
             }
 
             docs.push(fields);
         }
 
-        let inserted_ids = self
-            .db
-            .insert_many(&self.name, docs)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let inserted_ids = self.db.insert_many(&self.name, docs).map_err(to_py_err)?;
 
         let result_dict = PyDict::new(py);
         result_dict.set_item("acknowledged", true)?;
@@ -357,43 +650,49 @@ impl Collection {
         limit: Option<usize>,
         skip: Option<usize>,
     ) -> PyResult<Bound<'py, PyList>> {
-        use ironbase_core::find_options::FindOptions;
-
         let query_json = match query {
-            Some(q) => python_dict_to_json_value(py, &q)?,
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
             None => serde_json::json!({}),
         };
+        let options = find_options(projection, sort, limit, skip)?;
 
-        let mut options = FindOptions::new();
-
-        if let Some(proj) = projection {
-            let mut projection_map = HashMap::new();
-            for (key, value) in proj.iter() {
-                let field: String = key.extract()?;
-                let action: i32 = value.extract()?;
-                projection_map.insert(field, action);
-            }
-            options.projection = Some(projection_map);
+        let results = if query_json.get("$text").is_some() {
+            self.fresh_core()?.find_with_options(&query_json, options)
+        } else {
+            self.core.find_with_options(&query_json, options)
         }
+        .map_err(to_py_err)?;
 
-        if let Some(sort_list) = sort {
-            let mut sort_vec = Vec::new();
-            for item in sort_list.iter() {
-                let tuple = item.downcast::<PyTuple>()?;
-                let field: String = tuple.get_item(0)?.extract()?;
-                let direction: i32 = tuple.get_item(1)?.extract()?;
-                sort_vec.push((field, direction));
-            }
-            options.sort = Some(sort_vec);
+        let py_list = PyList::empty(py);
+        for doc in results {
+            let py_dict = json_to_python_dict(py, &doc)?;
+            py_list.append(py_dict)?;
         }
 
-        options.limit = limit;
-        options.skip = skip;
+        Ok(py_list)
+    }
 
-        let results = self
+    /// Find one page of documents plus the total match count: (docs, total)
+    #[pyo3(signature = (query=None, projection=None, sort=None, limit=None, skip=None))]
+    fn find_page<'py>(
+        &self,
+        py: Python<'py>,
+        query: Option<Bound<'_, PyDict>>,
+        projection: Option<Bound<'_, PyDict>>,
+        sort: Option<Bound<'_, PyList>>,
+        limit: Option<usize>,
+        skip: Option<usize>,
+    ) -> PyResult<(Bound<'py, PyList>, u64)> {
+        let query_json = match query {
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
+            None => serde_json::json!({}),
+        };
+        let options = find_options(projection, sort, limit, skip)?;
+
+        let (results, total) = self
             .core
-            .find_with_options(&query_json, options)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .find_page(&query_json, options)
+            .map_err(to_py_err)?;
 
         let py_list = PyList::empty(py);
         for doc in results {
@@ -401,7 +700,7 @@ impl Collection {
             py_list.append(py_dict)?;
         }
 
-        Ok(py_list)
+        Ok((py_list, total))
     }
 
     /// Find one document
@@ -411,14 +710,11 @@ impl Collection {
         query: Option<Bound<'_, PyDict>>,
     ) -> PyResult<PyObject> {
         let query_json = match query {
-            Some(q) => python_dict_to_json_value(py, &q)?,
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
             None => serde_json::json!({}),
         };
 
-        let result = self
-            .core
-            .find_one(&query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let result = self.core.find_one(&query_json).map_err(to_py_err)?;
 
         match result {
             Some(doc) => {
@@ -432,13 +728,11 @@ impl Collection {
     /// Count documents
     fn count_documents(&self, py: Python<'_>, query: Option<Bound<'_, PyDict>>) -> PyResult<u64> {
         let query_json = match query {
-            Some(q) => python_dict_to_json_value(py, &q)?,
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
             None => serde_json::json!({}),
         };
 
-        self.core
-            .count_documents(&query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.core.count_documents(&query_json).map_err(to_py_err)
     }
 
     /// Distinct values
@@ -449,14 +743,11 @@ impl Collection {
         query: Option<Bound<'_, PyDict>>,
     ) -> PyResult<Bound<'py, PyList>> {
         let query_json = match query {
-            Some(q) => python_dict_to_json_value(py, &q)?,
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
             None => serde_json::json!({}),
         };
 
-        let distinct_values = self
-            .core
-            .distinct(field, &query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let distinct_values = self.core.distinct(field, &query_json).map_err(to_py_err)?;
 
         let py_list = PyList::empty(py);
         for value in distinct_values {
@@ -466,20 +757,70 @@ impl Collection {
         Ok(py_list)
     }
 
-    /// Update one document
+    /// Update one document; with upsert=True insert one if nothing matches
+    ///
+    /// The inserted document combines the query's equality conditions with
+    /// the update, including $setOnInsert; its id is returned as upserted_id.
+    #[pyo3(signature = (query, update, upsert=false))]
     fn update_one<'py>(
         &self,
         py: Python<'py>,
         query: Bound<'_, PyDict>,
         update: Bound<'_, PyDict>,
+        upsert: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
+        let update_json = python_dict_to_json_value(py, &update, self.exact_numbers)?;
+
+        let (matched_count, modified_count, upserted_id) = if upsert {
+            self.db
+                .update_one_upsert(&self.name, &query_json, &update_json)
+                .map_err(to_py_err)?
+        } else {
+            let (matched, modified) = self
+                .db
+                .update_one(&self.name, &query_json, &update_json)
+                .map_err(to_py_err)?;
+            (matched, modified, None)
+        };
+
+        let result = PyDict::new(py);
+        result.set_item("acknowledged", true)?;
+        result.set_item("matched_count", matched_count)?;
+        result.set_item("modified_count", modified_count)?;
+        let upserted_id = match upserted_id {
+            Some(doc_id) => Some(doc_id_to_py(py, &doc_id)?),
+            None => None,
+        };
+        result.set_item("upserted_id", upserted_id)?;
+        Ok(result)
+    }
+
+    /// Apply a JSON Patch (list of RFC 6902 operations) to the first matching document
+    fn patch_one<'py>(
+        &self,
+        py: Python<'py>,
+        query: Bound<'_, PyDict>,
+        patch: Bound<'_, PyList>,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
-        let update_json = python_dict_to_json_value(py, &update)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
+        let mut ops = Vec::with_capacity(patch.len());
+        for op in patch.iter() {
+            let op_json =
+                python_dict_to_json_value(py, op.downcast::<PyDict>()?, self.exact_numbers)?;
+            let op: PatchOp = serde_json::from_value(op_json).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid JSON Patch operation: {}",
+                    e
+                ))
+            })?;
+            ops.push(op);
+        }
 
         let (matched_count, modified_count) = self
             .db
-            .update_one(&self.name, &query_json, &update_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .patch_one(&self.name, &query_json, &ops)
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -495,13 +836,13 @@ impl Collection {
         query: Bound<'_, PyDict>,
         update: Bound<'_, PyDict>,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
-        let update_json = python_dict_to_json_value(py, &update)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
+        let update_json = python_dict_to_json_value(py, &update, self.exact_numbers)?;
 
         let (matched_count, modified_count) = self
             .db
             .update_many(&self.name, &query_json, &update_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -510,18 +851,57 @@ impl Collection {
         Ok(result)
     }
 
+    /// Rewrite every document through `transform` and return how many changed
+    ///
+    /// `transform` gets each document as a dict and returns the new dict,
+    /// or None to keep the (possibly modified in place) argument. An
+    /// exception from it aborts the migration before anything is written.
+    fn migrate(&self, py: Python<'_>, transform: Bound<'_, PyAny>) -> PyResult<u64> {
+        let mut py_err = None;
+        let result = self.core.try_migrate(|doc| {
+            let apply = || -> PyResult<Value> {
+                let dict = json_to_python_dict(py, doc)?;
+                let returned = transform.call1((&dict,))?;
+                if returned.is_none() {
+                    python_dict_to_json_value(py, &dict, self.exact_numbers)
+                } else {
+                    python_dict_to_json_value(
+                        py,
+                        returned.downcast::<PyDict>()?,
+                        self.exact_numbers,
+                    )
+                }
+            };
+            match apply() {
+                Ok(new_doc) => {
+                    *doc = new_doc;
+                    Ok(())
+                }
+                Err(err) => {
+                    let message = err.to_string();
+                    py_err = Some(err);
+                    Err(MongoLiteError::InvalidQuery(message))
+                }
+            }
+        });
+        match (result, py_err) {
+            (_, Some(err)) => Err(err),
+            (result, None) => result.map_err(to_py_err),
+        }
+    }
+
     /// Delete one document
     fn delete_one<'py>(
         &self,
         py: Python<'py>,
         query: Bound<'_, PyDict>,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
 
         let deleted_count = self
             .db
             .delete_one(&self.name, &query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -535,12 +915,12 @@ impl Collection {
         py: Python<'py>,
         query: Bound<'_, PyDict>,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
 
         let deleted_count = self
             .db
             .delete_many(&self.name, &query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let result = PyDict::new(py);
         result.set_item("acknowledged", true)?;
@@ -551,9 +931,15 @@ impl Collection {
     /// Create an index
     #[pyo3(signature = (field, unique=false))]
     fn create_index(&self, field: String, unique: bool) -> PyResult<String> {
+        self.core.create_index(field, unique).map_err(to_py_err)
+    }
+
+    /// Create a TTL index: documents expire `expire_after_secs` after the
+    /// Unix timestamp (seconds) stored in `field`
+    fn create_ttl_index(&self, field: String, expire_after_secs: u64) -> PyResult<String> {
         self.core
-            .create_index(field, unique)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+            .create_ttl_index(field, expire_after_secs)
+            .map_err(to_py_err)
     }
 
     /// Create a compound index
@@ -567,14 +953,56 @@ impl Collection {
 
         self.core
             .create_compound_index(fields, unique)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+            .map_err(to_py_err)
+    }
+
+    /// Create the collection's full-text index over string fields
+    ///
+    /// `tokenizer` is "standard" (runs of letters and digits, the default)
+    /// or "whitespace". A collection has at most one text index.
+    #[pyo3(signature = (fields, tokenizer=None))]
+    fn create_text_index(
+        &self,
+        fields: Vec<String>,
+        tokenizer: Option<String>,
+    ) -> PyResult<String> {
+        let tokenizer = match tokenizer {
+            Some(name) => name.parse::<Tokenizer>().map_err(to_py_err)?,
+            None => Tokenizer::default(),
+        };
+        self.core
+            .create_text_index(fields, tokenizer)
+            .map_err(to_py_err)
+    }
+
+    /// Search the text index, best matches first
+    ///
+    /// `mode` is "and" (documents with every term) or "or" (any term). Each
+    /// result carries its relevance score under `_score`.
+    #[pyo3(signature = (query, mode="and", limit=None))]
+    fn fulltext_search<'py>(
+        &self,
+        py: Python<'py>,
+        query: String,
+        mode: &str,
+        limit: Option<usize>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let mode = mode.parse::<TextSearchMode>().map_err(to_py_err)?;
+        let results = self
+            .fresh_core()?
+            .fulltext_search(&query, mode, limit)
+            .map_err(to_py_err)?;
+
+        let py_list = PyList::empty(py);
+        for doc in results {
+            py_list.append(json_to_python_dict(py, &doc)?)?;
+        }
+        Ok(py_list)
     }
 
     /// Drop an index
     fn drop_index(&self, index_name: String) -> PyResult<()> {
-        self.core
-            .drop_index(&index_name)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+        self.core.drop_index(&index_name).map_err(to_py_err)
     }
 
     /// List all indexes
@@ -582,18 +1010,42 @@ impl Collection {
         Ok(self.core.list_indexes())
     }
 
-    /// Explain query
+    /// Describe the index usable for queries on a field, or None
+    ///
+    /// Returns a dict with name, fields, unique, sparse and compound.
+    fn has_index_on<'py>(
+        &self,
+        py: Python<'py>,
+        field: String,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.core
+            .has_index_on(&field)
+            .map(|info| index_info_dict(py, &info))
+            .transpose()
+    }
+
+    /// Fields covered by any index, sorted
+    fn indexed_fields(&self) -> PyResult<Vec<String>> {
+        Ok(self.core.indexed_fields())
+    }
+
+    /// Explain query; with execute=True the query is also run and
+    /// executionStats (nReturned, docsExamined, indexUsed) are included
+    #[pyo3(signature = (query, execute=false))]
     fn explain<'py>(
         &self,
         py: Python<'py>,
         query: Bound<'_, PyDict>,
+        execute: bool,
     ) -> PyResult<Bound<'py, PyDict>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
 
-        let plan = self
-            .core
-            .explain(&query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let plan = if execute {
+            self.core.explain_execution(&query_json)
+        } else {
+            self.core.explain(&query_json)
+        }
+        .map_err(to_py_err)?;
 
         json_to_python_dict(py, &plan)
     }
@@ -605,12 +1057,12 @@ impl Collection {
         query: Bound<'_, PyDict>,
         hint: String,
     ) -> PyResult<Bound<'py, PyList>> {
-        let query_json = python_dict_to_json_value(py, &query)?;
+        let query_json = python_dict_to_json_value(py, &query, self.exact_numbers)?;
 
         let results = self
             .core
             .find_with_hint(&query_json, &hint)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .map_err(to_py_err)?;
 
         let py_list = PyList::empty(py);
         for doc in results {
@@ -630,16 +1082,17 @@ impl Collection {
         let mut stages = Vec::new();
         for stage in pipeline.iter() {
             let stage_dict = stage.downcast::<PyDict>()?;
-            let stage_json = python_dict_to_json_value(py, stage_dict)?;
+            let stage_json = python_dict_to_json_value(py, stage_dict, self.exact_numbers)?;
             stages.push(stage_json);
         }
 
         let pipeline_json = serde_json::Value::Array(stages);
 
+        // Through the database, so a `$out` stage is logged to the WAL
         let results = self
-            .core
-            .aggregate(&pipeline_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+            .db
+            .aggregate(&self.name, &pipeline_json)
+            .map_err(to_py_err)?;
 
         let py_list = PyList::empty(py);
         for doc in results {
@@ -659,14 +1112,11 @@ impl Collection {
         batch_size: usize,
     ) -> PyResult<Cursor> {
         let query_json = match query {
-            Some(q) => python_dict_to_json_value(py, &q)?,
+            Some(q) => python_dict_to_json_value(py, &q, self.exact_numbers)?,
             None => serde_json::json!({}),
         };
 
-        let results = self
-            .core
-            .find(&query_json)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        let results = self.core.find(&query_json).map_err(to_py_err)?;
 
         Ok(Cursor {
             documents: results,
@@ -816,6 +1266,59 @@ impl Cursor {
 
 // ========== HELPER FUNCTIONS ==========
 
+/// Convert a core error to the matching Python exception
+fn to_py_err(e: MongoLiteError) -> PyErr {
+    let message = e.to_string();
+    match e {
+        MongoLiteError::IndexError(ref msg) if msg.starts_with("Duplicate key") => {
+            DuplicateKeyError::new_err(message)
+        }
+        MongoLiteError::DuplicateKey(_) => DuplicateKeyError::new_err(message),
+        MongoLiteError::SchemaError(_) => ValidationError::new_err(message),
+        MongoLiteError::DocumentNotFound => DocumentNotFoundError::new_err(message),
+        MongoLiteError::TransactionCommitted
+        | MongoLiteError::TransactionAborted(_)
+        | MongoLiteError::WriteConflict(_) => TransactionError::new_err(message),
+        _ => PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(message),
+    }
+}
+
+/// Convert compaction statistics to a Python dict
+fn compaction_stats_dict<'py>(
+    py: Python<'py>,
+    stats: &ironbase_core::CompactionStats,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("size_before", stats.size_before)?;
+    dict.set_item("size_after", stats.size_after)?;
+    dict.set_item("space_saved", stats.space_saved())?;
+    dict.set_item("documents_scanned", stats.documents_scanned)?;
+    dict.set_item("documents_kept", stats.documents_kept)?;
+    dict.set_item("tombstones_removed", stats.tombstones_removed)?;
+    dict.set_item("peak_memory_mb", stats.peak_memory_mb)?;
+    dict.set_item("compression_ratio", stats.compression_ratio())?;
+    if let Some(verification) = stats.verification {
+        dict.set_item("verified", verification.passed())?;
+        dict.set_item("verified_documents", verification.actual_documents)?;
+        dict.set_item("verified_checksum", verification.actual_checksum)?;
+    }
+    Ok(dict)
+}
+
+/// Convert an index description to a Python dict
+fn index_info_dict<'py>(
+    py: Python<'py>,
+    info: &ironbase_core::index::IndexInfo,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("name", &info.name)?;
+    dict.set_item("fields", &info.fields)?;
+    dict.set_item("unique", info.unique)?;
+    dict.set_item("sparse", info.sparse)?;
+    dict.set_item("compound", info.is_compound())?;
+    Ok(dict)
+}
+
 /// Convert DocumentId to Python value
 fn doc_id_to_py(py: Python<'_>, id: &DocumentId) -> PyResult<PyObject> {
     match id {
@@ -826,14 +1329,35 @@ fn doc_id_to_py(py: Python<'_>, id: &DocumentId) -> PyResult<PyObject> {
 }
 
 /// Python value -> JSON
+///
+/// `exact` keeps ints beyond 64 bits and `decimal.Decimal` values as tagged
+/// decimals (see `ironbase_core::decimal`) instead of rounding them to f64.
 #[allow(clippy::only_used_in_recursion)]
-fn python_to_json(py: Python<'_>, value: &Bound<'_, pyo3::PyAny>) -> PyResult<Value> {
+fn python_to_json(py: Python<'_>, value: &Bound<'_, pyo3::PyAny>, exact: bool) -> PyResult<Value> {
     if value.is_none() {
         Ok(Value::Null)
     } else if let Ok(b) = value.extract::<bool>() {
         Ok(Value::Bool(b))
     } else if let Ok(i) = value.extract::<i64>() {
         Ok(Value::Number(i.into()))
+    } else if let Ok(u) = value.extract::<u64>() {
+        Ok(Value::Number(u.into()))
+    } else if exact && value.is_instance_of::<PyInt>() {
+        decimal_to_json(&value.str()?.to_string())
+    } else if exact && value.is_instance(&decimal_type(py)?)? {
+        if !value.call_method0("is_finite")?.extract::<bool>()? {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Cannot store non-finite Decimal: {}",
+                value.str()?
+            )));
+        }
+        // "5E+0" keeps Decimal('5') from reading back as the int 5
+        let text = value.str()?.to_string();
+        if text.contains(['.', 'e', 'E']) {
+            decimal_to_json(&text)
+        } else {
+            decimal_to_json(&format!("{}E+0", text))
+        }
     } else if let Ok(f) = value.extract::<f64>() {
         Ok(serde_json::Number::from_f64(f)
             .map(Value::Number)
@@ -843,14 +1367,14 @@ fn python_to_json(py: Python<'_>, value: &Bound<'_, pyo3::PyAny>) -> PyResult<Va
     } else if let Ok(list) = value.downcast::<PyList>() {
         let mut arr = Vec::new();
         for item in list.iter() {
-            arr.push(python_to_json(py, &item)?);
+            arr.push(python_to_json(py, &item, exact)?);
         }
         Ok(Value::Array(arr))
     } else if let Ok(dict) = value.downcast::<PyDict>() {
         let mut map = serde_json::Map::new();
         for (k, v) in dict.iter() {
             let key: String = k.extract()?;
-            map.insert(key, python_to_json(py, &v)?);
+            map.insert(key, python_to_json(py, &v, exact)?);
         }
         Ok(Value::Object(map))
     } else {
@@ -861,12 +1385,62 @@ fn python_to_json(py: Python<'_>, value: &Bound<'_, pyo3::PyAny>) -> PyResult<Va
     }
 }
 
+/// Decimal text -> tagged decimal JSON value
+fn decimal_to_json(text: &str) -> PyResult<Value> {
+    ironbase_core::decimal::decimal_value(text)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// The `decimal.Decimal` class
+fn decimal_type(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    py.import("decimal")?.getattr("Decimal")
+}
+
+/// `find` keyword arguments -> FindOptions
+fn find_options(
+    projection: Option<Bound<'_, PyDict>>,
+    sort: Option<Bound<'_, PyList>>,
+    limit: Option<usize>,
+    skip: Option<usize>,
+) -> PyResult<ironbase_core::find_options::FindOptions> {
+    let mut options = ironbase_core::find_options::FindOptions::new();
+
+    if let Some(proj) = projection {
+        let mut projection_map = HashMap::new();
+        for (key, value) in proj.iter() {
+            let field: String = key.extract()?;
+            let action: i32 = value.extract()?;
+            projection_map.insert(field, action);
+        }
+        options.projection = Some(projection_map);
+    }
+
+    if let Some(sort_list) = sort {
+        let mut sort_vec = Vec::new();
+        for item in sort_list.iter() {
+            let tuple = item.downcast::<PyTuple>()?;
+            let field: String = tuple.get_item(0)?.extract()?;
+            let direction: i32 = tuple.get_item(1)?.extract()?;
+            sort_vec.push((field, direction));
+        }
+        options.sort = Some(sort_vec);
+    }
+
+    options.limit = limit;
+    options.skip = skip;
+    Ok(options)
+}
+
 /// Python dict -> JSON Value
-fn python_dict_to_json_value(py: Python<'_>, dict: &Bound<'_, PyDict>) -> PyResult<Value> {
+fn python_dict_to_json_value(
+    py: Python<'_>,
+    dict: &Bound<'_, PyDict>,
+    exact: bool,
+) -> PyResult<Value> {
     let mut map = serde_json::Map::new();
     for (k, v) in dict.iter() {
         let key: String = k.extract()?;
-        map.insert(key, python_to_json(py, &v)?);
+        map.insert(key, python_to_json(py, &v, exact)?);
     }
     Ok(Value::Object(map))
 }
@@ -893,6 +1467,8 @@ fn json_value_to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
         Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 Ok(i.into_pyobject(py)?.into_any().unbind())
+            } else if let Some(u) = n.as_u64() {
+                Ok(u.into_pyobject(py)?.into_any().unbind())
             } else if let Some(f) = n.as_f64() {
                 Ok(f.into_pyobject(py)?.into_any().unbind())
             } else {
@@ -908,6 +1484,14 @@ fn json_value_to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
             Ok(py_list.into_any().unbind())
         }
         Value::Object(map) => {
+            if let Some(text) = ironbase_core::decimal::decimal_text(value) {
+                // Tagged decimals come back as int when integral, else Decimal
+                return if text.contains(['.', 'e', 'E']) {
+                    Ok(decimal_type(py)?.call1((text,))?.unbind())
+                } else {
+                    Ok(py.get_type::<PyInt>().call1((text,))?.unbind())
+                };
+            }
             let py_dict = PyDict::new(py);
             for (k, v) in map.iter() {
                 py_dict.set_item(k, json_value_to_python(py, v)?)?;
@@ -923,5 +1507,13 @@ fn ironbase(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<IronBase>()?;
     m.add_class::<Collection>()?;
     m.add_class::<Cursor>()?;
+    m.add_class::<Transaction>()?;
+    m.add("DuplicateKeyError", m.py().get_type::<DuplicateKeyError>())?;
+    m.add("ValidationError", m.py().get_type::<ValidationError>())?;
+    m.add(
+        "DocumentNotFoundError",
+        m.py().get_type::<DocumentNotFoundError>(),
+    )?;
+    m.add("TransactionError", m.py().get_type::<TransactionError>())?;
     Ok(())
 }
@@ -0,0 +1,213 @@
+//! Micro-benchmark for comparing durability modes and indexing
+//!
+//! Runs insert, find, update and delete workloads against a scratch
+//! collection and reports throughput and latency percentiles per operation.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use ironbase_core::{storage::StorageEngine, DatabaseCore, DurabilityMode};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Scratch collection used by the benchmark (dropped afterwards)
+const BENCH_COLLECTION: &str = "__bench";
+
+/// Durability mode selectable from the command line
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Durability {
+    Safe,
+    Batch,
+    Unsafe,
+}
+
+impl Durability {
+    fn mode(self) -> DurabilityMode {
+        match self {
+            Durability::Safe => DurabilityMode::Safe,
+            Durability::Batch => DurabilityMode::Batch { batch_size: 100 },
+            Durability::Unsafe => DurabilityMode::unsafe_manual(),
+        }
+    }
+}
+
+/// Latency samples for one operation type
+struct OpStats {
+    name: &'static str,
+    samples: Vec<Duration>,
+    total: Duration,
+}
+
+impl OpStats {
+    fn new(name: &'static str, capacity: usize) -> Self {
+        OpStats {
+            name,
+            samples: Vec::with_capacity(capacity),
+            total: Duration::ZERO,
+        }
+    }
+
+    fn time<T>(&mut self, op: impl FnOnce() -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let result = op()?;
+        let elapsed = start.elapsed();
+        self.samples.push(elapsed);
+        self.total += elapsed;
+        Ok(result)
+    }
+
+    fn ops_per_sec(&self) -> f64 {
+        let secs = self.total.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.samples.len() as f64 / secs
+        }
+    }
+
+    /// Latency at the given percentile (0-100) in microseconds
+    fn percentile_us(&self, sorted: &[Duration], pct: f64) -> f64 {
+        if sorted.is_empty() {
+            return 0.0;
+        }
+        let rank = ((pct / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[rank].as_secs_f64() * 1_000_000.0
+    }
+
+    fn report_line(&self) -> String {
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        format!(
+            "{:<8} {:>10} {:>14.1} {:>12.1} {:>12.1}",
+            self.name,
+            self.samples.len(),
+            self.ops_per_sec(),
+            self.percentile_us(&sorted, 50.0),
+            self.percentile_us(&sorted, 99.0),
+        )
+    }
+}
+
+/// Run the benchmark and print one report line per operation type
+pub fn run(db_path: &Path, ops: usize, durability: Durability, indexed: bool) -> Result<()> {
+    if ops == 0 {
+        bail!("--ops must be greater than 0");
+    }
+
+    let existing_files = list_sibling_files(db_path)?;
+
+    let db = DatabaseCore::<StorageEngine>::open_with_durability(db_path, durability.mode())
+        .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+
+    if db.list_collections().iter().any(|c| c == BENCH_COLLECTION) {
+        bail!(
+            "Collection '{}' already exists in {}; refusing to overwrite it",
+            BENCH_COLLECTION,
+            db_path.display()
+        );
+    }
+
+    let result = run_workloads(&db, ops, indexed);
+
+    // Clean up: scratch collection, then any files the run created
+    let _ = db.drop_collection(BENCH_COLLECTION);
+    drop(db);
+    for path in list_sibling_files(db_path)?.difference(&existing_files) {
+        let _ = fs::remove_file(path);
+    }
+
+    let stats = result?;
+
+    println!(
+        "Benchmark: {} ops, durability={}, indexed={}",
+        ops,
+        durability.to_possible_value().unwrap().get_name(),
+        if indexed { "yes" } else { "no" }
+    );
+    println!(
+        "{:<8} {:>10} {:>14} {:>12} {:>12}",
+        "op", "count", "ops/sec", "p50 (us)", "p99 (us)"
+    );
+    for op in &stats {
+        println!("{}", op.report_line());
+    }
+    Ok(())
+}
+
+fn run_workloads(
+    db: &DatabaseCore<StorageEngine>,
+    ops: usize,
+    indexed: bool,
+) -> Result<Vec<OpStats>> {
+    let collection = db.collection(BENCH_COLLECTION)?;
+    if indexed {
+        collection.create_index("seq".to_string(), false)?;
+    }
+
+    let mut insert = OpStats::new("insert", ops);
+    for i in 0..ops {
+        let doc: HashMap<String, Value> = HashMap::from([
+            ("seq".to_string(), json!(i)),
+            ("group".to_string(), json!(i % 10)),
+            ("payload".to_string(), json!(format!("payload-{}", i))),
+        ]);
+        insert.time(|| Ok(db.insert_one(BENCH_COLLECTION, doc)?))?;
+    }
+
+    let collection = db.collection(BENCH_COLLECTION)?;
+    let mut find = OpStats::new("find", ops);
+    for i in 0..ops {
+        find.time(|| Ok(collection.find(&json!({"seq": i}))?))?;
+    }
+
+    let mut update = OpStats::new("update", ops);
+    for i in 0..ops {
+        update.time(|| {
+            Ok(db.update_one(
+                BENCH_COLLECTION,
+                &json!({"seq": i}),
+                &json!({"$set": {"payload": format!("updated-{}", i)}}),
+            )?)
+        })?;
+    }
+
+    let mut delete = OpStats::new("delete", ops);
+    for i in 0..ops {
+        delete.time(|| Ok(db.delete_one(BENCH_COLLECTION, &json!({"seq": i}))?))?;
+    }
+
+    Ok(vec![insert, find, update, delete])
+}
+
+/// Files next to the database sharing its file stem (data, WAL and .idx files)
+fn list_sibling_files(db_path: &Path) -> Result<HashSet<PathBuf>> {
+    let stem = db_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let parent = match db_path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut files = HashSet::new();
+    if !parent.exists() {
+        return Ok(files);
+    }
+    for entry in fs::read_dir(&parent)
+        .with_context(|| format!("Failed to read directory: {}", parent.display()))?
+    {
+        let path = entry?.path();
+        let matches_stem = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(&stem));
+        if matches_stem && path.is_file() {
+            files.insert(path);
+        }
+    }
+    Ok(files)
+}
@@ -1,11 +1,15 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use ironbase_core::value_utils::{check_duplicate_keys, to_json_output};
 use ironbase_core::{storage::StorageEngine, DatabaseCore};
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+mod bench;
+mod shell;
+
 #[derive(Parser)]
 #[command(name = "ironbase")]
 #[command(about = "IronBase CLI - Command-line interface for IronBase database")]
@@ -13,6 +17,12 @@ use std::path::{Path, PathBuf};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Write compact JSON (default: pretty unless the output is large)
+    #[arg(long, global = true, conflicts_with = "pretty")]
+    compact: bool,
+    /// Always pretty-print JSON, even large outputs
+    #[arg(long, global = true)]
+    pretty: bool,
 }
 
 #[derive(Subcommand)]
@@ -24,6 +34,15 @@ enum Commands {
         /// Database file path
         #[arg(long, default_value = "ironbase.mlite")]
         db: PathBuf,
+        /// Replace documents that already exist instead of inserting duplicates
+        #[arg(long)]
+        upsert: bool,
+        /// Field that identifies existing documents with --upsert
+        #[arg(long, requires = "upsert", default_value = "_id")]
+        key: String,
+        /// Reject the file if an object repeats a key (default: last one wins)
+        #[arg(long)]
+        strict: bool,
     },
     /// Export database to JSON file
     Export {
@@ -36,6 +55,47 @@ enum Commands {
         #[arg(long)]
         collection: Option<String>,
     },
+    /// Interactive shell (reads commands from stdin, one per line)
+    Shell {
+        /// Database file path
+        #[arg(long, default_value = "ironbase.mlite")]
+        db: PathBuf,
+    },
+    /// Run insert/find/update/delete micro-benchmarks
+    Bench {
+        /// Database file path (benchmark data is removed afterwards)
+        #[arg(long, default_value = "ironbase-bench.mlite")]
+        db: PathBuf,
+        /// Number of operations per workload
+        #[arg(long, default_value_t = 1000)]
+        ops: usize,
+        /// Durability mode to benchmark
+        #[arg(long, value_enum, default_value = "safe")]
+        durability: bench::Durability,
+        /// Create an index on the queried field before running
+        #[arg(long)]
+        indexed: bool,
+    },
+    /// Show or change a collection's document quota
+    Quota {
+        /// Collection name
+        collection: String,
+        /// Database file path
+        #[arg(long, default_value = "ironbase.mlite")]
+        db: PathBuf,
+        /// Maximum number of documents the collection may hold
+        #[arg(long, conflicts_with = "clear")]
+        max: Option<u64>,
+        /// Remove the quota
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Print database statistics (file, WAL and commit latency) as JSON
+    Stats {
+        /// Database file path
+        #[arg(long, default_value = "ironbase.mlite")]
+        db: PathBuf,
+    },
     /// Schema management commands
     Schema {
         #[command(subcommand)]
@@ -74,14 +134,39 @@ enum SchemaAction {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let pretty = match (cli.compact, cli.pretty) {
+        (true, _) => Some(false),
+        (_, true) => Some(true),
+        _ => None,
+    };
 
     match cli.command {
-        Commands::Import { file, db } => import_data(&file, &db),
+        Commands::Import {
+            file,
+            db,
+            upsert,
+            key,
+            strict,
+        } => import_data(&file, &db, upsert.then_some(key.as_str()), strict),
         Commands::Export {
             file,
             db,
             collection,
-        } => export_data(&file, &db, collection.as_deref()),
+        } => export_data(&file, &db, collection.as_deref(), pretty),
+        Commands::Shell { db } => run_shell(&db),
+        Commands::Bench {
+            db,
+            ops,
+            durability,
+            indexed,
+        } => bench::run(&db, ops, durability, indexed),
+        Commands::Quota {
+            collection,
+            db,
+            max,
+            clear,
+        } => quota(&db, &collection, max, clear),
+        Commands::Stats { db } => stats(&db, pretty),
         Commands::Schema { action } => match action {
             SchemaAction::Load {
                 path,
@@ -93,17 +178,28 @@ fn main() -> Result<()> {
                 db,
                 collection,
                 all,
-            } => save_schema(&path, &db, collection.as_deref(), all),
+            } => save_schema(&path, &db, collection.as_deref(), all, pretty),
         },
     }
 }
 
 /// Import data from JSON file
 /// Format: { "collection_name": [documents...], ... }
-fn import_data(file: &Path, db_path: &Path) -> Result<()> {
+///
+/// With `upsert_key`, a document whose key field matches a stored document
+/// replaces it, so re-running an import doesn't duplicate anything.
+///
+/// An object that repeats a key keeps its last value, unless `strict`
+/// rejects the whole file before anything is imported.
+fn import_data(file: &Path, db_path: &Path, upsert_key: Option<&str>, strict: bool) -> Result<()> {
     let content = fs::read_to_string(file)
         .with_context(|| format!("Failed to read file: {}", file.display()))?;
 
+    if strict {
+        check_duplicate_keys(&content)
+            .with_context(|| format!("Duplicate key in file: {}", file.display()))?;
+    }
+
     let data: Map<String, Value> = serde_json::from_str(&content)
         .with_context(|| format!("Invalid JSON in file: {}", file.display()))?;
 
@@ -111,12 +207,14 @@ fn import_data(file: &Path, db_path: &Path) -> Result<()> {
         .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
 
     let mut total_docs = 0;
+    let mut total_updated = 0;
 
     for (collection_name, documents) in data {
         let docs = documents
             .as_array()
             .with_context(|| format!("Collection '{}' must be an array", collection_name))?;
 
+        let mut updated = 0;
         for doc in docs {
             let doc_map: HashMap<String, Value> = doc
                 .as_object()
@@ -125,28 +223,59 @@ fn import_data(file: &Path, db_path: &Path) -> Result<()> {
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect();
 
-            db.insert_one(&collection_name, doc_map)
-                .with_context(|| format!("Failed to insert document into {}", collection_name))?;
+            match upsert_key {
+                Some(key) => {
+                    let (inserted, _) = db
+                        .upsert_by_key(&collection_name, key, doc_map)
+                        .with_context(|| {
+                            format!("Failed to upsert document into {}", collection_name)
+                        })?;
+                    if !inserted {
+                        updated += 1;
+                    }
+                }
+                None => {
+                    db.insert_one(&collection_name, doc_map).with_context(|| {
+                        format!("Failed to insert document into {}", collection_name)
+                    })?;
+                }
+            }
             total_docs += 1;
         }
+        total_updated += updated;
 
         println!(
-            "Imported {} documents into '{}'",
+            "Imported {} documents into '{}'{}",
             docs.len(),
-            collection_name
+            collection_name,
+            upsert_summary(upsert_key, docs.len() - updated, updated)
         );
     }
 
     println!(
-        "Total: {} documents imported to {}",
+        "Total: {} documents imported to {}{}",
         total_docs,
-        db_path.display()
+        db_path.display(),
+        upsert_summary(upsert_key, total_docs - total_updated, total_updated)
     );
     Ok(())
 }
 
+/// " (N inserted, M updated)" for upsert imports, empty otherwise
+fn upsert_summary(upsert_key: Option<&str>, inserted: usize, updated: usize) -> String {
+    match upsert_key {
+        Some(_) => format!(" ({} inserted, {} updated)", inserted, updated),
+        None => String::new(),
+    }
+}
+
 /// Export database to JSON file
-fn export_data(file: &Path, db_path: &Path, collection_filter: Option<&str>) -> Result<()> {
+fn export_data(
+    file: &Path,
+    db_path: &Path,
+    collection_filter: Option<&str>,
+    pretty: Option<bool>,
+) -> Result<()> {
     let db = DatabaseCore::<StorageEngine>::open(db_path)
         .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
 
@@ -175,8 +304,7 @@ fn export_data(file: &Path, db_path: &Path, collection_filter: Option<&str>) ->
         output.insert(coll_name.clone(), Value::Array(docs));
     }
 
-    let json =
-        serde_json::to_string_pretty(&output).with_context(|| "Failed to serialize to JSON")?;
+    let json = to_json_output(&output, pretty).with_context(|| "Failed to serialize to JSON")?;
 
     fs::write(file, json)
         .with_context(|| format!("Failed to write to file: {}", file.display()))?;
@@ -189,6 +317,45 @@ fn export_data(file: &Path, db_path: &Path, collection_filter: Option<&str>) ->
     Ok(())
 }
 
+/// Start the interactive shell on the given database
+fn run_shell(db_path: &Path) -> Result<()> {
+    let db = DatabaseCore::<StorageEngine>::open(db_path)
+        .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+    shell::run_stdio(&db)
+}
+
+/// Set (`max`), remove (`clear`) or print a collection's document quota
+fn quota(db_path: &Path, collection: &str, max: Option<u64>, clear: bool) -> Result<()> {
+    let db = DatabaseCore::<StorageEngine>::open(db_path)
+        .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+    let coll = db
+        .collection(collection)
+        .with_context(|| format!("Failed to get collection: {}", collection))?;
+
+    if max.is_some() || clear {
+        coll.set_max_documents(max)
+            .with_context(|| format!("Failed to set quota for: {}", collection))?;
+    }
+    match coll.max_documents() {
+        Some(limit) => println!(
+            "Collection '{}' holds at most {} documents",
+            collection, limit
+        ),
+        None => println!("Collection '{}' has no document quota", collection),
+    }
+    Ok(())
+}
+
+/// Print `DatabaseCore::stats` as JSON
+fn stats(db_path: &Path, pretty: Option<bool>) -> Result<()> {
+    let db = DatabaseCore::<StorageEngine>::open(db_path)
+        .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+    let json =
+        to_json_output(&db.stats(), pretty).with_context(|| "Failed to serialize to JSON")?;
+    println!("{}", json);
+    Ok(())
+}
+
 /// Load schema from file or directory (modular)
 fn load_schema(path: &Path, db_path: &Path, collection: Option<&str>) -> Result<()> {
     let db = DatabaseCore::<StorageEngine>::open(db_path)
@@ -253,7 +420,13 @@ fn load_schema(path: &Path, db_path: &Path, collection: Option<&str>) -> Result<
 }
 
 /// Save schema to file or directory
-fn save_schema(path: &Path, db_path: &Path, collection: Option<&str>, all: bool) -> Result<()> {
+fn save_schema(
+    path: &Path,
+    db_path: &Path,
+    collection: Option<&str>,
+    all: bool,
+    pretty: Option<bool>,
+) -> Result<()> {
     let db = DatabaseCore::<StorageEngine>::open(db_path)
         .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
 
@@ -280,7 +453,7 @@ fn save_schema(path: &Path, db_path: &Path, collection: Option<&str>, all: bool)
 
             if let Some(schema) = coll.get_schema() {
                 let file_path = dir_path.join(format!("{}.schema.json", coll_name));
-                let json = serde_json::to_string_pretty(&schema)
+                let json = to_json_output(&schema, pretty)
                     .with_context(|| "Failed to serialize schema")?;
 
                 fs::write(&file_path, json)
@@ -310,8 +483,7 @@ fn save_schema(path: &Path, db_path: &Path, collection: Option<&str>, all: bool)
             .get_schema()
             .ok_or_else(|| anyhow::anyhow!("Collection '{}' has no schema", coll_name))?;
 
-        let json =
-            serde_json::to_string_pretty(&schema).with_context(|| "Failed to serialize schema")?;
+        let json = to_json_output(&schema, pretty).with_context(|| "Failed to serialize schema")?;
 
         fs::write(path, json).with_context(|| format!("Failed to write: {}", path.display()))?;
 
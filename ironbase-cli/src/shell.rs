@@ -0,0 +1,188 @@
+//! Interactive shell (REPL) for exploring a database
+//!
+//! Line-oriented: one command per line, results printed one JSON value per
+//! line, so it works the same interactively and over pipes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ironbase_core::{storage::StorageEngine, DatabaseCore};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+
+const HELP: &str = "\
+Commands:
+  use <collection>     select the current collection
+  collections          list collections
+  find [query]         print matching documents (default: all)
+  insert <doc|[docs]>  insert one document or an array of documents
+  count [query]        count matching documents
+  indexes              list indexes of the current collection
+  agg <pipeline>       run an aggregation pipeline (JSON array)
+  help                 show this help
+  exit | quit          leave the shell";
+
+/// Run the shell until EOF or `exit`
+///
+/// Command errors are printed inline as `error: ...` and do not stop the shell.
+pub fn run<R: BufRead, W: Write>(
+    db: &DatabaseCore<StorageEngine>,
+    input: R,
+    out: &mut W,
+    interactive: bool,
+) -> Result<()> {
+    let mut state = ShellState { current: None };
+
+    if interactive {
+        writeln!(out, "IronBase shell - type 'help' for commands")?;
+    }
+
+    let mut lines = input.lines();
+    loop {
+        if interactive {
+            write!(out, "{}> ", state.current.as_deref().unwrap_or("ironbase"))?;
+            out.flush()?;
+        }
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (command, args) = match line.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line, ""),
+        };
+
+        if matches!(command, "exit" | "quit") {
+            break;
+        }
+
+        if let Err(e) = state.execute(db, command, args, out) {
+            writeln!(out, "error: {:#}", e)?;
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Run the shell on stdin/stdout, prompting only when stdin is a terminal
+pub fn run_stdio(db: &DatabaseCore<StorageEngine>) -> Result<()> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut stdout = std::io::stdout().lock();
+    run(db, stdin.lock(), &mut stdout, interactive)
+}
+
+struct ShellState {
+    current: Option<String>,
+}
+
+impl ShellState {
+    fn execute<W: Write>(
+        &mut self,
+        db: &DatabaseCore<StorageEngine>,
+        command: &str,
+        args: &str,
+        out: &mut W,
+    ) -> Result<()> {
+        match command {
+            "help" => writeln!(out, "{}", HELP)?,
+            "use" => {
+                if args.is_empty() || args.contains(char::is_whitespace) {
+                    bail!("usage: use <collection>");
+                }
+                self.current = Some(args.to_string());
+                writeln!(out, "using '{}'", args)?;
+            }
+            "collections" => {
+                let mut names = db.list_collections();
+                names.sort();
+                for name in names {
+                    writeln!(out, "{}", name)?;
+                }
+            }
+            "find" => {
+                let query = parse_json_or_empty(args)?;
+                let coll = db.collection(self.current()?)?;
+                for doc in coll.find(&query)? {
+                    writeln!(out, "{}", doc)?;
+                }
+            }
+            "insert" => {
+                let collection = self.current()?;
+                match parse_json(args)? {
+                    Value::Array(docs) => {
+                        let docs = docs
+                            .into_iter()
+                            .map(into_fields)
+                            .collect::<Result<Vec<_>>>()?;
+                        let ids = db.insert_many(collection, docs)?;
+                        writeln!(out, "inserted {}", serde_json::to_string(&ids)?)?;
+                    }
+                    doc => {
+                        let id = db.insert_one(collection, into_fields(doc)?)?;
+                        writeln!(out, "inserted {}", serde_json::to_string(&id)?)?;
+                    }
+                }
+            }
+            "count" => {
+                let query = parse_json_or_empty(args)?;
+                let coll = db.collection(self.current()?)?;
+                writeln!(out, "{}", coll.count_documents(&query)?)?;
+            }
+            "indexes" => {
+                let coll = db.collection(self.current()?)?;
+                let mut indexes = coll.list_indexes();
+                indexes.sort();
+                for name in indexes {
+                    writeln!(out, "{}", name)?;
+                }
+            }
+            "agg" => {
+                let pipeline = parse_json(args)?;
+                if !pipeline.is_array() {
+                    bail!("pipeline must be a JSON array");
+                }
+                let coll = db.collection(self.current()?)?;
+                for doc in coll.aggregate(&pipeline)? {
+                    writeln!(out, "{}", doc)?;
+                }
+            }
+            _ => bail!("unknown command '{}' (type 'help')", command),
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Result<&str> {
+        self.current
+            .as_deref()
+            .ok_or_else(|| anyhow!("no collection selected (use <collection>)"))
+    }
+}
+
+fn parse_json(args: &str) -> Result<Value> {
+    if args.is_empty() {
+        bail!("missing JSON argument");
+    }
+    serde_json::from_str(args).context("invalid JSON")
+}
+
+fn parse_json_or_empty(args: &str) -> Result<Value> {
+    if args.is_empty() {
+        Ok(serde_json::json!({}))
+    } else {
+        parse_json(args)
+    }
+}
+
+fn into_fields(doc: Value) -> Result<HashMap<String, Value>> {
+    match doc {
+        Value::Object(map) => Ok(map.into_iter().collect()),
+        _ => bail!("document must be a JSON object"),
+    }
+}
@@ -0,0 +1,65 @@
+// CLI bench smoke tests
+use std::process::Command;
+use tempfile::TempDir;
+
+fn run_bench(args: &[&str]) -> (TempDir, String) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("bench.mlite");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .arg("bench")
+        .arg("--db")
+        .arg(&db_path)
+        .args(args)
+        .output()
+        .expect("failed to run ironbase bench");
+    assert!(
+        output.status.success(),
+        "bench failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (temp_dir, String::from_utf8(output.stdout).unwrap())
+}
+
+/// Parse "<op> <count> <ops/sec> <p50> <p99>" report lines
+fn throughput_by_op(stdout: &str) -> Vec<(String, f64)> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() != 5 {
+                return None;
+            }
+            let ops_per_sec = cols[2].parse::<f64>().ok()?;
+            Some((cols[0].to_string(), ops_per_sec))
+        })
+        .collect()
+}
+
+#[test]
+fn test_bench_reports_throughput_for_each_operation() {
+    let (temp_dir, stdout) = run_bench(&["--ops", "20", "--durability", "unsafe", "--indexed"]);
+
+    let results = throughput_by_op(&stdout);
+    let ops: Vec<&str> = results.iter().map(|(op, _)| op.as_str()).collect();
+    assert_eq!(
+        ops,
+        vec!["insert", "find", "update", "delete"],
+        "{}",
+        stdout
+    );
+    for (op, ops_per_sec) in &results {
+        assert!(*ops_per_sec > 0.0, "{} reported zero throughput", op);
+    }
+
+    // Benchmark data is cleaned up
+    let leftovers: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().collect();
+    assert!(leftovers.is_empty(), "left files behind: {:?}", leftovers);
+}
+
+#[test]
+fn test_bench_safe_mode_smoke() {
+    let (_temp_dir, stdout) = run_bench(&["--ops", "5"]);
+    assert!(stdout.contains("durability=safe"), "{}", stdout);
+    assert_eq!(throughput_by_op(&stdout).len(), 4, "{}", stdout);
+}
@@ -0,0 +1,80 @@
+// CLI export output format tests
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn ironbase(args: &[&str], db_path: &Path) {
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .args(args)
+        .arg("--db")
+        .arg(db_path)
+        .output()
+        .expect("failed to run ironbase");
+    assert!(
+        output.status.success(),
+        "ironbase {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn import(temp_dir: &TempDir, db_path: &Path, docs: usize) {
+    let data = json!({
+        "items": (0..docs).map(|i| json!({"n": i, "label": format!("item-{}-{}", i, "x".repeat(200))})).collect::<Vec<_>>()
+    });
+    let file = temp_dir.path().join("in.json");
+    std::fs::write(&file, data.to_string()).unwrap();
+    ironbase(&["import", file.to_str().unwrap()], db_path);
+}
+
+fn export(temp_dir: &TempDir, db_path: &Path, name: &str, flags: &[&str]) -> String {
+    let file = temp_dir.path().join(name);
+    let mut args = vec!["export", file.to_str().unwrap()];
+    args.extend_from_slice(flags);
+    ironbase(&args, db_path);
+    std::fs::read_to_string(file).unwrap()
+}
+
+/// Exported items sorted by `_id` (export order follows the catalog, not insertion)
+fn items(export: &str) -> Vec<Value> {
+    let parsed: Value = serde_json::from_str(export).unwrap();
+    let mut items = parsed["items"].as_array().unwrap().clone();
+    items.sort_by_key(|doc| doc["_id"].as_i64().unwrap());
+    items
+}
+
+#[test]
+fn test_pretty_and_compact_exports_parse_to_same_value() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("fmt.mlite");
+    import(&temp_dir, &db_path, 20);
+
+    let pretty = export(&temp_dir, &db_path, "pretty.json", &["--pretty"]);
+    let compact = export(&temp_dir, &db_path, "compact.json", &["--compact"]);
+    let default = export(&temp_dir, &db_path, "default.json", &[]);
+
+    assert!(pretty.contains('\n'));
+    assert!(!compact.contains('\n'));
+    assert!(compact.len() < pretty.len());
+    assert_eq!(items(&pretty), items(&compact));
+    assert_eq!(items(&pretty).len(), 20);
+    // Small output stays pretty by default
+    assert!(default.contains('\n'));
+    assert_eq!(items(&default), items(&pretty));
+}
+
+#[test]
+fn test_large_export_defaults_to_compact() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("large.mlite");
+    import(&temp_dir, &db_path, 400);
+
+    let default = export(&temp_dir, &db_path, "default.json", &[]);
+    let pretty = export(&temp_dir, &db_path, "pretty.json", &["--pretty"]);
+
+    assert!(default.len() > ironbase_core::value_utils::COMPACT_OUTPUT_THRESHOLD);
+    assert!(!default.contains('\n'));
+    assert!(pretty.contains('\n'));
+    assert_eq!(items(&default), items(&pretty));
+}
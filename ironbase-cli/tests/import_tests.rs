@@ -0,0 +1,174 @@
+// CLI import tests
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Run ironbase against `db_path` and return its stdout
+fn ironbase(args: &[&str], db_path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .args(args)
+        .arg("--db")
+        .arg(db_path)
+        .output()
+        .expect("failed to run ironbase");
+    assert!(
+        output.status.success(),
+        "ironbase {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn write_fixture(temp_dir: &TempDir, name: &str, data: Value) -> String {
+    let file = temp_dir.path().join(name);
+    std::fs::write(&file, data.to_string()).unwrap();
+    file.to_str().unwrap().to_string()
+}
+
+/// Exported documents of one collection, sorted by `sort_field`
+fn exported(temp_dir: &TempDir, db_path: &Path, collection: &str, sort_field: &str) -> Vec<Value> {
+    let file = temp_dir.path().join("out.json");
+    ironbase(&["export", file.to_str().unwrap()], db_path);
+    let parsed: Value = serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap();
+    let mut docs = parsed[collection].as_array().unwrap().clone();
+    docs.sort_by_key(|doc| doc[sort_field].to_string());
+    docs
+}
+
+#[test]
+fn test_import_upsert_by_id_is_idempotent() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("import.mlite");
+    let fixture = write_fixture(
+        &temp_dir,
+        "users.json",
+        json!({"users": [
+            {"_id": 1, "name": "Ada"},
+            {"_id": 2, "name": "Grace"},
+            {"_id": 3, "name": "Linus"},
+        ]}),
+    );
+
+    let first = ironbase(&["import", &fixture, "--upsert"], &db_path);
+    assert!(first.contains("(3 inserted, 0 updated)"), "{}", first);
+    let second = ironbase(&["import", &fixture, "--upsert"], &db_path);
+    assert!(second.contains("(0 inserted, 3 updated)"), "{}", second);
+
+    let users = exported(&temp_dir, &db_path, "users", "_id");
+    assert_eq!(users.len(), 3);
+    assert_eq!(users[1]["name"], "Grace");
+}
+
+#[test]
+fn test_import_upsert_by_key_replaces_matching_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("import_key.mlite");
+    let v1 = write_fixture(
+        &temp_dir,
+        "v1.json",
+        json!({"users": [
+            {"email": "ada@x.io", "plan": "free", "beta": true},
+            {"email": "grace@x.io", "plan": "free"},
+        ]}),
+    );
+    let v2 = write_fixture(
+        &temp_dir,
+        "v2.json",
+        json!({"users": [
+            {"email": "ada@x.io", "plan": "pro"},
+            {"email": "linus@x.io", "plan": "free"},
+        ]}),
+    );
+
+    ironbase(&["import", &v1, "--upsert", "--key", "email"], &db_path);
+    let output = ironbase(&["import", &v2, "--upsert", "--key", "email"], &db_path);
+    assert!(output.contains("(1 inserted, 1 updated)"), "{}", output);
+
+    let users = exported(&temp_dir, &db_path, "users", "email");
+    let summary: Vec<(&str, &str)> = users
+        .iter()
+        .map(|u| (u["email"].as_str().unwrap(), u["plan"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("ada@x.io", "pro"),
+            ("grace@x.io", "free"),
+            ("linus@x.io", "free")
+        ]
+    );
+    // Replaced, not merged
+    assert!(users[0].get("beta").is_none());
+
+    // Without --upsert every document is inserted again
+    ironbase(&["import", &v2], &db_path);
+    assert_eq!(exported(&temp_dir, &db_path, "users", "email").len(), 5);
+}
+
+#[test]
+fn test_quota_command_caps_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("quota.mlite");
+    let set = ironbase(&["quota", "tenants", "--max", "2"], &db_path);
+    assert!(set.contains("holds at most 2 documents"), "{}", set);
+
+    let two = write_fixture(
+        &temp_dir,
+        "two.json",
+        json!({"tenants": [{"n": 1}, {"n": 2}]}),
+    );
+    ironbase(&["import", &two], &db_path);
+
+    let one = write_fixture(&temp_dir, "one.json", json!({"tenants": [{"n": 3}]}));
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .args(["import", &one, "--db"])
+        .arg(&db_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Quota exceeded"));
+
+    let cleared = ironbase(&["quota", "tenants", "--clear"], &db_path);
+    assert!(cleared.contains("has no document quota"), "{}", cleared);
+    ironbase(&["import", &one], &db_path);
+    assert_eq!(exported(&temp_dir, &db_path, "tenants", "n").len(), 3);
+}
+
+#[test]
+fn test_import_duplicate_keys_strict_and_lenient() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("dupes.mlite");
+    let file = temp_dir.path().join("dupes.json");
+    std::fs::write(
+        &file,
+        r#"{"users": [{"name": "Ann"}, {"name": "Bob", "role": "admin", "role": "guest"}]}"#,
+    )
+    .unwrap();
+    let file = file.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .args(["import", file, "--strict", "--db"])
+        .arg(&db_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("duplicate key 'role' at users[1]"),
+        "{}",
+        stderr
+    );
+    // Nothing was imported from the rejected file
+    let export = temp_dir.path().join("empty.json");
+    ironbase(&["export", export.to_str().unwrap()], &db_path);
+    let parsed: Value = serde_json::from_str(&std::fs::read_to_string(export).unwrap()).unwrap();
+    assert!(parsed.get("users").is_none());
+
+    // The default keeps the last value
+    ironbase(&["import", file], &db_path);
+    let users = exported(&temp_dir, &db_path, "users", "name");
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[1]["role"], json!("guest"));
+}
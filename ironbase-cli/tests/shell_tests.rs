@@ -0,0 +1,95 @@
+// CLI shell integration tests
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// Run `ironbase shell` with the given script on stdin and return stdout lines
+fn run_shell(db_path: &std::path::Path, script: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .arg("shell")
+        .arg("--db")
+        .arg(db_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start ironbase shell");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "shell failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_shell_scripted_session() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("shell.mlite");
+
+    let script = r#"
+use users
+insert {"name": "Alice", "age": 30}
+insert [{"name": "Bob", "age": 25}, {"name": "Carol", "age": 35}]
+count {}
+count {"age": {"$gte": 30}}
+find {"name": "Bob"}
+indexes
+agg [{"$group": {"_id": null, "total": {"$sum": "$age"}}}]
+"#;
+
+    let lines = run_shell(&db_path, script);
+    assert_eq!(
+        lines,
+        vec![
+            "using 'users'",
+            "inserted 1",
+            "inserted [2,3]",
+            "3",
+            "2",
+            r#"{"_collection":"users","_id":2,"age":25,"name":"Bob"}"#,
+            "users_id",
+            r#"{"_id":null,"total":90}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_shell_reports_errors_inline_and_continues() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("shell.mlite");
+
+    let script = "find {}\nuse items\nfind {not json}\nfrobnicate\nagg {}\ninsert {\"sku\": \"a1\"}\nexit\ncount {}\n";
+
+    let lines = run_shell(&db_path, script);
+    assert_eq!(lines.len(), 6, "{:?}", lines);
+    assert_eq!(lines[0], "error: no collection selected (use <collection>)");
+    assert_eq!(lines[1], "using 'items'");
+    assert!(lines[2].starts_with("error: invalid JSON"), "{}", lines[2]);
+    assert!(lines[3].starts_with("error: unknown command 'frobnicate'"));
+    assert_eq!(lines[4], "error: pipeline must be a JSON array");
+    assert_eq!(lines[5], "inserted 1");
+}
+
+#[test]
+fn test_shell_data_persists_between_sessions() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("shell.mlite");
+
+    run_shell(&db_path, "use notes\ninsert {\"text\": \"hi\"}\n");
+    let lines = run_shell(&db_path, "collections\nuse notes\ncount\n");
+    assert_eq!(lines, vec!["notes", "using 'notes'", "1"]);
+}
@@ -0,0 +1,39 @@
+// CLI stats output tests
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn ironbase(args: &[&str], db_path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ironbase"))
+        .args(args)
+        .arg("--db")
+        .arg(db_path)
+        .output()
+        .expect("failed to run ironbase");
+    assert!(
+        output.status.success(),
+        "ironbase {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_stats_prints_wal_metrics() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("stats.mlite");
+    let file = temp_dir.path().join("in.json");
+    std::fs::write(&file, json!({"items": [{"n": 1}, {"n": 2}]}).to_string()).unwrap();
+    ironbase(&["import", file.to_str().unwrap()], &db_path);
+
+    let stats: Value = serde_json::from_str(&ironbase(&["stats", "--compact"], &db_path)).unwrap();
+
+    assert_eq!(stats["collection_count"], 1);
+    let wal = &stats["wal"];
+    assert!(wal["size_bytes"].is_u64());
+    assert!(wal["entries_since_checkpoint"].is_u64());
+    // A freshly opened database has not committed anything yet
+    assert!(wal["avg_commit_latency_us"].is_null());
+}
@@ -1,11 +1,16 @@
 // src/aggregation.rs
 // Aggregation pipeline implementation
 
-use crate::document::Document;
+use crate::document::{Document, DocumentId};
 use crate::error::{MongoLiteError, Result};
+use crate::find_options::SizedDocument;
+use crate::query::operators::{ExprOperator, OperatorMatcher};
 use crate::query::Query;
-use crate::value_utils::{canonical_json_string, get_nested_value, set_nested_value};
+use crate::value_utils::{
+    canonical_json_string, compare_for_sort, compare_values, get_nested_value, set_nested_value,
+};
 use serde_json::Value;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
 // ============================================================================
@@ -33,6 +38,38 @@ fn parse_field_reference(value: &Value, op_name: &str) -> Result<String> {
     }
 }
 
+/// Sum numeric values, ignoring non-numbers
+///
+/// The result is an integer unless a float is seen or the integer sum
+/// overflows i64, in which case it is promoted to f64 rather than clamped.
+fn sum_numeric<'a>(values: impl Iterator<Item = &'a Value>) -> Value {
+    let mut sum_int: i64 = 0;
+    let mut sum_float: f64 = 0.0;
+    let mut has_float = false;
+
+    for value in values {
+        if let Some(n) = value.as_i64() {
+            match sum_int.checked_add(n) {
+                Some(sum) => sum_int = sum,
+                None => {
+                    sum_float += sum_int as f64 + n as f64;
+                    sum_int = 0;
+                    has_float = true;
+                }
+            }
+        } else if let Some(f) = value.as_f64() {
+            sum_float += f;
+            has_float = true;
+        }
+    }
+
+    if has_float {
+        Value::from(sum_float + sum_int as f64)
+    } else {
+        Value::from(sum_int)
+    }
+}
+
 /// Compute min or max over documents using a comparison function
 ///
 /// Used by $min and $max accumulators
@@ -59,6 +96,42 @@ where
     Ok(result.map(Value::from).unwrap_or(Value::Null))
 }
 
+/// Population or sample standard deviation of a field's numeric values
+///
+/// Used by $stdDevPop and $stdDevSamp. Non-numeric and missing values are
+/// ignored; null when there are no values (or only one, for a sample).
+fn compute_std_dev(docs: &[Value], field: &str, sample: bool) -> Value {
+    let values: Vec<f64> = docs
+        .iter()
+        .filter_map(|doc| get_nested_value(doc, field))
+        .filter_map(Value::as_f64)
+        .collect();
+
+    let n = values.len();
+    let divisor = if sample { n.saturating_sub(1) } else { n };
+    if divisor == 0 {
+        return Value::Null;
+    }
+
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let squared_deviations: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    Value::from((squared_deviations / divisor as f64).sqrt())
+}
+
+/// Merge object values into one object, later keys overwriting earlier ones
+///
+/// Used by the $mergeObjects accumulator and expression. Non-object values
+/// (including null) are skipped; no objects at all give `{}`.
+fn merge_objects(values: impl IntoIterator<Item = Value>) -> Value {
+    let mut merged = serde_json::Map::new();
+    for value in values {
+        if let Value::Object(obj) = value {
+            merged.extend(obj);
+        }
+    }
+    Value::Object(merged)
+}
+
 /// Aggregation pipeline
 #[derive(Debug, Clone)]
 pub struct Pipeline {
@@ -75,6 +148,17 @@ pub enum Stage {
     Limit(LimitStage),
     Skip(SkipStage),
     Unwind(UnwindStage),
+    Dedup(DedupStage),
+    Redact(RedactStage),
+    AddFields(AddFieldsStage),
+    Count(CountStage),
+    SortByCount(SortByCountStage),
+    Lookup(LookupStage),
+    Facet(FacetStage),
+    Bucket(BucketStage),
+    ReplaceRoot(ReplaceRootStage),
+    Sample(SampleStage),
+    Out(OutStage),
 }
 
 /// $match stage - filter documents
@@ -100,10 +184,117 @@ pub enum ProjectField {
 /// Expressions that can be used in $project stage
 #[derive(Debug, Clone)]
 pub enum ProjectExpression {
+    /// Field reference operand ("$address.city" -> "address.city")
+    Field(String),
+    /// Literal operand (anything that is not a field reference or operator
+    /// object, or the argument of `$literal`)
+    Literal(Value),
     /// $size - returns the length of an array field
     Size(String), // Field name (e.g., "$tags" -> "tags")
     /// $reduce - apply a custom reduction to an array
     Reduce(ReduceExpression),
+    /// $split - split a string by a delimiter into an array of substrings
+    Split {
+        input: Box<ProjectExpression>,
+        delimiter: Box<ProjectExpression>,
+    },
+    /// $trim / $ltrim / $rtrim - strip characters (whitespace by default)
+    Trim {
+        input: Box<ProjectExpression>,
+        chars: Option<Box<ProjectExpression>>,
+        mode: TrimMode,
+    },
+    /// $objectToArray - object to an array of {k, v} documents
+    ObjectToArray(Box<ProjectExpression>),
+    /// $arrayToObject - array of {k, v} documents or [key, value] pairs to an object
+    ArrayToObject(Box<ProjectExpression>),
+    /// $add / $subtract / $multiply / $divide - numeric operation over
+    /// operand expressions, applied left to right
+    Arithmetic {
+        op: ArithmeticOp,
+        operands: Vec<ProjectExpression>,
+    },
+    /// $concat - join operands into one string
+    ///
+    /// A null or missing operand makes the result null, as in MongoDB;
+    /// with `skip_nulls` such operands are left out instead.
+    Concat {
+        operands: Vec<ProjectExpression>,
+        skip_nulls: bool,
+    },
+    /// $toUpper / $toLower - change the case of the operand's string form
+    ///
+    /// Numbers and booleans are converted to strings; null and missing give "".
+    ChangeCase {
+        input: Box<ProjectExpression>,
+        upper: bool,
+    },
+    /// $substr - `length` characters starting at character `start`
+    ///
+    /// Counts Unicode characters, not bytes. A negative length takes the rest
+    /// of the string; a start past the end gives "".
+    Substr {
+        input: Box<ProjectExpression>,
+        start: Box<ProjectExpression>,
+        length: Box<ProjectExpression>,
+    },
+    /// $mergeObjects - merge the operands' objects, later keys win
+    MergeObjects(Vec<ProjectExpression>),
+    /// $eq / $ne / $gt / $gte / $lt / $lte - compare two operands to a boolean
+    Comparison {
+        op: ComparisonOp,
+        operands: Box<[ProjectExpression; 2]>,
+    },
+    /// $cond - `then` when the predicate is truthy, `otherwise` when not
+    ///
+    /// false, null, missing and zero are falsy; everything else is truthy.
+    Cond {
+        predicate: Box<ProjectExpression>,
+        then: Box<ProjectExpression>,
+        otherwise: Box<ProjectExpression>,
+    },
+    /// $switch - `then` of the first truthy `case`, else `default`
+    ///
+    /// Without a default, a document matching no branch is an error.
+    Switch {
+        branches: Vec<(ProjectExpression, ProjectExpression)>,
+        default: Option<Box<ProjectExpression>>,
+    },
+    /// $ifNull - the value, or the fallback when it is null or missing
+    IfNull {
+        value: Box<ProjectExpression>,
+        fallback: Box<ProjectExpression>,
+    },
+    /// Nested document spec like {"address": {"city": "$city"}}, built key by key
+    Object(Vec<(String, ProjectExpression)>),
+}
+
+/// Operator of an `Arithmetic` expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOp {
+    Add,      // $add
+    Subtract, // $subtract (exactly two operands)
+    Multiply, // $multiply
+    Divide,   // $divide (exactly two operands)
+}
+
+/// Operator of a `Comparison` expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComparisonOp {
+    Eq,  // $eq
+    Ne,  // $ne
+    Gt,  // $gt
+    Gte, // $gte
+    Lt,  // $lt
+    Lte, // $lte
+}
+
+/// Which end(s) of the string `$trim`-style expressions strip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimMode {
+    Both,  // $trim
+    Start, // $ltrim
+    End,   // $rtrim
 }
 
 /// $reduce expression - reduces an array to a single value
@@ -121,6 +312,10 @@ pub enum ProjectExpression {
 /// Special variables:
 /// - `$$value` - the accumulated value from previous iterations
 /// - `$$this` - the current array element
+///
+/// A `$concat` reduction turns null once it meets a null accumulator or
+/// element; `{$concat: {input: [...], skipNulls: true}}` skips null
+/// elements instead.
 #[derive(Debug, Clone)]
 pub struct ReduceExpression {
     /// Input array field name (without $)
@@ -129,6 +324,8 @@ pub struct ReduceExpression {
     initial_value: Value,
     /// Reduction expression to apply
     in_expr: ReduceInExpr,
+    /// Skip null elements of a `$concat` reduction instead of returning null
+    skip_nulls: bool,
 }
 
 /// Supported reduction operations
@@ -161,8 +358,9 @@ pub struct GroupStage {
 
 #[derive(Debug, Clone)]
 pub enum GroupId {
-    Field(String), // "$city"
-    Null,          // null (all documents in one group)
+    Field(String),                      // "$city"
+    Composite(HashMap<String, String>), // {"city": "$city", "year": "$year"}
+    Null,                               // null (all documents in one group)
 }
 
 #[derive(Debug, Clone)]
@@ -174,14 +372,18 @@ pub enum Accumulator {
     First(String),
     Last(String),
     Count,
-    Push(String),     // $push - collect all values into array
-    AddToSet(String), // $addToSet - collect unique values into array
+    Push(String),         // $push - collect all values into array
+    AddToSet(String),     // $addToSet - collect unique values into array
+    StdDevPop(String),    // $stdDevPop - population standard deviation
+    StdDevSamp(String),   // $stdDevSamp - sample standard deviation
+    MergeObjects(String), // $mergeObjects - merge object values, later keys win
 }
 
 #[derive(Debug, Clone)]
 pub enum SumExpression {
-    Constant(i64), // {"$sum": 1} - count
+    Constant(i64), // {"$sum": 1} - count (f64 if constant * count overflows i64)
     Field(String), // {"$sum": "$amount"} - sum field values
+    Expression(ProjectExpression), // {"$sum": {"$multiply": ["$price", "$qty"]}}
 }
 
 /// $sort stage - sort documents
@@ -202,6 +404,42 @@ pub struct LimitStage {
     limit: usize,
 }
 
+/// $sample stage - pick `size` documents at random
+///
+/// # Syntax
+///
+/// ```json
+/// {$sample: {size: 100}}
+/// ```
+///
+/// Samples whatever reaches the stage, so a preceding `$match` samples a
+/// filtered subset. Uses reservoir sampling, keeping at most `size`
+/// documents; with `size` at or above the input length every document is
+/// returned, in random order.
+#[derive(Debug, Clone)]
+pub struct SampleStage {
+    size: usize,
+    /// Fixed RNG seed; None draws a fresh one on every execution
+    seed: Option<u64>,
+}
+
+/// $count stage - replace the documents by `{<field>: <number of documents>}`
+#[derive(Debug, Clone)]
+pub struct CountStage {
+    field: String,
+}
+
+/// $sortByCount stage - `{_id: <value>, count: <n>}` per distinct value,
+/// most frequent first
+///
+/// Shorthand for a `$group` counting documents per value followed by a
+/// descending `$sort` on `count`; ties are ordered by `_id`.
+#[derive(Debug, Clone)]
+pub struct SortByCountStage {
+    group: GroupStage,
+    sort: SortStage,
+}
+
 /// $skip stage - skip documents
 #[derive(Debug, Clone)]
 pub struct SkipStage {
@@ -235,6 +473,260 @@ pub struct UnwindStage {
     preserve_null_and_empty_arrays: bool,
 }
 
+/// $dedup stage - keep the first document per distinct key
+///
+/// # Syntax
+///
+/// `{$dedup: {by: "field"}}` or `{$dedup: {by: ["a", "b.c"]}}`; `$distinctDocs`
+/// is an alias. Fields may be written with or without a leading `$`. A
+/// missing field counts as null.
+#[derive(Debug, Clone)]
+pub struct DedupStage {
+    /// Key field paths (without leading $)
+    by: Vec<String>,
+}
+
+/// $redact stage - prune subdocuments by a per-level condition
+///
+/// # Syntax
+///
+/// ```json
+/// {$redact: {$cond: {if: {$eq: ["$level", "public"]}, then: "$$DESCEND", else: "$$PRUNE"}}}
+/// ```
+///
+/// `$cond` also takes the array form `[if, then, else]`, and the stage may
+/// be a bare `"$$KEEP"` / `"$$PRUNE"` / `"$$DESCEND"`. The condition is an
+/// `$expr`-style expression evaluated against each level: the document
+/// itself, then (on `$$DESCEND`) every embedded document, including those
+/// inside arrays. `$$KEEP` keeps the level and everything below it
+/// unexamined; `$$PRUNE` drops it.
+#[derive(Debug, Clone)]
+pub struct RedactStage {
+    decision: RedactDecision,
+}
+
+#[derive(Debug, Clone)]
+enum RedactDecision {
+    Action(RedactAction),
+    Cond {
+        condition: Value,
+        then: Box<RedactDecision>,
+        otherwise: Box<RedactDecision>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RedactAction {
+    Keep,
+    Prune,
+    Descend,
+}
+
+/// $addFields stage (alias `$set`) - add computed fields, keeping the rest
+///
+/// # Syntax
+///
+/// ```json
+/// {$addFields: {"tagCount": {"$size": "$tags"}, "stats.total": {"$add": ["$a", "$b"]}}}
+/// ```
+///
+/// Values take the same expressions as `$project`. Every expression sees
+/// the input document, dotted targets create nested objects, and a result
+/// of null still sets the field.
+#[derive(Debug, Clone)]
+pub struct AddFieldsStage {
+    fields: Vec<(String, ProjectExpression)>,
+}
+
+/// $lookup stage - left outer join with another collection
+///
+/// # Syntax
+///
+/// ```json
+/// {$lookup: {from: "orders", localField: "_id", foreignField: "customerId", as: "orders"}}
+/// ```
+///
+/// Every document gets an array under `as` holding the `from` documents
+/// whose `foreignField` equals its `localField`, or an empty array when
+/// none do. An array on either side matches on any of its elements, and a
+/// missing field matches null. A `from` collection that doesn't exist
+/// joins nothing. With an index on `foreignField` each document is joined
+/// by index lookups; otherwise `from` is scanned once per stage.
+///
+/// The pipeline form joins on arbitrary conditions:
+///
+/// ```json
+/// {$lookup: {
+///     from: "orders",
+///     let: {cid: "$_id", min: "$minTotal"},
+///     pipeline: [
+///         {$match: {$expr: {$and: [
+///             {$eq: ["$customerId", "$$cid"]},
+///             {$gte: ["$total", "$$min"]}
+///         ]}}},
+///         {$project: {total: 1}}
+///     ],
+///     as: "bigOrders"
+/// }}
+/// ```
+///
+/// `let` evaluates expressions against the local document; the sub-pipeline
+/// runs over `from` with them available as `$$name` (inside `$match` only
+/// within `$expr`), and its results become the `as` array. An empty
+/// pipeline joins every `from` document.
+///
+/// Cost: the sub-pipeline runs once per distinct set of `let` values. When
+/// it starts with a `$match` holding an equality on an indexed field of
+/// `from` (`field: value`, or an `$expr` `$eq` of the field and a variable
+/// with a string, boolean or integer value), each run reads only the
+/// documents with that value. Otherwise `from` is read once per stage and
+/// every run processes all of it.
+#[derive(Debug, Clone)]
+pub struct LookupStage {
+    from: String,
+    join: LookupJoin,
+    as_field: String,
+}
+
+#[derive(Debug, Clone)]
+enum LookupJoin {
+    /// `localField` / `foreignField` equality
+    Fields {
+        local_field: String,
+        foreign_field: String,
+    },
+    /// `let` variables and the sub-pipeline, kept as JSON until the
+    /// variables are bound
+    Pipeline {
+        vars: Vec<(String, ProjectExpression)>,
+        pipeline: Vec<Value>,
+    },
+}
+
+/// $facet stage - run several sub-pipelines over the same input
+///
+/// # Syntax
+///
+/// ```json
+/// {$facet: {
+///     "byCity": [{"$group": {"_id": "$city", "n": {"$sum": 1}}}],
+///     "oldest": [{"$sort": {"age": -1}}, {"$limit": 3}]
+/// }}
+/// ```
+///
+/// Outputs a single document mapping each facet name to the result array
+/// of its sub-pipeline, even when the input is empty.
+#[derive(Debug, Clone)]
+pub struct FacetStage {
+    facets: Vec<(String, Pipeline)>,
+}
+
+/// $bucket stage - group documents into ranges of a numeric field
+///
+/// # Syntax
+///
+/// ```json
+/// {$bucket: {
+///     groupBy: "$age",
+///     boundaries: [0, 18, 40, 65],
+///     default: "other",                  // optional
+///     output: {"count": {"$sum": 1}}     // optional, this is the default
+/// }}
+/// ```
+///
+/// A document lands in the bucket `[boundaries[i], boundaries[i+1])` holding
+/// its value, and its `_id` is that lower bound. Values outside every bucket,
+/// non-numeric and missing ones go to the `default` bucket, or fail the stage
+/// when there is none. Only non-empty buckets are emitted, in boundary order
+/// with the default bucket last.
+#[derive(Debug, Clone)]
+pub struct BucketStage {
+    /// Field path (without leading $)
+    group_by: String,
+    boundaries: Vec<f64>,
+    /// Boundaries as written, used for the `_id`s
+    boundary_values: Vec<Value>,
+    default: Option<Value>,
+    output: Vec<(String, Accumulator)>,
+}
+
+/// $replaceRoot stage (shorthand `$replaceWith`) - promote a subdocument
+///
+/// # Syntax
+///
+/// ```json
+/// {$replaceRoot: {newRoot: "$address"}}
+/// {$replaceWith: "$address"}
+/// {$replaceWith: {"city": "$address.city", "name": "$name"}}
+/// ```
+///
+/// `newRoot` takes a `$project` expression; every document must evaluate
+/// it to an object, which replaces the document. Anything else, including
+/// a missing field, fails the stage.
+#[derive(Debug, Clone)]
+pub struct ReplaceRootStage {
+    new_root: ProjectExpression,
+}
+
+/// $out stage - write the pipeline's results into a collection
+///
+/// # Syntax
+///
+/// ```json
+/// {$out: "monthly_totals"}
+/// ```
+///
+/// The target collection is replaced: it is dropped and recreated holding
+/// exactly the results, with their `_id`s kept (documents without one get
+/// a fresh id). Must be the last stage; the pipeline then returns no
+/// documents.
+#[derive(Debug, Clone)]
+pub struct OutStage {
+    collection: String,
+}
+
+/// Opens other collections for stages that read them (`$lookup`)
+pub trait CollectionResolver {
+    /// Open `name` for reading; None when no such collection exists
+    fn open(&self, name: &str) -> Result<Option<Box<dyn LookupSource + '_>>>;
+}
+
+/// A collection opened by a [`CollectionResolver`]
+pub trait LookupSource {
+    /// Documents matching `query`
+    fn find(&self, query: &Value) -> Result<Vec<Value>>;
+
+    /// Whether equality queries on `field` are answered by an index
+    fn has_index(&self, field: &str) -> bool;
+}
+
+/// Writes collections for stages that produce one (`$out`)
+pub trait CollectionWriter {
+    /// Drop `name` if it exists and recreate it holding `docs`
+    fn replace_collection(&self, name: &str, docs: Vec<Value>) -> Result<()>;
+}
+
+/// Resolver and writer for pipelines run outside a database
+struct NoCollections;
+
+impl CollectionResolver for NoCollections {
+    fn open(&self, name: &str) -> Result<Option<Box<dyn LookupSource + '_>>> {
+        Err(MongoLiteError::AggregationError(format!(
+            "Cannot read collection '{}': pipeline is not running against a database",
+            name
+        )))
+    }
+}
+
+impl CollectionWriter for NoCollections {
+    fn replace_collection(&self, name: &str, _docs: Vec<Value>) -> Result<()> {
+        Err(MongoLiteError::AggregationError(format!(
+            "Cannot write collection '{}': pipeline is not running against a database",
+            name
+        )))
+    }
+}
+
 impl Pipeline {
     /// Create pipeline from JSON array
     pub fn from_json(pipeline_json: &Value) -> Result<Self> {
@@ -245,6 +737,8 @@ impl Pipeline {
                 ));
             }
 
+            Self::validate(pipeline_json)?;
+
             let mut stages = Vec::new();
             for stage_json in stages_array {
                 let stage = Stage::from_json(stage_json)?;
@@ -259,111 +753,586 @@ impl Pipeline {
         }
     }
 
+    /// Check stage ordering and variable scoping before anything runs
+    ///
+    /// `from_json` calls this before parsing individual stages, so a
+    /// pipeline that breaks these rules is rejected with every problem
+    /// listed, instead of failing (or silently misbehaving) mid-execution:
+    ///
+    /// - `$out` and `$merge` must be the last stage
+    /// - `$facet` sub-pipelines can't contain `$facet`, `$out` or `$merge`
+    /// - `$$name` variables must be bound by an enclosing `$let`, `$map`,
+    ///   `$filter` or `$reduce` (or be a system variable such as `$$ROOT`)
+    ///
+    /// Stage syntax is still checked by the individual stage parsers.
+    pub fn validate(pipeline_json: &Value) -> std::result::Result<(), PipelineValidationError> {
+        let mut issues = Vec::new();
+        if let Value::Array(stages) = pipeline_json {
+            for (index, stage) in stages.iter().enumerate() {
+                let is_last = index + 1 == stages.len();
+                validate_stage(stage, index, is_last, None, &mut issues);
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(PipelineValidationError { issues })
+        }
+    }
+
     /// Execute pipeline on documents
-    pub fn execute(&self, mut docs: Vec<Value>) -> Result<Vec<Value>> {
+    ///
+    /// Stages reading or writing other collections (`$lookup`, `$out`)
+    /// fail; use [`Pipeline::execute_with`] to run those.
+    pub fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        self.execute_with(docs, &NoCollections, &NoCollections)
+    }
+
+    /// Execute pipeline on documents, opening other collections through
+    /// `resolver` and writing `$out` results through `writer`
+    pub fn execute_with(
+        &self,
+        mut docs: Vec<Value>,
+        resolver: &dyn CollectionResolver,
+        writer: &dyn CollectionWriter,
+    ) -> Result<Vec<Value>> {
         for stage in &self.stages {
-            docs = stage.execute(docs)?;
+            docs = stage.execute(docs, resolver, writer)?;
         }
         Ok(docs)
     }
-}
 
-impl Stage {
-    /// Parse stage from JSON
-    fn from_json(stage_json: &Value) -> Result<Self> {
-        if let Value::Object(obj) = stage_json {
-            // Each stage should have exactly one key
-            if obj.len() != 1 {
-                return Err(MongoLiteError::AggregationError(
-                    "Each stage must have exactly one operator".to_string(),
-                ));
-            }
+    /// Whether any stage reads a collection other than the input
+    ///
+    /// The output of such a pipeline also depends on writes to those
+    /// collections.
+    pub fn reads_other_collections(&self) -> bool {
+        self.stages.iter().any(|stage| match stage {
+            Stage::Lookup(_) => true,
+            Stage::Facet(facet) => facet
+                .facets
+                .iter()
+                .any(|(_, pipeline)| pipeline.reads_other_collections()),
+            _ => false,
+        })
+    }
 
-            let (stage_name, stage_spec) = obj.iter().next().unwrap();
+    /// Whether the same input always yields the same output
+    ///
+    /// False when a stage (at any depth of `$facet`) draws random numbers,
+    /// as `$sample` does; such a pipeline must not be answered from a cache.
+    pub fn is_deterministic(&self) -> bool {
+        self.stages.iter().all(|stage| match stage {
+            Stage::Sample(_) => false,
+            Stage::Facet(facet) => facet
+                .facets
+                .iter()
+                .all(|(_, pipeline)| pipeline.is_deterministic()),
+            _ => true,
+        })
+    }
 
-            match stage_name.as_str() {
-                "$match" => Ok(Stage::Match(MatchStage::from_json(stage_spec)?)),
-                "$project" => Ok(Stage::Project(ProjectStage::from_json(stage_spec)?)),
-                "$group" => Ok(Stage::Group(GroupStage::from_json(stage_spec)?)),
-                "$sort" => Ok(Stage::Sort(SortStage::from_json(stage_spec)?)),
-                "$limit" => Ok(Stage::Limit(LimitStage::from_json(stage_spec)?)),
-                "$skip" => Ok(Stage::Skip(SkipStage::from_json(stage_spec)?)),
-                "$unwind" => Ok(Stage::Unwind(UnwindStage::from_json(stage_spec)?)),
-                _ => Err(MongoLiteError::AggregationError(format!(
-                    "Unknown pipeline stage: {}",
-                    stage_name
-                ))),
+    /// Whether the pipeline writes a collection (ends with `$out`)
+    ///
+    /// Running such a pipeline has an effect beyond its (empty) result, so
+    /// it must not be answered from a cache.
+    pub fn writes_collection(&self) -> bool {
+        matches!(self.stages.last(), Some(Stage::Out(_)))
+    }
+
+    /// Array paths the first stage needs, when it only projects `$size`
+    ///
+    /// Returns Some when the pipeline starts with a `$project` made solely of
+    /// `{"$size": "$path"}` expressions (plus an `_id` include or exclude).
+    /// Such input only needs each record's `_id` and array lengths, which
+    /// `find_options::parse_array_sizes` reads without decoding the arrays;
+    /// run the result through [`Pipeline::execute_sized`].
+    pub fn leading_size_paths(&self) -> Option<Vec<String>> {
+        let Some(Stage::Project(project)) = self.stages.first() else {
+            return None;
+        };
+
+        let mut paths = Vec::new();
+        for (field, action) in &project.fields {
+            match action {
+                ProjectField::Expression(ProjectExpression::Size(path)) => paths.push(path.clone()),
+                ProjectField::Include | ProjectField::Exclude if field == "_id" => {}
+                _ => return None,
             }
-        } else {
-            Err(MongoLiteError::AggregationError(
-                "Stage must be an object".to_string(),
-            ))
         }
+
+        // Array indexing ("items.0") and overlapping paths take the normal path
+        let simple = paths.iter().all(|path| {
+            path.split('.')
+                .all(|part| !part.is_empty() && part.parse::<usize>().is_err())
+        });
+        let overlapping = paths.iter().any(|a| {
+            paths.iter().any(|b| {
+                b.len() > a.len() && b.starts_with(a.as_str()) && b[a.len()..].starts_with('.')
+            })
+        });
+        if paths.is_empty() || !simple || overlapping {
+            return None;
+        }
+
+        paths.sort();
+        paths.dedup();
+        Some(paths)
     }
 
-    /// Execute this stage
-    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
-        match self {
-            Stage::Match(stage) => stage.execute(docs),
-            Stage::Project(stage) => stage.execute(docs),
-            Stage::Group(stage) => stage.execute(docs),
-            Stage::Sort(stage) => stage.execute(docs),
-            Stage::Limit(stage) => stage.execute(docs),
-            Stage::Skip(stage) => stage.execute(docs),
-            Stage::Unwind(stage) => stage.execute(docs),
+    /// Execute a pipeline accepted by [`Pipeline::leading_size_paths`]
+    ///
+    /// The first `$project` is evaluated from the pre-computed lengths; the
+    /// remaining stages run on its output as usual.
+    pub fn execute_sized(
+        &self,
+        docs: Vec<SizedDocument>,
+        resolver: &dyn CollectionResolver,
+        writer: &dyn CollectionWriter,
+    ) -> Result<Vec<Value>> {
+        let Some((Stage::Project(project), rest)) = self.stages.split_first() else {
+            return Err(MongoLiteError::AggregationError(
+                "Pipeline does not start with a $size projection".to_string(),
+            ));
+        };
+
+        let mut projected: Vec<Value> = docs.iter().map(|doc| project.project_sized(doc)).collect();
+        for stage in rest {
+            projected = stage.execute(projected, resolver, writer)?;
         }
+        Ok(projected)
     }
-}
 
-impl MatchStage {
-    fn from_json(spec: &Value) -> Result<Self> {
-        let query = Query::from_json(spec)?;
-        Ok(MatchStage { query })
+    /// Remove a leading `$match` stage and return its query
+    ///
+    /// Lets a caller select the input with the query instead, e.g. through
+    /// an index, and run the remaining stages on it.
+    pub fn take_leading_match(&mut self) -> Option<Value> {
+        if !matches!(self.stages.first(), Some(Stage::Match(_))) {
+            return None;
+        }
+        match self.stages.remove(0) {
+            Stage::Match(stage) => Some(stage.query.into_json()),
+            _ => unreachable!("checked above"),
+        }
     }
 
-    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
-        let mut results = Vec::new();
+    /// Split into the leading stages that work one document at a time and
+    /// the rest of the pipeline
+    ///
+    /// `$match`, `$project`, `$addFields`, `$unwind`, `$redact`,
+    /// `$replaceRoot`, `$skip` and `$limit` stream; everything from the
+    /// first other stage on (`$group`, `$sort`, `$lookup`, ...) is returned
+    /// as a pipeline that needs all of its input at once.
+    pub fn into_streaming(self) -> (StreamingStages, Option<Pipeline>) {
+        let split = self
+            .stages
+            .iter()
+            .position(|stage| !stage.is_streamable())
+            .unwrap_or(self.stages.len());
+        let mut stages = self.stages;
+        let rest = stages.split_off(split);
+
+        let steps = stages
+            .into_iter()
+            .map(|stage| match stage {
+                Stage::Skip(SkipStage { skip }) => StreamStep::Skip { remaining: skip },
+                Stage::Limit(LimitStage { limit }) => StreamStep::Limit { remaining: limit },
+                stage => StreamStep::Stage(stage),
+            })
+            .collect();
+        let rest = (!rest.is_empty()).then_some(Pipeline { stages: rest });
+        (StreamingStages { steps }, rest)
+    }
+}
 
-        for doc in docs {
-            // Add _id if not present (for aggregation intermediate results)
-            let doc_with_id = if doc.get("_id").is_none() {
-                let mut doc_obj = doc.clone();
-                if let Value::Object(ref mut map) = doc_obj {
-                    map.insert("_id".to_string(), Value::from(0)); // Temporary _id
-                }
-                doc_obj
-            } else {
-                doc.clone()
-            };
+/// Leading pipeline stages applied lazily, one input document at a time
+///
+/// Built by [`Pipeline::into_streaming`]. `$skip` and `$limit` keep their
+/// counts across documents, so feed the input in order.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingStages {
+    steps: Vec<StreamStep>,
+}
 
-            let doc_json_str = serde_json::to_string(&doc_with_id)?;
-            let document = Document::from_json(&doc_json_str)?;
+#[derive(Debug, Clone)]
+enum StreamStep {
+    Stage(Stage),
+    Skip { remaining: usize },
+    Limit { remaining: usize },
+}
 
-            if self.query.matches(&document) {
-                results.push(doc);
+impl StreamingStages {
+    /// Run one input document through the stages
+    ///
+    /// Returns the documents it turns into: none when filtered out or
+    /// skipped, several when `$unwind` expands it.
+    pub fn push(&mut self, doc: Value) -> Result<Vec<Value>> {
+        let mut docs = vec![doc];
+        for step in &mut self.steps {
+            if docs.is_empty() {
+                break;
+            }
+            match step {
+                StreamStep::Stage(stage) => {
+                    docs = stage.execute(docs, &NoCollections, &NoCollections)?;
+                }
+                StreamStep::Skip { remaining } => {
+                    let skipped = (*remaining).min(docs.len());
+                    docs.drain(..skipped);
+                    *remaining -= skipped;
+                }
+                StreamStep::Limit { remaining } => {
+                    docs.truncate(*remaining);
+                    *remaining -= docs.len();
+                }
             }
         }
+        Ok(docs)
+    }
 
-        Ok(results)
+    /// Whether a `$limit` is used up, so further input can't produce output
+    pub fn is_exhausted(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step, StreamStep::Limit { remaining: 0 }))
     }
 }
 
-impl ProjectStage {
-    fn from_json(spec: &Value) -> Result<Self> {
+// ============================================================================
+// PIPELINE VALIDATION
+// ============================================================================
+
+/// Stages that write their input somewhere and must end the pipeline
+const TERMINAL_STAGES: &[&str] = &["$out", "$merge"];
+
+/// Variables that are always defined
+const SYSTEM_VARIABLES: &[&str] = &["ROOT", "CURRENT", "REMOVE", "KEEP", "PRUNE", "DESCEND"];
+
+/// One problem found by [`Pipeline::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineIssue {
+    /// Index of the offending top-level stage
+    pub stage: usize,
+    pub message: String,
+}
+
+/// Every problem [`Pipeline::validate`] found, in stage order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineValidationError {
+    pub issues: Vec<PipelineIssue>,
+}
+
+impl std::fmt::Display for PipelineValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid pipeline")?;
+        for (i, issue) in self.issues.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(f, "{}stage {}: {}", sep, issue.stage, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PipelineValidationError {}
+
+impl From<PipelineValidationError> for MongoLiteError {
+    fn from(err: PipelineValidationError) -> Self {
+        MongoLiteError::AggregationError(err.to_string())
+    }
+}
+
+/// Validate one stage; `facet` names the enclosing `$facet` output, if any
+fn validate_stage(
+    stage: &Value,
+    index: usize,
+    is_last: bool,
+    facet: Option<&str>,
+    issues: &mut Vec<PipelineIssue>,
+) {
+    let Some((name, spec)) = stage.as_object().and_then(|obj| obj.iter().next()) else {
+        return;
+    };
+    let mut report = |message: String| {
+        let message = match facet {
+            Some(facet) => format!("$facet '{}': {}", facet, message),
+            None => message,
+        };
+        issues.push(PipelineIssue {
+            stage: index,
+            message,
+        });
+    };
+
+    let terminal = TERMINAL_STAGES.contains(&name.as_str());
+    if facet.is_some() && (terminal || name == "$facet") {
+        report(format!("{} is not allowed inside $facet", name));
+    } else if terminal && !is_last {
+        report(format!("{} must be the last stage", name));
+    }
+
+    match name.as_str() {
+        // Query values are matched literally, never as variables
+        "$match" => {}
+        "$facet" if facet.is_none() => {
+            for (output, sub_pipeline) in spec.as_object().into_iter().flatten() {
+                let sub_stages = sub_pipeline.as_array().map(Vec::as_slice).unwrap_or(&[]);
+                for (i, sub_stage) in sub_stages.iter().enumerate() {
+                    let sub_last = i + 1 == sub_stages.len();
+                    validate_stage(sub_stage, index, sub_last, Some(output), issues);
+                }
+            }
+        }
+        // The sub-pipeline sees the `let` variables, which are evaluated outside it
+        "$lookup" => {
+            let vars = spec.get("let").and_then(Value::as_object);
+            let mut undefined = Vec::new();
+            for value in vars.into_iter().flatten().map(|(_, v)| v) {
+                collect_undefined_variables(value, &mut Vec::new(), &mut undefined);
+            }
+            let names: Vec<String> = vars.into_iter().flatten().map(|(k, _)| k.clone()).collect();
+            let sub_stages = spec.get("pipeline").and_then(Value::as_array);
+            for sub_stage in sub_stages.into_iter().flatten() {
+                if sub_stage.get("$match").is_none() {
+                    collect_undefined_variables(sub_stage, &mut names.clone(), &mut undefined);
+                }
+            }
+            for variable in undefined {
+                report(format!("undefined variable $${}", variable));
+            }
+        }
+        _ => {
+            let mut undefined = Vec::new();
+            collect_undefined_variables(spec, &mut Vec::new(), &mut undefined);
+            for variable in undefined {
+                report(format!("undefined variable $${}", variable));
+            }
+        }
+    }
+}
+
+/// Record `$$name` references in `expr` not bound by `scope`
+fn collect_undefined_variables(expr: &Value, scope: &mut Vec<String>, undefined: &mut Vec<String>) {
+    match expr {
+        Value::String(s) => {
+            if let Some(reference) = s.strip_prefix("$$") {
+                let name = reference.split('.').next().unwrap_or_default();
+                let bound = scope.iter().any(|v| v == name) || SYSTEM_VARIABLES.contains(&name);
+                if !bound && !undefined.iter().any(|v| v == name) {
+                    undefined.push(name.to_string());
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_undefined_variables(item, scope, undefined);
+            }
+        }
+        Value::Object(obj) => {
+            let scoped = match obj.iter().next() {
+                Some((op, args)) if obj.len() == 1 => scoped_expression(op, args, scope, undefined),
+                _ => false,
+            };
+            if !scoped {
+                for value in obj.values() {
+                    collect_undefined_variables(value, scope, undefined);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Handle operators that bind variables; false for any other operator
+fn scoped_expression(
+    op: &str,
+    args: &Value,
+    scope: &mut Vec<String>,
+    undefined: &mut Vec<String>,
+) -> bool {
+    let Some(args) = args.as_object() else {
+        return false;
+    };
+    // (bound variable names, arguments evaluated with them in scope)
+    let (bound, inner): (Vec<String>, &[&str]) = match op {
+        "$reduce" => (vec!["value".to_string(), "this".to_string()], &["in"]),
+        "$map" | "$filter" => {
+            let name = args.get("as").and_then(Value::as_str).unwrap_or("this");
+            (vec![name.to_string()], &["in", "cond"])
+        }
+        "$let" => {
+            let vars = args.get("vars").and_then(Value::as_object);
+            let names = vars.into_iter().flatten().map(|(k, _)| k.clone()).collect();
+            (names, &["in"])
+        }
+        _ => return false,
+    };
+
+    for (key, value) in args {
+        if inner.contains(&key.as_str()) {
+            continue;
+        }
+        if key == "vars" {
+            // $let variable values are evaluated in the outer scope
+            for var_value in value.as_object().into_iter().flatten().map(|(_, v)| v) {
+                collect_undefined_variables(var_value, scope, undefined);
+            }
+        } else {
+            collect_undefined_variables(value, scope, undefined);
+        }
+    }
+
+    let outer_len = scope.len();
+    scope.extend(bound);
+    for key in inner {
+        if let Some(value) = args.get(*key) {
+            collect_undefined_variables(value, scope, undefined);
+        }
+    }
+    scope.truncate(outer_len);
+    true
+}
+
+impl Stage {
+    /// Whether the stage handles each document on its own (see
+    /// [`Pipeline::into_streaming`])
+    fn is_streamable(&self) -> bool {
+        matches!(
+            self,
+            Stage::Match(_)
+                | Stage::Project(_)
+                | Stage::AddFields(_)
+                | Stage::Unwind(_)
+                | Stage::Redact(_)
+                | Stage::ReplaceRoot(_)
+                | Stage::Skip(_)
+                | Stage::Limit(_)
+        )
+    }
+
+    /// Parse stage from JSON
+    fn from_json(stage_json: &Value) -> Result<Self> {
+        if let Value::Object(obj) = stage_json {
+            // Each stage should have exactly one key
+            if obj.len() != 1 {
+                return Err(MongoLiteError::AggregationError(
+                    "Each stage must have exactly one operator".to_string(),
+                ));
+            }
+
+            let (stage_name, stage_spec) = obj.iter().next().unwrap();
+
+            match stage_name.as_str() {
+                "$match" => Ok(Stage::Match(MatchStage::from_json(stage_spec)?)),
+                "$project" => Ok(Stage::Project(ProjectStage::from_json(stage_spec)?)),
+                "$group" => Ok(Stage::Group(GroupStage::from_json(stage_spec)?)),
+                "$sort" => Ok(Stage::Sort(SortStage::from_json(stage_spec)?)),
+                "$limit" => Ok(Stage::Limit(LimitStage::from_json(stage_spec)?)),
+                "$sample" => Ok(Stage::Sample(SampleStage::from_json(stage_spec)?)),
+                "$skip" => Ok(Stage::Skip(SkipStage::from_json(stage_spec)?)),
+                "$unwind" => Ok(Stage::Unwind(UnwindStage::from_json(stage_spec)?)),
+                "$dedup" | "$distinctDocs" => Ok(Stage::Dedup(DedupStage::from_json(stage_spec)?)),
+                "$redact" => Ok(Stage::Redact(RedactStage::from_json(stage_spec)?)),
+                "$count" => Ok(Stage::Count(CountStage::from_json(stage_spec)?)),
+                "$sortByCount" => Ok(Stage::SortByCount(SortByCountStage::from_json(stage_spec)?)),
+                "$lookup" => Ok(Stage::Lookup(LookupStage::from_json(stage_spec)?)),
+                "$facet" => Ok(Stage::Facet(FacetStage::from_json(stage_spec)?)),
+                "$out" => Ok(Stage::Out(OutStage::from_json(stage_spec)?)),
+                "$bucket" => Ok(Stage::Bucket(BucketStage::from_json(stage_spec)?)),
+                "$replaceRoot" => Ok(Stage::ReplaceRoot(ReplaceRootStage::from_json(stage_spec)?)),
+                "$replaceWith" => Ok(Stage::ReplaceRoot(ReplaceRootStage::from_new_root(
+                    stage_spec,
+                )?)),
+                "$addFields" | "$set" => {
+                    Ok(Stage::AddFields(AddFieldsStage::from_json(stage_spec)?))
+                }
+                _ => Err(MongoLiteError::AggregationError(format!(
+                    "Unknown pipeline stage: {}",
+                    stage_name
+                ))),
+            }
+        } else {
+            Err(MongoLiteError::AggregationError(
+                "Stage must be an object".to_string(),
+            ))
+        }
+    }
+
+    /// Execute this stage
+    fn execute(
+        &self,
+        docs: Vec<Value>,
+        resolver: &dyn CollectionResolver,
+        writer: &dyn CollectionWriter,
+    ) -> Result<Vec<Value>> {
+        match self {
+            Stage::Match(stage) => stage.execute(docs),
+            Stage::Project(stage) => stage.execute(docs),
+            Stage::Group(stage) => stage.execute(docs),
+            Stage::Sort(stage) => stage.execute(docs),
+            Stage::Limit(stage) => stage.execute(docs),
+            Stage::Sample(stage) => stage.execute(docs),
+            Stage::Skip(stage) => stage.execute(docs),
+            Stage::Unwind(stage) => stage.execute(docs),
+            Stage::Dedup(stage) => stage.execute(docs),
+            Stage::Redact(stage) => stage.execute(docs),
+            Stage::AddFields(stage) => stage.execute(docs),
+            Stage::Count(stage) => stage.execute(docs),
+            Stage::SortByCount(stage) => stage.execute(docs),
+            Stage::Lookup(stage) => stage.execute(docs, resolver),
+            Stage::Facet(stage) => stage.execute(docs, resolver, writer),
+            Stage::Bucket(stage) => stage.execute(docs),
+            Stage::ReplaceRoot(stage) => stage.execute(docs),
+            Stage::Out(stage) => stage.execute(docs, writer),
+        }
+    }
+}
+
+impl MatchStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let query = Query::from_json(spec)?;
+        Ok(MatchStage { query })
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        let mut results = Vec::new();
+
+        for doc in docs {
+            // Add _id if not present (for aggregation intermediate results)
+            let doc_with_id = if doc.get("_id").is_none() {
+                let mut doc_obj = doc.clone();
+                if let Value::Object(ref mut map) = doc_obj {
+                    map.insert("_id".to_string(), Value::from(0)); // Temporary _id
+                }
+                doc_obj
+            } else {
+                doc.clone()
+            };
+
+            let doc_json_str = serde_json::to_string(&doc_with_id)?;
+            let document = Document::from_json(&doc_json_str)?;
+
+            if self.query.matches(&document) {
+                results.push(doc);
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+impl ProjectStage {
+    fn from_json(spec: &Value) -> Result<Self> {
         if let Value::Object(obj) = spec {
             let mut fields = HashMap::new();
 
             for (field, value) in obj {
-                let project_field = if let Some(n) = value.as_i64() {
-                    match n {
-                        1 => ProjectField::Include,
-                        0 => ProjectField::Exclude,
-                        _ => {
-                            return Err(MongoLiteError::AggregationError(format!(
-                                "Invalid project value: {}",
-                                n
-                            )))
-                        }
+                let project_field = if let Some(include) = Self::parse_inclusion_flag(value)? {
+                    if include {
+                        ProjectField::Include
+                    } else {
+                        ProjectField::Exclude
                     }
                 } else if let Some(s) = value.as_str() {
                     if s.starts_with('$') {
@@ -374,9 +1343,14 @@ impl ProjectStage {
                             s
                         )));
                     }
-                } else if let Value::Object(expr_obj) = value {
-                    // Parse expression objects like {"$size": "$tags"}
-                    Self::parse_expression(expr_obj)?
+                } else if let Value::Object(spec_obj) = value {
+                    if Self::is_nested_spec(spec_obj) {
+                        // Reshaping objects like {"address": {"city": "$city"}}
+                        ProjectField::Expression(Self::parse_nested_spec(spec_obj)?)
+                    } else {
+                        // Parse expression objects like {"$size": "$tags"}
+                        ProjectField::Expression(Self::parse_expression(spec_obj)?)
+                    }
                 } else {
                     return Err(MongoLiteError::AggregationError(
                         "Project field must be 0, 1, field reference, or expression object"
@@ -395,8 +1369,27 @@ impl ProjectStage {
         }
     }
 
+    /// Interpret 1 / 0, 1.0 / 0.0 and true / false as include / exclude
+    ///
+    /// Returns None for values that are not inclusion flags (references,
+    /// expressions), and an error for any other number.
+    fn parse_inclusion_flag(value: &Value) -> Result<Option<bool>> {
+        match value {
+            Value::Bool(b) => Ok(Some(*b)),
+            Value::Number(n) => match n.as_f64() {
+                Some(1.0) => Ok(Some(true)),
+                Some(0.0) => Ok(Some(false)),
+                _ => Err(MongoLiteError::AggregationError(format!(
+                    "Invalid project value: {}",
+                    n
+                ))),
+            },
+            _ => Ok(None),
+        }
+    }
+
     /// Parse an expression object like {"$size": "$tags"} or {"$reduce": {...}}
-    fn parse_expression(obj: &serde_json::Map<String, Value>) -> Result<ProjectField> {
+    fn parse_expression(obj: &serde_json::Map<String, Value>) -> Result<ProjectExpression> {
         if obj.len() != 1 {
             return Err(MongoLiteError::AggregationError(
                 "Expression object must have exactly one operator".to_string(),
@@ -406,14 +1399,14 @@ impl ProjectStage {
         let (op, arg) = obj.iter().next().unwrap();
 
         match op.as_str() {
+            // The argument as-is, even if it looks like a reference or operator
+            "$literal" => Ok(ProjectExpression::Literal(arg.clone())),
             "$size" => {
                 // $size expects a field reference like "$tags"
                 if let Some(field_ref) = arg.as_str() {
                     if field_ref.starts_with('$') {
                         let field_name = field_ref.trim_start_matches('$').to_string();
-                        Ok(ProjectField::Expression(ProjectExpression::Size(
-                            field_name,
-                        )))
+                        Ok(ProjectExpression::Size(field_name))
                     } else {
                         Err(MongoLiteError::AggregationError(
                             "$size argument must be a field reference starting with $".to_string(),
@@ -426,6 +1419,70 @@ impl ProjectStage {
                 }
             }
             "$reduce" => Self::parse_reduce_expression(arg),
+            "$split" => Self::parse_split_expression(arg),
+            "$trim" => Self::parse_trim_expression(arg, TrimMode::Both, "$trim"),
+            "$ltrim" => Self::parse_trim_expression(arg, TrimMode::Start, "$ltrim"),
+            "$rtrim" => Self::parse_trim_expression(arg, TrimMode::End, "$rtrim"),
+            "$objectToArray" => Ok(ProjectExpression::ObjectToArray(Box::new(
+                Self::parse_single_operand(arg)?,
+            ))),
+            "$arrayToObject" => Ok(ProjectExpression::ArrayToObject(Box::new(
+                Self::parse_single_operand(arg)?,
+            ))),
+            "$add" => Self::parse_arithmetic_expression(arg, ArithmeticOp::Add, "$add"),
+            "$subtract" => {
+                Self::parse_arithmetic_expression(arg, ArithmeticOp::Subtract, "$subtract")
+            }
+            "$multiply" => {
+                Self::parse_arithmetic_expression(arg, ArithmeticOp::Multiply, "$multiply")
+            }
+            "$divide" => Self::parse_arithmetic_expression(arg, ArithmeticOp::Divide, "$divide"),
+            "$concat" => Self::parse_concat_expression(arg),
+            "$toUpper" => Ok(ProjectExpression::ChangeCase {
+                input: Box::new(Self::parse_single_operand(arg)?),
+                upper: true,
+            }),
+            "$toLower" => Ok(ProjectExpression::ChangeCase {
+                input: Box::new(Self::parse_single_operand(arg)?),
+                upper: false,
+            }),
+            "$substr" => match arg.as_array().map(Vec::as_slice) {
+                Some([input, start, length]) => Ok(ProjectExpression::Substr {
+                    input: Box::new(Self::parse_operand(input)?),
+                    start: Box::new(Self::parse_operand(start)?),
+                    length: Box::new(Self::parse_operand(length)?),
+                }),
+                _ => Err(MongoLiteError::AggregationError(
+                    "$substr requires an array of [string, start, length]".to_string(),
+                )),
+            },
+            "$eq" => Self::parse_comparison_expression(arg, ComparisonOp::Eq, "$eq"),
+            "$ne" => Self::parse_comparison_expression(arg, ComparisonOp::Ne, "$ne"),
+            "$gt" => Self::parse_comparison_expression(arg, ComparisonOp::Gt, "$gt"),
+            "$gte" => Self::parse_comparison_expression(arg, ComparisonOp::Gte, "$gte"),
+            "$lt" => Self::parse_comparison_expression(arg, ComparisonOp::Lt, "$lt"),
+            "$lte" => Self::parse_comparison_expression(arg, ComparisonOp::Lte, "$lte"),
+            "$cond" => Self::parse_cond_expression(arg),
+            "$switch" => Self::parse_switch_expression(arg),
+            "$ifNull" => match arg.as_array().map(Vec::as_slice) {
+                Some([value, fallback]) => Ok(ProjectExpression::IfNull {
+                    value: Box::new(Self::parse_operand(value)?),
+                    fallback: Box::new(Self::parse_operand(fallback)?),
+                }),
+                _ => Err(MongoLiteError::AggregationError(
+                    "$ifNull requires an array of exactly 2 operands".to_string(),
+                )),
+            },
+            "$mergeObjects" => {
+                let operands = match arg {
+                    Value::Array(args) => args
+                        .iter()
+                        .map(Self::parse_operand)
+                        .collect::<Result<Vec<_>>>()?,
+                    _ => vec![Self::parse_operand(arg)?],
+                };
+                Ok(ProjectExpression::MergeObjects(operands))
+            }
             _ => Err(MongoLiteError::AggregationError(format!(
                 "Unknown projection expression operator: {}",
                 op
@@ -433,66 +1490,349 @@ impl ProjectStage {
         }
     }
 
-    /// Parse $reduce expression
-    ///
-    /// Format: {input: "$arrayField", initialValue: value, in: {$op: [...]}}
-    fn parse_reduce_expression(spec: &Value) -> Result<ProjectField> {
-        let obj = spec.as_object().ok_or_else(|| {
-            MongoLiteError::AggregationError("$reduce must be an object".to_string())
-        })?;
-
-        // Parse input field
-        let input = obj.get("input").and_then(|v| v.as_str()).ok_or_else(|| {
-            MongoLiteError::AggregationError("$reduce requires 'input' field reference".to_string())
-        })?;
-
-        if !input.starts_with('$') {
-            return Err(MongoLiteError::AggregationError(
-                "$reduce input must be a field reference starting with $".to_string(),
-            ));
+    /// Parse an expression operand: "$field" reference, nested operator object, or literal
+    fn parse_operand(value: &Value) -> Result<ProjectExpression> {
+        match value {
+            Value::String(s) if s.starts_with('$') && !s.starts_with("$$") => {
+                Ok(ProjectExpression::Field(s[1..].to_string()))
+            }
+            Value::Object(obj) if obj.len() == 1 && obj.keys().all(|k| k.starts_with('$')) => {
+                Self::parse_expression(obj)
+            }
+            _ => Ok(ProjectExpression::Literal(value.clone())),
         }
+    }
 
-        let input_field = input.trim_start_matches('$').to_string();
+    /// True for a non-empty object without operator keys (a subdocument to build)
+    fn is_nested_spec(obj: &serde_json::Map<String, Value>) -> bool {
+        !obj.is_empty() && !obj.keys().any(|k| k.starts_with('$'))
+    }
 
-        // Parse initialValue
-        let initial_value = obj.get("initialValue").cloned().ok_or_else(|| {
-            MongoLiteError::AggregationError("$reduce requires 'initialValue'".to_string())
-        })?;
+    /// Parse a nested document spec
+    ///
+    /// Values are field references, operator objects, further nested specs,
+    /// or literals. Numbers and booleans here are literals, not inclusion flags.
+    fn parse_nested_spec(obj: &serde_json::Map<String, Value>) -> Result<ProjectExpression> {
+        let mut entries = Vec::with_capacity(obj.len());
+        for (key, value) in obj {
+            let expr = match value {
+                Value::Object(inner) if Self::is_nested_spec(inner) => {
+                    Self::parse_nested_spec(inner)?
+                }
+                Value::Object(inner) if inner.keys().any(|k| k.starts_with('$')) => {
+                    Self::parse_expression(inner)?
+                }
+                _ => Self::parse_operand(value)?,
+            };
+            entries.push((key.clone(), expr));
+        }
+        Ok(ProjectExpression::Object(entries))
+    }
 
-        // Parse in expression
-        let in_expr = obj.get("in").ok_or_else(|| {
-            MongoLiteError::AggregationError("$reduce requires 'in' expression".to_string())
-        })?;
+    /// Parse the argument of a one-operand operator
+    ///
+    /// MongoDB allows both `{"$op": expr}` and `{"$op": [expr]}`, so a literal
+    /// array argument has to be wrapped once more (`[[...]]`).
+    fn parse_single_operand(arg: &Value) -> Result<ProjectExpression> {
+        match arg {
+            Value::Array(args) if args.len() == 1 => Self::parse_operand(&args[0]),
+            _ => Self::parse_operand(arg),
+        }
+    }
 
-        let reduce_in = Self::parse_reduce_in_expr(in_expr)?;
+    /// Parse $eq / $ne / $gt / $gte / $lt / $lte expression
+    ///
+    /// Format: [left, right]
+    fn parse_comparison_expression(
+        spec: &Value,
+        op: ComparisonOp,
+        op_name: &str,
+    ) -> Result<ProjectExpression> {
+        match spec.as_array().map(Vec::as_slice) {
+            Some([left, right]) => Ok(ProjectExpression::Comparison {
+                op,
+                operands: Box::new([Self::parse_operand(left)?, Self::parse_operand(right)?]),
+            }),
+            _ => Err(MongoLiteError::AggregationError(format!(
+                "{} requires an array of exactly 2 operands",
+                op_name
+            ))),
+        }
+    }
 
-        Ok(ProjectField::Expression(ProjectExpression::Reduce(
-            ReduceExpression {
-                input: input_field,
-                initial_value,
-                in_expr: reduce_in,
+    /// Parse $cond expression
+    ///
+    /// Format: [if, then, else] or {if, then, else}
+    fn parse_cond_expression(spec: &Value) -> Result<ProjectExpression> {
+        let (predicate, then, otherwise) = match spec {
+            Value::Array(args) => match args.as_slice() {
+                [predicate, then, otherwise] => (predicate, then, otherwise),
+                _ => {
+                    return Err(MongoLiteError::AggregationError(format!(
+                        "$cond array requires exactly 3 elements [if, then, else], got {}",
+                        args.len()
+                    )))
+                }
             },
-        )))
+            Value::Object(obj) => match (obj.get("if"), obj.get("then"), obj.get("else")) {
+                (Some(predicate), Some(then), Some(otherwise)) if obj.len() == 3 => {
+                    (predicate, then, otherwise)
+                }
+                _ => {
+                    return Err(MongoLiteError::AggregationError(
+                        "$cond object requires exactly 'if', 'then' and 'else'".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(MongoLiteError::AggregationError(
+                    "$cond requires an array or an {if, then, else} object".to_string(),
+                ))
+            }
+        };
+        Ok(ProjectExpression::Cond {
+            predicate: Box::new(Self::parse_operand(predicate)?),
+            then: Box::new(Self::parse_operand(then)?),
+            otherwise: Box::new(Self::parse_operand(otherwise)?),
+        })
     }
 
-    /// Parse the 'in' expression of $reduce
+    /// Parse $switch expression
     ///
-    /// Supports: {$add: [...]}, {$multiply: [...]}, {$concat: [...]}
-    /// Also supports object field references: {$add: ["$$value", "$$this.field"]}
-    fn parse_reduce_in_expr(expr: &Value) -> Result<ReduceInExpr> {
-        let obj = expr.as_object().ok_or_else(|| {
-            MongoLiteError::AggregationError(
-                "$reduce 'in' must be an expression object".to_string(),
-            )
+    /// Format: {branches: [{case, then}, ...], default}
+    fn parse_switch_expression(spec: &Value) -> Result<ProjectExpression> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError("$switch requires an object".to_string())
         })?;
+        if let Some(key) = obj.keys().find(|k| *k != "branches" && *k != "default") {
+            return Err(MongoLiteError::AggregationError(format!(
+                "$switch: unknown argument '{}'",
+                key
+            )));
+        }
+        let branch_specs = obj
+            .get("branches")
+            .and_then(Value::as_array)
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| {
+                MongoLiteError::AggregationError(
+                    "$switch requires a non-empty 'branches' array".to_string(),
+                )
+            })?;
 
-        if obj.len() != 1 {
-            return Err(MongoLiteError::AggregationError(
-                "$reduce 'in' must have exactly one operator".to_string(),
-            ));
+        let mut branches = Vec::with_capacity(branch_specs.len());
+        for branch in branch_specs {
+            match branch.as_object() {
+                Some(b) if b.len() == 2 && b.contains_key("case") && b.contains_key("then") => {
+                    branches.push((
+                        Self::parse_operand(&b["case"])?,
+                        Self::parse_operand(&b["then"])?,
+                    ));
+                }
+                _ => {
+                    return Err(MongoLiteError::AggregationError(
+                        "$switch: each branch must be an object with exactly 'case' and 'then'"
+                            .to_string(),
+                    ))
+                }
+            }
         }
 
-        let (op, args) = obj.iter().next().unwrap();
+        let default = match obj.get("default") {
+            Some(default) => Some(Box::new(Self::parse_operand(default)?)),
+            None => None,
+        };
+        Ok(ProjectExpression::Switch { branches, default })
+    }
+
+    /// Parse $add / $subtract / $multiply / $divide expression
+    ///
+    /// Format: [operand, operand, ...]; exactly two for $subtract / $divide
+    fn parse_arithmetic_expression(
+        spec: &Value,
+        op: ArithmeticOp,
+        op_name: &str,
+    ) -> Result<ProjectExpression> {
+        let args = spec.as_array().filter(|a| !a.is_empty()).ok_or_else(|| {
+            MongoLiteError::AggregationError(format!(
+                "{} requires a non-empty array of operands",
+                op_name
+            ))
+        })?;
+        let binary = matches!(op, ArithmeticOp::Subtract | ArithmeticOp::Divide);
+        if binary && args.len() != 2 {
+            return Err(MongoLiteError::AggregationError(format!(
+                "{} requires exactly 2 operands, got {}",
+                op_name,
+                args.len()
+            )));
+        }
+
+        Ok(ProjectExpression::Arithmetic {
+            op,
+            operands: args
+                .iter()
+                .map(Self::parse_operand)
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    /// Parse $split expression
+    ///
+    /// Format: ["$stringField", delimiter]
+    fn parse_split_expression(spec: &Value) -> Result<ProjectExpression> {
+        let args = spec.as_array().filter(|a| a.len() == 2).ok_or_else(|| {
+            MongoLiteError::AggregationError(
+                "$split requires an array of [string, delimiter]".to_string(),
+            )
+        })?;
+
+        if args[1].as_str() == Some("") {
+            return Err(MongoLiteError::AggregationError(
+                "$split delimiter must not be empty".to_string(),
+            ));
+        }
+
+        Ok(ProjectExpression::Split {
+            input: Box::new(Self::parse_operand(&args[0])?),
+            delimiter: Box::new(Self::parse_operand(&args[1])?),
+        })
+    }
+
+    /// Parse $trim / $ltrim / $rtrim expression
+    ///
+    /// Format: {input: "$stringField", chars: " -"} (chars is optional)
+    fn parse_trim_expression(
+        spec: &Value,
+        mode: TrimMode,
+        op_name: &str,
+    ) -> Result<ProjectExpression> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError(format!("{} must be an object", op_name))
+        })?;
+
+        let input = obj.get("input").ok_or_else(|| {
+            MongoLiteError::AggregationError(format!("{} requires 'input'", op_name))
+        })?;
+
+        let chars = match obj.get("chars") {
+            Some(chars) => Some(Box::new(Self::parse_operand(chars)?)),
+            None => None,
+        };
+
+        Ok(ProjectExpression::Trim {
+            input: Box::new(Self::parse_operand(input)?),
+            chars,
+            mode,
+        })
+    }
+
+    /// Parse $concat expression
+    ///
+    /// Format: [operand, ...] or {input: [operand, ...], skipNulls: bool}
+    fn parse_concat_expression(spec: &Value) -> Result<ProjectExpression> {
+        let (args, skip_nulls) = Self::parse_concat_spec(spec, "$concat")?;
+        let args = args.as_array().ok_or_else(|| {
+            MongoLiteError::AggregationError("$concat requires an array of operands".to_string())
+        })?;
+
+        Ok(ProjectExpression::Concat {
+            operands: args
+                .iter()
+                .map(Self::parse_operand)
+                .collect::<Result<Vec<_>>>()?,
+            skip_nulls,
+        })
+    }
+
+    /// Split a `$concat` argument into its operands and the skipNulls option
+    fn parse_concat_spec<'a>(spec: &'a Value, op_name: &str) -> Result<(&'a Value, bool)> {
+        let Some(obj) = spec.as_object() else {
+            return Ok((spec, false));
+        };
+        if let Some(key) = obj.keys().find(|k| *k != "input" && *k != "skipNulls") {
+            return Err(MongoLiteError::AggregationError(format!(
+                "{} does not support '{}'",
+                op_name, key
+            )));
+        }
+        let input = obj.get("input").ok_or_else(|| {
+            MongoLiteError::AggregationError(format!("{} requires 'input' operands", op_name))
+        })?;
+        let skip_nulls = match obj.get("skipNulls") {
+            None => false,
+            Some(Value::Bool(skip)) => *skip,
+            Some(_) => {
+                return Err(MongoLiteError::AggregationError(format!(
+                    "{} skipNulls must be a boolean",
+                    op_name
+                )))
+            }
+        };
+        Ok((input, skip_nulls))
+    }
+
+    /// Parse $reduce expression
+    ///
+    /// Format: {input: "$arrayField", initialValue: value, in: {$op: [...]}}
+    fn parse_reduce_expression(spec: &Value) -> Result<ProjectExpression> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError("$reduce must be an object".to_string())
+        })?;
+
+        // Parse input field
+        let input = obj.get("input").and_then(|v| v.as_str()).ok_or_else(|| {
+            MongoLiteError::AggregationError("$reduce requires 'input' field reference".to_string())
+        })?;
+
+        if !input.starts_with('$') {
+            return Err(MongoLiteError::AggregationError(
+                "$reduce input must be a field reference starting with $".to_string(),
+            ));
+        }
+
+        let input_field = input.trim_start_matches('$').to_string();
+
+        // Parse initialValue
+        let initial_value = obj.get("initialValue").cloned().ok_or_else(|| {
+            MongoLiteError::AggregationError("$reduce requires 'initialValue'".to_string())
+        })?;
+
+        // Parse in expression
+        let in_expr = obj.get("in").ok_or_else(|| {
+            MongoLiteError::AggregationError("$reduce requires 'in' expression".to_string())
+        })?;
+
+        let (reduce_in, skip_nulls) = Self::parse_reduce_in_expr(in_expr)?;
+
+        Ok(ProjectExpression::Reduce(ReduceExpression {
+            input: input_field,
+            initial_value,
+            in_expr: reduce_in,
+            skip_nulls,
+        }))
+    }
+
+    /// Parse the 'in' expression of $reduce
+    ///
+    /// Supports: {$add: [...]}, {$multiply: [...]}, {$concat: [...]}
+    /// Also supports object field references: {$add: ["$$value", "$$this.field"]}
+    ///
+    /// Returns the operation and whether a `$concat` skips nulls.
+    fn parse_reduce_in_expr(expr: &Value) -> Result<(ReduceInExpr, bool)> {
+        let obj = expr.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError(
+                "$reduce 'in' must be an expression object".to_string(),
+            )
+        })?;
+
+        if obj.len() != 1 {
+            return Err(MongoLiteError::AggregationError(
+                "$reduce 'in' must have exactly one operator".to_string(),
+            ));
+        }
+
+        let (op, args) = obj.iter().next().unwrap();
 
         // Check for $$this.field reference
         let this_field = Self::parse_this_field_reference(args);
@@ -501,18 +1841,20 @@ impl ProjectStage {
             "$add" => {
                 Self::validate_reduce_args(args, "$add")?;
                 match this_field {
-                    Some(field) => Ok(ReduceInExpr::AddField(field)),
-                    None => Ok(ReduceInExpr::Add),
+                    Some(field) => Ok((ReduceInExpr::AddField(field), false)),
+                    None => Ok((ReduceInExpr::Add, false)),
                 }
             }
             "$multiply" => {
                 Self::validate_reduce_args(args, "$multiply")?;
                 match this_field {
-                    Some(field) => Ok(ReduceInExpr::MultiplyField(field)),
-                    None => Ok(ReduceInExpr::Multiply),
+                    Some(field) => Ok((ReduceInExpr::MultiplyField(field), false)),
+                    None => Ok((ReduceInExpr::Multiply, false)),
                 }
             }
             "$concat" => {
+                let (args, skip_nulls) = Self::parse_concat_spec(args, "$concat")?;
+                let this_field = Self::parse_this_field_reference(args);
                 // $concat can have 2 or 3 arguments
                 if let Some(arr) = args.as_array() {
                     if arr.len() == 3 {
@@ -521,27 +1863,22 @@ impl ProjectStage {
                         if let Some(sep) = arr.get(1).and_then(|v| v.as_str()) {
                             // Check it's not a variable reference
                             if !sep.starts_with("$$") {
-                                match this_field {
-                                    Some(field) => {
-                                        return Ok(ReduceInExpr::ConcatFieldWithSeparator {
-                                            field,
-                                            separator: sep.to_string(),
-                                        })
-                                    }
-                                    None => {
-                                        return Ok(ReduceInExpr::ConcatWithSeparator(
-                                            sep.to_string(),
-                                        ))
-                                    }
-                                }
+                                let concat = match this_field {
+                                    Some(field) => ReduceInExpr::ConcatFieldWithSeparator {
+                                        field,
+                                        separator: sep.to_string(),
+                                    },
+                                    None => ReduceInExpr::ConcatWithSeparator(sep.to_string()),
+                                };
+                                return Ok((concat, skip_nulls));
                             }
                         }
                     }
                 }
                 Self::validate_reduce_args(args, "$concat")?;
                 match this_field {
-                    Some(field) => Ok(ReduceInExpr::ConcatField(field)),
-                    None => Ok(ReduceInExpr::Concat),
+                    Some(field) => Ok((ReduceInExpr::ConcatField(field), skip_nulls)),
+                    None => Ok((ReduceInExpr::Concat, skip_nulls)),
                 }
             }
             _ => Err(MongoLiteError::AggregationError(format!(
@@ -630,7 +1967,7 @@ impl ProjectStage {
                         ProjectField::Include => {
                             // Use get_nested_value to support dot notation in include fields
                             if let Some(value) = get_nested_value(doc, field) {
-                                result.insert(field.clone(), value.clone());
+                                Self::insert_included(&mut result, field, value.clone());
                             }
                         }
                         ProjectField::Rename(source) => {
@@ -641,7 +1978,7 @@ impl ProjectStage {
                             }
                         }
                         ProjectField::Expression(expr) => {
-                            let value = Self::evaluate_expression(expr, doc);
+                            let value = Self::evaluate_expression(expr, doc)?;
                             result.insert(field.clone(), value);
                         }
                         ProjectField::Exclude => {
@@ -681,7 +2018,7 @@ impl ProjectStage {
                             }
                         }
                         ProjectField::Expression(expr) => {
-                            let value = Self::evaluate_expression(expr, doc);
+                            let value = Self::evaluate_expression(expr, doc)?;
                             result.insert(target_field.clone(), value);
                         }
                         _ => {}
@@ -693,9 +2030,82 @@ impl ProjectStage {
         Ok(Value::Object(result))
     }
 
+    /// Build an object from `{k, v}` documents or `[key, value]` pairs
+    ///
+    /// Later duplicates of a key win; any malformed element yields null.
+    fn array_to_object(items: Vec<Value>) -> Value {
+        let mut result = serde_json::Map::new();
+        for item in items {
+            let (key, value) = match item {
+                Value::Object(mut pair) if pair.len() == 2 => {
+                    match (pair.remove("k"), pair.remove("v")) {
+                        (Some(Value::String(k)), Some(v)) => (k, v),
+                        _ => return Value::Null,
+                    }
+                }
+                Value::Array(pair) if pair.len() == 2 => {
+                    let mut pair = pair.into_iter();
+                    match (pair.next(), pair.next()) {
+                        (Some(Value::String(k)), Some(v)) => (k, v),
+                        _ => return Value::Null,
+                    }
+                }
+                _ => return Value::Null,
+            };
+            result.insert(key, value);
+        }
+        Value::Object(result)
+    }
+
+    /// Project a record holding only `_id` and array lengths
+    ///
+    /// Mirrors `project_document` for a stage of `$size` expressions: a
+    /// missing or non-array value projects as null.
+    fn project_sized(&self, sized: &SizedDocument) -> Value {
+        let mut result = serde_json::Map::new();
+        for (field, action) in &self.fields {
+            match action {
+                ProjectField::Expression(ProjectExpression::Size(path)) => {
+                    let size = sized
+                        .sizes
+                        .get(path)
+                        .map_or(Value::Null, |&len| Value::from(len));
+                    result.insert(field.clone(), size);
+                }
+                ProjectField::Include => {
+                    if let Some(id) = sized.doc.get("_id") {
+                        result.insert(field.clone(), id.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Value::Object(result)
+    }
+
+    /// Place an included field in the output at the same (possibly nested) path
+    ///
+    /// `{"meta.idx": 1}` yields `{"meta": {"idx": ...}}`, keeping sibling
+    /// includes under the same parent together.
+    fn insert_included(result: &mut serde_json::Map<String, Value>, path: &str, value: Value) {
+        match path.split_once('.') {
+            None => {
+                result.insert(path.to_string(), value);
+            }
+            Some((head, rest)) => {
+                let parent = result
+                    .entry(head.to_string())
+                    .or_insert_with(|| Value::Object(serde_json::Map::new()));
+                set_nested_value(parent, rest, value);
+            }
+        }
+    }
+
     /// Evaluate a projection expression against a document
-    fn evaluate_expression(expr: &ProjectExpression, doc: &Value) -> Value {
-        match expr {
+    ///
+    /// Fails only for a `$switch` without a default that matches no branch.
+    fn evaluate_expression(expr: &ProjectExpression, doc: &Value) -> Result<Value> {
+        let value = match expr {
             ProjectExpression::Size(field_name) => {
                 // Get the array field and return its length
                 if let Some(value) = get_nested_value(doc, field_name) {
@@ -711,7 +2121,241 @@ impl ProjectStage {
                 }
             }
             ProjectExpression::Reduce(reduce_expr) => Self::evaluate_reduce(reduce_expr, doc),
+            ProjectExpression::Field(field) => {
+                get_nested_value(doc, field).cloned().unwrap_or(Value::Null)
+            }
+            ProjectExpression::Literal(value) => value.clone(),
+            ProjectExpression::Split { input, delimiter } => {
+                match (
+                    Self::evaluate_expression(input, doc)?,
+                    Self::evaluate_expression(delimiter, doc)?,
+                ) {
+                    (Value::String(s), Value::String(delim)) if !delim.is_empty() => Value::Array(
+                        s.split(delim.as_str())
+                            .map(|part| Value::String(part.to_string()))
+                            .collect(),
+                    ),
+                    // Non-string input or delimiter - return null
+                    _ => Value::Null,
+                }
+            }
+            ProjectExpression::ObjectToArray(input) => {
+                match Self::evaluate_expression(input, doc)? {
+                    Value::Object(obj) => Value::Array(
+                        obj.into_iter()
+                            .map(|(k, v)| serde_json::json!({"k": k, "v": v}))
+                            .collect(),
+                    ),
+                    _ => Value::Null,
+                }
+            }
+            ProjectExpression::ArrayToObject(input) => {
+                match Self::evaluate_expression(input, doc)? {
+                    Value::Array(items) => Self::array_to_object(items),
+                    _ => Value::Null,
+                }
+            }
+            ProjectExpression::Trim { input, chars, mode } => {
+                let s = match Self::evaluate_expression(input, doc)? {
+                    Value::String(s) => s,
+                    _ => return Ok(Value::Null),
+                };
+                let chars = match chars {
+                    Some(chars_expr) => match Self::evaluate_expression(chars_expr, doc)? {
+                        Value::String(c) => Some(c),
+                        _ => return Ok(Value::Null),
+                    },
+                    None => None,
+                };
+                let strip = |c: char| match &chars {
+                    Some(set) => set.contains(c),
+                    None => c.is_whitespace(),
+                };
+                let trimmed = match mode {
+                    TrimMode::Both => s.trim_matches(strip),
+                    TrimMode::Start => s.trim_start_matches(strip),
+                    TrimMode::End => s.trim_end_matches(strip),
+                };
+                Value::String(trimmed.to_string())
+            }
+            ProjectExpression::Arithmetic { op, operands } => {
+                let values = Self::evaluate_all(operands, doc)?;
+                Self::evaluate_arithmetic(*op, &values)
+            }
+            ProjectExpression::Concat {
+                operands,
+                skip_nulls,
+            } => {
+                let mut joined = String::new();
+                for operand in operands {
+                    match Self::evaluate_expression(operand, doc)? {
+                        Value::Null if *skip_nulls => {}
+                        value @ (Value::String(_) | Value::Number(_) | Value::Bool(_)) => {
+                            joined.push_str(&Self::value_to_string(&value));
+                        }
+                        // Null, arrays and objects
+                        _ => return Ok(Value::Null),
+                    }
+                }
+                Value::String(joined)
+            }
+            ProjectExpression::ChangeCase { input, upper } => {
+                let s = Self::value_to_string(&Self::evaluate_expression(input, doc)?);
+                Value::String(if *upper {
+                    s.to_uppercase()
+                } else {
+                    s.to_lowercase()
+                })
+            }
+            ProjectExpression::Substr {
+                input,
+                start,
+                length,
+            } => {
+                let s = Self::value_to_string(&Self::evaluate_expression(input, doc)?);
+                let (start, length) = match (
+                    Self::evaluate_expression(start, doc)?.as_f64(),
+                    Self::evaluate_expression(length, doc)?.as_f64(),
+                ) {
+                    (Some(start), Some(length)) => (start, length),
+                    // Non-numeric bounds
+                    _ => return Ok(Value::Null),
+                };
+                // Slice on char boundaries so multibyte characters stay whole
+                let chars = s.chars().skip(start.max(0.0) as usize);
+                let taken: String = if length < 0.0 {
+                    chars.collect()
+                } else {
+                    chars.take(length as usize).collect()
+                };
+                Value::String(taken)
+            }
+            ProjectExpression::MergeObjects(operands) => {
+                merge_objects(Self::evaluate_all(operands, doc)?)
+            }
+            ProjectExpression::Comparison { op, operands } => {
+                let left = Self::evaluate_expression(&operands[0], doc)?;
+                let right = Self::evaluate_expression(&operands[1], doc)?;
+                let holds = match compare_values(&left, &right) {
+                    Some(ordering) => match op {
+                        ComparisonOp::Eq => ordering == Ordering::Equal,
+                        ComparisonOp::Ne => ordering != Ordering::Equal,
+                        ComparisonOp::Gt => ordering == Ordering::Greater,
+                        ComparisonOp::Gte => ordering != Ordering::Less,
+                        ComparisonOp::Lt => ordering == Ordering::Less,
+                        ComparisonOp::Lte => ordering != Ordering::Greater,
+                    },
+                    // Incomparable types are never equal, so only $ne holds
+                    None => *op == ComparisonOp::Ne,
+                };
+                Value::Bool(holds)
+            }
+            ProjectExpression::Cond {
+                predicate,
+                then,
+                otherwise,
+            } => {
+                if Self::is_truthy(&Self::evaluate_expression(predicate, doc)?) {
+                    Self::evaluate_expression(then, doc)?
+                } else {
+                    Self::evaluate_expression(otherwise, doc)?
+                }
+            }
+            ProjectExpression::Switch { branches, default } => {
+                for (case, then) in branches {
+                    if Self::is_truthy(&Self::evaluate_expression(case, doc)?) {
+                        return Self::evaluate_expression(then, doc);
+                    }
+                }
+                match default {
+                    Some(default) => Self::evaluate_expression(default, doc)?,
+                    None => {
+                        return Err(MongoLiteError::AggregationError(format!(
+                            "$switch: no branch matched document {} and no default was given",
+                            doc.get("_id").unwrap_or(&Value::Null)
+                        )))
+                    }
+                }
+            }
+            ProjectExpression::IfNull { value, fallback } => {
+                match Self::evaluate_expression(value, doc)? {
+                    Value::Null => Self::evaluate_expression(fallback, doc)?,
+                    value => value,
+                }
+            }
+            ProjectExpression::Object(entries) => {
+                let mut built = serde_json::Map::new();
+                for (key, entry) in entries {
+                    // Missing field references are left out, not set to null
+                    if let ProjectExpression::Field(path) = entry {
+                        if get_nested_value(doc, path).is_none() {
+                            continue;
+                        }
+                    }
+                    built.insert(key.clone(), Self::evaluate_expression(entry, doc)?);
+                }
+                Value::Object(built)
+            }
+        };
+        Ok(value)
+    }
+
+    /// Evaluate each operand in order
+    fn evaluate_all(operands: &[ProjectExpression], doc: &Value) -> Result<Vec<Value>> {
+        operands
+            .iter()
+            .map(|operand| Self::evaluate_expression(operand, doc))
+            .collect()
+    }
+
+    /// Truthiness of a `$cond` predicate: false, null and zero are false
+    fn is_truthy(value: &Value) -> bool {
+        match value {
+            Value::Null | Value::Bool(false) => false,
+            Value::Number(n) => n.as_f64() != Some(0.0),
+            _ => true,
+        }
+    }
+
+    /// Apply an arithmetic operator left-to-right
+    ///
+    /// Any non-numeric (including null or missing) operand yields null, as
+    /// does division by zero. Integer operands stay integers unless the
+    /// result overflows (or, for $divide, isn't a whole number).
+    fn evaluate_arithmetic(op: ArithmeticOp, values: &[Value]) -> Value {
+        if !values.iter().all(Value::is_number) {
+            return Value::Null;
+        }
+        if op == ArithmeticOp::Divide && values[1..].iter().any(|v| v.as_f64() == Some(0.0)) {
+            return Value::Null;
+        }
+
+        if values.iter().all(|v| v.is_i64()) {
+            let ints: Vec<i64> = values.iter().filter_map(Value::as_i64).collect();
+            let (first, rest) = (ints[0], &ints[1..]);
+            let result = match op {
+                ArithmeticOp::Add => rest.iter().try_fold(first, |a, b| a.checked_add(*b)),
+                ArithmeticOp::Subtract => rest.iter().try_fold(first, |a, b| a.checked_sub(*b)),
+                ArithmeticOp::Multiply => rest.iter().try_fold(first, |a, b| a.checked_mul(*b)),
+                ArithmeticOp::Divide => rest.iter().try_fold(first, |a, b| {
+                    a.checked_rem(*b).filter(|r| *r == 0)?;
+                    a.checked_div(*b)
+                }),
+            };
+            if let Some(n) = result {
+                return Value::from(n);
+            }
         }
+
+        let mut floats = values.iter().map(Self::value_to_f64);
+        let first = floats.next().unwrap_or(0.0);
+        let result = floats.fold(first, |acc, x| match op {
+            ArithmeticOp::Add => acc + x,
+            ArithmeticOp::Subtract => acc - x,
+            ArithmeticOp::Multiply => acc * x,
+            ArithmeticOp::Divide => acc / x,
+        });
+        Value::from(result)
     }
 
     /// Evaluate a $reduce expression against a document
@@ -730,6 +2374,14 @@ impl ProjectStage {
 
         // Apply reduction for each element
         for element in array {
+            if let Some(part) = expr.in_expr.concat_part(&element) {
+                if !expr.skip_nulls && (accumulator.is_null() || part.is_null()) {
+                    return Value::Null;
+                }
+                if part.is_null() {
+                    continue;
+                }
+            }
             accumulator = match &expr.in_expr {
                 ReduceInExpr::Add => {
                     let acc_num = Self::value_to_f64(&accumulator);
@@ -810,6 +2462,20 @@ impl ProjectStage {
     }
 }
 
+impl ReduceInExpr {
+    /// The string a `$concat` reduction appends for `element` (None for other operations)
+    fn concat_part<'a>(&self, element: &'a Value) -> Option<&'a Value> {
+        match self {
+            ReduceInExpr::Concat | ReduceInExpr::ConcatWithSeparator(_) => Some(element),
+            ReduceInExpr::ConcatField(field)
+            | ReduceInExpr::ConcatFieldWithSeparator { field, .. } => {
+                Some(get_nested_value(element, field).unwrap_or(&Value::Null))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl GroupStage {
     fn from_json(spec: &Value) -> Result<Self> {
         if let Value::Object(obj) = spec {
@@ -818,13 +2484,19 @@ impl GroupStage {
                 if id_value.is_null() {
                     GroupId::Null
                 } else if let Some(s) = id_value.as_str() {
-                    if s.starts_with('$') {
-                        GroupId::Field(s.to_string())
-                    } else {
-                        return Err(MongoLiteError::AggregationError(
-                            "Group _id field reference must start with $".to_string(),
-                        ));
+                    GroupId::Field(Self::parse_id_reference(s)?)
+                } else if let Value::Object(fields) = id_value {
+                    let mut composite = HashMap::with_capacity(fields.len());
+                    for (name, reference) in fields {
+                        let reference = reference.as_str().ok_or_else(|| {
+                            MongoLiteError::AggregationError(format!(
+                                "Group _id field '{}' must be a field reference",
+                                name
+                            ))
+                        })?;
+                        composite.insert(name.clone(), Self::parse_id_reference(reference)?);
                     }
+                    GroupId::Composite(composite)
                 } else {
                     return Err(MongoLiteError::AggregationError(
                         "Group _id must be null or field reference".to_string(),
@@ -885,6 +2557,16 @@ impl GroupStage {
         Ok(results)
     }
 
+    fn parse_id_reference(reference: &str) -> Result<String> {
+        if reference.starts_with('$') {
+            Ok(reference.to_string())
+        } else {
+            Err(MongoLiteError::AggregationError(
+                "Group _id field reference must start with $".to_string(),
+            ))
+        }
+    }
+
     fn extract_group_key(&self, doc: &Value) -> Result<String> {
         match &self.id {
             GroupId::Null => Ok("__all__".to_string()),
@@ -897,6 +2579,18 @@ impl GroupStage {
                     Ok("null".to_string())
                 }
             }
+            GroupId::Composite(fields) => {
+                // Missing fields are left out of the _id, as in MongoDB
+                let id: serde_json::Map<String, Value> = fields
+                    .iter()
+                    .filter_map(|(name, field)| {
+                        get_nested_value(doc, field.trim_start_matches('$'))
+                            .map(|value| (name.clone(), value.clone()))
+                    })
+                    .collect();
+                // Canonical form: key order doesn't split a group
+                Ok(canonical_json_string(&Value::Object(id)))
+            }
         }
     }
 
@@ -934,12 +2628,23 @@ impl Accumulator {
                                 "$sum field reference must start with $".to_string(),
                             ))
                         }
+                    } else if let Value::Object(expr_obj) = value {
+                        Ok(Accumulator::Sum(SumExpression::Expression(
+                            ProjectStage::parse_expression(expr_obj)?,
+                        )))
                     } else {
                         Err(MongoLiteError::AggregationError(
-                            "$sum must be a number or field reference".to_string(),
+                            "$sum must be a number or field reference, or an expression object"
+                                .to_string(),
                         ))
                     }
                 }
+                "$count" => match value {
+                    Value::Object(args) if args.is_empty() => Ok(Accumulator::Count),
+                    _ => Err(MongoLiteError::AggregationError(
+                        "$count accumulator takes no arguments: use {\"$count\": {}}".to_string(),
+                    )),
+                },
                 "$avg" => Ok(Accumulator::Avg(parse_field_reference(value, "$avg")?)),
                 "$min" => Ok(Accumulator::Min(parse_field_reference(value, "$min")?)),
                 "$max" => Ok(Accumulator::Max(parse_field_reference(value, "$max")?)),
@@ -950,6 +2655,18 @@ impl Accumulator {
                     value,
                     "$addToSet",
                 )?)),
+                "$stdDevPop" => Ok(Accumulator::StdDevPop(parse_field_reference(
+                    value,
+                    "$stdDevPop",
+                )?)),
+                "$stdDevSamp" => Ok(Accumulator::StdDevSamp(parse_field_reference(
+                    value,
+                    "$stdDevSamp",
+                )?)),
+                "$mergeObjects" => Ok(Accumulator::MergeObjects(parse_field_reference(
+                    value,
+                    "$mergeObjects",
+                )?)),
                 _ => Err(MongoLiteError::AggregationError(format!(
                     "Unknown accumulator: {}",
                     op
@@ -967,31 +2684,21 @@ impl Accumulator {
             Accumulator::Count => Ok(Value::from(docs.len() as i64)),
 
             Accumulator::Sum(expr) => match expr {
-                SumExpression::Constant(n) => {
-                    Ok(Value::from((*n).saturating_mul(docs.len() as i64)))
-                }
-                SumExpression::Field(field) => {
-                    let mut sum_int: i64 = 0;
-                    let mut sum_float: f64 = 0.0;
-                    let mut has_float = false;
-
-                    for doc in docs {
-                        // Use get_nested_value to support dot notation (e.g., "$order.total")
-                        if let Some(value) = get_nested_value(doc, field) {
-                            if let Some(n) = value.as_i64() {
-                                sum_int = sum_int.saturating_add(n);
-                            } else if let Some(f) = value.as_f64() {
-                                sum_float += f;
-                                has_float = true;
-                            }
-                        }
-                    }
-
-                    if has_float {
-                        Ok(Value::from(sum_float + sum_int as f64))
-                    } else {
-                        Ok(Value::from(sum_int))
-                    }
+                SumExpression::Constant(n) => Ok(i64::try_from(docs.len())
+                    .ok()
+                    .and_then(|len| n.checked_mul(len))
+                    .map(Value::from)
+                    .unwrap_or_else(|| Value::from(*n as f64 * docs.len() as f64))),
+                // Use get_nested_value to support dot notation (e.g., "$order.total")
+                SumExpression::Field(field) => Ok(sum_numeric(
+                    docs.iter().filter_map(|doc| get_nested_value(doc, field)),
+                )),
+                SumExpression::Expression(expr) => {
+                    let values = docs
+                        .iter()
+                        .map(|doc| ProjectStage::evaluate_expression(expr, doc))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(sum_numeric(values.iter()))
                 }
             },
 
@@ -1023,6 +2730,15 @@ impl Accumulator {
 
             Accumulator::Max(field) => compute_extremum(docs, field, f64::max),
 
+            Accumulator::StdDevPop(field) => Ok(compute_std_dev(docs, field, false)),
+
+            Accumulator::StdDevSamp(field) => Ok(compute_std_dev(docs, field, true)),
+
+            Accumulator::MergeObjects(field) => Ok(merge_objects(
+                docs.iter()
+                    .filter_map(|doc| get_nested_value(doc, field).cloned()),
+            )),
+
             Accumulator::First(field) => docs
                 .first()
                 // Use get_nested_value to support dot notation
@@ -1112,7 +2828,7 @@ impl SortStage {
                 let val_a = get_nested_value(a, field);
                 let val_b = get_nested_value(b, field);
 
-                let cmp = compare_values(val_a, val_b);
+                let cmp = compare_for_sort(val_a, val_b);
                 let cmp = match direction {
                     SortDirection::Ascending => cmp,
                     SortDirection::Descending => cmp.reverse(),
@@ -1129,32 +2845,6 @@ impl SortStage {
     }
 }
 
-fn compare_values(a: Option<&Value>, b: Option<&Value>) -> std::cmp::Ordering {
-    match (a, b) {
-        (None, None) => std::cmp::Ordering::Equal,
-        (None, Some(_)) => std::cmp::Ordering::Less,
-        (Some(_), None) => std::cmp::Ordering::Greater,
-        (Some(a), Some(b)) => {
-            // String comparison
-            if let (Some(s1), Some(s2)) = (a.as_str(), b.as_str()) {
-                return s1.cmp(s2);
-            }
-
-            // Number comparison
-            if let (Some(n1), Some(n2)) = (a.as_f64(), b.as_f64()) {
-                return n1.partial_cmp(&n2).unwrap_or(std::cmp::Ordering::Equal);
-            }
-
-            // Boolean comparison
-            if let (Some(b1), Some(b2)) = (a.as_bool(), b.as_bool()) {
-                return b1.cmp(&b2);
-            }
-
-            std::cmp::Ordering::Equal
-        }
-    }
-}
-
 impl LimitStage {
     fn from_json(spec: &Value) -> Result<Self> {
         if let Some(n) = spec.as_u64() {
@@ -1171,19 +2861,144 @@ impl LimitStage {
     }
 }
 
-impl SkipStage {
+impl SampleStage {
+    /// Stage with a fixed seed, so the same input always yields the same sample
+    pub fn with_seed(size: usize, seed: u64) -> Self {
+        SampleStage {
+            size,
+            seed: Some(seed),
+        }
+    }
+
     fn from_json(spec: &Value) -> Result<Self> {
-        if let Some(n) = spec.as_u64() {
-            Ok(SkipStage { skip: n as usize })
-        } else {
-            Err(MongoLiteError::AggregationError(
-                "$skip must be a positive number".to_string(),
-            ))
+        match spec.as_object() {
+            Some(obj) if obj.len() == 1 => match obj.get("size").and_then(Value::as_u64) {
+                Some(size) => Ok(SampleStage {
+                    size: size as usize,
+                    seed: None,
+                }),
+                None => Err(MongoLiteError::AggregationError(
+                    "$sample size must be a non-negative integer".to_string(),
+                )),
+            },
+            _ => Err(MongoLiteError::AggregationError(
+                "$sample must be {size: <non-negative integer>}".to_string(),
+            )),
         }
     }
 
     fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
-        Ok(docs.into_iter().skip(self.skip).collect())
+        let mut rng = SplitMix64(self.seed.unwrap_or_else(|| {
+            use std::hash::{BuildHasher, Hasher};
+            std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish()
+        }));
+
+        let mut reservoir = Vec::with_capacity(self.size.min(docs.len()));
+        for (seen, doc) in docs.into_iter().enumerate() {
+            if seen < self.size {
+                reservoir.push(doc);
+            } else {
+                let slot = rng.below(seen as u64 + 1) as usize;
+                if slot < self.size {
+                    reservoir[slot] = doc;
+                }
+            }
+        }
+
+        // The reservoir keeps input order until it fills; shuffle so the
+        // output order is random too
+        for i in (1..reservoir.len()).rev() {
+            reservoir.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+        Ok(reservoir)
+    }
+}
+
+/// Small seedable generator for $sample (SplitMix64)
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound` (bound > 0)
+    fn below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(bound)) >> 64) as u64
+    }
+}
+
+impl CountStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        match spec.as_str() {
+            Some(field) if !field.is_empty() && !field.starts_with('$') && !field.contains('.') => {
+                Ok(CountStage {
+                    field: field.to_string(),
+                })
+            }
+            _ => Err(MongoLiteError::AggregationError(
+                "$count requires a non-empty field name without '$' prefix or '.'".to_string(),
+            )),
+        }
+    }
+
+    /// Always one document, `{<field>: 0}` for empty input
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        let mut result = serde_json::Map::new();
+        result.insert(self.field.clone(), Value::from(docs.len()));
+        Ok(vec![Value::Object(result)])
+    }
+}
+
+impl SortByCountStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        match spec.as_str() {
+            Some(field)
+                if field.len() > 1 && field.starts_with('$') && !field.starts_with("$$") =>
+            {
+                Ok(SortByCountStage {
+                    group: GroupStage::from_json(&serde_json::json!({
+                        "_id": field,
+                        "count": {"$sum": 1}
+                    }))?,
+                    sort: SortStage {
+                        fields: vec![
+                            ("count".to_string(), SortDirection::Descending),
+                            ("_id".to_string(), SortDirection::Ascending),
+                        ],
+                    },
+                })
+            }
+            _ => Err(MongoLiteError::AggregationError(
+                "$sortByCount requires a field reference string like \"$field\"".to_string(),
+            )),
+        }
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        self.sort.execute(self.group.execute(docs)?)
+    }
+}
+
+impl SkipStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        if let Some(n) = spec.as_u64() {
+            Ok(SkipStage { skip: n as usize })
+        } else {
+            Err(MongoLiteError::AggregationError(
+                "$skip must be a positive number".to_string(),
+            ))
+        }
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        Ok(docs.into_iter().skip(self.skip).collect())
     }
 }
 
@@ -1246,10 +3061,29 @@ impl UnwindStage {
     ///
     /// For each document, if the path field is an array, outputs one document
     /// per array element with the path field replaced by that element.
+    /// A path through an array of subdocuments (`$orders.items`) is expanded
+    /// at every array it crosses.
     fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
         let mut results = Vec::new();
+        let segments: Vec<&str> = self.path.split('.').collect();
 
         for doc in docs {
+            if path_crosses_array(&doc, &segments) {
+                let mut expanded = Vec::new();
+                self.unwind_through_arrays(&doc, &segments, &mut expanded);
+                for (mut new_doc, index) in expanded {
+                    if let (Some(index_field), Some(index)) = (&self.include_array_index, index) {
+                        set_nested_value(
+                            &mut new_doc,
+                            index_field,
+                            Value::Number(serde_json::Number::from(index)),
+                        );
+                    }
+                    results.push(new_doc);
+                }
+                continue;
+            }
+
             let array_value = get_nested_value(&doc, &self.path);
 
             match array_value {
@@ -1316,7 +3150,879 @@ impl UnwindStage {
             }
         }
 
-        Ok(results)
+        Ok(results)
+    }
+
+    /// Unwind `segments` below `value`, expanding every array met on the way
+    ///
+    /// Each output is a copy of `value` in which every array along the path
+    /// is replaced by one of its elements (the cartesian expansion), paired
+    /// with the index taken from the innermost, unwound array.
+    fn unwind_through_arrays(
+        &self,
+        value: &Value,
+        segments: &[&str],
+        out: &mut Vec<(Value, Option<usize>)>,
+    ) {
+        let (field, rest) = (segments[0], &segments[1..]);
+        let child = value.as_object().and_then(|map| map.get(field));
+        let with_field = |replacement: Value| {
+            let mut copy = value.clone();
+            if let Value::Object(map) = &mut copy {
+                map.insert(field.to_string(), replacement);
+            }
+            copy
+        };
+
+        if rest.is_empty() {
+            match child {
+                Some(Value::Array(arr)) if !arr.is_empty() => {
+                    for (index, element) in arr.iter().enumerate() {
+                        out.push((with_field(element.clone()), Some(index)));
+                    }
+                }
+                Some(Value::Null) => {
+                    if self.preserve_null_and_empty_arrays {
+                        out.push((value.clone(), None));
+                    }
+                }
+                Some(Value::Array(_)) | None => {
+                    if self.preserve_null_and_empty_arrays {
+                        out.push((with_field(Value::Null), None));
+                    }
+                }
+                Some(scalar) => out.push((with_field(scalar.clone()), Some(0))),
+            }
+            return;
+        }
+
+        let mut inner = Vec::new();
+        match child {
+            Some(Value::Array(arr)) if !arr.is_empty() => {
+                for element in arr {
+                    self.unwind_through_arrays(element, rest, &mut inner);
+                }
+            }
+            Some(object @ Value::Object(_)) => self.unwind_through_arrays(object, rest, &mut inner),
+            _ => {
+                if self.preserve_null_and_empty_arrays {
+                    out.push((value.clone(), None));
+                }
+                return;
+            }
+        }
+        for (replacement, index) in inner {
+            out.push((with_field(replacement), index));
+        }
+    }
+}
+
+/// Whether `segments` passes through an array before its last segment
+/// (numeric segments index into arrays and don't count)
+fn path_crosses_array(doc: &Value, segments: &[&str]) -> bool {
+    let mut value = doc;
+    for (i, segment) in segments.iter().enumerate() {
+        let next = match value {
+            Value::Object(map) => map.get(*segment),
+            Value::Array(arr) => match segment.parse::<usize>() {
+                Ok(index) => arr.get(index),
+                Err(_) => return true,
+            },
+            _ => None,
+        };
+        match next {
+            Some(Value::Array(_)) if i + 1 < segments.len() => {
+                if segments[i + 1].parse::<usize>().is_err() {
+                    return true;
+                }
+                value = next.unwrap();
+            }
+            Some(next) => value = next,
+            None => return false,
+        }
+    }
+    false
+}
+
+impl DedupStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let invalid = || {
+            MongoLiteError::AggregationError(
+                "$dedup requires 'by': a field name or a non-empty array of field names"
+                    .to_string(),
+            )
+        };
+        let fields: Vec<&Value> = match spec.get("by").ok_or_else(invalid)? {
+            Value::Array(fields) if !fields.is_empty() => fields.iter().collect(),
+            field @ Value::String(_) => vec![field],
+            _ => return Err(invalid()),
+        };
+
+        let by = fields
+            .into_iter()
+            .map(|field| {
+                field
+                    .as_str()
+                    .map(|name| name.trim_start_matches('$').to_string())
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DedupStage { by })
+    }
+
+    /// Keys are compared as canonical JSON, so `{a: 1, b: 2}` and
+    /// `{b: 2, a: 1}` are the same value
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        let mut seen = HashSet::new();
+        Ok(docs
+            .into_iter()
+            .filter(|doc| {
+                let key: Vec<Value> = self
+                    .by
+                    .iter()
+                    .map(|field| get_nested_value(doc, field).cloned().unwrap_or(Value::Null))
+                    .collect();
+                seen.insert(canonical_json_string(&Value::Array(key)))
+            })
+            .collect())
+    }
+}
+
+impl RedactStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        Ok(RedactStage {
+            decision: RedactDecision::from_json(spec)?,
+        })
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        let mut results = Vec::with_capacity(docs.len());
+        for doc in docs {
+            if let Some(redacted) = self.redact(doc)? {
+                results.push(redacted);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Apply the decision to one level; None when it is pruned
+    fn redact(&self, level: Value) -> Result<Option<Value>> {
+        let Value::Object(fields) = level else {
+            return Ok(Some(level));
+        };
+        let context = Document::new(
+            DocumentId::Int(0),
+            fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        );
+        match self.decision.evaluate(&context)? {
+            RedactAction::Keep => Ok(Some(Value::Object(fields))),
+            RedactAction::Prune => Ok(None),
+            RedactAction::Descend => {
+                let mut kept = serde_json::Map::with_capacity(fields.len());
+                for (key, value) in fields {
+                    if let Some(value) = self.descend(value)? {
+                        kept.insert(key, value);
+                    }
+                }
+                Ok(Some(Value::Object(kept)))
+            }
+        }
+    }
+
+    /// Redact an embedded value: documents are levels of their own, arrays
+    /// drop their pruned documents, anything else is kept as-is
+    fn descend(&self, value: Value) -> Result<Option<Value>> {
+        match value {
+            Value::Object(_) => self.redact(value),
+            Value::Array(items) => {
+                let mut kept = Vec::with_capacity(items.len());
+                for item in items {
+                    if let Some(item) = self.descend(item)? {
+                        kept.push(item);
+                    }
+                }
+                Ok(Some(Value::Array(kept)))
+            }
+            other => Ok(Some(other)),
+        }
+    }
+}
+
+impl AddFieldsStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError("$addFields must be an object".to_string())
+        })?;
+        let mut fields = Vec::with_capacity(obj.len());
+        for (field, value) in obj {
+            let expr = match value {
+                Value::Object(spec_obj) if ProjectStage::is_nested_spec(spec_obj) => {
+                    ProjectStage::parse_nested_spec(spec_obj)?
+                }
+                Value::Object(spec_obj) if spec_obj.keys().any(|k| k.starts_with('$')) => {
+                    ProjectStage::parse_expression(spec_obj)?
+                }
+                other => ProjectStage::parse_operand(other)?,
+            };
+            fields.push((field.clone(), expr));
+        }
+        Ok(AddFieldsStage { fields })
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        docs.into_iter().map(|doc| self.add_fields(doc)).collect()
+    }
+
+    fn add_fields(&self, doc: Value) -> Result<Value> {
+        let values = self
+            .fields
+            .iter()
+            .map(|(_, expr)| ProjectStage::evaluate_expression(expr, &doc))
+            .collect::<Result<Vec<_>>>()?;
+        let mut doc = doc;
+        for ((field, _), value) in self.fields.iter().zip(values) {
+            set_nested_value(&mut doc, field, value);
+        }
+        Ok(doc)
+    }
+}
+
+impl LookupStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError("$lookup must be an object".to_string())
+        })?;
+        let field = |name: &str| -> Result<String> {
+            match obj.get(name).and_then(Value::as_str) {
+                Some(value) if !value.is_empty() => Ok(value.to_string()),
+                _ => Err(MongoLiteError::AggregationError(format!(
+                    "$lookup requires a non-empty string '{}'",
+                    name
+                ))),
+            }
+        };
+        let allowed: &[&str] = if obj.contains_key("pipeline") {
+            &["from", "let", "pipeline", "as"]
+        } else {
+            &["from", "localField", "foreignField", "as"]
+        };
+        if let Some(key) = obj.keys().find(|k| !allowed.contains(&k.as_str())) {
+            return Err(MongoLiteError::AggregationError(format!(
+                "$lookup does not support '{}'",
+                key
+            )));
+        }
+
+        let join = match obj.get("pipeline") {
+            Some(pipeline) => Self::parse_pipeline_join(obj.get("let"), pipeline)?,
+            None => LookupJoin::Fields {
+                local_field: field("localField")?,
+                foreign_field: field("foreignField")?,
+            },
+        };
+        Ok(LookupStage {
+            from: field("from")?,
+            join,
+            as_field: field("as")?,
+        })
+    }
+
+    fn parse_pipeline_join(vars: Option<&Value>, pipeline: &Value) -> Result<LookupJoin> {
+        let vars = match vars {
+            None => Vec::new(),
+            Some(Value::Object(vars)) => vars
+                .iter()
+                .map(|(name, value)| {
+                    let mut chars = name.chars();
+                    let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+                        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if !valid {
+                        return Err(MongoLiteError::AggregationError(format!(
+                            "$lookup let variable '{}' must start with a lowercase letter \
+                             and contain only letters, digits and '_'",
+                            name
+                        )));
+                    }
+                    Ok((name.clone(), ProjectStage::parse_operand(value)?))
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => {
+                return Err(MongoLiteError::AggregationError(
+                    "$lookup let must be an object".to_string(),
+                ))
+            }
+        };
+
+        let pipeline = pipeline.as_array().ok_or_else(|| {
+            MongoLiteError::AggregationError("$lookup pipeline must be an array".to_string())
+        })?;
+        if let Some(name) = pipeline
+            .iter()
+            .filter_map(|stage| stage.as_object()?.keys().next())
+            .find(|name| TERMINAL_STAGES.contains(&name.as_str()))
+        {
+            return Err(MongoLiteError::AggregationError(format!(
+                "{} is not allowed inside a $lookup pipeline",
+                name
+            )));
+        }
+        // Parse once up front so a malformed sub-pipeline fails the stage
+        // rather than the first document joined. Stage by stage: the `let`
+        // variables are unbound here, and `validate_stage` checks them with
+        // the enclosing pipeline
+        for stage in pipeline {
+            Stage::from_json(stage)?;
+        }
+
+        Ok(LookupJoin::Pipeline {
+            vars,
+            pipeline: pipeline.clone(),
+        })
+    }
+
+    fn execute(&self, docs: Vec<Value>, resolver: &dyn CollectionResolver) -> Result<Vec<Value>> {
+        let Some(source) = resolver.open(&self.from)? else {
+            return Ok(docs
+                .into_iter()
+                .map(|doc| self.attach(doc, Vec::new()))
+                .collect());
+        };
+
+        match &self.join {
+            LookupJoin::Fields {
+                local_field,
+                foreign_field,
+            } => self.join_fields(docs, source.as_ref(), local_field, foreign_field),
+            LookupJoin::Pipeline { vars, pipeline } => {
+                self.join_pipeline(docs, source.as_ref(), resolver, vars, pipeline)
+            }
+        }
+    }
+
+    fn join_fields(
+        &self,
+        docs: Vec<Value>,
+        source: &dyn LookupSource,
+        local_field: &str,
+        foreign_field: &str,
+    ) -> Result<Vec<Value>> {
+        if source.has_index(foreign_field) {
+            let mut out = Vec::with_capacity(docs.len());
+            for doc in docs {
+                let mut matches = Vec::new();
+                for key in Self::join_keys(get_nested_value(&doc, local_field)) {
+                    let query = serde_json::json!({ foreign_field: key });
+                    for found in source.find(&query)? {
+                        if !matches.contains(&found) {
+                            matches.push(found);
+                        }
+                    }
+                }
+                out.push(self.attach(doc, matches));
+            }
+            return Ok(out);
+        }
+
+        // No index: hash the foreign side once, by canonical key
+        let foreign = source.find(&serde_json::json!({}))?;
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, doc) in foreign.iter().enumerate() {
+            for key in Self::join_keys(get_nested_value(doc, foreign_field)) {
+                let positions = by_key.entry(canonical_json_string(key)).or_default();
+                if positions.last() != Some(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+
+        Ok(docs
+            .into_iter()
+            .map(|doc| {
+                let mut positions: Vec<usize> =
+                    Self::join_keys(get_nested_value(&doc, local_field))
+                        .into_iter()
+                        .filter_map(|key| by_key.get(&canonical_json_string(key)))
+                        .flatten()
+                        .copied()
+                        .collect();
+                positions.sort_unstable();
+                positions.dedup();
+                let matches = positions.into_iter().map(|i| foreign[i].clone()).collect();
+                self.attach(doc, matches)
+            })
+            .collect())
+    }
+
+    fn join_pipeline(
+        &self,
+        docs: Vec<Value>,
+        source: &dyn LookupSource,
+        resolver: &dyn CollectionResolver,
+        vars: &[(String, ProjectExpression)],
+        pipeline: &[Value],
+    ) -> Result<Vec<Value>> {
+        // Results by canonical `let` values, and all of `from` once read
+        let mut runs: HashMap<String, Vec<Value>> = HashMap::new();
+        let mut foreign: Option<Vec<Value>> = None;
+
+        let mut out = Vec::with_capacity(docs.len());
+        for doc in docs {
+            let bound = vars
+                .iter()
+                .map(|(name, expr)| {
+                    Ok((
+                        name.as_str(),
+                        ProjectStage::evaluate_expression(expr, &doc)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let key = canonical_json_string(&Value::Array(
+                bound.iter().map(|(_, value)| value.clone()).collect(),
+            ));
+            if let Some(matches) = runs.get(&key) {
+                out.push(self.attach(doc, matches.clone()));
+                continue;
+            }
+
+            let stages: Vec<Value> = pipeline
+                .iter()
+                .map(|stage| bind_stage_variables(stage, &bound))
+                .collect();
+            // A leading $match with an equality on an indexed field reads
+            // only the documents with that value; the $match still runs
+            let lookup_key = stages
+                .first()
+                .and_then(|stage| stage.get("$match"))
+                .into_iter()
+                .flat_map(match_equalities)
+                .find(|(field, _)| source.has_index(field));
+            let input = match lookup_key {
+                Some((field, value)) => source.find(&serde_json::json!({ field: value }))?,
+                None => {
+                    if foreign.is_none() {
+                        foreign = Some(source.find(&serde_json::json!({}))?);
+                    }
+                    foreign.clone().unwrap_or_default()
+                }
+            };
+            let matches = if stages.is_empty() {
+                input
+            } else {
+                Pipeline::from_json(&Value::Array(stages))?.execute_with(
+                    input,
+                    resolver,
+                    &NoCollections,
+                )?
+            };
+
+            runs.insert(key, matches.clone());
+            out.push(self.attach(doc, matches));
+        }
+        Ok(out)
+    }
+
+    /// Values a field joins on: array elements, or the value itself (null when missing)
+    fn join_keys(value: Option<&Value>) -> Vec<&Value> {
+        match value {
+            Some(Value::Array(items)) => items.iter().collect(),
+            Some(value) => vec![value],
+            None => vec![&Value::Null],
+        }
+    }
+
+    fn attach(&self, mut doc: Value, matches: Vec<Value>) -> Value {
+        set_nested_value(&mut doc, &self.as_field, Value::Array(matches));
+        doc
+    }
+}
+
+/// Replace `$$name` references to `vars` in a sub-pipeline stage with
+/// `{"$literal": value}`
+///
+/// In `$match` only `$expr` is rewritten, since query values are matched
+/// literally. Other variables (`$$ROOT`, `$$this`, ...) are left alone.
+fn bind_stage_variables(stage: &Value, vars: &[(&str, Value)]) -> Value {
+    fn bind(value: &Value, vars: &[(&str, Value)], in_query: bool) -> Value {
+        match value {
+            Value::String(s) if !in_query => {
+                let Some(reference) = s.strip_prefix("$$") else {
+                    return value.clone();
+                };
+                let (name, path) = match reference.split_once('.') {
+                    Some((name, path)) => (name, Some(path)),
+                    None => (reference, None),
+                };
+                match vars.iter().find(|(var, _)| *var == name) {
+                    Some((_, bound)) => {
+                        let resolved = match path {
+                            Some(path) => get_nested_value(bound, path).cloned(),
+                            None => Some(bound.clone()),
+                        };
+                        serde_json::json!({"$literal": resolved.unwrap_or(Value::Null)})
+                    }
+                    None => value.clone(),
+                }
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| bind(item, vars, in_query))
+                    .collect(),
+            ),
+            Value::Object(obj) => Value::Object(
+                obj.iter()
+                    .map(|(key, item)| {
+                        let in_query = in_query && key != "$expr";
+                        (key.clone(), bind(item, vars, in_query))
+                    })
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    match stage.as_object() {
+        Some(obj) => Value::Object(
+            obj.iter()
+                .map(|(name, spec)| (name.clone(), bind(spec, vars, name == "$match")))
+                .collect(),
+        ),
+        None => stage.clone(),
+    }
+}
+
+/// `(field, value)` equalities a `$match` query implies, for index lookups
+///
+/// Covers top-level `field: value` conditions and `$expr` `$eq`s of a field
+/// and a bound variable, also under `$and`. Only strings, booleans and
+/// integers are returned: for them an equality query finds at least the
+/// documents the `$match` keeps.
+fn match_equalities(query: &Value) -> Vec<(String, Value)> {
+    fn indexable(value: &Value) -> bool {
+        value.is_string() || value.is_boolean() || value.is_i64() || value.is_u64()
+    }
+
+    fn collect_expr(expr: &Value, out: &mut Vec<(String, Value)>) {
+        let Some((op, args)) = expr.as_object().and_then(|obj| obj.iter().next()) else {
+            return;
+        };
+        match (op.as_str(), args.as_array().map(Vec::as_slice)) {
+            ("$and", Some(items)) => {
+                for item in items {
+                    collect_expr(item, out);
+                }
+            }
+            ("$eq", Some([a, b])) => {
+                let field = |v: &Value| {
+                    v.as_str()
+                        .filter(|s| s.starts_with('$') && !s.starts_with("$$"))
+                        .map(|s| s[1..].to_string())
+                };
+                let literal = |v: &Value| v.get("$literal").filter(|l| indexable(l)).cloned();
+                if let Some(pair) = field(a)
+                    .zip(literal(b))
+                    .or_else(|| field(b).zip(literal(a)))
+                {
+                    out.push(pair);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut equalities = Vec::new();
+    for (key, value) in query.as_object().into_iter().flatten() {
+        if key == "$expr" {
+            collect_expr(value, &mut equalities);
+        } else if !key.starts_with('$') && indexable(value) {
+            equalities.push((key.clone(), value.clone()));
+        }
+    }
+    equalities
+}
+
+impl ReplaceRootStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        match spec.as_object() {
+            Some(obj) if obj.len() == 1 && obj.contains_key("newRoot") => {
+                Self::from_new_root(&obj["newRoot"])
+            }
+            _ => Err(MongoLiteError::AggregationError(
+                "$replaceRoot must be {newRoot: <expression>}".to_string(),
+            )),
+        }
+    }
+
+    fn from_new_root(new_root: &Value) -> Result<Self> {
+        let new_root = match new_root {
+            Value::String(s) if s.starts_with('$') && !s.starts_with("$$") => {
+                ProjectExpression::Field(s[1..].to_string())
+            }
+            Value::Object(obj) if ProjectStage::is_nested_spec(obj) => {
+                ProjectStage::parse_nested_spec(obj)?
+            }
+            Value::Object(obj) if !obj.is_empty() => ProjectStage::parse_expression(obj)?,
+            Value::Object(_) => ProjectExpression::Object(Vec::new()),
+            _ => {
+                return Err(MongoLiteError::AggregationError(
+                    "$replaceRoot newRoot must be a field reference or an object expression"
+                        .to_string(),
+                ))
+            }
+        };
+        Ok(ReplaceRootStage { new_root })
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        docs.into_iter()
+            .map(
+                |doc| match ProjectStage::evaluate_expression(&self.new_root, &doc)? {
+                    root @ Value::Object(_) => Ok(root),
+                    other => Err(MongoLiteError::AggregationError(format!(
+                        "$replaceRoot: newRoot of document {} is {}, not an object",
+                        doc.get("_id").unwrap_or(&Value::Null),
+                        match &self.new_root {
+                            ProjectExpression::Field(path)
+                                if get_nested_value(&doc, path).is_none() =>
+                            {
+                                "missing".to_string()
+                            }
+                            _ => other.to_string(),
+                        }
+                    ))),
+                },
+            )
+            .collect()
+    }
+}
+
+impl BucketStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let obj = spec.as_object().ok_or_else(|| {
+            MongoLiteError::AggregationError("$bucket must be an object".to_string())
+        })?;
+        if let Some(key) = obj
+            .keys()
+            .find(|k| !["groupBy", "boundaries", "default", "output"].contains(&k.as_str()))
+        {
+            return Err(MongoLiteError::AggregationError(format!(
+                "$bucket does not support '{}'",
+                key
+            )));
+        }
+
+        let group_by = parse_field_reference(
+            obj.get("groupBy").unwrap_or(&Value::Null),
+            "$bucket groupBy",
+        )?;
+
+        let boundary_values = obj
+            .get("boundaries")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let boundaries: Vec<f64> = boundary_values.iter().filter_map(Value::as_f64).collect();
+        if boundaries.len() < 2 || boundaries.len() != boundary_values.len() {
+            return Err(MongoLiteError::AggregationError(
+                "$bucket boundaries must be an array of at least two numbers".to_string(),
+            ));
+        }
+        if boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(MongoLiteError::AggregationError(
+                "$bucket boundaries must be sorted in ascending order without duplicates"
+                    .to_string(),
+            ));
+        }
+
+        let output = match obj.get("output") {
+            None => vec![(
+                "count".to_string(),
+                Accumulator::Sum(SumExpression::Constant(1)),
+            )],
+            Some(Value::Object(fields)) => fields
+                .iter()
+                .map(|(field, acc)| Ok((field.clone(), Accumulator::from_json(acc)?)))
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => {
+                return Err(MongoLiteError::AggregationError(
+                    "$bucket output must be an object of accumulators".to_string(),
+                ))
+            }
+        };
+
+        Ok(BucketStage {
+            group_by,
+            boundaries,
+            boundary_values,
+            default: obj.get("default").cloned(),
+            output,
+        })
+    }
+
+    fn execute(&self, docs: Vec<Value>) -> Result<Vec<Value>> {
+        // One slot per bucket, plus the default bucket at the end
+        let mut buckets: Vec<Vec<Value>> = vec![Vec::new(); self.boundaries.len()];
+        for doc in docs {
+            let value = get_nested_value(&doc, &self.group_by).and_then(Value::as_f64);
+            let slot = value.and_then(|n| {
+                self.boundaries
+                    .windows(2)
+                    .position(|bounds| bounds[0] <= n && n < bounds[1])
+            });
+            match slot {
+                Some(i) => buckets[i].push(doc),
+                None if self.default.is_some() => buckets[self.boundaries.len() - 1].push(doc),
+                None => {
+                    return Err(MongoLiteError::AggregationError(format!(
+                        "$bucket: document {} has {} = {}, outside the boundaries and no default is set",
+                        doc.get("_id").unwrap_or(&Value::Null),
+                        self.group_by,
+                        get_nested_value(&doc, &self.group_by).unwrap_or(&Value::Null)
+                    )))
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        for (i, bucket_docs) in buckets.iter().enumerate() {
+            if bucket_docs.is_empty() {
+                continue;
+            }
+            let id = if i + 1 == self.boundaries.len() {
+                self.default.clone().unwrap_or(Value::Null)
+            } else {
+                self.boundary_values[i].clone()
+            };
+            let mut result = serde_json::Map::new();
+            result.insert("_id".to_string(), id);
+            for (field, accumulator) in &self.output {
+                result.insert(field.clone(), accumulator.compute(bucket_docs)?);
+            }
+            results.push(Value::Object(result));
+        }
+        Ok(results)
+    }
+}
+
+impl FacetStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let obj = spec
+            .as_object()
+            .filter(|obj| !obj.is_empty())
+            .ok_or_else(|| {
+                MongoLiteError::AggregationError(
+                    "$facet must be a non-empty object of sub-pipelines".to_string(),
+                )
+            })?;
+        let mut facets = Vec::with_capacity(obj.len());
+        for (name, sub_pipeline) in obj {
+            if name.is_empty() || name.starts_with('$') {
+                return Err(MongoLiteError::AggregationError(format!(
+                    "$facet name '{}' must be non-empty and not start with $",
+                    name
+                )));
+            }
+            let pipeline = Pipeline::from_json(sub_pipeline).map_err(|err| {
+                MongoLiteError::AggregationError(format!("$facet '{}': {}", name, err))
+            })?;
+            facets.push((name.clone(), pipeline));
+        }
+        Ok(FacetStage { facets })
+    }
+
+    fn execute(
+        &self,
+        docs: Vec<Value>,
+        resolver: &dyn CollectionResolver,
+        writer: &dyn CollectionWriter,
+    ) -> Result<Vec<Value>> {
+        let mut output = serde_json::Map::new();
+        for (name, pipeline) in &self.facets {
+            let results = pipeline.execute_with(docs.clone(), resolver, writer)?;
+            output.insert(name.clone(), Value::Array(results));
+        }
+        Ok(vec![Value::Object(output)])
+    }
+}
+
+impl OutStage {
+    fn from_json(spec: &Value) -> Result<Self> {
+        match spec.as_str() {
+            Some(name) if !name.is_empty() && !name.starts_with('$') => Ok(OutStage {
+                collection: name.to_string(),
+            }),
+            _ => Err(MongoLiteError::AggregationError(
+                "$out must be a collection name".to_string(),
+            )),
+        }
+    }
+
+    fn execute(&self, docs: Vec<Value>, writer: &dyn CollectionWriter) -> Result<Vec<Value>> {
+        if let Some(doc) = docs.iter().find(|doc| !doc.is_object()) {
+            return Err(MongoLiteError::AggregationError(format!(
+                "$out can only write documents, got {}",
+                doc
+            )));
+        }
+        writer.replace_collection(&self.collection, docs)?;
+        Ok(Vec::new())
+    }
+}
+
+impl RedactDecision {
+    fn from_json(spec: &Value) -> Result<Self> {
+        let invalid = || {
+            MongoLiteError::AggregationError(
+                "$redact requires $$KEEP, $$PRUNE, $$DESCEND or a $cond choosing between them"
+                    .to_string(),
+            )
+        };
+        match spec {
+            Value::String(s) => match s.as_str() {
+                "$$KEEP" => Ok(RedactDecision::Action(RedactAction::Keep)),
+                "$$PRUNE" => Ok(RedactDecision::Action(RedactAction::Prune)),
+                "$$DESCEND" => Ok(RedactDecision::Action(RedactAction::Descend)),
+                _ => Err(invalid()),
+            },
+            Value::Object(obj) if obj.len() == 1 => {
+                let (condition, then, otherwise) = match obj.get("$cond").ok_or_else(invalid)? {
+                    Value::Array(args) if args.len() == 3 => (&args[0], &args[1], &args[2]),
+                    Value::Object(args) => (
+                        args.get("if").ok_or_else(invalid)?,
+                        args.get("then").ok_or_else(invalid)?,
+                        args.get("else").ok_or_else(invalid)?,
+                    ),
+                    _ => return Err(invalid()),
+                };
+                Ok(RedactDecision::Cond {
+                    condition: condition.clone(),
+                    then: Box::new(Self::from_json(then)?),
+                    otherwise: Box::new(Self::from_json(otherwise)?),
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn evaluate(&self, level: &Document) -> Result<RedactAction> {
+        match self {
+            RedactDecision::Action(action) => Ok(*action),
+            RedactDecision::Cond {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let holds = ExprOperator
+                    .matches(None, condition, Some(level))
+                    .map_err(|e| MongoLiteError::AggregationError(format!("$redact: {}", e)))?;
+                if holds {
+                    then.evaluate(level)
+                } else {
+                    otherwise.evaluate(level)
+                }
+            }
+        }
     }
 }
 
@@ -1334,6 +4040,71 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("must be an array"));
     }
 
+    #[test]
+    fn test_validate_rejects_non_terminal_out() {
+        let pipeline = json!([
+            {"$match": {"active": true}},
+            {"$out": "archive"},
+            {"$sort": {"name": 1}},
+        ]);
+        let err = Pipeline::validate(&pipeline).unwrap_err();
+        assert_eq!(
+            err.issues,
+            [PipelineIssue {
+                stage: 1,
+                message: "$out must be the last stage".to_string()
+            }]
+        );
+
+        // The same check runs before any stage is parsed
+        let message = Pipeline::from_json(&pipeline).unwrap_err().to_string();
+        assert!(message.contains("stage 1: $out must be the last stage"));
+    }
+
+    #[test]
+    fn test_validate_rejects_nested_facet() {
+        let pipeline = json!([
+            {"$facet": {
+                "byCity": [{"$group": {"_id": "$city"}}],
+                "nested": [{"$match": {}}, {"$facet": {"inner": [{"$limit": 1}]}}],
+            }},
+        ]);
+        let err = Pipeline::validate(&pipeline).unwrap_err();
+        assert_eq!(err.issues.len(), 1);
+        assert_eq!(err.issues[0].stage, 0);
+        assert_eq!(
+            err.issues[0].message,
+            "$facet 'nested': $facet is not allowed inside $facet"
+        );
+    }
+
+    #[test]
+    fn test_validate_variable_scoping() {
+        // $$value/$$this are bound inside $reduce.in only
+        assert!(Pipeline::validate(&json!([
+            {"$project": {"total": {"$reduce": {
+                "input": "$items", "initialValue": 0,
+                "in": {"$add": ["$$value", "$$this.price"]}
+            }}}},
+        ]))
+        .is_ok());
+
+        let err = Pipeline::validate(&json!([
+            {"$match": {"note": "$$literal"}},
+            {"$project": {"a": "$$missing", "b": {"$let": {
+                "vars": {"x": "$$this"},
+                "in": {"$add": ["$$x", "$$ROOT.n"]}
+            }}}},
+        ]))
+        .unwrap_err();
+        let messages: Vec<&str> = err.issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["undefined variable $$missing", "undefined variable $$this"]
+        );
+        assert!(err.issues.iter().all(|i| i.stage == 1));
+    }
+
     #[test]
     fn test_pipeline_empty() {
         let result = Pipeline::from_json(&json!([]));
@@ -1396,6 +4167,60 @@ mod tests {
         assert_eq!(results[0]["userName"], "Alice");
     }
 
+    #[test]
+    fn test_project_nested_reshaping() {
+        let docs = vec![
+            json!({"_id": 1, "name": "Alice", "city": "Paris", "zip": "75001", "tags": ["a", "b"]}),
+            json!({"_id": 2, "name": "Bob", "city": "Rome"}),
+        ];
+        let stage = ProjectStage::from_json(&json!({
+            "name": 1,
+            "contact": {
+                "address": {"city": "$city", "zip": "$zip"},
+                "tagCount": {"$size": "$tags"}
+            }
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(
+            results[0],
+            json!({"name": "Alice", "contact": {
+                "address": {"city": "Paris", "zip": "75001"},
+                "tagCount": 2
+            }})
+        );
+        // A missing source field is left out of the built subdocument
+        assert_eq!(results[1]["contact"]["address"], json!({"city": "Rome"}));
+        assert!(results[1]["contact"]["tagCount"].is_null());
+    }
+
+    #[test]
+    fn test_project_nested_preserves_literals() {
+        let docs = vec![json!({"name": "Alice"})];
+        let stage = ProjectStage::from_json(&json!({
+            "profile": {
+                "name": "$name",
+                "source": "import",
+                "meta": {"version": 1, "active": true, "labels": ["x"], "none": null}
+            }
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(
+            results[0],
+            json!({"profile": {
+                "name": "Alice",
+                "source": "import",
+                "meta": {"version": 1, "active": true, "labels": ["x"], "none": null}
+            }})
+        );
+
+        // Operator and plain keys can't be mixed in one object
+        assert!(ProjectStage::from_json(&json!({"a": {"$size": "$t", "b": 1}})).is_err());
+    }
+
     #[test]
     fn test_project_size_expression() {
         let docs = vec![
@@ -1461,6 +4286,87 @@ mod tests {
         assert_eq!(results[0]["skillCount"], 3);
     }
 
+    #[test]
+    fn test_project_split_expression() {
+        let docs = vec![
+            json!({"csv": "a,b,,c"}),
+            json!({"csv": ""}),
+            json!({"csv": 42}),
+            json!({}),
+        ];
+        let stage = ProjectStage::from_json(&json!({
+            "parts": {"$split": ["$csv", ","]}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0]["parts"], json!(["a", "b", "", "c"]));
+        // Empty string yields a single empty element
+        assert_eq!(results[1]["parts"], json!([""]));
+        // Non-string and missing input -> null
+        assert!(results[2]["parts"].is_null());
+        assert!(results[3]["parts"].is_null());
+    }
+
+    #[test]
+    fn test_project_split_invalid_args() {
+        let result = ProjectStage::from_json(&json!({"parts": {"$split": "$csv"}}));
+        assert!(result.is_err());
+
+        let result = ProjectStage::from_json(&json!({"parts": {"$split": ["$csv", ""]}}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("delimiter must not be empty"));
+    }
+
+    #[test]
+    fn test_project_trim_expressions() {
+        let docs = vec![json!({"name": "  Alice \t", "code": "--x-y--"})];
+        let stage = ProjectStage::from_json(&json!({
+            "trimmed": {"$trim": {"input": "$name"}},
+            "left": {"$ltrim": {"input": "$name"}},
+            "right": {"$rtrim": {"input": "$name"}},
+            "code": {"$trim": {"input": "$code", "chars": "-"}},
+            "empty": {"$trim": {"input": "   "}}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0]["trimmed"], "Alice");
+        assert_eq!(results[0]["left"], "Alice \t");
+        assert_eq!(results[0]["right"], "  Alice");
+        assert_eq!(results[0]["code"], "x-y");
+        assert_eq!(results[0]["empty"], "");
+    }
+
+    #[test]
+    fn test_project_trim_non_string_is_null() {
+        let docs = vec![json!({"n": 5})];
+        let stage = ProjectStage::from_json(&json!({
+            "t": {"$trim": {"input": "$n"}},
+            "missing": {"$rtrim": {"input": "$nope"}}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert!(results[0]["t"].is_null());
+        assert!(results[0]["missing"].is_null());
+    }
+
+    #[test]
+    fn test_project_split_then_size() {
+        // Operands nest: trim first, then split the trimmed string
+        let docs = vec![json!({"tags": "  rust,go  "})];
+        let stage = ProjectStage::from_json(&json!({
+            "parts": {"$split": [{"$trim": {"input": "$tags"}}, ","]}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0]["parts"], json!(["rust", "go"]));
+    }
+
     #[test]
     fn test_project_invalid_value() {
         let result = ProjectStage::from_json(&json!({"field": 5}));
@@ -1553,6 +4459,131 @@ mod tests {
             .contains("must be null or field reference"));
     }
 
+    #[test]
+    fn test_group_composite_id() {
+        let docs = vec![
+            json!({"city": "NYC", "year": 2023, "value": 1}),
+            json!({"city": "NYC", "year": 2024, "value": 2}),
+            json!({"city": "NYC", "year": 2023, "value": 3}),
+            json!({"city": "LA", "year": 2023, "value": 4}),
+            json!({"city": "LA", "value": 5}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": {"city": "$city", "year": "$year"},
+            "total": {"$sum": "$value"}
+        }))
+        .unwrap();
+
+        let mut results = stage.execute(docs).unwrap();
+        results.sort_by_key(|r| canonical_json_string(&r["_id"]));
+        assert_eq!(
+            results,
+            vec![
+                json!({"_id": {"city": "LA", "year": 2023}, "total": 4}),
+                // A missing field is left out of the _id
+                json!({"_id": {"city": "LA"}, "total": 5}),
+                json!({"_id": {"city": "NYC", "year": 2023}, "total": 4}),
+                json!({"_id": {"city": "NYC", "year": 2024}, "total": 2}),
+            ]
+        );
+
+        let result = GroupStage::from_json(&json!({"_id": {"city": "city"}}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("must start with $"));
+        let result = GroupStage::from_json(&json!({"_id": {"city": 1}}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("must be a field reference"));
+    }
+
+    #[test]
+    fn test_group_std_dev() {
+        let docs = vec![
+            json!({"team": "a", "score": 2}),
+            json!({"team": "a", "score": 4}),
+            json!({"team": "a", "score": 4}),
+            json!({"team": "a", "score": 4}),
+            json!({"team": "a", "score": 5.0}),
+            json!({"team": "a", "score": 5}),
+            json!({"team": "a", "score": 7}),
+            json!({"team": "a", "score": 9}),
+            json!({"team": "a", "score": "n/a"}),
+            json!({"team": "b", "score": 3}),
+            json!({"team": "c"}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": "$team",
+            "pop": {"$stdDevPop": "$score"},
+            "samp": {"$stdDevSamp": "$score"}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+        let group = |team: &str| results.iter().find(|r| r["_id"] == team).unwrap();
+
+        // Non-numeric values are ignored
+        assert_eq!(group("a")["pop"], json!(2.0));
+        let samp = group("a")["samp"].as_f64().unwrap();
+        assert!((samp - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+
+        // One value: no spread in the population, no sample deviation
+        assert_eq!(group("b")["pop"], json!(0.0));
+        assert!(group("b")["samp"].is_null());
+        assert!(group("c")["pop"].is_null());
+        assert!(group("c")["samp"].is_null());
+
+        assert!(
+            GroupStage::from_json(&json!({"_id": null, "s": {"$stdDevPop": "score"}})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_group_merge_objects() {
+        let docs = vec![
+            json!({"item": "a", "qty": {"jan": 1, "feb": 2}}),
+            json!({"item": "a", "qty": "n/a"}),
+            json!({"item": "a", "qty": {"feb": 5, "mar": 3}}),
+            json!({"item": "b", "qty": null}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": "$item",
+            "qty": {"$mergeObjects": "$qty"}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+        let group = |item: &str| results.iter().find(|r| r["_id"] == item).unwrap();
+
+        // Later documents win on key collisions; non-objects are skipped
+        assert_eq!(group("a")["qty"], json!({"jan": 1, "feb": 5, "mar": 3}));
+        assert_eq!(group("b")["qty"], json!({}));
+    }
+
+    #[test]
+    fn test_project_merge_objects() {
+        let stage = ProjectStage::from_json(&json!({
+            "merged": {"$mergeObjects": ["$defaults", "$overrides", {"source": "literal"}]},
+            "single": {"$mergeObjects": "$defaults"}
+        }))
+        .unwrap();
+        let docs = vec![
+            json!({"defaults": {"color": "red", "size": 1, "source": "defaults"}, "overrides": {"size": 2}}),
+            json!({"defaults": 5}),
+        ];
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(
+            results[0]["merged"],
+            json!({"color": "red", "size": 2, "source": "literal"})
+        );
+        assert_eq!(results[1]["merged"], json!({"source": "literal"}));
+        assert_eq!(results[1]["single"], json!({}));
+    }
+
     #[test]
     fn test_group_not_object() {
         let result = GroupStage::from_json(&json!("invalid"));
@@ -1597,92 +4628,335 @@ mod tests {
         }))
         .unwrap();
 
-        let results = stage.execute(docs).unwrap();
-        assert_eq!(results[0]["avg"], 20.0);
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["avg"], 20.0);
+    }
+
+    #[test]
+    fn test_accumulator_avg_empty() {
+        let docs = vec![json!({})]; // No value field
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": null,
+            "avg": {"$avg": "$value"}
+        }))
+        .unwrap();
+
+        let results = stage.execute(docs).unwrap();
+        assert!(results[0]["avg"].is_null());
+    }
+
+    #[test]
+    fn test_accumulator_min() {
+        let docs = vec![
+            json!({"value": 30}),
+            json!({"value": 10}),
+            json!({"value": 20}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": null,
+            "min": {"$min": "$value"}
+        }))
+        .unwrap();
+
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["min"], 10.0);
+    }
+
+    #[test]
+    fn test_accumulator_max() {
+        let docs = vec![
+            json!({"value": 10}),
+            json!({"value": 30}),
+            json!({"value": 20}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": null,
+            "max": {"$max": "$value"}
+        }))
+        .unwrap();
+
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["max"], 30.0);
+    }
+
+    #[test]
+    fn test_accumulator_first_last() {
+        let docs = vec![
+            json!({"value": "first"}),
+            json!({"value": "middle"}),
+            json!({"value": "last"}),
+        ];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": null,
+            "first": {"$first": "$value"},
+            "last": {"$last": "$value"}
+        }))
+        .unwrap();
+
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["first"], "first");
+        assert_eq!(results[0]["last"], "last");
+    }
+
+    #[test]
+    fn test_accumulator_sum_float() {
+        let docs = vec![json!({"value": 1.5}), json!({"value": 2.5})];
+
+        let stage = GroupStage::from_json(&json!({
+            "_id": null,
+            "sum": {"$sum": "$value"}
+        }))
+        .unwrap();
+
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["sum"], 4.0);
+    }
+
+    #[test]
+    fn test_accumulator_sum_expression_per_group() {
+        let docs = vec![
+            json!({"store": "a", "price": 2, "qty": 3}),
+            json!({"store": "a", "price": 5, "qty": 1}),
+            json!({"store": "b", "price": 1.5, "qty": 4}),
+            json!({"store": "b", "qty": 10}), // missing price contributes nothing
+        ];
+
+        let grouped = Pipeline::from_json(&json!([
+            {"$group": {
+                "_id": "$store",
+                "revenue": {"$sum": {"$multiply": ["$price", "$qty"]}}
+            }},
+            {"$sort": {"_id": 1}}
+        ]))
+        .unwrap()
+        .execute(docs.clone())
+        .unwrap();
+
+        assert_eq!(grouped[0]["revenue"], json!(11));
+        assert_eq!(grouped[1]["revenue"], json!(6.0));
+
+        // Same result as computing the value in a preceding stage
+        let two_stage = Pipeline::from_json(&json!([
+            {"$project": {"store": 1, "line": {"$multiply": ["$price", "$qty"]}}},
+            {"$group": {"_id": "$store", "revenue": {"$sum": "$line"}}},
+            {"$sort": {"_id": 1}}
+        ]))
+        .unwrap()
+        .execute(docs)
+        .unwrap();
+
+        assert_eq!(grouped, two_stage);
     }
 
     #[test]
-    fn test_accumulator_avg_empty() {
-        let docs = vec![json!({})]; // No value field
-
-        let stage = GroupStage::from_json(&json!({
-            "_id": null,
-            "avg": {"$avg": "$value"}
+    fn test_project_subtract_and_divide() {
+        let stage = ProjectStage::from_json(&json!({
+            "net": {"$subtract": ["$price", "$discount"]},
+            "unit": {"$divide": ["$price", "$qty"]},
+            "margin": {"$divide": [{"$subtract": ["$price", "$cost"]}, "$price"]},
+            "total": {"$multiply": [{"$add": ["$price", 1]}, "$qty"]}
         }))
         .unwrap();
-
+        let docs = vec![
+            json!({"price": 10, "discount": 3, "qty": 4, "cost": 6}),
+            json!({"price": 12, "discount": 0.5, "qty": 0, "cost": 9}),
+            json!({"price": 10, "qty": 5}),
+        ];
         let results = stage.execute(docs).unwrap();
-        assert!(results[0]["avg"].is_null());
+
+        assert_eq!(results[0]["net"], json!(7));
+        assert_eq!(results[0]["unit"], json!(2.5));
+        assert_eq!(results[0]["margin"], json!(0.4));
+        assert_eq!(results[0]["total"], json!(44));
+        assert_eq!(results[1]["net"], json!(11.5));
+        // Division by zero and missing operands give null
+        assert!(results[1]["unit"].is_null());
+        assert_eq!(results[1]["margin"], json!(0.25));
+        assert!(results[2]["net"].is_null());
+        assert!(results[2]["margin"].is_null());
+        // Whole-number integer division stays an integer
+        assert_eq!(results[2]["unit"], json!(2));
     }
 
     #[test]
-    fn test_accumulator_min() {
-        let docs = vec![
-            json!({"value": 30}),
-            json!({"value": 10}),
-            json!({"value": 20}),
-        ];
+    fn test_project_arithmetic_arity() {
+        for spec in [
+            json!({"x": {"$subtract": ["$a"]}}),
+            json!({"x": {"$subtract": ["$a", "$b", "$c"]}}),
+            json!({"x": {"$divide": [1]}}),
+            json!({"x": {"$divide": []}}),
+            json!({"x": {"$add": []}}),
+        ] {
+            let err = ProjectStage::from_json(&spec).unwrap_err().to_string();
+            assert!(err.contains("operands"), "{}: {}", spec, err);
+        }
+        assert!(ProjectStage::from_json(&json!({"x": {"$add": ["$a", "$b", 1]}})).is_ok());
+    }
 
-        let stage = GroupStage::from_json(&json!({
-            "_id": null,
-            "min": {"$min": "$value"}
+    #[test]
+    fn test_project_cond_and_if_null() {
+        let stage = ProjectStage::from_json(&json!({
+            "status": {"$cond": [{"$gte": ["$age", 18]}, "adult", "minor"]},
+            "tier": {"$cond": {
+                "if": {"$gt": [{"$multiply": ["$age", 2]}, 60]},
+                "then": {"$cond": [{"$eq": ["$vip", true]}, "gold", "silver"]},
+                "else": "basic"
+            }},
+            "nickname": {"$ifNull": ["$nickname", "$name"]},
+            "flagged": {"$cond": ["$flag", 1, 0]}
         }))
         .unwrap();
-
+        let docs = vec![
+            json!({"name": "Ann", "age": 40, "vip": true, "nickname": "Annie", "flag": 0}),
+            json!({"name": "Bob", "age": 12, "nickname": null, "flag": "yes"}),
+            json!({"name": "Cy", "age": 31}),
+        ];
         let results = stage.execute(docs).unwrap();
-        assert_eq!(results[0]["min"], 10.0);
+
+        assert_eq!(results[0]["status"], json!("adult"));
+        assert_eq!(results[0]["tier"], json!("gold"));
+        assert_eq!(results[0]["nickname"], json!("Annie"));
+        assert_eq!(results[0]["flagged"], json!(0));
+        assert_eq!(results[1]["status"], json!("minor"));
+        assert_eq!(results[1]["tier"], json!("basic"));
+        assert_eq!(results[1]["nickname"], json!("Bob"));
+        assert_eq!(results[1]["flagged"], json!(1));
+        assert_eq!(results[2]["tier"], json!("silver"));
+        assert_eq!(results[2]["nickname"], json!("Cy"));
+        // Missing predicate field is falsy
+        assert_eq!(results[2]["flagged"], json!(0));
     }
 
     #[test]
-    fn test_accumulator_max() {
+    fn test_project_cond_rejects_malformed_arrays() {
+        for spec in [
+            json!({"x": {"$cond": [true, 1]}}),
+            json!({"x": {"$cond": [true, 1, 2, 3]}}),
+            json!({"x": {"$cond": {"if": true, "then": 1}}}),
+            json!({"x": {"$ifNull": ["$a"]}}),
+            json!({"x": {"$gte": ["$a"]}}),
+        ] {
+            assert!(ProjectStage::from_json(&spec).is_err(), "{}", spec);
+        }
+    }
+
+    fn grade_switch(default: Option<Value>) -> Value {
+        let mut switch = json!({"branches": [
+            {"case": {"$gte": ["$score", 90]}, "then": "A"},
+            {"case": {"$gte": ["$score", 80]}, "then": "B"},
+            {"case": {"$gte": ["$score", 70]}, "then": "C"}
+        ]});
+        if let Some(default) = default {
+            switch["default"] = default;
+        }
+        json!({"grade": {"$switch": switch}})
+    }
+
+    #[test]
+    fn test_project_switch_letter_grades() {
+        let stage = ProjectStage::from_json(&grade_switch(Some(json!("F")))).unwrap();
         let docs = vec![
-            json!({"value": 10}),
-            json!({"value": 30}),
-            json!({"value": 20}),
+            json!({"score": 95}),
+            json!({"score": 90}),
+            json!({"score": 84.5}),
+            json!({"score": 70}),
+            json!({"score": 12}),
+            json!({}),
         ];
+        let grades: Vec<Value> = stage
+            .execute(docs)
+            .unwrap()
+            .into_iter()
+            .map(|doc| doc["grade"].clone())
+            .collect();
 
-        let stage = GroupStage::from_json(&json!({
-            "_id": null,
-            "max": {"$max": "$value"}
-        }))
-        .unwrap();
+        assert_eq!(
+            grades,
+            vec![
+                json!("A"),
+                json!("A"),
+                json!("B"),
+                json!("C"),
+                json!("F"),
+                json!("F")
+            ]
+        );
+    }
 
-        let results = stage.execute(docs).unwrap();
-        assert_eq!(results[0]["max"], 30.0);
+    #[test]
+    fn test_project_switch_without_default_errors_when_nothing_matches() {
+        let stage = ProjectStage::from_json(&grade_switch(None)).unwrap();
+        assert_eq!(
+            stage.execute(vec![json!({"score": 91})]).unwrap()[0]["grade"],
+            json!("A")
+        );
+
+        let err = stage
+            .execute(vec![json!({"_id": 7, "score": 50})])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no branch matched"), "{}", err);
+
+        // Also through $addFields
+        let stage = AddFieldsStage::from_json(&grade_switch(None)).unwrap();
+        assert!(stage.execute(vec![json!({"score": 50})]).is_err());
     }
 
     #[test]
-    fn test_accumulator_first_last() {
-        let docs = vec![
-            json!({"value": "first"}),
-            json!({"value": "middle"}),
-            json!({"value": "last"}),
-        ];
+    fn test_project_switch_rejects_malformed_spec() {
+        for spec in [
+            json!({"x": {"$switch": {"branches": []}}}),
+            json!({"x": {"$switch": {"branches": [{"case": true}]}}}),
+            json!({"x": {"$switch": {"branches": [{"case": true, "then": 1}], "else": 2}}}),
+            json!({"x": {"$switch": [true, 1]}}),
+        ] {
+            assert!(ProjectStage::from_json(&spec).is_err(), "{}", spec);
+        }
+    }
 
+    #[test]
+    fn test_accumulator_sum_keeps_constant_and_field_forms() {
+        let docs = vec![json!({"v": 2}), json!({"v": 3})];
         let stage = GroupStage::from_json(&json!({
             "_id": null,
-            "first": {"$first": "$value"},
-            "last": {"$last": "$value"}
+            "count": {"$sum": 1},
+            "total": {"$sum": "$v"},
+            "doubled": {"$sum": {"$add": ["$v", "$v"]}}
         }))
         .unwrap();
 
         let results = stage.execute(docs).unwrap();
-        assert_eq!(results[0]["first"], "first");
-        assert_eq!(results[0]["last"], "last");
+        assert_eq!(results[0]["count"], 2);
+        assert_eq!(results[0]["total"], 5);
+        assert_eq!(results[0]["doubled"], 10);
     }
 
     #[test]
-    fn test_accumulator_sum_float() {
-        let docs = vec![json!({"value": 1.5}), json!({"value": 2.5})];
-
+    fn test_accumulator_sum_overflow_promotes_to_float() {
+        let docs = vec![
+            json!({"v": i64::MAX}),
+            json!({"v": i64::MAX}),
+            json!({"v": -1}),
+        ];
         let stage = GroupStage::from_json(&json!({
             "_id": null,
-            "sum": {"$sum": "$value"}
+            "total": {"$sum": "$v"},
+            "big": {"$sum": i64::MAX},
+            "count": {"$sum": 1}
         }))
         .unwrap();
 
         let results = stage.execute(docs).unwrap();
-        assert_eq!(results[0]["sum"], 4.0);
+        // Past i64 the sums become floats instead of clamping at i64::MAX
+        assert!(results[0]["total"].is_f64());
+        assert_eq!(results[0]["total"], 2.0 * i64::MAX as f64 - 1.0);
+        assert!(results[0]["big"].is_f64());
+        assert_eq!(results[0]["big"], 3.0 * i64::MAX as f64);
+        assert_eq!(results[0]["count"], 3);
     }
 
     #[test]
@@ -1811,6 +5085,280 @@ mod tests {
             .contains("exactly one operator"));
     }
 
+    #[test]
+    fn test_group_count_accumulator() {
+        let docs = vec![
+            json!({"city": "NYC"}),
+            json!({"city": "LA"}),
+            json!({"city": "NYC"}),
+        ];
+        let stage = GroupStage::from_json(&json!({
+            "_id": "$city",
+            "n": {"$count": {}},
+            "legacy": {"$sum": 1}
+        }))
+        .unwrap();
+
+        let mut results = stage.execute(docs).unwrap();
+        results.sort_by_key(|doc| doc["_id"].to_string());
+        assert_eq!(results[0], json!({"_id": "LA", "n": 1, "legacy": 1}));
+        assert_eq!(results[1], json!({"_id": "NYC", "n": 2, "legacy": 2}));
+
+        let result = Accumulator::from_json(&json!({"$count": {"field": 1}}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("takes no arguments"));
+        assert!(Accumulator::from_json(&json!({"$count": 1})).is_err());
+    }
+
+    // ========== SortByCountStage tests ==========
+
+    #[test]
+    fn test_sort_by_count_nested_field() {
+        let docs = vec![
+            json!({"address": {"city": "Pécs"}}),
+            json!({"address": {"city": "Győr"}}),
+            json!({"address": {"city": "Pécs"}}),
+            json!({"address": {"city": "Szeged"}}),
+            json!({"address": {"city": "Pécs"}}),
+            json!({"address": {"city": "Győr"}}),
+            json!({"address": {}}),
+        ];
+        let pipeline = Pipeline::from_json(&json!([{"$sortByCount": "$address.city"}])).unwrap();
+        let results = pipeline.execute(docs).unwrap();
+
+        assert_eq!(
+            results,
+            vec![
+                json!({"_id": "Pécs", "count": 3}),
+                json!({"_id": "Győr", "count": 2}),
+                json!({"_id": null, "count": 1}),
+                json!({"_id": "Szeged", "count": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_count_requires_field_reference() {
+        for spec in [
+            json!("city"),
+            json!("$"),
+            json!({"_id": "$city"}),
+            json!(["$city"]),
+            json!("$$ROOT"),
+        ] {
+            assert!(
+                Pipeline::from_json(&json!([{"$sortByCount": spec}])).is_err(),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_take_leading_match() {
+        let mut pipeline = Pipeline::from_json(&json!([
+            {"$match": {"age": {"$gte": 30}}},
+            {"$match": {"city": "NYC"}},
+            {"$count": "n"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            pipeline.take_leading_match(),
+            Some(json!({"age": {"$gte": 30}}))
+        );
+        assert_eq!(pipeline.take_leading_match(), Some(json!({"city": "NYC"})));
+        assert_eq!(pipeline.take_leading_match(), None);
+        assert_eq!(pipeline.stages.len(), 1);
+
+        // Nothing left but the matches: the remaining pipeline passes input through
+        let mut pipeline = Pipeline::from_json(&json!([{"$match": {}}])).unwrap();
+        assert_eq!(pipeline.take_leading_match(), Some(json!({})));
+        let docs = vec![json!({"a": 1})];
+        assert_eq!(pipeline.execute(docs.clone()).unwrap(), docs);
+    }
+
+    // ========== Streaming tests ==========
+
+    #[test]
+    fn test_into_streaming_splits_at_first_blocking_stage() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$unwind": "$tags"},
+            {"$skip": 1},
+            {"$limit": 3},
+            {"$sort": {"n": 1}},
+            {"$project": {"n": 1}}
+        ]))
+        .unwrap();
+        let (mut stages, rest) = pipeline.into_streaming();
+        assert_eq!(rest.unwrap().stages.len(), 2);
+
+        // Skip and limit carry over from one input document to the next
+        let first = stages.push(json!({"n": 1, "tags": ["a", "b"]})).unwrap();
+        assert_eq!(first, vec![json!({"n": 1, "tags": "b"})]);
+        assert!(!stages.is_exhausted());
+        let second = stages
+            .push(json!({"n": 2, "tags": ["c", "d", "e"]}))
+            .unwrap();
+        assert_eq!(second.len(), 2);
+        assert!(stages.is_exhausted());
+        assert!(stages
+            .push(json!({"n": 3, "tags": ["f"]}))
+            .unwrap()
+            .is_empty());
+    }
+
+    // ========== CountStage tests ==========
+
+    #[test]
+    fn test_count_stage() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$match": {"age": {"$gte": 30}}},
+            {"$count": "numMatched"}
+        ]))
+        .unwrap();
+        let docs = vec![json!({"age": 25}), json!({"age": 30}), json!({"age": 41})];
+        assert_eq!(
+            pipeline.execute(docs).unwrap(),
+            vec![json!({"numMatched": 2})]
+        );
+
+        // No upstream documents still yields a count
+        let docs = vec![json!({"age": 1})];
+        assert_eq!(
+            pipeline.execute(docs).unwrap(),
+            vec![json!({"numMatched": 0})]
+        );
+    }
+
+    #[test]
+    fn test_count_stage_rejects_bad_field_names() {
+        for spec in [
+            json!(""),
+            json!("$n"),
+            json!("a.b"),
+            json!(1),
+            json!({"n": 1}),
+        ] {
+            assert!(CountStage::from_json(&spec).is_err(), "{}", spec);
+        }
+    }
+
+    // ========== AddFieldsStage tests ==========
+
+    #[test]
+    fn test_add_fields_keeps_existing_fields() {
+        let docs = vec![
+            json!({"_id": 1, "name": "a", "tags": ["x", "y"], "price": 2, "qty": 5}),
+            json!({"_id": 2, "name": "b", "price": 3, "qty": 1}),
+        ];
+        let stage = AddFieldsStage::from_json(&json!({
+            "tagCount": {"$size": "$tags"},
+            "stats.total": {"$multiply": ["$price", "$qty"]},
+            "source": "import"
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(
+            results[0],
+            json!({
+                "_id": 1, "name": "a", "tags": ["x", "y"], "price": 2, "qty": 5,
+                "tagCount": 2, "stats": {"total": 10}, "source": "import"
+            })
+        );
+        // A null result still sets the field
+        assert_eq!(results[1]["tagCount"], Value::Null);
+        assert!(results[1].as_object().unwrap().contains_key("tagCount"));
+        assert_eq!(results[1]["stats"]["total"], 3);
+        assert_eq!(results[1]["name"], "b");
+    }
+
+    #[test]
+    fn test_add_fields_in_pipeline() {
+        let docs = vec![json!({"_id": 1, "scores": [1, 2, 3], "meta": {"a": 1}})];
+        let pipeline = Pipeline::from_json(&json!([
+            {"$addFields": {
+                "sum": {"$reduce": {
+                    "input": "$scores",
+                    "initialValue": 0,
+                    "in": {"$add": ["$$value", "$$this"]}
+                }},
+                "meta.b": "$scores"
+            }},
+            {"$set": {"scores": {"$size": "$scores"}}}
+        ]))
+        .unwrap();
+        let results = pipeline.execute(docs).unwrap();
+
+        assert_eq!(results[0]["sum"], 6.0);
+        assert_eq!(results[0]["meta"], json!({"a": 1, "b": [1, 2, 3]}));
+        assert_eq!(results[0]["scores"], 3);
+        assert!(AddFieldsStage::from_json(&json!([1])).is_err());
+    }
+
+    // ========== RedactStage tests ==========
+
+    fn classified_docs() -> Vec<Value> {
+        vec![
+            json!({
+                "_id": 1,
+                "level": "public",
+                "title": "Report",
+                "sections": [
+                    {"level": "public", "body": "summary"},
+                    {"level": "secret", "body": "sources"}
+                ],
+                "meta": {"level": "secret", "author": "M"}
+            }),
+            json!({"_id": 2, "level": "secret", "title": "Plans"}),
+        ]
+    }
+
+    #[test]
+    fn test_redact_prunes_unpermitted_subdocuments() {
+        let pipeline = Pipeline::from_json(&json!([{"$redact": {"$cond": {
+            "if": {"$eq": ["$level", "public"]},
+            "then": "$$DESCEND",
+            "else": "$$PRUNE"
+        }}}]))
+        .unwrap();
+
+        let results = pipeline.execute(classified_docs()).unwrap();
+        assert_eq!(
+            results,
+            vec![json!({
+                "_id": 1,
+                "level": "public",
+                "title": "Report",
+                "sections": [{"level": "public", "body": "summary"}]
+            })]
+        );
+    }
+
+    #[test]
+    fn test_redact_keep_stops_descending() {
+        // Array form of $cond; $$KEEP returns the whole level unexamined
+        let stage = RedactStage::from_json(&json!({"$cond": [
+            {"$eq": ["$level", "public"]}, "$$KEEP", "$$PRUNE"
+        ]}))
+        .unwrap();
+        let results = stage.execute(classified_docs()).unwrap();
+        assert_eq!(results, vec![classified_docs()[0].clone()]);
+
+        assert_eq!(
+            RedactStage::from_json(&json!("$$PRUNE"))
+                .unwrap()
+                .execute(classified_docs())
+                .unwrap()
+                .len(),
+            0
+        );
+        let err = RedactStage::from_json(&json!({"$cond": [true, "$$KEEP"]})).unwrap_err();
+        assert!(err.to_string().contains("$redact requires"));
+    }
+
     // ========== SortStage tests ==========
 
     #[test]
@@ -1829,6 +5377,21 @@ mod tests {
         assert_eq!(results[2]["age"], 25);
     }
 
+    #[test]
+    fn test_sort_orders_missing_null_and_values() {
+        let docs = vec![
+            json!({"n": 1, "v": 2}),
+            json!({"n": 2, "v": null}),
+            json!({"n": 3}),
+            json!({"n": 4, "v": 1}),
+        ];
+
+        let stage = SortStage::from_json(&json!({"v": 1})).unwrap();
+        let results = stage.execute(docs).unwrap();
+        let order: Vec<i64> = results.iter().map(|d| d["n"].as_i64().unwrap()).collect();
+        assert_eq!(order, [3, 2, 4, 1]);
+    }
+
     #[test]
     fn test_sort_invalid_direction_value() {
         let result = SortStage::from_json(&json!({"field": 0}));
@@ -2426,6 +5989,62 @@ mod tests {
         assert_eq!(results[1]["data"]["tags"], "mongodb");
     }
 
+    #[test]
+    fn test_unwind_path_through_array() {
+        let docs = vec![json!({
+            "customer": "ann",
+            "orders": [
+                {"id": 1, "items": ["a", "b"]},
+                {"id": 2, "items": ["c"]},
+                {"id": 3, "items": []}
+            ]
+        })];
+        let stage = UnwindStage::from_json(&json!({
+            "path": "$orders.items",
+            "includeArrayIndex": "itemIndex"
+        }))
+        .unwrap();
+        let results = stage.execute(docs.clone()).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0],
+            json!({"customer": "ann", "orders": {"id": 1, "items": "a"}, "itemIndex": 0})
+        );
+        assert_eq!(results[1]["orders"], json!({"id": 1, "items": "b"}));
+        assert_eq!(results[1]["itemIndex"], 1);
+        assert_eq!(results[2]["orders"], json!({"id": 2, "items": "c"}));
+        assert_eq!(results[2]["itemIndex"], 0);
+
+        // The order with no items survives as null when preserving
+        let stage = UnwindStage::from_json(&json!({
+            "path": "$orders.items",
+            "preserveNullAndEmptyArrays": true
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[3]["orders"], json!({"id": 3, "items": null}));
+    }
+
+    #[test]
+    fn test_unwind_through_nested_arrays() {
+        let docs = vec![json!({
+            "regions": [
+                {"stores": [{"tags": ["x", "y"]}, {"tags": ["z"]}]},
+                {"stores": [{"tags": ["w"]}]}
+            ]
+        })];
+        let stage = UnwindStage::from_json(&json!("$regions.stores.tags")).unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        let tags: Vec<&Value> = results
+            .iter()
+            .map(|doc| &doc["regions"]["stores"]["tags"])
+            .collect();
+        assert_eq!(tags, vec!["x", "y", "z", "w"]);
+    }
+
     #[test]
     fn test_unwind_pipeline_integration() {
         // Test $unwind in a full pipeline with $match and $group
@@ -2447,11 +6066,151 @@ mod tests {
 
         let results = pipeline.execute(docs).unwrap();
 
-        assert_eq!(results.len(), 2);
-        assert_eq!(results[0]["_id"], "A");
-        assert_eq!(results[0]["total"], 15); // 1+2+3+4+5
-        assert_eq!(results[1]["_id"], "B");
-        assert_eq!(results[1]["total"], 30); // 10+20
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["_id"], "A");
+        assert_eq!(results[0]["total"], 15); // 1+2+3+4+5
+        assert_eq!(results[1]["_id"], "B");
+        assert_eq!(results[1]["total"], 30); // 10+20
+    }
+
+    #[test]
+    fn test_unwind_index_survives_include_projection() {
+        let docs = vec![
+            json!({"_id": 1, "tags": ["a", "b", "c"], "other": true}),
+            json!({"_id": 2, "tags": ["d"], "other": false}),
+        ];
+
+        for (index_field, projection) in [
+            ("pos", json!({"tags": 1, "pos": 1})),
+            ("pos", json!({"tags": true, "pos": 1.0})),
+            ("meta.pos", json!({"tags": 1, "meta.pos": 1})),
+        ] {
+            let pipeline = Pipeline::from_json(&json!([
+                {"$unwind": {"path": "$tags", "includeArrayIndex": index_field}},
+                {"$project": projection}
+            ]))
+            .unwrap();
+            let results = pipeline.execute(docs.clone()).unwrap();
+
+            let positions: Vec<(String, i64)> = results
+                .iter()
+                .map(|doc| {
+                    assert!(doc.get("other").is_none());
+                    let pos = get_nested_value(doc, index_field)
+                        .and_then(Value::as_i64)
+                        .unwrap_or_else(|| panic!("{} missing from {}", index_field, doc));
+                    (doc["tags"].as_str().unwrap().to_string(), pos)
+                })
+                .collect();
+            assert_eq!(
+                positions,
+                vec![
+                    ("a".to_string(), 0),
+                    ("b".to_string(), 1),
+                    ("c".to_string(), 2),
+                    ("d".to_string(), 0)
+                ],
+                "index field '{}'",
+                index_field
+            );
+        }
+    }
+
+    #[test]
+    fn test_project_nested_include_keeps_structure() {
+        let docs = vec![json!({"a": {"x": 1, "y": 2, "z": 3}, "b": 4})];
+        let stage = ProjectStage::from_json(&json!({"a.x": 1, "a.z": 1})).unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0], json!({"a": {"x": 1, "z": 3}}));
+    }
+
+    #[test]
+    fn test_project_rejects_other_numbers() {
+        assert!(ProjectStage::from_json(&json!({"a": 2})).is_err());
+        assert!(ProjectStage::from_json(&json!({"a": 0.5})).is_err());
+        assert!(matches!(
+            ProjectStage::from_json(&json!({"a": false}))
+                .unwrap()
+                .fields["a"],
+            ProjectField::Exclude
+        ));
+    }
+
+    #[test]
+    fn test_object_to_array_round_trip() {
+        let docs = vec![
+            json!({"_id": 1, "attrs": {"color": "red", "size": 3, "nested": {"x": [1, 2]}}}),
+            json!({"_id": 2, "attrs": {}}),
+        ];
+        let pipeline = Pipeline::from_json(&json!([
+            {"$project": {"_id": 1, "attrs": 1, "pairs": {"$objectToArray": "$attrs"}}},
+            {"$project": {"_id": 1, "attrs": 1, "pairs": 1, "back": {"$arrayToObject": "$pairs"}}}
+        ]))
+        .unwrap();
+
+        let results = pipeline.execute(docs).unwrap();
+        assert_eq!(results[0]["back"], results[0]["attrs"]);
+        assert_eq!(results[0]["pairs"][0], json!({"k": "color", "v": "red"}));
+        assert_eq!(results[1]["pairs"], json!([]));
+        assert_eq!(results[1]["back"], json!({}));
+    }
+
+    #[test]
+    fn test_array_to_object_forms_and_wrong_types() {
+        let docs = vec![json!({
+            "pairs": [["a", 1], ["b", 2], ["a", 3]],
+            "bad": [{"k": 1, "v": 2}],
+            "text": "not an array",
+            "obj": [1, 2]
+        })];
+        let pipeline = Pipeline::from_json(&json!([{"$project": {
+            "fromPairs": {"$arrayToObject": "$pairs"},
+            "literal": {"$arrayToObject": [[{"k": "x", "v": true}]]},
+            "bad": {"$arrayToObject": "$bad"},
+            "text": {"$arrayToObject": "$text"},
+            "notObject": {"$objectToArray": "$obj"},
+            "missing": {"$objectToArray": "$missing"}
+        }}]))
+        .unwrap();
+
+        let result = &pipeline.execute(docs).unwrap()[0];
+        // Later duplicates win
+        assert_eq!(result["fromPairs"], json!({"a": 3, "b": 2}));
+        assert_eq!(result["literal"], json!({"x": true}));
+        assert_eq!(result["bad"], Value::Null);
+        assert_eq!(result["text"], Value::Null);
+        assert_eq!(result["notObject"], Value::Null);
+        assert_eq!(result["missing"], Value::Null);
+    }
+
+    #[test]
+    fn test_leading_size_paths() {
+        let paths = |pipeline: Value| Pipeline::from_json(&pipeline).unwrap().leading_size_paths();
+
+        assert_eq!(
+            paths(
+                json!([{"$project": {"_id": 0, "n": {"$size": "$tags"}, "m": {"$size": "$a.b"}}}])
+            ),
+            Some(vec!["a.b".to_string(), "tags".to_string()])
+        );
+        // Anything else in the projection, or a different first stage, needs the document
+        assert_eq!(
+            paths(json!([{"$project": {"name": 1, "n": {"$size": "$tags"}}}])),
+            None
+        );
+        assert_eq!(
+            paths(json!([{"$match": {}}, {"$project": {"n": {"$size": "$tags"}}}])),
+            None
+        );
+        assert_eq!(
+            paths(json!([{"$project": {"n": {"$size": "$items.0"}}}])),
+            None
+        );
+        assert_eq!(
+            paths(json!([{"$project": {"n": {"$size": "$a"}, "m": {"$size": "$a.b"}}}])),
+            None
+        );
     }
 
     #[test]
@@ -3137,4 +6896,689 @@ mod tests {
         let skills = results[0]["uniqueSkills"].as_array().unwrap();
         assert_eq!(skills.len(), 2); // Only unique: Excel, Python
     }
+
+    // ========== $concat null handling tests ==========
+
+    #[test]
+    fn test_concat_null_operand_returns_null() {
+        let docs = vec![
+            json!({"first": "Ada", "last": "Lovelace"}),
+            json!({"first": "Grace", "last": null}),
+            json!({"first": "Alan"}),
+        ];
+        let stage = ProjectStage::from_json(&json!({
+            "name": {"$concat": ["$first", " ", "$last"]}
+        }))
+        .unwrap();
+        let results = stage.execute(docs.clone()).unwrap();
+        assert_eq!(results[0]["name"], "Ada Lovelace");
+        assert_eq!(results[1]["name"], Value::Null);
+        assert_eq!(results[2]["name"], Value::Null);
+
+        let stage = ProjectStage::from_json(&json!({
+            "name": {"$concat": {"input": ["$first", "-", "$last"], "skipNulls": true}}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+        assert_eq!(results[0]["name"], "Ada-Lovelace");
+        assert_eq!(results[1]["name"], "Grace-");
+        assert_eq!(results[2]["name"], "Alan-");
+
+        let bad = json!({"name": {"$concat": {"input": ["$a"], "skipNulls": "yes"}}});
+        assert!(ProjectStage::from_json(&bad).is_err());
+    }
+
+    #[test]
+    fn test_project_change_case_and_substr() {
+        let docs = vec![
+            json!({"name": "Émile Zoë", "code": 42, "city": "Győr"}),
+            json!({"name": "日本語テキスト", "flag": true}),
+        ];
+        let stage = ProjectStage::from_json(&json!({
+            "upper": {"$toUpper": "$name"},
+            "lower": {"$toLower": ["$name"]},
+            "code": {"$toUpper": "$code"},
+            "flag": {"$toUpper": "$flag"},
+            "missing": {"$toLower": "$nope"},
+            "initial": {"$substr": ["$name", 0, 1]},
+            "middle": {"$substr": ["$name", 2, 3]},
+            "rest": {"$substr": ["$name", 6, -1]},
+            "past_end": {"$substr": ["$name", 50, 2]},
+            "label": {"$concat": [{"$toUpper": {"$substr": ["$city", 0, 3]}}, "-", {"$toLower": "$name"}]}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0]["upper"], "ÉMILE ZOË");
+        assert_eq!(results[0]["lower"], "émile zoë");
+        assert_eq!(results[0]["code"], "42");
+        assert_eq!(results[0]["missing"], "");
+        assert_eq!(results[0]["initial"], "É");
+        assert_eq!(results[0]["middle"], "ile");
+        assert_eq!(results[0]["rest"], "Zoë");
+        assert_eq!(results[0]["past_end"], "");
+        assert_eq!(results[0]["label"], "GYŐ-émile zoë");
+
+        // Three-byte characters are never split
+        assert_eq!(results[1]["initial"], "日");
+        assert_eq!(results[1]["middle"], "語テキ");
+        assert_eq!(results[1]["rest"], "ト");
+        assert_eq!(results[1]["flag"], "TRUE");
+
+        for bad in [
+            json!({"x": {"$substr": ["$name", 0]}}),
+            json!({"x": {"$substr": "$name"}}),
+        ] {
+            assert!(ProjectStage::from_json(&bad).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reduce_concat_null_elements() {
+        let docs = vec![json!({
+            "words": ["a", null, "b"],
+            "items": [{"label": "x"}, {"other": 1}, {"label": "y"}]
+        })];
+        let stage = ProjectStage::from_json(&json!({
+            "plain": {"$reduce": {
+                "input": "$words", "initialValue": "",
+                "in": {"$concat": ["$$value", "$$this"]}
+            }},
+            "labels": {"$reduce": {
+                "input": "$items", "initialValue": "",
+                "in": {"$concat": ["$$value", ", ", "$$this.label"]}
+            }},
+            "nullStart": {"$reduce": {
+                "input": "$words", "initialValue": null,
+                "in": {"$concat": {"input": ["$$value", "$$this"], "skipNulls": true}}
+            }},
+            "skipped": {"$reduce": {
+                "input": "$items", "initialValue": "",
+                "in": {"$concat": {"input": ["$$value", ", ", "$$this.label"], "skipNulls": true}}
+            }}
+        }))
+        .unwrap();
+        let results = stage.execute(docs).unwrap();
+
+        assert_eq!(results[0]["plain"], Value::Null);
+        assert_eq!(results[0]["labels"], Value::Null);
+        assert_eq!(results[0]["nullStart"], "ab");
+        assert_eq!(results[0]["skipped"], "x, y");
+    }
+
+    // ========== $dedup stage tests ==========
+
+    #[test]
+    fn test_dedup_by_single_field_keeps_first() {
+        let docs = vec![
+            json!({"_id": 1, "order": "a", "n": 1}),
+            json!({"_id": 2, "order": "b", "n": 2}),
+            json!({"_id": 3, "order": "a", "n": 3}),
+            json!({"_id": 4, "n": 4}),
+            json!({"_id": 5, "order": null, "n": 5}),
+        ];
+        let pipeline = Pipeline::from_json(&json!([{"$dedup": {"by": "order"}}])).unwrap();
+        let results = pipeline.execute(docs).unwrap();
+
+        let ns: Vec<i64> = results.iter().map(|d| d["n"].as_i64().unwrap()).collect();
+        assert_eq!(ns, vec![1, 2, 4]); // missing and null share a key
+    }
+
+    #[test]
+    fn test_dedup_by_multiple_fields() {
+        let docs = vec![
+            json!({"user": "u1", "item": {"sku": 1, "v": 1}, "seq": 1}),
+            json!({"user": "u1", "item": {"v": 1, "sku": 1}, "seq": 2}),
+            json!({"user": "u1", "item": {"sku": 2, "v": 1}, "seq": 3}),
+            json!({"user": "u2", "item": {"sku": 1, "v": 1}, "seq": 4}),
+            json!({"user": "u2", "item": {"sku": 1, "v": 1}, "seq": 5}),
+        ];
+        let pipeline = Pipeline::from_json(&json!([
+            {"$distinctDocs": {"by": ["$user", "item"]}}
+        ]))
+        .unwrap();
+        let results = pipeline.execute(docs).unwrap();
+
+        let seqs: Vec<i64> = results.iter().map(|d| d["seq"].as_i64().unwrap()).collect();
+        assert_eq!(seqs, vec![1, 3, 4]);
+
+        assert!(Pipeline::from_json(&json!([{"$dedup": {"by": []}}])).is_err());
+        assert!(Pipeline::from_json(&json!([{"$dedup": {"on": "x"}}])).is_err());
+    }
+
+    // ========== ReplaceRootStage tests ==========
+
+    #[test]
+    fn test_replace_root_promotes_subdocument() {
+        let docs = vec![
+            json!({"_id": 1, "name": "ann", "address": {"city": "Rome", "zip": "00100"}}),
+            json!({"_id": 2, "name": "bob", "address": {"city": "Oslo", "zip": "0150"}}),
+        ];
+        let pipeline = Pipeline::from_json(&json!([
+            {"$replaceRoot": {"newRoot": "$address"}},
+            {"$match": {"city": "Rome"}},
+            {"$project": {"_id": 0, "zip": 1}}
+        ]))
+        .unwrap();
+        assert_eq!(
+            pipeline.execute(docs.clone()).unwrap(),
+            vec![json!({"zip": "00100"})]
+        );
+
+        // $replaceWith shorthand, with an object expression
+        let pipeline = Pipeline::from_json(&json!([
+            {"$replaceWith": {"who": "$name", "city": "$address.city"}}
+        ]))
+        .unwrap();
+        assert_eq!(
+            pipeline.execute(docs).unwrap(),
+            vec![
+                json!({"who": "ann", "city": "Rome"}),
+                json!({"who": "bob", "city": "Oslo"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_root_rejects_non_objects() {
+        let pipeline = Pipeline::from_json(&json!([{"$replaceWith": "$address"}])).unwrap();
+        let err = pipeline
+            .execute(vec![json!({"_id": 7, "address": "Main St"})])
+            .unwrap_err();
+        assert!(err.to_string().contains("document 7"), "{}", err);
+        let err = pipeline.execute(vec![json!({"_id": 8})]).unwrap_err();
+        assert!(err.to_string().contains("missing"), "{}", err);
+
+        assert!(Pipeline::from_json(&json!([{"$replaceRoot": "$address"}])).is_err());
+        assert!(Pipeline::from_json(&json!([{"$replaceWith": 5}])).is_err());
+    }
+
+    // ========== SampleStage tests ==========
+
+    #[test]
+    fn test_sample_is_deterministic_with_a_seed() {
+        let docs: Vec<Value> = (0..1000).map(|i| json!({"_id": i})).collect();
+        let sample = SampleStage::with_seed(10, 42)
+            .execute(docs.clone())
+            .unwrap();
+        assert_eq!(sample.len(), 10);
+        assert_eq!(
+            sample,
+            SampleStage::with_seed(10, 42)
+                .execute(docs.clone())
+                .unwrap()
+        );
+        assert_ne!(
+            sample,
+            SampleStage::with_seed(10, 43)
+                .execute(docs.clone())
+                .unwrap()
+        );
+        let distinct: HashSet<String> = sample.iter().map(|d| d["_id"].to_string()).collect();
+        assert_eq!(distinct.len(), 10);
+
+        // Every document is equally likely: the tail of the input gets picked too
+        let late = (0..200u64)
+            .flat_map(|seed| {
+                SampleStage::with_seed(10, seed)
+                    .execute(docs.clone())
+                    .unwrap()
+            })
+            .filter(|d| d["_id"].as_i64().unwrap() >= 500)
+            .count();
+        assert!((800..1200).contains(&late), "late picks: {}", late);
+
+        // Asking for more than there is returns everything
+        let mut all = SampleStage::with_seed(5, 1)
+            .execute(docs[..3].to_vec())
+            .unwrap();
+        all.sort_by_key(|d| d["_id"].as_i64());
+        assert_eq!(all, docs[..3].to_vec());
+        assert!(SampleStage::with_seed(0, 1)
+            .execute(docs)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sample_after_match_and_bad_specs() {
+        let docs: Vec<Value> = (0..100)
+            .map(|i| json!({"_id": i, "even": i % 2 == 0}))
+            .collect();
+        let pipeline =
+            Pipeline::from_json(&json!([{"$match": {"even": true}}, {"$sample": {"size": 5}}]))
+                .unwrap();
+        let sample = pipeline.execute(docs).unwrap();
+        assert_eq!(sample.len(), 5);
+        assert!(sample.iter().all(|d| d["even"] == json!(true)));
+
+        for spec in [
+            json!(5),
+            json!({"size": -1}),
+            json!({"size": 1.5}),
+            json!({"size": 2, "seed": 1}),
+        ] {
+            assert!(Pipeline::from_json(&json!([{"$sample": spec}])).is_err());
+        }
+    }
+
+    // ========== OutStage tests ==========
+
+    struct RecordingWriter(std::cell::RefCell<Vec<(String, Vec<Value>)>>);
+
+    impl CollectionWriter for RecordingWriter {
+        fn replace_collection(&self, name: &str, docs: Vec<Value>) -> Result<()> {
+            self.0.borrow_mut().push((name.to_string(), docs));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_out_writes_results_and_returns_nothing() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$group": {"_id": "$city", "n": {"$sum": 1}}},
+            {"$sort": {"_id": 1}},
+            {"$out": "city_counts"}
+        ]))
+        .unwrap();
+        assert!(pipeline.writes_collection());
+        let docs = vec![
+            json!({"city": "Pécs"}),
+            json!({"city": "Győr"}),
+            json!({"city": "Pécs"}),
+        ];
+        let writer = RecordingWriter(Default::default());
+        let results = pipeline
+            .execute_with(docs, &NoCollections, &writer)
+            .unwrap();
+        assert!(results.is_empty());
+        assert_eq!(
+            writer.0.into_inner(),
+            [(
+                "city_counts".to_string(),
+                vec![
+                    json!({"_id": "Győr", "n": 1}),
+                    json!({"_id": "Pécs", "n": 2})
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_out_bad_specs_and_no_database() {
+        for spec in [
+            json!(5),
+            json!(""),
+            json!("$name"),
+            json!({"db": "x", "coll": "y"}),
+        ] {
+            assert!(Pipeline::from_json(&json!([{"$out": spec}])).is_err());
+        }
+
+        let pipeline = Pipeline::from_json(&json!([{"$out": "copy"}])).unwrap();
+        let err = pipeline.execute(vec![json!({"_id": 1})]).unwrap_err();
+        assert!(err.to_string().contains("not running against a database"));
+        assert!(!Pipeline::from_json(&json!([{"$match": {}}]))
+            .unwrap()
+            .writes_collection());
+    }
+
+    // ========== BucketStage tests ==========
+
+    #[test]
+    fn test_bucket_groups_into_ranges() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$bucket": {
+                "groupBy": "$age",
+                "boundaries": [0, 18, 40, 65],
+                "default": "other",
+                "output": {"count": {"$sum": 1}, "names": {"$push": "$name"}}
+            }}
+        ]))
+        .unwrap();
+        let docs = vec![
+            json!({"name": "a", "age": 39}),
+            json!({"name": "b", "age": 5}),
+            json!({"name": "c", "age": 18}),
+            json!({"name": "d", "age": 65}),
+            json!({"name": "e"}),
+            json!({"name": "f", "age": 17.5}),
+        ];
+
+        let results = pipeline.execute(docs).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                json!({"_id": 0, "count": 2, "names": ["b", "f"]}),
+                json!({"_id": 18, "count": 2, "names": ["a", "c"]}),
+                json!({"_id": "other", "count": 2, "names": ["d", "e"]}),
+            ]
+        );
+
+        // Without a default, a value outside the boundaries fails the stage
+        let pipeline = Pipeline::from_json(&json!([
+            {"$bucket": {"groupBy": "$age", "boundaries": [0, 18]}}
+        ]))
+        .unwrap();
+        assert_eq!(
+            pipeline.execute(vec![json!({"age": 3})]).unwrap(),
+            vec![json!({"_id": 0, "count": 1})]
+        );
+        let err = pipeline
+            .execute(vec![json!({"_id": 7, "age": 30})])
+            .unwrap_err();
+        assert!(err.to_string().contains("document 7"), "{}", err);
+    }
+
+    #[test]
+    fn test_bucket_rejects_bad_boundaries() {
+        for boundaries in [
+            json!([5]),
+            json!([0, 10, 10]),
+            json!([10, 0]),
+            json!([0, "x"]),
+        ] {
+            let spec = json!([{"$bucket": {"groupBy": "$n", "boundaries": boundaries}}]);
+            assert!(Pipeline::from_json(&spec).is_err(), "{}", spec);
+        }
+        let spec = json!([{"$bucket": {"groupBy": "n", "boundaries": [0, 1]}}]);
+        assert!(Pipeline::from_json(&spec).is_err());
+    }
+
+    // ========== FacetStage tests ==========
+
+    #[test]
+    fn test_facet_runs_each_sub_pipeline() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$match": {"age": {"$gte": 20}}},
+            {"$facet": {
+                "byCity": [
+                    {"$group": {"_id": "$city", "n": {"$sum": 1}}},
+                    {"$sort": {"_id": 1}}
+                ],
+                "oldest": [{"$sort": {"age": -1}}, {"$limit": 1}, {"$project": {"_id": 0, "age": 1}}],
+                "total": [{"$count": "n"}]
+            }}
+        ]))
+        .unwrap();
+        let docs = vec![
+            json!({"city": "Rome", "age": 30}),
+            json!({"city": "Oslo", "age": 45}),
+            json!({"city": "Rome", "age": 22}),
+            json!({"city": "Oslo", "age": 12}),
+        ];
+
+        let results = pipeline.execute(docs).unwrap();
+        assert_eq!(
+            results,
+            vec![json!({
+                "byCity": [{"_id": "Oslo", "n": 1}, {"_id": "Rome", "n": 2}],
+                "oldest": [{"age": 45}],
+                "total": [{"n": 3}]
+            })]
+        );
+
+        // Empty input still yields one document, with empty facets
+        let results = pipeline.execute(vec![]).unwrap();
+        assert_eq!(
+            results,
+            vec![json!({"byCity": [], "oldest": [], "total": [{"n": 0}]})]
+        );
+    }
+
+    #[test]
+    fn test_facet_rejects_bad_names_and_pipelines() {
+        for spec in [
+            json!({"$bad": [{"$limit": 1}]}),
+            json!({"": [{"$limit": 1}]}),
+            json!({"ok": {"$limit": 1}}),
+            json!({"ok": []}),
+            json!({}),
+        ] {
+            assert!(
+                Pipeline::from_json(&json!([{"$facet": spec}])).is_err(),
+                "{}",
+                spec
+            );
+        }
+        assert!(Pipeline::from_json(&json!([{"$facet": {"a": [{"$facet": {"b": []}}]}}])).is_err());
+    }
+
+    // ========== LookupStage tests ==========
+
+    struct FakeCollections(HashMap<String, Vec<Value>>);
+
+    struct FakeSource<'a>(&'a [Value]);
+
+    impl CollectionResolver for FakeCollections {
+        fn open(&self, name: &str) -> Result<Option<Box<dyn LookupSource + '_>>> {
+            Ok(self
+                .0
+                .get(name)
+                .map(|docs| Box::new(FakeSource(docs)) as Box<dyn LookupSource>))
+        }
+    }
+
+    impl LookupSource for FakeSource<'_> {
+        fn find(&self, query: &Value) -> Result<Vec<Value>> {
+            MatchStage::from_json(query)?.execute(self.0.to_vec())
+        }
+
+        fn has_index(&self, _field: &str) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_lookup_matches_arrays_and_missing_fields() {
+        let resolver = FakeCollections(HashMap::from([(
+            "tags".to_string(),
+            vec![
+                json!({"_id": 1, "name": "red"}),
+                json!({"_id": 2, "name": "blue", "aliases": ["navy", "sky"]}),
+                json!({"_id": 3}),
+            ],
+        )]));
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {"from": "tags", "localField": "tag", "foreignField": "name", "as": "joined"}}
+        ]))
+        .unwrap();
+        let docs = vec![
+            json!({"tag": ["red", "blue", "red"]}),
+            json!({"tag": "green"}),
+            json!({"other": true}),
+        ];
+
+        let results = pipeline
+            .execute_with(docs, &resolver, &NoCollections)
+            .unwrap();
+        let ids = |doc: &Value| -> Vec<Value> {
+            doc["joined"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d["_id"].clone())
+                .collect()
+        };
+        assert_eq!(ids(&results[0]), vec![json!(1), json!(2)]);
+        assert_eq!(ids(&results[1]), Vec::<Value>::new());
+        // A missing local field matches foreign documents lacking the field
+        assert_eq!(ids(&results[2]), vec![json!(3)]);
+
+        // Foreign arrays match on any element
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {"from": "tags", "localField": "tag", "foreignField": "aliases", "as": "joined"}}
+        ]))
+        .unwrap();
+        let results = pipeline
+            .execute_with(vec![json!({"tag": "sky"})], &resolver, &NoCollections)
+            .unwrap();
+        assert_eq!(ids(&results[0]), vec![json!(2)]);
+    }
+
+    #[test]
+    fn test_lookup_requires_a_database_and_valid_spec() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {"from": "b", "localField": "x", "foreignField": "y", "as": "z"}}
+        ]))
+        .unwrap();
+        assert!(pipeline.reads_other_collections());
+        assert!(pipeline.execute(vec![json!({"x": 1})]).is_err());
+
+        for spec in [
+            json!({"from": "b", "localField": "x", "foreignField": "y"}),
+            json!({"from": "", "localField": "x", "foreignField": "y", "as": "z"}),
+            json!({"from": "b", "localField": "x", "foreignField": "y", "as": "z", "let": {}}),
+            json!("b"),
+            json!({"from": "b", "pipeline": {}, "as": "z"}),
+            json!({"from": "b", "pipeline": [], "let": [], "as": "z"}),
+            json!({"from": "b", "pipeline": [], "let": {"Bad": 1}, "as": "z"}),
+            json!({"from": "b", "pipeline": [], "let": {"a-b": 1}, "as": "z"}),
+            json!({"from": "b", "pipeline": [], "localField": "x", "as": "z"}),
+            json!({"from": "b", "pipeline": [{"$out": "c"}], "as": "z"}),
+            json!({"from": "b", "pipeline": [{"$nope": 1}], "as": "z"}),
+        ] {
+            assert!(LookupStage::from_json(&spec).is_err(), "{}", spec);
+        }
+    }
+
+    fn orders() -> FakeCollections {
+        FakeCollections(HashMap::from([(
+            "orders".to_string(),
+            vec![
+                json!({"_id": 1, "customer": "ann", "total": 5}),
+                json!({"_id": 2, "customer": "ann", "total": 50}),
+                json!({"_id": 3, "customer": "bob", "total": 70}),
+                json!({"_id": 4, "customer": "ann", "total": 90}),
+            ],
+        )]))
+    }
+
+    #[test]
+    fn test_lookup_pipeline_with_let_variables() {
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {
+                "from": "orders",
+                "let": {"name": "$name", "min": "$threshold"},
+                "pipeline": [
+                    {"$match": {"$expr": {"$and": [
+                        {"$eq": ["$customer", "$$name"]},
+                        {"$gte": ["$total", "$$min"]}
+                    ]}}},
+                    {"$project": {"_id": 1, "total": 1, "over": {"$subtract": ["$total", "$$min"]}}},
+                    {"$sort": {"total": -1}}
+                ],
+                "as": "big"
+            }}
+        ]))
+        .unwrap();
+        let docs = vec![
+            json!({"name": "ann", "threshold": 40}),
+            json!({"name": "bob", "threshold": 100}),
+            json!({"name": "ann", "threshold": 40}),
+            json!({"name": "cy", "threshold": 0}),
+        ];
+
+        let results = pipeline
+            .execute_with(docs, &orders(), &NoCollections)
+            .unwrap();
+        let expected = json!([
+            {"_id": 4, "total": 90, "over": 50},
+            {"_id": 2, "total": 50, "over": 10}
+        ]);
+        assert_eq!(results[0]["big"], expected);
+        assert_eq!(results[1]["big"], json!([]));
+        assert_eq!(results[2]["big"], expected);
+        assert_eq!(results[3]["big"], json!([]));
+    }
+
+    #[test]
+    fn test_lookup_pipeline_without_leading_match() {
+        // No let and an empty pipeline: every foreign document
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {"from": "orders", "pipeline": [], "as": "all"}}
+        ]))
+        .unwrap();
+        let results = pipeline
+            .execute_with(vec![json!({"x": 1})], &orders(), &NoCollections)
+            .unwrap();
+        assert_eq!(results[0]["all"].as_array().unwrap().len(), 4);
+
+        // Variables are bound outside $match too; query values stay literal
+        let pipeline = Pipeline::from_json(&json!([
+            {"$lookup": {
+                "from": "orders",
+                "let": {"cap": "$cap"},
+                "pipeline": [
+                    {"$addFields": {
+                        "capped": {"$cond": [{"$gt": ["$total", "$$cap"]}, "$$cap", "$total"]},
+                        "cap": "$$cap"
+                    }},
+                    {"$match": {"customer": "ann", "note": {"$ne": "$$cap"}}},
+                    {"$group": {"_id": "$cap", "sum": {"$sum": "$capped"}}}
+                ],
+                "as": "capped"
+            }}
+        ]))
+        .unwrap();
+        let docs = vec![json!({"cap": 60}), json!({"cap": "$total"})];
+        let results = pipeline
+            .execute_with(docs, &orders(), &NoCollections)
+            .unwrap();
+        assert_eq!(results[0]["capped"], json!([{"_id": 60, "sum": 115}]));
+        // A string that looks like a field reference is still just a value
+        assert_eq!(results[1]["capped"], json!([{"_id": "$total", "sum": 145}]));
+    }
+
+    #[test]
+    fn test_lookup_pipeline_variables_validate() {
+        assert!(Pipeline::validate(&json!([
+            {"$lookup": {
+                "from": "orders",
+                "let": {"cid": "$_id"},
+                "pipeline": [
+                    {"$match": {"$expr": {"$eq": ["$customer", "$$cid"]}}},
+                    {"$project": {"cid": "$$cid"}}
+                ],
+                "as": "orders"
+            }}
+        ]))
+        .is_ok());
+
+        let err = Pipeline::validate(&json!([
+            {"$lookup": {
+                "from": "orders",
+                "let": {"cid": "$$nope"},
+                "pipeline": [{"$project": {"a": "$$cid", "b": "$$other"}}],
+                "as": "orders"
+            }}
+        ]))
+        .unwrap_err();
+        let messages: Vec<&str> = err.issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["undefined variable $$nope", "undefined variable $$other"]
+        );
+    }
+
+    #[test]
+    fn test_match_equalities_for_index_lookups() {
+        let query = json!({"$expr": {"$and": [
+            {"$eq": ["$customer", {"$literal": "ann"}]},
+            {"$eq": [{"$literal": 7}, "$n"]},
+            {"$eq": ["$ratio", {"$literal": 0.5}]},
+            {"$gte": ["$total", {"$literal": 1}]}
+        ]}, "status": "open", "qty": {"$gt": 2}});
+        let mut equalities = match_equalities(&query);
+        equalities.sort_by(|a, b| a.0.cmp(&b.0));
+        // Floats and ranges are left to the $match
+        assert_eq!(
+            equalities,
+            vec![
+                ("customer".to_string(), json!("ann")),
+                ("n".to_string(), json!(7)),
+                ("status".to_string(), json!("open")),
+            ]
+        );
+    }
 }
@@ -0,0 +1,163 @@
+// ironbase-core/src/aggregation_cache.rs
+// Aggregation result caching keyed by collection write-version
+
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// (collection, write-version, canonical pipeline JSON)
+type CacheKey = (String, u64, String);
+
+/// Database-wide cache of aggregation pipeline output
+///
+/// Every write to a collection bumps that collection's write-version, so
+/// entries computed against an older version simply stop matching and age
+/// out of the LRU. Disabled by default (capacity 0): versions are not
+/// tracked and nothing is stored.
+pub struct AggregationCache {
+    cache: Option<Mutex<LruCache<CacheKey, Vec<Value>>>>,
+    versions: Mutex<HashMap<String, u64>>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AggregationCache {
+    /// Create a cache holding up to `capacity` pipeline results (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        AggregationCache {
+            cache: NonZeroUsize::new(capacity).map(|cap| Mutex::new(LruCache::new(cap))),
+            versions: Mutex::new(HashMap::new()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a disabled cache
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    /// Whether results are actually cached
+    pub fn is_enabled(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// Current write-version of `collection`
+    pub fn version(&self, collection: &str) -> u64 {
+        self.versions.lock().get(collection).copied().unwrap_or(0)
+    }
+
+    /// Record a write to `collection`, invalidating its cached results
+    pub fn bump_version(&self, collection: &str) {
+        if self.is_enabled() {
+            *self
+                .versions
+                .lock()
+                .entry(collection.to_string())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Cached output of `pipeline` if it was computed at `version`
+    pub fn get(&self, collection: &str, version: u64, pipeline: &Value) -> Option<Vec<Value>> {
+        let cache = self.cache.as_ref()?;
+        let key = (collection.to_string(), version, canonical(pipeline));
+        match cache.lock().get(&key) {
+            Some(results) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(results.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Store the output of `pipeline` computed at `version` (no-op when disabled)
+    ///
+    /// `version` must be read before the pipeline runs: a write landing
+    /// mid-run then leaves the entry keyed under a version nobody asks for.
+    pub fn insert(&self, collection: &str, version: u64, pipeline: &Value, results: Vec<Value>) {
+        if let Some(cache) = &self.cache {
+            let key = (collection.to_string(), version, canonical(pipeline));
+            cache.lock().put(key, results);
+        }
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> AggregationCacheStats {
+        AggregationCacheStats {
+            capacity: self.capacity,
+            size: self.cache.as_ref().map(|c| c.lock().len()).unwrap_or(0),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for AggregationCache {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Pipeline JSON with object keys in sorted order
+fn canonical(pipeline: &Value) -> String {
+    // serde_json maps are ordered by key, so equal pipelines serialize equally
+    serde_json::to_string(pipeline).unwrap_or_default()
+}
+
+/// Aggregation cache statistics
+#[derive(Debug, Clone)]
+pub struct AggregationCacheStats {
+    pub capacity: usize,
+    pub size: usize,
+    /// Pipelines served from the cache
+    pub hits: u64,
+    /// Pipelines that had to be executed
+    pub misses: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let cache = AggregationCache::disabled();
+        let pipeline = json!([{"$count": "n"}]);
+
+        cache.insert("users", 0, &pipeline, vec![json!({"n": 1})]);
+        cache.bump_version("users");
+        assert_eq!(cache.get("users", 0, &pipeline), None);
+        assert_eq!(cache.version("users"), 0);
+    }
+
+    #[test]
+    fn test_version_bump_misses() {
+        let cache = AggregationCache::new(10);
+        let pipeline = json!([{"$group": {"_id": "$city", "n": {"$sum": 1}}}]);
+        cache.insert("users", 0, &pipeline, vec![json!({"_id": "Rome", "n": 2})]);
+
+        // Key order inside the pipeline does not matter
+        let reordered = json!([{"$group": {"n": {"$sum": 1}, "_id": "$city"}}]);
+        assert!(cache.get("users", 0, &reordered).is_some());
+        assert_eq!(cache.get("orders", 0, &pipeline), None);
+
+        cache.bump_version("users");
+        assert_eq!(cache.version("users"), 1);
+        assert_eq!(cache.version("orders"), 0);
+        assert_eq!(cache.get("users", 1, &pipeline), None);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 2);
+    }
+}
@@ -0,0 +1,244 @@
+// ironbase-core/src/backup.rs
+// Base and incremental backups into a directory, and reading them back
+
+use crate::error::{MongoLiteError, Result};
+use crate::value_utils::canonical_json_string;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// One backup listed in a backup directory's manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Version of this backup within its directory, starting at 1
+    pub version: u64,
+    /// Backup whose state this one builds on (None for a base backup)
+    pub since: Option<u64>,
+    /// Data file, relative to the backup directory
+    pub file: String,
+    /// Documents written to the data file
+    pub documents: u64,
+    /// Documents recorded as deleted since `since`
+    pub deletions: u64,
+    /// Size of the data file
+    pub bytes: u64,
+}
+
+/// Every backup of a directory, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub backups: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    /// Read the manifest of `dir` (empty when there is none yet)
+    pub fn load(dir: &Path) -> Result<Self> {
+        match fs::read(dir.join(MANIFEST_FILE)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        // Write then rename, so a crash never leaves a half-written manifest
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    fn entry(&self, version: u64) -> Result<&BackupEntry> {
+        self.backups
+            .iter()
+            .find(|entry| entry.version == version)
+            .ok_or_else(|| MongoLiteError::Unknown(format!("No backup with version {}", version)))
+    }
+
+    /// Backups to apply, base first, to reach `version`
+    fn chain(&self, version: u64) -> Result<Vec<&BackupEntry>> {
+        let mut chain = vec![self.entry(version)?];
+        while let Some(since) = chain.last().and_then(|entry| entry.since) {
+            chain.push(self.entry(since)?);
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+}
+
+/// One line of a backup data file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum BackupRecord {
+    /// Collection that exists (possibly empty)
+    Create { collection: String },
+    /// Current version of a document
+    Put { collection: String, document: Value },
+    /// Document deleted since the previous backup
+    Delete { collection: String, id: Value },
+    /// Collection dropped since the previous backup
+    Drop { collection: String },
+}
+
+/// Fingerprint of every document at backup time: collection -> `_id` -> fingerprint
+type BackupState = BTreeMap<String, BTreeMap<String, u64>>;
+
+fn state_file(version: u64) -> String {
+    format!("backup-{:06}.state.json", version)
+}
+
+/// Content fingerprint of a document: serialized length and CRC32
+fn fingerprint(doc: &Value) -> u64 {
+    let bytes = canonical_json_string(doc);
+    ((bytes.len() as u64) << 32) | u64::from(crc32fast::hash(bytes.as_bytes()))
+}
+
+fn id_key(doc: &Value) -> String {
+    canonical_json_string(doc.get("_id").unwrap_or(&Value::Null))
+}
+
+/// Write a backup of `collections` into `dir`
+///
+/// Without `since` every document is written; otherwise only documents
+/// added or changed since backup `since`, plus the deletions and dropped
+/// collections, are.
+pub(crate) fn write_backup(
+    dir: &Path,
+    collections: Vec<(String, Vec<Value>)>,
+    since: Option<u64>,
+) -> Result<BackupEntry> {
+    fs::create_dir_all(dir)?;
+    let mut manifest = BackupManifest::load(dir)?;
+    let previous: BackupState = match since {
+        Some(version) => {
+            manifest.entry(version)?;
+            serde_json::from_slice(&fs::read(dir.join(state_file(version)))?)?
+        }
+        None => BackupState::new(),
+    };
+
+    let version = manifest
+        .backups
+        .iter()
+        .map(|e| e.version)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let file = format!("backup-{:06}.jsonl", version);
+    let mut writer = BufWriter::new(fs::File::create(dir.join(&file))?);
+    let mut write = |record: &BackupRecord| -> Result<()> {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    };
+
+    let mut state = BackupState::new();
+    let (mut documents, mut deletions) = (0, 0);
+    for (collection, docs) in collections {
+        let old = previous.get(&collection);
+        if old.is_none() {
+            write(&BackupRecord::Create {
+                collection: collection.clone(),
+            })?;
+        }
+
+        let mut current = BTreeMap::new();
+        for document in docs {
+            let key = id_key(&document);
+            let print = fingerprint(&document);
+            if old.and_then(|old| old.get(&key)) != Some(&print) {
+                write(&BackupRecord::Put {
+                    collection: collection.clone(),
+                    document,
+                })?;
+                documents += 1;
+            }
+            current.insert(key, print);
+        }
+
+        for key in old.into_iter().flat_map(|old| old.keys()) {
+            if !current.contains_key(key) {
+                write(&BackupRecord::Delete {
+                    collection: collection.clone(),
+                    id: serde_json::from_str(key)?,
+                })?;
+                deletions += 1;
+            }
+        }
+        state.insert(collection, current);
+    }
+    for collection in previous.keys().filter(|name| !state.contains_key(*name)) {
+        write(&BackupRecord::Drop {
+            collection: collection.clone(),
+        })?;
+    }
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+
+    fs::write(dir.join(state_file(version)), serde_json::to_vec(&state)?)?;
+    let entry = BackupEntry {
+        version,
+        since,
+        bytes: fs::metadata(dir.join(&file))?.len(),
+        file,
+        documents,
+        deletions,
+    };
+    manifest.backups.push(entry.clone());
+    manifest.save(dir)?;
+    Ok(entry)
+}
+
+/// Rebuild the collections of the newest backup in `dir`
+///
+/// Returns its version and every collection's documents.
+pub(crate) fn read_backup(dir: &Path) -> Result<(u64, BTreeMap<String, Vec<Value>>)> {
+    let manifest = BackupManifest::load(dir)?;
+    let latest = manifest
+        .backups
+        .iter()
+        .map(|e| e.version)
+        .max()
+        .ok_or_else(|| MongoLiteError::Unknown(format!("No backups in {}", dir.display())))?;
+
+    let mut collections: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    for entry in manifest.chain(latest)? {
+        let reader = BufReader::new(fs::File::open(dir.join(&entry.file))?);
+        for line in reader.lines() {
+            match serde_json::from_str(&line?)? {
+                BackupRecord::Create { collection } => {
+                    collections.entry(collection).or_default();
+                }
+                BackupRecord::Put {
+                    collection,
+                    document,
+                } => {
+                    collections
+                        .entry(collection)
+                        .or_default()
+                        .insert(id_key(&document), document);
+                }
+                BackupRecord::Delete { collection, id } => {
+                    if let Some(docs) = collections.get_mut(&collection) {
+                        docs.remove(&canonical_json_string(&id));
+                    }
+                }
+                BackupRecord::Drop { collection } => {
+                    collections.remove(&collection);
+                }
+            }
+        }
+    }
+
+    let collections = collections
+        .into_iter()
+        .map(|(name, docs)| (name, docs.into_values().collect()))
+        .collect();
+    Ok((latest, collections))
+}
@@ -0,0 +1,176 @@
+// ironbase-core/src/bloom.rs
+// Bloom filter for short-circuiting index lookups of absent keys
+
+use crate::index::IndexKey;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Bits per expected key (~1% false positive rate with 7 hash functions)
+const BITS_PER_KEY: usize = 10;
+const NUM_HASHES: u64 = 7;
+/// Smallest filter built, so small indexes can grow without a rebuild per insert
+const MIN_CAPACITY: usize = 1024;
+
+/// Probabilistic set of index keys
+///
+/// `may_contain` never returns false for a key that was inserted; it returns
+/// true for an absent key with a small probability. Keys cannot be removed,
+/// so deleted keys only cost an extra tree lookup until the next rebuild.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    capacity: usize,
+    len: usize,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for `expected_keys` keys
+    pub fn with_capacity(expected_keys: usize) -> Self {
+        let capacity = expected_keys.max(MIN_CAPACITY);
+        let words = (capacity * BITS_PER_KEY).div_ceil(64);
+        BloomFilter {
+            bits: vec![0; words],
+            num_bits: (words * 64) as u64,
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Add a key to the filter
+    pub fn insert(&mut self, key: &IndexKey) {
+        let (h1, h2) = key_hashes(key);
+        for i in 0..NUM_HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Whether the key may have been inserted (false means definitely absent)
+    pub fn may_contain(&self, key: &IndexKey) -> bool {
+        let (h1, h2) = key_hashes(key);
+        (0..NUM_HASHES).all(|i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    /// Whether more keys were inserted than the filter was sized for
+    ///
+    /// A saturated filter is still correct, but its false positive rate
+    /// climbs; the owner should rebuild it with a larger capacity.
+    pub fn is_saturated(&self) -> bool {
+        self.len > self.capacity
+    }
+
+    /// Number of keys inserted
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no keys were inserted
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Two independent hashes for double hashing (h1 + i*h2)
+fn key_hashes(key: &IndexKey) -> (u64, u64) {
+    let mut first = DefaultHasher::new();
+    hash_key(key, &mut first);
+    let h1 = first.finish();
+
+    let mut second = DefaultHasher::new();
+    second.write_u64(0x9e37_79b9_7f4a_7c15);
+    hash_key(key, &mut second);
+    // Odd step so every probe lands on a different bit
+    let h2 = second.finish() | 1;
+
+    (h1, h2)
+}
+
+/// Feed a key into a hasher (IndexKey has no `Hash` impl because of floats)
+fn hash_key(key: &IndexKey, hasher: &mut DefaultHasher) {
+    match key {
+        IndexKey::Null => hasher.write_u8(0),
+        IndexKey::Bool(b) => {
+            hasher.write_u8(1);
+            hasher.write_u8(*b as u8);
+        }
+        IndexKey::Int(i) => {
+            hasher.write_u8(2);
+            hasher.write_i64(*i);
+        }
+        IndexKey::Float(f) => {
+            // Matches OrderedFloat equality, which compares bit patterns
+            hasher.write_u8(3);
+            hasher.write_u64(f.0.to_bits());
+        }
+        IndexKey::String(s) => {
+            hasher.write_u8(4);
+            hasher.write(s.as_bytes());
+            hasher.write_u8(0xff);
+        }
+        IndexKey::Compound(keys) => {
+            hasher.write_u8(5);
+            hasher.write_usize(keys.len());
+            for key in keys {
+                hash_key(key, hasher);
+            }
+        }
+        IndexKey::Decimal(d) => {
+            hasher.write_u8(6);
+            d.hash(hasher);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::OrderedFloat;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let mut filter = BloomFilter::with_capacity(5000);
+        let keys: Vec<IndexKey> = (0..5000)
+            .map(|i| match i % 4 {
+                0 => IndexKey::Int(i),
+                1 => IndexKey::String(format!("user-{}", i)),
+                2 => IndexKey::Float(OrderedFloat(i as f64 + 0.5)),
+                _ => IndexKey::Compound(vec![IndexKey::Int(i), IndexKey::Bool(true)]),
+            })
+            .collect();
+
+        for key in &keys {
+            filter.insert(key);
+        }
+        assert_eq!(filter.len(), 5000);
+        assert!(keys.iter().all(|key| filter.may_contain(key)));
+    }
+
+    #[test]
+    fn test_absent_keys_mostly_rejected() {
+        let mut filter = BloomFilter::with_capacity(2000);
+        for i in 0..2000 {
+            filter.insert(&IndexKey::Int(i));
+        }
+
+        let false_positives = (10_000..20_000)
+            .filter(|i| filter.may_contain(&IndexKey::Int(*i)))
+            .count();
+        // ~1% expected; allow generous slack
+        assert!(false_positives < 500, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut filter = BloomFilter::with_capacity(0);
+        assert!(filter.is_empty());
+        for i in 0..=MIN_CAPACITY as i64 {
+            filter.insert(&IndexKey::Int(i));
+        }
+        assert!(filter.is_saturated());
+    }
+}
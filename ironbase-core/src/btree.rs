@@ -56,6 +56,8 @@ impl BPlusTreeFull {
                 num_keys: 0,
                 tree_height: 1,
                 root_offset: 0,
+                bloom_filter: false,
+                expire_after_secs: None,
             },
         }
     }
@@ -110,10 +112,10 @@ impl BPlusTreeFull {
             }
             (left_node, Some(split)) => {
                 // Root split - create new root
-                self.root = Box::new(Node::Internal {
+                *self.root = Node::Internal {
                     keys: vec![split.key],
                     children: vec![left_node, split.right],
-                });
+                };
                 self.metadata.tree_height += 1;
             }
         }
@@ -0,0 +1,42 @@
+// ironbase-core/src/bulk_write.rs
+// Mixed batches of write operations applied in one pass
+
+use crate::document::DocumentId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One operation of a `DatabaseCore::bulk_write` batch
+///
+/// Deserializes from the MongoDB wire form, e.g.
+/// `{"insertOne": {"document": {...}}}` or
+/// `{"updateMany": {"filter": {...}, "update": {"$set": {...}}}}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkOp {
+    InsertOne { document: HashMap<String, Value> },
+    UpdateOne { filter: Value, update: Value },
+    UpdateMany { filter: Value, update: Value },
+    DeleteOne { filter: Value },
+    DeleteMany { filter: Value },
+}
+
+/// An operation of a batch that failed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BulkWriteError {
+    /// Position of the operation in the batch
+    pub index: usize,
+    pub message: String,
+}
+
+/// Combined outcome of a `bulk_write` batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BulkWriteResult {
+    pub inserted_ids: Vec<DocumentId>,
+    pub inserted_count: u64,
+    pub matched_count: u64,
+    pub modified_count: u64,
+    pub deleted_count: u64,
+    /// Failed operations, in batch order; an ordered batch has at most one
+    pub write_errors: Vec<BulkWriteError>,
+}
@@ -65,17 +65,21 @@ where
 }
 
 /// Try to load an index from .idx file (graceful degradation)
-/// Returns None if file doesn't exist or is corrupted (fallback to rebuild)
+/// Returns Ok(None) if the file doesn't exist, and an error if it is corrupted
+/// or written in an older/unknown format version (caller rebuilds from the catalog)
 pub fn try_load_index_from_file(
     db_file_path: &str,
     index_meta: &IndexMetadata,
-) -> Option<BPlusTree> {
-    let idx_path = build_index_file_path(db_file_path, &index_meta.name)?;
+) -> Result<Option<BPlusTree>> {
+    let idx_path = match build_index_file_path(db_file_path, &index_meta.name) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
 
     if !idx_path.exists() {
-        return None;
+        return Ok(None);
     }
 
-    let mut file = File::open(&idx_path).ok()?;
-    BPlusTree::load_from_file(&mut file, index_meta.clone()).ok()
+    let mut file = File::open(&idx_path)?;
+    BPlusTree::load_from_file(&mut file, index_meta.clone()).map(Some)
 }
@@ -3,19 +3,28 @@
 
 use std::sync::Arc;
 
-use parking_lot::RwLock;
+use parking_lot::{RwLock, RwLockWriteGuard};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use crate::aggregation::{CollectionResolver, CollectionWriter, LookupSource};
+use crate::aggregation_cache::AggregationCache;
+use crate::bulk_write::BulkOp;
 use crate::document::{Document, DocumentId};
 use crate::error::{MongoLiteError, Result};
-use crate::index::{IndexKey, IndexManager};
+use crate::field_interning::{FieldInterner, FieldInterningStats, InternedValue};
+use crate::find_options::{parse_array_sizes, SizedDocument};
+use crate::index::{index_keys_for_field, BPlusTree, IndexInfo, IndexKey, IndexManager};
+use crate::index_advisor::IndexSuggestion;
+use crate::metrics::Metrics;
 use crate::query::Query;
 use crate::query_cache::{QueryCache, QueryHash};
 use crate::query_planner::{QueryPlan, QueryPlanner};
-use crate::storage::{RawStorage, Storage};
-use crate::value_utils::get_nested_value;
-use crate::{log_debug, log_trace, log_warn};
+use crate::read_cache::ReadCache;
+use crate::storage::{CappedLimits, RawStorage, Storage};
+use crate::text_index::{parse_text_filter, TextIndexMetadata, TextSearchMode, Tokenizer};
+use crate::value_utils::{canonical_json_string, compare_for_sort, get_nested_value};
+use crate::{log_debug, log_error, log_trace, log_warn};
 
 mod index_persistence;
 mod raw_operations;
@@ -38,18 +47,8 @@ pub struct InsertManyResult {
 /// Single Responsibility: Transform user options into execution strategy
 #[derive(Debug)]
 struct QueryExecutionContext {
-    /// Single-field sort optimization info
-    sort_field: Option<String>,
-    sort_descending: bool,
-
-    /// Whether to defer limit/skip until after sorting
-    apply_limit_after_sort: bool,
-
-    /// Pre-fetch parameters (0/None if deferred)
-    fetch_skip: usize,
-    fetch_limit: Option<usize>,
-
-    /// Original user options for post-processing
+    /// Original user options; with a sort they are pushed down only if an
+    /// index serves every sort key
     original_skip: usize,
     original_limit: Option<usize>,
 
@@ -63,54 +62,40 @@ struct QueryExecutionContext {
 impl QueryExecutionContext {
     /// Build execution context from FindOptions
     fn from_options(options: &crate::find_options::FindOptions) -> Self {
-        let original_skip = options.skip.unwrap_or(0);
-        let original_limit = options.limit;
-
-        // Extract single-field sort for index optimization
-        let (sort_field, sort_descending) = options
-            .sort
-            .as_ref()
-            .filter(|s| s.len() == 1)
-            .map(|s| (Some(s[0].0.clone()), s[0].1 < 0))
-            .unwrap_or((None, false));
-
-        // Determine fetch strategy
-        let apply_limit_after_sort = options.sort.is_some();
-        let (fetch_skip, fetch_limit) = if apply_limit_after_sort {
-            (0, None)
-        } else {
-            (original_skip, original_limit)
-        };
-
         Self {
-            sort_field,
-            sort_descending,
-            apply_limit_after_sort,
-            fetch_skip,
-            fetch_limit,
-            original_skip,
-            original_limit,
+            original_skip: options.skip.unwrap_or(0),
+            original_limit: options.limit,
             sort_spec: options.sort.clone(),
             projection: options.projection.clone(),
         }
     }
 
-    /// Get sort field reference (for collect_doc_ids_with_options)
-    fn sort_field_ref(&self) -> Option<&str> {
-        self.sort_field.as_deref()
+    /// Get sort specification reference (for collect_doc_ids_with_options)
+    fn sort_spec_ref(&self) -> Option<&[(String, i32)]> {
+        self.sort_spec.as_deref()
     }
 
-    /// Determine if in-memory sort is needed (when index didn't sort for us)
-    fn needs_memory_sort(&self, index_sorted: bool) -> bool {
+    /// Determine if in-memory sort is needed (when the index order covers
+    /// only the first `sorted_keys` sort keys, or none)
+    fn needs_memory_sort(&self, sorted_keys: usize) -> bool {
         match &self.sort_spec {
-            Some(sort) => !(index_sorted && sort.len() == 1),
+            Some(sort) => sorted_keys < sort.len(),
             None => false,
         }
     }
 
+    /// Sort in memory whatever the index order left unsorted
+    fn apply_memory_sort(&self, docs: &mut [Value], sorted_keys: usize) {
+        if let Some(ref sort_spec) = self.sort_spec {
+            crate::find_options::apply_sort_after_prefix(docs, sort_spec, sorted_keys);
+        }
+    }
+
     /// Apply pagination after sorting (returns owned docs)
-    fn apply_post_sort_pagination(&self, docs: Vec<Value>) -> Vec<Value> {
-        if self.apply_limit_after_sort {
+    ///
+    /// A fully index-served sort already collected just the window.
+    fn apply_post_sort_pagination(&self, docs: Vec<Value>, sorted_keys: usize) -> Vec<Value> {
+        if self.needs_memory_sort(sorted_keys) {
             crate::find_options::apply_limit_skip(
                 docs,
                 self.original_limit,
@@ -133,6 +118,28 @@ impl QueryExecutionContext {
     }
 }
 
+/// Index key a transaction records for `index`, reading fields through `get`
+///
+/// Top-level fields only. None when a single-field index's field is absent;
+/// compound keys always exist, with `Null` for missing components.
+fn tx_index_key<'a>(
+    index: &BPlusTree,
+    get: impl Fn(&str) -> Option<&'a Value>,
+) -> Option<crate::transaction::IndexKey> {
+    use crate::transaction::IndexKey as TxKey;
+    if index.metadata.is_compound() {
+        let keys = index
+            .metadata
+            .fields
+            .iter()
+            .map(|field| get(field).map(TxKey::from).unwrap_or(TxKey::Null))
+            .collect();
+        Some(TxKey::Compound(keys))
+    } else {
+        get(&index.metadata.field).map(TxKey::from)
+    }
+}
+
 /// Pure Rust Collection - language-independent core logic
 ///
 /// Generic over Storage backend:
@@ -147,7 +154,104 @@ pub struct CollectionCore<S: Storage + RawStorage> {
     pub indexes: Arc<RwLock<IndexManager>>,
     /// Query result cache with LRU eviction (capacity: 1000 queries)
     pub query_cache: Arc<QueryCache>,
+    /// Decoded document cache for id lookups (disabled unless `with_read_cache` is used)
+    pub read_cache: Arc<ReadCache>,
+    /// Field-name pool for `scan_interned` (disabled unless `with_field_interning` is used)
+    field_interner: Arc<FieldInterner>,
     schema: Arc<RwLock<Option<CompiledSchema>>>,
+    /// Full scans examining more documents than this are logged (None = off)
+    slow_query_threshold: Option<usize>,
+    /// Cross-check index-driven results against a full scan
+    verify_reads: bool,
+    /// Operation counters (shared with the owning DatabaseCore)
+    metrics: Arc<Metrics>,
+    /// Pipeline results by collection write-version (shared with the owning DatabaseCore)
+    aggregation_cache: Arc<AggregationCache>,
+}
+
+/// `$lookup` opens sibling collections through the same storage
+impl<S: Storage + RawStorage> CollectionResolver for CollectionCore<S> {
+    fn open(&self, name: &str) -> Result<Option<Box<dyn LookupSource + '_>>> {
+        if self.storage.read().get_collection_meta(name).is_none() {
+            return Ok(None);
+        }
+        let collection = CollectionCore::new(name.to_string(), Arc::clone(&self.storage))?
+            .with_metrics(Arc::clone(&self.metrics))
+            .with_aggregation_cache(Arc::clone(&self.aggregation_cache))
+            .with_verify_reads(self.verify_reads);
+        Ok(Some(Box::new(collection)))
+    }
+}
+
+/// `$out` replaces sibling collections through the same storage
+///
+/// Like the other `CollectionCore` writes this bypasses the WAL; see
+/// `DatabaseCore::aggregate` for a logged `$out`.
+impl<S: Storage + RawStorage> CollectionWriter for CollectionCore<S> {
+    fn replace_collection(&self, name: &str, docs: Vec<Value>) -> Result<()> {
+        let ops = out_insert_ops(docs)?;
+
+        {
+            let mut storage = self.storage.write();
+            if storage.get_collection_meta(name).is_some() {
+                storage.drop_collection(name)?;
+            }
+        }
+        self.query_cache.invalidate_collection(name);
+        self.aggregation_cache.bump_version(name);
+
+        let target = CollectionCore::new(name.to_string(), Arc::clone(&self.storage))?
+            .with_metrics(Arc::clone(&self.metrics))
+            .with_aggregation_cache(Arc::clone(&self.aggregation_cache))
+            .with_verify_reads(self.verify_reads);
+        match target.bulk_write_raw(&ops, true)?.first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Inserts writing `$out` results into a fresh collection
+///
+/// Fails on a malformed or repeated `_id` (an `$unwind` keeps the `_id` of
+/// the unwound document), so the target is only dropped once the whole
+/// output is known to insert.
+pub(crate) fn out_insert_ops(docs: Vec<Value>) -> Result<Vec<BulkOp>> {
+    let mut ids = HashSet::new();
+    let mut ops = Vec::with_capacity(docs.len());
+    // OutStage has already rejected anything that is not a document
+    for doc in docs {
+        let Value::Object(fields) = doc else {
+            continue;
+        };
+        if let Some(id) = fields.get("_id") {
+            let parsed: DocumentId = serde_json::from_value(id.clone())
+                .map_err(|e| MongoLiteError::Serialization(format!("Invalid _id format: {}", e)))?;
+            if !ids.insert(parsed) {
+                return Err(MongoLiteError::DuplicateKey(format!(
+                    "$out output has more than one document with _id {}",
+                    id
+                )));
+            }
+        }
+        ops.push(BulkOp::InsertOne {
+            document: fields.into_iter().collect(),
+        });
+    }
+    Ok(ops)
+}
+
+impl<S: Storage + RawStorage> LookupSource for CollectionCore<S> {
+    fn find(&self, query: &Value) -> Result<Vec<Value>> {
+        self.find_reading_once(query)
+    }
+
+    fn has_index(&self, field: &str) -> bool {
+        // A sparse index would miss the null lookups of documents lacking the field
+        self.index_infos()
+            .iter()
+            .any(|info| info.fields == [field] && !info.sparse)
+    }
 }
 
 impl<S: Storage + RawStorage> CollectionCore<S> {
@@ -163,26 +267,36 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             }
         }
 
-        // Initialize index manager with automatic _id index
-        let mut index_manager = IndexManager::new();
-
-        // Create automatic _id index (unique)
-        let id_index_name = format!("{}_id", name);
-        index_manager.create_btree_index(
-            id_index_name.clone(),
-            "_id".to_string(),
-            true, // unique
-        )?;
-
         // PERSISTENCE FIX: Load persisted indexes and rebuild from document catalog
-        let schema_definition = {
+        let (schema_definition, skip_id_index, text_index) = {
             let storage_guard = storage.write();
             let meta = storage_guard
                 .get_collection_meta(&name)
                 .ok_or_else(|| MongoLiteError::CollectionNotFound(name.clone()))?;
-            meta.schema.clone()
+            (
+                meta.schema.clone(),
+                meta.skip_id_index,
+                meta.text_index.clone(),
+            )
         };
 
+        // Initialize index manager with automatic _id index
+        let mut index_manager = IndexManager::new();
+
+        // Create automatic _id index (unique), unless the collection opted out
+        let id_index_name = format!("{}_id", name);
+        if !skip_id_index {
+            index_manager.create_btree_index(
+                id_index_name.clone(),
+                "_id".to_string(),
+                true, // unique
+            )?;
+        }
+        // Text index postings are filled by the rebuild below
+        if let Some(text_index) = text_index {
+            index_manager.create_text_index(text_index)?;
+        }
+
         {
             let storage_guard = storage.write();
             let meta = storage_guard
@@ -205,6 +319,9 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
             drop(storage_guard); // Release write lock before rebuilding
 
+            // Indexes whose .idx file could not be used and must be rewritten
+            let mut stale_index_files = Vec::new();
+
             // Load persisted custom indexes (if any)
             for index_meta in &persisted_indexes {
                 // Skip _id index (already created)
@@ -214,28 +331,54 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
                 // Try to load from .idx file first (for index structure/metadata)
                 // NOTE: We still rebuild from documents below to ensure consistency
-                if let Some(loaded_tree) = try_load_index_from_file(&db_path, index_meta) {
-                    log_debug!(
-                        "Loaded index '{}' from .idx file (will rebuild from documents)",
-                        index_meta.name
-                    );
-                    index_manager.add_loaded_index(loaded_tree);
-                    // Index loaded, but we still rebuild from documents below
+                match try_load_index_from_file(&db_path, index_meta) {
+                    Ok(Some(loaded_tree)) => {
+                        log_debug!(
+                            "Loaded index '{}' from .idx file (will rebuild from documents)",
+                            index_meta.name
+                        );
+                        index_manager.add_loaded_index(loaded_tree);
+                        // Index loaded, but we still rebuild from documents below
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        // Old/unknown format or corrupt file: rebuild and rewrite it
+                        log_warn!(
+                            "Discarding .idx file for index '{}' ({}), rebuilding from catalog",
+                            index_meta.name,
+                            e
+                        );
+                        stale_index_files.push(index_meta.name.clone());
+                    }
+                }
+
+                // Fallback: create empty index (will be rebuilt from documents)
+                log_debug!(
+                    "Creating index '{}' on field '{}' (will rebuild from documents)",
+                    index_meta.name,
+                    index_meta.field
+                );
+
+                // Create index
+                if index_meta.is_compound() {
+                    index_manager.create_compound_index(
+                        index_meta.name.clone(),
+                        index_meta.fields.clone(),
+                        index_meta.unique,
+                    )?;
                 } else {
-                    // Fallback: create empty index (will be rebuilt from documents)
-                    log_debug!(
-                        "Creating index '{}' on field '{}' (will rebuild from documents)",
-                        index_meta.name,
-                        index_meta.field
-                    );
-
-                    // Create index
                     index_manager.create_btree_index(
                         index_meta.name.clone(),
                         index_meta.field.clone(),
                         index_meta.unique,
                     )?;
                 }
+                if index_meta.bloom_filter {
+                    if let Some(index) = index_manager.get_btree_index_mut(&index_meta.name) {
+                        index.enable_bloom_filter();
+                    }
+                }
             }
 
             // Rebuild all indexes from document catalog (always rebuild to ensure consistency)
@@ -273,6 +416,13 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                                             let _ = id_index.insert(index_key, doc_id.clone());
                                         }
 
+                                        // Rebuild the text index postings
+                                        if let Some(text_index) = index_manager.text_index_mut() {
+                                            if let Ok(document) = Document::from_value(&doc) {
+                                                text_index.insert(&document);
+                                            }
+                                        }
+
                                         // Rebuild ALL custom indexes (always rebuild to ensure correctness)
                                         for index_meta in &persisted_indexes {
                                             if index_meta.name == id_index_name {
@@ -282,14 +432,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                                             // The .idx file is only used as a fast path for initial loading,
                                             // but we still rebuild to catch any entries added after initial creation
 
-                                            // Use get_nested_value for dot notation support
-                                            if let Some(field_value) =
-                                                get_nested_value(&doc, &index_meta.field)
+                                            // Dot notation, including paths through arrays
+                                            if let Some(index) =
+                                                index_manager.get_btree_index_mut(&index_meta.name)
                                             {
-                                                let key = IndexKey::from(field_value);
-                                                if let Some(index) = index_manager
-                                                    .get_btree_index_mut(&index_meta.name)
-                                                {
+                                                for key in index.keys_for_value(&doc) {
                                                     let _ = index.insert(key, doc_id.clone());
                                                     rebuilt_count += 1;
                                                 }
@@ -320,6 +467,18 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 "Index rebuild completed - {} index entries rebuilt",
                 rebuilt_count
             );
+            drop(storage_guard);
+
+            // Migrate discarded .idx files to the current format
+            for index_name in &stale_index_files {
+                if let Some(index) = index_manager.get_btree_index_mut(index_name) {
+                    if let Err(e) =
+                        persist_index_to_disk(&db_path, index_name, |file| index.save_to_file(file))
+                    {
+                        log_warn!("Failed to rewrite .idx file for '{}': {}", index_name, e);
+                    }
+                }
+            }
         }
 
         let compiled_schema = if let Some(raw_schema) = schema_definition {
@@ -333,10 +492,71 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             storage,
             indexes: Arc::new(RwLock::new(index_manager)),
             query_cache: Arc::new(QueryCache::new(1000)), // LRU cache with 1000 query capacity
+            read_cache: Arc::new(ReadCache::disabled()),
+            field_interner: Arc::new(FieldInterner::disabled()),
             schema: Arc::new(RwLock::new(compiled_schema)),
+            slow_query_threshold: None,
+            verify_reads: false,
+            metrics: Arc::new(Metrics::default()),
+            aggregation_cache: Arc::new(AggregationCache::disabled()),
         })
     }
 
+    /// Report operations into `metrics` instead of a private counter set
+    pub(crate) fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Serve repeated aggregations from `cache`, shared across handles
+    pub(crate) fn with_aggregation_cache(mut self, cache: Arc<AggregationCache>) -> Self {
+        self.aggregation_cache = cache;
+        self
+    }
+
+    /// Enable the document read cache with room for `capacity` documents
+    ///
+    /// A capacity of 0 keeps caching disabled.
+    pub fn with_read_cache(mut self, capacity: usize) -> Self {
+        self.read_cache = Arc::new(ReadCache::new(capacity));
+        self
+    }
+
+    /// Share field names between documents decoded by `scan_interned`
+    ///
+    /// Up to `max_names` distinct names are pooled; 0 keeps interning disabled.
+    pub fn with_field_interning(mut self, max_names: usize) -> Self {
+        self.field_interner = Arc::new(FieldInterner::new(max_names));
+        self
+    }
+
+    /// Decode `scan_interned` results through `interner`, shared across handles
+    pub(crate) fn with_field_interner(mut self, interner: Arc<FieldInterner>) -> Self {
+        self.field_interner = interner;
+        self
+    }
+
+    /// Enable the slow query log
+    ///
+    /// A filtered query that falls back to a full collection scan and
+    /// examines more than `threshold` documents emits a warning suggesting
+    /// an index on the filtered fields.
+    pub fn with_slow_query_threshold(mut self, threshold: usize) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    /// Enable shadow reads
+    ///
+    /// Every query answered through an index is re-run as a full scan and
+    /// any divergence is logged as an error listing the ids involved (test
+    /// builds panic). This makes indexed queries as slow as scans; it is
+    /// meant for hunting index maintenance bugs.
+    pub fn with_verify_reads(mut self, enabled: bool) -> Self {
+        self.verify_reads = enabled;
+        self
+    }
+
     fn compile_schema(schema: &Value) -> Result<CompiledSchema> {
         CompiledSchema::from_value(schema)
     }
@@ -385,15 +605,152 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             .and_then(|meta| meta.schema.clone())
     }
 
+    /// Set or clear the maximum number of documents this collection may hold
+    ///
+    /// Inserts that would take the live document count above the quota fail
+    /// with `MongoLiteError::QuotaExceeded`. Lowering the quota below the
+    /// current count removes nothing; it only blocks further inserts.
+    pub fn set_max_documents(&self, max_documents: Option<u64>) -> Result<()> {
+        let mut storage = self.storage.write();
+        let meta = storage
+            .get_collection_meta_mut(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        meta.max_documents = max_documents;
+        storage.flush()
+    }
+
+    /// Get the document quota of this collection (if any)
+    pub fn max_documents(&self) -> Option<u64> {
+        let storage = self.storage.read();
+        storage
+            .get_collection_meta(&self.name)
+            .and_then(|meta| meta.max_documents)
+    }
+
+    /// Maintain the automatic unique `_id` index (the default) or not
+    ///
+    /// Without it inserts skip the index update, which suits append-only
+    /// collections that are never looked up by id. `_id` lookups and the
+    /// rejection of duplicate explicit `_id` values go through the document
+    /// catalog instead. Other handles on the collection pick the setting up
+    /// when they are next obtained.
+    pub fn set_id_index(&self, enabled: bool) -> Result<()> {
+        let id_index_name = format!("{}_id", self.name);
+        let has_index = self
+            .indexes
+            .read()
+            .get_btree_index(&id_index_name)
+            .is_some();
+        if enabled && !has_index {
+            let docs = self.scan_documents_via_catalog()?;
+            let mut indexes = self.indexes.write();
+            indexes.create_btree_index(id_index_name.clone(), "_id".to_string(), true)?;
+            let id_index = indexes.get_btree_index_mut(&id_index_name).unwrap();
+            // The catalog is keyed by _id, so these never collide
+            for doc_id in docs.into_keys() {
+                let id_key = match &doc_id {
+                    DocumentId::Int(i) => IndexKey::Int(*i),
+                    DocumentId::String(s) => IndexKey::String(s.clone()),
+                    DocumentId::ObjectId(oid) => IndexKey::String(oid.clone()),
+                };
+                id_index.insert(id_key, doc_id)?;
+            }
+        } else if !enabled && has_index {
+            self.indexes.write().drop_index(&id_index_name)?;
+        }
+
+        let mut storage = self.storage.write();
+        let meta = storage
+            .get_collection_meta_mut(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        meta.skip_id_index = !enabled;
+        storage.flush()
+    }
+
+    /// Whether the automatic `_id` index is maintained
+    pub fn has_id_index(&self) -> bool {
+        let storage = self.storage.read();
+        storage
+            .get_collection_meta(&self.name)
+            .is_some_and(|meta| !meta.skip_id_index)
+    }
+
+    /// Turn the collection into a capped collection, or back (None)
+    ///
+    /// Inserts into a capped collection evict its oldest documents (see
+    /// `CappedLimits`) instead of failing, and deletes by query are
+    /// rejected. Documents already over the limits are evicted by the next
+    /// insert. `max_bytes` makes every insert scan the collection's
+    /// documents to total their size.
+    pub fn set_capped(&self, limits: Option<CappedLimits>) -> Result<()> {
+        if let Some(limits) = limits {
+            let positive = |limit: Option<u64>| limit.is_some_and(|n| n > 0);
+            if !positive(limits.max_documents) && !positive(limits.max_bytes) {
+                return Err(MongoLiteError::InvalidQuery(
+                    "capped collection needs a positive max_documents or max_bytes".to_string(),
+                ));
+            }
+        }
+
+        let mut storage = self.storage.write();
+        let meta = storage
+            .get_collection_meta_mut(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        meta.capped = limits;
+        storage.flush()
+    }
+
+    /// Get the capped-collection limits (None for a regular collection)
+    pub fn capped(&self) -> Option<CappedLimits> {
+        let storage = self.storage.read();
+        storage
+            .get_collection_meta(&self.name)
+            .and_then(|meta| meta.capped)
+    }
+
+    /// Reject deletes by query on a capped collection
+    fn ensure_not_capped(&self, storage: &S) -> Result<()> {
+        let capped = storage
+            .get_collection_meta(&self.name)
+            .is_some_and(|meta| meta.capped.is_some());
+        if capped {
+            return Err(MongoLiteError::InvalidQuery(format!(
+                "cannot delete from capped collection '{}'",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail if inserting `adding` documents would exceed the collection's quota
+    fn check_quota(&self, storage: &S, adding: u64) -> Result<()> {
+        let Some(max) = storage
+            .get_collection_meta(&self.name)
+            .and_then(|meta| meta.max_documents)
+        else {
+            return Ok(());
+        };
+        let live = storage.get_live_count(&self.name).unwrap_or(0);
+        if live.saturating_add(adding) > max {
+            return Err(MongoLiteError::QuotaExceeded(format!(
+                "collection '{}' holds {} of at most {} documents; cannot insert {} more",
+                self.name, live, max, adding
+            )));
+        }
+        Ok(())
+    }
+
     // ========== QUERY OPERATIONS ==========
 
     /// Find documents matching query
     pub fn find(&self, query_json: &Value) -> Result<Vec<Value>> {
         log_debug!("find() called with query: {:?}", query_json);
+        Metrics::incr(&self.metrics.queries);
 
         // 🚀 OPTIMIZED: find({}) special case - return all docs directly
         // Avoids ID collection + re-read cycle - significant speedup for full scans
         if query_json.as_object().is_some_and(|o| o.is_empty()) {
+            Metrics::incr(&self.metrics.collection_scans);
             let docs_by_id = self.scan_documents_via_catalog()?;
             return Ok(docs_by_id.into_values().collect());
         }
@@ -408,6 +765,128 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(results)
     }
 
+    /// `find` returning each match as it was read to check the query
+    ///
+    /// `find` collects matching ids and then reads those documents again;
+    /// aggregation input and `$lookup` (a query per joined value) use this
+    /// instead to read every candidate once. With `verify_reads` on it is
+    /// plain `find`.
+    fn find_reading_once(&self, query_json: &Value) -> Result<Vec<Value>> {
+        if self.verify_reads
+            || query_json.as_object().is_some_and(|o| o.is_empty())
+            || query_json.get("$text").is_some()
+        {
+            return self.find(query_json);
+        }
+        let Some(plan) = self.plan_query(query_json) else {
+            return self.find(query_json);
+        };
+        Metrics::incr(&self.metrics.queries);
+        Metrics::incr(&self.metrics.index_hits);
+
+        let parsed_query = Query::from_json(query_json)?;
+        // Multikey indexes can list a document under several keys
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+        for doc_id in self.index_candidates(&plan) {
+            if !seen.insert(doc_id.clone()) {
+                continue;
+            }
+            if let Some(doc) = self.read_document_by_id(&doc_id)? {
+                if parsed_query.matches(&Document::from_value(&doc)?) {
+                    results.push(doc);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Fetch a single document by _id via the catalog (no query evaluation)
+    ///
+    /// Returns None if the id is unknown or the document was deleted.
+    pub fn find_by_id(&self, id: &DocumentId) -> Result<Option<Value>> {
+        self.read_document_by_id(id)
+    }
+
+    /// Fetch a single document by _id and project it
+    ///
+    /// The record is stream-parsed and only the fields the projection needs
+    /// are materialized, so reading a small field of a very large document
+    /// doesn't decode the rest of it. An empty projection reads the whole
+    /// document like `find_by_id`.
+    pub fn find_by_id_with_projection(
+        &self,
+        id: &DocumentId,
+        projection: &HashMap<String, i32>,
+    ) -> Result<Option<Value>> {
+        use crate::find_options::{apply_projection, parse_projected};
+
+        if projection.is_empty() {
+            return self.read_document_by_id(id);
+        }
+
+        let mut storage = self.storage.write();
+        let offset = match storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?
+            .document_catalog
+            .get(id)
+        {
+            Some(&offset) => offset,
+            None => return Ok(None),
+        };
+
+        let generation = storage.layout_generation();
+        let doc = match self.read_cache.get(id, generation, offset) {
+            Some(doc) => doc,
+            None => {
+                Metrics::incr(&self.metrics.documents_read);
+                storage.read_data_with(offset, &mut |reader| parse_projected(reader, projection))?
+            }
+        };
+
+        if doc
+            .get("_tombstone")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            return Ok(None);
+        }
+        Ok(Some(apply_projection(&doc, projection)))
+    }
+
+    /// Fetch documents by _id, in the order given
+    ///
+    /// Missing or deleted ids are skipped. Lookups go through the read cache
+    /// when it is enabled.
+    pub fn find_by_ids(&self, ids: &[DocumentId]) -> Result<Vec<Value>> {
+        let mut results = Vec::with_capacity(ids.len());
+        for doc_id in ids {
+            if let Some(doc) = self.read_document_by_id(doc_id)? {
+                results.push(doc);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Load documents into the read cache ahead of time
+    ///
+    /// Returns how many of the ids were found. When the read cache is disabled
+    /// nothing is read and 0 is returned.
+    pub fn prefetch(&self, ids: &[DocumentId]) -> Result<usize> {
+        if !self.read_cache.is_enabled() {
+            return Ok(0);
+        }
+
+        let mut found = 0;
+        for doc_id in ids {
+            if self.read_document_by_id(doc_id)?.is_some() {
+                found += 1;
+            }
+        }
+        Ok(found)
+    }
+
     /// Find documents with options (projection, sort, limit, skip)
     ///
     /// Clean Architecture: Uses QueryExecutionContext for configuration,
@@ -417,18 +896,18 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         query_json: &Value,
         options: crate::find_options::FindOptions,
     ) -> Result<Vec<Value>> {
+        Metrics::incr(&self.metrics.queries);
         // Phase 1: Build execution context (all setup logic centralized)
         let ctx = QueryExecutionContext::from_options(&options);
 
         // Phase 2: Collect document IDs (may use index for sorting)
-        let (doc_ids, index_sorted) = self.collect_doc_ids_with_options(
+        let (doc_ids, sorted_keys) = self.collect_doc_ids_with_options(
             query_json,
             None,
-            ctx.sort_field_ref(),
-            ctx.sort_descending,
-            ctx.fetch_skip,
-            ctx.fetch_limit,
-            ctx.sort_field.is_none(),
+            ctx.sort_spec_ref(),
+            ctx.original_skip,
+            ctx.original_limit,
+            true,
         )?;
 
         // Phase 3: Load documents
@@ -440,15 +919,13 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         }
 
         // Phase 4: Post-processing pipeline
-        // 4a. Apply sort if needed (index didn't sort for us)
-        if ctx.needs_memory_sort(index_sorted) {
-            if let Some(ref sort_spec) = ctx.sort_spec {
-                crate::find_options::apply_sort(&mut docs, sort_spec);
-            }
+        // 4a. Sort the keys the index order didn't cover
+        if ctx.needs_memory_sort(sorted_keys) {
+            ctx.apply_memory_sort(&mut docs, sorted_keys);
         }
 
         // 4b. Apply pagination after sorting
-        let docs = ctx.apply_post_sort_pagination(docs);
+        let docs = ctx.apply_post_sort_pagination(docs, sorted_keys);
 
         // 4c. Apply projection
         let docs = ctx.apply_projection_to_docs(docs);
@@ -456,6 +933,56 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(docs)
     }
 
+    /// One page of matches plus the total number of matching documents
+    ///
+    /// Matching ids are collected once without `skip`/`limit`: their count is
+    /// the total, and only the ids inside the page window are loaded. When
+    /// the sort can't be served by an index, every match is loaded and sorted
+    /// before the window is cut.
+    pub fn find_page(
+        &self,
+        query_json: &Value,
+        options: crate::find_options::FindOptions,
+    ) -> Result<(Vec<Value>, u64)> {
+        Metrics::incr(&self.metrics.queries);
+        let ctx = QueryExecutionContext::from_options(&options);
+
+        let (doc_ids, sorted_keys) = self.collect_doc_ids_with_options(
+            query_json,
+            None,
+            ctx.sort_spec_ref(),
+            0,
+            None,
+            true,
+        )?;
+        let total = doc_ids.len() as u64;
+
+        let docs = if ctx.needs_memory_sort(sorted_keys) {
+            let mut docs = Vec::with_capacity(doc_ids.len());
+            for doc_id in doc_ids {
+                if let Some(doc) = self.read_document_by_id(&doc_id)? {
+                    docs.push(doc);
+                }
+            }
+            ctx.apply_memory_sort(&mut docs, sorted_keys);
+            crate::find_options::apply_limit_skip(docs, options.limit, options.skip)
+        } else {
+            let window = doc_ids
+                .iter()
+                .skip(options.skip.unwrap_or(0))
+                .take(options.limit.unwrap_or(usize::MAX));
+            let mut docs = Vec::new();
+            for doc_id in window {
+                if let Some(doc) = self.read_document_by_id(doc_id)? {
+                    docs.push(doc);
+                }
+            }
+            docs
+        };
+
+        Ok((ctx.apply_projection_to_docs(docs), total))
+    }
+
     /// Streaming cursor for large result sets
     ///
     /// Returns a cursor that lazily loads documents, allowing memory-efficient
@@ -479,18 +1006,72 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     /// ```
     pub fn find_streaming(&self, query_json: &Value) -> Result<FindCursor<'_, S>> {
         let (doc_ids, _) =
-            self.collect_doc_ids_with_options(query_json, None, None, false, 0, None, true)?;
+            self.collect_doc_ids_with_options(query_json, None, None, 0, None, true)?;
         Ok(FindCursor::new(self, doc_ids))
     }
 
+    /// Full scan returning matching documents with shared field names
+    ///
+    /// Meant for callers that hold a large result set in memory: with
+    /// interning enabled (`DatabaseOptions::with_field_interning`, or
+    /// `with_field_interning` on this handle) every object key is an
+    /// `Arc<str>` from the pool, so identical schemas do not repeat their key
+    /// allocations per document. Without it, keys are allocated per document
+    /// as usual. Indexes are not used; results are in catalog order.
+    ///
+    /// `find` and the cursors keep returning `serde_json::Value`: the query
+    /// operators, projections and sorts all work on owned values, so
+    /// interning there would only add a conversion per result.
+    pub fn scan_interned(&self, query_json: &Value) -> Result<Vec<InternedValue>> {
+        let parsed_query = Query::from_json(query_json)?;
+        let match_all = Self::query_matches_all(query_json);
+
+        let mut storage = self.storage.write();
+        let offsets: Vec<u64> = storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?
+            .document_catalog
+            .values()
+            .copied()
+            .collect();
+
+        let mut results = Vec::new();
+        for offset in &offsets {
+            Metrics::incr(&self.metrics.documents_read);
+            let Ok(doc_bytes) = storage.read_data(*offset) else {
+                continue; // Skip corrupted entries
+            };
+            let Ok(Some(doc)) = self.field_interner.parse_live(&doc_bytes) else {
+                continue; // Skip corrupted JSON and tombstones
+            };
+            // The operators evaluate owned values, so matching needs a copy;
+            // it is dropped right away, so peak memory is the interned
+            // results plus one document
+            if match_all || parsed_query.matches(&doc.to_document()?) {
+                results.push(doc);
+            }
+        }
+        drop(storage);
+
+        self.log_if_slow_scan(query_json, offsets.len());
+        Ok(results)
+    }
+
+    /// Field interning statistics for `scan_interned`
+    pub fn field_interning_stats(&self) -> FieldInterningStats {
+        self.field_interner.stats()
+    }
+
     /// Find one document matching query
     pub fn find_one(&self, query_json: &Value) -> Result<Option<Value>> {
+        Metrics::incr(&self.metrics.queries);
         let parsed_query = Query::from_json(query_json)?;
 
         // OPTIMIZATION: Check if this is an _id equality query (O(1) lookup)
         if let Some(query_obj) = query_json.as_object() {
             if query_obj.len() == 1 && query_obj.contains_key("_id") {
                 if let Some(id_val) = query_obj.get("_id") {
+                    Metrics::incr(&self.metrics.index_hits);
                     // Direct O(1) lookup using document_catalog (direct DocumentId conversion!)
                     if let Ok(doc_id) = serde_json::from_value::<DocumentId>(id_val.clone()) {
                         if let Some(doc) = self.read_document_by_id(&doc_id)? {
@@ -508,36 +1089,29 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             }
         }
 
-        // Fallback: Full scan using catalog iteration (still faster than file scan)
-        let docs_by_id = self.scan_documents_via_catalog()?;
-
-        // Find first matching document (skip tombstones)
-        for (_, doc) in docs_by_id {
-            let doc_json_str = match serde_json::to_string(&doc) {
-                Ok(json) => json,
-                Err(_) => continue,
-            };
-            let document = match Document::from_json(&doc_json_str) {
-                Ok(doc) => doc,
-                Err(_) => continue,
-            };
-
-            if parsed_query.matches(&document) {
-                return Ok(Some(doc));
-            }
+        // Index seek when the planner finds one, else a scan stopping at the first match
+        let (doc_ids, _) =
+            self.collect_doc_ids_with_options(query_json, None, None, 0, Some(1), false)?;
+        match doc_ids.first() {
+            Some(doc_id) => self.read_document_by_id(doc_id),
+            None => Ok(None),
         }
-
-        Ok(None)
     }
 
     /// Count documents matching query
     pub fn count_documents(&self, query_json: &Value) -> Result<u64> {
+        Metrics::incr(&self.metrics.queries);
         if Self::query_matches_all(query_json) {
             let storage = self.storage.read();
             return Ok(storage.get_live_count(&self.name).unwrap_or(0));
         }
 
+        if query_json.get("$text").is_some() {
+            return Ok(self.collect_doc_ids(query_json)?.len() as u64);
+        }
+
         if let Some(doc_id) = Self::extract_id_query(query_json) {
+            Metrics::incr(&self.metrics.index_hits);
             return Ok(if self.read_document_by_id(&doc_id)?.is_some() {
                 1
             } else {
@@ -547,7 +1121,25 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
         let parsed_query = Query::from_json(query_json)?;
 
+        if let Some(plan) = self.plan_query(query_json) {
+            Metrics::incr(&self.metrics.index_hits);
+            let count = self.count_from_plan(query_json, &parsed_query, &plan)?;
+            if self.verify_reads {
+                let expected = self.shadow_scan(&parsed_query)?;
+                if expected.len() as u64 != count {
+                    let mut ids: Vec<&DocumentId> = expected.iter().collect();
+                    ids.sort_by_key(|id| format!("{:?}", id));
+                    Self::report_shadow_mismatch(format!(
+                        "Shadow read mismatch on '{}' for {}: index counted {}, scan matched {:?}",
+                        self.name, query_json, count, ids
+                    ));
+                }
+            }
+            return Ok(count);
+        }
+
         // OPTIMIZATION: Use catalog iteration instead of full file scan
+        Metrics::incr(&self.metrics.collection_scans);
         let docs_by_id = self.scan_documents_via_catalog()?;
 
         // Count matching documents (skip tombstones already filtered by catalog scan)
@@ -564,6 +1156,168 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(count)
     }
 
+    /// Count the documents an index plan matches without collecting their ids
+    ///
+    /// When the index entries alone answer the query (see
+    /// `plan_covers_query`) only the entries are counted. Otherwise each
+    /// candidate is read and checked against the query as it is visited; a
+    /// multikey document listed under several keys in the range is counted
+    /// at its first in-range key only.
+    fn count_from_plan(
+        &self,
+        query_json: &Value,
+        parsed_query: &Query,
+        plan: &QueryPlan,
+    ) -> Result<u64> {
+        if let QueryPlan::CompoundRangeScan { .. } | QueryPlan::IndexExistsScan { .. } = plan {
+            let (doc_ids, _) =
+                self.collect_doc_ids_from_plan(parsed_query, plan.clone(), None, 0, None)?;
+            return Ok(doc_ids.len() as u64);
+        }
+
+        let default_start = IndexKey::Null;
+        let default_end = IndexKey::String("\u{10ffff}".repeat(100));
+        let (index_name, field, start, end, inclusive_start, inclusive_end) = match plan {
+            QueryPlan::IndexScan {
+                index_name,
+                field,
+                key,
+            } => (index_name, field, key, key, true, true),
+            QueryPlan::IndexRangeScan {
+                index_name,
+                field,
+                start,
+                end,
+                inclusive_start,
+                inclusive_end,
+            } => (
+                index_name,
+                field,
+                start.as_ref().unwrap_or(&default_start),
+                end.as_ref().unwrap_or(&default_end),
+                *inclusive_start,
+                *inclusive_end,
+            ),
+            QueryPlan::CompoundRangeScan { .. }
+            | QueryPlan::IndexExistsScan { .. }
+            | QueryPlan::CollectionScan => return Ok(0),
+        };
+
+        // Storage before indexes: nothing takes the storage lock while holding the index lock
+        let mut storage = self.storage.write();
+        let indexes = self.indexes.read();
+        let Some(index) = indexes.get_btree_index(index_name) else {
+            return Ok(0);
+        };
+        if start == end && !index.may_contain(start) {
+            return Ok(0);
+        }
+
+        if Self::plan_covers_query(query_json, plan, index.metadata.is_compound()) {
+            let count = index.count_range(start, end, inclusive_start, inclusive_end);
+            return Ok(count as u64);
+        }
+
+        let mut count = 0u64;
+        let mut error = None;
+        index.for_each_in_range(start, end, inclusive_start, inclusive_end, |key, doc_id| {
+            if error.is_some() {
+                return;
+            }
+            let Some(offset) = storage
+                .get_collection_meta(&self.name)
+                .and_then(|meta| meta.document_catalog.get(doc_id).copied())
+            else {
+                return;
+            };
+            Metrics::incr(&self.metrics.documents_read);
+            let doc = match storage
+                .read_data(offset)
+                .and_then(|bytes| Ok(serde_json::from_slice::<Value>(&bytes)?))
+            {
+                Ok(doc) => doc,
+                Err(e) => {
+                    error = Some(e);
+                    return;
+                }
+            };
+            if doc.get("_tombstone").and_then(|v| v.as_bool()) == Some(true) {
+                return;
+            }
+
+            // Multikey documents: count only at the first key inside the range
+            let first_in_range = index_keys_for_field(&doc, field).into_iter().find(|k| {
+                let after_start = if inclusive_start {
+                    k >= start
+                } else {
+                    k > start
+                };
+                let before_end = if inclusive_end { k <= end } else { k < end };
+                after_start && before_end
+            });
+            if first_in_range.as_ref().is_some_and(|first| first != key) {
+                return;
+            }
+
+            match Document::from_value(&doc) {
+                Ok(document) if parsed_query.matches(&document) => count += 1,
+                Ok(_) => {}
+                Err(e) => error = Some(e.into()),
+            }
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(count),
+        }
+    }
+
+    /// Whether every index entry the plan visits is a match for the query
+    ///
+    /// True for a query on the plan's field alone that is either an equality
+    /// on a non-null scalar or a range with both bounds of the same type on a
+    /// top-level field (top-level fields never produce multikey entries, so
+    /// each document is visited once). Compound indexes never qualify.
+    fn plan_covers_query(query_json: &Value, plan: &QueryPlan, compound: bool) -> bool {
+        let Some(map) = query_json.as_object().filter(|m| m.len() == 1) else {
+            return false;
+        };
+        if compound {
+            return false;
+        }
+
+        match plan {
+            QueryPlan::IndexScan { field, key, .. } => {
+                let scalar = matches!(
+                    map.get(field),
+                    Some(Value::Bool(_) | Value::Number(_) | Value::String(_))
+                );
+                scalar && !matches!(key, IndexKey::Null)
+            }
+            QueryPlan::IndexRangeScan {
+                field,
+                start: Some(start),
+                end: Some(end),
+                ..
+            } => {
+                let only_bounds = map
+                    .get(field)
+                    .and_then(Value::as_object)
+                    .is_some_and(|ops| {
+                        ops.keys()
+                            .all(|op| matches!(op.as_str(), "$gt" | "$gte" | "$lt" | "$lte"))
+                    });
+                let same_type = matches!(
+                    (start, end),
+                    (IndexKey::Int(_), IndexKey::Int(_))
+                        | (IndexKey::String(_), IndexKey::String(_))
+                );
+                !field.contains('.') && only_bounds && same_type
+            }
+            _ => false,
+        }
+    }
+
     // =========================================================================
     // HELPER FUNCTIONS (Extracted for reduced CC and cognitive complexity)
     // =========================================================================
@@ -600,17 +1354,34 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         }
     }
 
+    /// Take the storage write lock to apply a write, counting it in the metrics
+    fn lock_storage_for_write(&self) -> RwLockWriteGuard<'_, S> {
+        Metrics::incr(&self.metrics.write_locks);
+        self.storage.write()
+    }
+
+    /// Drop cached query results after the collection changed
+    fn invalidate_query_cache(&self) {
+        self.query_cache.invalidate_collection(&self.name);
+        self.aggregation_cache.bump_version(&self.name);
+        Metrics::incr(&self.metrics.cache_invalidations);
+    }
+
     /// Batch write tombstones and updated documents to storage
     ///
     /// Acquires storage lock once and writes all updates atomically.
-    /// Each update consists of: (doc_id, tombstone, updated_json)
+    /// Each update consists of: (doc_id, tombstone, updated_json). Updates that
+    /// fit their padded slot are overwritten in place and skip the tombstone.
     fn batch_write_updates(&self, writes: Vec<(DocumentId, Value, String)>) -> Result<()> {
         if writes.is_empty() {
             return Ok(());
         }
 
-        let mut storage = self.storage.write();
+        let mut storage = self.lock_storage_for_write();
         for (doc_id, tombstone, updated_json) in writes {
+            if storage.overwrite_document_in_place(&self.name, &doc_id, updated_json.as_bytes())? {
+                continue;
+            }
             let tombstone_json = serde_json::to_string(&tombstone)?;
             storage.write_data(tombstone_json.as_bytes())?;
             storage.write_document_raw(&self.name, &doc_id, updated_json.as_bytes())?;
@@ -620,16 +1391,83 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
     /// Distinct values for a field
     pub fn distinct(&self, field: &str, query_json: &Value) -> Result<Vec<Value>> {
+        let mut values = Vec::new();
+        self.distinct_each(field, query_json, |value| {
+            values.push(value);
+            true
+        })?;
+        Ok(values)
+    }
+
+    /// At most `max_values` distinct values for a field, and whether there
+    /// were more
+    ///
+    /// Stops looking once the cap is passed, so with an index on `field`
+    /// only the first `max_values + 1` values are ever produced.
+    pub fn distinct_capped(
+        &self,
+        field: &str,
+        query_json: &Value,
+        max_values: usize,
+    ) -> Result<(Vec<Value>, bool)> {
+        let mut values = Vec::new();
+        let mut truncated = false;
+        self.distinct_each(field, query_json, |value| {
+            if values.len() == max_values {
+                truncated = true;
+                return false;
+            }
+            values.push(value);
+            true
+        })?;
+        Ok((values, truncated))
+    }
+
+    /// Pass each distinct value of a field to `visit`, until it returns false
+    ///
+    /// For an empty query on a top-level field with a single-field index,
+    /// values stream from the index in key order without reading documents
+    /// or remembering earlier values; `visit` then runs under the index read
+    /// lock and must not write to this collection. Keys that don't hold the
+    /// value itself (null, arrays, objects, decimals) are resolved from their
+    /// documents after the index pass. Otherwise matching documents are
+    /// scanned and every value seen is remembered.
+    pub fn distinct_each(
+        &self,
+        field: &str,
+        query_json: &Value,
+        mut visit: impl FnMut(Value) -> bool,
+    ) -> Result<()> {
         if let Some(doc_id) = Self::extract_id_query(query_json) {
             if let Some(doc) = self.read_document_by_id(&doc_id)? {
                 if let Some(value) = doc.get(field) {
-                    return Ok(vec![value.clone()]);
+                    visit(value.clone());
                 }
             }
-            return Ok(Vec::new());
+            return Ok(());
         }
 
         let match_all = Self::query_matches_all(query_json);
+        if match_all && !field.contains('.') {
+            if let Some(unresolved) = self.distinct_from_index(field, &mut visit) {
+                let Some(unresolved) = unresolved else {
+                    return Ok(());
+                };
+                let mut seen_values: HashSet<String> = HashSet::new();
+                for doc_id in unresolved {
+                    if let Some(value) = self
+                        .read_document_by_id(&doc_id)?
+                        .and_then(|mut doc| doc.get_mut(field).map(Value::take))
+                    {
+                        if seen_values.insert(canonical_json_string(&value)) && !visit(value) {
+                            break;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+        }
+
         let parsed_query = if match_all {
             None
         } else {
@@ -638,9 +1476,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
         let docs_by_id = self.scan_documents_via_catalog()?;
 
-        // Collect distinct values from matching documents (skip tombstones)
+        // Visit distinct values of matching documents (skip tombstones)
         let mut seen_values: HashSet<String> = HashSet::new();
-        let mut distinct_values = Vec::new();
 
         for (_, doc) in docs_by_id {
             // Skip tombstones (deleted documents)
@@ -665,26 +1502,94 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                     let value_key =
                         serde_json::to_string(field_value).unwrap_or_else(|_| "null".to_string());
 
-                    if seen_values.insert(value_key) {
-                        distinct_values.push(field_value.clone());
+                    if seen_values.insert(value_key) && !visit(field_value.clone()) {
+                        break;
                     }
                 }
             }
         }
 
-        Ok(distinct_values)
+        Ok(())
+    }
+
+    /// Index pass of `distinct_each`: None without a single-field index on
+    /// `field`; otherwise the documents whose keys didn't hold their value,
+    /// or None inside if `visit` asked to stop
+    fn distinct_from_index(
+        &self,
+        field: &str,
+        visit: &mut impl FnMut(Value) -> bool,
+    ) -> Option<Option<Vec<DocumentId>>> {
+        let indexes = self.indexes.read();
+        let index_name = indexes.index_infos().into_iter().find_map(|info| {
+            (!info.is_compound() && info.fields.first().map(String::as_str) == Some(field))
+                .then_some(info.name)
+        })?;
+        let index = indexes.get_btree_index(&index_name)?;
+
+        let mut unresolved = Vec::new();
+        let mut previous: Option<&IndexKey> = None;
+        let mut stopped = false;
+        index.for_each_entry_while(|key, doc_id| {
+            match key.to_value() {
+                // Equal keys are adjacent: emit each once
+                Some(value) if previous != Some(key) => {
+                    if !visit(value) {
+                        stopped = true;
+                        return false;
+                    }
+                }
+                Some(_) => {}
+                None => unresolved.push(doc_id.clone()),
+            }
+            previous = Some(key);
+            true
+        });
+        Some((!stopped).then_some(unresolved))
     }
 
     // ========== PRIVATE HELPER METHODS ==========
 
-    /// Extract field name from index name (e.g., "users_age" -> "age")
-    fn extract_field_from_index_name(&self, index_name: &str) -> String {
-        // Remove collection prefix: "users_age" -> "age"
-        let prefix = format!("{}_", self.name);
-        index_name
-            .strip_prefix(&prefix)
-            .unwrap_or(index_name)
-            .to_string()
+    /// Leading field of a named index (the one a hinted plan can match on)
+    fn index_leading_field(&self, index_name: &str) -> Result<String> {
+        self.indexes
+            .read()
+            .index_info(index_name)
+            .and_then(|info| info.fields.into_iter().next())
+            .ok_or_else(|| {
+                MongoLiteError::IndexError(format!("Index '{}' not found (hint)", index_name))
+            })
+    }
+
+    /// Index plan for a query, or None for a collection scan
+    ///
+    /// A compound prefix-plus-range plan wins when one applies; otherwise
+    /// the single-field planner picks from the non-compound indexes.
+    fn plan_query(&self, query_json: &Value) -> Option<QueryPlan> {
+        let (compound, single) = self.planner_indexes(query_json);
+        QueryPlanner::analyze_compound_query(query_json, &compound)
+            .or_else(|| QueryPlanner::analyze_query(query_json, &single).map(|(_, plan)| plan))
+    }
+
+    /// Compound index descriptions, and names of the single-field indexes on fields `query_json` filters
+    ///
+    /// The planner finds single-field indexes by name suffix, which would
+    /// let a filter on `id` pick the `{collection}_id` index on `_id`, so
+    /// only indexes on the query's own fields are offered.
+    fn planner_indexes(&self, query_json: &Value) -> (Vec<IndexInfo>, Vec<String>) {
+        let (compound, single): (Vec<IndexInfo>, Vec<IndexInfo>) = self
+            .indexes
+            .read()
+            .index_infos()
+            .into_iter()
+            .partition(IndexInfo::is_compound);
+        let filtered = |field: &String| query_json.get(field.as_str()).is_some();
+        let single = single
+            .into_iter()
+            .filter(|info| info.fields.first().is_some_and(filtered))
+            .map(|info| info.name)
+            .collect();
+        (compound, single)
     }
 
     /// Create a query plan for a hinted index
@@ -694,6 +1599,23 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         index_name: &str,
         field: &str,
     ) -> Result<QueryPlan> {
+        let compound_fields = self
+            .indexes
+            .read()
+            .index_info(index_name)
+            .filter(IndexInfo::is_compound)
+            .map(|info| info.fields);
+        if let Some(fields) = compound_fields {
+            return QueryPlanner::compound_plan(query_json, index_name, &fields)
+                .map(|(_, plan)| plan)
+                .ok_or_else(|| {
+                    MongoLiteError::IndexError(format!(
+                        "Cannot use index '{}' for this query",
+                        index_name
+                    ))
+                });
+        }
+
         // Parse the query to understand what we're looking for
         if let Value::Object(ref map) = query_json {
             // Check if querying this field
@@ -732,6 +1654,13 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                             inclusive_end: has_lte || (!has_lt && !has_lte),
                         });
                     }
+
+                    if ops.get("$exists") == Some(&Value::Bool(true)) {
+                        return Ok(QueryPlan::IndexExistsScan {
+                            index_name: index_name.to_string(),
+                            field: field.to_string(),
+                        });
+                    }
                 }
 
                 // Equality query
@@ -752,8 +1681,7 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
     /// Execute query using an index
     fn find_with_index(&self, parsed_query: Query, plan: QueryPlan) -> Result<Vec<Value>> {
-        let (doc_ids, _) =
-            self.collect_doc_ids_from_plan(&parsed_query, plan, None, false, 0, None)?;
+        let (doc_ids, _) = self.collect_doc_ids_from_plan(&parsed_query, plan, None, 0, None)?;
         let mut results = Vec::with_capacity(doc_ids.len());
         for doc_id in doc_ids {
             if let Some(doc) = self.read_document_by_id(&doc_id)? {
@@ -768,7 +1696,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     /// Remove a document from all indexes
     /// Used during update and delete operations
     fn remove_from_indexes(&self, doc: &Document) -> Result<()> {
-        let mut indexes = self.indexes.write();
+        self.remove_from_index_manager(&mut self.indexes.write(), doc)
+    }
+
+    /// `remove_from_indexes` for a caller already holding the index lock
+    fn remove_from_index_manager(&self, indexes: &mut IndexManager, doc: &Document) -> Result<()> {
         let id_index_name = format!("{}_id", self.name);
 
         // Remove from _id index
@@ -788,13 +1720,14 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             }
 
             if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-                let field = index.metadata.field.clone();
-                if let Some(field_value) = doc.get(&field) {
-                    let index_key = IndexKey::from(field_value);
+                for index_key in index.keys_for_document(doc) {
                     index.delete(&index_key, &doc.id)?;
                 }
             }
         }
+        if let Some(text_index) = indexes.text_index_mut() {
+            text_index.remove(&doc.id);
+        }
 
         Ok(())
     }
@@ -802,7 +1735,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     /// Add a document to all indexes (with unique constraint checking)
     /// Used during update operations after removing old values
     fn add_to_indexes(&self, doc: &Document) -> Result<()> {
-        let mut indexes = self.indexes.write();
+        self.add_to_index_manager(&mut self.indexes.write(), doc)
+    }
+
+    /// `add_to_indexes` for a caller already holding the index lock
+    fn add_to_index_manager(&self, indexes: &mut IndexManager, doc: &Document) -> Result<()> {
         let id_index_name = format!("{}_id", self.name);
 
         // Add to _id index
@@ -822,13 +1759,14 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             }
 
             if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-                let field = index.metadata.field.clone();
-                if let Some(field_value) = doc.get(&field) {
-                    let index_key = IndexKey::from(field_value);
+                for index_key in index.keys_for_document(doc) {
                     index.insert(index_key, doc.id.clone())?;
                 }
             }
         }
+        if let Some(text_index) = indexes.text_index_mut() {
+            text_index.insert(doc);
+        }
 
         Ok(())
     }
@@ -887,75 +1825,52 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 continue;
             }
 
-            // Get the field name first
-            let field = if let Some(idx) = indexes.get_btree_index(index_name) {
-                idx.metadata.field.clone()
-            } else {
+            let Some(idx) = indexes.get_btree_index(index_name) else {
                 continue;
             };
 
-            // Collect updates for this index
-            let field_updates: Vec<(IndexKey, DocumentId, IndexKey, DocumentId)> = updates
-                .iter()
-                .filter_map(|(original_doc, updated_doc)| {
-                    let old_value = original_doc.get(&field)?;
-                    let new_value = updated_doc.get(&field)?;
-                    Some((
-                        IndexKey::from(old_value),
+            // Collect updates for this index: one-key-to-one-key changes go through
+            // the batch path, multikey (array path) changes are applied individually
+            let mut field_updates: Vec<(IndexKey, DocumentId, IndexKey, DocumentId)> = Vec::new();
+            let mut multikey_updates = Vec::new();
+            for (original_doc, updated_doc) in updates {
+                let old_keys = idx.keys_for_document(original_doc);
+                let new_keys = idx.keys_for_document(updated_doc);
+                match (old_keys.as_slice(), new_keys.as_slice()) {
+                    ([], []) => {}
+                    ([old_key], [new_key]) => field_updates.push((
+                        old_key.clone(),
                         original_doc.id.clone(),
-                        IndexKey::from(new_value),
+                        new_key.clone(),
                         updated_doc.id.clone(),
-                    ))
-                })
-                .collect();
-
-            if !field_updates.is_empty() {
-                if let Some(index) = indexes.get_btree_index_mut(index_name) {
-                    index.apply_batch_updates(field_updates)?;
+                    )),
+                    _ => multikey_updates.push((original_doc, old_keys, updated_doc, new_keys)),
                 }
             }
-        }
 
-        Ok(())
-    }
-
-    /// Batch add multiple documents to all indexes
-    /// Single lock acquisition for performance - used by insert_many
-    fn batch_add_to_indexes(&self, docs: &[Document]) -> Result<()> {
-        if docs.is_empty() {
-            return Ok(());
-        }
-
-        let mut indexes = self.indexes.write();
-        let id_index_name = format!("{}_id", self.name);
-
-        for doc in docs {
-            // Add to _id index
-            if let Some(id_index) = indexes.get_btree_index_mut(&id_index_name) {
-                let id_key = match &doc.id {
-                    DocumentId::Int(i) => IndexKey::Int(*i),
-                    DocumentId::String(s) => IndexKey::String(s.clone()),
-                    DocumentId::ObjectId(oid) => IndexKey::String(oid.clone()),
-                };
-                id_index.insert(id_key, doc.id.clone())?;
-            }
-
-            // Add to all other indexes
-            for index_name in indexes.list_indexes() {
-                if index_name == id_index_name {
-                    continue; // Already handled
+            if let Some(index) = indexes.get_btree_index_mut(index_name) {
+                if !field_updates.is_empty() {
+                    index.apply_batch_updates(field_updates)?;
                 }
-
-                if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-                    let field = index.metadata.field.clone();
-                    if let Some(field_value) = doc.get(&field) {
-                        let index_key = IndexKey::from(field_value);
-                        index.insert(index_key, doc.id.clone())?;
+                for (original_doc, old_keys, updated_doc, new_keys) in multikey_updates {
+                    for key in &old_keys {
+                        index.delete(key, &original_doc.id)?;
+                    }
+                    for key in new_keys {
+                        index.insert(key, updated_doc.id.clone())?;
                     }
                 }
             }
         }
 
+        // --- TEXT INDEX: re-tokenize the updated documents ---
+        if let Some(text_index) = indexes.text_index_mut() {
+            for (original_doc, updated_doc) in updates {
+                text_index.remove(&original_doc.id);
+                text_index.insert(updated_doc);
+            }
+        }
+
         Ok(())
     }
 
@@ -966,7 +1881,16 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         doc: &Document,
         exclude_id: Option<&DocumentId>,
     ) -> Result<()> {
-        let indexes = self.indexes.read();
+        self.check_index_manager_constraints(&self.indexes.read(), doc, exclude_id)
+    }
+
+    /// `check_index_constraints` for a caller already holding the index lock
+    fn check_index_manager_constraints(
+        &self,
+        indexes: &IndexManager,
+        doc: &Document,
+        exclude_id: Option<&DocumentId>,
+    ) -> Result<()> {
         let id_index_name = format!("{}_id", self.name);
 
         // Check all indexes (except _id which is handled separately)
@@ -981,10 +1905,12 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                     continue;
                 }
 
-                let field = &index.metadata.field;
-                if let Some(field_value) = doc.get(field) {
-                    let index_key = IndexKey::from(field_value);
-
+                let field = if index.metadata.is_compound() {
+                    index.metadata.fields.join(", ")
+                } else {
+                    index.metadata.field.clone()
+                };
+                for index_key in index.keys_for_document(doc) {
                     // Check if key already exists
                     if let Some(existing_id) = index.search(&index_key) {
                         // If exclude_id is provided, skip if it's the same document
@@ -1003,8 +1929,84 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(())
     }
 
+    /// Check that no two updated documents of a batch share a unique index key
+    ///
+    /// `check_index_constraints` compares each document with the index as
+    /// it was before the batch, so it can't see collisions among the batch.
+    fn check_batch_unique(&self, updates: &[(Document, Document)]) -> Result<()> {
+        let indexes = self.indexes.read();
+        let id_index_name = format!("{}_id", self.name);
+
+        for index_name in indexes.list_indexes() {
+            if index_name == id_index_name {
+                continue;
+            }
+            let Some(index) = indexes.get_btree_index(&index_name) else {
+                continue;
+            };
+            if !index.metadata.unique {
+                continue;
+            }
+
+            let mut keys: Vec<IndexKey> = updates
+                .iter()
+                .flat_map(|(_, updated)| index.keys_for_document(updated))
+                .collect();
+            keys.sort();
+            if let Some(pair) = keys.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(MongoLiteError::IndexError(format!(
+                    "Duplicate key: {:?} in field '{}' (unique index)",
+                    pair[0],
+                    index.metadata.fields.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fields of the document an upsert inserts when nothing matches `query_json`
+    ///
+    /// Starts from the query's top-level equalities (`{f: v}` and
+    /// `{f: {"$eq": v}}`), then applies `update_json` with `$setOnInsert`.
+    /// An `_id` equality becomes the new document's `_id`.
+    pub(crate) fn upsert_fields(
+        &self,
+        query_json: &Value,
+        update_json: &Value,
+    ) -> Result<HashMap<String, Value>> {
+        let mut document = Document::new(DocumentId::Int(0), HashMap::new());
+        if let Value::Object(ref conditions) = query_json {
+            for (field, condition) in conditions {
+                if field.starts_with('$') {
+                    continue;
+                }
+                let value = match condition {
+                    Value::Object(ops) if ops.keys().any(|k| k.starts_with('$')) => {
+                        match ops.get("$eq") {
+                            Some(value) if ops.len() == 1 => value,
+                            _ => continue,
+                        }
+                    }
+                    value => value,
+                };
+                document.set_nested(field, value.clone());
+            }
+        }
+        self.apply_update_operators(&mut document, update_json, true)?;
+        Ok(document.fields)
+    }
+
     /// Apply update operators to document - returns whether document was modified
-    fn apply_update_operators(&self, document: &mut Document, update_json: &Value) -> Result<bool> {
+    ///
+    /// `$setOnInsert` only takes effect when `inserting` (an upsert that
+    /// matched nothing); on a matched document it is ignored.
+    fn apply_update_operators(
+        &self,
+        document: &mut Document,
+        update_json: &Value,
+        inserting: bool,
+    ) -> Result<bool> {
         let mut was_modified = false;
 
         if let Value::Object(ref update_ops) = update_json {
@@ -1018,6 +2020,16 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                             }
                         }
                     }
+                    "$setOnInsert" => {
+                        if let Value::Object(ref field_values) = fields {
+                            if inserting {
+                                for (field, value) in field_values {
+                                    document.set_nested(field, value.clone());
+                                    was_modified = true;
+                                }
+                            }
+                        }
+                    }
                     "$inc" => {
                         if let Value::Object(ref field_values) = fields {
                             for (field, inc_value) in field_values {
@@ -1039,6 +2051,65 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                             }
                         }
                     }
+                    "$mul" => {
+                        if let Value::Object(ref field_values) = fields {
+                            for (field, factor) in field_values {
+                                if !factor.is_number() {
+                                    return Err(MongoLiteError::InvalidQuery(format!(
+                                        "$mul: factor for '{}' must be a number",
+                                        field
+                                    )));
+                                }
+                                let product = match document.get(field) {
+                                    // MongoDB: a missing field becomes 0 of the factor's type
+                                    None if factor.is_i64() => Value::from(0),
+                                    None => Value::from(0.0),
+                                    Some(current) => {
+                                        // Integers stay integers, saturating on overflow
+                                        if let (Some(curr_int), Some(factor_int)) =
+                                            (current.as_i64(), factor.as_i64())
+                                        {
+                                            Value::from(curr_int.saturating_mul(factor_int))
+                                        } else if let (Some(curr_num), Some(factor_num)) =
+                                            (current.as_f64(), factor.as_f64())
+                                        {
+                                            Value::from(curr_num * factor_num)
+                                        } else {
+                                            return Err(MongoLiteError::InvalidQuery(format!(
+                                                "$mul: field '{}' is not a number",
+                                                field
+                                            )));
+                                        }
+                                    }
+                                };
+                                document.set_nested(field, product);
+                                was_modified = true;
+                            }
+                        }
+                    }
+                    "$min" | "$max" => {
+                        if let Value::Object(ref field_values) = fields {
+                            let wanted = if op == "$min" {
+                                std::cmp::Ordering::Less
+                            } else {
+                                std::cmp::Ordering::Greater
+                            };
+                            for (field, operand) in field_values {
+                                // A missing field is set unconditionally; values the
+                                // comparator cannot order (e.g. number vs string) are kept
+                                let replace = match document.get(field) {
+                                    None => true,
+                                    Some(current) => {
+                                        Self::compare_values(operand, current) == Some(wanted)
+                                    }
+                                };
+                                if replace {
+                                    document.set_nested(field, operand.clone());
+                                    was_modified = true;
+                                }
+                            }
+                        }
+                    }
                     "$unset" => {
                         if let Value::Object(ref field_values) = fields {
                             for (field, _) in field_values {
@@ -1047,37 +2118,87 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                             }
                         }
                     }
+                    "$rename" => {
+                        if let Value::Object(ref renames) = fields {
+                            for (from, to) in renames {
+                                let to = to.as_str().ok_or_else(|| {
+                                    MongoLiteError::InvalidQuery(format!(
+                                        "$rename: new name for '{}' must be a string",
+                                        from
+                                    ))
+                                })?;
+                                let is_id = |path: &str| path.split('.').next() == Some("_id");
+                                if is_id(from) || is_id(to) {
+                                    return Err(MongoLiteError::InvalidQuery(
+                                        "$rename: cannot rename _id".to_string(),
+                                    ));
+                                }
+                                // Moving a field into or out of itself has no meaning
+                                let contains = |outer: &str, inner: &str| {
+                                    inner == outer
+                                        || (inner.starts_with(outer)
+                                            && inner[outer.len()..].starts_with('.'))
+                                };
+                                if from.is_empty()
+                                    || to.is_empty()
+                                    || contains(from, to)
+                                    || contains(to, from)
+                                {
+                                    return Err(MongoLiteError::InvalidQuery(format!(
+                                        "$rename: cannot rename '{}' to '{}'",
+                                        from, to
+                                    )));
+                                }
+                                // Renaming a missing field is a no-op
+                                if let Some(value) = document.remove_nested(from) {
+                                    document.set_nested(to, value);
+                                    was_modified = true;
+                                }
+                            }
+                        }
+                    }
                     "$push" => {
                         if let Value::Object(ref field_values) = fields {
                             for (field, value) in field_values {
-                                // Handle modifiers: $each, $position, $slice
-                                let (items, position, slice) = if let Value::Object(ref modifiers) =
-                                    value
-                                {
-                                    let items = if let Some(each_val) = modifiers.get("$each") {
-                                        // $each: push multiple items
-                                        if let Value::Array(ref arr) = each_val {
-                                            arr.clone()
+                                // Handle modifiers: $each, $position, $sort, $slice
+                                let (items, position, sort, slice) =
+                                    if let Value::Object(ref modifiers) = value {
+                                        let items = if let Some(each_val) = modifiers.get("$each") {
+                                            // $each: push multiple items
+                                            if let Value::Array(ref arr) = each_val {
+                                                arr.clone()
+                                            } else {
+                                                vec![each_val.clone()]
+                                            }
                                         } else {
-                                            vec![each_val.clone()]
-                                        }
-                                    } else {
-                                        // No $each, treat entire value as single item
-                                        vec![value.clone()]
-                                    };
-
-                                    let position = modifiers
-                                        .get("$position")
-                                        .and_then(|v| v.as_i64())
-                                        .map(|p| p as usize);
+                                            // No $each, treat entire value as single item
+                                            vec![value.clone()]
+                                        };
+
+                                        let position = modifiers
+                                            .get("$position")
+                                            .and_then(|v| v.as_i64())
+                                            .map(|p| p as usize);
+
+                                        let sort = match modifiers.get("$sort") {
+                                            Some(_) if !modifiers.contains_key("$each") => {
+                                                return Err(MongoLiteError::InvalidQuery(format!(
+                                                    "$push: $sort on field '{}' requires $each",
+                                                    field
+                                                )));
+                                            }
+                                            Some(spec) => Some(Self::push_sort_spec(spec)?),
+                                            None => None,
+                                        };
 
-                                    let slice = modifiers.get("$slice").and_then(|v| v.as_i64());
+                                        let slice =
+                                            modifiers.get("$slice").and_then(|v| v.as_i64());
 
-                                    (items, position, slice)
-                                } else {
-                                    // Simple push: single value
-                                    (vec![value.clone()], None, None)
-                                };
+                                        (items, position, sort, slice)
+                                    } else {
+                                        // Simple push: single value
+                                        (vec![value.clone()], None, None, None)
+                                    };
 
                                 // Get or create array
                                 let mut array = match document.get(field) {
@@ -1101,6 +2222,31 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                                     array.extend(items);
                                 }
 
+                                // Apply $sort before $slice, so a slice keeps the top N
+                                if let Some(sort) = sort {
+                                    array.sort_by(|a, b| {
+                                        for (path, direction) in &sort {
+                                            let (val_a, val_b) = if path.is_empty() {
+                                                (Some(a), Some(b))
+                                            } else {
+                                                (
+                                                    get_nested_value(a, path),
+                                                    get_nested_value(b, path),
+                                                )
+                                            };
+                                            let cmp = compare_for_sort(val_a, val_b);
+                                            if cmp != std::cmp::Ordering::Equal {
+                                                return if *direction == 1 {
+                                                    cmp
+                                                } else {
+                                                    cmp.reverse()
+                                                };
+                                            }
+                                        }
+                                        std::cmp::Ordering::Equal
+                                    });
+                                }
+
                                 // Apply $slice if specified
                                 if let Some(slice_val) = slice {
                                     if slice_val < 0 {
@@ -1147,6 +2293,38 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                             }
                         }
                     }
+                    "$pullAll" => {
+                        if let Value::Object(ref field_values) = fields {
+                            for (field, values) in field_values {
+                                let Value::Array(ref values) = values else {
+                                    return Err(MongoLiteError::InvalidQuery(format!(
+                                        "$pullAll: value for '{}' must be an array",
+                                        field
+                                    )));
+                                };
+                                match document.get(field) {
+                                    Some(Value::Array(arr)) => {
+                                        let filtered: Vec<Value> = arr
+                                            .iter()
+                                            .filter(|item| !values.contains(item))
+                                            .cloned()
+                                            .collect();
+                                        if filtered.len() != arr.len() {
+                                            document.set_nested(field, Value::Array(filtered));
+                                            was_modified = true;
+                                        }
+                                    }
+                                    Some(_) => {
+                                        return Err(MongoLiteError::InvalidQuery(format!(
+                                            "$pullAll: field '{}' is not an array",
+                                            field
+                                        )));
+                                    }
+                                    None => {}
+                                }
+                            }
+                        }
+                    }
                     "$addToSet" => {
                         if let Value::Object(ref field_values) = fields {
                             for (field, value) in field_values {
@@ -1255,15 +2433,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 // Evaluate query operators
                 for (op, op_value) in cond_obj {
                     match op.as_str() {
-                        "$eq" => {
-                            if value != op_value {
-                                return false;
-                            }
+                        "$eq" if value != op_value => {
+                            return false;
                         }
-                        "$ne" => {
-                            if value == op_value {
-                                return false;
-                            }
+                        "$ne" if value == op_value => {
+                            return false;
                         }
                         "$gt" => {
                             use std::cmp::Ordering;
@@ -1326,6 +2500,28 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         value == condition
     }
 
+    /// Parse the `$sort` modifier of `$push`: `1`/`-1` sorts whole elements
+    /// (an empty path), `{path: 1|-1, ...}` sorts object elements by fields
+    fn push_sort_spec(spec: &Value) -> Result<Vec<(String, i32)>> {
+        let direction = |value: &Value| match value.as_i64() {
+            Some(1) => Ok(1),
+            Some(-1) => Ok(-1),
+            _ => Err(MongoLiteError::InvalidQuery(
+                "$push: $sort direction must be 1 or -1".to_string(),
+            )),
+        };
+        match spec {
+            Value::Object(fields) if !fields.is_empty() => fields
+                .iter()
+                .map(|(path, value)| Ok((path.clone(), direction(value)?)))
+                .collect(),
+            Value::Object(_) => Err(MongoLiteError::InvalidQuery(
+                "$push: $sort document must not be empty".to_string(),
+            )),
+            _ => Ok(vec![(String::new(), direction(spec)?)]),
+        }
+    }
+
     /// Helper to compare two JSON values for ordering
     fn compare_values(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
         match (a, b) {
@@ -1344,13 +2540,81 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
     /// Explain query execution plan without executing
     pub fn explain(&self, query_json: &Value) -> Result<Value> {
-        let indexes = self.indexes.read();
-        let available_indexes = indexes.list_indexes();
+        let (compound, single) = self.planner_indexes(query_json);
+        if let Some(plan) = QueryPlanner::analyze_compound_query(query_json, &compound) {
+            return Ok(QueryPlanner::describe_plan(&plan));
+        }
 
-        let plan = QueryPlanner::explain_query(query_json, &available_indexes);
+        let plan = QueryPlanner::explain_query(query_json, &single);
         Ok(plan)
     }
 
+    /// Run a query and report its plan with execution statistics
+    ///
+    /// Returns the [`CollectionCore::explain`] output plus `executionStats`:
+    /// `nReturned`, `docsExamined` (documents read and checked against the
+    /// query: the index candidates, or every live document for a collection
+    /// scan), `indexUsed` (null for a scan) and `executionTimeMicros`. The
+    /// query cache is bypassed so the figures reflect real work.
+    pub fn explain_execution(&self, query_json: &Value) -> Result<Value> {
+        let mut report = self.explain(query_json)?;
+        let plan = self.plan_query(query_json);
+
+        let started = std::time::Instant::now();
+        let (doc_ids, _) =
+            self.collect_doc_ids_with_options(query_json, None, None, 0, None, false)?;
+        let mut returned = 0u64;
+        for doc_id in &doc_ids {
+            if self.read_document_by_id(doc_id)?.is_some() {
+                returned += 1;
+            }
+        }
+        let elapsed = started.elapsed();
+
+        let (docs_examined, index_used) = match &plan {
+            Some(plan) => {
+                let candidates: HashSet<DocumentId> =
+                    self.index_candidates(plan).into_iter().collect();
+                (candidates.len(), plan.index_name())
+            }
+            None => {
+                let storage = self.storage.read();
+                let live = storage
+                    .get_collection_meta(&self.name)
+                    .map_or(0, |meta| meta.document_catalog.len());
+                (live, None)
+            }
+        };
+
+        report["executionStats"] = serde_json::json!({
+            "nReturned": returned,
+            "docsExamined": docs_examined,
+            "indexUsed": index_used,
+            "executionTimeMicros": elapsed.as_micros() as u64,
+        });
+        Ok(report)
+    }
+
+    /// Recommend indexes for a representative query workload
+    ///
+    /// Each query is a filter, or `{"filter": ..., "sort": ...}`. Fields
+    /// that aren't yet indexed (alone or as a compound prefix) are ranked
+    /// by how often they appear times the share of documents their
+    /// predicates would let the query skip, measured on a sample of up to
+    /// 1000 stored documents. Nothing is created; each
+    /// suggestion carries the `create_index` call to run.
+    pub fn suggest_indexes(&self, queries: &[Value]) -> Result<Vec<IndexSuggestion>> {
+        let sample = self.find_with_options(
+            &serde_json::json!({}),
+            crate::find_options::FindOptions::new().with_limit(crate::index_advisor::SAMPLE_SIZE),
+        )?;
+        Ok(crate::index_advisor::suggest_indexes(
+            queries,
+            &sample,
+            |field| self.has_index_on(field).is_some(),
+        ))
+    }
+
     /// Find with manual index hint
     pub fn find_with_hint(&self, query_json: &Value, hint: &str) -> Result<Vec<Value>> {
         let parsed_query = Query::from_json(query_json)?;
@@ -1368,7 +2632,7 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
         // Try to create a plan using the hinted index
         // For now, we try to match the query to the index field
-        let field = self.extract_field_from_index_name(hint);
+        let field = self.index_leading_field(hint)?;
 
         // Create a forced plan
         let plan = self.create_plan_for_hint(query_json, hint, &field)?;
@@ -1377,6 +2641,96 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         self.find_with_index(parsed_query, plan)
     }
 
+    // ========== MIGRATION ==========
+
+    /// Rewrite every live document through `transform`
+    ///
+    /// Documents the transform leaves unchanged are not rewritten. Every
+    /// changed document is checked against the schema and unique indexes
+    /// before any is written; then all are written in one batch, with a
+    /// single index update and cache invalidation. `_id` can't be changed.
+    /// Like the other `CollectionCore` writes this bypasses the WAL.
+    ///
+    /// Returns the number of documents changed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ironbase_core::DatabaseCore;
+    /// use serde_json::json;
+    ///
+    /// let db = DatabaseCore::open("test.db").unwrap();
+    /// let users = db.collection("users").unwrap();
+    ///
+    /// // Backfill a default
+    /// let changed = users
+    ///     .migrate(|doc| {
+    ///         if doc.get("role").is_none() {
+    ///             doc["role"] = json!("member");
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn migrate(&self, transform: impl Fn(&mut Value)) -> Result<u64> {
+        self.try_migrate(|doc| {
+            transform(doc);
+            Ok(())
+        })
+    }
+
+    /// [`CollectionCore::migrate`] with a fallible transform
+    ///
+    /// An error from `transform` aborts the migration before anything is written.
+    pub fn try_migrate(&self, mut transform: impl FnMut(&mut Value) -> Result<()>) -> Result<u64> {
+        let docs_by_id = self.scan_documents_via_catalog()?;
+
+        let mut index_updates: Vec<(Document, Document)> = Vec::new(); // (original, updated)
+        let mut storage_writes: Vec<(DocumentId, Value, String)> = Vec::new(); // (id, tombstone, updated_json)
+
+        for (doc_id, stored) in docs_by_id {
+            let mut doc = stored.clone();
+            if let Value::Object(ref mut map) = doc {
+                map.remove("_collection");
+            }
+            let before = doc.clone();
+            transform(&mut doc)?;
+            if doc == before {
+                continue;
+            }
+            if doc.get("_id") != before.get("_id") {
+                return Err(MongoLiteError::InvalidQuery(format!(
+                    "Migration can't change _id (document {:?})",
+                    doc_id
+                )));
+            }
+
+            let original_document = Document::from_value(&stored)?;
+            let mut document = Document::from_value(&doc)?;
+            document.set("_collection".to_string(), Value::String(self.name.clone()));
+            self.check_index_constraints(&document, Some(&document.id))?;
+            self.validate_document(&document)?;
+
+            let mut tombstone = stored;
+            if let Value::Object(ref mut map) = tombstone {
+                map.insert("_tombstone".to_string(), Value::Bool(true));
+                map.insert("_collection".to_string(), Value::String(self.name.clone()));
+            }
+
+            storage_writes.push((doc_id, tombstone, document.to_json()?));
+            index_updates.push((original_document, document));
+        }
+
+        let changed = storage_writes.len() as u64;
+        if changed > 0 {
+            self.check_batch_unique(&index_updates)?;
+            self.batch_update_indexes(&index_updates)?;
+            self.batch_write_updates(storage_writes)?;
+            self.invalidate_query_cache();
+        }
+        Metrics::add(&self.metrics.updates, changed);
+
+        Ok(changed)
+    }
+
     // ========== AGGREGATION ==========
 
     /// Execute aggregation pipeline
@@ -1399,38 +2753,145 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     /// ])).unwrap();
     /// ```
     pub fn aggregate(&self, pipeline_json: &Value) -> Result<Vec<Value>> {
+        self.aggregate_with_writer(pipeline_json, self)
+    }
+
+    /// [`CollectionCore::aggregate`] with `$out` written through `writer`
+    pub fn aggregate_with_writer(
+        &self,
+        pipeline_json: &Value,
+        writer: &dyn CollectionWriter,
+    ) -> Result<Vec<Value>> {
         use crate::aggregation::Pipeline;
 
         // Parse pipeline
-        let pipeline = Pipeline::from_json(pipeline_json)?;
-
-        // OPTIMIZATION: Use index if $match is first stage (query optimizer)
-        //
-        // Current: Always full collection scan (self.find on empty query)
-        // Impact: Aggregation pipelines with selective $match are slow
-        //
-        // Index-based optimization:
-        // 1. Check if pipeline[0] is $match stage
-        // 2. Extract query from $match (e.g., {"age": {"$gt": 25}})
-        // 3. Use query optimizer to select best index (see IMPLEMENTATION_QUERY_OPTIMIZER.md)
-        // 4. Use index.search() or range_scan() to get filtered doc IDs
-        // 5. Load only matching documents (not entire collection)
-        //
-        // Benefit: 10-1000x speedup for selective aggregations
-        // Example: db.collection.aggregate([{$match: {email: "foo@bar.com"}}, {$group: ...}])
-        //          - Without index: scan 650K docs → 33 seconds
-        //          - With index: 1 B+ tree lookup → <1ms
-        //
-        // Prerequisites:
-        // - Index child loading (index.rs:195 - documented in commit 90045d8)
-        // - Query optimizer (see IMPLEMENTATION_QUERY_OPTIMIZER.md)
-        // - Range scan support (B+ tree leaf sibling pointers)
-        //
-        // Priority: Medium (correctness unaffected, but significant performance gain)
-        let docs = self.find(&serde_json::json!({}))?;
-
-        // Execute pipeline
-        pipeline.execute(docs)
+        let mut pipeline = Pipeline::from_json(pipeline_json)?;
+
+        // Versions only track this collection, so joins are never cached;
+        // neither is $out, whose write must happen on every run, nor a
+        // $sample, which draws a new sample every run
+        let cacheable = !pipeline.reads_other_collections()
+            && !pipeline.writes_collection()
+            && pipeline.is_deterministic();
+        let version = self.aggregation_cache.version(&self.name);
+        if cacheable {
+            if let Some(results) = self
+                .aggregation_cache
+                .get(&self.name, version, pipeline_json)
+            {
+                return Ok(results);
+            }
+        }
+
+        let results = if let Some(paths) = pipeline.leading_size_paths() {
+            pipeline.execute_sized(self.scan_array_sizes(&paths)?, self, writer)?
+        } else {
+            // A leading $match selects the input through the query planner,
+            // so a selective one on an indexed field reads only its matches
+            let query = pipeline
+                .take_leading_match()
+                .unwrap_or_else(|| serde_json::json!({}));
+            let docs = self.find_reading_once(&query)?;
+
+            // Execute pipeline
+            pipeline.execute_with(docs, self, writer)?
+        };
+
+        if cacheable {
+            self.aggregation_cache
+                .insert(&self.name, version, pipeline_json, results.clone());
+        }
+        Ok(results)
+    }
+
+    /// Streaming aggregation: a cursor producing the pipeline's results lazily
+    ///
+    /// Unlike [`CollectionCore::aggregate`], documents are read one at a time
+    /// and pass through the leading `$match`, `$project`, `$addFields`,
+    /// `$unwind`, `$redact`, `$replaceRoot`, `$skip` and `$limit` stages
+    /// without the collection being materialized; a `$limit` stops reading
+    /// once it is reached. The first stage of any other kind (`$group`,
+    /// `$sort`, ...) buffers what reaches it, and it and the stages after it
+    /// run on the first read. A leading `$match` selects the input through
+    /// the query planner, so selective pipelines can use an index.
+    /// Results are not cached.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let mut cursor = collection.aggregate_streaming(&json!([
+    ///     {"$match": {"status": "active"}},
+    ///     {"$project": {"name": 1}}
+    /// ]))?;
+    /// loop {
+    ///     let batch = cursor.next_chunk(500)?;
+    ///     if batch.is_empty() {
+    ///         break;
+    ///     }
+    ///     export(batch);
+    /// }
+    /// ```
+    pub fn aggregate_streaming(&self, pipeline_json: &Value) -> Result<AggregateCursor<'_, S>> {
+        use crate::aggregation::Pipeline;
+
+        let mut pipeline = Pipeline::from_json(pipeline_json)?;
+        // A leading $match selects the input instead of being run again
+        let query = pipeline
+            .take_leading_match()
+            .unwrap_or_else(|| serde_json::json!({}));
+        let (stages, rest) = pipeline.into_streaming();
+
+        // Match-all takes the catalog as is; the cursor reads each document
+        let doc_ids = if Self::query_matches_all(&query) {
+            self.catalog_doc_ids()?
+        } else {
+            self.collect_doc_ids(&query)?
+        };
+        Ok(AggregateCursor {
+            source: FindCursor::new(self, doc_ids),
+            stages,
+            rest,
+            ready: VecDeque::new(),
+            batch_size: 100,
+        })
+    }
+
+    /// Read `_id` and the lengths of the arrays at `paths` from every live document
+    ///
+    /// Records are stream-parsed, so arrays are counted but never decoded.
+    fn scan_array_sizes(&self, paths: &[String]) -> Result<Vec<SizedDocument>> {
+        let mut storage = self.storage.write();
+        let offsets: Vec<u64> = storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?
+            .document_catalog
+            .values()
+            .copied()
+            .collect();
+
+        let mut results = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            let mut sizes = HashMap::new();
+            Metrics::incr(&self.metrics.documents_read);
+            let read = storage.read_data_with(offset, &mut |reader| {
+                let sized = parse_array_sizes(reader, paths)?;
+                sizes = sized.sizes;
+                Ok(sized.doc)
+            });
+            let Ok(doc) = read else {
+                continue; // Skip corrupted entries, as a full scan does
+            };
+
+            if doc
+                .get("_tombstone")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            results.push(SizedDocument { doc, sizes });
+        }
+
+        Ok(results)
     }
 
     // ========== INDEX OPERATIONS ==========
@@ -1474,9 +2935,13 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         indexes.create_compound_index(index_name.clone(), fields.clone(), unique)?;
 
         // Populate index with existing documents
-        let docs_by_id = {
-            drop(indexes); // Release write lock before acquiring storage lock
-            self.scan_documents_via_catalog()?
+        drop(indexes); // Release write lock before acquiring storage lock
+        let docs_by_id = match self.scan_documents_via_catalog() {
+            Ok(docs) => docs,
+            Err(e) => {
+                self.indexes.write().drop_index(&index_name)?;
+                return Err(e);
+            }
         };
 
         // 🚀 OPTIMIZED: Bulk load for compound index
@@ -1496,7 +2961,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
         // Build index from sorted entries - O(n)
         if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-            index.build_from_sorted(entries, unique)?;
+            if let Err(e) = index.build_from_sorted(entries, unique) {
+                // Existing duplicates: leave no partial index behind
+                indexes.drop_index(&index_name)?;
+                return Err(e);
+            }
         }
         drop(indexes); // Release index lock
 
@@ -1514,6 +2983,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                     num_keys: 0,
                     tree_height: 1,
                     root_offset: 0,
+                    bloom_filter: false,
+                    expire_after_secs: None,
                 };
 
                 meta.indexes.push(index_meta);
@@ -1545,9 +3016,13 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         indexes.create_btree_index(index_name.clone(), field.clone(), unique)?;
 
         // Populate index with existing documents
-        let docs_by_id = {
-            drop(indexes); // Release write lock before acquiring storage lock
-            self.scan_documents_via_catalog()?
+        drop(indexes); // Release write lock before acquiring storage lock
+        let docs_by_id = match self.scan_documents_via_catalog() {
+            Ok(docs) => docs,
+            Err(e) => {
+                self.indexes.write().drop_index(&index_name)?;
+                return Err(e);
+            }
         };
 
         // 🚀 OPTIMIZED: Bulk load instead of per-doc insert
@@ -1555,9 +3030,10 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         // instead of O(n²) from repeated Vec::insert() calls
         let mut entries: Vec<(IndexKey, DocumentId)> = docs_by_id
             .iter()
-            .filter_map(|(doc_id, doc)| {
-                get_nested_value(doc, &field)
-                    .map(|field_value| (IndexKey::from(field_value), doc_id.clone()))
+            .flat_map(|(doc_id, doc)| {
+                index_keys_for_field(doc, &field)
+                    .into_iter()
+                    .map(move |key| (key, doc_id.clone()))
             })
             .collect();
 
@@ -1567,7 +3043,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         // Re-acquire write lock and build index from sorted entries - O(n)
         let mut indexes = self.indexes.write();
         if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-            index.build_from_sorted(entries, unique)?;
+            if let Err(e) = index.build_from_sorted(entries, unique) {
+                // Existing duplicates: leave no partial index behind
+                indexes.drop_index(&index_name)?;
+                return Err(e);
+            }
         }
         drop(indexes); // Release index lock
 
@@ -1586,6 +3066,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                     num_keys: 0,
                     tree_height: 1,
                     root_offset: 0,
+                    bloom_filter: false,
+                    expire_after_secs: None,
                 };
 
                 // Add to persisted indexes list
@@ -1598,18 +3080,205 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 let db_file_path = storage.get_file_path().to_string();
                 drop(storage); // Release storage lock before acquiring index lock
 
-                if !db_file_path.is_empty() {
-                    let mut indexes = self.indexes.write();
-                    if let Some(index) = indexes.get_btree_index_mut(&index_name) {
-                        persist_index_to_disk(&db_file_path, &index_name, |file| {
-                            index.save_to_file(file)
-                        })?;
-                    }
+                if !db_file_path.is_empty() {
+                    let mut indexes = self.indexes.write();
+                    if let Some(index) = indexes.get_btree_index_mut(&index_name) {
+                        persist_index_to_disk(&db_file_path, &index_name, |file| {
+                            index.save_to_file(file)
+                        })?;
+                    }
+                }
+            }
+        }
+
+        Ok(index_name)
+    }
+
+    /// Create an index whose documents expire `expire_after_secs` after the
+    /// time stored in `field`
+    ///
+    /// `field` holds a Unix timestamp in seconds; documents where it is
+    /// missing or not a number never expire. Expired documents are deleted
+    /// by `DatabaseCore::run_ttl_sweep_now` or the background sweeper
+    /// started with `DatabaseCore::set_ttl_sweep_interval`.
+    pub fn create_ttl_index(&self, field: String, expire_after_secs: u64) -> Result<String> {
+        let index_name = self.create_index(field, false)?;
+        if let Some(index) = self.indexes.write().get_btree_index_mut(&index_name) {
+            index.metadata.expire_after_secs = Some(expire_after_secs);
+        }
+
+        let mut storage = self.storage.write();
+        if let Some(meta) = storage.get_collection_meta_mut(&self.name) {
+            if let Some(index_meta) = meta.indexes.iter_mut().find(|idx| idx.name == index_name) {
+                index_meta.expire_after_secs = Some(expire_after_secs);
+                storage.flush()?;
+            }
+        }
+
+        Ok(index_name)
+    }
+
+    /// Create the collection's full-text index over string fields
+    ///
+    /// Strings and arrays of strings under each of `fields` are split into
+    /// terms by `tokenizer`; other values are ignored. A collection has at
+    /// most one text index. Only its definition is persisted: the postings
+    /// live in memory and are rebuilt when the collection is opened.
+    pub fn create_text_index(&self, fields: Vec<String>, tokenizer: Tokenizer) -> Result<String> {
+        if fields.is_empty() {
+            return Err(MongoLiteError::IndexError(
+                "Text index must have at least one field".to_string(),
+            ));
+        }
+        let metadata = TextIndexMetadata {
+            name: format!("{}_{}_text", self.name, fields.join("_")),
+            fields,
+            tokenizer,
+        };
+        let index_name = metadata.name.clone();
+        self.indexes.write().create_text_index(metadata.clone())?;
+
+        // Populate from existing documents
+        let docs_by_id = match self.scan_documents_via_catalog() {
+            Ok(docs) => docs,
+            Err(e) => {
+                self.indexes.write().drop_index(&index_name)?;
+                return Err(e);
+            }
+        };
+        {
+            let mut indexes = self.indexes.write();
+            if let Some(text_index) = indexes.text_index_mut() {
+                for doc in docs_by_id.values() {
+                    text_index.insert(&Document::from_value(doc)?);
+                }
+            }
+        }
+
+        let mut storage = self.storage.write();
+        if let Some(meta) = storage.get_collection_meta_mut(&self.name) {
+            meta.text_index = Some(metadata);
+            storage.flush()?;
+        }
+
+        Ok(index_name)
+    }
+
+    /// Search the text index, best matches first
+    ///
+    /// Each returned document carries its BM25 relevance score under
+    /// `_score`. `limit` caps the number of documents read.
+    pub fn fulltext_search(
+        &self,
+        query: &str,
+        mode: TextSearchMode,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>> {
+        Metrics::incr(&self.metrics.queries);
+        let hits = self.text_search_hits(query, mode)?;
+
+        let mut results = Vec::new();
+        for (doc_id, score) in hits {
+            if limit.is_some_and(|limit| results.len() >= limit) {
+                break;
+            }
+            if let Some(mut doc) = self.read_document_by_id(&doc_id)? {
+                if let Some(fields) = doc.as_object_mut() {
+                    fields.insert("_score".to_string(), Value::from(score));
+                }
+                results.push(doc);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Ranked `(id, score)` hits from the text index
+    fn text_search_hits(
+        &self,
+        query: &str,
+        mode: TextSearchMode,
+    ) -> Result<Vec<(DocumentId, f64)>> {
+        let indexes = self.indexes.read();
+        let text_index = indexes.text_index().ok_or_else(|| {
+            MongoLiteError::IndexError(format!("Collection '{}' has no text index", self.name))
+        })?;
+        Metrics::incr(&self.metrics.index_hits);
+        Ok(text_index.search(query, mode))
+    }
+
+    /// Ids matching a query with a top-level `$text`, in relevance order
+    ///
+    /// The text index supplies the candidates; the rest of the filter is
+    /// checked against each document. As in `collect_doc_ids_with_options`,
+    /// `skip`/`limit` are left to the caller when there is a `sort`.
+    fn collect_text_doc_ids(
+        &self,
+        query_json: &Value,
+        text_filter: &Value,
+        sort: Option<&[(String, i32)]>,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<DocumentId>> {
+        let (search, mode) = parse_text_filter(text_filter)?;
+        let mut rest = query_json.clone();
+        if let Some(filter) = rest.as_object_mut() {
+            filter.remove("$text");
+        }
+        let match_all = Self::query_matches_all(&rest);
+        let parsed_query = Query::from_json(&rest)?;
+        let (skip, limit) = if sort.is_some() {
+            (0, None)
+        } else {
+            (skip, limit)
+        };
+
+        let mut doc_ids = Vec::new();
+        let mut skipped = 0usize;
+        for (doc_id, _) in self.text_search_hits(&search, mode)? {
+            if limit.is_some_and(|limit| doc_ids.len() >= limit) {
+                break;
+            }
+            if !match_all {
+                let Some(doc) = self.read_document_by_id(&doc_id)? else {
+                    continue;
+                };
+                if !parsed_query.matches(&Document::from_value(&doc)?) {
+                    continue;
                 }
             }
+            if skipped < skip {
+                skipped += 1;
+                continue;
+            }
+            doc_ids.push(doc_id);
         }
+        Ok(doc_ids)
+    }
 
-        Ok(index_name)
+    /// Carry single-field indexes along with a `$rename` of their field
+    ///
+    /// The new field gets an index with the same uniqueness unless it already
+    /// leads one; the old field's index is dropped once no document still
+    /// has the old field.
+    pub(crate) fn migrate_renamed_indexes(&self, update_json: &Value) -> Result<()> {
+        let Some(renames) = update_json.get("$rename").and_then(Value::as_object) else {
+            return Ok(());
+        };
+        for (from, to) in renames {
+            let Some(to) = to.as_str() else { continue };
+            let Some(old_index) = self.has_index_on(from).filter(|info| !info.is_compound()) else {
+                continue;
+            };
+            if self.has_index_on(to).is_none() {
+                self.create_index(to.to_string(), old_index.unique)?;
+            }
+            let old_field_left =
+                self.count_documents(&serde_json::json!({ from: {"$exists": true} }))? > 0;
+            if !old_field_left {
+                self.drop_index(&old_index.name)?;
+            }
+        }
+        Ok(())
     }
 
     /// Drop an index
@@ -1624,6 +3293,38 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             let mut storage = self.storage.write();
             if let Some(meta) = storage.get_collection_meta_mut(&self.name) {
                 meta.indexes.retain(|idx| idx.name != index_name);
+                if meta
+                    .text_index
+                    .as_ref()
+                    .is_some_and(|text| text.name == index_name)
+                {
+                    meta.text_index = None;
+                }
+                storage.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enable a bloom filter on an existing index
+    ///
+    /// Equality lookups and unique checks then skip the tree walk for keys the
+    /// filter rules out. The setting is persisted with the index metadata and
+    /// the filter is rebuilt whenever the index is loaded.
+    pub fn enable_bloom_filter(&self, index_name: &str) -> Result<()> {
+        {
+            let mut indexes = self.indexes.write();
+            let index = indexes.get_btree_index_mut(index_name).ok_or_else(|| {
+                MongoLiteError::IndexError(format!("Index not found: {}", index_name))
+            })?;
+            index.enable_bloom_filter();
+        }
+
+        let mut storage = self.storage.write();
+        if let Some(meta) = storage.get_collection_meta_mut(&self.name) {
+            if let Some(index_meta) = meta.indexes.iter_mut().find(|idx| idx.name == index_name) {
+                index_meta.bloom_filter = true;
                 storage.flush()?;
             }
         }
@@ -1631,12 +3332,58 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(())
     }
 
+    /// Whether an index value is present, consulting the bloom filter first
+    pub fn index_contains(&self, index_name: &str, value: &Value) -> Result<bool> {
+        let indexes = self.indexes.read();
+        let index = indexes.get_btree_index(index_name).ok_or_else(|| {
+            MongoLiteError::IndexError(format!("Index not found: {}", index_name))
+        })?;
+        Ok(index.contains_key(&IndexKey::from(value)))
+    }
+
     /// List all indexes
     pub fn list_indexes(&self) -> Vec<String> {
         let indexes = self.indexes.read();
         indexes.list_indexes()
     }
 
+    /// Find an index usable for queries on `field`
+    ///
+    /// Prefers a single-field index; otherwise returns a compound index
+    /// whose leading field is `field` (a compound index can only serve
+    /// queries on its prefix). Includes the built-in `_id` index.
+    pub fn has_index_on(&self, field: &str) -> Option<IndexInfo> {
+        let infos = self.indexes.read().index_infos();
+        let leading = |info: &IndexInfo| info.fields.first().map(String::as_str) == Some(field);
+        infos
+            .iter()
+            .find(|info| !info.is_compound() && leading(info))
+            .or_else(|| infos.iter().find(|info| leading(info)))
+            .cloned()
+    }
+
+    /// All fields covered by an index, sorted and without duplicates
+    ///
+    /// Every field of a compound index is listed; use `has_index_on` to
+    /// check whether a field leads an index.
+    pub fn indexed_fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = self
+            .indexes
+            .read()
+            .index_infos()
+            .into_iter()
+            .flat_map(|info| info.fields)
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    /// Describe every index of the collection, sorted by name
+    pub fn index_infos(&self) -> Vec<IndexInfo> {
+        self.indexes.read().index_infos()
+    }
+
     // ========== TRANSACTION OPERATIONS ==========
 
     /// Insert one document within a transaction
@@ -1680,11 +3427,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         for index_name in indexes.list_indexes() {
             // Get the index to extract field name
             if let Some(btree_index) = indexes.get_btree_index(&index_name) {
-                let field_name = &btree_index.metadata.field;
-
-                // Get the field value from the document
-                if let Some(key_value) = doc_with_id.get(field_name) {
-                    let key = crate::transaction::IndexKey::from(key_value);
+                // Get the key from the document's fields
+                if let Some(key) = tx_index_key(btree_index, |field| doc_with_id.get(field)) {
                     tx.add_index_change(
                         index_name.clone(),
                         crate::transaction::IndexChange {
@@ -1700,6 +3444,81 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(doc_id)
     }
 
+    /// Find documents as seen from inside a transaction (read committed)
+    ///
+    /// Returns committed documents with the transaction's own buffered
+    /// inserts, updates and deletes applied on top. Other transactions'
+    /// uncommitted operations are never visible, since they only exist in
+    /// their own buffers.
+    pub fn find_tx(
+        &self,
+        query_json: &Value,
+        tx: &crate::transaction::Transaction,
+    ) -> Result<Vec<Value>> {
+        use crate::transaction::Operation;
+
+        // Final state of every document this transaction touched
+        let mut touched: Vec<(DocumentId, Option<Value>)> = Vec::new();
+        for op in tx.operations() {
+            let (collection, doc_id, doc) = match op {
+                Operation::Insert {
+                    collection,
+                    doc_id,
+                    doc,
+                } => (collection, doc_id, Some(doc)),
+                Operation::Update {
+                    collection,
+                    doc_id,
+                    new_doc,
+                    ..
+                } => (collection, doc_id, Some(new_doc)),
+                Operation::Delete {
+                    collection, doc_id, ..
+                } => (collection, doc_id, None),
+            };
+            if collection != &self.name {
+                continue;
+            }
+            match touched.iter_mut().find(|(id, _)| id == doc_id) {
+                Some(entry) => entry.1 = doc.cloned(),
+                None => touched.push((doc_id.clone(), doc.cloned())),
+            }
+        }
+
+        let mut results = self.find(query_json)?;
+        if touched.is_empty() {
+            return Ok(results);
+        }
+
+        let touched_ids: HashSet<&DocumentId> = touched.iter().map(|(id, _)| id).collect();
+        results.retain(|doc| Self::value_doc_id(doc).is_none_or(|id| !touched_ids.contains(&id)));
+
+        let parsed_query = Query::from_json(query_json)?;
+        for (_, doc) in touched {
+            if let Some(doc) = doc {
+                if parsed_query.matches(&Document::from_value(&doc)?) {
+                    results.push(doc);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Find the first matching document as seen from inside a transaction
+    pub fn find_one_tx(
+        &self,
+        query_json: &Value,
+        tx: &crate::transaction::Transaction,
+    ) -> Result<Option<Value>> {
+        Ok(self.find_tx(query_json, tx)?.into_iter().next())
+    }
+
+    /// DocumentId of a document value, read from its `_id` field
+    fn value_doc_id(doc: &Value) -> Option<DocumentId> {
+        doc.get("_id")
+            .and_then(|id| serde_json::from_value(id.clone()).ok())
+    }
+
     /// Update one document within a transaction
     ///
     /// Note: Pass the new_doc directly (not update operators).
@@ -1713,8 +3532,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     ) -> Result<(u64, u64)> {
         use crate::transaction::Operation;
 
-        // Find the document first
-        let doc = self.find_one(query)?;
+        // Find the document first (including this transaction's own writes)
+        let doc = self.find_one_tx(query, tx)?;
 
         if let Some(old_doc) = doc {
             // Extract document ID from _id field
@@ -1758,19 +3577,16 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             let indexes = self.indexes.read();
             for index_name in indexes.list_indexes() {
                 if let Some(btree_index) = indexes.get_btree_index(&index_name) {
-                    let field_name = &btree_index.metadata.field;
-
-                    // Get old and new values
-                    let old_value = old_doc.get(field_name);
-                    let new_value = if let Value::Object(ref map) = new_doc_for_tracking {
-                        map.get(field_name)
-                    } else {
-                        None
-                    };
+                    // Get old and new keys
+                    let old_key = tx_index_key(btree_index, |field| old_doc.get(field));
+                    let new_key = tx_index_key(btree_index, |field| {
+                        new_doc_for_tracking
+                            .as_object()
+                            .and_then(|map| map.get(field))
+                    });
 
                     // Delete old key if exists
-                    if let Some(old_val) = old_value {
-                        let old_key = crate::transaction::IndexKey::from(old_val);
+                    if let Some(old_key) = old_key {
                         tx.add_index_change(
                             index_name.clone(),
                             crate::transaction::IndexChange {
@@ -1782,8 +3598,7 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                     }
 
                     // Insert new key if exists
-                    if let Some(new_val) = new_value {
-                        let new_key = crate::transaction::IndexKey::from(new_val);
+                    if let Some(new_key) = new_key {
                         tx.add_index_change(
                             index_name.clone(),
                             crate::transaction::IndexChange {
@@ -1813,8 +3628,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     ) -> Result<u64> {
         use crate::transaction::Operation;
 
-        // Find the document first
-        let doc = self.find_one(query)?;
+        // Find the document first (including this transaction's own writes)
+        let doc = self.find_one_tx(query, tx)?;
 
         if let Some(old_doc) = doc {
             // Extract document ID from _id field
@@ -1842,11 +3657,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             let indexes = self.indexes.read();
             for index_name in indexes.list_indexes() {
                 if let Some(btree_index) = indexes.get_btree_index(&index_name) {
-                    let field_name = &btree_index.metadata.field;
-
                     // Delete key from index if exists
-                    if let Some(old_val) = old_doc.get(field_name) {
-                        let old_key = crate::transaction::IndexKey::from(old_val);
+                    if let Some(old_key) = tx_index_key(btree_index, |field| old_doc.get(field)) {
                         tx.add_index_change(
                             index_name.clone(),
                             crate::transaction::IndexChange {
@@ -1885,6 +3697,12 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         // O(1) lookup in document_catalog (direct DocumentId lookup - no serialization!)
         if let Some(&offset) = meta.document_catalog.get(doc_id) {
             log_trace!("Found doc_id {:?} at offset {}", doc_id, offset);
+            let generation = storage.layout_generation();
+            if let Some(doc) = self.read_cache.get(doc_id, generation, offset) {
+                return Ok(Some(doc));
+            }
+
+            Metrics::incr(&self.metrics.documents_read);
             let doc_bytes = storage.read_data(offset)?;
             let doc: Value = serde_json::from_slice(&doc_bytes)?;
 
@@ -1898,6 +3716,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 return Ok(None);
             }
 
+            self.read_cache
+                .insert(doc_id.clone(), generation, offset, doc.clone());
             Ok(Some(doc))
         } else {
             log_trace!(
@@ -1912,8 +3732,11 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
     /// Scan documents via document_catalog instead of full file scan
     /// Much faster than scan_documents() for large collections
     fn scan_documents_via_catalog(&self) -> Result<HashMap<DocumentId, Value>> {
-        let mut storage = self.storage.write();
+        self.scan_catalog_locked(&mut self.storage.write())
+    }
 
+    /// `scan_documents_via_catalog` for a caller already holding the storage lock
+    fn scan_catalog_locked(&self, storage: &mut S) -> Result<HashMap<DocumentId, Value>> {
         // Clone the catalog to avoid borrow checker issues
         let catalog = {
             let meta = storage
@@ -1931,6 +3754,7 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
 
         // Iterate over catalog instead of sequential file scan (direct DocumentId iteration!)
         for (doc_id, offset) in &catalog {
+            Metrics::incr(&self.metrics.documents_read);
             match storage.read_data(*offset) {
                 Ok(doc_bytes) => {
                     // Try to deserialize JSON - skip if corrupt
@@ -1961,6 +3785,9 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         &self,
         doc_ids: &[DocumentId],
     ) -> Result<HashMap<DocumentId, Value>> {
+        if doc_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
         let mut storage = self.storage.write();
         let meta = storage
             .get_collection_meta(&self.name)
@@ -1981,6 +3808,7 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         let mut docs_by_id: HashMap<DocumentId, Value> = HashMap::with_capacity(offsets.len());
 
         for (doc_id, offset) in offsets {
+            Metrics::incr(&self.metrics.documents_read);
             match storage.read_data(offset) {
                 Ok(doc_bytes) => {
                     if let Ok(doc) = serde_json::from_slice::<Value>(&doc_bytes) {
@@ -2001,64 +3829,97 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
         Ok(docs_by_id)
     }
 
+    /// Every catalogued id in file order, without reading a document
+    ///
+    /// Deleted documents can still be listed; readers skip their tombstones.
+    fn catalog_doc_ids(&self) -> Result<Vec<DocumentId>> {
+        let storage = self.storage.read();
+        let meta = storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        let mut entries: Vec<(&DocumentId, &u64)> = meta.document_catalog.iter().collect();
+        entries.sort_by_key(|(_, &offset)| offset);
+        Ok(entries.into_iter().map(|(id, _)| id.clone()).collect())
+    }
+
     fn collect_doc_ids(&self, query_json: &Value) -> Result<Vec<DocumentId>> {
-        let (ids, _) =
-            self.collect_doc_ids_with_options(query_json, None, None, false, 0, None, true)?;
+        let (ids, _) = self.collect_doc_ids_with_options(query_json, None, None, 0, None, true)?;
         Ok(ids)
     }
 
+    /// Ids of documents matching `query_json`, and how many leading keys of
+    /// `sort` their order already satisfies
+    ///
+    /// With a `sort`, `skip`/`limit` are applied only when the index order
+    /// serves every sort key; otherwise every match is returned for the
+    /// caller to sort and paginate.
     fn collect_doc_ids_with_options(
         &self,
         query_json: &Value,
         hint: Option<&str>,
-        sort_field: Option<&str>,
-        sort_desc: bool,
+        sort: Option<&[(String, i32)]>,
         skip: usize,
         limit: Option<usize>,
         use_cache: bool,
-    ) -> Result<(Vec<DocumentId>, bool)> {
-        let cache_hash = if use_cache
-            && hint.is_none()
-            && sort_field.is_none()
-            && skip == 0
-            && limit.is_none()
-        {
-            Some(QueryHash::new(&self.name, query_json))
-        } else {
-            None
-        };
+    ) -> Result<(Vec<DocumentId>, usize)> {
+        // Text queries are answered by the text index, never from the cache
+        if let Some(text_filter) = query_json.get("$text") {
+            let ids = self.collect_text_doc_ids(query_json, text_filter, sort, skip, limit)?;
+            return Ok((ids, 0));
+        }
+
+        let cache_hash =
+            if use_cache && hint.is_none() && sort.is_none() && skip == 0 && limit.is_none() {
+                Some(QueryHash::new(&self.name, query_json))
+            } else {
+                None
+            };
 
         if let Some(hash) = cache_hash {
             if let Some(cached) = self.query_cache.get(&hash) {
-                return Ok((cached, false));
+                Metrics::incr(&self.metrics.cache_hits);
+                return Ok((cached, 0));
             }
+            Metrics::incr(&self.metrics.cache_misses);
         }
 
         let parsed_query = Query::from_json(query_json)?;
 
         let plan = if let Some(hint_name) = hint {
-            let field = self.extract_field_from_index_name(hint_name);
+            let field = self.index_leading_field(hint_name)?;
             Some(self.create_plan_for_hint(query_json, hint_name, &field)?)
         } else {
-            let indexes = self.indexes.read();
-            let available_indexes = indexes.list_indexes();
-            drop(indexes);
-            QueryPlanner::analyze_query(query_json, &available_indexes).map(|(_, plan)| plan)
+            self.plan_query(query_json)
         };
 
-        let (doc_ids_vec, used_sort) = if let Some(plan) = plan {
-            self.collect_doc_ids_from_plan(&parsed_query, plan, sort_field, sort_desc, skip, limit)?
+        let (doc_ids_vec, sorted_keys) = if let Some(plan) = plan {
+            Metrics::incr(&self.metrics.index_hits);
+            let (doc_ids, sorted_keys) =
+                self.collect_doc_ids_from_plan(&parsed_query, plan, sort, skip, limit)?;
+            if self.verify_reads {
+                let complete = skip == 0 && limit.is_none();
+                self.verify_index_result(query_json, &parsed_query, &doc_ids, complete)?;
+            }
+            (doc_ids, sorted_keys)
         } else {
             // Fallback to full scan using catalog
+            Metrics::incr(&self.metrics.collection_scans);
             let docs_by_id = self.scan_documents_via_catalog()?;
             log_debug!(
                 "scan_documents_via_catalog returned {} documents",
                 docs_by_id.len()
             );
+            let (skip, limit) = if sort.is_some() {
+                (0, None)
+            } else {
+                (skip, limit)
+            };
             let mut doc_ids = Vec::new();
             let mut skipped = 0usize;
+            let mut examined = 0usize;
 
             for (doc_id, doc) in docs_by_id {
+                examined += 1;
                 // 🚀 OPTIMIZED: Direct Value → Document conversion
                 // Avoids Value → String → Document round-trip serialization
                 let document = Document::from_value(&doc)?;
@@ -2077,7 +3938,8 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 }
             }
 
-            (doc_ids, false)
+            self.log_if_slow_scan(query_json, examined);
+            (doc_ids, 0)
         };
 
         if let Some(hash) = cache_hash {
@@ -2085,70 +3947,227 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
                 .insert(&self.name, hash, doc_ids_vec.clone());
         }
 
-        Ok((doc_ids_vec, used_sort))
+        Ok((doc_ids_vec, sorted_keys))
     }
 
-    fn collect_doc_ids_from_plan(
+    /// Shadow read: compare the ids an index plan produced with a full scan
+    ///
+    /// Returned ids that don't match the query are always reported; matching
+    /// documents the index missed only when the result is `complete` (no
+    /// skip or limit).
+    fn verify_index_result(
         &self,
+        query_json: &Value,
         parsed_query: &Query,
-        plan: QueryPlan,
-        sort_field: Option<&str>,
-        sort_desc: bool,
-        skip: usize,
-        limit: Option<usize>,
-    ) -> Result<(Vec<DocumentId>, bool)> {
-        let mut doc_ids = {
-            let indexes = self.indexes.read();
-            match plan {
-                QueryPlan::IndexScan {
-                    ref index_name,
-                    ref key,
-                    ..
-                } => {
-                    if let Some(index) = indexes.get_btree_index(index_name) {
-                        index.range_scan(key, key, true, true)
-                    } else {
-                        vec![]
+        index_ids: &[DocumentId],
+        complete: bool,
+    ) -> Result<()> {
+        let expected = self.shadow_scan(parsed_query)?;
+        let returned: HashSet<&DocumentId> = index_ids.iter().collect();
+
+        let mut extra: Vec<&DocumentId> = returned
+            .iter()
+            .copied()
+            .filter(|id| !expected.contains(*id))
+            .collect();
+        let mut missing: Vec<&DocumentId> = if complete {
+            expected
+                .iter()
+                .filter(|id| !returned.contains(id))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if extra.is_empty() && missing.is_empty() {
+            return Ok(());
+        }
+
+        extra.sort_by_key(|id| format!("{:?}", id));
+        missing.sort_by_key(|id| format!("{:?}", id));
+        Self::report_shadow_mismatch(format!(
+            "Shadow read mismatch on '{}' for {}: index returned non-matching {:?}, missed {:?}",
+            self.name, query_json, extra, missing
+        ));
+        Ok(())
+    }
+
+    /// Ids of the documents matching `parsed_query`, by full scan
+    fn shadow_scan(&self, parsed_query: &Query) -> Result<HashSet<DocumentId>> {
+        let mut matching = HashSet::new();
+        for (doc_id, doc) in self.scan_documents_via_catalog()? {
+            if parsed_query.matches(&Document::from_value(&doc)?) {
+                matching.insert(doc_id);
+            }
+        }
+        Ok(matching)
+    }
+
+    fn report_shadow_mismatch(message: String) {
+        if cfg!(test) {
+            panic!("{}", message);
+        }
+        log_error!("{}", message);
+    }
+
+    /// Slow query log: warn when a full scan examined more documents than the threshold
+    fn log_if_slow_scan(&self, query_json: &Value, examined: usize) {
+        let Some(threshold) = self.slow_query_threshold else {
+            return;
+        };
+        if examined <= threshold {
+            return;
+        }
+
+        let mut fields = Vec::new();
+        Self::collect_filtered_fields(query_json, &mut fields);
+        if fields.is_empty() {
+            return;
+        }
+        log_warn!(
+            "Slow query on '{}': full collection scan examined {} documents (threshold {}); consider an index on {}",
+            self.name,
+            examined,
+            threshold,
+            fields
+                .iter()
+                .map(|f| format!("'{}'", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    /// Field names a query filters on, descending into $and/$or/$nor
+    fn collect_filtered_fields(query_json: &Value, fields: &mut Vec<String>) {
+        let Value::Object(map) = query_json else {
+            return;
+        };
+        for (key, value) in map {
+            if matches!(key.as_str(), "$and" | "$or" | "$nor") {
+                if let Value::Array(clauses) = value {
+                    for clause in clauses {
+                        Self::collect_filtered_fields(clause, fields);
                     }
                 }
-                QueryPlan::IndexRangeScan {
-                    ref index_name,
-                    ref start,
-                    ref end,
-                    inclusive_start,
-                    inclusive_end,
-                    ..
-                } => {
-                    if let Some(index) = indexes.get_btree_index(index_name) {
-                        let default_start = IndexKey::Null;
-                        let default_end = IndexKey::String("\u{10ffff}".repeat(100));
-
-                        let start_key = start.as_ref().unwrap_or(&default_start);
-                        let end_key = end.as_ref().unwrap_or(&default_end);
-                        index.range_scan(start_key, end_key, inclusive_start, inclusive_end)
-                    } else {
-                        vec![]
-                    }
+            } else if !key.starts_with('$') && !fields.contains(key) {
+                fields.push(key.clone());
+            }
+        }
+    }
+
+    /// Ids an index plan yields before the query is checked (may repeat
+    /// for multikey indexes; empty for a collection scan)
+    fn index_candidates(&self, plan: &QueryPlan) -> Vec<DocumentId> {
+        let indexes = self.indexes.read();
+        match *plan {
+            QueryPlan::IndexScan {
+                ref index_name,
+                ref key,
+                ..
+            } => {
+                if let Some(index) = indexes.get_btree_index(index_name) {
+                    index.lookup(key)
+                } else {
+                    vec![]
                 }
-                QueryPlan::CollectionScan => vec![],
             }
-        };
+            QueryPlan::IndexRangeScan {
+                ref index_name,
+                ref start,
+                ref end,
+                inclusive_start,
+                inclusive_end,
+                ..
+            } => {
+                if let Some(index) = indexes.get_btree_index(index_name) {
+                    let default_start = IndexKey::Null;
+                    let default_end = IndexKey::String("\u{10ffff}".repeat(100));
+
+                    let start_key = start.as_ref().unwrap_or(&default_start);
+                    let end_key = end.as_ref().unwrap_or(&default_end);
+                    index.range_scan(start_key, end_key, inclusive_start, inclusive_end)
+                } else {
+                    vec![]
+                }
+            }
+            QueryPlan::CompoundRangeScan {
+                ref index_name,
+                ref prefix,
+                ref start,
+                ref end,
+                inclusive_start,
+                inclusive_end,
+                ..
+            } => {
+                if let Some(index) = indexes.get_btree_index(index_name) {
+                    index.compound_range_scan(
+                        prefix,
+                        start.as_ref(),
+                        end.as_ref(),
+                        inclusive_start,
+                        inclusive_end,
+                    )
+                } else {
+                    vec![]
+                }
+            }
+            QueryPlan::IndexExistsScan { ref index_name, .. } => {
+                if let Some(index) = indexes.get_btree_index(index_name) {
+                    index
+                        .get_all_entries()
+                        .into_iter()
+                        .map(|(_, doc_id)| doc_id)
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+            QueryPlan::CollectionScan => vec![],
+        }
+    }
 
-        let uses_index_sort = match (&plan, sort_field) {
-            (QueryPlan::IndexScan { ref field, .. }, Some(sf)) if field == sf => true,
-            (QueryPlan::IndexRangeScan { ref field, .. }, Some(sf)) if field == sf => true,
-            _ => false,
+    fn collect_doc_ids_from_plan(
+        &self,
+        parsed_query: &Query,
+        plan: QueryPlan,
+        sort: Option<&[(String, i32)]>,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> Result<(Vec<DocumentId>, usize)> {
+        let mut doc_ids = self.index_candidates(&plan);
+
+        let (sorted_keys, sort_desc) = match sort {
+            Some(sort) => self.index_sorted_keys(&plan, sort),
+            None => (0, false),
         };
 
-        if uses_index_sort && sort_desc {
+        // Nothing under the index keys: no document needs reading or checking
+        if doc_ids.is_empty() {
+            return Ok((Vec::new(), sorted_keys));
+        }
+
+        if sort_desc {
             doc_ids.reverse();
         }
 
+        // Unless the index order is the whole sort, the window is cut after
+        // the caller's sort
+        let fully_sorted = sort.is_none_or(|sort| sorted_keys == sort.len());
+        let (skip, limit) = if !fully_sorted {
+            (0, None)
+        } else {
+            (skip, limit)
+        };
+
         // Apply skip/limit while verifying query
         let mut results = Vec::new();
         let mut skipped = 0usize;
+        // Multikey indexes can list a document under several keys
+        let mut seen = HashSet::new();
 
         for doc_id in doc_ids {
+            if !seen.insert(doc_id.clone()) {
+                continue;
+            }
             if let Some(doc) = self.read_document_by_id(&doc_id)? {
                 let doc_json_str = serde_json::to_string(&doc)?;
                 let document = Document::from_json(&doc_json_str)?;
@@ -2169,7 +4188,49 @@ impl<S: Storage + RawStorage> CollectionCore<S> {
             }
         }
 
-        Ok((results, uses_index_sort))
+        Ok((results, sorted_keys))
+    }
+
+    /// How many leading keys of `sort` the plan's candidate order satisfies,
+    /// and whether the candidates must be reversed for it
+    ///
+    /// Fields a compound plan pins with an equality are constant, so they
+    /// count as sorted wherever they appear in `sort`. The other served keys
+    /// must follow the index field order and share one direction.
+    fn index_sorted_keys(&self, plan: &QueryPlan, sort: &[(String, i32)]) -> (usize, bool) {
+        let (constant, ordered): (Vec<String>, Vec<String>) = match *plan {
+            QueryPlan::IndexScan { ref field, .. }
+            | QueryPlan::IndexRangeScan { ref field, .. }
+            | QueryPlan::IndexExistsScan { ref field, .. } => (Vec::new(), vec![field.clone()]),
+            QueryPlan::CompoundRangeScan {
+                ref index_name,
+                ref prefix,
+                ..
+            } => {
+                let indexes = self.indexes.read();
+                let Some(index) = indexes.get_btree_index(index_name) else {
+                    return (0, false);
+                };
+                let fields = &index.metadata.fields;
+                let pinned = prefix.len().min(fields.len());
+                (fields[..pinned].to_vec(), fields[pinned..].to_vec())
+            }
+            QueryPlan::CollectionScan => return (0, false),
+        };
+
+        let mut next = ordered.iter();
+        let mut direction = None;
+        let mut sorted_keys = 0;
+        for (field, dir) in sort {
+            if !constant.contains(field) {
+                if next.next() != Some(field) || direction.is_some_and(|desc| desc != (*dir < 0)) {
+                    break;
+                }
+                direction = Some(*dir < 0);
+            }
+            sorted_keys += 1;
+        }
+        (sorted_keys, direction.unwrap_or(false))
     }
 
     fn query_matches_all(query_json: &Value) -> bool {
@@ -2348,3 +4409,86 @@ impl<'a, S: Storage + RawStorage> FindCursor<'a, S> {
         Ok(results)
     }
 }
+
+/// Cursor over aggregation results, returned by
+/// [`CollectionCore::aggregate_streaming`]
+///
+/// Reads source documents only as results are requested. The buffering
+/// part of the pipeline, if any, runs on the first read.
+pub struct AggregateCursor<'a, S: Storage + RawStorage> {
+    source: FindCursor<'a, S>,
+    stages: crate::aggregation::StreamingStages,
+    /// Pipeline from the first buffering stage on, not yet run
+    rest: Option<crate::aggregation::Pipeline>,
+    /// Results produced but not yet returned
+    ready: VecDeque<Value>,
+    /// Default batch size for chunk operations
+    batch_size: usize,
+}
+
+impl<'a, S: Storage + RawStorage> AggregateCursor<'a, S> {
+    /// Set the default batch size for chunk operations
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Fetch the next result, or None if exhausted
+    pub fn next(&mut self) -> Result<Option<Value>> {
+        if let Some(rest) = self.rest.take() {
+            let mut streamed = Vec::new();
+            while let Some(doc) = self.next_streamed()? {
+                streamed.push(doc);
+            }
+            let collection = self.source.collection;
+            self.ready = rest.execute_with(streamed, collection, collection)?.into();
+        }
+        self.next_streamed()
+    }
+
+    /// Next result of the streaming stages, reading source documents as needed
+    fn next_streamed(&mut self) -> Result<Option<Value>> {
+        loop {
+            if let Some(doc) = self.ready.pop_front() {
+                return Ok(Some(doc));
+            }
+            if self.stages.is_exhausted() {
+                return Ok(None);
+            }
+            match self.source.next()? {
+                Some(doc) => self.ready.extend(self.stages.push(doc)?),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Fetch the next chunk of results (up to `chunk_size`)
+    ///
+    /// Returns an empty Vec once the cursor is exhausted.
+    pub fn next_chunk(&mut self, chunk_size: usize) -> Result<Vec<Value>> {
+        let mut results = Vec::new();
+        while results.len() < chunk_size {
+            match self.next()? {
+                Some(doc) => results.push(doc),
+                None => break,
+            }
+        }
+        Ok(results)
+    }
+
+    /// Fetch the next chunk using the default batch size
+    pub fn next_batch(&mut self) -> Result<Vec<Value>> {
+        self.next_chunk(self.batch_size)
+    }
+
+    /// Collect all remaining results into a Vec
+    ///
+    /// Warning: This loads all remaining results into memory
+    pub fn collect_all(&mut self) -> Result<Vec<Value>> {
+        let mut results = Vec::new();
+        while let Some(doc) = self.next()? {
+            results.push(doc);
+        }
+        Ok(results)
+    }
+}
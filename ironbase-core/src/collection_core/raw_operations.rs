@@ -12,16 +12,21 @@
 //!
 //! If you need write operations, use DatabaseCore::insert_one(), etc.
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use serde_json::Value;
 
+use crate::bulk_write::{BulkOp, BulkWriteError, BulkWriteResult};
 use crate::document::{Document, DocumentId};
 use crate::error::{MongoLiteError, Result};
+use crate::index::IndexManager;
+use crate::metrics::Metrics;
 use crate::query::Query;
-use crate::storage::{RawStorage, Storage};
+use crate::storage::{CappedLimits, CollectionMeta, RawStorage, Storage};
+use crate::transaction::Operation;
 
-use super::{CollectionCore, InsertManyResult};
+use super::CollectionCore;
 
 /// Private module that seals the trait
 mod sealed {
@@ -50,9 +55,6 @@ pub(crate) trait RawOperations: sealed::Sealed {
     /// This bypasses durability. Use `DatabaseCore::insert_one()` instead.
     fn insert_one_raw(&self, fields: HashMap<String, Value>) -> Result<DocumentId>;
 
-    /// Insert many documents WITHOUT WAL protection
-    fn insert_many_raw(&self, documents: Vec<HashMap<String, Value>>) -> Result<InsertManyResult>;
-
     /// Update one document WITHOUT WAL protection
     fn update_one_raw(&self, query: &Value, update: &Value) -> Result<(u64, u64)>;
 
@@ -64,6 +66,90 @@ pub(crate) trait RawOperations: sealed::Sealed {
 
     /// Delete many documents WITHOUT WAL protection
     fn delete_many_raw(&self, query: &Value) -> Result<u64>;
+
+    /// Apply a batch of writes WITHOUT WAL protection
+    ///
+    /// The caller logs the returned operations to make them durable.
+    fn bulk_write_raw(&self, ops: &[BulkOp], ordered: bool) -> Result<BulkApplied>;
+
+    /// Exchange `field` between two documents WITHOUT WAL protection
+    ///
+    /// The caller logs the returned operations to make them durable.
+    fn swap_field_raw(
+        &self,
+        id_a: &DocumentId,
+        id_b: &DocumentId,
+        field: &str,
+    ) -> Result<Vec<Operation>>;
+}
+
+/// What `bulk_write_raw` did
+pub(crate) struct BulkApplied {
+    pub result: BulkWriteResult,
+    /// Every document change made, in order, for the WAL
+    pub operations: Vec<Operation>,
+    /// Error of the first failed operation
+    pub first_error: Option<MongoLiteError>,
+}
+
+/// Locks and documents shared by the operations of one bulk batch
+struct BulkBatch<'a, S> {
+    storage: &'a mut S,
+    indexes: &'a mut IndexManager,
+    /// Live documents, loaded by the first filter that isn't an `_id` lookup
+    /// and kept current as the batch writes
+    live: Option<HashMap<DocumentId, Value>>,
+}
+
+/// Take the document's `_id` from `fields` or generate one, keeping
+/// `meta.last_id` ahead of every numeric `_id` so auto ids never collide
+///
+/// An explicit `_id` already in the catalog is rejected here when the
+/// collection has no `_id` index to catch it, since the catalog (keyed by
+/// `_id`) would otherwise silently replace the earlier document.
+fn assign_document_id(
+    meta: &mut CollectionMeta,
+    fields: &mut HashMap<String, Value>,
+) -> Result<DocumentId> {
+    if let Some(existing_id) = fields.get("_id") {
+        let parsed_id: DocumentId = serde_json::from_value(existing_id.clone())
+            .map_err(|e| MongoLiteError::Serialization(format!("Invalid _id format: {}", e)))?;
+        if meta.skip_id_index && meta.document_catalog.contains_key(&parsed_id) {
+            return Err(MongoLiteError::DuplicateKey(format!(
+                "_id {} already exists in collection '{}'",
+                existing_id, meta.name
+            )));
+        }
+        if let DocumentId::Int(num) = parsed_id {
+            if num >= 0 && num as u64 > meta.last_id {
+                meta.last_id = num as u64;
+            }
+        }
+        Ok(parsed_id)
+    } else {
+        let new_id = DocumentId::new_auto(meta.last_id);
+        meta.last_id += 1;
+        // Add _id to fields for query matching
+        fields.insert("_id".to_string(), serde_json::to_value(&new_id).unwrap());
+        Ok(new_id)
+    }
+}
+
+/// Eviction order of a capped collection: integer ids ascending, then string ids
+fn capped_order(a: &DocumentId, b: &DocumentId) -> Ordering {
+    match (a, b) {
+        (DocumentId::Int(x), DocumentId::Int(y)) => x.cmp(y),
+        (DocumentId::Int(_), _) => Ordering::Less,
+        (_, DocumentId::Int(_)) => Ordering::Greater,
+        (
+            DocumentId::String(x) | DocumentId::ObjectId(x),
+            DocumentId::String(y) | DocumentId::ObjectId(y),
+        ) => x.cmp(y),
+    }
+}
+
+fn serialized_len(doc: &Value) -> Result<u64> {
+    Ok(serde_json::to_string(doc)?.len() as u64)
 }
 
 // ============================================================================
@@ -74,39 +160,15 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
     /// Insert one document (raw, no WAL) - use DatabaseCore::insert_one for durability
     /// For batch operations, use DurabilityMode::Batch
     fn insert_one_raw(&self, mut fields: HashMap<String, Value>) -> Result<DocumentId> {
-        let mut storage = self.storage.write();
+        let mut storage = self.lock_storage_for_write();
+        self.check_quota(&storage, 1)?;
 
         // Get mutable reference to collection metadata
         let meta = storage
             .get_collection_meta_mut(&self.name)
             .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
 
-        // Check if _id already exists in fields
-        let doc_id = if let Some(existing_id) = fields.get("_id") {
-            // Use existing _id from fields
-            let parsed_id: DocumentId = serde_json::from_value(existing_id.clone())
-                .map_err(|e| MongoLiteError::Serialization(format!("Invalid _id format: {}", e)))?;
-
-            // Ensure last_id tracks the highest numeric _id to avoid auto-ID collisions
-            if let DocumentId::Int(num) = parsed_id {
-                if num >= 0 {
-                    let numeric = num as u64;
-                    if numeric > meta.last_id {
-                        meta.last_id = numeric;
-                    }
-                }
-            }
-
-            parsed_id
-        } else {
-            // Auto-generate new _id
-            let new_id = DocumentId::new_auto(meta.last_id);
-            meta.last_id += 1;
-
-            // Add _id to fields for query matching
-            fields.insert("_id".to_string(), serde_json::to_value(&new_id).unwrap());
-            new_id
-        };
+        let doc_id = assign_document_id(meta, &mut fields)?;
 
         // Add _collection field for multi-collection isolation
         fields.insert("_collection".to_string(), Value::String(self.name.clone()));
@@ -114,12 +176,20 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
         // Dokumentum létrehozása
         let doc = Document::new(doc_id.clone(), fields);
         self.validate_document(&doc)?;
+        let doc_json = doc.to_json()?;
+        let capped = self.capped_fit(&storage, doc_json.len() as u64)?;
 
         // Update indexes BEFORE writing to storage
         self.add_to_indexes(&doc)?;
 
+        // Make room in a capped collection (not logged: the durable
+        // insert_one goes through bulk_write_raw for capped collections)
+        if let Some(limits) = capped {
+            let mut indexes = self.indexes.write();
+            self.evict_oldest(&mut storage, &mut indexes, limits, doc_json.len() as u64)?;
+        }
+
         // Szerializálás és írás - USE NEW write_document with catalog tracking
-        let doc_json = doc.to_json()?;
         storage.write_document_raw(&self.name, &doc_id, doc_json.as_bytes())?;
         storage.adjust_live_count(&self.name, 1);
 
@@ -131,104 +201,12 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
         // This prevents O(n) metadata rewrites on every insert
 
         // Invalidate query cache (collection has changed)
-        self.query_cache.invalidate_collection(&self.name);
+        self.invalidate_query_cache();
+        Metrics::incr(&self.metrics.inserts);
 
         Ok(doc_id)
     }
 
-    /// Insert many documents (raw, no WAL) - use DatabaseCore::insert_many for durability
-    /// For batch operations, use DurabilityMode::Batch
-    fn insert_many_raw(&self, documents: Vec<HashMap<String, Value>>) -> Result<InsertManyResult> {
-        if documents.is_empty() {
-            return Ok(InsertManyResult {
-                inserted_ids: Vec::new(),
-                inserted_count: 0,
-            });
-        }
-
-        let mut storage = self.storage.write();
-        let mut inserted_ids = Vec::with_capacity(documents.len());
-        let mut live_delta = 0i64;
-
-        // Get mutable reference to collection metadata ONCE
-        let meta = storage
-            .get_collection_meta_mut(&self.name)
-            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
-
-        // Get starting ID for auto-generation (don't pre-reserve)
-        let start_id = meta.last_id;
-
-        // Prepare all documents with IDs
-        let mut prepared_docs = Vec::with_capacity(documents.len());
-        let mut auto_id_count = 0u64;
-        for mut fields in documents.into_iter() {
-            // Check if _id already exists in fields (same logic as insert_one)
-            let doc_id = if let Some(existing_id) = fields.get("_id") {
-                // Use existing _id from fields - MongoDB compatible behavior
-                let parsed_id: DocumentId =
-                    serde_json::from_value(existing_id.clone()).map_err(|e| {
-                        MongoLiteError::Serialization(format!("Invalid _id format: {}", e))
-                    })?;
-
-                // Ensure last_id tracks highest numeric _id from manual inserts
-                if let DocumentId::Int(num) = parsed_id {
-                    if num >= 0 {
-                        let numeric = num as u64;
-                        if numeric > meta.last_id {
-                            meta.last_id = numeric;
-                        }
-                    }
-                }
-
-                parsed_id
-            } else {
-                // Auto-generate new _id only if not provided
-                let new_id = DocumentId::new_auto(start_id + auto_id_count);
-                auto_id_count += 1;
-                fields.insert("_id".to_string(), serde_json::to_value(&new_id).unwrap());
-                new_id
-            };
-
-            // Add _collection field
-            fields.insert("_collection".to_string(), Value::String(self.name.clone()));
-
-            // Create document
-            let doc = Document::new(doc_id.clone(), fields);
-            self.validate_document(&doc)?;
-            prepared_docs.push((doc_id.clone(), doc));
-            inserted_ids.push(doc_id);
-        }
-
-        // Update last_id with max of manual + auto-generated IDs
-        meta.last_id = meta.last_id.max(start_id + auto_id_count);
-
-        // Update indexes in batch BEFORE writing to storage
-        let docs_for_index: Vec<Document> =
-            prepared_docs.iter().map(|(_, doc)| doc.clone()).collect();
-        self.batch_add_to_indexes(&docs_for_index)?;
-
-        // Write all documents to storage
-        for (doc_id, doc) in prepared_docs {
-            let doc_json = doc.to_json()?;
-            storage.write_document_raw(&self.name, &doc_id, doc_json.as_bytes())?;
-            live_delta += 1;
-        }
-
-        // NOTE: We don't flush metadata here for performance!
-        // Catalog changes are kept in memory and flushed on database close
-
-        // Invalidate query cache (collection has changed)
-        self.query_cache.invalidate_collection(&self.name);
-        if live_delta != 0 {
-            storage.adjust_live_count(&self.name, live_delta);
-        }
-
-        Ok(InsertManyResult {
-            inserted_count: inserted_ids.len(),
-            inserted_ids,
-        })
-    }
-
     /// Update one document (raw, no WAL) - use DatabaseCore::update_one for durability
     /// Returns (matched_count, modified_count)
     fn update_one_raw(&self, query_json: &Value, update_json: &Value) -> Result<(u64, u64)> {
@@ -264,7 +242,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
         // Find first matching and update (skip tombstones already filtered by catalog scan)
         let mut matched = 0u64;
         let mut modified = 0u64;
-        let mut storage = self.storage.write();
+        let mut storage = self.lock_storage_for_write();
 
         for (_, doc) in docs_by_id {
             if matched > 0 {
@@ -282,7 +260,8 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
                 let original_document = document.clone();
 
                 // Apply update operators
-                let was_modified = self.apply_update_operators(&mut document, update_json)?;
+                let was_modified =
+                    self.apply_update_operators(&mut document, update_json, false)?;
 
                 if was_modified {
                     // ✅ Ensure updated document has _collection before constraint check
@@ -302,7 +281,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
                     self.add_to_indexes(&document)?;
 
                     // Re-acquire storage lock
-                    storage = self.storage.write();
+                    storage = self.lock_storage_for_write();
 
                     // Mark old document as tombstone
                     let mut tombstone = doc.clone();
@@ -312,18 +291,26 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
                     }
                     let tombstone_json = serde_json::to_string(&tombstone)?;
 
-                    // Write tombstone (no catalog tracking for tombstones)
-                    storage.write_data(tombstone_json.as_bytes())?;
-
                     self.validate_document(&document)?;
 
-                    // Write updated document WITH catalog tracking
                     let updated_json = document.to_json()?;
-                    storage.write_document_raw(
+
+                    // Reuse the padded slot if the new version fits
+                    if !storage.overwrite_document_in_place(
                         &self.name,
                         &document.id,
                         updated_json.as_bytes(),
-                    )?;
+                    )? {
+                        // Write tombstone (no catalog tracking for tombstones)
+                        storage.write_data(tombstone_json.as_bytes())?;
+
+                        // Write updated document WITH catalog tracking
+                        storage.write_document_raw(
+                            &self.name,
+                            &document.id,
+                            updated_json.as_bytes(),
+                        )?;
+                    }
                     storage.adjust_live_count(&self.name, -1);
                     storage.adjust_live_count(&self.name, 1);
 
@@ -334,8 +321,9 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
 
         // Invalidate query cache if any document was modified
         if modified > 0 {
-            self.query_cache.invalidate_collection(&self.name);
+            self.invalidate_query_cache();
         }
+        Metrics::add(&self.metrics.updates, modified);
 
         Ok((matched, modified))
     }
@@ -346,6 +334,9 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
         // 🚀 MAJOR OPTIMIZATION: Use index-based query to get matching doc IDs
         // This uses indexes when available (34ms vs 1.8s for 10K matching docs!)
         let doc_ids = self.collect_doc_ids(query_json)?;
+        if doc_ids.is_empty() {
+            return Ok((0, 0));
+        }
 
         let mut matched = 0u64;
         let mut modified = 0u64;
@@ -385,7 +376,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
             let original_document = document.clone();
 
             // Apply update operators
-            let was_modified = self.apply_update_operators(&mut document, update_json)?;
+            let was_modified = self.apply_update_operators(&mut document, update_json, false)?;
 
             if was_modified {
                 // ✅ Ensure updated document has _collection before constraint check
@@ -423,8 +414,10 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
 
         // Invalidate query cache if any document was modified
         if modified > 0 {
-            self.query_cache.invalidate_collection(&self.name);
+            self.invalidate_query_cache();
+            self.migrate_renamed_indexes(update_json)?;
         }
+        Metrics::add(&self.metrics.updates, modified);
 
         Ok((matched, modified))
     }
@@ -432,6 +425,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
     /// Delete one document (raw, no WAL) - use DatabaseCore::delete_one for durability
     /// Returns deleted_count
     fn delete_one_raw(&self, query_json: &Value) -> Result<u64> {
+        self.ensure_not_capped(&self.storage.read())?;
         let parsed_query = Query::from_json(query_json)?;
 
         // OPTIMIZATION: Try O(1) _id lookup first, fallback to full scan
@@ -442,7 +436,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
 
         // Find first matching and delete (skip tombstones already filtered by catalog scan)
         let mut deleted = 0u64;
-        let mut storage = self.storage.write();
+        let mut storage = self.lock_storage_for_write();
 
         for (_, doc) in docs_by_id {
             if deleted > 0 {
@@ -458,7 +452,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
                 // Drop storage lock temporarily to avoid potential deadlock
                 drop(storage);
                 self.remove_from_indexes(&document)?;
-                storage = self.storage.write();
+                storage = self.lock_storage_for_write();
 
                 // Mark as tombstone (logical delete)
                 let mut tombstone = doc.clone();
@@ -478,8 +472,9 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
 
         // Invalidate query cache if any document was deleted
         if deleted > 0 {
-            self.query_cache.invalidate_collection(&self.name);
+            self.invalidate_query_cache();
         }
+        Metrics::add(&self.metrics.deletes, deleted);
 
         Ok(deleted)
     }
@@ -487,9 +482,10 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
     /// Delete many documents (raw, no WAL) - use DatabaseCore::delete_many for durability
     /// Returns deleted_count
     fn delete_many_raw(&self, query_json: &Value) -> Result<u64> {
+        self.ensure_not_capped(&self.storage.read())?;
         let parsed_query = Query::from_json(query_json)?;
         let docs_by_id = self.scan_documents_via_catalog()?;
-        let mut storage = self.storage.write();
+        let mut storage = self.lock_storage_for_write();
 
         let mut deleted = 0u64;
 
@@ -512,7 +508,7 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
                 // Drop storage lock temporarily to avoid potential deadlock
                 drop(storage);
                 self.remove_from_indexes(&document)?;
-                storage = self.storage.write();
+                storage = self.lock_storage_for_write();
 
                 // Mark as tombstone (logical delete)
                 let mut tombstone = doc.clone();
@@ -530,10 +526,477 @@ impl<S: Storage + RawStorage> RawOperations for CollectionCore<S> {
 
         // Invalidate query cache if any document was deleted
         if deleted > 0 {
-            self.query_cache.invalidate_collection(&self.name);
+            self.invalidate_query_cache();
             storage.adjust_live_count(&self.name, -(deleted as i64));
         }
+        Metrics::add(&self.metrics.deletes, deleted);
 
         Ok(deleted)
     }
+
+    /// Apply a batch of writes (raw, no WAL) - use DatabaseCore::bulk_write for durability
+    ///
+    /// Holds the storage and index locks for the whole batch and invalidates
+    /// the query cache once at the end. Operations run in order; each sees
+    /// the effects of those before it. A failed operation is recorded in
+    /// `write_errors` and, when `ordered`, stops the batch. Changes already
+    /// made (including part of a failed `updateMany`/`deleteMany`) are kept.
+    fn bulk_write_raw(&self, ops: &[BulkOp], ordered: bool) -> Result<BulkApplied> {
+        let mut applied = BulkApplied {
+            result: BulkWriteResult::default(),
+            operations: Vec::new(),
+            first_error: None,
+        };
+        if ops.is_empty() {
+            return Ok(applied);
+        }
+
+        let mut storage = self.lock_storage_for_write();
+        // An over-quota batch is rejected whole, before anything is applied
+        let inserts = ops
+            .iter()
+            .filter(|op| matches!(op, BulkOp::InsertOne { .. }))
+            .count();
+        if inserts > 0 {
+            self.check_quota(&storage, inserts as u64)?;
+        }
+        let mut indexes = self.indexes.write();
+        let mut batch = BulkBatch {
+            storage: &mut *storage,
+            indexes: &mut indexes,
+            live: None,
+        };
+
+        for (index, op) in ops.iter().enumerate() {
+            if let Err(e) = self.apply_bulk_op(&mut batch, op, &mut applied) {
+                applied.result.write_errors.push(BulkWriteError {
+                    index,
+                    message: e.to_string(),
+                });
+                applied.first_error.get_or_insert(e);
+                if ordered {
+                    break;
+                }
+            }
+        }
+
+        if !applied.operations.is_empty() {
+            self.invalidate_query_cache();
+        }
+        let result = &applied.result;
+        Metrics::add(&self.metrics.inserts, result.inserted_count);
+        Metrics::add(&self.metrics.updates, result.modified_count);
+        Metrics::add(&self.metrics.deletes, result.deleted_count);
+
+        Ok(applied)
+    }
+
+    /// Swap a field between two documents (raw, no WAL) - use DatabaseCore::swap_field for durability
+    ///
+    /// Holds the storage and index locks for the whole swap. Both old
+    /// versions leave the indexes before either new version enters them, so
+    /// exchanging two values of a unique index never looks like a duplicate.
+    /// A field missing from one document is removed from the other.
+    fn swap_field_raw(
+        &self,
+        id_a: &DocumentId,
+        id_b: &DocumentId,
+        field: &str,
+    ) -> Result<Vec<Operation>> {
+        if field.is_empty() || field.split('.').next() == Some("_id") {
+            return Err(MongoLiteError::InvalidQuery(format!(
+                "swap_field: cannot swap '{}'",
+                field
+            )));
+        }
+        if id_a == id_b {
+            return Ok(Vec::new());
+        }
+
+        let mut storage = self.lock_storage_for_write();
+        let mut indexes = self.indexes.write();
+
+        let mut old_docs = Vec::with_capacity(2);
+        for doc_id in [id_a, id_b] {
+            let doc = self
+                .read_locked(&mut storage, doc_id)?
+                .ok_or(MongoLiteError::DocumentNotFound)?;
+            old_docs.push((doc_id.clone(), doc));
+        }
+
+        let originals = old_docs
+            .iter()
+            .map(|(_, doc)| Document::from_value(doc).map_err(MongoLiteError::from))
+            .collect::<Result<Vec<_>>>()?;
+        let values: Vec<Option<Value>> = originals
+            .iter()
+            .map(|doc| doc.get(field).cloned())
+            .collect();
+        if values[0] == values[1] {
+            return Ok(Vec::new());
+        }
+
+        let mut updated = Vec::with_capacity(2);
+        for (original, value) in originals.iter().zip(values.into_iter().rev()) {
+            let mut document = original.clone();
+            match value {
+                Some(value) => document.set_nested(field, value),
+                None => {
+                    document.remove_nested(field);
+                }
+            }
+            document.set("_collection".to_string(), Value::String(self.name.clone()));
+            self.validate_document(&document)?;
+            updated.push(document);
+        }
+
+        for original in &originals {
+            self.remove_from_index_manager(&mut indexes, original)?;
+        }
+        // Other documents can still collide through a compound index; on a
+        // violation put the original entries back before reporting it
+        let mut added = Vec::with_capacity(2);
+        let mut violation = None;
+        for document in &updated {
+            if let Err(e) = self.check_index_manager_constraints(&indexes, document, None) {
+                violation = Some(e);
+                break;
+            }
+            self.add_to_index_manager(&mut indexes, document)?;
+            added.push(document);
+        }
+        if let Some(e) = violation {
+            for document in added {
+                self.remove_from_index_manager(&mut indexes, document)?;
+            }
+            for original in &originals {
+                self.add_to_index_manager(&mut indexes, original)?;
+            }
+            return Err(e);
+        }
+
+        let mut operations = Vec::with_capacity(2);
+        for ((doc_id, old_doc), document) in old_docs.into_iter().zip(updated) {
+            let updated_json = document.to_json()?;
+            if !storage.overwrite_document_in_place(&self.name, &doc_id, updated_json.as_bytes())? {
+                let tombstone = self.tombstone_of(&old_doc);
+                storage.write_data(serde_json::to_string(&tombstone)?.as_bytes())?;
+                storage.write_document_raw(&self.name, &doc_id, updated_json.as_bytes())?;
+            }
+            operations.push(Operation::Update {
+                collection: self.name.clone(),
+                doc_id,
+                old_doc,
+                new_doc: serde_json::from_str(&updated_json)?,
+            });
+        }
+
+        self.invalidate_query_cache();
+        Metrics::add(&self.metrics.updates, operations.len() as u64);
+
+        Ok(operations)
+    }
+}
+
+// ============================================================================
+// BULK WRITE HELPERS (run with the batch's locks held)
+// ============================================================================
+
+impl<S: Storage + RawStorage> CollectionCore<S> {
+    fn apply_bulk_op(
+        &self,
+        batch: &mut BulkBatch<'_, S>,
+        op: &BulkOp,
+        applied: &mut BulkApplied,
+    ) -> Result<()> {
+        match op {
+            BulkOp::InsertOne { document } => self.bulk_insert(batch, document.clone(), applied),
+            BulkOp::UpdateOne { filter, update } => {
+                self.bulk_update(batch, filter, update, false, applied)
+            }
+            BulkOp::UpdateMany { filter, update } => {
+                self.bulk_update(batch, filter, update, true, applied)
+            }
+            BulkOp::DeleteOne { filter } => self.bulk_delete(batch, filter, false, applied),
+            BulkOp::DeleteMany { filter } => self.bulk_delete(batch, filter, true, applied),
+        }
+    }
+
+    fn bulk_insert(
+        &self,
+        batch: &mut BulkBatch<'_, S>,
+        mut fields: HashMap<String, Value>,
+        applied: &mut BulkApplied,
+    ) -> Result<()> {
+        let meta = batch
+            .storage
+            .get_collection_meta_mut(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        let doc_id = assign_document_id(meta, &mut fields)?;
+        fields.insert("_collection".to_string(), Value::String(self.name.clone()));
+
+        let doc = Document::new(doc_id.clone(), fields);
+        self.validate_document(&doc)?;
+        let doc_json = doc.to_json()?;
+        let capped = self.capped_fit(batch.storage, doc_json.len() as u64)?;
+        // Check first so a violation leaves no partial index entries
+        self.check_index_manager_constraints(batch.indexes, &doc, None)?;
+        self.add_to_index_manager(batch.indexes, &doc)?;
+
+        if let Some(limits) = capped {
+            let evicted =
+                self.evict_oldest(batch.storage, batch.indexes, limits, doc_json.len() as u64)?;
+            for (evicted_id, old_doc) in evicted {
+                if let Some(live) = &mut batch.live {
+                    live.remove(&evicted_id);
+                }
+                applied.operations.push(Operation::Delete {
+                    collection: self.name.clone(),
+                    doc_id: evicted_id,
+                    old_doc,
+                });
+            }
+        }
+
+        batch
+            .storage
+            .write_document_raw(&self.name, &doc_id, doc_json.as_bytes())?;
+        batch.storage.adjust_live_count(&self.name, 1);
+
+        let stored: Value = serde_json::from_str(&doc_json)?;
+        if let Some(live) = &mut batch.live {
+            live.insert(doc_id.clone(), stored.clone());
+        }
+        applied.result.inserted_ids.push(doc_id.clone());
+        applied.result.inserted_count += 1;
+        applied.operations.push(Operation::Insert {
+            collection: self.name.clone(),
+            doc_id,
+            doc: stored,
+        });
+        Ok(())
+    }
+
+    fn bulk_update(
+        &self,
+        batch: &mut BulkBatch<'_, S>,
+        filter: &Value,
+        update: &Value,
+        many: bool,
+        applied: &mut BulkApplied,
+    ) -> Result<()> {
+        for (doc_id, old_doc) in self.bulk_matches(batch, filter, many)? {
+            applied.result.matched_count += 1;
+
+            let mut document = Document::from_value(&old_doc)?;
+            let original_document = document.clone();
+            if !self.apply_update_operators(&mut document, update, false)? {
+                continue;
+            }
+            document.set("_collection".to_string(), Value::String(self.name.clone()));
+            self.check_index_manager_constraints(batch.indexes, &document, Some(&doc_id))?;
+            self.validate_document(&document)?;
+
+            self.remove_from_index_manager(batch.indexes, &original_document)?;
+            self.add_to_index_manager(batch.indexes, &document)?;
+
+            let updated_json = document.to_json()?;
+            if !batch.storage.overwrite_document_in_place(
+                &self.name,
+                &doc_id,
+                updated_json.as_bytes(),
+            )? {
+                let tombstone = self.tombstone_of(&old_doc);
+                batch
+                    .storage
+                    .write_data(serde_json::to_string(&tombstone)?.as_bytes())?;
+                batch
+                    .storage
+                    .write_document_raw(&self.name, &doc_id, updated_json.as_bytes())?;
+            }
+
+            let new_doc: Value = serde_json::from_str(&updated_json)?;
+            if let Some(live) = &mut batch.live {
+                live.insert(doc_id.clone(), new_doc.clone());
+            }
+            applied.result.modified_count += 1;
+            applied.operations.push(Operation::Update {
+                collection: self.name.clone(),
+                doc_id,
+                old_doc,
+                new_doc,
+            });
+        }
+        Ok(())
+    }
+
+    fn bulk_delete(
+        &self,
+        batch: &mut BulkBatch<'_, S>,
+        filter: &Value,
+        many: bool,
+        applied: &mut BulkApplied,
+    ) -> Result<()> {
+        self.ensure_not_capped(batch.storage)?;
+        for (doc_id, old_doc) in self.bulk_matches(batch, filter, many)? {
+            let document = Document::from_value(&old_doc)?;
+            self.remove_from_index_manager(batch.indexes, &document)?;
+
+            let tombstone = self.tombstone_of(&old_doc);
+            batch.storage.write_document_raw(
+                &self.name,
+                &doc_id,
+                serde_json::to_string(&tombstone)?.as_bytes(),
+            )?;
+            batch.storage.adjust_live_count(&self.name, -1);
+
+            if let Some(live) = &mut batch.live {
+                live.remove(&doc_id);
+            }
+            applied.result.deleted_count += 1;
+            applied.operations.push(Operation::Delete {
+                collection: self.name.clone(),
+                doc_id,
+                old_doc,
+            });
+        }
+        Ok(())
+    }
+
+    /// Documents matching `filter` (at most one unless `many`)
+    ///
+    /// `{"_id": ...}` filters read the one document through the catalog;
+    /// anything else is matched against the batch's live documents.
+    fn bulk_matches(
+        &self,
+        batch: &mut BulkBatch<'_, S>,
+        filter: &Value,
+        many: bool,
+    ) -> Result<Vec<(DocumentId, Value)>> {
+        let query = Query::from_json(filter)?;
+
+        if batch.live.is_none() {
+            if let Some(doc_id) = Self::extract_id_query(filter) {
+                let doc = self.read_locked(batch.storage, &doc_id)?;
+                return Ok(doc.map(|doc| (doc_id, doc)).into_iter().collect());
+            }
+            batch.live = Some(self.scan_catalog_locked(batch.storage)?);
+        }
+
+        let mut matches = Vec::new();
+        for (doc_id, doc) in batch.live.iter().flatten() {
+            if query.matches(&Document::from_value(doc)?) {
+                matches.push((doc_id.clone(), doc.clone()));
+                if !many {
+                    break;
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Read a live document through the catalog with the storage lock held
+    fn read_locked(&self, storage: &mut S, doc_id: &DocumentId) -> Result<Option<Value>> {
+        let meta = storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?;
+        let Some(&offset) = meta.document_catalog.get(doc_id) else {
+            return Ok(None);
+        };
+        Metrics::incr(&self.metrics.documents_read);
+        let doc: Value = serde_json::from_slice(&storage.read_data(offset)?)?;
+        let is_tombstone = doc
+            .get("_tombstone")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        Ok((!is_tombstone).then_some(doc))
+    }
+
+    /// Capped limits of the collection, if any, after checking a document of
+    /// `incoming_bytes` can fit them at all
+    fn capped_fit(&self, storage: &S, incoming_bytes: u64) -> Result<Option<CappedLimits>> {
+        let Some(limits) = storage
+            .get_collection_meta(&self.name)
+            .and_then(|meta| meta.capped)
+        else {
+            return Ok(None);
+        };
+        if let Some(max_bytes) = limits.max_bytes {
+            if incoming_bytes > max_bytes {
+                return Err(MongoLiteError::QuotaExceeded(format!(
+                    "document of {} bytes exceeds capped collection '{}' max_bytes {}",
+                    incoming_bytes, self.name, max_bytes
+                )));
+            }
+        }
+        Ok(Some(limits))
+    }
+
+    /// Tombstone the oldest documents until one more of `incoming_bytes` fits `limits`
+    ///
+    /// Returns the evicted documents, oldest first.
+    fn evict_oldest(
+        &self,
+        storage: &mut S,
+        indexes: &mut IndexManager,
+        limits: CappedLimits,
+        incoming_bytes: u64,
+    ) -> Result<Vec<(DocumentId, Value)>> {
+        let mut ids: Vec<DocumentId> = storage
+            .get_collection_meta(&self.name)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(self.name.clone()))?
+            .document_catalog
+            .keys()
+            .cloned()
+            .collect();
+        ids.sort_by(capped_order);
+
+        let mut live = storage.get_live_count(&self.name).unwrap_or(0);
+        let mut bytes = 0;
+        if limits.max_bytes.is_some() {
+            for doc_id in &ids {
+                if let Some(doc) = self.read_locked(storage, doc_id)? {
+                    bytes += serialized_len(&doc)?;
+                }
+            }
+        }
+        let over = |live: u64, bytes: u64| {
+            limits.max_documents.is_some_and(|max| live >= max)
+                || limits
+                    .max_bytes
+                    .is_some_and(|max| bytes + incoming_bytes > max)
+        };
+
+        let mut evicted = Vec::new();
+        for doc_id in ids {
+            if !over(live, bytes) {
+                break;
+            }
+            let Some(old_doc) = self.read_locked(storage, &doc_id)? else {
+                continue;
+            };
+            self.remove_from_index_manager(indexes, &Document::from_value(&old_doc)?)?;
+            let tombstone = self.tombstone_of(&old_doc);
+            storage.write_document_raw(
+                &self.name,
+                &doc_id,
+                serde_json::to_string(&tombstone)?.as_bytes(),
+            )?;
+            storage.adjust_live_count(&self.name, -1);
+
+            live = live.saturating_sub(1);
+            bytes = bytes.saturating_sub(serialized_len(&old_doc)?);
+            evicted.push((doc_id, old_doc));
+        }
+        Ok(evicted)
+    }
+
+    fn tombstone_of(&self, doc: &Value) -> Value {
+        let mut tombstone = doc.clone();
+        if let Value::Object(ref mut map) = tombstone {
+            map.insert("_tombstone".to_string(), Value::Bool(true));
+            map.insert("_collection".to_string(), Value::String(self.name.clone()));
+        }
+        tombstone
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use regex::Regex;
 use serde_json::Value;
@@ -31,9 +31,26 @@ impl PropertySchema {
 pub struct CompiledSchema {
     pub(super) required: Vec<String>,
     pub(super) properties: HashMap<String, PropertySchema>,
+    /// Every field named in `properties`, typed or not
+    pub(super) declared: HashSet<String>,
+    pub(super) additional: AdditionalProperties,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// How fields not declared in `properties` are treated (`additionalProperties`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdditionalProperties {
+    /// Any extra field is accepted (default)
+    Allowed,
+    /// `false`: extra fields are rejected
+    Forbidden,
+    /// `{"type": ...}`: extra fields must have this type
+    Typed(SchemaType),
+}
+
+/// Fields the database adds to every document; never "additional"
+const RESERVED_FIELDS: [&str; 2] = ["_id", "_collection"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SchemaType {
     String,
     Number,
@@ -106,11 +123,13 @@ impl CompiledSchema {
         }
 
         let mut properties = HashMap::new();
+        let mut declared = HashSet::new();
         if let Some(props) = obj.get("properties") {
             let props_obj = props.as_object().ok_or_else(|| {
                 MongoLiteError::SchemaError("properties must be an object".to_string())
             })?;
             for (field, spec) in props_obj {
+                declared.insert(field.clone());
                 if let Some(type_value) = spec.get("type") {
                     let type_str = type_value.as_str().ok_or_else(|| {
                         MongoLiteError::SchemaError(format!(
@@ -182,9 +201,35 @@ impl CompiledSchema {
             }
         }
 
+        let additional = match obj.get("additionalProperties") {
+            None | Some(Value::Bool(true)) => AdditionalProperties::Allowed,
+            Some(Value::Bool(false)) => AdditionalProperties::Forbidden,
+            Some(Value::Object(spec)) => {
+                let type_str = spec.get("type").and_then(Value::as_str).ok_or_else(|| {
+                    MongoLiteError::SchemaError(
+                        "additionalProperties object must have a string type".to_string(),
+                    )
+                })?;
+                let parsed_type = SchemaType::from_str(type_str).ok_or_else(|| {
+                    MongoLiteError::SchemaError(format!(
+                        "Unsupported type '{}' for additionalProperties",
+                        type_str
+                    ))
+                })?;
+                AdditionalProperties::Typed(parsed_type)
+            }
+            Some(_) => {
+                return Err(MongoLiteError::SchemaError(
+                    "additionalProperties must be a boolean or an object with a type".to_string(),
+                ))
+            }
+        };
+
         Ok(Self {
             required,
             properties,
+            declared,
+            additional,
         })
     }
 
@@ -203,6 +248,34 @@ impl CompiledSchema {
             }
         }
 
+        // Fields not declared in properties
+        if self.additional != AdditionalProperties::Allowed {
+            let mut extra: Vec<&String> = obj
+                .keys()
+                .filter(|k| !self.declared.contains(*k) && !RESERVED_FIELDS.contains(&k.as_str()))
+                .collect();
+            extra.sort();
+            match self.additional {
+                AdditionalProperties::Forbidden if !extra.is_empty() => {
+                    let names: Vec<&str> = extra.iter().map(|k| k.as_str()).collect();
+                    return Err(MongoLiteError::SchemaError(format!(
+                        "Fields not declared in properties: {}",
+                        names.join(", ")
+                    )));
+                }
+                AdditionalProperties::Typed(schema_type) => {
+                    if let Some(field) = extra.iter().find(|k| !schema_type.matches(&obj[**k])) {
+                        return Err(MongoLiteError::SchemaError(format!(
+                            "Additional field '{}' expected type {}",
+                            field,
+                            schema_type.as_str()
+                        )));
+                    }
+                }
+                _ => {}
+            }
+        }
+
         // Validate each property
         for (field, prop_schema) in &self.properties {
             if let Some(field_value) = obj.get(field) {
@@ -619,6 +692,59 @@ mod tests {
         assert!(compiled.validate(&doc).is_ok());
     }
 
+    #[test]
+    fn test_additional_properties_false_rejects_extra_fields() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "note": {}
+            },
+            "additionalProperties": false
+        });
+        let compiled = CompiledSchema::from_value(&schema).unwrap();
+
+        // Declared (even untyped) and database-managed fields are fine
+        let doc = json!({"_id": 1, "_collection": "users", "name": "Alice", "note": 3});
+        assert!(compiled.validate(&doc).is_ok());
+
+        let doc = json!({"name": "Alice", "nmae": "typo", "age": 30});
+        let err = compiled.validate(&doc).unwrap_err().to_string();
+        assert!(
+            err.contains("Fields not declared in properties: age, nmae"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_additional_properties_typed() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "additionalProperties": {"type": "number"}
+        });
+        let compiled = CompiledSchema::from_value(&schema).unwrap();
+
+        assert!(compiled
+            .validate(&json!({"name": "Alice", "age": 30, "score": 1.5}))
+            .is_ok());
+        let err = compiled
+            .validate(&json!({"name": "Alice", "age": "thirty"}))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Additional field 'age' expected type number"),
+            "{}",
+            err
+        );
+
+        for bad in [json!("no"), json!({"type": "date"}), json!({})] {
+            let schema = json!({"type": "object", "additionalProperties": bad});
+            assert!(CompiledSchema::from_value(&schema).is_err(), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_validate_optional_field_absent() {
         let schema = json!({
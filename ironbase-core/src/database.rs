@@ -1,18 +1,26 @@
 // ironbase-core/src/database.rs
 // Pure Rust database API - NO PyO3 dependencies
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-
-use crate::collection_core::{CollectionCore, RawOperations};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::aggregation::CollectionWriter;
+use crate::aggregation_cache::{AggregationCache, AggregationCacheStats};
+use crate::bulk_write::{BulkOp, BulkWriteResult};
+use crate::collection_core::{out_insert_ops, CollectionCore, RawOperations};
 use crate::document::DocumentId;
 use crate::durability::DurabilityMode;
 use crate::error::Result;
+use crate::field_interning::FieldInterner;
+use crate::metrics::{Metrics, MetricsSnapshot};
 use crate::storage::{MemoryStorage, RawStorage, Storage, StorageEngine};
 use crate::transaction::{Operation, Transaction, TransactionId};
+use crate::{log_debug, log_info, log_warn};
 use serde_json::Value;
 
 /// Internal trait to flush any pending batch buffers before metadata sync
@@ -29,6 +37,20 @@ impl BatchFlush for DatabaseCore<StorageEngine> {
     }
 }
 
+/// Lock table key; the id is JSON-encoded so an ObjectId and the same
+/// string passed back by a caller lock the same document
+fn lock_key(collection: &str, id: &DocumentId) -> (String, String) {
+    let id = serde_json::to_string(id).unwrap_or_default();
+    (collection.to_string(), id)
+}
+
+fn lock_conflict(key: &(String, String), owner: TransactionId) -> crate::error::MongoLiteError {
+    crate::error::MongoLiteError::WriteConflict(format!(
+        "document {} in '{}' is locked by transaction {}",
+        key.1, key.0, owner
+    ))
+}
+
 /// Convert transaction::IndexKey to index::IndexKey
 fn convert_index_key(tx_key: &crate::transaction::IndexKey) -> crate::index::IndexKey {
     match tx_key {
@@ -39,7 +61,122 @@ fn convert_index_key(tx_key: &crate::transaction::IndexKey) -> crate::index::Ind
         }
         crate::transaction::IndexKey::Bool(b) => crate::index::IndexKey::Bool(*b),
         crate::transaction::IndexKey::Null => crate::index::IndexKey::Null,
+        crate::transaction::IndexKey::Compound(keys) => {
+            crate::index::IndexKey::Compound(keys.iter().map(convert_index_key).collect())
+        }
+        crate::transaction::IndexKey::Decimal(d) => crate::index::IndexKey::Decimal(d.clone()),
+    }
+}
+
+/// What `insert_one_with_conflict` does when the document's `_id` is already taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Fail with the unique `_id` index error (same as `insert_one`)
+    #[default]
+    Error,
+    /// Overwrite the stored document with the new one
+    Replace,
+    /// Keep the stored document and return its `_id` without writing
+    Ignore,
+}
+
+/// Options for `DatabaseCore::open_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseOptions {
+    /// Durability mode (Safe by default)
+    pub durability: DurabilityMode,
+    /// WAL file or directory; None keeps the WAL next to the data file
+    pub wal_path: Option<PathBuf>,
+    /// Cross-check every index-driven query against a full scan (slow; for
+    /// debugging index maintenance)
+    pub verify_reads: bool,
+    /// Number of aggregation results to cache across calls (0 disables the cache)
+    pub aggregation_cache_size: usize,
+    /// Create new collections without the automatic `_id` index
+    /// (see `CollectionCore::set_id_index`)
+    pub skip_id_index: bool,
+    /// Fsync the data file on every commit as well as the WAL (off: the
+    /// data file is fsynced at checkpoints, and crash recovery replays the WAL)
+    pub sync_data_on_commit: bool,
+    /// Compact right after recovery when the fragmentation ratio exceeds
+    /// this share (see `StorageEngine::fragmentation_ratio`); None never does
+    pub compact_on_open: Option<f64>,
+    /// Distinct field names pooled by `CollectionCore::scan_interned`, shared
+    /// by every collection handle (0 disables interning)
+    pub field_interning: usize,
+}
+
+impl DatabaseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_durability(mut self, durability: DurabilityMode) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    pub fn with_wal_path(mut self, wal_path: impl Into<PathBuf>) -> Self {
+        self.wal_path = Some(wal_path.into());
+        self
+    }
+
+    pub fn with_verify_reads(mut self, verify_reads: bool) -> Self {
+        self.verify_reads = verify_reads;
+        self
+    }
+
+    pub fn with_aggregation_cache(mut self, capacity: usize) -> Self {
+        self.aggregation_cache_size = capacity;
+        self
+    }
+
+    pub fn with_skip_id_index(mut self, skip_id_index: bool) -> Self {
+        self.skip_id_index = skip_id_index;
+        self
+    }
+
+    pub fn with_sync_data_on_commit(mut self, sync_data_on_commit: bool) -> Self {
+        self.sync_data_on_commit = sync_data_on_commit;
+        self
+    }
+
+    pub fn with_compact_on_open(mut self, threshold: f64) -> Self {
+        self.compact_on_open = Some(threshold);
+        self
+    }
+
+    pub fn with_field_interning(mut self, max_names: usize) -> Self {
+        self.field_interning = max_names;
+        self
+    }
+}
+
+/// Update that turns `old` into exactly `new` (keeping `_id`)
+fn replacement_update(old: &Value, new: &HashMap<String, Value>) -> Value {
+    let set: serde_json::Map<String, Value> = new
+        .iter()
+        .filter(|(field, _)| field.as_str() != "_id")
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+    let unset: serde_json::Map<String, Value> = old
+        .as_object()
+        .into_iter()
+        .flat_map(|obj| obj.keys())
+        .filter(|field| {
+            !matches!(field.as_str(), "_id" | "_collection") && !new.contains_key(*field)
+        })
+        .map(|field| (field.clone(), Value::from("")))
+        .collect();
+
+    let mut update = serde_json::Map::new();
+    if !set.is_empty() {
+        update.insert("$set".to_string(), Value::Object(set));
     }
+    if !unset.is_empty() {
+        update.insert("$unset".to_string(), Value::Object(unset));
+    }
+    Value::Object(update)
 }
 
 /// Pure Rust IronBase Database - language-independent
@@ -65,6 +202,64 @@ pub struct DatabaseCore<S: Storage + RawStorage> {
 
     // NEW: Operation counter for Unsafe mode auto-checkpoint
     unsafe_op_counter: AtomicU64,
+
+    // Set while a compaction is rewriting the data file
+    compacting: AtomicBool,
+
+    // Held exclusively while a transaction commit is applied, so transactional
+    // reads never observe a half-applied commit
+    commit_lock: RwLock<()>,
+
+    // Documents locked with lock_for_update: (collection, JSON-encoded _id) -> owner
+    document_locks: RwLock<HashMap<(String, String), TransactionId>>,
+
+    // Operation counters, shared with every collection handle
+    metrics: Arc<Metrics>,
+
+    // Serializes the check-then-insert of insert_if_absent and upserts
+    conditional_insert_lock: Mutex<()>,
+
+    // Background TTL sweeper, if one is running
+    ttl_sweeper: Mutex<Option<TtlSweeper>>,
+
+    // Shadow-read index results against full scans (DatabaseOptions::verify_reads)
+    verify_reads: bool,
+
+    // Aggregation results keyed by collection write-version, shared with every handle
+    aggregation_cache: Arc<AggregationCache>,
+
+    // New collections skip the automatic _id index (DatabaseOptions::skip_id_index)
+    skip_id_index: bool,
+
+    // Field-name pool for scan_interned, shared with every handle (DatabaseOptions::field_interning)
+    field_interner: Arc<FieldInterner>,
+}
+
+/// Handle to the background TTL sweeper thread
+///
+/// Dropping it disconnects the stop channel, which ends the thread at its
+/// next wakeup; the thread itself only holds a weak reference to the database.
+struct TtlSweeper {
+    interval_secs: u64,
+    _stop: mpsc::Sender<()>,
+}
+
+/// Clears the compaction flag when dropped, so a failed compaction
+/// does not leave the database permanently locked.
+struct CompactionGuard<'a>(&'a AtomicBool);
+
+impl<'a> CompactionGuard<'a> {
+    fn acquire(flag: &'a AtomicBool) -> Result<Self> {
+        flag.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map_err(|_| crate::error::MongoLiteError::CompactionInProgress)?;
+        Ok(CompactionGuard(flag))
+    }
+}
+
+impl Drop for CompactionGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
 }
 
 // ============================================================================
@@ -76,65 +271,7 @@ impl DatabaseCore<StorageEngine> {
     ///
     /// This method is StorageEngine-specific because it handles WAL recovery.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path_str = path.as_ref().to_string_lossy().to_string();
-        let mut storage = StorageEngine::open(&path_str)?;
-
-        // Recover from WAL (includes both data and index changes)
-        let (_wal_entries, recovered_index_changes) = storage.recover_from_wal()?;
-
-        // NOTE: WAL recovery now uses write_document() which updates the catalog.
-        // The document_catalog is loaded from metadata by StorageEngine::open(),
-        // and recover_from_wal() properly updates it for any recovered operations.
-
-        // Create DatabaseCore instance with default Safe mode
-        let db = DatabaseCore {
-            storage: Arc::new(RwLock::new(storage)),
-            db_path: path_str,
-            next_tx_id: AtomicU64::new(1),
-            active_transactions: Arc::new(RwLock::new(std::collections::HashMap::new())),
-            durability_mode: DurabilityMode::default(), // Safe mode by default
-            batch_buffer: Arc::new(RwLock::new(Vec::new())),
-            unsafe_op_counter: AtomicU64::new(0),
-        };
-
-        // Apply recovered index changes to collections
-        // Group index changes by collection name
-        let mut changes_by_collection: HashMap<String, Vec<crate::storage::RecoveredIndexChange>> =
-            HashMap::new();
-
-        for change in recovered_index_changes {
-            // Group by collection name (now properly included in RecoveredIndexChange)
-            changes_by_collection
-                .entry(change.collection.clone())
-                .or_default()
-                .push(change);
-        }
-
-        // Apply changes to each collection's indexes
-        for (collection_name, changes) in changes_by_collection {
-            // Get collection (creates if doesn't exist)
-            if let Ok(collection) = db.collection(&collection_name) {
-                for change in changes {
-                    // Apply the index change to the collection's indexes
-                    let mut indexes = collection.indexes.write();
-                    if let Some(btree_index) = indexes.get_btree_index_mut(&change.index_name) {
-                        // Convert transaction::IndexKey to index::IndexKey
-                        let index_key = convert_index_key(&change.key);
-
-                        match change.operation {
-                            crate::transaction::IndexOperation::Insert => {
-                                btree_index.insert(index_key, change.doc_id)?;
-                            }
-                            crate::transaction::IndexOperation::Delete => {
-                                btree_index.delete(&index_key, &change.doc_id)?;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(db)
+        Self::open_with_options(path, DatabaseOptions::default())
     }
 
     /// Open or create database with explicit durability mode
@@ -174,8 +311,38 @@ impl DatabaseCore<StorageEngine> {
     /// # Ok::<(), ironbase_core::MongoLiteError>(())
     /// ```
     pub fn open_with_durability<P: AsRef<Path>>(path: P, mode: DurabilityMode) -> Result<Self> {
+        Self::open_with_options(path, DatabaseOptions::new().with_durability(mode))
+    }
+
+    /// Open or create database with explicit options
+    ///
+    /// Covers everything `open_with_durability` does, plus a custom WAL
+    /// location: with `wal_path` set, the WAL is created, written and
+    /// recovered from there instead of next to the data file. The WAL
+    /// directory is checked for writability before anything is opened.
+    /// With `compact_on_open`, a file whose fragmentation exceeds the
+    /// threshold is compacted once recovery has finished.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use ironbase_core::{DatabaseCore, DatabaseOptions, DurabilityMode};
+    /// use ironbase_core::storage::StorageEngine;
+    ///
+    /// let db = DatabaseCore::<StorageEngine>::open_with_options(
+    ///     "/data/app.mlite",
+    ///     DatabaseOptions::new()
+    ///         .with_durability(DurabilityMode::Safe)
+    ///         .with_wal_path("/fast-disk/wal"),
+    /// )?;
+    /// # Ok::<(), ironbase_core::MongoLiteError>(())
+    /// ```
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: DatabaseOptions) -> Result<Self> {
         let path_str = path.as_ref().to_string_lossy().to_string();
-        let mut storage = StorageEngine::open(&path_str)?;
+        let compact_on_open = options.compact_on_open;
+        let mut storage =
+            StorageEngine::open_with_wal_path(&path_str, options.wal_path.as_deref())?;
+        storage.set_sync_data_on_commit(options.sync_data_on_commit);
+        storage.set_in_place_updates(!options.durability.is_safe());
 
         // Recover from WAL (includes both data and index changes)
         let (_wal_entries, recovered_index_changes) = storage.recover_from_wal()?;
@@ -190,9 +357,19 @@ impl DatabaseCore<StorageEngine> {
             db_path: path_str,
             next_tx_id: AtomicU64::new(1),
             active_transactions: Arc::new(RwLock::new(std::collections::HashMap::new())),
-            durability_mode: mode,
+            durability_mode: options.durability,
             batch_buffer: Arc::new(RwLock::new(Vec::new())),
             unsafe_op_counter: AtomicU64::new(0),
+            compacting: AtomicBool::new(false),
+            commit_lock: RwLock::new(()),
+            document_locks: RwLock::new(HashMap::new()),
+            metrics: Arc::new(Metrics::default()),
+            conditional_insert_lock: Mutex::new(()),
+            ttl_sweeper: Mutex::new(None),
+            verify_reads: options.verify_reads,
+            aggregation_cache: Arc::new(AggregationCache::new(options.aggregation_cache_size)),
+            skip_id_index: options.skip_id_index,
+            field_interner: Arc::new(FieldInterner::new(options.field_interning)),
         };
 
         // Apply recovered index changes to collections
@@ -232,6 +409,27 @@ impl DatabaseCore<StorageEngine> {
             }
         }
 
+        if let Some(threshold) = compact_on_open {
+            let fragmentation = db.fragmentation_ratio()?;
+            if fragmentation > threshold {
+                let stats = db.compact()?;
+                log_info!(
+                    "Compacted {} on open: fragmentation {:.1}%, {} -> {} bytes, {} tombstones removed",
+                    db.db_path,
+                    fragmentation * 100.0,
+                    stats.size_before,
+                    stats.size_after,
+                    stats.tombstones_removed
+                );
+            } else {
+                log_debug!(
+                    "Skipped compaction on open of {}: fragmentation {:.1}%",
+                    db.db_path,
+                    fragmentation * 100.0
+                );
+            }
+        }
+
         Ok(db)
     }
 
@@ -241,12 +439,116 @@ impl DatabaseCore<StorageEngine> {
         storage.stats()
     }
 
+    /// On-disk size of a collection's live documents in bytes (StorageEngine-specific)
+    pub fn collection_size_bytes(&self, collection_name: &str) -> Result<u64> {
+        let mut storage = self.storage.write();
+        storage.collection_size_bytes(collection_name)
+    }
+
+    /// Share of the data file that compaction would reclaim (StorageEngine-specific)
+    pub fn fragmentation_ratio(&self) -> Result<f64> {
+        self.storage.write().fragmentation_ratio()
+    }
+
+    /// Pad new document records to a multiple of `block_size` bytes (StorageEngine-specific)
+    ///
+    /// In the Batch and Unsafe durability modes, updates that still fit the
+    /// padded slot are written in place instead of appending a new version
+    /// plus a tombstone. Safe mode always appends, so the previous version
+    /// survives a crash until the new one is committed. 0 disables padding.
+    pub fn set_document_padding(&self, block_size: usize) {
+        self.storage.write().set_padding_block_size(block_size);
+    }
+
+    /// Fsyncs issued on the WAL and the data file so far (StorageEngine-specific)
+    pub fn sync_counts(&self) -> crate::storage::SyncCounts {
+        self.storage.read().sync_counts()
+    }
+
     /// Storage compaction - removes tombstones and old document versions (StorageEngine-specific)
+    ///
+    /// Only one compaction may run at a time; a concurrent call fails fast with
+    /// `MongoLiteError::CompactionInProgress`. Writes issued meanwhile block on the
+    /// storage lock until the rewrite has finished.
     pub fn compact(&self) -> Result<crate::storage::CompactionStats> {
+        let _guard = CompactionGuard::acquire(&self.compacting)?;
         let mut storage = self.storage.write();
         storage.compact()
     }
 
+    /// Storage compaction that verifies the compacted file before swapping it in (StorageEngine-specific)
+    ///
+    /// Aborts with `MongoLiteError::Corruption` and keeps the original file if
+    /// the compacted output's live documents differ from the original's.
+    pub fn compact_verified(&self) -> Result<crate::storage::CompactionStats> {
+        let _guard = CompactionGuard::acquire(&self.compacting)?;
+        let mut storage = self.storage.write();
+        storage.compact_verified()
+    }
+
+    /// Write a full backup of every collection into `dest_dir` (StorageEngine-specific)
+    ///
+    /// The directory keeps a manifest of its backups; the returned entry's
+    /// version is the marker to pass to [`DatabaseCore::incremental_backup`].
+    /// Only documents are backed up, not indexes or collection options.
+    pub fn backup<P: AsRef<Path>>(&self, dest_dir: P) -> Result<crate::backup::BackupEntry> {
+        crate::backup::write_backup(dest_dir.as_ref(), self.backup_contents()?, None)
+    }
+
+    /// Back up only what changed since backup `since_version` of `dest_dir` (StorageEngine-specific)
+    ///
+    /// Added and modified documents are written in full; deleted documents
+    /// and dropped collections are recorded by name. Every document is
+    /// still read to find the changes.
+    pub fn incremental_backup<P: AsRef<Path>>(
+        &self,
+        dest_dir: P,
+        since_version: u64,
+    ) -> Result<crate::backup::BackupEntry> {
+        crate::backup::write_backup(
+            dest_dir.as_ref(),
+            self.backup_contents()?,
+            Some(since_version),
+        )
+    }
+
+    /// Replace every collection by the newest backup in `backup_dir` (StorageEngine-specific)
+    ///
+    /// The base backup and the increments leading to it are applied in
+    /// order. Collections missing from the backup are dropped. Returns the
+    /// restored backup version.
+    pub fn restore_from<P: AsRef<Path>>(&self, backup_dir: P) -> Result<u64> {
+        let (version, collections) = crate::backup::read_backup(backup_dir.as_ref())?;
+        for name in self.list_collections() {
+            self.drop_collection(&name)?;
+        }
+        for (name, docs) in collections {
+            self.collection(&name)?;
+            let docs = docs
+                .into_iter()
+                .filter_map(|doc| match doc {
+                    Value::Object(map) => Some(map.into_iter().collect()),
+                    _ => None,
+                })
+                .collect();
+            self.insert_many(&name, docs)?;
+        }
+        self.flush()?;
+        Ok(version)
+    }
+
+    fn backup_contents(&self) -> Result<Vec<(String, Vec<Value>)>> {
+        let mut names = self.list_collections();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let docs = self.collection(&name)?.find(&serde_json::json!({}))?;
+                Ok((name, docs))
+            })
+            .collect()
+    }
+
     /// Commit a transaction (applies all buffered operations atomically) - StorageEngine-specific
     pub fn commit_transaction(&self, tx_id: TransactionId) -> Result<()> {
         // Remove transaction from active list
@@ -261,10 +563,17 @@ impl DatabaseCore<StorageEngine> {
         };
 
         // Commit through storage engine
-        let mut storage = self.storage.write();
-        storage.commit_transaction(&mut transaction)?;
+        let _commit = self.commit_lock.write();
+        let result = self.check_write_locks(&transaction).and_then(|()| {
+            let mut storage = self.storage.write();
+            storage.commit_transaction(&mut transaction)
+        });
+        self.release_document_locks(tx_id);
+        if result.is_ok() {
+            self.record_commit(&transaction);
+        }
 
-        Ok(())
+        result
     }
 
     /// Rollback a transaction (discard all buffered operations) - StorageEngine-specific
@@ -281,6 +590,7 @@ impl DatabaseCore<StorageEngine> {
         };
 
         // Rollback through storage engine
+        self.release_document_locks(tx_id);
         let mut storage = self.storage.write();
         storage.rollback_transaction(&mut transaction)?;
 
@@ -301,10 +611,31 @@ impl DatabaseCore<StorageEngine> {
         };
 
         // Commit through storage engine with index operations
-        let mut storage = self.storage.write();
-        storage.commit_transaction(&mut transaction)?;
+        let _commit = self.commit_lock.write();
+        let result = self.check_write_locks(&transaction).and_then(|()| {
+            let mut storage = self.storage.write();
+            storage.commit_transaction(&mut transaction)
+        });
+        self.release_document_locks(tx_id);
+        if result.is_ok() {
+            self.record_commit(&transaction);
+        }
 
-        Ok(())
+        result
+    }
+
+    /// Count a committed transaction's writes and its WAL flush
+    fn record_commit(&self, transaction: &Transaction) {
+        for op in transaction.operations() {
+            let (counter, collection) = match op {
+                Operation::Insert { collection, .. } => (&self.metrics.inserts, collection),
+                Operation::Update { collection, .. } => (&self.metrics.updates, collection),
+                Operation::Delete { collection, .. } => (&self.metrics.deletes, collection),
+            };
+            Metrics::incr(counter);
+            self.aggregation_cache.bump_version(collection);
+        }
+        Metrics::incr(&self.metrics.wal_flushes);
     }
 
     // ========== Auto-Commit Transaction Helpers (StorageEngine-specific, INTERNAL) ==========
@@ -330,6 +661,7 @@ impl DatabaseCore<StorageEngine> {
 
         // Write to WAL and commit
         storage.commit_transaction(&mut transaction)?;
+        Metrics::incr(&self.metrics.wal_flushes);
 
         // WAL is automatically flushed in commit_transaction()
         // This ensures durability even on power failure
@@ -380,6 +712,51 @@ impl DatabaseCore<StorageEngine> {
         }
     }
 
+    /// Log operations a raw batch already applied, as the durability mode requires
+    ///
+    /// Safe mode commits them as one auto-transaction; Batch mode buffers
+    /// them; Unsafe mode only counts them towards the auto-checkpoint.
+    fn log_applied_operations(&self, operations: Vec<Operation>) -> Result<()> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        match self.durability_mode {
+            DurabilityMode::Safe => {
+                let mut auto_tx = self.begin_auto_transaction();
+                for operation in operations {
+                    auto_tx.add_operation(operation)?;
+                }
+                auto_tx.mark_operations_applied();
+                self.commit_auto_transaction(auto_tx)
+            }
+
+            DurabilityMode::Batch { .. } => {
+                for operation in operations {
+                    if self.add_to_batch(operation)? {
+                        self.flush_batch()?;
+                    }
+                }
+                Ok(())
+            }
+
+            DurabilityMode::Unsafe {
+                auto_checkpoint_ops,
+            } => {
+                if let Some(threshold) = auto_checkpoint_ops {
+                    let applied = operations.len() as u64;
+                    let count =
+                        self.unsafe_op_counter.fetch_add(applied, Ordering::Relaxed) + applied;
+                    if count >= threshold as u64 {
+                        self.unsafe_op_counter.store(0, Ordering::Relaxed);
+                        self.checkpoint()?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
     // ========== Auto-Commit CRUD Operations (StorageEngine-specific, PUBLIC API) ==========
 
     /// Insert one document with auto-commit (respects durability mode)
@@ -408,6 +785,18 @@ impl DatabaseCore<StorageEngine> {
         collection_name: &str,
         document: HashMap<String, Value>,
     ) -> Result<DocumentId> {
+        // A capped insert may evict older documents; the bulk path logs
+        // those deletes along with the insert
+        let capped = self
+            .storage
+            .read()
+            .get_collection_meta(collection_name)
+            .is_some_and(|meta| meta.capped.is_some());
+        if capped {
+            let mut ids = self.insert_many(collection_name, vec![document])?;
+            return Ok(ids.remove(0));
+        }
+
         match self.durability_mode {
             DurabilityMode::Safe => {
                 // Safe mode: Auto-commit every operation
@@ -496,6 +885,107 @@ impl DatabaseCore<StorageEngine> {
         }
     }
 
+    /// Insert one document, choosing what happens if its `_id` already exists
+    ///
+    /// Without an explicit `_id`, or when the `_id` is free, this is
+    /// `insert_one`. On a duplicate, `OnConflict::Replace` overwrites the
+    /// stored document through a regular update (old version tombstoned, new
+    /// version written, indexes and WAL updated) and `OnConflict::Ignore`
+    /// leaves it untouched. Both return the existing `_id`.
+    pub fn insert_one_with_conflict(
+        &self,
+        collection_name: &str,
+        document: HashMap<String, Value>,
+        on_conflict: OnConflict,
+    ) -> Result<DocumentId> {
+        match self.conflicting_document(collection_name, &document, on_conflict)? {
+            None => self.insert_one(collection_name, document),
+            Some((doc_id, existing)) => {
+                if on_conflict == OnConflict::Replace {
+                    let update = replacement_update(&existing, &document);
+                    self.update_one(
+                        collection_name,
+                        &serde_json::json!({"_id": &doc_id}),
+                        &update,
+                    )?;
+                }
+                Ok(doc_id)
+            }
+        }
+    }
+
+    /// Insert `document` only if no document in the collection matches `query`
+    ///
+    /// The check and the insert run under a lock shared by every
+    /// `insert_if_absent` call on this database, so concurrent callers
+    /// seeding the same document insert it exactly once. (Plain inserts
+    /// don't take the lock.) Returns whether the document was inserted and
+    /// the relevant `_id`: the new document's, or the existing match's.
+    pub fn insert_if_absent(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        document: HashMap<String, Value>,
+    ) -> Result<(bool, DocumentId)> {
+        let _guard = self.conditional_insert_lock.lock();
+        if let Some(doc_id) = self.first_match_id(collection_name, query)? {
+            return Ok((false, doc_id));
+        }
+        Ok((true, self.insert_one(collection_name, document)?))
+    }
+
+    /// Replace the document whose `key` field equals the new document's,
+    /// or insert it if there is none
+    ///
+    /// With `key` = `"_id"` this upserts by id. A replaced document keeps its
+    /// stored `_id`, and fields missing from `document` are removed. A
+    /// document without a `key` field is always inserted. Takes the same
+    /// lock as `insert_if_absent`. Returns whether the document was
+    /// inserted and its `_id`.
+    pub fn upsert_by_key(
+        &self,
+        collection_name: &str,
+        key: &str,
+        document: HashMap<String, Value>,
+    ) -> Result<(bool, DocumentId)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let Some((doc_id, existing)) = self.upsert_target(collection_name, key, &document)? else {
+            return Ok((true, self.insert_one(collection_name, document)?));
+        };
+        let update = replacement_update(&existing, &document);
+        self.update_one(
+            collection_name,
+            &serde_json::json!({"_id": &doc_id}),
+            &update,
+        )?;
+        Ok((false, doc_id))
+    }
+
+    /// Apply a JSON Patch (RFC 6902) to the first document matching `query`
+    ///
+    /// The patch is applied to a copy first; a failing operation (such as a
+    /// `test` that doesn't match) returns an error before anything is
+    /// written. The patched document is then stored through `update_one`,
+    /// so indexes and the WAL see a regular update. `_id` can't be changed.
+    ///
+    /// Returns (matched_count, modified_count)
+    pub fn patch_one(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        patch: &[crate::json_patch::PatchOp],
+    ) -> Result<(u64, u64)> {
+        match self.patched_document(collection_name, query, patch)? {
+            None => Ok((0, 0)),
+            Some((_, None)) => Ok((1, 0)),
+            Some((doc_id, Some(update))) => self.update_one(
+                collection_name,
+                &serde_json::json!({"_id": &doc_id}),
+                &update,
+            ),
+        }
+    }
+
     /// Update one document with WAL durability
     ///
     /// This method wraps update_one with proper WAL logging for crash recovery.
@@ -748,7 +1238,10 @@ impl DatabaseCore<StorageEngine> {
 
     /// Insert multiple documents with WAL durability
     ///
-    /// Each document is logged individually to the WAL for crash recovery.
+    /// The documents are written under a single storage lock and logged
+    /// together (one WAL transaction in Safe mode). On the first failing
+    /// document its error is returned; the documents before it stay
+    /// inserted and are logged.
     ///
     /// Returns vector of inserted document IDs
     pub fn insert_many(
@@ -756,99 +1249,156 @@ impl DatabaseCore<StorageEngine> {
         collection_name: &str,
         documents: Vec<HashMap<String, Value>>,
     ) -> Result<Vec<DocumentId>> {
-        match self.durability_mode {
-            DurabilityMode::Safe => {
-                let collection = self.collection(collection_name)?;
-                let mut auto_tx = self.begin_auto_transaction();
-                let mut inserted_ids = Vec::with_capacity(documents.len());
-
-                for document in documents {
-                    let doc_id = collection.insert_one_raw(document.clone())?;
-
-                    // Add full document to WAL
-                    let mut doc_with_metadata = document.clone();
-                    doc_with_metadata
-                        .insert("_id".to_string(), serde_json::to_value(&doc_id).unwrap());
-                    doc_with_metadata.insert(
-                        "_collection".to_string(),
-                        Value::String(collection_name.to_string()),
-                    );
-                    let doc_value = serde_json::to_value(&doc_with_metadata)
-                        .map_err(|e| crate::error::MongoLiteError::Serialization(e.to_string()))?;
-
-                    auto_tx.add_operation(Operation::Insert {
-                        collection: collection_name.to_string(),
-                        doc_id: doc_id.clone(),
-                        doc: doc_value,
-                    })?;
-
-                    inserted_ids.push(doc_id);
-                }
-
-                auto_tx.mark_operations_applied();
-                self.commit_auto_transaction(auto_tx)?;
+        let ops: Vec<BulkOp> = documents
+            .into_iter()
+            .map(|document| BulkOp::InsertOne { document })
+            .collect();
+        let applied = self
+            .collection(collection_name)?
+            .bulk_write_raw(&ops, true)?;
+        self.log_applied_operations(applied.operations)?;
+
+        match applied.first_error {
+            Some(e) => Err(e),
+            None => Ok(applied.result.inserted_ids),
+        }
+    }
 
-                Ok(inserted_ids)
-            }
+    /// Apply a batch of inserts, updates and deletes with WAL durability
+    ///
+    /// The whole batch takes the storage and index locks once, invalidates
+    /// the query cache once and is logged together (one WAL transaction in
+    /// Safe mode). Operations run in order and see each other's effects.
+    /// A failing operation is reported in `write_errors`: with `ordered` it
+    /// stops the batch, otherwise the rest still run. Changes made before a
+    /// failure are kept, as in MongoDB; the batch is not a transaction.
+    pub fn bulk_write(
+        &self,
+        collection_name: &str,
+        ops: &[BulkOp],
+        ordered: bool,
+    ) -> Result<BulkWriteResult> {
+        let applied = self
+            .collection(collection_name)?
+            .bulk_write_raw(ops, ordered)?;
+        self.log_applied_operations(applied.operations)?;
+        Ok(applied.result)
+    }
 
-            DurabilityMode::Batch { .. } => {
-                let collection = self.collection(collection_name)?;
-                let mut inserted_ids = Vec::with_capacity(documents.len());
-
-                for document in documents {
-                    let doc_id = collection.insert_one_raw(document.clone())?;
-
-                    let mut doc_with_metadata = document.clone();
-                    doc_with_metadata
-                        .insert("_id".to_string(), serde_json::to_value(&doc_id).unwrap());
-                    doc_with_metadata.insert(
-                        "_collection".to_string(),
-                        Value::String(collection_name.to_string()),
-                    );
-                    let doc_value = serde_json::to_value(&doc_with_metadata)
-                        .map_err(|e| crate::error::MongoLiteError::Serialization(e.to_string()))?;
-
-                    let should_flush = self.add_to_batch(Operation::Insert {
-                        collection: collection_name.to_string(),
-                        doc_id: doc_id.clone(),
-                        doc: doc_value,
-                    })?;
-
-                    if should_flush {
-                        self.flush_batch()?;
-                    }
-
-                    inserted_ids.push(doc_id);
-                }
-
-                Ok(inserted_ids)
-            }
-
-            DurabilityMode::Unsafe {
-                auto_checkpoint_ops,
-            } => {
-                let collection = self.collection(collection_name)?;
-                let mut inserted_ids = Vec::with_capacity(documents.len());
+    /// Exchange the value of `field` between two documents with WAL durability
+    ///
+    /// Both documents are rewritten under one lock and logged together, so
+    /// two items of an ordered list can trade positions even when the
+    /// ordering field has a unique index; updating them one at a time would
+    /// hit a duplicate key in between. Errors with `DocumentNotFound` if
+    /// either document is missing.
+    ///
+    /// Returns whether anything changed (false when the values are equal).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ironbase_core::{DatabaseCore, DocumentId};
+    ///
+    /// let db = DatabaseCore::open("test.db").unwrap();
+    /// db.swap_field("tasks", &DocumentId::Int(1), &DocumentId::Int(2), "position")
+    ///     .unwrap();
+    /// ```
+    pub fn swap_field(
+        &self,
+        collection_name: &str,
+        id_a: &DocumentId,
+        id_b: &DocumentId,
+        field: &str,
+    ) -> Result<bool> {
+        let operations = self
+            .collection(collection_name)?
+            .swap_field_raw(id_a, id_b, field)?;
+        let changed = !operations.is_empty();
+        self.log_applied_operations(operations)?;
+        Ok(changed)
+    }
 
-                for document in documents {
-                    let doc_id = collection.insert_one_raw(document)?;
-                    inserted_ids.push(doc_id);
-                }
+    /// Run an aggregation pipeline on a collection, logging `$out` writes
+    ///
+    /// Same as [`CollectionCore::aggregate`], except that a `$out` stage
+    /// writes its target through the WAL according to the durability mode.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ironbase_core::DatabaseCore;
+    /// use serde_json::json;
+    ///
+    /// let db = DatabaseCore::open("test.db").unwrap();
+    /// db.aggregate("sales", &json!([
+    ///     {"$group": {"_id": "$city", "total": {"$sum": "$amount"}}},
+    ///     {"$out": "totals"}
+    /// ])).unwrap();
+    /// ```
+    pub fn aggregate(&self, collection_name: &str, pipeline: &Value) -> Result<Vec<Value>> {
+        self.collection(collection_name)?
+            .aggregate_with_writer(pipeline, self)
+    }
 
-                if let Some(threshold) = auto_checkpoint_ops {
-                    let count = self
-                        .unsafe_op_counter
-                        .fetch_add(inserted_ids.len() as u64, Ordering::Relaxed)
-                        + inserted_ids.len() as u64;
-                    if count >= threshold as u64 {
-                        self.unsafe_op_counter.store(0, Ordering::Relaxed);
-                        self.checkpoint()?;
-                    }
-                }
+    /// Update the first document matching `query`, or insert one if none matches
+    ///
+    /// The inserted document is built from the query's equality conditions
+    /// and `update`, including its `$setOnInsert` fields (which are ignored
+    /// when a document matches). It goes through `insert_one`, so it gets an
+    /// `_id` (unless the query fixes one), index entries and a WAL record.
+    /// Takes the same lock as `insert_if_absent`.
+    ///
+    /// Returns (matched_count, modified_count, upserted_id); an upsert
+    /// reports `(0, 0, Some(id))`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ironbase_core::DatabaseCore;
+    /// use serde_json::json;
+    ///
+    /// let db = DatabaseCore::open("test.db").unwrap();
+    /// let (_, _, upserted_id) = db
+    ///     .update_one_upsert(
+    ///         "counters",
+    ///         &json!({"name": "visits"}),
+    ///         &json!({"$inc": {"n": 1}, "$setOnInsert": {"created": "today"}}),
+    ///     )
+    ///     .unwrap();
+    /// ```
+    pub fn update_one_upsert(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        update: &Value,
+    ) -> Result<(u64, u64, Option<DocumentId>)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let (matched, modified) = self.update_one(collection_name, query, update)?;
+        if matched > 0 {
+            return Ok((matched, modified, None));
+        }
+        let fields = self
+            .collection(collection_name)?
+            .upsert_fields(query, update)?;
+        Ok((0, 0, Some(self.insert_one(collection_name, fields)?)))
+    }
 
-                Ok(inserted_ids)
-            }
+    /// `update_many` that inserts one document when nothing matches
+    ///
+    /// See [`DatabaseCore::update_one_upsert`].
+    pub fn update_many_upsert(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        update: &Value,
+    ) -> Result<(u64, u64, Option<DocumentId>)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let (matched, modified) = self.update_many(collection_name, query, update)?;
+        if matched > 0 {
+            return Ok((matched, modified, None));
         }
+        let fields = self
+            .collection(collection_name)?
+            .upsert_fields(query, update)?;
+        Ok((0, 0, Some(self.insert_one(collection_name, fields)?)))
     }
 
     /// Update multiple documents with WAL durability
@@ -1088,6 +1638,26 @@ impl DatabaseCore<StorageEngine> {
         }
     }
 
+    /// Delete every document past its TTL index expiry
+    ///
+    /// Runs synchronously through `delete_many`, so expired documents are
+    /// removed durably. Returns the number deleted across all TTL indexes.
+    pub fn run_ttl_sweep_now(&self) -> Result<u64> {
+        let mut expired = 0;
+        for (collection_name, query) in self.ttl_expiry_queries() {
+            expired += self.delete_many(&collection_name, &query)?;
+        }
+        Ok(expired)
+    }
+
+    /// Run `run_ttl_sweep_now` in a background thread every `secs` seconds
+    ///
+    /// Replaces any running sweeper; 0 stops it. No sweeper runs until an
+    /// interval is set.
+    pub fn set_ttl_sweep_interval(self: &Arc<Self>, secs: u64) {
+        self.start_ttl_sweeper(secs, Self::run_ttl_sweep_now);
+    }
+
     // ========== Two-Phase Commit Helper Methods (StorageEngine-specific) ==========
 
     /// Construct index file path for a collection's index
@@ -1125,6 +1695,23 @@ impl DatabaseCore<StorageEngine> {
 // MEMORYSTORAGE-SPECIFIC IMPLEMENTATION (in-memory, no WAL)
 // ============================================================================
 
+/// `$out` for [`DatabaseCore::aggregate`]: the target is replaced by a
+/// logged bulk insert
+impl CollectionWriter for DatabaseCore<StorageEngine> {
+    fn replace_collection(&self, name: &str, docs: Vec<Value>) -> Result<()> {
+        let ops = out_insert_ops(docs)?;
+        if self.collection_exists(name) {
+            self.drop_collection(name)?;
+            // Drops are not logged, so persist this one before the inserts
+            // the WAL will replay into the emptied collection
+            self.flush()?;
+        }
+        let applied = self.collection(name)?.bulk_write_raw(&ops, true)?;
+        self.log_applied_operations(applied.operations)?;
+        applied.first_error.map_or(Ok(()), Err)
+    }
+}
+
 impl BatchFlush for DatabaseCore<MemoryStorage> {
     fn flush_pending_batch(&self) -> Result<()> {
         // No-op for MemoryStorage (no persistence)
@@ -1173,6 +1760,16 @@ impl DatabaseCore<MemoryStorage> {
             durability_mode: DurabilityMode::default(),
             batch_buffer: Arc::new(RwLock::new(Vec::new())),
             unsafe_op_counter: AtomicU64::new(0),
+            compacting: AtomicBool::new(false),
+            commit_lock: RwLock::new(()),
+            document_locks: RwLock::new(HashMap::new()),
+            metrics: Arc::new(Metrics::default()),
+            conditional_insert_lock: Mutex::new(()),
+            ttl_sweeper: Mutex::new(None),
+            verify_reads: false,
+            aggregation_cache: Arc::new(AggregationCache::disabled()),
+            skip_id_index: false,
+            field_interner: Arc::new(FieldInterner::disabled()),
         })
     }
 
@@ -1191,6 +1788,82 @@ impl DatabaseCore<MemoryStorage> {
         collection.insert_one_raw(document)
     }
 
+    /// Insert one document with a duplicate `_id` policy (MemoryStorage version)
+    pub fn insert_one_with_conflict(
+        &self,
+        collection_name: &str,
+        document: HashMap<String, Value>,
+        on_conflict: OnConflict,
+    ) -> Result<DocumentId> {
+        match self.conflicting_document(collection_name, &document, on_conflict)? {
+            None => self.insert_one(collection_name, document),
+            Some((doc_id, existing)) => {
+                if on_conflict == OnConflict::Replace {
+                    let update = replacement_update(&existing, &document);
+                    self.update_one(
+                        collection_name,
+                        &serde_json::json!({"_id": &doc_id}),
+                        &update,
+                    )?;
+                }
+                Ok(doc_id)
+            }
+        }
+    }
+
+    /// Insert unless a document matches `query` (MemoryStorage version)
+    pub fn insert_if_absent(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        document: HashMap<String, Value>,
+    ) -> Result<(bool, DocumentId)> {
+        let _guard = self.conditional_insert_lock.lock();
+        if let Some(doc_id) = self.first_match_id(collection_name, query)? {
+            return Ok((false, doc_id));
+        }
+        Ok((true, self.insert_one(collection_name, document)?))
+    }
+
+    /// Replace the document matching the new document's `key` field, or
+    /// insert it (MemoryStorage version)
+    pub fn upsert_by_key(
+        &self,
+        collection_name: &str,
+        key: &str,
+        document: HashMap<String, Value>,
+    ) -> Result<(bool, DocumentId)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let Some((doc_id, existing)) = self.upsert_target(collection_name, key, &document)? else {
+            return Ok((true, self.insert_one(collection_name, document)?));
+        };
+        let update = replacement_update(&existing, &document);
+        self.update_one(
+            collection_name,
+            &serde_json::json!({"_id": &doc_id}),
+            &update,
+        )?;
+        Ok((false, doc_id))
+    }
+
+    /// Apply a JSON Patch to the first matching document (MemoryStorage version)
+    pub fn patch_one(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        patch: &[crate::json_patch::PatchOp],
+    ) -> Result<(u64, u64)> {
+        match self.patched_document(collection_name, query, patch)? {
+            None => Ok((0, 0)),
+            Some((_, None)) => Ok((1, 0)),
+            Some((doc_id, Some(update))) => self.update_one(
+                collection_name,
+                &serde_json::json!({"_id": &doc_id}),
+                &update,
+            ),
+        }
+    }
+
     /// Update one document (MemoryStorage version - no WAL/durability)
     ///
     /// Returns (matched_count, modified_count)
@@ -1220,9 +1893,85 @@ impl DatabaseCore<MemoryStorage> {
         collection_name: &str,
         documents: Vec<HashMap<String, Value>>,
     ) -> Result<Vec<DocumentId>> {
-        let collection = self.collection(collection_name)?;
-        let result = collection.insert_many_raw(documents)?;
-        Ok(result.inserted_ids)
+        let ops: Vec<BulkOp> = documents
+            .into_iter()
+            .map(|document| BulkOp::InsertOne { document })
+            .collect();
+        let applied = self
+            .collection(collection_name)?
+            .bulk_write_raw(&ops, true)?;
+        match applied.first_error {
+            Some(e) => Err(e),
+            None => Ok(applied.result.inserted_ids),
+        }
+    }
+
+    /// Apply a batch of inserts, updates and deletes (MemoryStorage version - no WAL/durability)
+    pub fn bulk_write(
+        &self,
+        collection_name: &str,
+        ops: &[BulkOp],
+        ordered: bool,
+    ) -> Result<BulkWriteResult> {
+        Ok(self
+            .collection(collection_name)?
+            .bulk_write_raw(ops, ordered)?
+            .result)
+    }
+
+    /// Exchange the value of `field` between two documents (MemoryStorage version - no WAL/durability)
+    pub fn swap_field(
+        &self,
+        collection_name: &str,
+        id_a: &DocumentId,
+        id_b: &DocumentId,
+        field: &str,
+    ) -> Result<bool> {
+        Ok(!self
+            .collection(collection_name)?
+            .swap_field_raw(id_a, id_b, field)?
+            .is_empty())
+    }
+
+    /// Run an aggregation pipeline on a collection (MemoryStorage version - no WAL/durability)
+    pub fn aggregate(&self, collection_name: &str, pipeline: &Value) -> Result<Vec<Value>> {
+        self.collection(collection_name)?.aggregate(pipeline)
+    }
+
+    /// Update one document or insert one if none matches (MemoryStorage version)
+    pub fn update_one_upsert(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        update: &Value,
+    ) -> Result<(u64, u64, Option<DocumentId>)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let (matched, modified) = self.update_one(collection_name, query, update)?;
+        if matched > 0 {
+            return Ok((matched, modified, None));
+        }
+        let fields = self
+            .collection(collection_name)?
+            .upsert_fields(query, update)?;
+        Ok((0, 0, Some(self.insert_one(collection_name, fields)?)))
+    }
+
+    /// Update many documents or insert one if none matches (MemoryStorage version)
+    pub fn update_many_upsert(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        update: &Value,
+    ) -> Result<(u64, u64, Option<DocumentId>)> {
+        let _guard = self.conditional_insert_lock.lock();
+        let (matched, modified) = self.update_many(collection_name, query, update)?;
+        if matched > 0 {
+            return Ok((matched, modified, None));
+        }
+        let fields = self
+            .collection(collection_name)?
+            .upsert_fields(query, update)?;
+        Ok((0, 0, Some(self.insert_one(collection_name, fields)?)))
     }
 
     /// Update many documents (MemoryStorage version - no WAL/durability)
@@ -1245,6 +1994,21 @@ impl DatabaseCore<MemoryStorage> {
         let collection = self.collection(collection_name)?;
         collection.delete_many_raw(query)
     }
+
+    /// Delete every document past its TTL index expiry (MemoryStorage version)
+    pub fn run_ttl_sweep_now(&self) -> Result<u64> {
+        let mut expired = 0;
+        for (collection_name, query) in self.ttl_expiry_queries() {
+            expired += self.delete_many(&collection_name, &query)?;
+        }
+        Ok(expired)
+    }
+
+    /// Run `run_ttl_sweep_now` in a background thread every `secs` seconds
+    /// (MemoryStorage version); 0 stops it
+    pub fn set_ttl_sweep_interval(self: &Arc<Self>, secs: u64) {
+        self.start_ttl_sweeper(secs, Self::run_ttl_sweep_now);
+    }
 }
 
 // ============================================================================
@@ -1254,7 +2018,162 @@ impl DatabaseCore<MemoryStorage> {
 impl<S: Storage + RawStorage> DatabaseCore<S> {
     /// Get collection (creates if doesn't exist)
     pub fn collection(&self, name: &str) -> Result<CollectionCore<S>> {
-        CollectionCore::new(name.to_string(), Arc::clone(&self.storage))
+        if self.skip_id_index {
+            let mut storage = self.storage.write();
+            if storage.get_collection_meta(name).is_none() {
+                storage.create_collection(name)?;
+                if let Some(meta) = storage.get_collection_meta_mut(name) {
+                    meta.skip_id_index = true;
+                }
+            }
+        }
+        Ok(
+            CollectionCore::new(name.to_string(), Arc::clone(&self.storage))?
+                .with_metrics(Arc::clone(&self.metrics))
+                .with_aggregation_cache(Arc::clone(&self.aggregation_cache))
+                .with_field_interner(Arc::clone(&self.field_interner))
+                .with_verify_reads(self.verify_reads),
+        )
+    }
+
+    /// Get a collection without creating it
+    ///
+    /// Fails with `CollectionNotFound` for a missing collection, so a typo
+    /// in the name is reported instead of creating an empty collection.
+    pub fn existing_collection(&self, name: &str) -> Result<CollectionCore<S>> {
+        if !self.collection_exists(name) {
+            return Err(crate::error::MongoLiteError::CollectionNotFound(
+                name.to_string(),
+            ));
+        }
+        self.collection(name)
+    }
+
+    /// True if the collection exists (never creates it)
+    pub fn collection_exists(&self, name: &str) -> bool {
+        self.storage.read().get_collection_meta(name).is_some()
+    }
+
+    /// Create a collection, failing with `CollectionExists` if it already exists
+    pub fn create_collection(&self, name: &str) -> Result<CollectionCore<S>> {
+        {
+            let mut storage = self.storage.write();
+            storage.create_collection(name)?;
+            if self.skip_id_index {
+                if let Some(meta) = storage.get_collection_meta_mut(name) {
+                    meta.skip_id_index = true;
+                }
+            }
+        }
+        self.collection(name)
+    }
+
+    /// Read-only view querying the named collections together
+    ///
+    /// Names must refer to existing collections (the view never creates
+    /// one); a name listed twice is queried once.
+    pub fn union_view(&self, names: &[String]) -> Result<crate::union_view::UnionView<S>> {
+        let mut collections: Vec<CollectionCore<S>> = Vec::with_capacity(names.len());
+        for name in names {
+            if collections.iter().any(|c| &c.name == name) {
+                continue;
+            }
+            collections.push(self.existing_collection(name)?);
+        }
+        Ok(crate::union_view::UnionView::new(collections))
+    }
+
+    /// The stored document a `_id`-conflicting insert would collide with
+    ///
+    /// None when the policy is `Error` (the insert reports the conflict
+    /// itself), the document has no `_id`, or the `_id` is free.
+    fn conflicting_document(
+        &self,
+        collection_name: &str,
+        document: &HashMap<String, Value>,
+        on_conflict: OnConflict,
+    ) -> Result<Option<(DocumentId, Value)>> {
+        let Some(id_value) = document.get("_id") else {
+            return Ok(None);
+        };
+        if on_conflict == OnConflict::Error {
+            return Ok(None);
+        }
+
+        let doc_id: DocumentId = serde_json::from_value(id_value.clone()).map_err(|e| {
+            crate::error::MongoLiteError::Serialization(format!("Invalid _id format: {}", e))
+        })?;
+        let existing = self.collection(collection_name)?.find_by_id(&doc_id)?;
+        Ok(existing.map(|doc| (doc_id, doc)))
+    }
+
+    /// `_id` of the first document matching `query`
+    fn first_match_id(&self, collection_name: &str, query: &Value) -> Result<Option<DocumentId>> {
+        let Some(doc) = self.collection(collection_name)?.find_one(query)? else {
+            return Ok(None);
+        };
+        let id_value = doc.get("_id").cloned().unwrap_or(Value::Null);
+        let doc_id = serde_json::from_value(id_value).map_err(|e| {
+            crate::error::MongoLiteError::Serialization(format!("Invalid _id format: {}", e))
+        })?;
+        Ok(Some(doc_id))
+    }
+
+    /// Stored document `upsert_by_key` replaces: the first whose `key` equals
+    /// the new document's, with its `_id`
+    fn upsert_target(
+        &self,
+        collection_name: &str,
+        key: &str,
+        document: &HashMap<String, Value>,
+    ) -> Result<Option<(DocumentId, Value)>> {
+        let Some(value) = document.get(key) else {
+            return Ok(None);
+        };
+        let query = serde_json::json!({ key: value });
+        let Some(existing) = self.collection(collection_name)?.find_one(&query)? else {
+            return Ok(None);
+        };
+        let doc_id: DocumentId = serde_json::from_value(existing["_id"].clone())?;
+        Ok(Some((doc_id, existing)))
+    }
+
+    /// First document matching `query` and the update that applies `patch` to it
+    ///
+    /// None when nothing matches; the update is None when the patch leaves
+    /// the document as it was.
+    fn patched_document(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        patch: &[crate::json_patch::PatchOp],
+    ) -> Result<Option<(DocumentId, Option<Value>)>> {
+        let Some(existing) = self.collection(collection_name)?.find_one(query)? else {
+            return Ok(None);
+        };
+        let doc_id: DocumentId = serde_json::from_value(existing["_id"].clone())?;
+
+        let mut patched = existing.clone();
+        crate::json_patch::apply_patch(&mut patched, patch)?;
+        if patched == existing {
+            return Ok(Some((doc_id, None)));
+        }
+        if patched.get("_id") != existing.get("_id") {
+            return Err(crate::error::MongoLiteError::InvalidQuery(
+                "JSON Patch: _id cannot be modified".to_string(),
+            ));
+        }
+        let Value::Object(fields) = patched else {
+            return Err(crate::error::MongoLiteError::InvalidQuery(
+                "JSON Patch: result must be a document".to_string(),
+            ));
+        };
+
+        let new_fields: HashMap<String, Value> = fields.into_iter().collect();
+        Ok(Some((
+            doc_id,
+            Some(replacement_update(&existing, &new_fields)),
+        )))
     }
 
     /// Set or clear JSON schema for a collection
@@ -1263,6 +2182,32 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
         collection.set_schema(schema)
     }
 
+    /// Set or clear the document quota of a collection
+    pub fn set_collection_max_documents(
+        &self,
+        name: &str,
+        max_documents: Option<u64>,
+    ) -> Result<()> {
+        let collection = self.collection(name)?;
+        collection.set_max_documents(max_documents)
+    }
+
+    /// Make a collection capped (or regular again with None)
+    pub fn set_collection_capped(
+        &self,
+        name: &str,
+        limits: Option<crate::storage::CappedLimits>,
+    ) -> Result<()> {
+        let collection = self.collection(name)?;
+        collection.set_capped(limits)
+    }
+
+    /// Turn a collection's automatic `_id` index on or off
+    pub fn set_collection_id_index(&self, name: &str, enabled: bool) -> Result<()> {
+        let collection = self.collection(name)?;
+        collection.set_id_index(enabled)
+    }
+
     /// List all collection names
     pub fn list_collections(&self) -> Vec<String> {
         let storage = self.storage.read();
@@ -1272,7 +2217,9 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
     /// Drop collection
     pub fn drop_collection(&self, name: &str) -> Result<()> {
         let mut storage = self.storage.write();
-        storage.drop_collection(name)
+        storage.drop_collection(name)?;
+        self.aggregation_cache.bump_version(name);
+        Ok(())
     }
 
     /// Flush all changes to disk
@@ -1362,6 +2309,112 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
         })
     }
 
+    /// Find documents within a transaction (read committed)
+    ///
+    /// Sees committed data plus the transaction's own writes, never another
+    /// transaction's uncommitted operations or a commit that is only partly
+    /// applied.
+    pub fn find_tx(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        tx_id: TransactionId,
+    ) -> Result<Vec<Value>> {
+        let transaction = self.transaction_snapshot(tx_id)?;
+        let _committed = self.commit_lock.read();
+        let collection = self.collection(collection_name)?;
+        collection.find_tx(query, &transaction)
+    }
+
+    /// Find one document within a transaction (read committed)
+    pub fn find_one_tx(
+        &self,
+        collection_name: &str,
+        query: &Value,
+        tx_id: TransactionId,
+    ) -> Result<Option<Value>> {
+        let transaction = self.transaction_snapshot(tx_id)?;
+        let _committed = self.commit_lock.read();
+        let collection = self.collection(collection_name)?;
+        collection.find_one_tx(query, &transaction)
+    }
+
+    /// Lock a document for the rest of a transaction (SELECT ... FOR UPDATE)
+    ///
+    /// Another transaction that then tries to lock the same document, or to
+    /// commit a write to it, fails with `WriteConflict`; a commit that fails
+    /// this way is rolled back. Locking before reading makes read-modify-write
+    /// safe: the second writer learns of the conflict instead of silently
+    /// overwriting the first. Locks are held until the owning transaction
+    /// commits or rolls back, and re-locking a document the transaction
+    /// already holds is a no-op.
+    ///
+    /// Only transactions are checked against the locks. Auto-commit writes
+    /// (`insert_one`, `update_one`, `delete_one`, `patch_one`, `bulk_write`,
+    /// ...) ignore them and can overwrite a locked document; writers that
+    /// must not lose updates have to go through transactions as well.
+    pub fn lock_for_update(
+        &self,
+        tx_id: TransactionId,
+        collection_name: &str,
+        id: &DocumentId,
+    ) -> Result<()> {
+        self.transaction_snapshot(tx_id)?;
+        let key = lock_key(collection_name, id);
+        let mut locks = self.document_locks.write();
+        match locks.get(&key) {
+            Some(&owner) if owner != tx_id => Err(lock_conflict(&key, owner)),
+            _ => {
+                locks.insert(key, tx_id);
+                Ok(())
+            }
+        }
+    }
+
+    /// Fail if any buffered write targets a document another transaction locked
+    fn check_write_locks(&self, transaction: &Transaction) -> Result<()> {
+        let locks = self.document_locks.read();
+        if locks.is_empty() {
+            return Ok(());
+        }
+        for op in transaction.operations() {
+            let (collection, doc_id) = match op {
+                Operation::Insert {
+                    collection, doc_id, ..
+                }
+                | Operation::Update {
+                    collection, doc_id, ..
+                }
+                | Operation::Delete {
+                    collection, doc_id, ..
+                } => (collection, doc_id),
+            };
+            let key = lock_key(collection, doc_id);
+            if let Some(&owner) = locks.get(&key) {
+                if owner != transaction.id {
+                    return Err(lock_conflict(&key, owner));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn release_document_locks(&self, tx_id: TransactionId) {
+        self.document_locks
+            .write()
+            .retain(|_, owner| *owner != tx_id);
+    }
+
+    /// Copy of an active transaction, so reads don't hold the transaction table lock
+    fn transaction_snapshot(&self, tx_id: TransactionId) -> Result<Transaction> {
+        self.get_transaction(tx_id).ok_or_else(|| {
+            crate::error::MongoLiteError::TransactionAborted(format!(
+                "Transaction {} not found",
+                tx_id
+            ))
+        })
+    }
+
     /// Update one document within a transaction (convenience method)
     ///
     /// Returns (matched_count, modified_count)
@@ -1372,6 +2425,7 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
         update: Value,
         tx_id: TransactionId,
     ) -> Result<(u64, u64)> {
+        let _committed = self.commit_lock.read();
         let collection = self.collection(collection_name)?;
 
         self.with_transaction(tx_id, |transaction| {
@@ -1388,6 +2442,7 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
         query: &Value,
         tx_id: TransactionId,
     ) -> Result<u64> {
+        let _committed = self.commit_lock.read();
         let collection = self.collection(collection_name)?;
 
         self.with_transaction(tx_id, |transaction| {
@@ -1395,10 +2450,88 @@ impl<S: Storage + RawStorage> DatabaseCore<S> {
         })
     }
 
+    /// Snapshot of the operation counters
+    ///
+    /// Counts cover all collection handles obtained from this database
+    /// since it was opened; they are not persisted.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Aggregation result cache statistics (DatabaseOptions::aggregation_cache_size)
+    pub fn aggregation_cache_stats(&self) -> AggregationCacheStats {
+        self.aggregation_cache.stats()
+    }
+
     /// Get current durability mode
     pub fn durability_mode(&self) -> DurabilityMode {
         self.durability_mode
     }
+
+    /// Seconds between background TTL sweeps (0 when no sweeper runs)
+    pub fn ttl_sweep_interval(&self) -> u64 {
+        self.ttl_sweeper
+            .lock()
+            .as_ref()
+            .map_or(0, |sweeper| sweeper.interval_secs)
+    }
+
+    /// One `delete_many` query per TTL index, matching its expired documents
+    fn ttl_expiry_queries(&self) -> Vec<(String, Value)> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let storage = self.storage.read();
+
+        let mut queries = Vec::new();
+        for collection_name in storage.list_collections() {
+            let Some(meta) = storage.get_collection_meta(&collection_name) else {
+                continue;
+            };
+            for index in &meta.indexes {
+                if let Some(expire_after) = index.expire_after_secs {
+                    let cutoff = now.saturating_sub(expire_after);
+                    let query = serde_json::json!({ index.field.as_str(): {"$lte": cutoff} });
+                    queries.push((collection_name.clone(), query));
+                }
+            }
+        }
+        queries
+    }
+
+    fn start_ttl_sweeper(self: &Arc<Self>, secs: u64, sweep: fn(&Self) -> Result<u64>)
+    where
+        S: Send + Sync + 'static,
+    {
+        let mut sweeper = self.ttl_sweeper.lock();
+        // Dropping the old handle stops the previous thread
+        *sweeper = None;
+        if secs == 0 {
+            return;
+        }
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let db: Weak<Self> = Arc::downgrade(self);
+        let interval = Duration::from_secs(secs);
+        std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let Some(db) = db.upgrade() else {
+                    break;
+                };
+                match sweep(&db) {
+                    Ok(expired) if expired > 0 => {
+                        log_debug!("TTL sweep expired {} documents", expired);
+                    }
+                    Ok(_) => {}
+                    Err(e) => log_warn!("TTL sweep failed: {}", e),
+                }
+            }
+        });
+        *sweeper = Some(TtlSweeper {
+            interval_secs: secs,
+            _stop: stop,
+        });
+    }
 }
 
 #[cfg(test)]
@@ -1427,6 +2560,55 @@ mod tests {
         assert_eq!(tx.unwrap().id, tx_id);
     }
 
+    #[test]
+    fn test_concurrent_compaction_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.mlite");
+        let db = Arc::new(DatabaseCore::open(&db_path).unwrap());
+
+        for i in 0..20 {
+            db.insert_one("items", HashMap::from([("n".to_string(), json!(i))]))
+                .unwrap();
+        }
+        db.delete_many("items", &json!({"n": {"$lt": 10}})).unwrap();
+
+        // Hold the storage lock so the first compaction parks after taking the guard
+        let storage_lock = db.storage.write();
+
+        let first = {
+            let db = Arc::clone(&db);
+            std::thread::spawn(move || db.compact())
+        };
+        while !db.compacting.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+
+        let second = {
+            let db = Arc::clone(&db);
+            std::thread::spawn(move || db.compact())
+        };
+        let second_result = second.join().unwrap();
+        assert!(matches!(
+            second_result,
+            Err(crate::error::MongoLiteError::CompactionInProgress)
+        ));
+
+        drop(storage_lock);
+        let stats = first.join().unwrap().unwrap();
+        assert_eq!(stats.documents_kept, 10);
+        assert!(!db.compacting.load(Ordering::Acquire));
+
+        let collection = db.collection("items").unwrap();
+        assert_eq!(collection.count_documents(&json!({})).unwrap(), 10);
+        assert_eq!(
+            collection.find(&json!({"n": {"$gte": 10}})).unwrap().len(),
+            10
+        );
+
+        // Flag released: a later compaction is allowed again
+        assert!(db.compact().is_ok());
+    }
+
     #[test]
     fn test_commit_empty_transaction() {
         let temp_dir = TempDir::new().unwrap();
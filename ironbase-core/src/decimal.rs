@@ -0,0 +1,262 @@
+// ironbase-core/src/decimal.rs
+//! Exact numbers that don't fit an `i64`/`u64`/`f64`
+//!
+//! JSON numbers are stored as `serde_json::Number`, which holds an `i64`, a
+//! `u64` or an `f64`. Anything else (an integer beyond `u64::MAX`, a decimal
+//! with more than ~17 significant digits) is stored as a tagged string:
+//!
+//! ```json
+//! {"price": {"$numberDecimal": "3.14159265358979323846264338327950288"}}
+//! ```
+//!
+//! Such values compare, sort, match `$eq`/`$gt`/... and are indexed by their
+//! exact numeric value, both against each other and against plain numbers.
+//!
+//! # Limits
+//!
+//! - Decimals are opaque to arithmetic: `$inc`, `$sum`, `$avg` and friends
+//!   skip or reject them like any other non-number.
+//! - Index scans are not planned for decimal filter values; the query falls
+//!   back to a collection scan (results stay correct, only slower).
+//! - In an index, a decimal that is an exact `i64` shares the plain integer's
+//!   key; any other decimal gets its own key, so a unique index can hold both
+//!   `0.5` and `{"$numberDecimal": "0.5"}`.
+//! - Index keys of different numeric kinds order by exact value, so range
+//!   scans and index-served sorts see decimals among plain numbers. Equal
+//!   values of different kinds order integer, float, decimal.
+
+use crate::error::{MongoLiteError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Key of the single-field object that tags a decimal string
+pub const DECIMAL_MARKER: &str = "$numberDecimal";
+
+/// Exact decimal number, normalized as `±0.DIGITS × 10^exponent`
+///
+/// `digits` has no leading or trailing zeros; zero is the empty digit string
+/// with exponent 0, so equal values have equal representations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// True for zero (of either sign)
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// The value as an `i64`, if it is an integer in range
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.is_zero() {
+            return Some(0);
+        }
+        if self.exponent < self.digits.len() as i64 || self.exponent > 19 {
+            return None;
+        }
+        let mut text = String::with_capacity(20);
+        if self.negative {
+            text.push('-');
+        }
+        text.push_str(&self.digits);
+        for _ in self.digits.len() as i64..self.exponent {
+            text.push('0');
+        }
+        text.parse().ok()
+    }
+
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        match (self.is_zero(), other.is_zero()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // Leading digits are non-zero, so the exponent decides first and
+            // the digit strings then compare like the fractions they are
+            (false, false) => self
+                .exponent
+                .cmp(&other.exponent)
+                .then_with(|| self.digits.cmp(&other.digits)),
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = MongoLiteError;
+
+    /// Parse `[+-]digits[.digits][(e|E)[+-]digits]`
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || MongoLiteError::InvalidQuery(format!("Invalid decimal: '{}'", text));
+
+        let (negative, rest) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (mantissa, exp) = match rest.find(['e', 'E']) {
+            Some(pos) => (
+                &rest[..pos],
+                rest[pos + 1..].parse::<i64>().map_err(|_| invalid())?,
+            ),
+            None => (rest, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() && frac_part.is_empty()
+            || !int_part
+                .bytes()
+                .chain(frac_part.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let all_digits = format!("{}{}", int_part, frac_part);
+        let leading = all_digits.len() - all_digits.trim_start_matches('0').len();
+        let digits = all_digits.trim_matches('0').to_string();
+        if digits.is_empty() {
+            return Ok(Decimal {
+                negative: false,
+                digits,
+                exponent: 0,
+            });
+        }
+        let exponent = (int_part.len() as i64 - leading as i64)
+            .checked_add(exp)
+            .ok_or_else(invalid)?;
+
+        Ok(Decimal {
+            negative,
+            digits,
+            exponent,
+        })
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let self_neg = self.negative && !self.is_zero();
+        let other_neg = other.negative && !other.is_zero();
+        match (self_neg, other_neg) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Build a tagged decimal value, validating `text`
+///
+/// # Examples
+///
+/// ```
+/// use ironbase_core::decimal::decimal_value;
+/// use serde_json::json;
+///
+/// let v = decimal_value("123456789012345678901234567890").unwrap();
+/// assert_eq!(v, json!({"$numberDecimal": "123456789012345678901234567890"}));
+/// assert!(decimal_value("12abc").is_err());
+/// ```
+pub fn decimal_value(text: &str) -> Result<Value> {
+    text.parse::<Decimal>()?;
+    let mut map = serde_json::Map::new();
+    map.insert(DECIMAL_MARKER.to_string(), Value::String(text.to_string()));
+    Ok(Value::Object(map))
+}
+
+/// The decimal text of a tagged value, if `value` is one
+pub fn decimal_text(value: &Value) -> Option<&str> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.get(DECIMAL_MARKER)?.as_str(),
+        _ => None,
+    }
+}
+
+/// True if `value` is a tagged decimal
+pub fn is_decimal(value: &Value) -> bool {
+    decimal_text(value).is_some()
+}
+
+/// Exact numeric value of a JSON number or a tagged decimal
+pub fn numeric_value(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => n.to_string().parse().ok(),
+        _ => decimal_text(value)?.parse().ok(),
+    }
+}
+
+/// Exact comparison when at least one side is a tagged decimal
+///
+/// Returns `None` if neither side is a decimal (callers use their usual
+/// comparison) or if the other side isn't a number.
+pub fn compare_decimal(a: &Value, b: &Value) -> Option<Ordering> {
+    if !is_decimal(a) && !is_decimal(b) {
+        return None;
+    }
+    Some(numeric_value(a)?.cmp(&numeric_value(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dec(text: &str) -> Decimal {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        assert_eq!(dec("1.50"), dec("1.5"));
+        assert_eq!(dec("001.5"), dec("15e-1"));
+        assert_eq!(dec("0.000"), dec("-0"));
+        assert_eq!(dec("+12"), dec("1.2E1"));
+        for bad in ["", "-", ".", "1.2.3", "1e", "abc", "1e5x", "NaN"] {
+            assert!(bad.parse::<Decimal>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_ordering_is_exact() {
+        let big = "123456789012345678901234567890";
+        assert!(dec(big) > dec("123456789012345678901234567889"));
+        assert!(
+            dec("3.14159265358979323846264338327950288")
+                > dec("3.14159265358979323846264338327950287")
+        );
+        assert!(dec("-2") < dec("-1.5"));
+        assert!(dec("-0.001") < dec("0"));
+        assert!(dec("0.1") < dec("1"));
+        assert!(dec("99") < dec("100"));
+        assert_eq!(dec("1e2").to_i64(), Some(100));
+        assert_eq!(dec("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(dec("9223372036854775808").to_i64(), None);
+        assert_eq!(dec("1.5").to_i64(), None);
+    }
+
+    #[test]
+    fn test_compare_with_plain_numbers() {
+        let big = decimal_value("18446744073709551616").unwrap(); // u64::MAX + 1
+        assert_eq!(
+            compare_decimal(&big, &json!(u64::MAX)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_decimal(&json!(2.5), &decimal_value("2.50").unwrap()),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(compare_decimal(&json!(1), &json!(2)), None);
+        assert_eq!(compare_decimal(&big, &json!("x")), None);
+        assert!(!is_decimal(&json!({"$numberDecimal": "1", "other": 2})));
+    }
+}
@@ -126,58 +126,12 @@ impl Document {
         let mut results = Vec::new();
 
         if let Some(initial) = self.fields.get(parts[0]) {
-            Self::collect_values_recursive(initial, &parts[1..], &mut results);
+            crate::value_utils::collect_path_values(initial, &parts[1..], &mut results);
         }
 
         results
     }
 
-    /// Helper function for get_all() - recursively collects values from nested structures
-    fn collect_values_recursive<'a>(
-        value: &'a Value,
-        remaining_path: &[&str],
-        results: &mut Vec<&'a Value>,
-    ) {
-        if remaining_path.is_empty() {
-            results.push(value);
-            return;
-        }
-
-        let next_part = remaining_path[0];
-        let rest = &remaining_path[1..];
-
-        match value {
-            Value::Object(map) => {
-                if let Some(next_value) = map.get(next_part) {
-                    Self::collect_values_recursive(next_value, rest, results);
-                }
-            }
-            Value::Array(arr) => {
-                // Try numeric index first (explicit array access like "items.0.name")
-                if let Ok(index) = next_part.parse::<usize>() {
-                    if let Some(elem) = arr.get(index) {
-                        Self::collect_values_recursive(elem, rest, results);
-                    }
-                } else {
-                    // MongoDB-style implicit array traversal:
-                    // Iterate all elements looking for the field
-                    for elem in arr {
-                        if let Value::Object(map) = elem {
-                            if let Some(next_value) = map.get(next_part) {
-                                Self::collect_values_recursive(next_value, rest, results);
-                            }
-                        }
-                        // Also handle nested arrays (array of arrays)
-                        if let Value::Array(_) = elem {
-                            Self::collect_values_recursive(elem, remaining_path, results);
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
     /// MongoDB $** wildcard operator support.
     /// Recursively finds ALL occurrences of a field name at ANY depth in the document.
     ///
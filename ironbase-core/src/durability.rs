@@ -4,6 +4,8 @@
 //! Similar to SQL databases, IronBase can operate in different durability modes.
 
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
 
 /// Durability mode determines how operations are committed to WAL
 ///
@@ -11,7 +13,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// - **Safe**: Every operation is auto-committed (like SQL auto-commit)
 ///   - WAL written for every operation
-///   - fsync after every commit
+///   - WAL fsync after every commit (data file at checkpoints)
 ///   - Slow but guaranteed durability
 ///   - Performance: ~1,000-5,000 inserts/sec
 ///
@@ -48,7 +50,7 @@ use serde::{Deserialize, Serialize};
 pub enum DurabilityMode {
     /// Safe mode: Every operation is auto-committed (like SQL)
     /// - WAL written for every operation
-    /// - fsync after every commit
+    /// - WAL fsync after every commit
     /// - Slow but guaranteed durability
     Safe,
 
@@ -129,6 +131,43 @@ impl DurabilityMode {
     }
 }
 
+/// Number of recent commits averaged by [`CommitLatency`]
+pub const COMMIT_LATENCY_WINDOW: usize = 128;
+
+/// Rolling average of commit latency over the last
+/// [`COMMIT_LATENCY_WINDOW`] commits
+///
+/// A commit's latency runs from the start of the commit until its WAL fsync
+/// returns, so it is the time an auto-committed operation waits for
+/// durability.
+#[derive(Debug, Clone, Default)]
+pub struct CommitLatency {
+    samples: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl CommitLatency {
+    /// Record one commit, evicting the oldest once the window is full
+    pub fn record(&mut self, latency: Duration) {
+        if self.samples.len() == COMMIT_LATENCY_WINDOW {
+            if let Some(oldest) = self.samples.pop_front() {
+                self.total -= oldest;
+            }
+        }
+        self.samples.push_back(latency);
+        self.total += latency;
+    }
+
+    /// Average over the recorded window; None before the first commit
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            Some(self.total / self.samples.len() as u32)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,4 +234,20 @@ mod tests {
             }
         ));
     }
+
+    #[test]
+    fn test_commit_latency_rolling_average() {
+        let mut latency = CommitLatency::default();
+        assert_eq!(latency.average(), None);
+
+        latency.record(Duration::from_micros(100));
+        latency.record(Duration::from_micros(300));
+        assert_eq!(latency.average(), Some(Duration::from_micros(200)));
+
+        // Filling the window pushes the early samples out
+        for _ in 0..COMMIT_LATENCY_WINDOW {
+            latency.record(Duration::from_micros(50));
+        }
+        assert_eq!(latency.average(), Some(Duration::from_micros(50)));
+    }
 }
@@ -42,9 +42,21 @@ pub enum MongoLiteError {
     #[error("Transaction aborted: {0}")]
     TransactionAborted(String),
 
+    #[error("Write conflict: {0}")]
+    WriteConflict(String),
+
     #[error("WAL corruption detected")]
     WALCorruption,
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Duplicate key: {0}")]
+    DuplicateKey(String),
+
+    #[error("Compaction already in progress")]
+    CompactionInProgress,
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
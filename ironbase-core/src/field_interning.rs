@@ -0,0 +1,404 @@
+// ironbase-core/src/field_interning.rs
+// Shared field-name pool for memory-lean document scans
+
+use crate::document::{Document, DocumentId};
+use parking_lot::Mutex;
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Decoded document whose object keys are shared `Arc<str>` handles
+///
+/// `serde_json::Value` owns a fresh `String` for every key of every
+/// document; in a wide collection with a fixed schema that is the same
+/// handful of names allocated once per row. Keys here point into the
+/// `FieldInterner` pool instead, so a scan holding many documents pays for
+/// each distinct name once. String values are not interned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedValue {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<InternedValue>),
+    Object(Vec<(Arc<str>, InternedValue)>),
+}
+
+impl InternedValue {
+    /// Field of an object (None for other types or missing keys)
+    pub fn get(&self, key: &str) -> Option<&InternedValue> {
+        match self {
+            InternedValue::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name.as_ref() == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Nested field by dot notation ("address.city")
+    pub fn get_path(&self, path: &str) -> Option<&InternedValue> {
+        path.split('.')
+            .try_fold(self, |value, part| value.get(part))
+    }
+
+    /// Build an owned `serde_json::Value` (keys are copied)
+    pub fn to_value(&self) -> Value {
+        match self {
+            InternedValue::Null => Value::Null,
+            InternedValue::Bool(b) => Value::Bool(*b),
+            InternedValue::Number(n) => Value::Number(n.clone()),
+            InternedValue::String(s) => Value::String(s.clone()),
+            InternedValue::Array(items) => Value::Array(items.iter().map(Self::to_value).collect()),
+            InternedValue::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_value()))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+
+    /// Owned `Document` for the query matcher, converting each field once
+    pub(crate) fn to_document(&self) -> serde_json::Result<Document> {
+        let InternedValue::Object(fields) = self else {
+            return Err(de::Error::custom("document is not an object"));
+        };
+        let fields: HashMap<String, Value> = fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_value()))
+            .collect();
+        let id = DocumentId::deserialize(fields.get("_id").unwrap_or(&Value::Null))?;
+        Ok(Document::new(id, fields))
+    }
+
+    fn is_tombstone(&self) -> bool {
+        matches!(self.get("_tombstone"), Some(InternedValue::Bool(true)))
+    }
+}
+
+impl From<InternedValue> for Value {
+    fn from(value: InternedValue) -> Self {
+        value.to_value()
+    }
+}
+
+/// Per-collection pool of field names shared across decoded documents
+///
+/// Disabled by default (no pool): every key gets its own allocation, which
+/// is the cheaper choice for small scans. Once `max_names` distinct names
+/// are pooled, further new names are allocated without pooling so
+/// data-dependent keys cannot grow the pool without bound.
+pub struct FieldInterner {
+    pool: Option<Mutex<HashSet<Arc<str>>>>,
+    max_names: usize,
+    keys: AtomicU64,
+    hits: AtomicU64,
+    bytes_saved: AtomicU64,
+}
+
+impl FieldInterner {
+    /// Create an interner pooling up to `max_names` distinct names (0 disables it)
+    pub fn new(max_names: usize) -> Self {
+        FieldInterner {
+            pool: (max_names > 0).then(|| Mutex::new(HashSet::new())),
+            max_names,
+            keys: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            bytes_saved: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a disabled interner
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    /// Whether keys are actually shared
+    pub fn is_enabled(&self) -> bool {
+        self.pool.is_some()
+    }
+
+    /// Decode a JSON document, sharing its keys through the pool
+    pub fn parse(&self, bytes: &[u8]) -> serde_json::Result<InternedValue> {
+        let mut guard = self.pool.as_ref().map(|pool| pool.lock());
+        let mut seed = InternSeed {
+            pool: guard.as_deref_mut(),
+            max_names: self.max_names,
+            counts: KeyCounts::default(),
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let value = (&mut seed).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+
+        self.keys.fetch_add(seed.counts.keys, Ordering::Relaxed);
+        self.hits.fetch_add(seed.counts.hits, Ordering::Relaxed);
+        self.bytes_saved
+            .fetch_add(seed.counts.bytes_saved, Ordering::Relaxed);
+        Ok(value)
+    }
+
+    /// Decode a stored record, returning None for tombstones
+    pub(crate) fn parse_live(&self, bytes: &[u8]) -> serde_json::Result<Option<InternedValue>> {
+        let value = self.parse(bytes)?;
+        Ok((!value.is_tombstone()).then_some(value))
+    }
+
+    /// Get interning statistics
+    pub fn stats(&self) -> FieldInterningStats {
+        FieldInterningStats {
+            enabled: self.is_enabled(),
+            distinct_names: self.pool.as_ref().map(|p| p.lock().len()).unwrap_or(0),
+            keys: self.keys.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            bytes_saved: self.bytes_saved.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for FieldInterner {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Field interning statistics
+#[derive(Debug, Clone)]
+pub struct FieldInterningStats {
+    pub enabled: bool,
+    /// Names currently held by the pool
+    pub distinct_names: usize,
+    /// Object keys decoded
+    pub keys: u64,
+    /// Keys served by an already pooled name
+    pub hits: u64,
+    /// Key bytes that did not have to be allocated thanks to pooling
+    pub bytes_saved: u64,
+}
+
+#[derive(Default)]
+struct KeyCounts {
+    keys: u64,
+    hits: u64,
+    bytes_saved: u64,
+}
+
+/// Deserialization state for one document (holds the pool lock)
+struct InternSeed<'p> {
+    pool: Option<&'p mut HashSet<Arc<str>>>,
+    max_names: usize,
+    counts: KeyCounts,
+}
+
+impl InternSeed<'_> {
+    fn intern(&mut self, name: &str) -> Arc<str> {
+        self.counts.keys += 1;
+        let Some(pool) = self.pool.as_deref_mut() else {
+            return Arc::from(name);
+        };
+
+        if let Some(shared) = pool.get(name) {
+            self.counts.hits += 1;
+            self.counts.bytes_saved += name.len() as u64;
+            return Arc::clone(shared);
+        }
+
+        let shared: Arc<str> = Arc::from(name);
+        if pool.len() < self.max_names {
+            pool.insert(Arc::clone(&shared));
+        }
+        shared
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &mut InternSeed<'_> {
+    type Value = InternedValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for &mut InternSeed<'_> {
+    type Value = InternedValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(InternedValue::Null)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(InternedValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(InternedValue::Number(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(InternedValue::Number(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Number::from_f64(v).map_or(InternedValue::Null, InternedValue::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(InternedValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(InternedValue::String(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element_seed(&mut *self)? {
+            items.push(item);
+        }
+        Ok(InternedValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields: Vec<(Arc<str>, InternedValue)> = Vec::new();
+        while let Some(key) = map.next_key::<KeyName<'de>>()? {
+            let name = self.intern(key.as_str());
+            let value = map.next_value_seed(&mut *self)?;
+            // Last occurrence wins, as with serde_json::Map
+            match fields.iter_mut().find(|(existing, _)| *existing == name) {
+                Some(slot) => slot.1 = value,
+                None => fields.push((name, value)),
+            }
+        }
+        fields.shrink_to_fit();
+        Ok(InternedValue::Object(fields))
+    }
+}
+
+/// Object key borrowed from the input where possible (escaped keys are owned)
+enum KeyName<'de> {
+    Borrowed(&'de str),
+    Owned(String),
+}
+
+impl KeyName<'_> {
+    fn as_str(&self) -> &str {
+        match self {
+            KeyName::Borrowed(s) => s,
+            KeyName::Owned(s) => s,
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for KeyName<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = KeyName<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object key")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(KeyName::Borrowed(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(KeyName::Owned(v.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(KeyName::Owned(v))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip_matches_serde_json() {
+        let doc = json!({
+            "_id": 1,
+            "name": "Alice \"A\"",
+            "score": 9.5,
+            "big": u64::MAX,
+            "neg": -3,
+            "tags": ["x", {"nested": null}],
+            "address": {"city": "Budapest", "zip": "1011"},
+            "esc\u{00e9}": true
+        });
+        let bytes = serde_json::to_vec(&doc).unwrap();
+
+        for interner in [FieldInterner::new(64), FieldInterner::disabled()] {
+            let parsed = interner.parse(&bytes).unwrap();
+            assert_eq!(parsed.to_value(), doc);
+            assert_eq!(
+                parsed.get_path("address.city"),
+                Some(&InternedValue::String("Budapest".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_keys_shared_across_documents() {
+        let interner = FieldInterner::new(64);
+        let a = interner.parse(br#"{"customer_name": "a"}"#).unwrap();
+        let b = interner.parse(br#"{"customer_name": "b"}"#).unwrap();
+
+        let (InternedValue::Object(a), InternedValue::Object(b)) = (a, b) else {
+            panic!("expected objects");
+        };
+        assert!(Arc::ptr_eq(&a[0].0, &b[0].0));
+
+        let stats = interner.stats();
+        assert_eq!(stats.keys, 2);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.bytes_saved, "customer_name".len() as u64);
+        assert_eq!(stats.distinct_names, 1);
+    }
+
+    #[test]
+    fn test_disabled_interner_shares_nothing() {
+        let interner = FieldInterner::disabled();
+        interner.parse(br#"{"k": 1}"#).unwrap();
+        interner.parse(br#"{"k": 2}"#).unwrap();
+
+        let stats = interner.stats();
+        assert!(!stats.enabled);
+        assert_eq!(stats.keys, 2);
+        assert_eq!(stats.hits, 0);
+    }
+
+    #[test]
+    fn test_pool_size_is_bounded() {
+        let interner = FieldInterner::new(2);
+        interner.parse(br#"{"a": 1, "b": 2, "c": 3}"#).unwrap();
+        interner.parse(br#"{"c": 4}"#).unwrap();
+
+        let stats = interner.stats();
+        assert_eq!(stats.distinct_names, 2);
+        // "c" did not fit in the pool, so the second read is not a hit
+        assert_eq!(stats.hits, 0);
+    }
+
+    #[test]
+    fn test_duplicate_keys_last_wins() {
+        let parsed = FieldInterner::new(8).parse(br#"{"a": 1, "a": 2}"#).unwrap();
+        assert_eq!(parsed.to_value(), json!({"a": 2}));
+    }
+}
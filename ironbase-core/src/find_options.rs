@@ -1,9 +1,15 @@
 // ironbase-core/src/find_options.rs
 // Find query options: projection, sort, limit, skip
 
-use crate::value_utils::get_nested_value;
+use crate::error::Result;
+use crate::value_utils::{compare_for_sort, get_nested_value};
+use serde::de::{
+    self, DeserializeSeed, Deserializer as _, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
 
 /// Options for find queries
 #[derive(Debug, Clone, Default)]
@@ -55,13 +61,7 @@ pub fn apply_projection(doc: &Value, projection: &HashMap<String, i32>) -> Value
         return doc.clone();
     }
 
-    // Detect mode
-    let has_inclusions = projection.values().any(|&v| v == 1);
-    let has_non_id_exclusions = projection
-        .iter()
-        .any(|(field, &action)| action == 0 && field != "_id");
-
-    let include_mode = has_inclusions && !has_non_id_exclusions;
+    let include_mode = is_include_mode(projection);
 
     if let Value::Object(obj) = doc {
         let mut result = serde_json::Map::new();
@@ -100,8 +100,265 @@ pub fn apply_projection(doc: &Value, projection: &HashMap<String, i32>) -> Value
     }
 }
 
+/// Include mode: only inclusions (plus an optional `_id` exclusion)
+fn is_include_mode(projection: &HashMap<String, i32>) -> bool {
+    let has_inclusions = projection.values().any(|&v| v == 1);
+    let has_non_id_exclusions = projection
+        .iter()
+        .any(|(field, &action)| action == 0 && field != "_id");
+
+    has_inclusions && !has_non_id_exclusions
+}
+
+/// Stream-parse a JSON document, keeping only the top-level fields a projection needs
+///
+/// Skipped fields are scanned but never materialized, so projecting a small
+/// field out of a very large document stays cheap. `_id` and `_tombstone` are
+/// always kept; nested projections keep their whole top-level field. The
+/// result is still a (pruned) document - pass it to [`apply_projection`] for
+/// the final shape.
+pub fn parse_projected<R: Read>(reader: R, projection: &HashMap<String, i32>) -> Result<Value> {
+    let include_mode = is_include_mode(projection);
+    let keep = |key: &str| {
+        if key == "_id" || key == "_tombstone" {
+            true
+        } else if include_mode {
+            projection
+                .iter()
+                .any(|(field, &action)| action == 1 && field.split('.').next() == Some(key))
+        } else {
+            projection.get(key) != Some(&0)
+        }
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let doc = deserializer.deserialize_map(PrunedDocument { keep })?;
+    deserializer.end()?;
+    Ok(doc)
+}
+
+struct PrunedDocument<F> {
+    keep: F,
+}
+
+impl<'de, F: Fn(&str) -> bool> Visitor<'de> for PrunedDocument<F> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut fields = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if (self.keep)(&key) {
+                let value = map.next_value::<Value>()?;
+                fields.insert(key, value);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+/// Array lengths stream-read from a record
+#[derive(Debug, Clone, Default)]
+pub struct SizedDocument {
+    /// The record's `_id` and `_tombstone` (every other field is skipped)
+    pub doc: Value,
+    /// Length per requested path; absent when the value is missing or not an array
+    pub sizes: HashMap<String, usize>,
+}
+
+/// Stream-parse a JSON document, counting the elements of arrays at `paths`
+///
+/// Arrays are walked element by element without materializing them, so
+/// only `$size` of a huge array costs no more memory than the rest of the
+/// record's framing. Paths use dot notation through nested objects; no path
+/// may be a prefix of another.
+pub fn parse_array_sizes<R: Read>(reader: R, paths: &[String]) -> Result<SizedDocument> {
+    let mut sized = SizedDocument::default();
+    let targets: Vec<(&str, &str)> = paths.iter().map(|p| (p.as_str(), p.as_str())).collect();
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut meta = serde_json::Map::new();
+    deserializer.deserialize_map(SizeVisitor {
+        targets,
+        sizes: &mut sized.sizes,
+        meta: Some(&mut meta),
+    })?;
+    deserializer.end()?;
+
+    sized.doc = Value::Object(meta);
+    Ok(sized)
+}
+
+/// Walks one object level looking for target paths
+///
+/// `targets` holds (remaining path, full path) pairs; `meta` is only set at
+/// the top level, where `_id` and `_tombstone` are captured.
+struct SizeVisitor<'a, 'm> {
+    targets: Vec<(&'a str, &'a str)>,
+    sizes: &'m mut HashMap<String, usize>,
+    meta: Option<&'m mut serde_json::Map<String, Value>>,
+}
+
+impl<'de> DeserializeSeed<'de> for SizeVisitor<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> std::result::Result<(), D::Error> {
+        d.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SizeVisitor<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if let Some(meta) = self.meta.as_deref_mut() {
+                if key == "_id" || key == "_tombstone" {
+                    meta.insert(key, map.next_value::<Value>()?);
+                    continue;
+                }
+            }
+
+            if let Some(&(_, full)) = self.targets.iter().find(|(rest, _)| *rest == key) {
+                match map.next_value_seed(ArrayLength)? {
+                    Some(len) => self.sizes.insert(full.to_string(), len),
+                    None => self.sizes.remove(full),
+                };
+                continue;
+            }
+
+            let nested: Vec<(&str, &str)> = self
+                .targets
+                .iter()
+                .filter_map(|&(rest, full)| {
+                    rest.strip_prefix(key.as_str())
+                        .and_then(|r| r.strip_prefix('.'))
+                        .map(|r| (r, full))
+                })
+                .collect();
+            if nested.is_empty() {
+                map.next_value::<IgnoredAny>()?;
+            } else {
+                map.next_value_seed(SizeVisitor {
+                    targets: nested,
+                    sizes: &mut *self.sizes,
+                    meta: None,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    // Anything but an object holds none of the target paths
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Element count of an array value (None for any other type)
+struct ArrayLength;
+
+impl<'de> DeserializeSeed<'de> for ArrayLength {
+    type Value = Option<usize>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        d: D,
+    ) -> std::result::Result<Option<usize>, D::Error> {
+        d.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArrayLength {
+    type Value = Option<usize>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut len = 0;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            len += 1;
+        }
+        Ok(Some(len))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
 /// Apply sort to documents
 /// Supports dot notation for nested fields (e.g., "address.city")
+///
+/// Values are ordered by `value_utils::compare_for_sort`: documents missing
+/// a sort field come before documents where it is `null`.
 pub fn apply_sort(docs: &mut [Value], sort: &[(String, i32)]) {
     if sort.is_empty() {
         return;
@@ -113,7 +370,7 @@ pub fn apply_sort(docs: &mut [Value], sort: &[(String, i32)]) {
             let val_a = get_nested_value(a, field);
             let val_b = get_nested_value(b, field);
 
-            let cmp = compare_values(val_a, val_b);
+            let cmp = compare_for_sort(val_a, val_b);
 
             if cmp != std::cmp::Ordering::Equal {
                 return if *direction == 1 { cmp } else { cmp.reverse() };
@@ -123,39 +380,35 @@ pub fn apply_sort(docs: &mut [Value], sort: &[(String, i32)]) {
     });
 }
 
-/// Compare two JSON values for sorting
-fn compare_values(a: Option<&Value>, b: Option<&Value>) -> std::cmp::Ordering {
-    use std::cmp::Ordering;
-
-    match (a, b) {
-        (None, None) => Ordering::Equal,
-        (None, Some(_)) => Ordering::Less, // null < any value
-        (Some(_), None) => Ordering::Greater,
-
-        (Some(Value::Number(n1)), Some(Value::Number(n2))) => {
-            let f1 = n1.as_f64().unwrap_or(0.0);
-            let f2 = n2.as_f64().unwrap_or(0.0);
-            f1.partial_cmp(&f2).unwrap_or(Ordering::Equal)
-        }
-
-        (Some(Value::String(s1)), Some(Value::String(s2))) => s1.cmp(s2),
-
-        (Some(Value::Bool(b1)), Some(Value::Bool(b2))) => b1.cmp(b2),
-
-        // Type priority: null < number < string < bool < object < array
-        (Some(a_val), Some(b_val)) => type_priority(a_val).cmp(&type_priority(b_val)),
+/// Sort documents that are already ordered by the first `sorted_keys` sort keys
+///
+/// Used when an index order covers a prefix of the sort: only runs of
+/// documents equal on that prefix are sorted, by the remaining keys, so the
+/// comparator never looks at the prefix.
+pub fn apply_sort_after_prefix(docs: &mut [Value], sort: &[(String, i32)], sorted_keys: usize) {
+    if sorted_keys == 0 {
+        apply_sort(docs, sort);
+        return;
+    }
+    let (prefix, rest) = sort.split_at(sorted_keys.min(sort.len()));
+    if rest.is_empty() {
+        return;
     }
-}
 
-/// Get type priority for mixed-type sorting
-fn type_priority(val: &Value) -> u8 {
-    match val {
-        Value::Null => 0,
-        Value::Number(_) => 1,
-        Value::String(_) => 2,
-        Value::Bool(_) => 3,
-        Value::Object(_) => 4,
-        Value::Array(_) => 5,
+    let same_prefix = |a: &Value, b: &Value| {
+        prefix.iter().all(|(field, _)| {
+            compare_for_sort(get_nested_value(a, field), get_nested_value(b, field))
+                == std::cmp::Ordering::Equal
+        })
+    };
+    let mut start = 0;
+    while start < docs.len() {
+        let mut end = start + 1;
+        while end < docs.len() && same_prefix(&docs[start], &docs[end]) {
+            end += 1;
+        }
+        apply_sort(&mut docs[start..end], rest);
+        start = end;
     }
 }
 
@@ -265,6 +518,40 @@ mod tests {
         assert_eq!(docs[2].get("name").unwrap(), "Bob"); // age=30, name=B
     }
 
+    #[test]
+    fn test_sort_after_prefix_only_breaks_ties() {
+        // The first two keys are out of order on purpose: the index order is
+        // trusted for them, only the third key is compared within each run
+        let mut docs = vec![
+            json!({"a": 2, "b": 1, "c": "z", "id": 0}),
+            json!({"a": 2, "b": 1, "c": "x", "id": 1}),
+            json!({"a": 1, "b": 5, "c": "y", "id": 2}),
+            json!({"a": 1, "b": 5, "c": "w", "id": 3}),
+            json!({"a": 1, "b": 5, "c": "x", "id": 4}),
+            json!({"a": 1, "b": 2, "c": "a", "id": 5}),
+        ];
+        let sort = vec![
+            ("a".to_string(), 1),
+            ("b".to_string(), 1),
+            ("c".to_string(), 1),
+        ];
+
+        apply_sort_after_prefix(&mut docs, &sort, 2);
+
+        let ids: Vec<i64> = docs.iter().map(|d| d["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, vec![1, 0, 3, 4, 2, 5]);
+
+        // Nothing left to sort once the prefix covers every key
+        apply_sort_after_prefix(&mut docs, &sort, 3);
+        let unchanged: Vec<i64> = docs.iter().map(|d| d["id"].as_i64().unwrap()).collect();
+        assert_eq!(unchanged, ids);
+
+        // Without a prefix it is a full sort
+        apply_sort_after_prefix(&mut docs, &sort, 0);
+        let sorted: Vec<i64> = docs.iter().map(|d| d["id"].as_i64().unwrap()).collect();
+        assert_eq!(sorted, vec![5, 3, 4, 2, 1, 0]);
+    }
+
     #[test]
     fn test_sort_string() {
         let mut docs = vec![
@@ -483,4 +770,87 @@ mod tests {
         assert_eq!(docs[1].get("name").unwrap(), "Alice");
         assert_eq!(docs[2].get("name").unwrap(), "Charlie");
     }
+
+    #[test]
+    fn test_sort_missing_before_null() {
+        let mut docs = vec![
+            json!({"n": 1, "v": 5}),
+            json!({"n": 2, "v": null}),
+            json!({"n": 3}),
+            json!({"n": 4, "v": "a"}),
+            json!({"n": 5, "v": null}),
+            json!({"n": 6}),
+            json!({"n": 7, "v": 0}),
+        ];
+        let order = |docs: &[Value]| {
+            docs.iter()
+                .map(|d| d["n"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        apply_sort(&mut docs, &[("v".to_string(), 1)]);
+        // missing < null < numbers < strings; ties keep their input order
+        assert_eq!(order(&docs), [3, 6, 2, 5, 7, 1, 4]);
+
+        apply_sort(&mut docs, &[("v".to_string(), -1)]);
+        assert_eq!(order(&docs), [4, 1, 7, 2, 5, 3, 6]);
+    }
+
+    #[test]
+    fn test_parse_projected_keeps_only_needed_fields() {
+        let raw = br#"{"_id": 1, "name": "Alice", "blob": {"big": [1, 2, 3]}, "address": {"city": "NYC", "zip": "1"}}"#;
+
+        let include = HashMap::from([("name".to_string(), 1), ("address.city".to_string(), 1)]);
+        let pruned = parse_projected(&raw[..], &include).unwrap();
+        assert_eq!(
+            pruned,
+            json!({"_id": 1, "name": "Alice", "address": {"city": "NYC", "zip": "1"}})
+        );
+
+        // Final projection matches projecting the fully parsed document
+        let full: Value = serde_json::from_slice(raw).unwrap();
+        assert_eq!(
+            apply_projection(&pruned, &include),
+            apply_projection(&full, &include)
+        );
+
+        let exclude = HashMap::from([("blob".to_string(), 0)]);
+        let pruned = parse_projected(&raw[..], &exclude).unwrap();
+        assert!(pruned.get("blob").is_none());
+        assert_eq!(pruned["address"]["zip"], "1");
+    }
+
+    #[test]
+    fn test_parse_array_sizes_counts_without_materializing() {
+        let raw = serde_json::to_vec(&json!({
+            "_id": 7,
+            "tags": ["a", "b", "c"],
+            "name": "x",
+            "profile": {"skills": [1, 2], "bio": "long"},
+            "scalar": 5
+        }))
+        .unwrap();
+        let paths: Vec<String> = ["tags", "profile.skills", "scalar", "missing"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+
+        let sized = parse_array_sizes(&raw[..], &paths).unwrap();
+        assert_eq!(sized.doc, json!({"_id": 7}));
+        assert_eq!(sized.sizes.get("tags"), Some(&3));
+        assert_eq!(sized.sizes.get("profile.skills"), Some(&2));
+        // Not an array / not present
+        assert_eq!(sized.sizes.get("scalar"), None);
+        assert_eq!(sized.sizes.get("missing"), None);
+    }
+
+    #[test]
+    fn test_parse_projected_accepts_padded_records() {
+        let raw = b"{\"_id\": 7, \"_tombstone\": true, \"x\": 1}        ";
+        let projection = HashMap::from([("y".to_string(), 1)]);
+        assert_eq!(
+            parse_projected(&raw[..], &projection).unwrap(),
+            json!({"_id": 7, "_tombstone": true})
+        );
+    }
 }
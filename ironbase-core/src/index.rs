@@ -1,9 +1,12 @@
 // src/index.rs
 // B+ Tree Index Implementation
 
-use crate::document::DocumentId;
+use crate::bloom::BloomFilter;
+use crate::decimal::{decimal_text, Decimal};
+use crate::document::{Document, DocumentId};
 use crate::error::{MongoLiteError, Result};
-use crate::value_utils::get_nested_value;
+use crate::text_index::{TextIndex, TextIndexMetadata};
+use crate::value_utils::{get_all_nested_values, get_nested_value};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -15,6 +18,13 @@ pub const NODE_PAGE_SIZE: usize = 4096; // 4KB pages
 const NODE_TYPE_INTERNAL: u8 = 0;
 const NODE_TYPE_LEAF: u8 = 1;
 
+/// Magic bytes at the start of every persisted .idx file
+const INDEX_FILE_MAGIC: [u8; 8] = *b"IRONIDX\0";
+/// Current on-disk index format version
+pub const INDEX_FILE_VERSION: u32 = 1;
+/// Header layout: [magic: 8][version: u32][reserved: u32][root_offset: u64]
+const INDEX_FILE_HEADER_SIZE: usize = 24;
+
 /// Index key - supported types for indexing
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IndexKey {
@@ -25,6 +35,9 @@ pub enum IndexKey {
     String(String),
     /// Compound key for multi-field indexes (e.g., ["country", "city"])
     Compound(Vec<IndexKey>),
+    /// Tagged decimal that isn't an exact `i64` (see `crate::decimal`);
+    /// ordered by value among `Int` and `Float`
+    Decimal(Decimal),
 }
 
 /// OrderedFloat wrapper for f64 to enable Ord
@@ -79,12 +92,15 @@ impl Ord for IndexKey {
             (_, Bool(_)) => std::cmp::Ordering::Greater,
 
             (Int(a), Int(b)) => a.cmp(b),
-            (Int(_), _) => std::cmp::Ordering::Less,
-            (_, Int(_)) => std::cmp::Ordering::Greater,
-
             (Float(a), Float(b)) => a.cmp(b),
-            (Float(_), _) => std::cmp::Ordering::Less,
-            (_, Float(_)) => std::cmp::Ordering::Greater,
+            (Decimal(a), Decimal(b)) => a.cmp(b),
+            // Mixed numbers compare by value so range scans see every kind;
+            // equal values order Int, Float, Decimal
+            (Int(_) | Float(_) | Decimal(_), Int(_) | Float(_) | Decimal(_)) => self
+                .cmp_numeric(other)
+                .then_with(|| self.numeric_rank().cmp(&other.numeric_rank())),
+            (Int(_) | Float(_) | Decimal(_), _) => std::cmp::Ordering::Less,
+            (_, Int(_) | Float(_) | Decimal(_)) => std::cmp::Ordering::Greater,
 
             (String(a), String(b)) => a.cmp(b),
             (String(_), Compound(_)) => std::cmp::Ordering::Less,
@@ -96,6 +112,79 @@ impl Ord for IndexKey {
     }
 }
 
+/// Exact comparison of an integer with a float of magnitude below 2^53
+fn cmp_int_float(i: i64, f: f64) -> std::cmp::Ordering {
+    let whole = f.trunc();
+    i.cmp(&(whole as i64))
+        .then_with(|| whole.partial_cmp(&f).expect("finite float"))
+}
+
+impl IndexKey {
+    /// Value comparison of two numeric keys of different kinds
+    ///
+    /// A float stands for the shortest decimal that round-trips to it (its
+    /// `Display` form), the value query matching gives it when comparing
+    /// against a decimal, so `0.1` ties with decimal `0.1`. NaN sorts above
+    /// every number, as it does among floats.
+    fn cmp_numeric(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        // Below 2^53 a float's shortest form orders against any integer like
+        // the float itself, so compare without building decimals
+        const EXACT_INT: f64 = 9_007_199_254_740_992.0;
+        match (self, other) {
+            (IndexKey::Int(i), IndexKey::Float(f)) if f.0.abs() < EXACT_INT => {
+                return cmp_int_float(*i, f.0)
+            }
+            (IndexKey::Float(f), IndexKey::Int(i)) if f.0.abs() < EXACT_INT => {
+                return cmp_int_float(*i, f.0).reverse()
+            }
+            _ => {}
+        }
+        let exact = |key: &Self| -> std::result::Result<Decimal, f64> {
+            match key {
+                IndexKey::Int(i) => Ok(i.to_string().parse().expect("integer parses")),
+                IndexKey::Decimal(d) => Ok(d.clone()),
+                // Display never uses exponent notation for f64
+                IndexKey::Float(f) if f.0.is_finite() => {
+                    Ok(f.0.to_string().parse().expect("finite float parses"))
+                }
+                IndexKey::Float(f) => Err(f.0),
+                _ => unreachable!("cmp_numeric called on a non-numeric key"),
+            }
+        };
+        match (exact(self), exact(other)) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Err(a), Err(b)) => OrderedFloat(a).cmp(&OrderedFloat(b)),
+            (Err(a), Ok(_)) if a == f64::NEG_INFINITY => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Ok(_), Err(b)) if b == f64::NEG_INFINITY => Ordering::Greater,
+            (Ok(_), Err(_)) => Ordering::Less,
+        }
+    }
+
+    fn numeric_rank(&self) -> u8 {
+        match self {
+            IndexKey::Int(_) => 0,
+            IndexKey::Float(_) => 1,
+            _ => 2,
+        }
+    }
+
+    /// The JSON value behind a key, for keys that store it exactly
+    ///
+    /// None for `Null` (which also stands for arrays and objects), decimals
+    /// and compound keys.
+    pub fn to_value(&self) -> Option<serde_json::Value> {
+        match self {
+            IndexKey::Bool(b) => Some(serde_json::Value::Bool(*b)),
+            IndexKey::Int(i) => Some(serde_json::Value::from(*i)),
+            IndexKey::Float(f) => Some(serde_json::Value::from(f.0)),
+            IndexKey::String(s) => Some(serde_json::Value::String(s.clone())),
+            IndexKey::Null | IndexKey::Decimal(_) | IndexKey::Compound(_) => None,
+        }
+    }
+}
+
 /// Convert serde_json::Value to IndexKey
 impl From<&serde_json::Value> for IndexKey {
     fn from(value: &serde_json::Value) -> Self {
@@ -112,11 +201,50 @@ impl From<&serde_json::Value> for IndexKey {
                 }
             }
             serde_json::Value::String(s) => IndexKey::String(s.clone()),
+            serde_json::Value::Object(_) => {
+                match decimal_text(value).and_then(|text| text.parse::<Decimal>().ok()) {
+                    Some(d) => d.to_i64().map_or(IndexKey::Decimal(d), IndexKey::Int),
+                    None => IndexKey::Null,
+                }
+            }
             _ => IndexKey::Null, // Arrays and objects -> Null for simple index
         }
     }
 }
 
+/// Index keys a single-field index stores for a document
+///
+/// A path that resolves directly (through objects and numeric array positions)
+/// yields exactly one key. A path that only resolves by descending into an
+/// array of objects (e.g. `items.price`) yields one key per distinct value
+/// reached, so a query matching any element can still use the index.
+///
+/// A document without the field yields no keys at all, while an explicit
+/// `null` yields `IndexKey::Null`. An index lookup for `null` therefore finds
+/// exactly the documents `{"field": null}` matches on a scan; documents
+/// missing the field are only reachable through a scan (`$exists: false`).
+pub fn index_keys_for_field(doc: &serde_json::Value, field: &str) -> Vec<IndexKey> {
+    match get_nested_value(doc, field) {
+        Some(value) => vec![IndexKey::from(value)],
+        None => multikey(get_all_nested_values(doc, field)),
+    }
+}
+
+/// Same as `index_keys_for_field`, for a parsed document
+pub(crate) fn index_keys_for_document(doc: &Document, field: &str) -> Vec<IndexKey> {
+    match doc.get(field) {
+        Some(value) => vec![IndexKey::from(value)],
+        None => multikey(doc.get_all(field)),
+    }
+}
+
+fn multikey(values: Vec<&serde_json::Value>) -> Vec<IndexKey> {
+    let mut keys: Vec<IndexKey> = values.into_iter().map(IndexKey::from).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
 /// B+ Tree Node types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BTreeNode {
@@ -144,6 +272,8 @@ pub struct LeafNode {
 pub struct BPlusTree {
     root: Box<BTreeNode>,
     pub metadata: IndexMetadata,
+    /// Present when `metadata.bloom_filter` is set; rebuilt rather than persisted
+    bloom: Option<BloomFilter>,
 }
 
 /// Index metadata
@@ -157,11 +287,20 @@ pub struct IndexMetadata {
     #[serde(default)]
     pub fields: Vec<String>,
     pub unique: bool,
+    /// Reserved; single-field indexes already skip documents missing the
+    /// field and always index explicit nulls
     pub sparse: bool,
     pub num_keys: u64,
     pub tree_height: u32,
     #[serde(default)]
     pub root_offset: u64, // File offset to root node (0 = in-memory only)
+    /// Consult a bloom filter before the tree on point lookups (opt-in)
+    #[serde(default)]
+    pub bloom_filter: bool,
+    /// TTL index: documents expire this many seconds after the Unix
+    /// timestamp stored in `field`
+    #[serde(default)]
+    pub expire_after_secs: Option<u64>,
 }
 
 impl IndexMetadata {
@@ -171,6 +310,40 @@ impl IndexMetadata {
     }
 }
 
+/// Logical description of an index: the fields it covers, not its storage name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    /// Index name (carries the collection prefix, e.g. "users_age")
+    pub name: String,
+    /// Indexed fields in key order (one entry for single-field indexes)
+    pub fields: Vec<String>,
+    pub unique: bool,
+    pub sparse: bool,
+}
+
+impl IndexInfo {
+    /// Check if this is a compound index (multiple fields)
+    pub fn is_compound(&self) -> bool {
+        self.fields.len() > 1
+    }
+}
+
+impl From<&IndexMetadata> for IndexInfo {
+    fn from(metadata: &IndexMetadata) -> Self {
+        let fields = if metadata.fields.is_empty() {
+            vec![metadata.field.clone()]
+        } else {
+            metadata.fields.clone()
+        };
+        IndexInfo {
+            name: metadata.name.clone(),
+            fields,
+            unique: metadata.unique,
+            sparse: metadata.sparse,
+        }
+    }
+}
+
 impl BPlusTree {
     /// Create new B+ tree index (single field)
     pub fn new(name: String, field: String, unique: bool) -> Self {
@@ -192,7 +365,10 @@ impl BPlusTree {
                 num_keys: 0,
                 tree_height: 1,
                 root_offset: 0,
+                bloom_filter: false,
+                expire_after_secs: None,
             },
+            bloom: None,
         }
     }
 
@@ -236,7 +412,10 @@ impl BPlusTree {
                 num_keys: 0,
                 tree_height: 1,
                 root_offset: 0,
+                bloom_filter: false,
+                expire_after_secs: None,
             },
+            bloom: None,
         }
     }
 
@@ -264,11 +443,92 @@ impl BPlusTree {
         }
     }
 
+    /// Keys this index stores for `doc`
+    ///
+    /// A compound index stores one `Compound` key (see `extract_key`); a
+    /// single-field index stores the keys from `index_keys_for_field`.
+    pub fn keys_for_value(&self, doc: &serde_json::Value) -> Vec<IndexKey> {
+        if self.metadata.is_compound() {
+            vec![self.extract_key(doc)]
+        } else {
+            index_keys_for_field(doc, &self.metadata.field)
+        }
+    }
+
+    /// Same as `keys_for_value`, for a parsed document
+    pub(crate) fn keys_for_document(&self, doc: &Document) -> Vec<IndexKey> {
+        if self.metadata.is_compound() {
+            let keys = self
+                .metadata
+                .fields
+                .iter()
+                .map(|field| doc.get(field).map(IndexKey::from).unwrap_or(IndexKey::Null))
+                .collect();
+            vec![IndexKey::Compound(keys)]
+        } else {
+            index_keys_for_document(doc, &self.metadata.field)
+        }
+    }
+
     /// Search for a key in the index
     pub fn search(&self, key: &IndexKey) -> Option<DocumentId> {
+        if !self.may_contain(key) {
+            return None;
+        }
         self.search_in_node(&self.root, key)
     }
 
+    /// Whether any document is indexed under `key`
+    pub fn contains_key(&self, key: &IndexKey) -> bool {
+        self.search(key).is_some()
+    }
+
+    /// All documents indexed under `key` (equality lookup)
+    pub fn lookup(&self, key: &IndexKey) -> Vec<DocumentId> {
+        if !self.may_contain(key) {
+            return Vec::new();
+        }
+        self.range_scan(key, key, true, true)
+    }
+
+    /// False only if `key` is definitely not in the index
+    ///
+    /// Always true when the index has no bloom filter.
+    pub fn may_contain(&self, key: &IndexKey) -> bool {
+        self.bloom
+            .as_ref()
+            .is_none_or(|bloom| bloom.may_contain(key))
+    }
+
+    /// Turn on the bloom filter for this index and build it from the current keys
+    pub fn enable_bloom_filter(&mut self) {
+        self.metadata.bloom_filter = true;
+        self.rebuild_bloom_filter();
+    }
+
+    /// Whether point lookups consult a bloom filter first
+    pub fn has_bloom_filter(&self) -> bool {
+        self.bloom.is_some()
+    }
+
+    /// Rebuild the bloom filter from the keys in the tree (no-op when disabled)
+    ///
+    /// Sized at twice the current key count so inserts have room before the
+    /// filter saturates; also drops bits left behind by deleted keys.
+    fn rebuild_bloom_filter(&mut self) {
+        if !self.metadata.bloom_filter {
+            self.bloom = None;
+            return;
+        }
+
+        let entries = self.get_all_entries();
+        let mut bloom = BloomFilter::with_capacity(entries.len() * 2);
+        for (key, _) in &entries {
+            bloom.insert(key);
+        }
+        self.bloom = Some(bloom);
+    }
+
     fn search_in_node(&self, node: &BTreeNode, key: &IndexKey) -> Option<DocumentId> {
         match node {
             BTreeNode::Internal(internal) => {
@@ -344,11 +604,18 @@ impl BPlusTree {
         // Full implementation would handle splits and internal nodes
         if let BTreeNode::Leaf(ref mut leaf) = *self.root {
             let insert_pos = leaf.keys.binary_search(&key).unwrap_or_else(|pos| pos);
+            if let Some(bloom) = self.bloom.as_mut() {
+                bloom.insert(&key);
+            }
             leaf.keys.insert(insert_pos, key);
             leaf.document_ids.insert(insert_pos, doc_id);
             self.metadata.num_keys += 1;
         }
 
+        if self.bloom.as_ref().is_some_and(BloomFilter::is_saturated) {
+            self.rebuild_bloom_filter();
+        }
+
         Ok(())
     }
 
@@ -364,21 +631,19 @@ impl BPlusTree {
     ///
     /// # Returns
     /// * `Ok(())` on success
-    /// * `Err(IndexError)` if unique constraint violated and check_unique is true
+    /// * `Err(DuplicateKey)` if unique constraint violated and check_unique is true
     pub fn build_from_sorted(
         &mut self,
         entries: Vec<(IndexKey, DocumentId)>,
         check_unique: bool,
     ) -> Result<()> {
         // Check unique constraint if required - O(n) scan for adjacent duplicates
-        if check_unique && entries.len() > 1 {
-            for i in 0..entries.len() - 1 {
-                if entries[i].0 == entries[i + 1].0 {
-                    return Err(MongoLiteError::IndexError(format!(
-                        "Duplicate key: {:?} (unique index)",
-                        entries[i].0
-                    )));
-                }
+        if check_unique {
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(MongoLiteError::DuplicateKey(format!(
+                    "{:?} in unique index '{}' (documents {:?} and {:?})",
+                    pair[0].0, self.metadata.name, pair[0].1, pair[1].1
+                )));
             }
         }
 
@@ -391,23 +656,27 @@ impl BPlusTree {
             leaf.keys = keys;
             leaf.document_ids = document_ids;
         }
+        self.rebuild_bloom_filter();
 
         Ok(())
     }
 
     /// Delete key-document pair from index
+    ///
+    /// The bloom filter keeps the deleted key's bits until the next rebuild,
+    /// which only costs a tree lookup for that key.
     pub fn delete(&mut self, key: &IndexKey, doc_id: &DocumentId) -> Result<()> {
         // For now, simplified delete from leaf
         // Full implementation would handle merges and internal nodes
         if let BTreeNode::Leaf(ref mut leaf) = *self.root {
-            // Find the key position
-            if let Ok(pos) = leaf.keys.binary_search(key) {
-                // Verify this is the correct document ID
-                if &leaf.document_ids[pos] == doc_id {
-                    leaf.keys.remove(pos);
-                    leaf.document_ids.remove(pos);
-                    self.metadata.num_keys -= 1;
-                }
+            // Non-unique indexes can hold the key for several documents:
+            // search the whole run of equal keys for this document ID
+            let start = leaf.keys.partition_point(|k| k < key);
+            let end = leaf.keys.partition_point(|k| k <= key);
+            if let Some(pos) = (start..end).find(|&pos| &leaf.document_ids[pos] == doc_id) {
+                leaf.keys.remove(pos);
+                leaf.document_ids.remove(pos);
+                self.metadata.num_keys = leaf.keys.len() as u64;
             }
         }
 
@@ -568,57 +837,121 @@ impl BPlusTree {
         inclusive_start: bool,
         inclusive_end: bool,
     ) -> Vec<DocumentId> {
-        fn collect_leaf(
-            node: &BTreeNode,
-            start: &IndexKey,
-            end: &IndexKey,
-            inclusive_start: bool,
-            inclusive_end: bool,
-            results: &mut Vec<DocumentId>,
-        ) {
-            match node {
-                BTreeNode::Leaf(leaf) => {
-                    let start_idx = if inclusive_start {
-                        leaf.keys.partition_point(|k| k < start)
-                    } else {
-                        leaf.keys.partition_point(|k| k <= start)
-                    };
-                    let end_idx = if inclusive_end {
-                        leaf.keys.partition_point(|k| k <= end)
-                    } else {
-                        leaf.keys.partition_point(|k| k < end)
-                    };
-                    for idx in start_idx..end_idx {
-                        if idx < leaf.document_ids.len() {
-                            results.push(leaf.document_ids[idx].clone());
-                        }
-                    }
+        let mut results = Vec::new();
+        self.for_each_in_range(start, end, inclusive_start, inclusive_end, |_, doc_id| {
+            results.push(doc_id.clone())
+        });
+        results
+    }
+
+    /// Compound index scan over one key prefix
+    ///
+    /// Visits the entries whose compound key starts with `prefix` and whose
+    /// next component lies between `start` and `end` (`None` leaves that
+    /// side open). The scan seeks to `prefix + start` and stops at the first
+    /// key past `prefix + end`, so only in-range entries are read.
+    pub fn compound_range_scan(
+        &self,
+        prefix: &[IndexKey],
+        start: Option<&IndexKey>,
+        end: Option<&IndexKey>,
+        inclusive_start: bool,
+        inclusive_end: bool,
+    ) -> Vec<DocumentId> {
+        let BTreeNode::Leaf(leaf) = self.root.as_ref() else {
+            return Vec::new();
+        };
+        let mut seek = prefix.to_vec();
+        seek.extend(start.cloned());
+        let seek = IndexKey::Compound(seek);
+
+        let first = leaf.keys.partition_point(|k| k < &seek);
+        let mut results = Vec::new();
+        for (key, doc_id) in leaf.keys[first..].iter().zip(&leaf.document_ids[first..]) {
+            let IndexKey::Compound(parts) = key else {
+                break;
+            };
+            if !parts.starts_with(prefix) {
+                break;
+            }
+            let next = parts.get(prefix.len());
+            if let Some(end) = end {
+                match next {
+                    Some(next) if next < end || (inclusive_end && next == end) => {}
+                    _ => break,
                 }
-                BTreeNode::Internal(internal) => {
-                    let child_index = internal.keys.partition_point(|k| k <= start);
-                    if child_index < internal.children_offsets.len() {
-                        // Load child node from file or memory (not implemented for persistent tree)
-                        // For now, fallback to scanning entire tree in memory
-                        for child_idx in child_index..internal.children_offsets.len() {
-                            // TODO: load child node properly (currently unsupported)
-                            // This is a placeholder to keep compiler happy
-                            let _child_offset = internal.children_offsets[child_idx];
-                        }
+            }
+            if !inclusive_start && start.is_some() && next == start {
+                continue;
+            }
+            results.push(doc_id.clone());
+        }
+        results
+    }
+
+    /// Number of entries between start and end (no id vector is built)
+    pub fn count_range(
+        &self,
+        start: &IndexKey,
+        end: &IndexKey,
+        inclusive_start: bool,
+        inclusive_end: bool,
+    ) -> usize {
+        let mut count = 0;
+        self.for_each_in_range(start, end, inclusive_start, inclusive_end, |_, _| {
+            count += 1
+        });
+        count
+    }
+
+    /// Visit every (key, document id) entry between start and end in key order
+    pub fn for_each_in_range<F: FnMut(&IndexKey, &DocumentId)>(
+        &self,
+        start: &IndexKey,
+        end: &IndexKey,
+        inclusive_start: bool,
+        inclusive_end: bool,
+        mut visit: F,
+    ) {
+        match self.root.as_ref() {
+            BTreeNode::Leaf(leaf) => {
+                let start_idx = if inclusive_start {
+                    leaf.keys.partition_point(|k| k < start)
+                } else {
+                    leaf.keys.partition_point(|k| k <= start)
+                };
+                let end_idx = if inclusive_end {
+                    leaf.keys.partition_point(|k| k <= end)
+                } else {
+                    leaf.keys.partition_point(|k| k < end)
+                };
+                for idx in start_idx..end_idx {
+                    if let Some(doc_id) = leaf.document_ids.get(idx) {
+                        visit(&leaf.keys[idx], doc_id);
                     }
                 }
             }
+            BTreeNode::Internal(_) => {
+                // Child loading for persistent internal nodes is not implemented;
+                // the in-memory tree is a single leaf
+            }
         }
+    }
 
-        let mut results = Vec::new();
-        collect_leaf(
-            &self.root,
-            start,
-            end,
-            inclusive_start,
-            inclusive_end,
-            &mut results,
-        );
-        results
+    /// Visit every (key, document id) entry in key order until `visit`
+    /// returns false
+    pub fn for_each_entry_while<'a, F>(&'a self, mut visit: F)
+    where
+        F: FnMut(&'a IndexKey, &'a DocumentId) -> bool,
+    {
+        // Like for_each_in_range, only the in-memory single leaf is walked
+        if let BTreeNode::Leaf(leaf) = self.root.as_ref() {
+            for (key, doc_id) in leaf.keys.iter().zip(&leaf.document_ids) {
+                if !visit(key, doc_id) {
+                    break;
+                }
+            }
+        }
     }
 
     /// Get index size (number of keys)
@@ -710,14 +1043,63 @@ impl BPlusTree {
     }
 
     /// Save entire tree to file (recursive)
+    ///
+    /// Writes the versioned file header at offset 0, followed by the node pages.
+    /// The file is expected to be empty (callers truncate before saving).
     pub fn save_to_file(&mut self, file: &mut File) -> Result<u64> {
+        // Reserve header space so node pages never start at offset 0
+        Self::write_file_header(file, 0)?;
+
         // Clone root to avoid borrowing issues
         let root_clone = self.root.clone();
         let root_offset = self.save_node_recursive(file, &root_clone)?;
+
+        Self::write_file_header(file, root_offset)?;
         self.metadata.root_offset = root_offset;
         Ok(root_offset)
     }
 
+    /// Write the .idx header pointing at the given root node
+    fn write_file_header(file: &mut File, root_offset: u64) -> Result<()> {
+        let mut header = [0u8; INDEX_FILE_HEADER_SIZE];
+        header[0..8].copy_from_slice(&INDEX_FILE_MAGIC);
+        header[8..12].copy_from_slice(&INDEX_FILE_VERSION.to_le_bytes());
+        header[16..24].copy_from_slice(&root_offset.to_le_bytes());
+
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Read and validate the .idx header, returning the root node offset
+    ///
+    /// Files written before versioning (no magic) and files with a different
+    /// version are rejected so the caller can rebuild from the catalog.
+    fn read_file_header(file: &mut File) -> Result<u64> {
+        let mut header = [0u8; INDEX_FILE_HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header).map_err(|_| {
+            MongoLiteError::IndexError("Index file is missing its version header".to_string())
+        })?;
+
+        if header[0..8] != INDEX_FILE_MAGIC {
+            return Err(MongoLiteError::IndexError(
+                "Index file has no version header (legacy format)".to_string(),
+            ));
+        }
+
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != INDEX_FILE_VERSION {
+            return Err(MongoLiteError::IndexError(format!(
+                "Unsupported index file version {} (expected {})",
+                version, INDEX_FILE_VERSION
+            )));
+        }
+
+        Ok(u64::from_le_bytes(header[16..24].try_into().unwrap()))
+    }
+
     /// Save node and children recursively
     fn save_node_recursive(&mut self, file: &mut File, node: &BTreeNode) -> Result<u64> {
         match node {
@@ -752,14 +1134,36 @@ impl BPlusTree {
     }
 
     /// Load tree from file given root offset
-    pub fn load_from_file(file: &mut File, metadata: IndexMetadata) -> Result<Self> {
-        // Note: offset 0 is valid (start of file), so we don't check for it
-        // An empty file would fail on load_node instead
+    ///
+    /// The root offset recorded in the file header takes precedence over the
+    /// one in `metadata`, which may have been persisted before the save, and
+    /// so does the key count of a leaf root. A leaf saved before numbers of
+    /// different kinds were ordered by value is re-sorted.
+    pub fn load_from_file(file: &mut File, mut metadata: IndexMetadata) -> Result<Self> {
+        metadata.root_offset = Self::read_file_header(file)?;
 
         // Load root node
-        let root = Box::new(Self::load_node(file, metadata.root_offset)?);
+        let mut root = Box::new(Self::load_node(file, metadata.root_offset)?);
+        if let BTreeNode::Leaf(leaf) = root.as_mut() {
+            metadata.num_keys = leaf.keys.len() as u64;
+            if !leaf.keys.is_sorted() {
+                let mut entries: Vec<_> = leaf
+                    .keys
+                    .drain(..)
+                    .zip(leaf.document_ids.drain(..))
+                    .collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                (leaf.keys, leaf.document_ids) = entries.into_iter().unzip();
+            }
+        }
 
-        Ok(BPlusTree { root, metadata })
+        let mut tree = BPlusTree {
+            root,
+            metadata,
+            bloom: None,
+        };
+        tree.rebuild_bloom_filter();
+        Ok(tree)
     }
 
     /// Two-Phase Commit: Phase 1 - Prepare changes to a temporary file
@@ -888,6 +1292,8 @@ pub struct IndexManager {
     legacy_indexes: HashMap<String, Index>,
     /// File paths for persistent indexes (for two-phase commit)
     index_file_paths: HashMap<String, PathBuf>,
+    /// The collection's full-text index, if any (at most one per collection)
+    text_index: Option<TextIndex>,
 }
 
 impl IndexManager {
@@ -896,6 +1302,7 @@ impl IndexManager {
             btree_indexes: HashMap::new(),
             legacy_indexes: HashMap::new(),
             index_file_paths: HashMap::new(),
+            text_index: None,
         }
     }
 
@@ -977,8 +1384,44 @@ impl IndexManager {
         Ok(())
     }
 
+    /// Create the collection's text index
+    pub fn create_text_index(&mut self, metadata: TextIndexMetadata) -> Result<()> {
+        if let Some(existing) = &self.text_index {
+            return Err(MongoLiteError::IndexError(format!(
+                "Collection already has a text index: {}",
+                existing.metadata.name
+            )));
+        }
+        if self.btree_indexes.contains_key(&metadata.name) {
+            return Err(MongoLiteError::IndexError(format!(
+                "Index already exists: {}",
+                metadata.name
+            )));
+        }
+        self.text_index = Some(TextIndex::new(metadata));
+        Ok(())
+    }
+
+    /// Get the text index
+    pub fn text_index(&self) -> Option<&TextIndex> {
+        self.text_index.as_ref()
+    }
+
+    /// Get the text index (mutable)
+    pub fn text_index_mut(&mut self) -> Option<&mut TextIndex> {
+        self.text_index.as_mut()
+    }
+
     /// Drop index by name
     pub fn drop_index(&mut self, name: &str) -> Result<()> {
+        if self
+            .text_index
+            .as_ref()
+            .is_some_and(|index| index.metadata.name == name)
+        {
+            self.text_index = None;
+            return Ok(());
+        }
         if self.btree_indexes.remove(name).is_none() && self.legacy_indexes.remove(name).is_none() {
             return Err(MongoLiteError::IndexError(format!(
                 "Index not found: {}",
@@ -1022,11 +1465,36 @@ impl IndexManager {
             .btree_indexes
             .keys()
             .chain(self.legacy_indexes.keys())
+            .chain(self.text_index.iter().map(|index| &index.metadata.name))
             .cloned()
             .collect();
         names.sort();
         names
     }
+
+    /// Describe every index by its fields, sorted by name
+    pub fn index_infos(&self) -> Vec<IndexInfo> {
+        let mut infos: Vec<IndexInfo> = self
+            .btree_indexes
+            .values()
+            .map(|tree| IndexInfo::from(&tree.metadata))
+            .chain(self.legacy_indexes.values().map(|index| IndexInfo {
+                name: index.definition.name.clone(),
+                fields: vec![index.definition.field.clone()],
+                unique: index.definition.unique,
+                sparse: false,
+            }))
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Describe one index by name
+    pub fn index_info(&self, name: &str) -> Option<IndexInfo> {
+        self.index_infos()
+            .into_iter()
+            .find(|info| info.name == name)
+    }
 }
 
 impl Default for IndexManager {
@@ -1050,6 +1518,42 @@ mod tests {
         assert!(IndexKey::String("a".to_string()) < IndexKey::String("b".to_string()));
     }
 
+    #[test]
+    fn test_index_key_from_decimal() {
+        use serde_json::json;
+
+        let key = |text: &str| IndexKey::from(&json!({ "$numberDecimal": text }));
+        assert_eq!(key("1.2e1"), IndexKey::Int(12));
+        assert!(matches!(key("0.5"), IndexKey::Decimal(_)));
+        assert!(key("18446744073709551616") < key("18446744073709551617"));
+        assert!(key("0.1") < IndexKey::Float(OrderedFloat(1e30)));
+        assert!(IndexKey::Float(OrderedFloat(0.25)) < key("0.5"));
+        assert!(key("0.5") < IndexKey::Int(1));
+        assert!(key("-1e400") < IndexKey::Float(OrderedFloat(f64::MIN)));
+        assert!(key("1e400") < IndexKey::Float(OrderedFloat(f64::INFINITY)));
+        assert!(key("1e400") < IndexKey::Float(OrderedFloat(f64::NAN)));
+        assert!(key("1e40") < IndexKey::String(String::new()));
+        assert_eq!(key("not a number"), IndexKey::Null);
+    }
+
+    #[test]
+    fn test_index_key_int_float_ordering() {
+        use serde_json::json;
+
+        let float = |f: f64| IndexKey::Float(OrderedFloat(f));
+        assert!(IndexKey::Int(-1) < float(-0.5));
+        assert!(float(-0.5) < IndexKey::Int(0));
+        assert!(IndexKey::Int(2) < float(2.0));
+        assert!(float(2.5) < IndexKey::Int(3));
+        assert!(IndexKey::Int(i64::MAX) < float(9.3e18));
+        assert!(float(-9.3e18) < IndexKey::Int(i64::MIN));
+        assert!(IndexKey::Int(i64::MAX) < float(f64::NAN));
+        // Floats tie with the decimal they display as
+        let tenth = IndexKey::from(&json!({ "$numberDecimal": "0.1" }));
+        assert_eq!(float(0.1).cmp_numeric(&tenth), std::cmp::Ordering::Equal);
+        assert!(float(0.1) < tenth);
+    }
+
     #[test]
     fn test_btree_insert_search() {
         let mut tree = BPlusTree::new("test_idx".to_string(), "age".to_string(), false);
@@ -1191,6 +1695,80 @@ mod tests {
         std::fs::remove_file(temp_path).ok();
     }
 
+    #[test]
+    fn test_load_recounts_keys_from_stale_metadata() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(temp_dir.path().join("stale.idx"))
+            .unwrap();
+
+        let mut tree = BPlusTree::new("sku_idx".to_string(), "sku".to_string(), true);
+        let stale_metadata = tree.metadata.clone(); // num_keys: 0, as persisted at creation
+        for i in 0..3 {
+            tree.insert(IndexKey::Int(i), DocumentId::Int(i)).unwrap();
+        }
+        tree.save_to_file(&mut file).unwrap();
+
+        let mut loaded = BPlusTree::load_from_file(&mut file, stale_metadata).unwrap();
+        assert_eq!(loaded.size(), 3);
+        for i in 0..3 {
+            loaded
+                .delete(&IndexKey::Int(i), &DocumentId::Int(i))
+                .unwrap();
+        }
+        assert_eq!(loaded.size(), 0);
+    }
+
+    #[test]
+    fn test_load_rejects_unversioned_or_unknown_version() {
+        use std::fs::OpenOptions;
+
+        let temp_path = "test_tree_version.tmp";
+        let mut tree = BPlusTree::new("test_idx".to_string(), "age".to_string(), false);
+        tree.insert(IndexKey::Int(1), DocumentId::Int(1)).unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(temp_path)
+            .unwrap();
+        tree.save_to_file(&mut file).unwrap();
+
+        // Header records the current version
+        let bytes = std::fs::read(temp_path).unwrap();
+        assert_eq!(&bytes[0..8], &INDEX_FILE_MAGIC);
+        assert_eq!(
+            u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            INDEX_FILE_VERSION
+        );
+
+        // Legacy layout: node page at offset 0, no header
+        std::fs::write(temp_path, &bytes[INDEX_FILE_HEADER_SIZE..]).unwrap();
+        let mut file = File::open(temp_path).unwrap();
+        assert!(matches!(
+            BPlusTree::load_from_file(&mut file, tree.metadata.clone()),
+            Err(MongoLiteError::IndexError(_))
+        ));
+
+        // Unknown future version
+        let mut future = bytes.clone();
+        future[8..12].copy_from_slice(&(INDEX_FILE_VERSION + 1).to_le_bytes());
+        std::fs::write(temp_path, &future).unwrap();
+        let mut file = File::open(temp_path).unwrap();
+        assert!(matches!(
+            BPlusTree::load_from_file(&mut file, tree.metadata.clone()),
+            Err(MongoLiteError::IndexError(_))
+        ));
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
     #[test]
     fn test_compound_index_key_ordering() {
         // Test that compound keys are ordered lexicographically
@@ -1215,6 +1793,44 @@ mod tests {
         assert!(key2 < key1);
     }
 
+    #[test]
+    fn test_compound_range_scan() {
+        let mut tree = BPlusTree::new_compound(
+            "events_tenant_ts".to_string(),
+            vec!["tenant".to_string(), "ts".to_string()],
+            false,
+        );
+        for (id, tenant, ts) in [(1, "t1", 5), (2, "t1", 10), (3, "t1", 15), (4, "t1", 20)]
+            .into_iter()
+            .chain([(5, "t0", 12), (6, "t2", 12)])
+        {
+            let doc = serde_json::json!({"tenant": tenant, "ts": ts});
+            for key in tree.keys_for_value(&doc) {
+                tree.insert(key, DocumentId::Int(id)).unwrap();
+            }
+        }
+        let prefix = [IndexKey::String("t1".to_string())];
+        let scan = |start: Option<i64>, end: Option<i64>, inclusive_start, inclusive_end| {
+            let start = start.map(IndexKey::Int);
+            let end = end.map(IndexKey::Int);
+            tree.compound_range_scan(
+                &prefix,
+                start.as_ref(),
+                end.as_ref(),
+                inclusive_start,
+                inclusive_end,
+            )
+        };
+
+        let ids = |ids: &[i64]| ids.iter().map(|&i| DocumentId::Int(i)).collect::<Vec<_>>();
+        assert_eq!(scan(Some(10), Some(20), true, false), ids(&[2, 3]));
+        assert_eq!(scan(Some(10), Some(20), false, true), ids(&[3, 4]));
+        assert_eq!(scan(None, Some(10), true, true), ids(&[1, 2]));
+        assert_eq!(scan(Some(15), None, true, true), ids(&[3, 4]));
+        assert_eq!(scan(None, None, true, true), ids(&[1, 2, 3, 4]));
+        assert!(scan(Some(21), None, true, true).is_empty());
+    }
+
     #[test]
     fn test_compound_index_create() {
         let tree = BPlusTree::new_compound(
@@ -1381,4 +1997,87 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_bloom_filter_no_false_negatives() {
+        let mut tree = BPlusTree::new("idx".to_string(), "email".to_string(), true);
+        let entries: Vec<(IndexKey, DocumentId)> = (0..500)
+            .map(|i| {
+                (
+                    IndexKey::String(format!("user{:04}@x", i)),
+                    DocumentId::Int(i),
+                )
+            })
+            .collect();
+        tree.build_from_sorted(entries, true).unwrap();
+        tree.enable_bloom_filter();
+        assert!(tree.has_bloom_filter());
+
+        // Grow well past the initial sizing to force a rebuild on insert
+        for i in 500..3000 {
+            tree.insert(
+                IndexKey::String(format!("user{:04}@x", i)),
+                DocumentId::Int(i),
+            )
+            .unwrap();
+        }
+
+        for i in 0..3000 {
+            let key = IndexKey::String(format!("user{:04}@x", i));
+            assert!(tree.may_contain(&key));
+            assert_eq!(tree.search(&key), Some(DocumentId::Int(i)));
+            assert_eq!(tree.lookup(&key), vec![DocumentId::Int(i)]);
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter_rejects_absent_keys() {
+        let mut tree = BPlusTree::new("idx".to_string(), "n".to_string(), false);
+        tree.enable_bloom_filter();
+        for i in 0..1000 {
+            tree.insert(IndexKey::Int(i), DocumentId::Int(i)).unwrap();
+        }
+
+        let absent: Vec<IndexKey> = (5000..6000).map(IndexKey::Int).collect();
+        assert!(absent.iter().all(|key| !tree.contains_key(key)));
+        assert!(absent.iter().all(|key| tree.lookup(key).is_empty()));
+
+        // Most absent keys never reach the tree
+        let short_circuited = absent.iter().filter(|key| !tree.may_contain(key)).count();
+        assert!(short_circuited > 950, "only {} rejected", short_circuited);
+
+        // A deleted key may still pass the filter but is not found
+        tree.delete(&IndexKey::Int(7), &DocumentId::Int(7)).unwrap();
+        assert!(!tree.contains_key(&IndexKey::Int(7)));
+    }
+
+    #[test]
+    fn test_bloom_filter_disabled_by_default_and_rebuilt_on_load() {
+        use std::fs::OpenOptions;
+
+        let mut tree = BPlusTree::new("idx".to_string(), "n".to_string(), false);
+        assert!(!tree.has_bloom_filter());
+        assert!(tree.may_contain(&IndexKey::Int(1)));
+
+        for i in 0..10 {
+            tree.insert(IndexKey::Int(i), DocumentId::Int(i)).unwrap();
+        }
+        tree.enable_bloom_filter();
+
+        let temp_path = "test_bloom_persist.tmp";
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(temp_path)
+            .unwrap();
+        tree.save_to_file(&mut file).unwrap();
+
+        let loaded = BPlusTree::load_from_file(&mut file, tree.metadata.clone()).unwrap();
+        assert!(loaded.has_bloom_filter());
+        assert!((0..10).all(|i| loaded.contains_key(&IndexKey::Int(i))));
+
+        std::fs::remove_file(temp_path).ok();
+    }
 }
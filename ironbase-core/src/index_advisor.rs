@@ -0,0 +1,225 @@
+// ironbase-core/src/index_advisor.rs
+// Index recommendations from a representative query workload
+
+use crate::document::Document;
+use crate::query::Query;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Stored documents read to measure predicate selectivity
+pub(crate) const SAMPLE_SIZE: usize = 1000;
+
+/// Weight of a query that only sorts on a field (no filter on it)
+const SORT_ONLY_BENEFIT: f64 = 0.1;
+
+/// Selectivity assumed when there are no sample documents to measure
+const UNKNOWN_SELECTIVITY: f64 = 0.5;
+
+/// One recommended single-field index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexSuggestion {
+    /// Field to index
+    pub field: String,
+    /// Number of workload queries that filter or sort on the field
+    pub query_count: usize,
+    /// Average fraction of sampled documents the field's predicates keep
+    /// (lower is more selective; 1.0 for sort-only use)
+    pub estimated_selectivity: f64,
+    /// Ranking score: sum over queries of the fraction of documents the
+    /// index would let the query skip
+    pub benefit: f64,
+    /// The call that creates the index, e.g. `create_index("email", false)`
+    pub create_index: String,
+}
+
+/// How one query uses a field
+struct FieldUse {
+    /// Predicate on the field alone; None when the query only sorts on it
+    predicate: Option<Value>,
+}
+
+/// Rank unindexed fields by how much an index on them would help `queries`
+///
+/// Each query is a filter document, or `{"filter": {...}, "sort": {...}}`
+/// to include sort fields. Fields reached through `$and` count; `$or`
+/// branches and purely negative predicates (`$ne`, `$nin`, `$not`,
+/// `$exists`) don't, since a single index can't serve them. Selectivity is
+/// measured by evaluating each field's predicate against `sample`.
+pub(crate) fn suggest_indexes(
+    queries: &[Value],
+    sample: &[Value],
+    is_indexed: impl Fn(&str) -> bool,
+) -> Vec<IndexSuggestion> {
+    let sample: Vec<Document> = sample
+        .iter()
+        .filter_map(|doc| Document::from_value(doc).ok())
+        .collect();
+
+    // field -> (query count, summed selectivity, summed benefit)
+    let mut tally: HashMap<String, (usize, f64, f64)> = HashMap::new();
+    for query in queries {
+        for (field, usage) in field_uses(query) {
+            if is_indexed(&field) {
+                continue;
+            }
+            let (selectivity, benefit) = match &usage.predicate {
+                Some(predicate) => {
+                    let selectivity = measure_selectivity(predicate, &sample);
+                    (selectivity, 1.0 - selectivity)
+                }
+                None => (1.0, SORT_ONLY_BENEFIT),
+            };
+            let entry = tally.entry(field).or_insert((0, 0.0, 0.0));
+            entry.0 += 1;
+            entry.1 += selectivity;
+            entry.2 += benefit;
+        }
+    }
+
+    let mut suggestions: Vec<IndexSuggestion> = tally
+        .into_iter()
+        .filter(|(_, (_, _, benefit))| *benefit > 0.0)
+        .map(|(field, (count, selectivity, benefit))| IndexSuggestion {
+            create_index: format!("create_index({:?}, false)", field),
+            field,
+            query_count: count,
+            estimated_selectivity: selectivity / count as f64,
+            benefit,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.benefit
+            .total_cmp(&a.benefit)
+            .then_with(|| a.field.cmp(&b.field))
+    });
+    suggestions
+}
+
+/// Fields a query filters or sorts on, each listed once
+fn field_uses(query: &Value) -> Vec<(String, FieldUse)> {
+    let (filter, sort) = match query.as_object() {
+        Some(map)
+            if !map.is_empty()
+                && map.keys().all(|k| k == "filter" || k == "sort")
+                && map.get("filter").is_none_or(Value::is_object) =>
+        {
+            (map.get("filter"), map.get("sort"))
+        }
+        _ => (Some(query), None),
+    };
+
+    let mut uses: Vec<(String, FieldUse)> = Vec::new();
+    if let Some(filter) = filter {
+        collect_predicates(filter, &mut uses);
+    }
+    if let Some(Value::Object(sort)) = sort {
+        for field in sort.keys() {
+            if !uses.iter().any(|(f, _)| f == field) {
+                uses.push((field.clone(), FieldUse { predicate: None }));
+            }
+        }
+    }
+    uses
+}
+
+fn collect_predicates(filter: &Value, uses: &mut Vec<(String, FieldUse)>) {
+    let Some(map) = filter.as_object() else {
+        return;
+    };
+    for (key, condition) in map {
+        if key == "$and" {
+            for clause in condition.as_array().into_iter().flatten() {
+                collect_predicates(clause, uses);
+            }
+            continue;
+        }
+        if key.starts_with('$') || is_negative(condition) {
+            continue;
+        }
+
+        let mut predicate = Map::new();
+        predicate.insert(key.clone(), condition.clone());
+        match uses.iter_mut().find(|(f, _)| f == key) {
+            // Same field twice (via $and): both conditions apply
+            Some((_, usage)) => {
+                let previous = usage.predicate.take().unwrap_or_else(|| json!({}));
+                usage.predicate = Some(json!({"$and": [previous, Value::Object(predicate)]}));
+            }
+            None => uses.push((
+                key.clone(),
+                FieldUse {
+                    predicate: Some(Value::Object(predicate)),
+                },
+            )),
+        }
+    }
+}
+
+/// Condition made only of operators an index can't narrow down
+fn is_negative(condition: &Value) -> bool {
+    match condition.as_object() {
+        Some(ops) if !ops.is_empty() && ops.keys().all(|k| k.starts_with('$')) => ops
+            .keys()
+            .all(|op| matches!(op.as_str(), "$ne" | "$nin" | "$not" | "$exists")),
+        _ => false,
+    }
+}
+
+fn measure_selectivity(predicate: &Value, sample: &[Document]) -> f64 {
+    if sample.is_empty() {
+        return UNKNOWN_SELECTIVITY;
+    }
+    let Ok(query) = Query::from_json(predicate) else {
+        return 1.0;
+    };
+    let matched = sample.iter().filter(|doc| query.matches(doc)).count();
+    matched as f64 / sample.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_uses() {
+        let uses = field_uses(&json!({
+            "filter": {"a": 1, "$and": [{"b": {"$gt": 2}}, {"a": {"$lt": 5}}], "c": {"$ne": 0}},
+            "sort": {"d": 1, "a": -1},
+        }));
+        let fields: Vec<&str> = uses.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(fields, ["b", "a", "d"]);
+        // Object keys iterate sorted, so the $and clause comes first
+        assert_eq!(
+            uses[1].1.predicate,
+            Some(json!({"$and": [{"a": {"$lt": 5}}, {"a": 1}]}))
+        );
+        assert!(uses[2].1.predicate.is_none());
+
+        // A collection field literally named "filter" is still a filter
+        let uses = field_uses(&json!({"filter": "x", "sort": 1}));
+        assert_eq!(uses.len(), 2);
+    }
+
+    #[test]
+    fn test_selective_fields_rank_first() {
+        let sample: Vec<Value> = (0..100)
+            .map(|i| json!({"_id": i, "email": format!("u{}@x.io", i), "active": i % 2 == 0}))
+            .collect();
+        let queries = [
+            json!({"active": true}),
+            json!({"email": "u1@x.io"}),
+            json!({"filter": {"active": true}, "sort": {"email": 1}}),
+        ];
+        let suggestions = suggest_indexes(&queries, &sample, |f| f == "_id");
+
+        assert_eq!(suggestions[0].field, "email");
+        assert_eq!(suggestions[0].query_count, 2);
+        assert!((suggestions[0].benefit - (0.99 + SORT_ONLY_BENEFIT)).abs() < 1e-9);
+        assert_eq!(suggestions[1].field, "active");
+        assert!((suggestions[1].estimated_selectivity - 0.5).abs() < 1e-9);
+
+        let none = suggest_indexes(&queries, &sample, |_| true);
+        assert!(none.is_empty());
+    }
+}
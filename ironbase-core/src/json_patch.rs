@@ -0,0 +1,297 @@
+// ironbase-core/src/json_patch.rs
+// JSON Patch (RFC 6902) documents applied to stored documents
+
+use crate::error::{MongoLiteError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One JSON Patch operation
+///
+/// Paths are JSON Pointers (RFC 6901): `/address/city`, `/tags/0`, with
+/// `~1` for `/` and `~0` for `~` inside a key. `-` as the last array token
+/// in `add` appends. Deserializes from the standard wire form, e.g.
+/// `{"op": "replace", "path": "/name", "value": "Bob"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Insert into an array or set an object member (replacing it if present)
+    Add { path: String, value: Value },
+    /// Delete an existing member or array element
+    Remove { path: String },
+    /// Overwrite an existing value
+    Replace { path: String, value: Value },
+    /// Remove the value at `from` and add it at `path`
+    Move { from: String, path: String },
+    /// Add a copy of the value at `from` at `path`
+    Copy { from: String, path: String },
+    /// Fail the whole patch unless the value at `path` equals `value`
+    Test { path: String, value: Value },
+}
+
+/// Apply a patch to `doc`, all or nothing
+///
+/// Operations run in order against a working copy; the first failing
+/// operation (including a `test` that doesn't match) returns an error and
+/// `doc` is left exactly as it was.
+pub fn apply_patch(doc: &mut Value, patch: &[PatchOp]) -> Result<()> {
+    let mut working = doc.clone();
+    for op in patch {
+        apply_op(&mut working, op)?;
+    }
+    *doc = working;
+    Ok(())
+}
+
+fn apply_op(doc: &mut Value, op: &PatchOp) -> Result<()> {
+    match op {
+        PatchOp::Add { path, value } => add(doc, path, value.clone()),
+        PatchOp::Remove { path } => remove(doc, path).map(|_| ()),
+        PatchOp::Replace { path, value } => {
+            *resolve_mut(doc, path)? = value.clone();
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if path.starts_with(from.as_str())
+                && path.len() > from.len()
+                && path.as_bytes()[from.len()] == b'/'
+            {
+                return Err(patch_error(format!(
+                    "cannot move '{}' into its own child '{}'",
+                    from, path
+                )));
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOp::Copy { from, path } => {
+            let value = resolve_mut(doc, from)?.clone();
+            add(doc, path, value)
+        }
+        PatchOp::Test { path, value } => {
+            if &*resolve_mut(doc, path)? == value {
+                Ok(())
+            } else {
+                Err(patch_error(format!("test failed at '{}'", path)))
+            }
+        }
+    }
+}
+
+fn patch_error(message: String) -> MongoLiteError {
+    MongoLiteError::InvalidQuery(format!("JSON Patch: {}", message))
+}
+
+/// Split a JSON Pointer into unescaped reference tokens
+fn parse_pointer(path: &str) -> Result<Vec<String>> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err(patch_error(format!(
+            "pointer '{}' must be empty or start with '/'",
+            path
+        )));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Array index token: decimal digits without leading zeros
+fn array_index(token: &str, path: &str) -> Result<usize> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    if !valid {
+        return Err(patch_error(format!(
+            "invalid array index '{}' in '{}'",
+            token, path
+        )));
+    }
+    token
+        .parse()
+        .map_err(|_| patch_error(format!("invalid array index '{}' in '{}'", token, path)))
+}
+
+fn resolve_mut<'a>(doc: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    resolve_tokens_mut(doc, &parse_pointer(path)?, path)
+}
+
+fn resolve_tokens_mut<'a>(
+    doc: &'a mut Value,
+    tokens: &[String],
+    path: &str,
+) -> Result<&'a mut Value> {
+    let mut current = doc;
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(arr) => {
+                let index = array_index(token, path)?;
+                arr.get_mut(index)
+            }
+            _ => None,
+        }
+        .ok_or_else(|| patch_error(format!("path '{}' does not exist", path)))?;
+    }
+    Ok(current)
+}
+
+/// Parent container of the pointer target plus the last token
+fn parent_mut<'a>(doc: &'a mut Value, path: &str) -> Result<Option<(&'a mut Value, String)>> {
+    let mut tokens = parse_pointer(path)?;
+    let Some(last) = tokens.pop() else {
+        return Ok(None);
+    };
+    Ok(Some((resolve_tokens_mut(doc, &tokens, path)?, last)))
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<()> {
+    let Some((parent, last)) = parent_mut(doc, path)? else {
+        *doc = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+            Ok(())
+        }
+        Value::Array(arr) => {
+            let index = if last == "-" {
+                arr.len()
+            } else {
+                array_index(&last, path)?
+            };
+            if index > arr.len() {
+                return Err(patch_error(format!("index out of bounds in '{}'", path)));
+            }
+            arr.insert(index, value);
+            Ok(())
+        }
+        _ => Err(patch_error(format!(
+            "parent of '{}' is not a container",
+            path
+        ))),
+    }
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value> {
+    let Some((parent, last)) = parent_mut(doc, path)? else {
+        return Err(patch_error("cannot remove the whole document".to_string()));
+    };
+    let removed = match parent {
+        Value::Object(map) => map.remove(&last),
+        Value::Array(arr) => {
+            let index = array_index(&last, path)?;
+            (index < arr.len()).then(|| arr.remove(index))
+        }
+        _ => None,
+    };
+    removed.ok_or_else(|| patch_error(format!("path '{}' does not exist", path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patched(doc: Value, patch: Value) -> Result<Value> {
+        let ops: Vec<PatchOp> = serde_json::from_value(patch).unwrap();
+        let mut doc = doc;
+        apply_patch(&mut doc, &ops)?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let doc = json!({"name": "a", "tags": ["x", "z"]});
+        let out = patched(
+            doc,
+            json!([
+                {"op": "add", "path": "/age", "value": 3},
+                {"op": "add", "path": "/tags/1", "value": "y"},
+                {"op": "add", "path": "/tags/-", "value": "end"},
+                {"op": "remove", "path": "/name"},
+            ]),
+        )
+        .unwrap();
+        assert_eq!(out, json!({"age": 3, "tags": ["x", "y", "z", "end"]}));
+
+        assert!(patched(json!({}), json!([{"op": "remove", "path": "/missing"}])).is_err());
+        assert!(patched(
+            json!({"a": [1]}),
+            json!([{"op": "add", "path": "/a/5", "value": 0}])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_replace_move_copy() {
+        let doc = json!({"a": {"b": 1}, "list": [1, 2, 3], "k/e~y": 0});
+        let out = patched(
+            doc,
+            json!([
+                {"op": "replace", "path": "/list/0", "value": 10},
+                {"op": "replace", "path": "/k~1e~0y", "value": 5},
+                {"op": "move", "from": "/a/b", "path": "/moved"},
+                {"op": "copy", "from": "/list", "path": "/a/copy"},
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            json!({"a": {"copy": [10, 2, 3]}, "list": [10, 2, 3], "k/e~y": 5, "moved": 1})
+        );
+
+        // replace needs an existing target; a value can't move into itself
+        assert!(patched(
+            json!({}),
+            json!([{"op": "replace", "path": "/x", "value": 1}])
+        )
+        .is_err());
+        assert!(patched(
+            json!({"a": {"b": 1}}),
+            json!([{"op": "move", "from": "/a", "path": "/a/c"}])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_failing_test_leaves_document_unchanged() {
+        let mut doc = json!({"version": 1, "name": "a"});
+        let ops: Vec<PatchOp> = serde_json::from_value(json!([
+            {"op": "replace", "path": "/name", "value": "b"},
+            {"op": "test", "path": "/version", "value": 2},
+        ]))
+        .unwrap();
+        assert!(apply_patch(&mut doc, &ops).is_err());
+        assert_eq!(doc, json!({"version": 1, "name": "a"}));
+
+        let ok = patched(
+            doc,
+            json!([{"op": "test", "path": "/version", "value": 1}, {"op": "remove", "path": "/name"}]),
+        )
+        .unwrap();
+        assert_eq!(ok, json!({"version": 1}));
+    }
+
+    #[test]
+    fn test_invalid_pointers() {
+        assert!(patched(
+            json!({}),
+            json!([{"op": "add", "path": "name", "value": 1}])
+        )
+        .is_err());
+        assert!(patched(
+            json!({"a": [1, 2]}),
+            json!([{"op": "remove", "path": "/a/01"}])
+        )
+        .is_err());
+        assert!(patched(
+            json!({"a": 1}),
+            json!([{"op": "add", "path": "/a/b", "value": 1}])
+        )
+        .is_err());
+    }
+}
@@ -28,22 +28,34 @@
 #![cfg_attr(test, allow(unused_variables))]
 
 pub mod aggregation;
+pub mod aggregation_cache;
+pub mod backup;
+pub mod bloom;
 pub mod btree;
+pub mod bulk_write;
 pub mod catalog_serde;
 pub mod collection_core;
 pub mod database;
+pub mod decimal;
 pub mod document;
 pub mod durability;
 pub mod error;
+pub mod field_interning;
 pub mod find_options;
 pub mod index;
+pub mod index_advisor;
+pub mod json_patch;
 pub mod logging;
+pub mod metrics;
 pub mod query;
 pub mod query_cache;
 pub mod query_planner;
+pub mod read_cache;
 pub mod recovery;
 pub mod storage;
+pub mod text_index;
 pub mod transaction;
+pub mod union_view;
 pub mod value_utils;
 pub mod wal;
 
@@ -57,21 +69,35 @@ mod transaction_integration_tests;
 mod transaction_property_tests;
 
 // Public exports
-pub use collection_core::{CollectionCore, FindCursor, InsertManyResult};
-pub use database::DatabaseCore;
+pub use aggregation_cache::{AggregationCache, AggregationCacheStats};
+pub use backup::{BackupEntry, BackupManifest};
+pub use bulk_write::{BulkOp, BulkWriteError, BulkWriteResult};
+pub use collection_core::{AggregateCursor, CollectionCore, FindCursor, InsertManyResult};
+pub use database::{DatabaseCore, DatabaseOptions, OnConflict};
+pub use decimal::Decimal;
 pub use document::{Document, DocumentId};
 pub use durability::DurabilityMode;
 pub use error::{MongoLiteError, Result};
+pub use field_interning::{FieldInterner, FieldInterningStats, InternedValue};
 pub use find_options::FindOptions;
-pub use logging::{get_log_level, set_log_level, LogLevel};
+pub use index_advisor::IndexSuggestion;
+pub use json_patch::PatchOp;
+pub use logging::{get_log_level, set_log_hook, set_log_level, LogHook, LogLevel};
+pub use metrics::MetricsSnapshot;
 pub use query::Query;
 pub use query_cache::{CacheStats, QueryCache, QueryHash};
+pub use read_cache::{ReadCache, ReadCacheStats};
 pub use recovery::{
     IndexOperation, IndexReplay, IndexReplayStats, OperationReplay, RecoveredIndexChange,
     RecoveryCoordinator, RecoveryStats, ReplayStats,
 };
-pub use storage::{CompactionStats, StorageEngine};
+pub use storage::{
+    CappedLimits, CatalogRepairStats, CompactionStats, CompactionVerification, StorageEngine,
+    SyncCounts,
+};
+pub use text_index::{TextIndex, TextIndexMetadata, TextSearchMode, Tokenizer};
 pub use transaction::{Operation, Transaction, TransactionId, TransactionState};
+pub use union_view::UnionView;
 pub use wal::{
     CommittedTransaction, TransactionGrouper, WALEntry, WALEntryIterator, WALEntryType,
     WriteAheadLog,
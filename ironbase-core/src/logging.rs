@@ -1,6 +1,7 @@
 // logging.rs - Simple, embedded-friendly logging system
 // Designed for IronBase (no external dependencies like env_logger)
 
+use parking_lot::RwLock;
 use std::sync::atomic::{AtomicU8, Ordering};
 
 /// Log levels (ordered by severity)
@@ -82,6 +83,18 @@ pub fn should_log(level: LogLevel) -> bool {
     level <= get_log_level()
 }
 
+/// Receiver for log messages: (level, module, message)
+pub type LogHook = fn(LogLevel, &str, &str);
+
+static LOG_HOOK: RwLock<Option<LogHook>> = RwLock::new(None);
+
+/// Route log messages to `hook` instead of stderr (`None` restores stderr)
+///
+/// Only messages that pass the global log level reach the hook.
+pub fn set_log_hook(hook: Option<LogHook>) {
+    *LOG_HOOK.write() = hook;
+}
+
 /// Maximum length for log messages (will be truncated with "...")
 /// 1 KB limit to prevent massive log spam
 const MAX_LOG_LENGTH: usize = 1024;
@@ -97,6 +110,11 @@ pub fn log_message(level: LogLevel, module: &str, message: &str) {
             message.to_string()
         };
 
+        if let Some(hook) = *LOG_HOOK.read() {
+            hook(level, module, &truncated);
+            return;
+        }
+
         eprintln!(
             "{} [{}] {}: {}",
             level.icon(),
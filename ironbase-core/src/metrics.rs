@@ -0,0 +1,132 @@
+// ironbase-core/src/metrics.rs
+// Operation counters for monitoring a running database
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Operation counters shared by a `DatabaseCore` and its collection handles
+///
+/// Counters only ever increase and use relaxed atomics: they are for
+/// monitoring, so a snapshot taken while operations run may be a few
+/// increments behind.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub(crate) inserts: AtomicU64,
+    pub(crate) updates: AtomicU64,
+    pub(crate) deletes: AtomicU64,
+    pub(crate) queries: AtomicU64,
+    pub(crate) index_hits: AtomicU64,
+    pub(crate) collection_scans: AtomicU64,
+    pub(crate) cache_hits: AtomicU64,
+    pub(crate) cache_misses: AtomicU64,
+    pub(crate) wal_flushes: AtomicU64,
+    pub(crate) cache_invalidations: AtomicU64,
+    pub(crate) write_locks: AtomicU64,
+    pub(crate) documents_read: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn incr(counter: &AtomicU64) {
+        Self::add(counter, 1);
+    }
+
+    /// Current counter values
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            inserts: load(&self.inserts),
+            updates: load(&self.updates),
+            deletes: load(&self.deletes),
+            queries: load(&self.queries),
+            index_hits: load(&self.index_hits),
+            collection_scans: load(&self.collection_scans),
+            cache_hits: load(&self.cache_hits),
+            cache_misses: load(&self.cache_misses),
+            wal_flushes: load(&self.wal_flushes),
+            cache_invalidations: load(&self.cache_invalidations),
+            write_locks: load(&self.write_locks),
+            documents_read: load(&self.documents_read),
+        }
+    }
+}
+
+/// Point-in-time copy of `Metrics`, returned by `DatabaseCore::metrics`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    /// Documents inserted
+    pub inserts: u64,
+    /// Documents modified by updates
+    pub updates: u64,
+    /// Documents deleted
+    pub deletes: u64,
+    /// Read queries run (find, find_one, count_documents, ...)
+    pub queries: u64,
+    /// Query lookups answered through an index
+    pub index_hits: u64,
+    /// Query lookups that scanned the whole collection
+    pub collection_scans: u64,
+    /// Query result cache hits
+    pub cache_hits: u64,
+    /// Query result cache misses
+    pub cache_misses: u64,
+    /// WAL commits flushed to disk
+    pub wal_flushes: u64,
+    /// Query cache invalidations caused by writes
+    pub cache_invalidations: u64,
+    /// Exclusive storage locks taken to apply writes
+    pub write_locks: u64,
+    /// Document records read from storage (read cache hits excluded)
+    pub documents_read: u64,
+}
+
+impl MetricsSnapshot {
+    /// (name, description, value) for every counter, in a stable order
+    pub fn counters(&self) -> [(&'static str, &'static str, u64); 12] {
+        [
+            ("inserts", "Documents inserted", self.inserts),
+            ("updates", "Documents modified by updates", self.updates),
+            ("deletes", "Documents deleted", self.deletes),
+            ("queries", "Read queries run", self.queries),
+            (
+                "index_hits",
+                "Query lookups answered through an index",
+                self.index_hits,
+            ),
+            (
+                "collection_scans",
+                "Query lookups that scanned the whole collection",
+                self.collection_scans,
+            ),
+            ("cache_hits", "Query result cache hits", self.cache_hits),
+            (
+                "cache_misses",
+                "Query result cache misses",
+                self.cache_misses,
+            ),
+            (
+                "wal_flushes",
+                "WAL commits flushed to disk",
+                self.wal_flushes,
+            ),
+            (
+                "cache_invalidations",
+                "Query cache invalidations caused by writes",
+                self.cache_invalidations,
+            ),
+            (
+                "write_locks",
+                "Exclusive storage locks taken to apply writes",
+                self.write_locks,
+            ),
+            (
+                "documents_read",
+                "Document records read from storage",
+                self.documents_read,
+            ),
+        ]
+    }
+}
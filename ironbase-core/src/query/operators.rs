@@ -22,6 +22,7 @@
 //! - **Reduced Complexity**: Each operator has CC ~2-4 instead of one giant function
 //! - **Type Safety**: Compile-time guarantees for operator implementations
 
+use crate::decimal::{compare_decimal, is_decimal};
 use crate::document::Document;
 use crate::error::{MongoLiteError, Result};
 use crate::value_utils::compare_values;
@@ -175,6 +176,12 @@ pub trait OperatorMatcher: Send + Sync {
 /// // Shorthand: { field: value }
 /// ```
 ///
+/// A scalar matches the field itself or any element of an array field. An
+/// array value only matches a field holding exactly that array, so
+/// `{"coords": {"$eq": [1, 2]}}` never matches `[[1, 2], [3, 4]]`; the
+/// shorthand `{"coords": [1, 2]}` matches both the whole array and an
+/// element (see `equality_matches`).
+///
 /// # Complexity: CC = 2
 pub struct EqOperator;
 
@@ -189,25 +196,40 @@ impl OperatorMatcher for EqOperator {
         filter_value: &Value,
         _document: Option<&Document>,
     ) -> Result<bool> {
-        match doc_value {
-            None => Ok(false),
-            Some(v) => {
-                // Direct equality check
-                if v == filter_value {
-                    return Ok(true);
-                }
-                // MongoDB array element matching: if doc_value is an array,
-                // check if any element equals filter_value
-                if let Value::Array(arr) = v {
-                    Ok(arr.iter().any(|elem| elem == filter_value))
-                } else {
-                    Ok(false)
-                }
+        if filter_value.is_array() {
+            // Explicit whole-array equality
+            return Ok(doc_value == Some(filter_value));
+        }
+        Ok(equality_matches(doc_value, filter_value))
+    }
+}
+
+/// Bare equality (`{field: value}`): the whole value or any array element
+fn equality_matches(doc_value: Option<&Value>, filter_value: &Value) -> bool {
+    match doc_value {
+        None => false,
+        Some(v) => {
+            // Direct equality check
+            if values_equal(v, filter_value) {
+                return true;
+            }
+            // MongoDB array element matching: if doc_value is an array,
+            // check if any element equals filter_value
+            if let Value::Array(arr) = v {
+                arr.iter().any(|elem| values_equal(elem, filter_value))
+            } else {
+                false
             }
         }
     }
 }
 
+/// Structural equality, except that tagged decimals equal numerically
+/// equal numbers (`{"$numberDecimal": "2.50"}` equals `2.5`)
+fn values_equal(a: &Value, b: &Value) -> bool {
+    a == b || compare_decimal(a, b) == Some(std::cmp::Ordering::Equal)
+}
+
 /// $ne operator: Matches values that are not equal to a specified value
 ///
 /// # MongoDB Spec
@@ -972,6 +994,7 @@ pub struct ExprOperator;
 /// Helper: Resolve a value that might be a field reference
 ///
 /// - If value starts with "$", extract field from document
+/// - If value is `{"$literal": v}`, return `v` as-is
 /// - Otherwise return the literal value
 fn resolve_expr_value<'a>(value: &'a Value, document: &'a Document) -> Option<&'a Value> {
     if let Some(field_ref) = value.as_str() {
@@ -980,6 +1003,13 @@ fn resolve_expr_value<'a>(value: &'a Value, document: &'a Document) -> Option<&'
             return document.get(field_name);
         }
     }
+    if let Some(obj) = value.as_object() {
+        if obj.len() == 1 {
+            if let Some(literal) = obj.get("$literal") {
+                return Some(literal);
+            }
+        }
+    }
     // Return the literal value
     Some(value)
 }
@@ -1196,6 +1226,28 @@ where
     }
 }
 
+/// Evaluates an operator against every value reached through an array path
+///
+/// Positive operators match if ANY value matches. Negations ($ne, $nin,
+/// $not) must hold for ALL values: `{"items.price": {"$ne": 5}}` excludes a
+/// document where any item has price 5.
+fn matches_multi_value(
+    op_name: &str,
+    operator: &dyn OperatorMatcher,
+    doc_values: &[&Value],
+    op_value: &Value,
+    document: &Document,
+) -> Result<bool> {
+    let negated = matches!(op_name, "$ne" | "$nin" | "$not");
+    for dv in doc_values {
+        let matched = operator.matches(Some(*dv), op_value, Some(document))?;
+        if matched != negated {
+            return Ok(!negated);
+        }
+    }
+    Ok(negated)
+}
+
 /// Matches a single filter value against a document value
 ///
 /// This is used by $not and other operators that need to recursively evaluate conditions
@@ -1207,7 +1259,10 @@ fn matches_filter_value(
     document: Option<&Document>,
 ) -> Result<bool> {
     // If filter is an object with operators, evaluate them
-    if let Value::Object(filter_obj) = filter_value {
+    let filter_ops = filter_value
+        .as_object()
+        .filter(|_| !is_decimal(filter_value));
+    if let Some(filter_obj) = filter_ops {
         for (op_name, op_value) in filter_obj {
             if op_name.starts_with('$') {
                 // Look up operator in registry
@@ -1231,7 +1286,7 @@ fn matches_filter_value(
         Ok(true)
     } else {
         // Direct value comparison (implicit $eq)
-        Ok(doc_value == Some(filter_value))
+        Ok(doc_value.is_some_and(|v| values_equal(v, filter_value)))
     }
 }
 
@@ -1324,7 +1379,9 @@ pub fn matches_filter(document: &Document, filter: &Value) -> Result<bool> {
             // check if ANY of them matches the condition
             let use_multi_value_matching = !doc_values.is_empty();
 
-            if let Value::Object(condition_obj) = value {
+            // A tagged decimal is a value to compare against, not operators
+            let condition = value.as_object().filter(|_| !is_decimal(value));
+            if let Some(condition_obj) = condition {
                 // Special handling for $regex + $options combination
                 // MongoDB allows: { field: { $regex: "pattern", $options: "i" } }
                 let has_regex = condition_obj.contains_key("$regex");
@@ -1392,15 +1449,15 @@ pub fn matches_filter(document: &Document, filter: &Value) -> Result<bool> {
                         if op_name.starts_with('$') {
                             if let Some(operator) = OPERATOR_REGISTRY.get(op_name.as_str()) {
                                 // MongoDB-style: if we have multiple values, ANY match is success
+                                // (ALL for negations, see matches_multi_value)
                                 if use_multi_value_matching {
-                                    let mut any_match = false;
-                                    for dv in &doc_values {
-                                        if operator.matches(Some(*dv), op_value, Some(document))? {
-                                            any_match = true;
-                                            break;
-                                        }
-                                    }
-                                    if !any_match {
+                                    if !matches_multi_value(
+                                        op_name,
+                                        operator.as_ref(),
+                                        &doc_values,
+                                        op_value,
+                                        document,
+                                    )? {
                                         return Ok(false);
                                     }
                                 } else if !operator.matches(doc_value, op_value, Some(document))? {
@@ -1421,15 +1478,15 @@ pub fn matches_filter(document: &Document, filter: &Value) -> Result<bool> {
                         if op_name.starts_with('$') {
                             if let Some(operator) = OPERATOR_REGISTRY.get(op_name.as_str()) {
                                 // MongoDB-style: if we have multiple values, ANY match is success
+                                // (ALL for negations, see matches_multi_value)
                                 if use_multi_value_matching {
-                                    let mut any_match = false;
-                                    for dv in &doc_values {
-                                        if operator.matches(Some(*dv), op_value, Some(document))? {
-                                            any_match = true;
-                                            break;
-                                        }
-                                    }
-                                    if !any_match {
+                                    if !matches_multi_value(
+                                        op_name,
+                                        operator.as_ref(),
+                                        &doc_values,
+                                        op_value,
+                                        document,
+                                    )? {
                                         return Ok(false);
                                     }
                                 } else if !operator.matches(doc_value, op_value, Some(document))? {
@@ -1445,21 +1502,17 @@ pub fn matches_filter(document: &Document, filter: &Value) -> Result<bool> {
                     }
                 }
             } else {
-                // Direct equality check like { name: "Alice" }
-                // Use EqOperator for array element matching support
+                // Direct equality check like { name: "Alice" }, with array
+                // element matching
                 // MongoDB-style: if we have multiple values, ANY match is success
                 if use_multi_value_matching {
-                    let mut any_match = false;
-                    for dv in &doc_values {
-                        if EqOperator.matches(Some(*dv), value, Some(document))? {
-                            any_match = true;
-                            break;
-                        }
-                    }
-                    if !any_match {
+                    if !doc_values
+                        .iter()
+                        .any(|dv| equality_matches(Some(*dv), value))
+                    {
                         return Ok(false);
                     }
-                } else if !EqOperator.matches(doc_value, value, Some(document))? {
+                } else if !equality_matches(doc_value, value) {
                     return Ok(false);
                 }
             }
@@ -1976,6 +2029,36 @@ mod tests {
         assert!(!op.matches(None, &json!("Alice"), None).unwrap());
     }
 
+    #[test]
+    fn test_eq_array_is_whole_array_equality() {
+        let doc = create_test_document(
+            1,
+            vec![("tags", json!(["a"])), ("coords", json!([[1, 2], [3, 4]]))],
+        );
+        let multi = create_test_document(2, vec![("tags", json!(["a", "b"]))]);
+
+        // Bare equality matches an element
+        assert!(matches_filter(&doc, &json!({"tags": "a"})).unwrap());
+        assert!(matches_filter(&multi, &json!({"tags": "a"})).unwrap());
+        assert!(matches_filter(&doc, &json!({"coords": [1, 2]})).unwrap());
+
+        // Explicit $eq with an array demands the exact array
+        assert!(matches_filter(&doc, &json!({"tags": {"$eq": ["a"]}})).unwrap());
+        assert!(!matches_filter(&multi, &json!({"tags": {"$eq": ["a"]}})).unwrap());
+        assert!(!matches_filter(&doc, &json!({"coords": {"$eq": [1, 2]}})).unwrap());
+        assert!(matches_filter(&doc, &json!({"coords": {"$eq": [[1, 2], [3, 4]]}})).unwrap());
+        // ...while a scalar $eq still matches elements
+        assert!(matches_filter(&multi, &json!({"tags": {"$eq": "b"}})).unwrap());
+
+        // Composes under $and and $not
+        let and = json!({"$and": [{"tags": {"$eq": ["a", "b"]}}, {"tags": "b"}]});
+        assert!(matches_filter(&multi, &and).unwrap());
+        assert!(!matches_filter(&doc, &and).unwrap());
+        let not = json!({"tags": {"$not": {"$eq": ["a"]}}});
+        assert!(!matches_filter(&doc, &not).unwrap());
+        assert!(matches_filter(&multi, &not).unwrap());
+    }
+
     #[test]
     fn test_ne_operator() {
         let op = NeOperator;
@@ -2046,6 +2129,25 @@ mod tests {
         assert!(matches_filter(&doc, &filter).unwrap());
     }
 
+    #[test]
+    fn test_matches_filter_decimal_values() {
+        let pi = json!({"$numberDecimal": "3.14159265358979323846264338327950288"});
+        let doc = create_test_document(1, vec![("pi", pi.clone()), ("n", json!(2.5))]);
+
+        assert!(matches_filter(&doc, &json!({"pi": pi})).unwrap());
+        assert!(!matches_filter(
+            &doc,
+            &json!({"pi": {"$numberDecimal": "3.14159265358979323846264338327950289"}})
+        )
+        .unwrap());
+        assert!(matches_filter(&doc, &json!({"n": {"$numberDecimal": "2.50"}})).unwrap());
+        assert!(matches_filter(
+            &doc,
+            &json!({"pi": {"$gt": 3.14159, "$lt": {"$numberDecimal": "3.1416"}}})
+        )
+        .unwrap());
+    }
+
     #[test]
     fn test_matches_filter_logical_and() {
         let doc = create_test_document(1, vec![("age", json!(25)), ("city", json!("NYC"))]);
@@ -2302,6 +2404,17 @@ mod tests {
         assert!(!matches_filter(&doc, &filter).unwrap());
     }
 
+    #[test]
+    fn test_expr_literal() {
+        let doc = create_test_document(1, vec![("code", json!("$abc"))]);
+
+        // "$abc" alone would be a reference to a missing field
+        let filter = json!({"$expr": {"$eq": ["$code", {"$literal": "$abc"}]}});
+        assert!(matches_filter(&doc, &filter).unwrap());
+        let filter = json!({"$expr": {"$eq": ["$code", "$abc"]}});
+        assert!(!matches_filter(&doc, &filter).unwrap());
+    }
+
     #[test]
     fn test_expr_nested_logical_operators() {
         // Test nested logical operators in $expr
@@ -2525,4 +2638,31 @@ mod tests {
         assert!(matches_filter(&doc2, &filter).unwrap()); // Bob starts with B
         assert!(matches_filter(&doc3, &filter).unwrap()); // Charlie starts with C
     }
+
+    // ========================================================================
+    // ARRAY-OF-OBJECTS DOTTED PATH TESTS
+    // ========================================================================
+
+    #[test]
+    fn test_dotted_path_matches_any_array_element() {
+        let doc = create_test_document(1, vec![("items", json!([{"price": 50}, {"price": 150}]))]);
+
+        assert!(matches_filter(&doc, &json!({"items.price": {"$gt": 100}})).unwrap());
+        assert!(matches_filter(&doc, &json!({"items.price": 50})).unwrap());
+        assert!(!matches_filter(&doc, &json!({"items.price": {"$gt": 200}})).unwrap());
+        assert!(!matches_filter(&doc, &json!({"items.price": 75})).unwrap());
+    }
+
+    #[test]
+    fn test_negated_dotted_path_requires_every_element() {
+        let doc = create_test_document(1, vec![("items", json!([{"price": 50}, {"price": 150}]))]);
+
+        // One element equals 150, so the document does not satisfy $ne
+        assert!(!matches_filter(&doc, &json!({"items.price": {"$ne": 150}})).unwrap());
+        assert!(!matches_filter(&doc, &json!({"items.price": {"$nin": [150, 300]}})).unwrap());
+        assert!(!matches_filter(&doc, &json!({"items.price": {"$not": {"$gt": 100}}})).unwrap());
+
+        assert!(matches_filter(&doc, &json!({"items.price": {"$ne": 75}})).unwrap());
+        assert!(matches_filter(&doc, &json!({"items.price": {"$not": {"$gt": 200}}})).unwrap());
+    }
 }
@@ -1,7 +1,8 @@
 // src/query_planner.rs
 // Query planner and optimizer - index selection
 
-use crate::index::IndexKey;
+use crate::decimal::is_decimal;
+use crate::index::{IndexInfo, IndexKey};
 use serde_json::Value;
 
 /// Query plan - describes how to execute a query
@@ -26,8 +27,44 @@ pub enum QueryPlan {
         inclusive_start: bool,
         inclusive_end: bool,
     },
+
+    /// Compound index scan: equality on leading fields, optionally a range
+    /// on the field after them
+    CompoundRangeScan {
+        index_name: String,
+        /// Equality keys for the leading index fields, in index order
+        prefix: Vec<IndexKey>,
+        /// Index field after the prefix (the last field if the prefix covers all)
+        field: String,
+        start: Option<IndexKey>,
+        end: Option<IndexKey>,
+        inclusive_start: bool,
+        inclusive_end: bool,
+    },
+
+    /// Every entry of a single-field index, for `{"field": {"$exists": true}}`
+    ///
+    /// Single-field indexes leave out documents missing the field (they are
+    /// sparse), so their entries are exactly the documents that have it.
+    IndexExistsScan { index_name: String, field: String },
 }
 
+impl QueryPlan {
+    /// Index the plan reads, or None for a collection scan
+    pub fn index_name(&self) -> Option<&str> {
+        match self {
+            QueryPlan::CollectionScan => None,
+            QueryPlan::IndexScan { index_name, .. }
+            | QueryPlan::IndexRangeScan { index_name, .. }
+            | QueryPlan::CompoundRangeScan { index_name, .. }
+            | QueryPlan::IndexExistsScan { index_name, .. } => Some(index_name),
+        }
+    }
+}
+
+/// (start, end, inclusive_start, inclusive_end) of a range condition
+type RangeBounds = (Option<IndexKey>, Option<IndexKey>, bool, bool);
+
 /// Query planner - analyzes queries and selects optimal execution plan
 pub struct QueryPlanner;
 
@@ -38,13 +75,18 @@ impl QueryPlanner {
         query_json: &Value,
         available_indexes: &[String],
     ) -> Option<(String, QueryPlan)> {
-        // Check for simple equality query: { "field": value }
-        if let Value::Object(ref map) = query_json {
-            // First try range query analysis (handles { "field": { "$gte": ... } })
-            if let Some((field, plan)) = Self::analyze_range_query(query_json, available_indexes) {
-                return Some((field, plan));
-            }
+        // Range and equality conditions narrow the scan more than $exists
+        Self::analyze_range_query(query_json, available_indexes)
+            .or_else(|| Self::analyze_equality_query(query_json, available_indexes))
+            .or_else(|| Self::analyze_exists_query(query_json, available_indexes))
+    }
 
+    /// Analyze query for a simple equality: { "field": value }
+    fn analyze_equality_query(
+        query_json: &Value,
+        available_indexes: &[String],
+    ) -> Option<(String, QueryPlan)> {
+        if let Value::Object(ref map) = query_json {
             // Skip logical operators like $and, $or, $nor
             if map.keys().any(|k| k.starts_with('$')) {
                 return None;
@@ -55,7 +97,7 @@ impl QueryPlanner {
                 // Skip if value contains operators (like {"age": {"$gt": 5}})
                 if let Value::Object(ref val_map) = value {
                     if val_map.keys().any(|k| k.starts_with('$')) {
-                        // Already handled by range query analysis above
+                        // Ranges and $exists are analyzed separately
                         return None;
                     }
                 }
@@ -97,6 +139,11 @@ impl QueryPlanner {
                     let has_lte = cond_map.contains_key("$lte");
 
                     if has_gt || has_gte || has_lt || has_lte {
+                        // Decimal bounds fall back to a scan (see crate::decimal)
+                        if Self::has_decimal_bound(cond_map) {
+                            return None;
+                        }
+
                         // We have a range query
                         let index_name = Self::find_index_for_field(field, available_indexes)?;
 
@@ -138,6 +185,132 @@ impl QueryPlanner {
         None
     }
 
+    /// Analyze query for `{ "field": { "$exists": true } }`
+    ///
+    /// `$exists: false` is left to a scan: the documents it matches are
+    /// exactly the ones the index leaves out.
+    fn analyze_exists_query(
+        query_json: &Value,
+        available_indexes: &[String],
+    ) -> Option<(String, QueryPlan)> {
+        let map = query_json.as_object()?;
+        map.iter()
+            .filter(|(field, conditions)| {
+                !field.starts_with('$') && conditions.get("$exists") == Some(&Value::Bool(true))
+            })
+            .find_map(|(field, _)| {
+                let index_name = Self::find_index_for_field(field, available_indexes)?;
+                Some((
+                    field.clone(),
+                    QueryPlan::IndexExistsScan {
+                        index_name,
+                        field: field.clone(),
+                    },
+                ))
+            })
+    }
+
+    /// Plan a compound index scan for `{"a": x, "b": {"$gte": lo, "$lt": hi}}`
+    /// style queries
+    ///
+    /// An index qualifies when the query has scalar equality conditions on
+    /// a prefix of its fields; a range on the field after the prefix
+    /// narrows the scan further. Only plans that constrain at least two
+    /// index fields are returned (a single-field index serves the rest),
+    /// preferring the index that constrains the most fields.
+    pub fn analyze_compound_query(
+        query_json: &Value,
+        compound_indexes: &[IndexInfo],
+    ) -> Option<QueryPlan> {
+        let mut best: Option<(usize, QueryPlan)> = None;
+        for index in compound_indexes {
+            if let Some((constrained, plan)) =
+                Self::compound_plan(query_json, &index.name, &index.fields)
+            {
+                if constrained >= 2 && best.as_ref().is_none_or(|(most, _)| constrained > *most) {
+                    best = Some((constrained, plan));
+                }
+            }
+        }
+        best.map(|(_, plan)| plan)
+    }
+
+    /// Compound scan of one index for a query, with the number of index
+    /// fields it constrains; None unless the leading field has an equality
+    pub fn compound_plan(
+        query_json: &Value,
+        index_name: &str,
+        fields: &[String],
+    ) -> Option<(usize, QueryPlan)> {
+        let map = query_json.as_object()?;
+        let prefix: Vec<IndexKey> = fields
+            .iter()
+            .map_while(|field| match map.get(field) {
+                Some(value @ (Value::Bool(_) | Value::Number(_) | Value::String(_))) => {
+                    Some(IndexKey::from(value))
+                }
+                _ => None,
+            })
+            .collect();
+        if prefix.is_empty() {
+            return None;
+        }
+
+        let next = fields.get(prefix.len());
+        let range = next
+            .and_then(|field| map.get(field))
+            .and_then(Self::range_bounds);
+        let constrained = prefix.len() + usize::from(range.is_some());
+        let (start, end, inclusive_start, inclusive_end) =
+            range.unwrap_or((None, None, true, true));
+        let field = next.or(fields.last())?.clone();
+
+        Some((
+            constrained,
+            QueryPlan::CompoundRangeScan {
+                index_name: index_name.to_string(),
+                prefix,
+                field,
+                start,
+                end,
+                inclusive_start,
+                inclusive_end,
+            },
+        ))
+    }
+
+    /// Bounds of a `$gt`/`$gte`/`$lt`/`$lte` condition; None if it has none
+    fn range_bounds(conditions: &Value) -> Option<RangeBounds> {
+        let ops = conditions.as_object()?;
+        if Self::has_decimal_bound(ops) {
+            return None;
+        }
+        let bound = |inclusive: &str, exclusive: &str| {
+            if let Some(value) = ops.get(inclusive) {
+                Some((IndexKey::from(value), true))
+            } else {
+                ops.get(exclusive)
+                    .map(|value| (IndexKey::from(value), false))
+            }
+        };
+        let start = bound("$gte", "$gt");
+        let end = bound("$lte", "$lt");
+        if start.is_none() && end.is_none() {
+            return None;
+        }
+
+        let (start, inclusive_start) = start.map_or((None, true), |(k, inc)| (Some(k), inc));
+        let (end, inclusive_end) = end.map_or((None, true), |(k, inc)| (Some(k), inc));
+        Some((start, end, inclusive_start, inclusive_end))
+    }
+
+    /// True if a range operator compares against a tagged decimal
+    fn has_decimal_bound(ops: &serde_json::Map<String, Value>) -> bool {
+        ["$gt", "$gte", "$lt", "$lte"]
+            .iter()
+            .any(|op| ops.get(*op).is_some_and(is_decimal))
+    }
+
     /// Find an index for a given field
     fn find_index_for_field(field: &str, available_indexes: &[String]) -> Option<String> {
         // Look for index ending with _{field}
@@ -151,57 +324,8 @@ impl QueryPlanner {
     pub fn explain_query(query_json: &Value, available_indexes: &[String]) -> Value {
         use serde_json::json;
 
-        if let Some((field, plan)) = Self::analyze_query(query_json, available_indexes) {
-            // Index-based plan
-            match plan {
-                QueryPlan::IndexScan {
-                    ref index_name,
-                    ref key,
-                    ..
-                } => {
-                    json!({
-                        "queryPlan": "IndexScan",
-                        "indexUsed": index_name,
-                        "field": field,
-                        "stage": "FETCH_WITH_INDEX",
-                        "indexType": "equality",
-                        "searchKey": format!("{:?}", key),
-                        "estimatedCost": "O(log n)",
-                    })
-                }
-                QueryPlan::IndexRangeScan {
-                    ref index_name,
-                    ref start,
-                    ref end,
-                    inclusive_start,
-                    inclusive_end,
-                    ..
-                } => {
-                    json!({
-                        "queryPlan": "IndexRangeScan",
-                        "indexUsed": index_name,
-                        "field": field,
-                        "stage": "FETCH_WITH_INDEX",
-                        "indexType": "range",
-                        "range": {
-                            "start": format!("{:?}", start),
-                            "end": format!("{:?}", end),
-                            "inclusiveStart": inclusive_start,
-                            "inclusiveEnd": inclusive_end,
-                        },
-                        "estimatedCost": "O(log n + k)",
-                    })
-                }
-                QueryPlan::CollectionScan => {
-                    json!({
-                        "queryPlan": "CollectionScan",
-                        "indexUsed": null,
-                        "stage": "FULL_SCAN",
-                        "reason": "No suitable index",
-                        "estimatedCost": "O(n)",
-                    })
-                }
-            }
+        if let Some((_, plan)) = Self::analyze_query(query_json, available_indexes) {
+            Self::describe_plan(&plan)
         } else {
             // No index available
             json!({
@@ -214,6 +338,96 @@ impl QueryPlanner {
             })
         }
     }
+
+    /// Explain output for a chosen plan
+    pub fn describe_plan(plan: &QueryPlan) -> Value {
+        use serde_json::json;
+
+        match plan {
+            QueryPlan::IndexScan {
+                index_name,
+                field,
+                key,
+            } => {
+                json!({
+                    "queryPlan": "IndexScan",
+                    "indexUsed": index_name,
+                    "field": field,
+                    "stage": "FETCH_WITH_INDEX",
+                    "indexType": "equality",
+                    "searchKey": format!("{:?}", key),
+                    "estimatedCost": "O(log n)",
+                })
+            }
+            QueryPlan::IndexRangeScan {
+                index_name,
+                field,
+                start,
+                end,
+                inclusive_start,
+                inclusive_end,
+            } => {
+                json!({
+                    "queryPlan": "IndexRangeScan",
+                    "indexUsed": index_name,
+                    "field": field,
+                    "stage": "FETCH_WITH_INDEX",
+                    "indexType": "range",
+                    "range": {
+                        "start": format!("{:?}", start),
+                        "end": format!("{:?}", end),
+                        "inclusiveStart": inclusive_start,
+                        "inclusiveEnd": inclusive_end,
+                    },
+                    "estimatedCost": "O(log n + k)",
+                })
+            }
+            QueryPlan::CompoundRangeScan {
+                index_name,
+                prefix,
+                field,
+                start,
+                end,
+                inclusive_start,
+                inclusive_end,
+            } => {
+                json!({
+                    "queryPlan": "CompoundRangeScan",
+                    "indexUsed": index_name,
+                    "field": field,
+                    "stage": "FETCH_WITH_INDEX",
+                    "indexType": "compound",
+                    "prefix": format!("{:?}", prefix),
+                    "range": {
+                        "start": format!("{:?}", start),
+                        "end": format!("{:?}", end),
+                        "inclusiveStart": inclusive_start,
+                        "inclusiveEnd": inclusive_end,
+                    },
+                    "estimatedCost": "O(log n + k)",
+                })
+            }
+            QueryPlan::IndexExistsScan { index_name, field } => {
+                json!({
+                    "queryPlan": "IndexExistsScan",
+                    "indexUsed": index_name,
+                    "field": field,
+                    "stage": "FETCH_WITH_INDEX",
+                    "indexType": "exists",
+                    "estimatedCost": "O(k)",
+                })
+            }
+            QueryPlan::CollectionScan => {
+                json!({
+                    "queryPlan": "CollectionScan",
+                    "indexUsed": null,
+                    "stage": "FULL_SCAN",
+                    "reason": "No suitable index",
+                    "estimatedCost": "O(n)",
+                })
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -276,6 +490,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_exists_query_analysis() {
+        let indexes = vec!["users_email".to_string(), "users_age".to_string()];
+
+        let query = json!({"email": {"$exists": true}});
+        match QueryPlanner::analyze_query(&query, &indexes) {
+            Some((field, QueryPlan::IndexExistsScan { index_name, .. })) => {
+                assert_eq!(field, "email");
+                assert_eq!(index_name, "users_email");
+            }
+            other => panic!("Expected IndexExistsScan, got {:?}", other),
+        }
+
+        // A range on another indexed field is preferred
+        let query = json!({"email": {"$exists": true}, "age": {"$gt": 30}});
+        assert!(matches!(
+            QueryPlanner::analyze_query(&query, &indexes),
+            Some((_, QueryPlan::IndexRangeScan { .. }))
+        ));
+
+        // Documents lacking the field are not in the index
+        let query = json!({"email": {"$exists": false}});
+        assert!(QueryPlanner::analyze_query(&query, &indexes).is_none());
+    }
+
+    #[test]
+    fn test_compound_prefix_plus_range_analysis() {
+        let index = |name: &str, fields: &[&str]| IndexInfo {
+            name: name.to_string(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            unique: false,
+            sparse: false,
+        };
+        let indexes = [
+            index("events_tenant_kind", &["tenant", "kind"]),
+            index("events_tenant_ts", &["tenant", "ts"]),
+        ];
+
+        let query = json!({"tenant": "t1", "ts": {"$gte": 100, "$lt": 200}, "x": 1});
+        match QueryPlanner::analyze_compound_query(&query, &indexes) {
+            Some(QueryPlan::CompoundRangeScan {
+                index_name,
+                prefix,
+                field,
+                start,
+                end,
+                inclusive_start,
+                inclusive_end,
+            }) => {
+                assert_eq!(index_name, "events_tenant_ts");
+                assert_eq!(prefix, [IndexKey::String("t1".to_string())]);
+                assert_eq!(field, "ts");
+                assert_eq!(start, Some(IndexKey::Int(100)));
+                assert_eq!(end, Some(IndexKey::Int(200)));
+                assert!(inclusive_start);
+                assert!(!inclusive_end);
+            }
+            other => panic!("Expected CompoundRangeScan, got {:?}", other),
+        }
+
+        // Leading field alone, or a range without the prefix: no compound plan
+        let prefix_only = json!({"tenant": "t1"});
+        assert!(QueryPlanner::analyze_compound_query(&prefix_only, &indexes).is_none());
+        let range_only = json!({"ts": {"$gt": 5}});
+        assert!(QueryPlanner::analyze_compound_query(&range_only, &indexes).is_none());
+    }
+
     #[test]
     fn test_no_index_available() {
         let query = json!({"name": "Alice"});
@@ -0,0 +1,188 @@
+// ironbase-core/src/read_cache.rs
+// Document read cache with LRU eviction policy
+
+use crate::document::DocumentId;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Location a cached document was read from
+///
+/// An entry is only served while the catalog still points at the same
+/// offset within the same file layout, so updates (which append a new
+/// version) and compaction (which rewrites every offset) never return
+/// stale documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CachedLocation {
+    layout_generation: u64,
+    offset: u64,
+}
+
+/// Per-collection cache of decoded documents keyed by DocumentId
+///
+/// Disabled by default (capacity 0): every lookup misses and nothing is stored.
+pub struct ReadCache {
+    cache: Option<Mutex<LruCache<DocumentId, (CachedLocation, Value)>>>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ReadCache {
+    /// Create a read cache holding up to `capacity` documents (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        ReadCache {
+            cache: NonZeroUsize::new(capacity).map(|cap| Mutex::new(LruCache::new(cap))),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a disabled cache
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    /// Whether documents are actually cached
+    pub fn is_enabled(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// Get a cached document if it was read from `offset` in the current layout
+    pub fn get(&self, doc_id: &DocumentId, layout_generation: u64, offset: u64) -> Option<Value> {
+        let cache = self.cache.as_ref()?;
+        let expected = CachedLocation {
+            layout_generation,
+            offset,
+        };
+
+        let mut cache = cache.lock();
+        match cache.get(doc_id) {
+            Some((location, doc)) if *location == expected => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(doc.clone())
+            }
+            Some(_) => {
+                // Document moved (updated or compacted) - drop the old version
+                cache.pop(doc_id);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Store a document read from `offset` (no-op when disabled)
+    pub fn insert(&self, doc_id: DocumentId, layout_generation: u64, offset: u64, doc: Value) {
+        if let Some(cache) = &self.cache {
+            let location = CachedLocation {
+                layout_generation,
+                offset,
+            };
+            cache.lock().put(doc_id, (location, doc));
+        }
+    }
+
+    /// Remove a single document from the cache
+    pub fn invalidate(&self, doc_id: &DocumentId) {
+        if let Some(cache) = &self.cache {
+            cache.lock().pop(doc_id);
+        }
+    }
+
+    /// Remove all cached documents
+    pub fn clear(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lock().clear();
+        }
+    }
+
+    /// Get cache statistics
+    pub fn stats(&self) -> ReadCacheStats {
+        ReadCacheStats {
+            capacity: self.capacity,
+            size: self.cache.as_ref().map(|c| c.lock().len()).unwrap_or(0),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Read cache statistics
+#[derive(Debug, Clone)]
+pub struct ReadCacheStats {
+    pub capacity: usize,
+    pub size: usize,
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to read from storage
+    pub misses: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let cache = ReadCache::disabled();
+        assert!(!cache.is_enabled());
+
+        cache.insert(DocumentId::Int(1), 0, 100, json!({"_id": 1}));
+        assert_eq!(cache.get(&DocumentId::Int(1), 0, 100), None);
+        assert_eq!(cache.stats().size, 0);
+    }
+
+    #[test]
+    fn test_hit_requires_same_location() {
+        let cache = ReadCache::new(10);
+        cache.insert(DocumentId::Int(1), 0, 100, json!({"_id": 1, "v": 1}));
+
+        assert_eq!(
+            cache.get(&DocumentId::Int(1), 0, 100),
+            Some(json!({"_id": 1, "v": 1}))
+        );
+        // Moved by an update
+        assert_eq!(cache.get(&DocumentId::Int(1), 0, 200), None);
+        // Stale entry was evicted on the mismatch
+        assert_eq!(cache.get(&DocumentId::Int(1), 0, 100), None);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 2);
+    }
+
+    #[test]
+    fn test_layout_generation_mismatch_misses() {
+        let cache = ReadCache::new(10);
+        cache.insert(DocumentId::Int(1), 0, 100, json!({"_id": 1}));
+
+        // Same offset after a compaction may hold a different version
+        assert_eq!(cache.get(&DocumentId::Int(1), 1, 100), None);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = ReadCache::new(2);
+        cache.insert(DocumentId::Int(1), 0, 10, json!({"_id": 1}));
+        cache.insert(DocumentId::Int(2), 0, 20, json!({"_id": 2}));
+        cache.insert(DocumentId::Int(3), 0, 30, json!({"_id": 3}));
+
+        assert_eq!(cache.get(&DocumentId::Int(1), 0, 10), None);
+        assert!(cache.get(&DocumentId::Int(3), 0, 30).is_some());
+        assert_eq!(cache.stats().size, 2);
+    }
+}
@@ -0,0 +1,290 @@
+// storage/catalog_repair.rs
+// Rebuild collection metadata from the data file and the WAL when the stored
+// catalog is unreadable
+
+use super::{StorageEngine, HEADER_SIZE};
+use crate::document::DocumentId;
+use crate::error::Result;
+use crate::transaction::Operation;
+use crate::wal::{TransactionGrouper, WALEntryIterator};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// Catalog repair statistics
+#[derive(Debug, Clone, Default)]
+pub struct CatalogRepairStats {
+    /// Document and tombstone records found by scanning the data file
+    pub scanned_records: usize,
+    /// Bytes between records that could not be read and were stepped over
+    pub skipped_bytes: u64,
+    /// Committed transactions replayed from the WAL
+    pub transactions: usize,
+    /// Insert/update/delete operations replayed
+    pub operations: usize,
+    /// Operations matched to a record already in the data file
+    pub reused_records: usize,
+    /// Operations whose record was missing and had to be written again
+    pub rewritten_records: usize,
+}
+
+/// A length-prefixed document or tombstone record in the data file
+struct DataRecord {
+    offset: u64,
+    value: Value,
+}
+
+/// Everything a scan of the data file found
+#[derive(Default)]
+struct DataScan {
+    records: Vec<DataRecord>,
+    /// Collection names from earlier (superseded) metadata blocks
+    collection_names: Vec<String>,
+    skipped_bytes: u64,
+}
+
+/// Upper bound on the collection count of a metadata block, so a document's
+/// length prefix is not mistaken for one
+const MAX_METADATA_COLLECTIONS: u32 = 4096;
+
+/// Upper bound on a record length, so garbage read as a length prefix is not
+/// allocated and read in full at every offset of a damaged region
+const MAX_RECORD_LEN: u64 = 256 * 1024 * 1024;
+
+impl StorageEngine {
+    /// Rebuild the document catalog from the data file and the WAL
+    ///
+    /// Scans every record from the end of the header to the end of the file,
+    /// so documents written before the last checkpoint (which the WAL no
+    /// longer describes) are kept; later records win and tombstones remove.
+    /// The damaged metadata block is skipped, and superseded metadata blocks
+    /// contribute their collection names. Committed WAL operations are then
+    /// matched against the records after the last metadata block, and
+    /// operations whose record is not there are written again. Indexes and
+    /// schemas were part of the lost metadata and are not restored.
+    ///
+    /// An empty WAL (the catalog was damaged after a clean checkpoint) leaves
+    /// the scan alone to rebuild it. On success the repaired metadata is
+    /// flushed and the WAL cleared.
+    pub fn repair_catalog_from_wal(&mut self) -> Result<CatalogRepairStats> {
+        // Commit order matters here (later versions must win), so group the
+        // WAL as a stream instead of using WriteAheadLog::recover()
+        let wal_file = BufReader::new(File::open(self.wal.path())?);
+        let committed = TransactionGrouper::new(WALEntryIterator::new(wal_file)?)
+            .collect::<Result<Vec<_>>>()?;
+
+        let scan = self.scan_data_records()?;
+        let mut stats = CatalogRepairStats {
+            scanned_records: scan.records.len(),
+            skipped_bytes: scan.skipped_bytes,
+            transactions: committed.len(),
+            ..Default::default()
+        };
+
+        self.collections.clear();
+        for name in &scan.collection_names {
+            let _ = self.create_collection(name);
+        }
+        for record in &scan.records {
+            self.catalog_record(record)?;
+        }
+
+        // The scan already applied every record in the file; the WAL only
+        // has to supply the ones that never made it there
+        let tail_start = self.header.metadata_offset + self.header.metadata_size;
+        let tail: Vec<&DataRecord> = scan
+            .records
+            .iter()
+            .filter(|record| record.offset >= tail_start)
+            .collect();
+        let mut cursor = 0;
+
+        for entry in committed.iter().flat_map(|tx| tx.operations()) {
+            let operation: Operation = serde_json::from_slice(&entry.data)?;
+            stats.operations += 1;
+
+            let (collection, doc_id, expected) = match &operation {
+                Operation::Insert {
+                    collection,
+                    doc_id,
+                    doc,
+                }
+                | Operation::Update {
+                    collection,
+                    doc_id,
+                    new_doc: doc,
+                    ..
+                } => (collection, doc_id, Some(doc)),
+                Operation::Delete {
+                    collection, doc_id, ..
+                } => (collection, doc_id, None),
+            };
+            let _ = self.create_collection(collection);
+
+            // Data records are appended in commit order, so search forward only
+            let found = tail[cursor..].iter().position(|record| match expected {
+                Some(doc) => &record.value == doc,
+                None => is_tombstone_for(&record.value, collection, doc_id),
+            });
+
+            match found {
+                Some(pos) => {
+                    cursor += pos + 1;
+                    stats.reused_records += 1;
+                }
+                None => {
+                    self.apply_wal_operation(&operation)?;
+                    stats.rewritten_records += 1;
+                }
+            }
+        }
+
+        self.catalog_incomplete = false;
+        self.metadata_dirty = true;
+        self.flush()?;
+
+        Ok(stats)
+    }
+
+    /// Point the catalog at a scanned record, or drop its document for a tombstone
+    fn catalog_record(&mut self, record: &DataRecord) -> Result<()> {
+        let (Some(collection), Some(doc_id)) = (
+            record.value.get("_collection").and_then(Value::as_str),
+            record
+                .value
+                .get("_id")
+                .and_then(|id| serde_json::from_value::<DocumentId>(id.clone()).ok()),
+        ) else {
+            return Ok(());
+        };
+        let _ = self.create_collection(collection);
+
+        if record.value.get("_tombstone").and_then(Value::as_bool) == Some(true) {
+            self.uncatalog_document(collection, &doc_id)
+        } else {
+            self.catalog_document(collection, &doc_id, record.offset)
+        }
+    }
+
+    /// Every document and tombstone record from the end of the header to the
+    /// end of the file, in file order
+    ///
+    /// The current metadata block is skipped by its header position; older
+    /// metadata blocks are recognised by their layout. Bytes that are neither
+    /// (the leftovers of a shorter block written over a longer one, or a torn
+    /// final write) are stepped over one at a time until a record parses.
+    fn scan_data_records(&mut self) -> Result<DataScan> {
+        let file_len = self.file.metadata()?.len();
+        let current_metadata = (self.header.metadata_offset >= HEADER_SIZE).then(|| {
+            (
+                self.header.metadata_offset,
+                self.header.metadata_offset + self.header.metadata_size,
+            )
+        });
+
+        let mut scan = DataScan::default();
+        let mut offset = HEADER_SIZE;
+        while offset < file_len {
+            if let Some((start, end)) = current_metadata {
+                if offset == start {
+                    offset = end;
+                    continue;
+                }
+            }
+            if let Some((value, next)) = self.read_document_record(offset, file_len)? {
+                scan.records.push(DataRecord { offset, value });
+                offset = next;
+            } else if let Some((names, next)) = self.read_metadata_block(offset, file_len)? {
+                scan.collection_names.extend(names);
+                offset = next;
+            } else {
+                scan.skipped_bytes += 1;
+                offset += 1;
+            }
+        }
+
+        Ok(scan)
+    }
+
+    /// The length-prefixed JSON object at `offset`, if the prefix is
+    /// plausible and the bytes after it open an object
+    ///
+    /// Both are checked from the first 5 bytes, before anything is allocated.
+    fn read_length_prefixed(&mut self, offset: u64, file_len: u64) -> Result<Option<Vec<u8>>> {
+        if offset + 5 > file_len {
+            return Ok(None);
+        }
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut prefix = [0u8; 5];
+        self.file.read_exact(&mut prefix)?;
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as u64;
+        if len == 0 || len > MAX_RECORD_LEN || offset + 4 + len > file_len || prefix[4] != b'{' {
+            return Ok(None);
+        }
+
+        let mut data = vec![0u8; len as usize];
+        data[0] = b'{';
+        self.file.read_exact(&mut data[1..])?;
+        Ok(Some(data))
+    }
+
+    /// A document or tombstone record at `offset` and the offset after it
+    fn read_document_record(&mut self, offset: u64, file_len: u64) -> Result<Option<(Value, u64)>> {
+        let Some(data) = self.read_length_prefixed(offset, file_len)? else {
+            return Ok(None);
+        };
+        Ok(serde_json::from_slice::<Value>(&data)
+            .ok()
+            .filter(|value| value.get("_collection").is_some() && value.get("_id").is_some())
+            .map(|value| (value, offset + 4 + data.len() as u64)))
+    }
+
+    /// A metadata block at `offset` (collection count, then one
+    /// length-prefixed collection entry each): its collection names and the
+    /// offset after it
+    fn read_metadata_block(
+        &mut self,
+        offset: u64,
+        file_len: u64,
+    ) -> Result<Option<(Vec<String>, u64)>> {
+        if offset + 4 > file_len {
+            return Ok(None);
+        }
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut count_bytes = [0u8; 4];
+        self.file.read_exact(&mut count_bytes)?;
+        let count = u32::from_le_bytes(count_bytes);
+        if count == 0 || count > MAX_METADATA_COLLECTIONS {
+            return Ok(None);
+        }
+
+        let mut names = Vec::new();
+        let mut next = offset + 4;
+        for _ in 0..count {
+            let Some(data) = self.read_length_prefixed(next, file_len)? else {
+                return Ok(None);
+            };
+            let Some(name) = serde_json::from_slice::<Value>(&data)
+                .ok()
+                .filter(|meta| meta.get("document_catalog").is_some())
+                .and_then(|meta| meta.get("name").and_then(Value::as_str).map(String::from))
+            else {
+                return Ok(None);
+            };
+            names.push(name);
+            next += 4 + data.len() as u64;
+        }
+
+        Ok(Some((names, next)))
+    }
+}
+
+fn is_tombstone_for(record: &Value, collection: &str, doc_id: &DocumentId) -> bool {
+    record.get("_tombstone").and_then(Value::as_bool) == Some(true)
+        && record.get("_collection").and_then(Value::as_str) == Some(collection)
+        && record
+            .get("_id")
+            .and_then(|id| serde_json::from_value::<DocumentId>(id.clone()).ok())
+            .as_ref()
+            == Some(doc_id)
+}
@@ -2,11 +2,11 @@
 // Storage compaction functionality
 
 use super::StorageEngine;
-use crate::error::Result;
+use crate::error::{MongoLiteError, Result};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::fs::{self, OpenOptions};
-use std::io::{Seek, SeekFrom, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 /// Compaction configuration
 #[derive(Debug, Clone)]
@@ -30,6 +30,53 @@ pub struct CompactionStats {
     pub documents_kept: u64,
     pub tombstones_removed: u64,
     pub peak_memory_mb: u64, // Peak memory usage during compaction
+    /// Result of the post-compaction check (only set by `compact_verified`)
+    pub verification: Option<CompactionVerification>,
+}
+
+/// Live document set before compaction compared with the compacted output
+///
+/// The checksum is order-independent, so it is unaffected by the compacted
+/// file storing documents in a different order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionVerification {
+    pub expected_documents: u64,
+    pub actual_documents: u64,
+    pub expected_checksum: u64,
+    pub actual_checksum: u64,
+}
+
+impl CompactionVerification {
+    pub fn passed(&self) -> bool {
+        self.expected_documents == self.actual_documents
+            && self.expected_checksum == self.actual_checksum
+    }
+}
+
+/// Document count and combined checksum of a set of live documents
+#[derive(Debug, Clone, Copy, Default)]
+struct LiveSetDigest {
+    documents: u64,
+    checksum: u64,
+}
+
+impl LiveSetDigest {
+    fn add(&mut self, collection: &str, doc: &Value) -> Result<()> {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(collection.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&serde_json::to_vec(doc)?);
+        // Wrapping sum keeps the result independent of document order
+        self.checksum = self.checksum.wrapping_add(hasher.finalize() as u64);
+        self.documents += 1;
+        Ok(())
+    }
+}
+
+fn is_tombstone(doc: &Value) -> bool {
+    doc.get("_tombstone")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
 }
 
 impl CompactionStats {
@@ -47,6 +94,37 @@ impl CompactionStats {
 }
 
 impl StorageEngine {
+    /// Share of the file's document area not taken by live documents
+    ///
+    /// The rest is dead space left by deleted documents and their tombstones,
+    /// superseded versions and earlier metadata copies, which compaction
+    /// reclaims. 0.0 for a file without documents.
+    pub fn fragmentation_ratio(&mut self) -> Result<f64> {
+        let file_len = self.file.metadata()?.len();
+        let area = file_len
+            .saturating_sub(super::HEADER_SIZE)
+            .saturating_sub(self.header.metadata_size);
+        if area == 0 {
+            return Ok(0.0);
+        }
+
+        // Deleted documents stay catalogued, pointing at their tombstone
+        let offsets: Vec<u64> = self
+            .list_collections()
+            .iter()
+            .filter_map(|name| self.get_collection_meta(name))
+            .flat_map(|meta| meta.document_catalog.values().copied())
+            .collect();
+        let mut live = 0u64;
+        for offset in offsets {
+            let data = self.read_data(offset)?;
+            if !is_tombstone(&serde_json::from_slice(&data)?) {
+                live += 4 + data.len() as u64;
+            }
+        }
+        Ok(area.saturating_sub(live) as f64 / area as f64)
+    }
+
     /// Storage compaction - removes tombstones and old document versions
     /// Uses chunked processing to minimize memory usage
     pub fn compact(&mut self) -> Result<CompactionStats> {
@@ -63,7 +141,81 @@ impl StorageEngine {
     /// 4. finalize_compaction() - Atomic file swap and reload
     pub fn compact_with_config(&mut self, config: &CompactionConfig) -> Result<CompactionStats> {
         let mut stats = CompactionStats::default();
+        let new_file = self.write_compacted_file(config, &mut stats)?;
+
+        // 4. Finalize: close files, rename, reload
+        let temp_path = format!("{}.compact", self.file_path);
+        self.finalize_compaction(&temp_path, new_file)?;
+
+        Ok(stats)
+    }
+
+    /// Storage compaction that verifies the output before swapping it in
+    ///
+    /// The compacted temp file is re-scanned and its live document count and
+    /// content checksum are compared with the live set of the original file.
+    /// On mismatch the temp file is discarded, the original stays in place and
+    /// `MongoLiteError::Corruption` is returned.
+    pub fn compact_verified(&mut self) -> Result<CompactionStats> {
+        self.compact_verified_with(&CompactionConfig::default(), |_| Ok(()))
+    }
+
+    /// `compact_verified` with a hook that runs on the temp file before verification
+    fn compact_verified_with(
+        &mut self,
+        config: &CompactionConfig,
+        before_verify: impl FnOnce(&str) -> Result<()>,
+    ) -> Result<CompactionStats> {
+        let expected = self.live_set_digest()?;
+
+        let mut stats = CompactionStats::default();
+        let new_file = self.write_compacted_file(config, &mut stats)?;
+        let temp_path = format!("{}.compact", self.file_path);
+
+        let actual =
+            before_verify(&temp_path).and_then(|_| Self::compacted_file_digest(&temp_path));
+        let verification = actual.map(|actual| CompactionVerification {
+            expected_documents: expected.documents,
+            actual_documents: actual.documents,
+            expected_checksum: expected.checksum,
+            actual_checksum: actual.checksum,
+        });
+
+        match verification {
+            Ok(verification) if verification.passed() => {
+                stats.verification = Some(verification);
+                self.finalize_compaction(&temp_path, new_file)?;
+                Ok(stats)
+            }
+            Ok(verification) => {
+                drop(new_file);
+                let _ = fs::remove_file(&temp_path);
+                Err(MongoLiteError::Corruption(format!(
+                    "Compaction verification failed, original file kept: expected {} documents \
+                     (checksum {:016x}), compacted file has {} (checksum {:016x})",
+                    verification.expected_documents,
+                    verification.expected_checksum,
+                    verification.actual_documents,
+                    verification.actual_checksum
+                )))
+            }
+            Err(e) => {
+                drop(new_file);
+                let _ = fs::remove_file(&temp_path);
+                Err(MongoLiteError::Corruption(format!(
+                    "Compaction verification failed, original file kept: {}",
+                    e
+                )))
+            }
+        }
+    }
 
+    /// Phases 1-3 of compaction: produce the compacted temp file (not yet swapped in)
+    fn write_compacted_file(
+        &mut self,
+        config: &CompactionConfig,
+        stats: &mut CompactionStats,
+    ) -> Result<std::fs::File> {
         // 1. Prepare: flush metadata, create temp file, initialize collections
         let (mut new_file, mut new_collections, file_len) = self.prepare_compaction()?;
         stats.size_before = self.file.metadata()?.len();
@@ -76,7 +228,7 @@ impl StorageEngine {
             &collections_snapshot,
             file_len,
             config,
-            &mut stats,
+            stats,
         )?;
 
         // 3. Write metadata at end of file
@@ -89,11 +241,53 @@ impl StorageEngine {
 
         stats.size_after = new_file.metadata()?.len();
 
-        // 4. Finalize: close files, rename, reload
-        let temp_path = format!("{}.compact", self.file_path);
-        self.finalize_compaction(&temp_path, new_file)?;
+        Ok(new_file)
+    }
 
-        Ok(stats)
+    /// Digest of the live documents currently referenced by the catalogs
+    ///
+    /// Unreadable records are left out, matching what compaction itself skips.
+    fn live_set_digest(&mut self) -> Result<LiveSetDigest> {
+        let mut digest = LiveSetDigest::default();
+        let collections = self.collections.clone();
+        for (coll_name, coll_meta) in &collections {
+            for &offset in coll_meta.document_catalog.values() {
+                let Ok(bytes) = self.read_data(offset) else {
+                    continue;
+                };
+                if let Ok(doc) = serde_json::from_slice::<Value>(&bytes) {
+                    if !is_tombstone(&doc) {
+                        digest.add(coll_name, &doc)?;
+                    }
+                }
+            }
+        }
+        Ok(digest)
+    }
+
+    /// Re-scan a compacted file through its own metadata and digest its documents
+    fn compacted_file_digest(path: &str) -> Result<LiveSetDigest> {
+        let mut file = File::open(path)?;
+        let (_, collections) = Self::load_metadata(&mut file)?;
+
+        let mut digest = LiveSetDigest::default();
+        for (coll_name, coll_meta) in &collections {
+            for &offset in coll_meta.document_catalog.values() {
+                file.seek(SeekFrom::Start(offset))?;
+                let mut len_bytes = [0u8; 4];
+                file.read_exact(&mut len_bytes)?;
+                let mut data = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+                file.read_exact(&mut data)?;
+
+                // A record that no longer parses is simply missing from the digest
+                if let Ok(doc) = serde_json::from_slice::<Value>(&data) {
+                    if !is_tombstone(&doc) {
+                        digest.add(coll_name, &doc)?;
+                    }
+                }
+            }
+        }
+        Ok(digest)
     }
 
     // =========================================================================
@@ -144,7 +338,7 @@ impl StorageEngine {
         // Write header only (no metadata yet - documents start at HEADER_SIZE)
         new_file.seek(SeekFrom::Start(0))?;
         let header_bytes = bincode::serialize(&self.header)
-            .map_err(|e| crate::error::MongoLiteError::Serialization(e.to_string()))?;
+            .map_err(|e| MongoLiteError::Serialization(e.to_string()))?;
         new_file.write_all(&header_bytes)?;
 
         // Position at start of document region
@@ -308,7 +502,7 @@ impl StorageEngine {
         // Rewrite header at file start
         new_file.seek(SeekFrom::Start(0))?;
         let header_bytes = bincode::serialize(&updated_header)
-            .map_err(|e| crate::error::MongoLiteError::Serialization(e.to_string()))?;
+            .map_err(|e| MongoLiteError::Serialization(e.to_string()))?;
         new_file.write_all(&header_bytes)?;
 
         new_file.sync_all()?;
@@ -323,26 +517,40 @@ impl StorageEngine {
         // Close new file before renaming
         drop(new_file);
 
-        // Close old file and mmap
-        drop(self.mmap.take());
-
-        // Replace old file with new file (atomic on most filesystems)
-        fs::rename(temp_path, &self.file_path)?;
-
-        // Reopen the compacted file
-        let mut file = OpenOptions::new()
+        // Load the compacted catalog before giving anything up: if it can't
+        // be read, the original file and catalog stay in use
+        let loaded = OpenOptions::new()
             .read(true)
             .write(true)
-            .open(&self.file_path)?;
+            .open(temp_path)
+            .map_err(MongoLiteError::from)
+            .and_then(|mut file| {
+                let (header, collections) = Self::load_metadata(&mut file)?;
+                Ok((file, header, collections))
+            });
+        let (file, header, collections) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                let _ = fs::remove_file(temp_path);
+                return Err(e);
+            }
+        };
+
+        // Close old mmap
+        drop(self.mmap.take());
 
-        // Reload metadata
-        let (header, collections) = Self::load_metadata(&mut file)?;
+        // Replace old file with new file (atomic on most filesystems); the
+        // handle opened above follows the file to its new name
+        fs::rename(temp_path, &self.file_path)?;
 
-        // Update self
+        // Swap the file and the catalog pointing into it as one step. Readers
+        // take the storage lock compaction holds, so they resolve offsets
+        // against either the old file and catalog or the new ones, never a mix
         self.file = file;
         self.header = header;
         self.collections = collections;
         self.mmap = None; // Reset mmap
+        self.layout_generation += 1; // Every document offset has changed
 
         Ok(())
     }
@@ -359,11 +567,7 @@ impl StorageEngine {
     ) -> Result<u64> {
         for (doc_id, doc) in docs_by_id.iter() {
             // Skip tombstones (deleted documents)
-            if doc
-                .get("_tombstone")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false)
-            {
+            if is_tombstone(doc) {
                 stats.tombstones_removed += 1;
                 continue;
             }
@@ -392,3 +596,71 @@ impl StorageEngine {
         Ok(write_offset)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::DocumentId;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn setup_with_docs() -> (TempDir, StorageEngine) {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = StorageEngine::open(temp_dir.path().join("verify.mlite")).unwrap();
+        storage.create_collection("items").unwrap();
+        for i in 0..5 {
+            let doc = json!({"_id": i, "value": i * 10});
+            storage
+                .write_document("items", &DocumentId::Int(i), doc.to_string().as_bytes())
+                .unwrap();
+        }
+        let tombstone = json!({"_id": 0, "_tombstone": true});
+        storage
+            .write_document(
+                "items",
+                &DocumentId::Int(0),
+                tombstone.to_string().as_bytes(),
+            )
+            .unwrap();
+        (temp_dir, storage)
+    }
+
+    #[test]
+    fn test_compact_verified_reports_passing_verification() {
+        let (_temp, mut storage) = setup_with_docs();
+
+        let stats = storage.compact_verified().unwrap();
+        let verification = stats.verification.unwrap();
+        assert!(verification.passed());
+        assert_eq!(verification.expected_documents, 4);
+        assert_eq!(stats.documents_kept, 4);
+    }
+
+    #[test]
+    fn test_corrupted_compaction_output_aborts_swap() {
+        let (temp, mut storage) = setup_with_docs();
+        let temp_path = format!("{}.compact", storage.file_path);
+
+        let result = storage.compact_verified_with(&CompactionConfig::default(), |path| {
+            // Flip a byte inside the first document record
+            let mut file = OpenOptions::new().write(true).open(path)?;
+            file.seek(SeekFrom::Start(super::super::HEADER_SIZE + 6))?;
+            file.write_all(b"#")?;
+            Ok(())
+        });
+
+        assert!(matches!(result, Err(MongoLiteError::Corruption(_))));
+        assert!(!std::path::Path::new(&temp_path).exists());
+
+        // Original file is still the live one, before and after reopening
+        let offset = storage.collections["items"].document_catalog[&DocumentId::Int(3)];
+        let doc: Value = serde_json::from_slice(&storage.read_data(offset).unwrap()).unwrap();
+        assert_eq!(doc["value"], 30);
+
+        drop(storage);
+        let mut storage = StorageEngine::open(temp.path().join("verify.mlite")).unwrap();
+        let offset = storage.collections["items"].document_catalog[&DocumentId::Int(3)];
+        let doc: Value = serde_json::from_slice(&storage.read_data(offset).unwrap()).unwrap();
+        assert_eq!(doc["value"], 30);
+    }
+}
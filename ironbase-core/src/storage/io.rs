@@ -22,6 +22,30 @@ impl StorageEngine {
 
     /// Read data from specified offset
     pub fn read_data(&mut self, offset: u64) -> Result<Vec<u8>> {
+        let len = self.seek_to_record(offset)?;
+
+        // Adat olvasása
+        let mut data = vec![0u8; len];
+        self.file.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+
+    /// Parse the record at `offset` straight from the file, without buffering it whole
+    pub fn read_data_with(
+        &mut self,
+        offset: u64,
+        parse: &mut dyn FnMut(&mut dyn Read) -> Result<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let len = self.seek_to_record(offset)?;
+        let mut reader = std::io::BufReader::new((&self.file).take(len as u64));
+        parse(&mut reader)
+    }
+
+    /// Validate the record at `offset` and position the file at its data
+    ///
+    /// Returns the record's data length.
+    fn seek_to_record(&mut self, offset: u64) -> Result<usize> {
         use crate::error::MongoLiteError;
 
         // CRITICAL FIX: Validate offset is within file bounds BEFORE reading
@@ -66,11 +90,94 @@ impl StorageEngine {
             )));
         }
 
-        // Adat olvasása
-        let mut data = vec![0u8; len];
-        self.file.read_exact(&mut data)?;
+        Ok(len)
+    }
 
-        Ok(data)
+    /// Append a document record, padded to the configured block size
+    ///
+    /// Padding is trailing JSON whitespace inside the record, so readers and
+    /// sequential scans see an ordinary (slightly longer) record.
+    fn append_document_record(&mut self, data: &[u8]) -> Result<u64> {
+        let offset = self.file.seek(SeekFrom::End(0))?;
+
+        let slot_len = match self.padding_block_size {
+            0 => data.len(),
+            block => data.len().div_ceil(block) * block,
+        };
+        let len = (slot_len as u32).to_le_bytes();
+        self.file.write_all(&len)?;
+        self.file.write_all(data)?;
+        if slot_len > data.len() {
+            self.file.write_all(&vec![b' '; slot_len - data.len()])?;
+        }
+
+        Ok(offset)
+    }
+
+    /// Reserve padding when writing documents so small updates can be done in place
+    ///
+    /// New document records are rounded up to a multiple of `block_size` bytes
+    /// (0 disables padding, the default). Existing records are not rewritten.
+    pub fn set_padding_block_size(&mut self, block_size: usize) {
+        self.padding_block_size = block_size;
+    }
+
+    /// Current document padding block size (0 = disabled)
+    pub fn padding_block_size(&self) -> usize {
+        self.padding_block_size
+    }
+
+    /// Allow updates to overwrite records in their padded slot
+    ///
+    /// Off by default. An overwrite replaces the only copy of the previous
+    /// version before the WAL commit of the new one, so a crash or torn
+    /// write during it loses both; `DatabaseCore` enables it only for the
+    /// Batch and Unsafe durability modes.
+    pub fn set_in_place_updates(&mut self, enabled: bool) {
+        self.in_place_updates = enabled;
+    }
+
+    /// Overwrite a document's current record if the new bytes fit in its slot
+    ///
+    /// Only used when padding and in-place updates are enabled. The previous
+    /// version is replaced rather than kept behind a tombstone, and the
+    /// catalog offset is unchanged. Returns `false` (nothing written) when
+    /// the document is not in the catalog or the new version is larger than
+    /// the slot.
+    pub fn overwrite_document_in_place(
+        &mut self,
+        collection: &str,
+        doc_id: &crate::document::DocumentId,
+        data: &[u8],
+    ) -> Result<bool> {
+        if self.padding_block_size == 0 || !self.in_place_updates {
+            return Ok(false);
+        }
+
+        let offset = match self
+            .get_collection_meta(collection)
+            .and_then(|meta| meta.document_catalog.get(doc_id))
+        {
+            Some(&offset) => offset,
+            None => return Ok(false),
+        };
+
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut len_bytes = [0u8; 4];
+        self.file.read_exact(&mut len_bytes)?;
+        let slot_len = u32::from_le_bytes(len_bytes) as usize;
+        if data.len() > slot_len {
+            return Ok(false);
+        }
+
+        let mut record = Vec::with_capacity(slot_len);
+        record.extend_from_slice(data);
+        record.resize(slot_len, b' ');
+        self.file.write_all(&record)?;
+
+        // Same offset, different bytes: cached reads must not be served
+        self.layout_generation += 1;
+        Ok(true)
     }
 
     /// Get file length
@@ -78,6 +185,29 @@ impl StorageEngine {
         Ok(self.file.metadata()?.len())
     }
 
+    /// Total on-disk size of a collection's live documents (length headers included)
+    ///
+    /// Only the 4-byte length prefix of each catalog entry is read, so this
+    /// stays cheap even for large documents.
+    pub fn collection_size_bytes(&mut self, collection: &str) -> Result<u64> {
+        use crate::error::MongoLiteError;
+
+        let meta = self
+            .get_collection_meta(collection)
+            .ok_or_else(|| MongoLiteError::CollectionNotFound(collection.to_string()))?;
+
+        let mut total = 0u64;
+        for &offset in meta.document_catalog.values() {
+            let mut file = &self.file;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut len_bytes = [0u8; 4];
+            file.read_exact(&mut len_bytes)?;
+            total += 4 + u32::from_le_bytes(len_bytes) as u64;
+        }
+
+        Ok(total)
+    }
+
     /// Write document and update catalog
     /// This is the new persistent write method that tracks document offsets
     /// Stores ABSOLUTE offsets in catalog for simplicity and correctness
@@ -90,12 +220,7 @@ impl StorageEngine {
         use crate::error::MongoLiteError;
 
         // Append document after existing data
-        let absolute_offset = self.file.seek(SeekFrom::End(0))?;
-
-        // Write length + data (same format as write_data)
-        let len = (data.len() as u32).to_le_bytes();
-        self.file.write_all(&len)?;
-        self.file.write_all(data)?;
+        let absolute_offset = self.append_document_record(data)?;
 
         self.metadata_dirty = true;
         // Update catalog in metadata with ABSOLUTE offset
@@ -139,15 +264,23 @@ impl StorageEngine {
         doc_id: &crate::document::DocumentId,
         data: &[u8],
     ) -> Result<u64> {
-        use crate::error::MongoLiteError;
-
         // Append document after existing data
-        let absolute_offset = self.file.seek(SeekFrom::End(0))?;
+        let absolute_offset = self.append_document_record(data)?;
+        self.catalog_document(collection, doc_id, absolute_offset)?;
 
-        // Write length + data (same format as write_data)
-        let len = (data.len() as u32).to_le_bytes();
-        self.file.write_all(&len)?;
-        self.file.write_all(data)?;
+        Ok(absolute_offset)
+    }
+
+    /// Point the catalog at a document record already in the file
+    ///
+    /// Performs the metadata half of `write_document_full` (catalog, counts, last_id).
+    pub(super) fn catalog_document(
+        &mut self,
+        collection: &str,
+        doc_id: &crate::document::DocumentId,
+        absolute_offset: u64,
+    ) -> Result<()> {
+        use crate::error::MongoLiteError;
 
         self.metadata_dirty = true;
 
@@ -178,7 +311,7 @@ impl StorageEngine {
             }
         }
 
-        Ok(absolute_offset)
+        Ok(())
     }
 
     /// Write tombstone with full metadata update
@@ -206,6 +339,17 @@ impl StorageEngine {
         self.file.write_all(&len)?;
         self.file.write_all(tombstone_json.as_bytes())?;
 
+        self.uncatalog_document(collection, doc_id)
+    }
+
+    /// Remove a document from the catalog whose tombstone is already in the file
+    pub(super) fn uncatalog_document(
+        &mut self,
+        collection: &str,
+        doc_id: &crate::document::DocumentId,
+    ) -> Result<()> {
+        use crate::error::MongoLiteError;
+
         self.metadata_dirty = true;
 
         // Update metadata
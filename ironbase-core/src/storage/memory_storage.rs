@@ -207,6 +207,10 @@ impl Storage for MemoryStorage {
             document_catalog: HashMap::new(),
             indexes: Vec::new(),
             schema: None,
+            max_documents: None,
+            capped: None,
+            skip_id_index: false,
+            text_index: None,
         };
 
         self.metadata.insert(name.to_string(), meta);
@@ -12,6 +12,22 @@ impl StorageEngine {
     pub(super) fn load_metadata(
         file: &mut File,
     ) -> Result<(Header, HashMap<String, CollectionMeta>)> {
+        let header = Self::load_header(file)?;
+
+        // Load collections based on version
+        let collections = if header.version >= 2 && header.metadata_offset > 0 {
+            // Version 2+: Dynamic metadata at end of file
+            Self::load_metadata_dynamic(file, &header)?
+        } else {
+            // Version 1: Legacy format (metadata after header)
+            Self::load_metadata_legacy(file, &header)?
+        };
+
+        Ok((header, collections))
+    }
+
+    /// Read and validate the file header only
+    pub(super) fn load_header(file: &mut File) -> Result<Header> {
         file.seek(SeekFrom::Start(0))?;
 
         // Read header (with dynamic size for version 2+)
@@ -26,16 +42,7 @@ impl StorageEngine {
             return Err(MongoLiteError::Corruption("Invalid magic number".into()));
         }
 
-        // Load collections based on version
-        let collections = if header.version >= 2 && header.metadata_offset > 0 {
-            // Version 2+: Dynamic metadata at end of file
-            Self::load_metadata_dynamic(file, &header)?
-        } else {
-            // Version 1: Legacy format (metadata after header)
-            Self::load_metadata_legacy(file, &header)?
-        };
-
-        Ok((header, collections))
+        Ok(header)
     }
 
     /// Load metadata from dynamic location (version 2+)
@@ -167,6 +174,7 @@ impl StorageEngine {
             &metadata_bytes,
             metadata_offset,
         )?;
+        self.sync_counts.data += 1; // write_metadata_and_header fsyncs the file
 
         self.metadata_dirty = false;
         Ok(())
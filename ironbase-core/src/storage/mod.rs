@@ -1,6 +1,7 @@
 // storage/mod.rs
 // Storage engine module
 
+mod catalog_repair;
 mod compaction;
 pub mod file_storage; // NEW: FileStorage wrapper
 mod io;
@@ -9,6 +10,7 @@ pub mod metadata; // Make metadata public for CollectionMeta
 pub mod traits; // NEW: Storage trait definitions
 
 use crate::document::{Document, DocumentId};
+use crate::durability::CommitLatency;
 use crate::error::{MongoLiteError, Result};
 use crate::transaction::Transaction;
 use crate::wal::WriteAheadLog;
@@ -19,7 +21,8 @@ use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
 
 // Re-export public types
-pub use compaction::{CompactionConfig, CompactionStats};
+pub use catalog_repair::CatalogRepairStats;
+pub use compaction::{CompactionConfig, CompactionStats, CompactionVerification};
 
 // Re-export traits module
 // NOTE: RawStorage is intentionally NOT public - it uses sealed trait pattern
@@ -106,6 +109,36 @@ pub struct CollectionMeta {
     /// Optional JSON schema for validation
     #[serde(default)]
     pub schema: Option<serde_json::Value>,
+
+    /// Optional cap on the number of live documents
+    #[serde(default)]
+    pub max_documents: Option<u64>,
+
+    /// Ring-buffer limits; inserts past them evict the oldest documents
+    #[serde(default)]
+    pub capped: Option<CappedLimits>,
+
+    /// No automatic unique `_id` index is maintained; `_id` lookups go
+    /// through the document catalog and explicit `_id` values are not
+    /// checked for duplicates
+    #[serde(default)]
+    pub skip_id_index: bool,
+
+    /// Full-text index definition; its postings are rebuilt when a
+    /// collection handle is opened
+    #[serde(default)]
+    pub text_index: Option<crate::text_index::TextIndexMetadata>,
+}
+
+/// Size limits of a capped collection
+///
+/// Documents are evicted in `_id` order (integer ids first, ascending, then
+/// string ids); with auto-generated ids that is insertion order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CappedLimits {
+    pub max_documents: Option<u64>,
+    /// Total serialized size of the live documents
+    pub max_bytes: Option<u64>,
 }
 
 /// Index record for persistence
@@ -124,11 +157,44 @@ pub struct StorageEngine {
     file_path: String,
     wal: WriteAheadLog,
     metadata_dirty: bool,
+    /// Bumped on compaction and in-place overwrites (see RawStorage::layout_generation)
+    layout_generation: u64,
+    /// Document records are padded up to a multiple of this size (0 = no padding)
+    padding_block_size: usize,
+    /// Updates may overwrite the only copy of a record (off for Safe durability)
+    in_place_updates: bool,
+    /// In-memory catalog is a partial repair; dropping must not flush it over the file
+    catalog_incomplete: bool,
+    /// Fsync the data file on every commit, not just at checkpoints
+    sync_data_on_commit: bool,
+    sync_counts: SyncCounts,
+    commit_latency: CommitLatency,
+}
+
+/// Number of fsyncs issued on the WAL and on the data file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncCounts {
+    pub wal: u64,
+    pub data: u64,
 }
 
 impl StorageEngine {
     /// Adatbázis megnyitása vagy létrehozása
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_wal_path(path, None)
+    }
+
+    /// Open with the WAL at `wal_path` instead of next to the data file
+    ///
+    /// `wal_path` may name the WAL file itself or an existing directory, in
+    /// which case the WAL is `<data file name>.wal` inside it. The directory
+    /// must exist and be writable. `None` keeps the default
+    /// `<data file>.wal`.
+    pub fn open_with_wal_path<P: AsRef<Path>>(path: P, wal_path: Option<&Path>) -> Result<Self> {
+        let wal_path = match wal_path {
+            Some(custom) => Self::prepare_wal_path(path.as_ref(), custom)?,
+            None => path.as_ref().with_extension("wal"),
+        };
         let path_str = path.as_ref().to_string_lossy().to_string();
         let exists = path.as_ref().exists();
 
@@ -138,9 +204,21 @@ impl StorageEngine {
             .create(true)
             .open(&path)?;
 
+        // Set when the header is valid but the collection metadata is not
+        let mut catalog_error = None;
+
         let (header, collections) = if exists && file.metadata()?.len() > 0 {
             // Meglévő adatbázis betöltése
-            Self::load_metadata(&mut file)?
+            match Self::load_metadata(&mut file) {
+                Ok(loaded) => loaded,
+                Err(e) => match Self::load_header(&mut file) {
+                    Ok(header) => {
+                        catalog_error = Some(e);
+                        (header, HashMap::new())
+                    }
+                    Err(_) => return Err(e),
+                },
+            }
         } else {
             // Új adatbázis inicializálása
             let header = Header::default();
@@ -159,10 +237,9 @@ impl StorageEngine {
         };
 
         // WAL fájl megnyitása
-        let wal_path = PathBuf::from(&path_str).with_extension("wal");
         let wal = WriteAheadLog::open(wal_path)?;
 
-        let storage = StorageEngine {
+        let mut storage = StorageEngine {
             file,
             mmap,
             header,
@@ -170,14 +247,74 @@ impl StorageEngine {
             file_path: path_str,
             wal,
             metadata_dirty: false,
+            layout_generation: 0,
+            padding_block_size: 0,
+            in_place_updates: false,
+            catalog_incomplete: catalog_error.is_some(),
+            sync_data_on_commit: false,
+            sync_counts: SyncCounts::default(),
+            commit_latency: CommitLatency::default(),
         };
 
+        // Damaged catalog: fall back to rebuilding it from the data file and WAL
+        if let Some(e) = catalog_error {
+            let stats = storage.repair_catalog_from_wal().map_err(|repair_err| {
+                crate::log_warn!("Catalog repair from WAL failed: {}", repair_err);
+                e
+            })?;
+            crate::log_warn!(
+                "Collection metadata was unreadable; rebuilt catalog from {} data records ({} unreadable bytes skipped) and {} WAL operations ({} records reused, {} rewritten)",
+                stats.scanned_records,
+                stats.skipped_bytes,
+                stats.operations,
+                stats.reused_records,
+                stats.rewritten_records
+            );
+        }
+
         // NOTE: WAL recovery is now handled by DatabaseCore::open() for index atomicity
         // This allows Database to coordinate index recovery across all collections
 
         Ok(storage)
     }
 
+    /// Resolve a custom WAL location and check its directory is writable
+    fn prepare_wal_path(data_path: &Path, wal_path: &Path) -> Result<PathBuf> {
+        let (dir, file) = if wal_path.is_dir() {
+            let name = data_path
+                .with_extension("wal")
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("ironbase.wal"));
+            (wal_path.to_path_buf(), wal_path.join(name))
+        } else {
+            let dir = match wal_path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            (dir, wal_path.to_path_buf())
+        };
+
+        if !dir.is_dir() {
+            return Err(MongoLiteError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("WAL directory {} does not exist", dir.display()),
+            )));
+        }
+        // Creating a file is the only reliable writability check across platforms
+        let probe = dir.join(".ironbase-wal-probe");
+        File::create(&probe)
+            .and_then(|_| std::fs::remove_file(&probe))
+            .map_err(|e| {
+                MongoLiteError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("WAL directory {} is not writable: {}", dir.display(), e),
+                ))
+            })?;
+
+        Ok(file)
+    }
+
     /// Collection létrehozása
     pub fn create_collection(&mut self, name: &str) -> Result<()> {
         if self.collections.contains_key(name) {
@@ -195,6 +332,10 @@ impl StorageEngine {
             document_catalog: HashMap::new(), // Initialize empty catalog
             indexes: Vec::new(),              // Initialize empty index list
             schema: None,
+            max_documents: None,
+            capped: None,
+            skip_id_index: false,
+            text_index: None,
         };
 
         self.collections.insert(name.to_string(), meta);
@@ -245,7 +386,7 @@ impl StorageEngine {
     pub fn flush(&mut self) -> Result<()> {
         // Flush metadata to disk with proper convergence
         self.flush_metadata()?;
-        self.file.sync_all()?;
+        self.sync_data_file()?;
 
         // CRITICAL: Clear WAL AFTER metadata is safely on disk
         // This prevents WAL from growing indefinitely in long-running processes
@@ -254,6 +395,33 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Also fsync the data file on every commit (off by default)
+    ///
+    /// A commit is durable once its WAL records are fsynced: after a crash,
+    /// recovery replays them over whatever data-file writes were lost. The
+    /// data file itself is fsynced when metadata is flushed (checkpoint,
+    /// flush, compaction), before the WAL is cleared.
+    pub fn set_sync_data_on_commit(&mut self, enabled: bool) {
+        self.sync_data_on_commit = enabled;
+    }
+
+    /// Fsyncs issued so far on the WAL and on the data file
+    pub fn sync_counts(&self) -> SyncCounts {
+        self.sync_counts
+    }
+
+    fn sync_wal(&mut self) -> Result<()> {
+        self.wal.flush()?;
+        self.sync_counts.wal += 1;
+        Ok(())
+    }
+
+    fn sync_data_file(&mut self) -> Result<()> {
+        self.file.sync_all()?;
+        self.sync_counts.data += 1;
+        Ok(())
+    }
+
     /// Get mutable reference to the database file (for index persistence)
     pub fn get_file_mut(&mut self) -> &mut File {
         &mut self.file
@@ -274,10 +442,19 @@ impl StorageEngine {
     }
 
     /// Statisztikák
+    ///
+    /// `wal` reports the WAL's size, the entries appended since the last
+    /// checkpoint and the rolling average commit latency (null before the
+    /// first commit).
     pub fn stats(&self) -> serde_json::Value {
         serde_json::json!({
             "file_path": self.file_path,
             "file_size": self.file.metadata().map(|m| m.len()).unwrap_or(0),
+            "wal": {
+                "size_bytes": self.wal.size_bytes().unwrap_or(0),
+                "entries_since_checkpoint": self.wal.entries_since_checkpoint(),
+                "avg_commit_latency_us": self.commit_latency.average().map(|d| d.as_micros() as u64),
+            },
             "page_size": self.header.page_size,
             "collection_count": self.header.collection_count,
             "collections": self.collections.iter().map(|(name, meta)| {
@@ -300,6 +477,7 @@ impl StorageEngine {
         }
 
         let already_applied = transaction.operations_applied();
+        let started = std::time::Instant::now();
 
         // Step 1: Write BEGIN marker to WAL
         let begin_entry = WALEntry::new(transaction.id, WALEntryType::Begin, vec![]);
@@ -358,7 +536,8 @@ impl StorageEngine {
         self.wal.append(&commit_entry)?;
 
         // Step 4: Fsync WAL (durability guarantee)
-        self.wal.flush()?;
+        self.sync_wal()?;
+        self.commit_latency.record(started.elapsed());
 
         // Step 5: Apply operations to storage
         if !already_applied {
@@ -421,8 +600,11 @@ impl StorageEngine {
             }
         }
 
-        // Step 8: Fsync storage file
-        self.file.sync_all()?;
+        // Step 8: Fsync storage file, unless left to the next checkpoint (the
+        // WAL fsynced in step 4 already makes the commit recoverable)
+        if self.sync_data_on_commit {
+            self.sync_data_file()?;
+        }
 
         // Step 9: Mark transaction as committed
         transaction.mark_committed()?;
@@ -441,7 +623,7 @@ impl StorageEngine {
         // Write ABORT marker to WAL
         let abort_entry = WALEntry::new(transaction.id, WALEntryType::Abort, vec![]);
         self.wal.append(&abort_entry)?;
-        self.wal.flush()?;
+        self.sync_wal()?;
 
         // Discard all buffered operations
         transaction.rollback()?;
@@ -737,6 +919,10 @@ impl StorageEngine {
                                     document_catalog: HashMap::new(),
                                     indexes: Vec::new(),
                                     schema: None,
+                                    max_documents: None,
+                                    capped: None,
+                                    skip_id_index: false,
+                                    text_index: None,
                                 });
 
                             if is_tombstone {
@@ -796,7 +982,9 @@ impl StorageEngine {
 // Automatikus bezárás
 impl Drop for StorageEngine {
     fn drop(&mut self) {
-        let _ = self.flush();
+        if !self.catalog_incomplete {
+            let _ = self.flush();
+        }
     }
 }
 
@@ -951,9 +1139,30 @@ impl RawStorage for StorageEngine {
         StorageEngine::read_data(self, offset)
     }
 
+    fn read_data_with(
+        &mut self,
+        offset: u64,
+        parse: &mut dyn FnMut(&mut dyn std::io::Read) -> Result<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        StorageEngine::read_data_with(self, offset, parse)
+    }
+
     fn file_len(&self) -> Result<u64> {
         StorageEngine::file_len(self)
     }
+
+    fn layout_generation(&self) -> u64 {
+        self.layout_generation
+    }
+
+    fn overwrite_document_in_place(
+        &mut self,
+        collection: &str,
+        doc_id: &DocumentId,
+        data: &[u8],
+    ) -> Result<bool> {
+        StorageEngine::overwrite_document_in_place(self, collection, doc_id, data)
+    }
 }
 
 #[cfg(test)]
@@ -1220,6 +1429,27 @@ mod tests {
         assert_eq!(collections.len(), 2);
     }
 
+    #[test]
+    fn test_collection_size_bytes() {
+        let (_temp, mut storage) = setup_test_db();
+        storage.create_collection("users").unwrap();
+
+        assert_eq!(storage.collection_size_bytes("users").unwrap(), 0);
+
+        let doc_a = br#"{"_id":1,"name":"a"}"#;
+        let doc_b = br#"{"_id":2,"name":"bb"}"#;
+        storage
+            .write_document("users", &DocumentId::Int(1), doc_a)
+            .unwrap();
+        storage
+            .write_document("users", &DocumentId::Int(2), doc_b)
+            .unwrap();
+
+        let expected = (4 + doc_a.len() + 4 + doc_b.len()) as u64;
+        assert_eq!(storage.collection_size_bytes("users").unwrap(), expected);
+        assert!(storage.collection_size_bytes("missing").is_err());
+    }
+
     #[test]
     fn test_file_len() {
         let (_temp, mut storage) = setup_test_db();
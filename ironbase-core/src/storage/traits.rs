@@ -240,6 +240,19 @@ pub(crate) trait RawStorage: Storage + sealed_raw::SealedRawStorage {
     /// Raw bytes
     fn read_data(&mut self, offset: u64) -> Result<Vec<u8>>;
 
+    /// Parse the raw data at offset with a streaming parser
+    ///
+    /// File-backed storage feeds the parser from disk so a large record is
+    /// never held in memory whole; the default reads the bytes first.
+    fn read_data_with(
+        &mut self,
+        offset: u64,
+        parse: &mut dyn FnMut(&mut dyn std::io::Read) -> Result<Value>,
+    ) -> Result<Value> {
+        let data = self.read_data(offset)?;
+        parse(&mut data.as_slice())
+    }
+
     /// Get current file length
     ///
     /// # Returns
@@ -247,6 +260,30 @@ pub(crate) trait RawStorage: Storage + sealed_raw::SealedRawStorage {
     /// Total file size in bytes
     #[allow(dead_code)] // Used by compaction internally
     fn file_len(&self) -> Result<u64>;
+
+    /// Counter bumped whenever existing document offsets are invalidated
+    ///
+    /// Compaction rewrites the data file, so an offset seen before it may point
+    /// at a different document afterwards; an in-place overwrite changes the
+    /// bytes behind an unchanged offset. Backends that never relocate or
+    /// overwrite documents can keep the default.
+    fn layout_generation(&self) -> u64 {
+        0
+    }
+
+    /// Overwrite the current version of a document in its existing slot
+    ///
+    /// Returns `false` without writing anything when the backend doesn't
+    /// support it or the new bytes don't fit; the caller then falls back to
+    /// appending a new version plus a tombstone.
+    fn overwrite_document_in_place(
+        &mut self,
+        _collection: &str,
+        _doc_id: &DocumentId,
+        _data: &[u8],
+    ) -> Result<bool> {
+        Ok(false)
+    }
 }
 
 // ============================================================================
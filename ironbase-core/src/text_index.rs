@@ -0,0 +1,327 @@
+// ironbase-core/src/text_index.rs
+// Inverted index over string fields for relevance-ranked full-text search
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::document::{Document, DocumentId};
+use crate::error::{MongoLiteError, Result};
+use crate::index::IndexKey;
+
+/// BM25 term-frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document-length normalization
+const B: f64 = 0.75;
+
+/// How indexed text and search queries are split into terms
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Tokenizer {
+    /// Runs of letters and digits, lowercased ("Fast-charging" → "fast", "charging")
+    #[default]
+    Standard,
+    /// Whitespace-separated words, lowercased, punctuation kept
+    Whitespace,
+}
+
+impl Tokenizer {
+    /// Split `text` into lowercased terms, in order and with repeats
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let words: Box<dyn Iterator<Item = &str>> = match self {
+            Tokenizer::Standard => Box::new(text.split(|c: char| !c.is_alphanumeric())),
+            Tokenizer::Whitespace => Box::new(text.split_whitespace()),
+        };
+        words
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+}
+
+impl FromStr for Tokenizer {
+    type Err = MongoLiteError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "standard" => Ok(Tokenizer::Standard),
+            "whitespace" => Ok(Tokenizer::Whitespace),
+            _ => Err(MongoLiteError::IndexError(format!(
+                "Unknown tokenizer: {} (expected \"standard\" or \"whitespace\")",
+                name
+            ))),
+        }
+    }
+}
+
+/// Whether a search needs every query term or any of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextSearchMode {
+    /// Documents containing every term
+    #[default]
+    And,
+    /// Documents containing at least one term
+    Or,
+}
+
+impl FromStr for TextSearchMode {
+    type Err = MongoLiteError;
+
+    fn from_str(mode: &str) -> Result<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "and" => Ok(TextSearchMode::And),
+            "or" => Ok(TextSearchMode::Or),
+            _ => Err(MongoLiteError::InvalidQuery(format!(
+                "Unknown text search mode: {} (expected \"and\" or \"or\")",
+                mode
+            ))),
+        }
+    }
+}
+
+/// Persisted definition of a collection's text index
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TextIndexMetadata {
+    pub name: String,
+    /// Fields whose strings (or arrays of strings) are indexed; dot notation allowed
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+}
+
+/// In-memory inverted index with BM25 relevance scores
+///
+/// Only the definition is persisted; like the B+ tree indexes, the postings
+/// are rebuilt from the documents whenever a collection handle is opened.
+#[derive(Debug, Clone)]
+pub struct TextIndex {
+    pub metadata: TextIndexMetadata,
+    /// Term -> occurrences per document
+    postings: HashMap<String, HashMap<DocumentId, u32>>,
+    /// Length in terms and distinct terms with their counts, per document
+    doc_terms: HashMap<DocumentId, (u32, Vec<(String, u32)>)>,
+    /// Terms indexed across all documents, for the average document length
+    total_terms: u64,
+}
+
+impl TextIndex {
+    pub fn new(metadata: TextIndexMetadata) -> Self {
+        TextIndex {
+            metadata,
+            postings: HashMap::new(),
+            doc_terms: HashMap::new(),
+            total_terms: 0,
+        }
+    }
+
+    /// Number of documents with at least one indexed term
+    pub fn len(&self) -> usize {
+        self.doc_terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_terms.is_empty()
+    }
+
+    /// Index the text fields of `doc`, replacing what was indexed for its `_id`
+    pub fn insert(&mut self, doc: &Document) {
+        self.remove(&doc.id);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for field in &self.metadata.fields {
+            for value in doc.get_all(field) {
+                for text in strings_of(value) {
+                    for term in self.metadata.tokenizer.tokenize(text) {
+                        *counts.entry(term).or_default() += 1;
+                    }
+                }
+            }
+        }
+        if counts.is_empty() {
+            return;
+        }
+
+        let mut length = 0;
+        for (term, &count) in &counts {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(doc.id.clone(), count);
+            length += count;
+        }
+        self.total_terms += length as u64;
+        self.doc_terms
+            .insert(doc.id.clone(), (length, counts.into_iter().collect()));
+    }
+
+    /// Drop everything indexed for `doc_id`
+    pub fn remove(&mut self, doc_id: &DocumentId) {
+        let Some((length, terms)) = self.doc_terms.remove(doc_id) else {
+            return;
+        };
+        for (term, _) in terms {
+            if let Some(docs) = self.postings.get_mut(&term) {
+                docs.remove(doc_id);
+                if docs.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        self.total_terms -= length as u64;
+    }
+
+    /// Documents matching `query`, best first, with their BM25 scores
+    ///
+    /// The query goes through the index's tokenizer; repeated terms count
+    /// once. Equal scores are ordered by `_id`.
+    pub fn search(&self, query: &str, mode: TextSearchMode) -> Vec<(DocumentId, f64)> {
+        let mut terms = self.metadata.tokenizer.tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let lists: Vec<&HashMap<DocumentId, u32>> = terms
+            .iter()
+            .filter_map(|term| self.postings.get(term))
+            .collect();
+        if lists.is_empty() || (mode == TextSearchMode::And && lists.len() < terms.len()) {
+            return Vec::new();
+        }
+
+        let documents = self.doc_terms.len() as f64;
+        let average_length = self.total_terms as f64 / documents;
+        let mut scores: HashMap<&DocumentId, (f64, usize)> = HashMap::new();
+        for docs in &lists {
+            let frequency = docs.len() as f64;
+            let idf = (1.0 + (documents - frequency + 0.5) / (frequency + 0.5)).ln();
+            for (doc_id, &count) in docs.iter() {
+                let length = self.doc_terms[doc_id].0 as f64;
+                let tf = count as f64;
+                let norm = K1 * (1.0 - B + B * length / average_length);
+                let entry = scores.entry(doc_id).or_default();
+                entry.0 += idf * tf * (K1 + 1.0) / (tf + norm);
+                entry.1 += 1;
+            }
+        }
+
+        let mut hits: Vec<(DocumentId, f64)> = scores
+            .into_iter()
+            .filter(|(_, (_, matched))| mode == TextSearchMode::Or || *matched == terms.len())
+            .map(|(doc_id, (score, _))| (doc_id.clone(), score))
+            .collect();
+        hits.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| id_key(&a.0).cmp(&id_key(&b.0)))
+        });
+        hits
+    }
+}
+
+/// A `$text` filter: `{"$search": "terms", "$mode": "or"}`
+///
+/// `$mode` is optional and defaults to "or", matching documents with any of
+/// the terms as MongoDB does.
+pub(crate) fn parse_text_filter(filter: &Value) -> Result<(String, TextSearchMode)> {
+    let search = filter
+        .get("$search")
+        .and_then(Value::as_str)
+        .ok_or_else(|| MongoLiteError::InvalidQuery("$text requires a $search string".into()))?;
+    let mode = match filter.get("$mode") {
+        None => TextSearchMode::Or,
+        Some(Value::String(mode)) => mode.parse()?,
+        Some(other) => {
+            return Err(MongoLiteError::InvalidQuery(format!(
+                "$text $mode must be a string, got {}",
+                other
+            )))
+        }
+    };
+    Ok((search.to_string(), mode))
+}
+
+/// Strings of an indexed value: the value itself or the strings of an array
+fn strings_of(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(text) => vec![text],
+        Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn id_key(doc_id: &DocumentId) -> IndexKey {
+    match doc_id {
+        DocumentId::Int(i) => IndexKey::Int(*i),
+        DocumentId::String(s) | DocumentId::ObjectId(s) => IndexKey::String(s.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn index_of(docs: &[(i64, &str)]) -> TextIndex {
+        let mut index = TextIndex::new(TextIndexMetadata {
+            name: "items_text".to_string(),
+            fields: vec!["description".to_string()],
+            tokenizer: Tokenizer::Standard,
+        });
+        for (id, text) in docs {
+            index.insert(&Document::from_value(&json!({"_id": id, "description": text})).unwrap());
+        }
+        index
+    }
+
+    #[test]
+    fn test_tokenizers() {
+        assert_eq!(
+            Tokenizer::Standard.tokenize("Fast-charging, USB-C!"),
+            vec!["fast", "charging", "usb", "c"]
+        );
+        assert_eq!(
+            Tokenizer::Whitespace.tokenize("Fast-charging, USB-C!"),
+            vec!["fast-charging,", "usb-c!"]
+        );
+        assert!("ngram".parse::<Tokenizer>().is_err());
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters_by_mode() {
+        let mut index = index_of(&[
+            (1, "red apple"),
+            (2, "red red apple pie with a long description of the pie"),
+            (3, "green apple"),
+            (4, "red car"),
+        ]);
+
+        let and: Vec<i64> = index
+            .search("Apple RED", TextSearchMode::And)
+            .into_iter()
+            .map(|(id, _)| match id {
+                DocumentId::Int(i) => i,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(and, vec![1, 2]);
+        assert_eq!(index.search("red apple", TextSearchMode::Or).len(), 4);
+        assert!(index.search("apple banana", TextSearchMode::And).is_empty());
+        assert!(index.search("", TextSearchMode::Or).is_empty());
+
+        // Removing and re-inserting keeps postings consistent
+        index.remove(&DocumentId::Int(1));
+        assert_eq!(index.search("red apple", TextSearchMode::And).len(), 1);
+        index.insert(&Document::from_value(&json!({"_id": 2, "description": "blue"})).unwrap());
+        assert!(index.search("red apple", TextSearchMode::And).is_empty());
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_text_filter() {
+        let (search, mode) = parse_text_filter(&json!({"$search": "a b"})).unwrap();
+        assert_eq!((search.as_str(), mode), ("a b", TextSearchMode::Or));
+        let (_, mode) = parse_text_filter(&json!({"$search": "a", "$mode": "AND"})).unwrap();
+        assert_eq!(mode, TextSearchMode::And);
+        assert!(parse_text_filter(&json!({"$search": 1})).is_err());
+        assert!(parse_text_filter(&json!({"$search": "a", "$mode": "xor"})).is_err());
+    }
+}
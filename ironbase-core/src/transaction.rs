@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use crate::decimal::{decimal_text, Decimal};
 use crate::document::DocumentId;
 use crate::error::{MongoLiteError, Result};
 
@@ -69,6 +70,10 @@ pub enum IndexKey {
     Float(OrderedFloat),
     Bool(bool),
     Null,
+    /// Key of a compound index, one component per indexed field
+    Compound(Vec<IndexKey>),
+    /// Tagged decimal that isn't an exact `i64`
+    Decimal(Decimal),
 }
 
 /// Ordered float wrapper for IndexKey
@@ -123,6 +128,12 @@ impl From<&Value> for IndexKey {
             Value::String(s) => IndexKey::String(s.clone()),
             Value::Bool(b) => IndexKey::Bool(*b),
             Value::Null => IndexKey::Null,
+            Value::Object(_) => {
+                match decimal_text(value).and_then(|text| text.parse::<Decimal>().ok()) {
+                    Some(d) => d.to_i64().map_or(IndexKey::Decimal(d), IndexKey::Int),
+                    None => IndexKey::Null,
+                }
+            }
             _ => IndexKey::Null, // Arrays and objects as null for now
         }
     }
@@ -5,6 +5,7 @@
 mod integration_tests {
     use crate::database::DatabaseCore;
     use crate::document::DocumentId;
+    use crate::error::MongoLiteError;
     use crate::transaction::Operation;
     use serde_json::json;
     use tempfile::TempDir;
@@ -403,4 +404,265 @@ mod integration_tests {
             db.commit_transaction(tx).unwrap();
         }
     }
+
+    #[test]
+    fn test_read_committed_visibility() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+
+        let mut doc = std::collections::HashMap::new();
+        doc.insert("name".to_string(), json!("alice"));
+        doc.insert("balance".to_string(), json!(10));
+        db.insert_one("accounts", doc).unwrap();
+
+        let writer = db.begin_transaction();
+        let reader = db.begin_transaction();
+
+        db.update_one_tx(
+            "accounts",
+            &json!({"name": "alice"}),
+            json!({"name": "alice", "balance": 99}),
+            writer,
+        )
+        .unwrap();
+        let mut new_doc = std::collections::HashMap::new();
+        new_doc.insert("name".to_string(), json!("bob"));
+        new_doc.insert("balance".to_string(), json!(5));
+        db.insert_one_tx("accounts", new_doc, writer).unwrap();
+
+        // The writer sees its own writes
+        let own = db.find_tx("accounts", &json!({}), writer).unwrap();
+        assert_eq!(own.len(), 2);
+        let alice = db
+            .find_one_tx("accounts", &json!({"name": "alice"}), writer)
+            .unwrap()
+            .unwrap();
+        assert_eq!(alice["balance"], 99);
+        assert!(db
+            .find_one_tx("accounts", &json!({"balance": 10}), writer)
+            .unwrap()
+            .is_none());
+
+        // The reader sees only committed data
+        let other = db.find_tx("accounts", &json!({}), reader).unwrap();
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0]["balance"], 10);
+
+        db.commit_transaction(writer).unwrap();
+
+        // After the commit the reader sees it in full
+        let other = db.find_tx("accounts", &json!({}), reader).unwrap();
+        assert_eq!(other.len(), 2);
+        let alice = db
+            .find_one_tx("accounts", &json!({"name": "alice"}), reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(alice["balance"], 99);
+
+        // A delete buffered in the reader hides the document only for the reader
+        db.delete_one_tx("accounts", &json!({"name": "bob"}), reader)
+            .unwrap();
+        assert_eq!(db.find_tx("accounts", &json!({}), reader).unwrap().len(), 1);
+        let third = db.begin_transaction();
+        assert_eq!(db.find_tx("accounts", &json!({}), third).unwrap().len(), 2);
+        db.rollback_transaction(reader).unwrap();
+        db.rollback_transaction(third).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_transactions_never_see_partial_commits() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        const TOTAL: i64 = 1000;
+        const ACCOUNTS: usize = 32;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = Arc::new(DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap());
+        for i in 0..ACCOUNTS {
+            let mut doc = std::collections::HashMap::new();
+            doc.insert("account".to_string(), json!(i));
+            let balance = if i == 0 { TOTAL } else { 0 };
+            doc.insert("balance".to_string(), json!(balance));
+            db.insert_one("bank", doc).unwrap();
+        }
+
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Writer: each transaction moves money between every pair of
+        // neighbouring accounts, touching all of them in one commit
+        let writer = {
+            let db = Arc::clone(&db);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                for round in 0..30i64 {
+                    let tx = db.begin_transaction();
+                    let mut docs = db.find_tx("bank", &json!({}), tx).unwrap();
+                    docs.sort_by_key(|d| d["account"].as_i64().unwrap());
+                    let mut balances: Vec<i64> = docs
+                        .iter()
+                        .map(|d| d["balance"].as_i64().unwrap())
+                        .collect();
+                    for i in 0..ACCOUNTS - 1 {
+                        let amount = balances[i].min(round + 1);
+                        balances[i] -= amount;
+                        balances[i + 1] += amount;
+                    }
+                    for (i, balance) in balances.iter().enumerate() {
+                        db.update_one_tx(
+                            "bank",
+                            &json!({"account": i}),
+                            json!({"account": i, "balance": balance}),
+                            tx,
+                        )
+                        .unwrap();
+                    }
+
+                    // Mid-transaction the total is intact from the writer's own view
+                    let own: i64 = db
+                        .find_tx("bank", &json!({}), tx)
+                        .unwrap()
+                        .iter()
+                        .map(|d| d["balance"].as_i64().unwrap())
+                        .sum();
+                    assert_eq!(own, TOTAL);
+
+                    db.commit_transaction(tx).unwrap();
+                }
+                done.store(true, Ordering::SeqCst);
+            })
+        };
+
+        // Readers: any uncommitted or half-applied transfer would break the total
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                let done = Arc::clone(&done);
+                thread::spawn(move || {
+                    let mut observations = 0;
+                    while !done.load(Ordering::SeqCst) || observations == 0 {
+                        let tx = db.begin_transaction();
+                        // A filtered query collects ids first, then reads each document
+                        let docs = db
+                            .find_tx("bank", &json!({"balance": {"$gte": 0}}), tx)
+                            .unwrap();
+                        assert_eq!(docs.len(), ACCOUNTS);
+                        let total: i64 = docs.iter().map(|d| d["balance"].as_i64().unwrap()).sum();
+                        assert_eq!(total, TOTAL, "observed a partial commit: {:?}", docs);
+                        db.rollback_transaction(tx).unwrap();
+                        observations += 1;
+                    }
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_lock_for_update_prevents_lost_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+
+        let mut doc = std::collections::HashMap::new();
+        doc.insert("name".to_string(), json!("counter"));
+        doc.insert("value".to_string(), json!(0));
+        let id = db.insert_one("counters", doc).unwrap();
+
+        // Both transactions try to increment the same counter
+        let first = db.begin_transaction();
+        let second = db.begin_transaction();
+        let increment = |tx| {
+            db.lock_for_update(tx, "counters", &id)?;
+            let current = db
+                .find_one_tx("counters", &json!({"_id": id}), tx)?
+                .unwrap();
+            let value = current["value"].as_i64().unwrap() + 1;
+            db.update_one_tx(
+                "counters",
+                &json!({"_id": id}),
+                json!({"name": "counter", "value": value}),
+                tx,
+            )
+        };
+
+        increment(first).unwrap();
+        // Re-locking a document the transaction already holds is fine
+        db.lock_for_update(first, "counters", &id).unwrap();
+        assert!(matches!(
+            increment(second),
+            Err(MongoLiteError::WriteConflict(_))
+        ));
+
+        // A write to the locked document without locking fails at commit
+        db.update_one_tx(
+            "counters",
+            &json!({"_id": id}),
+            json!({"name": "counter", "value": 100}),
+            second,
+        )
+        .unwrap();
+        assert!(matches!(
+            db.commit_transaction(second),
+            Err(MongoLiteError::WriteConflict(_))
+        ));
+        assert!(db.get_transaction(second).is_none());
+
+        db.commit_transaction(first).unwrap();
+        let stored = db
+            .collection("counters")
+            .unwrap()
+            .find_by_id(&id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored["value"], 1);
+
+        // Commit released the lock
+        let third = db.begin_transaction();
+        db.lock_for_update(third, "counters", &id).unwrap();
+        db.rollback_transaction(third).unwrap();
+        let fourth = db.begin_transaction();
+        db.lock_for_update(fourth, "counters", &id).unwrap();
+        db.commit_transaction(fourth).unwrap();
+    }
+
+    #[test]
+    fn test_lock_for_update_does_not_block_auto_commit_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+
+        let mut doc = std::collections::HashMap::new();
+        doc.insert("value".to_string(), json!(0));
+        let id = db.insert_one("counters", doc).unwrap();
+
+        let tx = db.begin_transaction();
+        db.lock_for_update(tx, "counters", &id).unwrap();
+
+        // Documented limitation: only transactional writes honour the lock
+        db.update_one(
+            "counters",
+            &json!({"_id": id}),
+            &json!({"$set": {"value": 5}}),
+        )
+        .unwrap();
+        let stored = db
+            .collection("counters")
+            .unwrap()
+            .find_by_id(&id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored["value"], 5);
+
+        // The lock itself is still held against other transactions
+        let other = db.begin_transaction();
+        assert!(matches!(
+            db.lock_for_update(other, "counters", &id),
+            Err(MongoLiteError::WriteConflict(_))
+        ));
+        db.rollback_transaction(other).unwrap();
+        db.commit_transaction(tx).unwrap();
+    }
 }
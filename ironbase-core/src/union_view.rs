@@ -0,0 +1,56 @@
+// ironbase-core/src/union_view.rs
+// Read-only view that queries several collections as one
+
+use crate::collection_core::CollectionCore;
+use crate::error::Result;
+use crate::storage::{RawStorage, Storage};
+use serde_json::Value;
+
+/// Merge-on-read view over several collections
+///
+/// Created with `DatabaseCore::union_view`. Every query runs against each
+/// member collection in turn through its own `find`/`count_documents`, so
+/// each member still uses its own indexes. Results carry their source in
+/// `_collection`. The view has no write methods.
+pub struct UnionView<S: Storage + RawStorage> {
+    collections: Vec<CollectionCore<S>>,
+}
+
+impl<S: Storage + RawStorage> UnionView<S> {
+    pub(crate) fn new(collections: Vec<CollectionCore<S>>) -> Self {
+        UnionView { collections }
+    }
+
+    /// Member collection names, in the order results are returned
+    pub fn collection_names(&self) -> Vec<String> {
+        self.collections.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// Documents matching `query` in any member collection
+    ///
+    /// Results are grouped by collection in view order; each document's
+    /// `_collection` field names the collection it came from.
+    pub fn find(&self, query_json: &Value) -> Result<Vec<Value>> {
+        let mut results = Vec::new();
+        for collection in &self.collections {
+            for mut doc in collection.find(query_json)? {
+                if let Value::Object(map) = &mut doc {
+                    map.insert(
+                        "_collection".to_string(),
+                        Value::String(collection.name.clone()),
+                    );
+                }
+                results.push(doc);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Total number of matching documents across member collections
+    pub fn count_documents(&self, query_json: &Value) -> Result<u64> {
+        self.collections
+            .iter()
+            .map(|collection| collection.count_documents(query_json))
+            .sum()
+    }
+}
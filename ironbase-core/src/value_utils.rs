@@ -3,6 +3,7 @@
 //! This module provides common functions for working with JSON values,
 //! including nested field access and value comparison.
 
+use crate::decimal::{compare_decimal, is_decimal};
 use serde_json::Value;
 use std::cmp::Ordering;
 
@@ -46,6 +47,79 @@ pub fn get_nested_value<'a>(doc: &'a Value, path: &str) -> Option<&'a Value> {
     Some(value)
 }
 
+/// Get all values reached by a dotted path, descending into arrays
+///
+/// MongoDB-style implicit array traversal: when a path segment meets an
+/// array, numeric segments index into it and other segments are looked up
+/// in every element, so `items.price` reaches the price of each item.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use ironbase_core::value_utils::get_all_nested_values;
+///
+/// let doc = json!({"items": [{"price": 5}, {"price": 150}]});
+/// assert_eq!(get_all_nested_values(&doc, "items.price"), vec![&json!(5), &json!(150)]);
+/// ```
+pub fn get_all_nested_values<'a>(doc: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut results = Vec::new();
+    if path.is_empty() {
+        return results;
+    }
+    let parts: Vec<&str> = path.split('.').collect();
+    if let Some(initial) = doc.get(parts[0]) {
+        collect_path_values(initial, &parts[1..], &mut results);
+    }
+    results
+}
+
+/// Recursive helper for implicit array traversal (see `get_all_nested_values`)
+pub(crate) fn collect_path_values<'a>(
+    value: &'a Value,
+    remaining_path: &[&str],
+    results: &mut Vec<&'a Value>,
+) {
+    if remaining_path.is_empty() {
+        results.push(value);
+        return;
+    }
+
+    let next_part = remaining_path[0];
+    let rest = &remaining_path[1..];
+
+    match value {
+        Value::Object(map) => {
+            if let Some(next_value) = map.get(next_part) {
+                collect_path_values(next_value, rest, results);
+            }
+        }
+        Value::Array(arr) => {
+            // Try numeric index first (explicit array access like "items.0.name")
+            if let Ok(index) = next_part.parse::<usize>() {
+                if let Some(elem) = arr.get(index) {
+                    collect_path_values(elem, rest, results);
+                }
+            } else {
+                // MongoDB-style implicit array traversal:
+                // Iterate all elements looking for the field
+                for elem in arr {
+                    if let Value::Object(map) = elem {
+                        if let Some(next_value) = map.get(next_part) {
+                            collect_path_values(next_value, rest, results);
+                        }
+                    }
+                    // Also handle nested arrays (array of arrays)
+                    if let Value::Array(_) = elem {
+                        collect_path_values(elem, remaining_path, results);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Set a value at a nested path with dot notation support
 ///
 /// Creates intermediate objects if they don't exist.
@@ -102,7 +176,8 @@ pub fn set_nested_value(doc: &mut Value, path: &str, value: Value) {
 ///
 /// # Supported comparisons
 ///
-/// - Number vs Number (uses f64 comparison)
+/// - Number vs Number (exact for integers, f64 otherwise)
+/// - Tagged decimal vs Number or decimal (exact, see `crate::decimal`)
 /// - String vs String (lexicographic)
 /// - Bool vs Bool (false < true)
 ///
@@ -119,11 +194,8 @@ pub fn set_nested_value(doc: &mut Value, path: &str, value: Value) {
 /// ```
 pub fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
     match (a, b) {
-        (Value::Number(n1), Value::Number(n2)) => {
-            let f1 = n1.as_f64()?;
-            let f2 = n2.as_f64()?;
-            f1.partial_cmp(&f2)
-        }
+        (Value::Number(n1), Value::Number(n2)) => compare_numbers(n1, n2),
+        (Value::Object(_), _) | (_, Value::Object(_)) => compare_decimal(a, b),
         (Value::String(s1), Value::String(s2)) => Some(s1.cmp(s2)),
         (Value::Bool(b1), Value::Bool(b2)) => Some(b1.cmp(b2)),
         _ => None,
@@ -160,6 +232,72 @@ pub fn compare_values_with_none(a: Option<&Value>, b: Option<&Value>) -> Orderin
     }
 }
 
+/// Total order used by `$sort` and `find` sort options
+///
+/// A missing field (`None`) sorts before an explicit `null`, which sorts
+/// before every other value; across types the order is
+/// missing < null < number < string < bool < object < array, with tagged
+/// decimals (`crate::decimal`) ordered among the numbers. Within a type
+/// numbers, strings and bools compare by value; objects and arrays compare
+/// equal to each other, so a stable sort keeps their input order. Descending
+/// sorts reverse the whole order, putting missing fields last.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use std::cmp::Ordering;
+/// use ironbase_core::value_utils::compare_for_sort;
+///
+/// assert_eq!(compare_for_sort(None, Some(&json!(null))), Ordering::Less);
+/// assert_eq!(compare_for_sort(Some(&json!(null)), Some(&json!(0))), Ordering::Less);
+/// assert_eq!(compare_for_sort(Some(&json!(1)), Some(&json!("a"))), Ordering::Less);
+/// ```
+pub fn compare_for_sort(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(Value::Number(n1)), Some(Value::Number(n2))) => {
+            compare_numbers(n1, n2).unwrap_or(Ordering::Equal)
+        }
+        (Some(Value::String(s1)), Some(Value::String(s2))) => s1.cmp(s2),
+        (Some(Value::Bool(b1)), Some(Value::Bool(b2))) => b1.cmp(b2),
+        (Some(a), Some(b)) => {
+            compare_decimal(a, b).unwrap_or_else(|| sort_type_rank(a).cmp(&sort_type_rank(b)))
+        }
+    }
+}
+
+/// Compare two JSON numbers, exactly when both are integers
+///
+/// Integers beyond 2^53 lose precision as f64, so `u64::MAX` and
+/// `u64::MAX - 1` would otherwise compare equal.
+fn compare_numbers(n1: &serde_json::Number, n2: &serde_json::Number) -> Option<Ordering> {
+    let as_int = |n: &serde_json::Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    match (as_int(n1), as_int(n2)) {
+        (Some(i1), Some(i2)) => Some(i1.cmp(&i2)),
+        _ => n1.as_f64()?.partial_cmp(&n2.as_f64()?),
+    }
+}
+
+fn sort_type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Number(_) => 1,
+        // Tagged decimals sort among the numbers
+        Value::Object(_) if is_decimal(value) => 1,
+        Value::String(_) => 2,
+        Value::Bool(_) => 3,
+        Value::Object(_) => 4,
+        Value::Array(_) => 5,
+    }
+}
+
 /// Creates a canonical string representation of a JSON value
 /// where object keys are always sorted alphabetically.
 ///
@@ -208,11 +346,186 @@ pub fn canonical_json_string(value: &Value) -> String {
     }
 }
 
+/// Serialized size above which output defaults to compact JSON (64 KiB)
+pub const COMPACT_OUTPUT_THRESHOLD: usize = 64 * 1024;
+
+/// Serialize `value` for display or export
+///
+/// `Some(true)` pretty-prints, `Some(false)` writes compact JSON. `None`
+/// pretty-prints small values only: once the compact form exceeds
+/// `COMPACT_OUTPUT_THRESHOLD` bytes, the indentation would mostly add size
+/// and serialization time, so the compact form is returned. Both forms
+/// parse back to the same value.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+/// use ironbase_core::value_utils::to_json_output;
+///
+/// let v = json!({"a": [1, 2]});
+/// assert_eq!(to_json_output(&v, Some(false)).unwrap(), r#"{"a":[1,2]}"#);
+/// assert!(to_json_output(&v, None).unwrap().contains('\n'));
+/// ```
+pub fn to_json_output<T: serde::Serialize + ?Sized>(
+    value: &T,
+    pretty: Option<bool>,
+) -> serde_json::Result<String> {
+    match pretty {
+        Some(true) => serde_json::to_string_pretty(value),
+        Some(false) => serde_json::to_string(value),
+        None => {
+            let compact = serde_json::to_string(value)?;
+            if compact.len() > COMPACT_OUTPUT_THRESHOLD {
+                Ok(compact)
+            } else {
+                serde_json::to_string_pretty(value)
+            }
+        }
+    }
+}
+
+/// Check JSON text for objects that repeat a key
+///
+/// Parsing into a `Value` keeps only the last of repeated keys, which can
+/// hide mistakes in imported data. The error names the first repeated key
+/// with its path and position; malformed JSON fails the same way it would
+/// when parsed.
+///
+/// # Examples
+///
+/// ```
+/// use ironbase_core::value_utils::check_duplicate_keys;
+///
+/// assert!(check_duplicate_keys(r#"{"a": 1, "b": {"a": 2}}"#).is_ok());
+/// let err = check_duplicate_keys(r#"{"users": [{"name": "x", "name": "y"}]}"#).unwrap_err();
+/// assert!(err.to_string().contains("duplicate key 'name' at users[0]"));
+/// ```
+pub fn check_duplicate_keys(json: &str) -> serde_json::Result<()> {
+    use serde::de::DeserializeSeed;
+
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    DuplicateKeyCheck {
+        path: String::new(),
+    }
+    .deserialize(&mut deserializer)?;
+    deserializer.end()
+}
+
+/// Walks a JSON document without building it, remembering where it is
+struct DuplicateKeyCheck {
+    path: String,
+}
+
+impl DuplicateKeyCheck {
+    fn child(&self, step: &str) -> Self {
+        let path = if self.path.is_empty() || step.starts_with('[') {
+            format!("{}{}", self.path, step)
+        } else {
+            format!("{}.{}", self.path, step)
+        };
+        Self { path }
+    }
+}
+
+impl<'de> serde::de::DeserializeSeed<'de> for DuplicateKeyCheck {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for DuplicateKeyCheck {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = std::collections::HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if seen.contains(&key) {
+                let at = if self.path.is_empty() {
+                    "top level"
+                } else {
+                    &self.path
+                };
+                return Err(serde::de::Error::custom(format!(
+                    "duplicate key '{}' at {}",
+                    key, at
+                )));
+            }
+            map.next_value_seed(self.child(&key))?;
+            seen.insert(key);
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while seq
+            .next_element_seed(self.child(&format!("[{}]", index)))?
+            .is_some()
+        {
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_check_duplicate_keys() {
+        assert!(check_duplicate_keys(r#"{"a": 1, "b": [{"a": 1}, {"a": 2}]}"#).is_ok());
+
+        let err = check_duplicate_keys(r#"{"a": 1, "a": 2}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("duplicate key 'a' at top level"),
+            "{}",
+            err
+        );
+        let err = check_duplicate_keys(r#"{"c": [{"x": {"k": 1, "k": 1}}]}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("duplicate key 'k' at c[0].x"),
+            "{}",
+            err
+        );
+
+        // Malformed JSON still fails like a normal parse
+        assert!(check_duplicate_keys(r#"{"a": 1"#).is_err());
+        assert!(check_duplicate_keys(r#"{"a": 1} x"#).is_err());
+    }
+
     #[test]
     fn test_get_nested_value_simple() {
         let doc = json!({"name": "Alice", "age": 30});
@@ -281,6 +594,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compare_values_exact_numbers() {
+        assert_eq!(
+            compare_values(&json!(u64::MAX), &json!(u64::MAX - 1)),
+            Some(Ordering::Greater)
+        );
+        let big = json!({"$numberDecimal": "18446744073709551616"});
+        assert_eq!(
+            compare_values(&big, &json!(u64::MAX)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_values(&json!(0.5), &json!({"$numberDecimal": "0.50"})),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(compare_values(&big, &json!("a")), None);
+
+        let mut values = vec![json!("a"), big.clone(), json!(3), json!(null)];
+        values.sort_by(|a, b| compare_for_sort(Some(a), Some(b)));
+        assert_eq!(values, vec![json!(null), json!(3), big, json!("a")]);
+    }
+
     #[test]
     fn test_compare_values_strings() {
         assert_eq!(
@@ -442,4 +777,25 @@ mod tests {
         });
         assert_eq!(canonical_json_string(&v1), canonical_json_string(&v2));
     }
+
+    #[test]
+    fn test_to_json_output_styles() {
+        let small = json!({"name": "a", "tags": [1, 2, {"x": null}]});
+        let pretty = to_json_output(&small, Some(true)).unwrap();
+        let compact = to_json_output(&small, Some(false)).unwrap();
+        assert!(pretty.contains('\n') && !compact.contains('\n'));
+        assert!(compact.len() < pretty.len());
+        assert_eq!(serde_json::from_str::<Value>(&pretty).unwrap(), small);
+        assert_eq!(serde_json::from_str::<Value>(&compact).unwrap(), small);
+        assert_eq!(to_json_output(&small, None).unwrap(), pretty);
+
+        // Auto mode switches to compact above the threshold
+        let docs: Vec<Value> = (0..5000).map(|i| json!({"i": i, "s": "xxxx"})).collect();
+        let large = Value::Array(docs);
+        let auto = to_json_output(&large, None).unwrap();
+        assert!(auto.len() > COMPACT_OUTPUT_THRESHOLD);
+        assert_eq!(auto, serde_json::to_string(&large).unwrap());
+        assert_eq!(serde_json::from_str::<Value>(&auto).unwrap(), large);
+        assert!(to_json_output(&large, Some(true)).unwrap().contains('\n'));
+    }
 }
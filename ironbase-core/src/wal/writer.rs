@@ -17,6 +17,8 @@ use super::reader::WALEntryIterator;
 pub struct WriteAheadLog {
     file: File,
     path: PathBuf,
+    /// Entries appended since the WAL was opened, cleared or checkpointed
+    entries_since_checkpoint: u64,
 }
 
 impl WriteAheadLog {
@@ -30,7 +32,11 @@ impl WriteAheadLog {
             .append(true)
             .open(&path)?;
 
-        Ok(WriteAheadLog { file, path })
+        Ok(WriteAheadLog {
+            file,
+            path,
+            entries_since_checkpoint: 0,
+        })
     }
 
     /// Get the path to this WAL file
@@ -38,11 +44,24 @@ impl WriteAheadLog {
         &self.path
     }
 
+    /// Current size of the WAL file in bytes
+    pub fn size_bytes(&self) -> Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Entries appended since the WAL was opened, cleared or checkpointed
+    ///
+    /// After a `checkpoint` this counts the uncommitted entries it kept.
+    pub fn entries_since_checkpoint(&self) -> u64 {
+        self.entries_since_checkpoint
+    }
+
     /// Append an entry to the WAL
     pub fn append(&mut self, entry: &WALEntry) -> Result<u64> {
         let serialized = entry.serialize();
         let offset = self.file.seek(SeekFrom::End(0))?;
         self.file.write_all(&serialized)?;
+        self.entries_since_checkpoint += 1;
         Ok(offset)
     }
 
@@ -94,6 +113,7 @@ impl WriteAheadLog {
         self.file.set_len(0)?;
         self.file.seek(SeekFrom::Start(0))?;
         self.file.sync_all()?; // Ensure truncation is persisted to disk
+        self.entries_since_checkpoint = 0;
         Ok(())
     }
 
@@ -127,6 +147,7 @@ impl WriteAheadLog {
             .truncate(true)
             .open(&temp_path)?;
 
+        let kept = active_entries.len() as u64;
         for entry in active_entries {
             temp_file.write_all(&entry.serialize())?;
         }
@@ -141,6 +162,7 @@ impl WriteAheadLog {
             .read(true)
             .append(true)
             .open(&self.path)?;
+        self.entries_since_checkpoint = kept;
 
         Ok(())
     }
@@ -277,4 +299,32 @@ mod tests {
             assert_eq!(recovered.len(), 0);
         }
     }
+
+    #[test]
+    fn test_wal_size_and_entries_since_checkpoint() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let wal_path = temp_dir.path().join("test.wal");
+        let mut wal = WriteAheadLog::open(&wal_path).unwrap();
+        assert_eq!(wal.entries_since_checkpoint(), 0);
+        assert_eq!(wal.size_bytes().unwrap(), 0);
+
+        wal.append(&WALEntry::new(1, WALEntryType::Begin, vec![]))
+            .unwrap();
+        wal.append(&WALEntry::new(1, WALEntryType::Commit, vec![]))
+            .unwrap();
+        wal.append(&WALEntry::new(2, WALEntryType::Begin, vec![]))
+            .unwrap();
+        wal.flush().unwrap();
+        assert_eq!(wal.entries_since_checkpoint(), 3);
+        assert!(wal.size_bytes().unwrap() > 0);
+
+        // The checkpoint keeps transaction 2's entry
+        wal.checkpoint(&[1]).unwrap();
+        assert_eq!(wal.entries_since_checkpoint(), 1);
+        assert!(wal.size_bytes().unwrap() > 0);
+
+        wal.clear().unwrap();
+        assert_eq!(wal.entries_since_checkpoint(), 0);
+        assert_eq!(wal.size_bytes().unwrap(), 0);
+    }
 }
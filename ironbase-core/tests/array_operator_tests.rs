@@ -1,5 +1,6 @@
 // array_operator_tests.rs
-// Comprehensive tests for array update operators: $push, $pull, $addToSet, $pop
+// Comprehensive tests for array update operators: $push (with $each, $position,
+// $sort and $slice), $pull, $pullAll, $addToSet, $pop
 
 use ironbase_core::DatabaseCore;
 use serde_json::{json, Value};
@@ -210,6 +211,85 @@ fn test_push_slice_negative() {
     cleanup_test_db("push_slice_neg");
 }
 
+#[test]
+fn test_push_sort_scalars_and_documents() {
+    let db = setup_test_db("push_sort");
+    let coll = db.collection("test").unwrap();
+
+    db.insert_one(
+        "test",
+        json_to_hashmap(json!({
+            "_id": 1,
+            "tags": ["b", "d"],
+            "scores": [{"value": 7, "at": 1}, {"value": 3, "at": 2}]
+        })),
+    )
+    .unwrap();
+
+    // Scalars sort whole elements
+    db.update_one(
+        "test",
+        &json!({"_id": 1}),
+        &json!({"$push": {"tags": {"$each": ["c", "a"], "$sort": 1}}}),
+    )
+    .unwrap();
+
+    // Documents sort by field, and $sort runs before $slice: keep the top 2
+    db.update_one(
+        "test",
+        &json!({"_id": 1}),
+        &json!({"$push": {"scores": {
+            "$each": [{"value": 9, "at": 3}, {"value": 1, "at": 4}],
+            "$sort": {"value": -1},
+            "$slice": 2
+        }}}),
+    )
+    .unwrap();
+
+    let docs = coll.find(&json!({"_id": 1})).unwrap();
+    assert_eq!(docs[0]["tags"], json!(["a", "b", "c", "d"]));
+    assert_eq!(
+        docs[0]["scores"],
+        json!([{"value": 9, "at": 3}, {"value": 7, "at": 1}])
+    );
+
+    // An empty $each just re-sorts the existing array
+    db.update_one(
+        "test",
+        &json!({"_id": 1}),
+        &json!({"$push": {"tags": {"$each": [], "$sort": -1}}}),
+    )
+    .unwrap();
+    let docs = coll.find(&json!({"_id": 1})).unwrap();
+    assert_eq!(docs[0]["tags"], json!(["d", "c", "b", "a"]));
+
+    cleanup_test_db("push_sort");
+}
+
+#[test]
+fn test_push_sort_validation() {
+    let db = setup_test_db("push_sort_invalid");
+    let coll = db.collection("test").unwrap();
+
+    db.insert_one("test", json_to_hashmap(json!({"_id": 1, "items": [2, 1]})))
+        .unwrap();
+
+    for update in [
+        json!({"$push": {"items": {"$sort": 1}}}),
+        json!({"$push": {"items": {"$each": [3], "$sort": 2}}}),
+        json!({"$push": {"items": {"$each": [3], "$sort": {}}}}),
+        json!({"$push": {"items": {"$each": [3], "$sort": {"value": "asc"}}}}),
+    ] {
+        let result = db.update_one("test", &json!({"_id": 1}), &update);
+        assert!(result.is_err(), "{} should be rejected", update);
+    }
+
+    let docs = coll.find(&json!({"_id": 1})).unwrap();
+    assert_eq!(docs[0]["items"], json!([2, 1]));
+
+    cleanup_test_db("push_sort_invalid");
+}
+
 #[test]
 fn test_push_to_non_array_field_should_error() {
     let db = setup_test_db("push_error");
@@ -371,6 +451,75 @@ fn test_pull_from_non_array_field_should_error() {
     cleanup_test_db("pull_error");
 }
 
+// ========== $pullAll TESTS ==========
+
+#[test]
+fn test_pullall_removes_every_copy_in_order() {
+    let db = setup_test_db("pullall_simple");
+    let coll = db.collection("test").unwrap();
+
+    db.insert_one(
+        "test",
+        json_to_hashmap(json!({
+            "_id": 1,
+            "tags": ["a", "b", "c", "a", {"k": 1}, "d", "b", {"k": 2}]
+        })),
+    )
+    .unwrap();
+
+    let (matched, modified) = db
+        .update_one(
+            "test",
+            &json!({"_id": 1}),
+            &json!({"$pullAll": {"tags": ["a", "b", {"k": 1}, "zzz"]}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (1, 1));
+
+    let docs = coll.find(&json!({"_id": 1})).unwrap();
+    assert_eq!(docs[0]["tags"], json!(["c", "d", {"k": 2}]));
+
+    // Nothing left to remove: not modified
+    let (_, modified) = db
+        .update_one(
+            "test",
+            &json!({"_id": 1}),
+            &json!({"$pullAll": {"tags": ["a"], "missing": ["x"]}}),
+        )
+        .unwrap();
+    assert_eq!(modified, 0);
+
+    cleanup_test_db("pullall_simple");
+}
+
+#[test]
+fn test_pullall_rejects_non_arrays() {
+    let db = setup_test_db("pullall_error");
+    let coll = db.collection("test").unwrap();
+
+    db.insert_one(
+        "test",
+        json_to_hashmap(json!({"_id": 1, "name": "test", "tags": ["a"]})),
+    )
+    .unwrap();
+
+    for update in [
+        json!({"$pullAll": {"name": ["test"]}}),
+        json!({"$pullAll": {"tags": "a"}}),
+    ] {
+        let result = db.update_one("test", &json!({"_id": 1}), &update);
+        assert!(
+            matches!(result, Err(ironbase_core::MongoLiteError::InvalidQuery(_))),
+            "{:?}",
+            result
+        );
+    }
+    let docs = coll.find(&json!({"_id": 1})).unwrap();
+    assert_eq!(docs[0]["tags"], json!(["a"]));
+
+    cleanup_test_db("pullall_error");
+}
+
 // ========== $addToSet TESTS ==========
 
 #[test]
@@ -0,0 +1,100 @@
+// Base and incremental backups restored into a fresh database
+use ironbase_core::{BackupManifest, DatabaseCore, StorageEngine};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+fn contents(db: &DatabaseCore<StorageEngine>) -> Vec<(String, Vec<Value>)> {
+    let mut names = db.list_collections();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let mut docs = db.collection(&name).unwrap().find(&json!({})).unwrap();
+            docs.sort_by_key(|doc| doc["_id"].to_string());
+            (name, docs)
+        })
+        .collect()
+}
+
+#[test]
+fn test_base_and_increments_restore_live_state() {
+    let temp_dir = TempDir::new().unwrap();
+    let backups = temp_dir.path().join("backups");
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("live.mlite")).unwrap();
+    let users: Vec<HashMap<String, Value>> = (0..200)
+        .map(|i| {
+            HashMap::from([
+                ("_id".to_string(), json!(i)),
+                ("name".to_string(), json!(format!("user-{}", i))),
+                ("bio".to_string(), json!("x".repeat(100))),
+            ])
+        })
+        .collect();
+    db.insert_many("users", users).unwrap();
+    db.insert_one("orders", HashMap::from([("_id".to_string(), json!("o1"))]))
+        .unwrap();
+
+    let base = db.backup(&backups).unwrap();
+    assert_eq!((base.version, base.since, base.documents), (1, None, 201));
+
+    // First increment: updates, a delete, an insert and a new empty collection
+    db.update_one(
+        "users",
+        &json!({"_id": 3}),
+        &json!({"$set": {"name": "renamed"}}),
+    )
+    .unwrap();
+    db.delete_one("users", &json!({"_id": 7})).unwrap();
+    db.insert_one("users", HashMap::from([("_id".to_string(), json!(500))]))
+        .unwrap();
+    db.collection("audit").unwrap();
+    let first = db.incremental_backup(&backups, base.version).unwrap();
+    assert_eq!((first.version, first.since), (2, Some(1)));
+    assert_eq!((first.documents, first.deletions), (2, 1));
+
+    // Second increment: a dropped collection and another update
+    db.drop_collection("orders").unwrap();
+    db.update_one(
+        "users",
+        &json!({"_id": 500}),
+        &json!({"$set": {"late": true}}),
+    )
+    .unwrap();
+    let second = db.incremental_backup(&backups, first.version).unwrap();
+    assert_eq!((second.version, second.documents), (3, 1));
+
+    assert!(first.bytes < base.bytes / 10, "{:?} vs {:?}", first, base);
+    assert!(second.bytes < base.bytes / 10, "{:?} vs {:?}", second, base);
+    let manifest = BackupManifest::load(&backups).unwrap();
+    assert_eq!(manifest.backups, vec![base, first, second]);
+
+    let restored =
+        DatabaseCore::<StorageEngine>::open(temp_dir.path().join("restored.mlite")).unwrap();
+    restored
+        .insert_one("stale", HashMap::from([("n".to_string(), json!(1))]))
+        .unwrap();
+    assert_eq!(restored.restore_from(&backups).unwrap(), 3);
+    assert_eq!(contents(&restored), contents(&db));
+
+    // The restore survives a reopen
+    drop(restored);
+    let reopened =
+        DatabaseCore::<StorageEngine>::open(temp_dir.path().join("restored.mlite")).unwrap();
+    assert_eq!(contents(&reopened), contents(&db));
+}
+
+#[test]
+fn test_incremental_backup_needs_a_known_version() {
+    let temp_dir = TempDir::new().unwrap();
+    let backups = temp_dir.path().join("backups");
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("live.mlite")).unwrap();
+    db.insert_one("users", HashMap::from([("n".to_string(), json!(1))]))
+        .unwrap();
+
+    assert!(db.incremental_backup(&backups, 1).is_err());
+    assert!(db.restore_from(&backups).is_err());
+    db.backup(&backups).unwrap();
+    assert!(db.incremental_backup(&backups, 2).is_err());
+    assert_eq!(db.incremental_backup(&backups, 1).unwrap().documents, 0);
+}
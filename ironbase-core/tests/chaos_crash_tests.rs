@@ -826,3 +826,371 @@ fn test_wal_recovery_mixed_operations_metadata() {
             .contains_key(&ironbase_core::document::DocumentId::Int(3)));
     }
 }
+
+/// Test: Corrupted collection metadata with an intact WAL
+/// Expected: Open falls back to rebuilding the catalog from the WAL
+#[test]
+fn test_corrupted_catalog_repaired_from_wal() {
+    use ironbase_core::storage::Header;
+    use ironbase_core::DatabaseCore;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let mut ids = Vec::new();
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        db.collection("users").unwrap();
+        db.flush().unwrap();
+
+        for i in 0..20 {
+            let mut fields = HashMap::new();
+            fields.insert("n".to_string(), json!(i));
+            ids.push(db.insert_one("users", fields).unwrap());
+        }
+        db.update_one(
+            "users",
+            &json!({"n": 3}),
+            &json!({"$set": {"tag": "updated"}}),
+        )
+        .unwrap();
+        db.delete_one("users", &json!({"n": 4})).unwrap();
+
+        // Crash: neither metadata flush nor WAL truncation happens
+        std::mem::forget(db);
+    }
+
+    // Overwrite the stored collection metadata with garbage
+    let header_bytes = read_bytes_at(&db_path, 0, 256).unwrap();
+    let header: Header = bincode::deserialize(&header_bytes).unwrap();
+    assert!(header.metadata_offset > 0);
+    corrupt_bytes_at(
+        &db_path,
+        header.metadata_offset + 4,
+        b"\xff\xff\xff\x7fgarbage",
+    )
+    .unwrap();
+
+    let len_before = file_len(&db_path).unwrap();
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("users").unwrap();
+
+    for (i, id) in ids.iter().enumerate() {
+        let doc = collection.find_one(&json!({"_id": id})).unwrap();
+        if i == 4 {
+            assert!(doc.is_none(), "deleted document must stay deleted");
+            continue;
+        }
+        let doc = doc.unwrap_or_else(|| panic!("document {:?} not addressable", id));
+        assert_eq!(doc["n"], json!(i));
+        if i == 3 {
+            assert_eq!(doc["tag"], "updated");
+        }
+    }
+    assert_eq!(collection.count_documents(&json!({})).unwrap(), 19);
+
+    // Records already in the data file were reused rather than appended again
+    let grown = file_len(&db_path).unwrap() - len_before;
+    assert!(grown < 4096, "data file grew by {} bytes", grown);
+
+    // New inserts do not collide with recovered ids
+    let mut fields = HashMap::new();
+    fields.insert("n".to_string(), json!(100));
+    let new_id = db.insert_one("users", fields).unwrap();
+    assert!(!ids.contains(&new_id));
+}
+
+/// Test: Catalog repair after checkpoints have cleared the WAL
+/// Expected: Documents written before the last checkpoint survive the repair
+#[test]
+fn test_catalog_repair_keeps_documents_from_before_checkpoint() {
+    use ironbase_core::storage::Header;
+    use ironbase_core::DatabaseCore;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let insert = |db: &DatabaseCore<StorageEngine>, n: i64| {
+        let mut fields = HashMap::new();
+        fields.insert("n".to_string(), json!(n));
+        db.insert_one("users", fields).unwrap()
+    };
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        db.collection("users").unwrap();
+        db.collection("empty").unwrap();
+        for n in 0..10 {
+            insert(&db, n);
+        }
+        db.flush().unwrap();
+
+        // Several checkpoints leave superseded metadata blocks between records
+        db.delete_one("users", &json!({"n": 2})).unwrap();
+        db.flush().unwrap();
+        insert(&db, 10);
+        db.flush().unwrap();
+
+        // Only this insert is still in the WAL at the crash
+        insert(&db, 11);
+        std::mem::forget(db);
+    }
+
+    let header: Header = bincode::deserialize(&read_bytes_at(&db_path, 0, 256).unwrap()).unwrap();
+    corrupt_bytes_at(
+        &db_path,
+        header.metadata_offset + 4,
+        b"\xff\xff\xff\x7fgarbage",
+    )
+    .unwrap();
+
+    let expected: Vec<i64> = (0..12).filter(|n| *n != 2).collect();
+    for _ in 0..2 {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        let collection = db.collection("users").unwrap();
+        let mut found: Vec<i64> = collection
+            .find(&json!({}))
+            .unwrap()
+            .iter()
+            .map(|doc| doc["n"].as_i64().unwrap())
+            .collect();
+        found.sort();
+        assert_eq!(found, expected);
+        assert_eq!(collection.count_documents(&json!({})).unwrap(), 11);
+        assert!(db.list_collections().contains(&"empty".to_string()));
+    }
+}
+
+/// Test: Crash after an aggregation with `$out` ran through the database
+/// Expected: The written collection is recovered from the WAL
+#[test]
+fn test_out_results_recovered_from_wal() {
+    use ironbase_core::DatabaseCore;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        for (city, amount) in [("Pécs", 10), ("Győr", 5), ("Pécs", 7)] {
+            let mut fields = HashMap::new();
+            fields.insert("city".to_string(), json!(city));
+            fields.insert("amount".to_string(), json!(amount));
+            db.insert_one("sales", fields).unwrap();
+        }
+        let mut stale = HashMap::new();
+        stale.insert("_id".to_string(), json!("Szeged"));
+        db.insert_one("totals", stale).unwrap();
+
+        db.aggregate(
+            "sales",
+            &json!([
+                {"$group": {"_id": "$city", "total": {"$sum": "$amount"}}},
+                {"$out": "totals"}
+            ]),
+        )
+        .unwrap();
+
+        // Crash: no flush after the $out writes
+        std::mem::forget(db);
+    }
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let mut totals: Vec<(String, i64)> = db
+        .collection("totals")
+        .unwrap()
+        .find(&json!({}))
+        .unwrap()
+        .iter()
+        .map(|doc| {
+            (
+                doc["_id"].as_str().unwrap().to_string(),
+                doc["total"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    totals.sort();
+    assert_eq!(
+        totals,
+        vec![("Győr".to_string(), 5), ("Pécs".to_string(), 17)]
+    );
+}
+
+/// Test: Damaged catalog after a clean checkpoint, with an empty WAL
+/// Expected: The scan of the data file alone rebuilds the catalog
+#[test]
+fn test_catalog_repair_with_empty_wal() {
+    use ironbase_core::storage::Header;
+    use ironbase_core::DatabaseCore;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        for n in 0..10 {
+            let mut fields = HashMap::new();
+            fields.insert("n".to_string(), json!(n));
+            db.insert_one("users", fields).unwrap();
+        }
+        db.delete_one("users", &json!({"n": 5})).unwrap();
+        db.flush().unwrap();
+    }
+    assert_eq!(
+        WriteAheadLog::open(temp_dir.path().join("test.wal"))
+            .unwrap()
+            .recover()
+            .unwrap()
+            .len(),
+        0
+    );
+
+    let header: Header = bincode::deserialize(&read_bytes_at(&db_path, 0, 256).unwrap()).unwrap();
+    corrupt_bytes_at(
+        &db_path,
+        header.metadata_offset + 4,
+        b"\xff\xff\xff\x7fgarbage",
+    )
+    .unwrap();
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("users").unwrap();
+    let mut found: Vec<i64> = collection
+        .find(&json!({}))
+        .unwrap()
+        .iter()
+        .map(|doc| doc["n"].as_i64().unwrap())
+        .collect();
+    found.sort();
+    assert_eq!(found, vec![0, 1, 2, 3, 4, 6, 7, 8, 9]);
+}
+
+/// Test: Catalog repair when the WAL describes writes missing from the data file
+/// Expected: The missing documents are written again
+#[test]
+fn test_catalog_repair_rewrites_missing_records() {
+    use ironbase_core::document::DocumentId;
+    use ironbase_core::storage::Header;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+    let wal_path = temp_dir.path().join("test.wal");
+
+    {
+        let mut storage = StorageEngine::open(&db_path).unwrap();
+        storage.create_collection("users").unwrap();
+        storage.flush().unwrap();
+    }
+
+    let corrupt_catalog = || {
+        let header: Header =
+            bincode::deserialize(&read_bytes_at(&db_path, 0, 256).unwrap()).unwrap();
+        corrupt_bytes_at(&db_path, header.metadata_offset + 4, b"\xff\xff\xff\x7f").unwrap();
+    };
+
+    corrupt_catalog();
+    {
+        let mut wal = WriteAheadLog::open(&wal_path).unwrap();
+        let operation = ironbase_core::transaction::Operation::Insert {
+            collection: "users".to_string(),
+            doc_id: DocumentId::Int(7),
+            doc: json!({"_id": 7, "_collection": "users", "name": "from_wal"}),
+        };
+        wal.append(&WALEntry::new(1, WALEntryType::Begin, vec![]))
+            .unwrap();
+        wal.append(&WALEntry::new(
+            1,
+            WALEntryType::Operation,
+            serde_json::to_vec(&operation).unwrap(),
+        ))
+        .unwrap();
+        wal.append(&WALEntry::new(1, WALEntryType::Commit, vec![]))
+            .unwrap();
+        wal.flush().unwrap();
+    }
+
+    let mut storage = StorageEngine::open(&db_path).unwrap();
+    let meta = storage.get_collection_meta("users").unwrap();
+    assert_eq!(meta.last_id, 7);
+    let offset = meta.document_catalog[&DocumentId::Int(7)];
+    let doc: serde_json::Value =
+        serde_json::from_slice(&storage.read_document_at("users", offset).unwrap()).unwrap();
+    assert_eq!(doc["name"], "from_wal");
+
+    // The repaired catalog was persisted and the WAL cleared
+    drop(storage);
+    assert_eq!(
+        WriteAheadLog::open(&wal_path)
+            .unwrap()
+            .recover()
+            .unwrap()
+            .len(),
+        0
+    );
+    let storage = StorageEngine::open(&db_path).unwrap();
+    assert!(storage
+        .get_collection_meta("users")
+        .unwrap()
+        .document_catalog
+        .contains_key(&DocumentId::Int(7)));
+}
+
+/// Test: Commits fsync only the WAL; lost data-file writes are replayed
+#[test]
+fn test_commit_syncs_wal_not_data_file() {
+    use ironbase_core::{DatabaseCore, DatabaseOptions};
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let len_before;
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        db.collection("events").unwrap();
+        db.flush().unwrap();
+        len_before = file_len(&db_path).unwrap();
+
+        let before = db.sync_counts();
+        for i in 0..10 {
+            let mut fields = HashMap::new();
+            fields.insert("n".to_string(), json!(i));
+            db.insert_one("events", fields).unwrap();
+        }
+        let after = db.sync_counts();
+        assert_eq!(after.wal - before.wal, 10, "one WAL fsync per commit");
+        assert_eq!(
+            after.data, before.data,
+            "commits must not fsync the data file"
+        );
+
+        // Crash before any checkpoint
+        std::mem::forget(db);
+    }
+
+    // The data-file writes were never fsynced: drop them
+    truncate_file(&db_path, len_before).unwrap();
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let events = db.collection("events").unwrap();
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 10);
+    for i in 0..10 {
+        assert!(events.find_one(&json!({"n": i})).unwrap().is_some());
+    }
+
+    // A checkpoint fsyncs the data file before the WAL is cleared
+    let before = db.sync_counts();
+    db.checkpoint().unwrap();
+    assert!(db.sync_counts().data > before.data);
+    drop(db);
+
+    // Opting back in fsyncs the data file on every commit
+    let options = DatabaseOptions::new().with_sync_data_on_commit(true);
+    let db = DatabaseCore::<StorageEngine>::open_with_options(&db_path, options).unwrap();
+    let before = db.sync_counts();
+    let mut fields = HashMap::new();
+    fields.insert("n".to_string(), json!(10));
+    db.insert_one("events", fields).unwrap();
+    let after = db.sync_counts();
+    assert_eq!(after.wal - before.wal, 1);
+    assert_eq!(after.data - before.data, 1);
+}
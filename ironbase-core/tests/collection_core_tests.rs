@@ -3,7 +3,7 @@
 //! These tests cover the main CRUD operations and various edge cases
 
 use ironbase_core::storage::{MemoryStorage, StorageEngine};
-use ironbase_core::DatabaseCore;
+use ironbase_core::{DatabaseCore, DatabaseOptions, DocumentId, DurabilityMode, MongoLiteError};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -294,6 +294,91 @@ fn test_update_one_inc() {
     assert_eq!(updated["counter"], 15);
 }
 
+#[test]
+fn test_update_one_mul() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let doc = HashMap::from([
+        ("qty".to_string(), json!(6)),
+        ("price".to_string(), json!(2.5)),
+        ("big".to_string(), json!(i64::MAX / 2)),
+        ("name".to_string(), json!("widget")),
+    ]);
+    let id = db.insert_one(&coll_name, doc).unwrap();
+
+    db.update_one(
+        &coll_name,
+        &json!({"_id": id}),
+        &json!({"$mul": {"qty": 3, "price": 2, "big": 3, "missing_int": 7, "missing_float": 1.5}}),
+    )
+    .unwrap();
+
+    let updated = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(updated["qty"], json!(18));
+    assert_eq!(updated["price"], json!(5.0));
+    // Integer overflow saturates instead of wrapping
+    assert_eq!(updated["big"], json!(i64::MAX));
+    // A missing field becomes zero of the factor's type
+    assert_eq!(updated["missing_int"], json!(0));
+    assert_eq!(updated["missing_float"], json!(0.0));
+
+    for update in [json!({"$mul": {"name": 2}}), json!({"$mul": {"qty": "2"}})] {
+        let err = db
+            .update_one(&coll_name, &json!({"_id": id}), &update)
+            .unwrap_err();
+        assert!(matches!(err, MongoLiteError::InvalidQuery(_)), "{:?}", err);
+    }
+    let unchanged = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(unchanged, updated);
+}
+
+#[test]
+fn test_update_one_min_max() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let doc = HashMap::from([
+        ("low".to_string(), json!(10)),
+        ("high".to_string(), json!(10)),
+        ("name".to_string(), json!("m")),
+    ]);
+    let id = db.insert_one(&coll_name, doc).unwrap();
+
+    let (matched, modified) = db
+        .update_one(
+            &coll_name,
+            &json!({"_id": id}),
+            &json!({"$min": {"low": 3, "name": "a"}, "$max": {"high": 12.5, "fresh": 1}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (1, 1));
+
+    let updated = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(updated["low"], json!(3));
+    assert_eq!(updated["high"], json!(12.5));
+    assert_eq!(updated["name"], json!("a"));
+    // A missing field is set unconditionally
+    assert_eq!(updated["fresh"], json!(1));
+
+    // Guards that fail (or compare mismatched types) must not write anything
+    let updates_before = db.metrics().updates;
+    for update in [
+        json!({"$min": {"low": 5}}),
+        json!({"$max": {"high": 12.5}}),
+        json!({"$max": {"name": "Z"}}),
+        json!({"$min": {"low": "1"}}),
+    ] {
+        let (matched, modified) = db
+            .update_one(&coll_name, &json!({"_id": id}), &update)
+            .unwrap();
+        assert_eq!((matched, modified), (1, 0), "{}", update);
+    }
+    assert_eq!(db.metrics().updates, updates_before);
+    let unchanged = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(unchanged, updated);
+}
+
 #[test]
 fn test_update_one_unset() {
     let (db, coll_name) = create_test_db("test");
@@ -316,6 +401,70 @@ fn test_update_one_unset() {
     assert!(updated.get("temp").is_none());
 }
 
+#[test]
+fn test_update_one_rename() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let doc = HashMap::from([
+        ("nick".to_string(), json!("ally")),
+        ("name".to_string(), json!("Alice")),
+        (
+            "address".to_string(),
+            json!({"zip": "1011", "city": "Budapest"}),
+        ),
+    ]);
+    let id = db.insert_one(&coll_name, doc).unwrap();
+
+    // Collision: the target is overwritten; dotted paths move in and out
+    let (_, modified) = db
+        .update_one(
+            &coll_name,
+            &json!({"_id": id}),
+            &json!({"$rename": {"nick": "name", "address.zip": "postal.code"}}),
+        )
+        .unwrap();
+    assert_eq!(modified, 1);
+    let updated = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(updated["name"], "ally");
+    assert!(updated.get("nick").is_none());
+    assert_eq!(updated["address"], json!({"city": "Budapest"}));
+    assert_eq!(updated["postal"], json!({"code": "1011"}));
+
+    // Missing source: nothing changes
+    let (matched, modified) = db
+        .update_one(
+            &coll_name,
+            &json!({"_id": id}),
+            &json!({"$rename": {"nick": "alias", "address.zip": "zip"}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (1, 0));
+    let unchanged = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(unchanged, updated);
+    assert!(unchanged.get("alias").is_none());
+
+    for rename in [
+        json!({"_id": "id"}),
+        json!({"name": "_id"}),
+        json!({"name": "_id.name"}),
+        json!({"name": "name"}),
+        json!({"address": "address.old"}),
+        json!({"postal.code": "postal"}),
+        json!({"name": 1}),
+    ] {
+        let err = db
+            .update_one(&coll_name, &json!({"_id": id}), &json!({"$rename": rename}))
+            .unwrap_err();
+        assert!(
+            matches!(err, MongoLiteError::InvalidQuery(_)),
+            "{}: {:?}",
+            rename,
+            err
+        );
+    }
+}
+
 #[test]
 fn test_update_one_push() {
     let (db, coll_name) = create_test_db("test");
@@ -469,6 +618,220 @@ fn test_update_many() {
     assert_eq!(modified, 5);
 }
 
+#[test]
+fn test_update_one_upsert_inserts_when_nothing_matches() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+    collection.create_index("name".to_string(), true).unwrap();
+
+    let update = json!({
+        "$inc": {"visits": 1},
+        "$set": {"last": "mon"},
+        "$setOnInsert": {"created": "mon"}
+    });
+    let (matched, modified, upserted_id) = db
+        .update_one_upsert(
+            &coll_name,
+            &json!({"name": "home", "kind": {"$eq": "page"}, "visits": {"$gt": 5}}),
+            &update,
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (0, 0));
+    let id = upserted_id.expect("nothing matched, so a document is inserted");
+    // A fresh handle sees the index entries written through `db`
+    let collection = db.collection(&coll_name).unwrap();
+
+    let inserted = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(inserted["name"], json!("home"));
+    assert_eq!(inserted["kind"], json!("page"));
+    // Non-equality conditions don't become fields; the update sets them
+    assert_eq!(inserted["visits"], json!(1));
+    assert_eq!(inserted["last"], json!("mon"));
+    assert_eq!(inserted["created"], json!("mon"));
+    // Inserted through the normal path, so the index knows it
+    let plan = collection.explain(&json!({"name": "home"})).unwrap();
+    assert_eq!(plan["queryPlan"], json!("IndexScan"));
+    assert_eq!(collection.find(&json!({"name": "home"})).unwrap().len(), 1);
+
+    // A match is updated and $setOnInsert is ignored
+    let update = json!({"$inc": {"visits": 1}, "$setOnInsert": {"created": "tue"}});
+    let (matched, modified, upserted_id) = db
+        .update_one_upsert(&coll_name, &json!({"name": "home"}), &update)
+        .unwrap();
+    assert_eq!((matched, modified, upserted_id), (1, 1, None));
+    let updated = collection.find_one(&json!({"_id": id})).unwrap().unwrap();
+    assert_eq!(updated["visits"], json!(2));
+    assert_eq!(updated["created"], json!("mon"));
+
+    // Plain updates ignore $setOnInsert too
+    let (matched, modified) = db
+        .update_one(
+            &coll_name,
+            &json!({"name": "home"}),
+            &json!({"$setOnInsert": {"created": "wed"}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (1, 0));
+    assert_eq!(collection.count_documents(&json!({})).unwrap(), 1);
+}
+
+#[test]
+fn test_update_upsert_with_id_and_many() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let (_, _, upserted_id) = db
+        .update_one_upsert(
+            &coll_name,
+            &json!({"_id": "settings"}),
+            &json!({"$set": {"theme": "dark"}}),
+        )
+        .unwrap();
+    assert_eq!(
+        upserted_id,
+        Some(DocumentId::String("settings".to_string()))
+    );
+    let doc = collection
+        .find_one(&json!({"_id": "settings"}))
+        .unwrap()
+        .unwrap();
+    assert_eq!(doc["theme"], json!("dark"));
+
+    let (matched, modified, upserted_id) = db
+        .update_many_upsert(
+            &coll_name,
+            &json!({"group": "a"}),
+            &json!({"$set": {"flag": true}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified), (0, 0));
+    assert!(upserted_id.is_some());
+    db.insert_one(
+        &coll_name,
+        HashMap::from([("group".to_string(), json!("a"))]),
+    )
+    .unwrap();
+
+    let (matched, modified, upserted_id) = db
+        .update_many_upsert(
+            &coll_name,
+            &json!({"group": "a"}),
+            &json!({"$set": {"flag": false}}),
+        )
+        .unwrap();
+    assert_eq!((matched, modified, upserted_id), (2, 2, None));
+}
+
+#[test]
+fn test_swap_field_on_unique_index() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+    collection
+        .create_index("position".to_string(), true)
+        .unwrap();
+
+    let first = db
+        .insert_one(
+            &coll_name,
+            HashMap::from([
+                ("title".to_string(), json!("first")),
+                ("position".to_string(), json!(1)),
+            ]),
+        )
+        .unwrap();
+    let second = db
+        .insert_one(
+            &coll_name,
+            HashMap::from([
+                ("title".to_string(), json!("second")),
+                ("position".to_string(), json!(2)),
+            ]),
+        )
+        .unwrap();
+
+    // One update at a time collides with the other document's position
+    let err = db
+        .update_one(
+            &coll_name,
+            &json!({"_id": first}),
+            &json!({"$set": {"position": 2}}),
+        )
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::IndexError(_)), "{:?}", err);
+
+    assert!(db
+        .swap_field(&coll_name, &first, &second, "position")
+        .unwrap());
+    // A fresh handle sees the index entries written through `db`
+    let collection = db.collection(&coll_name).unwrap();
+
+    let at_one = collection.find(&json!({"position": 1})).unwrap();
+    let at_two = collection.find(&json!({"position": 2})).unwrap();
+    assert_eq!(at_one.len(), 1);
+    assert_eq!(at_two.len(), 1);
+    assert_eq!(at_one[0]["title"], json!("second"));
+    assert_eq!(at_two[0]["title"], json!("first"));
+    let plan = collection.explain(&json!({"position": 1})).unwrap();
+    assert_eq!(plan["queryPlan"], json!("IndexScan"));
+
+    // The index still rejects a real duplicate
+    let err = db
+        .update_one(
+            &coll_name,
+            &json!({"_id": second}),
+            &json!({"$set": {"position": 2}}),
+        )
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::IndexError(_)), "{:?}", err);
+
+    // Swapping back restores the original order
+    assert!(db
+        .swap_field(&coll_name, &first, &second, "position")
+        .unwrap());
+    let at_one = db
+        .collection(&coll_name)
+        .unwrap()
+        .find(&json!({"position": 1}))
+        .unwrap();
+    assert_eq!(at_one[0]["title"], json!("first"));
+}
+
+#[test]
+fn test_swap_field_edge_cases() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let a = db
+        .insert_one(
+            &coll_name,
+            HashMap::from([
+                ("rank".to_string(), json!(5)),
+                ("tag".to_string(), json!("x")),
+            ]),
+        )
+        .unwrap();
+    let b = db
+        .insert_one(&coll_name, HashMap::from([("tag".to_string(), json!("x"))]))
+        .unwrap();
+
+    // Equal values change nothing
+    assert!(!db.swap_field(&coll_name, &a, &b, "tag").unwrap());
+
+    // A missing field moves to the other document
+    assert!(db.swap_field(&coll_name, &a, &b, "rank").unwrap());
+    let doc_a = collection.find_one(&json!({"_id": a})).unwrap().unwrap();
+    let doc_b = collection.find_one(&json!({"_id": b})).unwrap().unwrap();
+    assert!(doc_a.get("rank").is_none());
+    assert_eq!(doc_b["rank"], json!(5));
+
+    let err = db.swap_field(&coll_name, &a, &b, "_id").unwrap_err();
+    assert!(matches!(err, MongoLiteError::InvalidQuery(_)), "{:?}", err);
+    let err = db
+        .swap_field(&coll_name, &a, &DocumentId::Int(999), "rank")
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::DocumentNotFound), "{:?}", err);
+}
+
 // ========== DELETE TESTS ==========
 
 #[test]
@@ -577,6 +940,61 @@ fn test_distinct_by_id() {
     assert_eq!(distinct[0], "NYC");
 }
 
+#[test]
+fn test_distinct_capped_streams_from_index() {
+    let (db, coll_name) = create_test_db("test");
+    db.collection(&coll_name)
+        .unwrap()
+        .create_index("code".to_string(), false)
+        .unwrap();
+
+    for i in 0..1000 {
+        let doc = HashMap::from([("code".to_string(), json!(i))]);
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+    let collection = db.collection(&coll_name).unwrap();
+
+    let before = db.metrics().documents_read;
+    let (values, truncated) = collection.distinct_capped("code", &json!({}), 10).unwrap();
+    assert_eq!(values, (0..10).map(|i| json!(i)).collect::<Vec<_>>());
+    assert!(truncated);
+    assert_eq!(db.metrics().documents_read, before);
+
+    let mut seen = 0;
+    collection
+        .distinct_each("code", &json!({}), |_| {
+            seen += 1;
+            seen < 3
+        })
+        .unwrap();
+    assert_eq!(seen, 3);
+
+    // Values the index doesn't store exactly still come from the documents
+    db.insert_one(
+        &coll_name,
+        HashMap::from([("code".to_string(), json!([1, 2]))]),
+    )
+    .unwrap();
+    db.insert_one(
+        &coll_name,
+        HashMap::from([("code".to_string(), json!(null))]),
+    )
+    .unwrap();
+    db.insert_one(&coll_name, HashMap::from([("code".to_string(), json!(5))]))
+        .unwrap();
+    let collection = db.collection(&coll_name).unwrap();
+    let values = collection.distinct("code", &json!({})).unwrap();
+    assert_eq!(values.len(), 1002);
+    assert!(values.contains(&json!([1, 2])));
+    assert!(values.contains(&json!(null)));
+
+    let (values, truncated) = collection
+        .distinct_capped("code", &json!({"code": {"$gte": 998}}), 10)
+        .unwrap();
+    assert_eq!(values.len(), 2);
+    assert!(!truncated);
+}
+
 #[test]
 fn test_distinct_missing_field() {
     let (db, coll_name) = create_test_db("test");
@@ -688,6 +1106,51 @@ fn test_aggregate_match() {
     assert_eq!(results.len(), 5);
 }
 
+#[test]
+fn test_aggregate_leading_match_uses_index() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    for i in 0..40 {
+        let doc = HashMap::from([
+            ("email".to_string(), json!(format!("user{}@example.com", i))),
+            ("group".to_string(), json!(i % 4)),
+        ]);
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+    let pipeline = |email: &str| {
+        json!([
+            {"$match": {"email": email}},
+            {"$group": {"_id": "$group", "n": {"$sum": 1}}}
+        ])
+    };
+
+    // Without an index the scan finds the same answer
+    let scanned = collection
+        .aggregate(&pipeline("user7@example.com"))
+        .unwrap();
+    assert_eq!(scanned, vec![json!({"_id": 3, "n": 1})]);
+
+    collection.create_index("email".to_string(), true).unwrap();
+    let before = db.metrics();
+    let indexed = collection
+        .aggregate(&pipeline("user9@example.com"))
+        .unwrap();
+    assert_eq!(indexed, vec![json!({"_id": 1, "n": 1})]);
+    let after = db.metrics();
+    assert!(after.documents_read - before.documents_read <= 1);
+    assert_eq!(after.collection_scans, before.collection_scans);
+
+    // No match at all, and a later $match still runs in the pipeline
+    let results = collection
+        .aggregate(&json!([
+            {"$match": {"email": "nobody@example.com"}},
+            {"$match": {"group": 0}}
+        ]))
+        .unwrap();
+    assert!(results.is_empty());
+}
+
 #[test]
 fn test_aggregate_group() {
     let (db, coll_name) = create_test_db("test");
@@ -777,6 +1240,106 @@ fn test_aggregate_project() {
     assert!(results[0].get("secret").is_none());
 }
 
+#[test]
+fn test_aggregate_streaming_matches_aggregate() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    for i in 0..20 {
+        let doc = HashMap::from([
+            ("value".to_string(), json!(i)),
+            ("city".to_string(), json!(["NYC", "LA", "SF"][i % 3])),
+            ("tags".to_string(), json!(["a", "b"])),
+        ]);
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+
+    // Scan order isn't defined, so the pipelines sort before paginating
+    let pipelines = [
+        json!([
+            {"$match": {"value": {"$gte": 5}}},
+            {"$unwind": "$tags"},
+            {"$project": {"_id": 0, "value": 1, "tags": 1}},
+            {"$sort": {"value": 1, "tags": 1}},
+            {"$skip": 3},
+            {"$limit": 7}
+        ]),
+        json!([
+            {"$match": {"value": {"$lt": 15}}},
+            {"$group": {"_id": "$city", "total": {"$sum": "$value"}}},
+            {"$sort": {"_id": 1}}
+        ]),
+    ];
+    for pipeline in &pipelines {
+        let expected = collection.aggregate(pipeline).unwrap();
+        let mut cursor = collection.aggregate_streaming(pipeline).unwrap();
+        let mut streamed = Vec::new();
+        loop {
+            let chunk = cursor.next_chunk(2).unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            assert!(chunk.len() <= 2);
+            streamed.extend(chunk);
+        }
+        assert_eq!(streamed, expected, "pipeline {}", pipeline);
+        assert!(cursor.next().unwrap().is_none());
+    }
+
+    // Streaming stages alone: same documents, skip/limit counted across input
+    let results = collection
+        .aggregate_streaming(&json!([
+            {"$unwind": "$tags"},
+            {"$match": {"tags": "b"}},
+            {"$skip": 5},
+            {"$limit": 10}
+        ]))
+        .unwrap()
+        .collect_all()
+        .unwrap();
+    assert_eq!(results.len(), 10);
+    assert!(results.iter().all(|doc| doc["tags"] == "b"));
+}
+
+#[test]
+fn test_aggregate_streaming_reads_lazily() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    for i in 0..100 {
+        let doc = HashMap::from([
+            ("value".to_string(), json!(i)),
+            ("email".to_string(), json!(format!("user{}@example.com", i))),
+        ]);
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+    collection.create_index("email".to_string(), true).unwrap();
+
+    // The leading $match goes through the index
+    let before = db.metrics().documents_read;
+    let results = collection
+        .aggregate_streaming(&json!([
+            {"$match": {"email": "user42@example.com"}},
+            {"$project": {"_id": 0, "value": 1}}
+        ]))
+        .unwrap()
+        .collect_all()
+        .unwrap();
+    assert_eq!(results, vec![json!({"value": 42})]);
+    assert!(db.metrics().documents_read - before < 5);
+
+    // A reached $limit stops reading the collection
+    let before = db.metrics().documents_read;
+    let mut cursor = collection
+        .aggregate_streaming(&json!([{"$limit": 5}]))
+        .unwrap()
+        .with_batch_size(3);
+    assert_eq!(cursor.next_batch().unwrap().len(), 3);
+    assert_eq!(cursor.next_batch().unwrap().len(), 2);
+    assert!(cursor.next_batch().unwrap().is_empty());
+    assert!(db.metrics().documents_read - before <= 5);
+}
+
 // ========== SCHEMA VALIDATION TESTS ==========
 
 #[test]
@@ -922,6 +1485,114 @@ fn test_find_with_limit_skip() {
     assert_eq!(results[0]["value"], 10);
 }
 
+#[test]
+fn test_find_page_returns_window_and_total() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+
+    for i in 0..30 {
+        let doc = HashMap::from([
+            ("value".to_string(), json!(i)),
+            ("even".to_string(), json!(i % 2 == 0)),
+        ]);
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+    let query = json!({"even": true});
+
+    // In-memory sort
+    let options = ironbase_core::FindOptions::new()
+        .with_sort(vec![("value".to_string(), -1)])
+        .with_skip(3)
+        .with_limit(4);
+    let (docs, total) = collection.find_page(&query, options).unwrap();
+    assert_eq!(total, collection.count_documents(&query).unwrap());
+    assert_eq!(total, 15);
+    let values: Vec<i64> = docs.iter().map(|d| d["value"].as_i64().unwrap()).collect();
+    assert_eq!(values, vec![22, 20, 18, 16]);
+
+    // Index-served sort only loads the window
+    collection.create_index("value".to_string(), false).unwrap();
+    let options = ironbase_core::FindOptions::new()
+        .with_sort(vec![("value".to_string(), 1)])
+        .with_skip(12)
+        .with_limit(10);
+    let (docs, total) = collection.find_page(&query, options).unwrap();
+    assert_eq!(total, 15);
+    let values: Vec<i64> = docs.iter().map(|d| d["value"].as_i64().unwrap()).collect();
+    assert_eq!(values, vec![24, 26, 28]);
+
+    // A window past the end still reports the total
+    let options = ironbase_core::FindOptions::new()
+        .with_skip(50)
+        .with_limit(5);
+    let (docs, total) = collection.find_page(&json!({}), options).unwrap();
+    assert!(docs.is_empty());
+    assert_eq!(total, 30);
+}
+
+#[test]
+fn test_index_sorted_find_reads_only_the_window() {
+    let (db, coll_name) = create_test_db("test");
+    let collection = db.collection(&coll_name).unwrap();
+    collection.create_index("value".to_string(), false).unwrap();
+
+    let docs = (0..1000)
+        .map(|i| {
+            HashMap::from([
+                ("value".to_string(), json!(i)),
+                ("even".to_string(), json!(i % 2 == 0)),
+            ])
+        })
+        .collect();
+    db.insert_many(&coll_name, docs).unwrap();
+    // A fresh handle sees the index entries written through `db`
+    let collection = db.collection(&coll_name).unwrap();
+
+    let values = |docs: Vec<serde_json::Value>| -> Vec<i64> {
+        docs.iter().map(|d| d["value"].as_i64().unwrap()).collect()
+    };
+    let window = |skip, limit, desc: bool| {
+        ironbase_core::FindOptions::new()
+            .with_sort(vec![("value".to_string(), if desc { -1 } else { 1 })])
+            .with_skip(skip)
+            .with_limit(limit)
+    };
+
+    let before = db.metrics().documents_read;
+    let docs = collection
+        .find_with_options(&json!({"value": {"$gte": 100}}), window(50, 5, false))
+        .unwrap();
+    assert_eq!(values(docs), vec![150, 151, 152, 153, 154]);
+    let read = db.metrics().documents_read - before;
+    assert!(
+        read <= 2 * 55,
+        "read {} documents for a 55-document window",
+        read
+    );
+
+    // A filter the index can't answer is verified while walking the index
+    let before = db.metrics().documents_read;
+    let docs = collection
+        .find_with_options(
+            &json!({"value": {"$lt": 900}, "even": true}),
+            window(10, 3, true),
+        )
+        .unwrap();
+    assert_eq!(values(docs), vec![878, 876, 874]);
+    let read = db.metrics().documents_read - before;
+    assert!(
+        read <= 2 * 30,
+        "read {} documents for a 13-match window",
+        read
+    );
+
+    // Without an index-served sort every match is sorted first
+    let docs = collection
+        .find_with_options(&json!({"even": false}), window(2, 2, true))
+        .unwrap();
+    assert_eq!(values(docs), vec![995, 993]);
+}
+
 // ========== EXPLAIN AND HINT TESTS ==========
 
 #[test]
@@ -2272,3 +2943,369 @@ fn test_wildcard_operator_with_comparison() {
     let results = coll.find(&json!({"$**.score": {"$gte": 60}})).unwrap();
     assert_eq!(results.len(), 2, "Should find 2 documents with score >= 60");
 }
+
+// ============================================================================
+// READ CACHE / PREFETCH
+// ============================================================================
+
+#[test]
+fn test_find_by_ids_preserves_order_and_skips_missing() {
+    let (db, coll_name) = create_test_db("find_by_ids");
+    let collection = db.collection(&coll_name).unwrap();
+
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let doc = HashMap::from([("n".to_string(), json!(i))]);
+        ids.push(db.insert_one(&coll_name, doc).unwrap());
+    }
+
+    let wanted = vec![
+        ids[2].clone(),
+        ironbase_core::DocumentId::Int(999),
+        ids[0].clone(),
+    ];
+    let docs = collection.find_by_ids(&wanted).unwrap();
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs[0]["n"], 2);
+    assert_eq!(docs[1]["n"], 0);
+}
+
+#[test]
+fn test_prefetch_warms_read_cache() {
+    let (db, coll_name) = create_test_db("prefetch");
+
+    let mut ids = Vec::new();
+    for i in 0..10 {
+        let doc = HashMap::from([("n".to_string(), json!(i))]);
+        ids.push(db.insert_one(&coll_name, doc).unwrap());
+    }
+
+    let collection = db.collection(&coll_name).unwrap().with_read_cache(100);
+
+    let mut wanted = ids[..5].to_vec();
+    wanted.push(ironbase_core::DocumentId::Int(999));
+    assert_eq!(collection.prefetch(&wanted).unwrap(), 5);
+
+    let before = collection.read_cache.stats();
+    assert_eq!(before.size, 5);
+
+    let docs = collection.find_by_ids(&ids[..5]).unwrap();
+    assert_eq!(docs.len(), 5);
+    for id in &ids[..5] {
+        let query = json!({"_id": serde_json::to_value(id).unwrap()});
+        assert!(collection.find_one(&query).unwrap().is_some());
+    }
+
+    // Every read was served from the cache: no new misses, no storage reads
+    let after = collection.read_cache.stats();
+    assert_eq!(after.misses, before.misses);
+    assert_eq!(after.hits, before.hits + 10);
+}
+
+#[test]
+fn test_prefetch_noop_when_cache_disabled() {
+    let (db, coll_name) = create_test_db("prefetch_disabled");
+    let id = db
+        .insert_one(&coll_name, HashMap::from([("n".to_string(), json!(1))]))
+        .unwrap();
+
+    let collection = db.collection(&coll_name).unwrap();
+    assert_eq!(collection.prefetch(&[id]).unwrap(), 0);
+    assert_eq!(collection.read_cache.stats().size, 0);
+}
+
+#[test]
+fn test_read_cache_not_stale_after_update_and_compaction() {
+    let (db, coll_name) = create_test_db("read_cache_stale");
+    let id = db
+        .insert_one(&coll_name, HashMap::from([("v".to_string(), json!(1))]))
+        .unwrap();
+
+    let collection = db.collection(&coll_name).unwrap().with_read_cache(10);
+    assert_eq!(collection.prefetch(std::slice::from_ref(&id)).unwrap(), 1);
+
+    db.update_one(&coll_name, &json!({"v": 1}), &json!({"$set": {"v": 2}}))
+        .unwrap();
+    assert_eq!(
+        collection.find_by_ids(std::slice::from_ref(&id)).unwrap()[0]["v"],
+        2
+    );
+
+    db.compact().unwrap();
+    db.update_one(&coll_name, &json!({"v": 2}), &json!({"$set": {"v": 3}}))
+        .unwrap();
+    assert_eq!(
+        collection.find_by_ids(std::slice::from_ref(&id)).unwrap()[0]["v"],
+        3
+    );
+}
+
+// ========== DOCUMENT PADDING TESTS ==========
+
+/// `create_test_db` in Batch durability mode, where padded slots are overwritten in place
+fn create_batch_test_db(name: &str) -> (DatabaseCore<StorageEngine>, String) {
+    let (db, coll_name) = create_test_db(name);
+    let path = db.path().to_string();
+    drop(db);
+    let db = DatabaseCore::open_with_options(
+        &path,
+        DatabaseOptions::new().with_durability(DurabilityMode::Batch { batch_size: 10 }),
+    )
+    .unwrap();
+    (db, coll_name)
+}
+
+/// Apply `updates` small growing updates to one document, return (tombstones on disk, final doc)
+fn run_growing_updates(name: &str, padding: usize, updates: usize) -> (usize, serde_json::Value) {
+    let (db, coll_name) = create_batch_test_db(name);
+    db.set_document_padding(padding);
+    db.insert_one(
+        &coll_name,
+        HashMap::from([
+            ("key".to_string(), json!("k")),
+            ("log".to_string(), json!("")),
+        ]),
+    )
+    .unwrap();
+
+    for i in 0..updates {
+        let log = "x".repeat(i + 1);
+        db.update_one(
+            &coll_name,
+            &json!({"key": "k"}),
+            &json!({"$set": {"log": log}}),
+        )
+        .unwrap();
+    }
+    db.flush().unwrap();
+
+    let doc = db
+        .collection(&coll_name)
+        .unwrap()
+        .find_one(&json!({"key": "k"}))
+        .unwrap()
+        .unwrap();
+    let path = db.path().to_string();
+    let bytes = std::fs::read(path).unwrap();
+    let needle = b"\"_tombstone\":true";
+    let tombstones = bytes.windows(needle.len()).filter(|w| w == needle).count();
+    (tombstones, doc)
+}
+
+#[test]
+fn test_padding_reduces_tombstones_for_small_updates() {
+    let (plain_tombstones, plain_doc) = run_growing_updates("padding_off", 0, 50);
+    let (padded_tombstones, padded_doc) = run_growing_updates("padding_on", 256, 50);
+
+    assert_eq!(plain_doc["log"], json!("x".repeat(50)));
+    assert_eq!(padded_doc, plain_doc);
+
+    assert!(plain_tombstones >= 50, "got {}", plain_tombstones);
+    assert!(
+        padded_tombstones * 10 <= plain_tombstones,
+        "padded {} vs plain {}",
+        padded_tombstones,
+        plain_tombstones
+    );
+}
+
+#[test]
+fn test_padding_keeps_appending_in_safe_mode() {
+    let (db, coll_name) = create_test_db("padding_safe");
+    db.set_document_padding(256);
+    db.insert_one(&coll_name, HashMap::from([("key".to_string(), json!("k"))]))
+        .unwrap();
+    for i in 0..5 {
+        db.update_one(&coll_name, &json!({"key": "k"}), &json!({"$set": {"n": i}}))
+            .unwrap();
+    }
+
+    // Every update appended a new version; none overwrote the committed one
+    let bytes = std::fs::read(db.path()).unwrap();
+    let needle = b"\"_tombstone\":true";
+    let tombstones = bytes.windows(needle.len()).filter(|w| w == needle).count();
+    assert_eq!(tombstones, 5);
+}
+
+#[test]
+fn test_padding_falls_back_to_append_when_slot_too_small() {
+    let (db, coll_name) = create_batch_test_db("padding_fallback");
+    db.set_document_padding(64);
+    db.insert_one(&coll_name, HashMap::from([("key".to_string(), json!("k"))]))
+        .unwrap();
+
+    let big = "y".repeat(500);
+    db.update_many(
+        &coll_name,
+        &json!({"key": "k"}),
+        &json!({"$set": {"big": big}}),
+    )
+    .unwrap();
+    db.update_many(&coll_name, &json!({"key": "k"}), &json!({"$set": {"n": 1}}))
+        .unwrap();
+
+    let collection = db.collection(&coll_name).unwrap();
+    let docs = collection.find(&json!({"key": "k"})).unwrap();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["big"], json!(big));
+    assert_eq!(docs[0]["n"], json!(1));
+    assert_eq!(collection.count_documents(&json!({})).unwrap(), 1);
+
+    // In-place and appended versions both survive a reopen
+    let path = db.path().to_string();
+    drop(collection);
+    drop(db);
+    let db = DatabaseCore::open(&path).unwrap();
+    let docs = db.collection(&coll_name).unwrap().find(&json!({})).unwrap();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["n"], json!(1));
+}
+
+// ========== MIGRATION TESTS ==========
+
+#[test]
+fn test_migrate_backfills_default_and_maintains_indexes() {
+    let (db, coll_name) = create_test_db("migrate");
+    for (name, role) in [("ann", Some("admin")), ("bob", None), ("cid", None)] {
+        let mut doc = HashMap::from([("name".to_string(), json!(name))]);
+        if let Some(role) = role {
+            doc.insert("role".to_string(), json!(role));
+        }
+        db.insert_one(&coll_name, doc).unwrap();
+    }
+    let collection = db.collection(&coll_name).unwrap();
+    collection.create_index("role".to_string(), false).unwrap();
+    assert!(collection
+        .find(&json!({"role": "member"}))
+        .unwrap()
+        .is_empty());
+
+    let changed = collection
+        .migrate(|doc| {
+            if doc.get("role").is_none() {
+                doc["role"] = json!("member");
+            }
+        })
+        .unwrap();
+    assert_eq!(changed, 2);
+
+    // The index sees the backfilled values, the cache doesn't serve stale results
+    let plan = collection.explain(&json!({"role": "member"})).unwrap();
+    assert_eq!(plan["queryPlan"], "IndexScan");
+    let mut members: Vec<String> = collection
+        .find(&json!({"role": "member"}))
+        .unwrap()
+        .iter()
+        .map(|d| d["name"].as_str().unwrap().to_string())
+        .collect();
+    members.sort();
+    assert_eq!(members, ["bob", "cid"]);
+    assert_eq!(collection.find(&json!({"role": "admin"})).unwrap().len(), 1);
+    assert_eq!(collection.count_documents(&json!({})).unwrap(), 3);
+
+    // Running it again changes nothing
+    let changed = collection
+        .migrate(|doc| {
+            if doc.get("role").is_none() {
+                doc["role"] = json!("member");
+            }
+        })
+        .unwrap();
+    assert_eq!(changed, 0);
+}
+
+#[test]
+fn test_update_many_rename_moves_index_to_new_field() {
+    let (db, coll_name) = create_test_db("rename_index");
+    for n in 0..5 {
+        db.insert_one(&coll_name, HashMap::from([("sku".to_string(), json!(n))]))
+            .unwrap();
+    }
+    let collection = db.collection(&coll_name).unwrap();
+    let old_index = collection.create_index("sku".to_string(), true).unwrap();
+
+    let (matched, modified) = db
+        .update_many(&coll_name, &json!({}), &json!({"$rename": {"sku": "code"}}))
+        .unwrap();
+    assert_eq!((matched, modified), (5, 5));
+
+    let collection = db.collection(&coll_name).unwrap();
+    let indexes = collection.list_indexes();
+    assert!(!indexes.contains(&old_index), "{:?}", indexes);
+    let new_index = collection.has_index_on("code").unwrap();
+    assert!(new_index.unique);
+    assert!(collection.has_index_on("sku").is_none());
+
+    let plan = collection.explain(&json!({"code": 3})).unwrap();
+    assert_eq!(plan["queryPlan"], "IndexScan");
+    assert_eq!(collection.find(&json!({"code": 3})).unwrap().len(), 1);
+    assert!(collection.find(&json!({"sku": 3})).unwrap().is_empty());
+}
+
+#[test]
+fn test_partial_rename_keeps_old_index_while_field_is_in_use() {
+    let (db, coll_name) = create_test_db("rename_partial");
+    for n in 0..4 {
+        db.insert_one(&coll_name, HashMap::from([("sku".to_string(), json!(n))]))
+            .unwrap();
+    }
+    let collection = db.collection(&coll_name).unwrap();
+    collection.create_index("sku".to_string(), false).unwrap();
+
+    db.update_many(
+        &coll_name,
+        &json!({"sku": {"$lt": 2}}),
+        &json!({"$rename": {"sku": "code"}}),
+    )
+    .unwrap();
+
+    // Both fields now hold values, so both are indexed
+    let collection = db.collection(&coll_name).unwrap();
+    assert!(collection.has_index_on("sku").is_some());
+    assert!(collection.has_index_on("code").is_some());
+    assert_eq!(collection.find(&json!({"sku": 3})).unwrap().len(), 1);
+    assert_eq!(collection.find(&json!({"code": 1})).unwrap().len(), 1);
+}
+
+#[test]
+fn test_migrate_rejects_id_changes_and_unique_violations() {
+    let (db, coll_name) = create_test_db("migrate_reject");
+    for n in 0..3 {
+        db.insert_one(
+            &coll_name,
+            HashMap::from([("email".to_string(), json!(format!("u{}@x.com", n)))]),
+        )
+        .unwrap();
+    }
+    let collection = db.collection(&coll_name).unwrap();
+    collection.create_index("email".to_string(), true).unwrap();
+
+    let err = collection
+        .migrate(|doc| doc["_id"] = json!("renamed"))
+        .unwrap_err();
+    assert!(err.to_string().contains("_id"), "{}", err);
+
+    // Collapsing the unique emails fails, and nothing is written
+    assert!(collection
+        .migrate(|doc| doc["email"] = json!("same@x.com"))
+        .is_err());
+    assert!(collection
+        .find(&json!({"email": "same@x.com"}))
+        .unwrap()
+        .is_empty());
+
+    // A failing transform aborts before any write
+    let mut seen = 0;
+    let result = collection.try_migrate(|doc| {
+        seen += 1;
+        if seen == 3 {
+            return Err(ironbase_core::MongoLiteError::InvalidQuery(
+                "bad".to_string(),
+            ));
+        }
+        doc["flag"] = json!(true);
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert!(collection.find(&json!({"flag": true})).unwrap().is_empty());
+}
@@ -1,7 +1,10 @@
 // Storage compaction tests using public DatabaseCore API
-use ironbase_core::{DatabaseCore, StorageEngine};
+use ironbase_core::{DatabaseCore, DatabaseOptions, StorageEngine};
 use serde_json::json;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
 use tempfile::TempDir;
 
 #[test]
@@ -199,3 +202,172 @@ fn test_compaction_persistence() {
         assert_eq!(docs.len(), 5);
     }
 }
+
+/// Write `count` documents and delete all but `keep` of them, then close
+fn write_and_delete(db_path: &std::path::Path, count: i64, keep: i64) {
+    let db = DatabaseCore::<StorageEngine>::open(db_path).unwrap();
+    for i in 0..count {
+        let mut doc = HashMap::new();
+        doc.insert("id".to_string(), json!(i));
+        doc.insert("payload".to_string(), json!("x".repeat(200)));
+        db.insert_one("items", doc).unwrap();
+    }
+    db.delete_many("items", &json!({"id": {"$gte": keep}}))
+        .unwrap();
+    db.flush().unwrap();
+}
+
+#[test]
+fn test_compact_on_open_reclaims_fragmented_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("compact_on_open.mlite");
+    write_and_delete(&db_path, 200, 20);
+
+    let size_before = std::fs::metadata(&db_path).unwrap().len();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        &db_path,
+        DatabaseOptions::new().with_compact_on_open(0.5),
+    )
+    .unwrap();
+
+    assert!(std::fs::metadata(&db_path).unwrap().len() < size_before);
+    assert!(db.fragmentation_ratio().unwrap() < 0.5);
+    let mut ids: Vec<i64> = db
+        .collection("items")
+        .unwrap()
+        .find(&json!({}))
+        .unwrap()
+        .iter()
+        .map(|doc| doc["id"].as_i64().unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_compact_on_open_leaves_compact_file_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("compact_on_open_low.mlite");
+    write_and_delete(&db_path, 100, 95);
+
+    let size_before = std::fs::metadata(&db_path).unwrap().len();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        &db_path,
+        DatabaseOptions::new().with_compact_on_open(0.5),
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::metadata(&db_path).unwrap().len(), size_before);
+    assert!(db.fragmentation_ratio().unwrap() < 0.5);
+    let count = db
+        .collection("items")
+        .unwrap()
+        .count_documents(&json!({}))
+        .unwrap();
+    assert_eq!(count, 95);
+}
+
+#[test]
+fn test_compact_verified_passes_and_keeps_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("compact_verified.mlite");
+
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    for i in 0..10 {
+        let mut doc = HashMap::new();
+        doc.insert("n".to_string(), json!(i));
+        db.insert_one("items", doc).unwrap();
+    }
+    db.delete_many("items", &json!({"n": {"$lt": 3}})).unwrap();
+    db.update_one("items", &json!({"n": 5}), &json!({"$set": {"tag": "x"}}))
+        .unwrap();
+
+    let stats = db.compact_verified().unwrap();
+    let verification = stats.verification.expect("verification result");
+    assert!(verification.passed());
+    assert_eq!(verification.actual_documents, 7);
+    assert_eq!(stats.documents_kept, 7);
+
+    // Plain compaction does not verify
+    assert!(db.compact().unwrap().verification.is_none());
+
+    let coll = db.collection("items").unwrap();
+    assert_eq!(coll.find(&json!({})).unwrap().len(), 7);
+    assert_eq!(coll.find(&json!({"tag": "x"})).unwrap().len(), 1);
+}
+
+#[test]
+fn test_reads_during_compaction_see_whole_documents() {
+    const STABLE: i64 = 100;
+    const CHURNED: i64 = 100;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db = Arc::new(
+        DatabaseCore::<StorageEngine>::open(temp_dir.path().join("stress.mlite")).unwrap(),
+    );
+    for id in 0..STABLE + CHURNED {
+        db.insert_one(
+            "items",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("value".to_string(), json!(id * 10)),
+                ("pad".to_string(), json!("x".repeat(64))),
+            ]),
+        )
+        .unwrap();
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let db = Arc::clone(&db);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let items = db.collection("items").unwrap();
+                let mut reads = 0u64;
+                while !done.load(Ordering::Relaxed) || reads == 0 {
+                    for id in 0..STABLE + CHURNED {
+                        // An error here would mean a stale offset hit rewritten bytes
+                        let found = items.find_one(&json!({"_id": id})).unwrap();
+                        match found {
+                            Some(doc) => {
+                                assert_eq!(doc["_id"], id);
+                                assert_eq!(doc["value"], id * 10);
+                            }
+                            None => assert!(id >= STABLE, "stable document {} vanished", id),
+                        }
+                        reads += 1;
+                    }
+                }
+                reads
+            })
+        })
+        .collect();
+
+    // Rewrite and delete churned documents, compacting in between
+    for round in 0..10i64 {
+        for id in STABLE..STABLE + CHURNED {
+            if (id + round) % 3 == 0 {
+                db.delete_one("items", &json!({"_id": id})).unwrap();
+            } else {
+                db.update_one(
+                    "items",
+                    &json!({"_id": id}),
+                    &json!({"$set": {"round": round}}),
+                )
+                .unwrap();
+            }
+        }
+        db.compact().unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+    let items = db.collection("items").unwrap();
+    for id in 0..STABLE {
+        let doc = items.find_one(&json!({"_id": id})).unwrap().unwrap();
+        assert_eq!(doc["value"], id * 10);
+    }
+}
@@ -0,0 +1,126 @@
+// count_documents through an index, measured with a counting allocator
+use ironbase_core::{DatabaseCore, StorageEngine};
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::TempDir;
+
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(now, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Peak bytes allocated on top of the current baseline while running `f`
+fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let result = f();
+    (result, PEAK.load(Ordering::SeqCst) - baseline)
+}
+
+const DOCS: i64 = 20_000;
+
+fn scored_collection(db: &DatabaseCore<StorageEngine>) {
+    let docs: Vec<HashMap<String, Value>> = (0..DOCS)
+        .map(|i| {
+            HashMap::from([
+                ("score".to_string(), json!(i)),
+                ("bucket".to_string(), json!(i % 4)),
+                (
+                    "items".to_string(),
+                    json!([{"price": i % 7}, {"price": i % 7 + 1}]),
+                ),
+            ])
+        })
+        .collect();
+    db.insert_many("scores", docs).unwrap();
+}
+
+#[test]
+fn test_indexed_range_count_builds_no_id_vector() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("count.mlite")).unwrap();
+    db.collection("scores")
+        .unwrap()
+        .create_index("score".to_string(), false)
+        .unwrap();
+    scored_collection(&db);
+
+    let collection = db.collection("scores").unwrap();
+    let range = json!({"score": {"$gte": 1000, "$lt": 19000}});
+
+    let (count, count_peak) = peak_allocation(|| collection.count_documents(&range).unwrap());
+    let (found, find_peak) = peak_allocation(|| collection.find(&range).unwrap());
+
+    assert_eq!(count, 18_000);
+    assert_eq!(found.len(), 18_000);
+    // One DocumentId per match alone would be hundreds of KB
+    assert!(count_peak < 16 * 1024, "count peak: {} bytes", count_peak);
+    assert!(
+        count_peak * 100 < find_peak,
+        "count {} vs find {}",
+        count_peak,
+        find_peak
+    );
+
+    // Open-ended range: candidates are read and checked one at a time
+    let open = json!({"score": {"$gte": 2000}});
+    let (count, open_peak) = peak_allocation(|| collection.count_documents(&open).unwrap());
+    assert_eq!(count, 18_000);
+    assert!(
+        open_peak < 64 * 1024,
+        "open range peak: {} bytes",
+        open_peak
+    );
+}
+
+#[test]
+fn test_indexed_count_matches_find() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("count.mlite")).unwrap();
+    let collection = db.collection("scores").unwrap();
+    for field in ["score", "bucket", "items.price"] {
+        collection.create_index(field.to_string(), false).unwrap();
+    }
+    scored_collection(&db);
+    db.delete_one("scores", &json!({"score": 5})).unwrap();
+
+    let collection = db.collection("scores").unwrap();
+
+    for query in [
+        json!({"score": 7}),
+        json!({"score": 5}),
+        json!({"score": -1}),
+        json!({"bucket": 1}),
+        json!({"bucket": 1, "score": {"$lt": 100}}),
+        json!({"score": {"$gt": 10, "$lte": 50}}),
+        json!({"score": {"$lt": 30}}),
+        // Multikey: each document has two prices, some both in range
+        json!({"items.price": {"$gte": 2, "$lte": 4}}),
+        json!({"items.price": 3}),
+    ] {
+        let count = collection.count_documents(&query).unwrap();
+        let found = collection.find(&query).unwrap().len() as u64;
+        assert_eq!(count, found, "query {}", query);
+    }
+}
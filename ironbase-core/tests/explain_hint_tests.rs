@@ -73,6 +73,43 @@ fn test_explain_without_index() {
     assert_eq!(plan.get("estimatedCost").unwrap(), "O(n)");
 }
 
+#[test]
+fn test_explain_execution_reports_work_done() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    for i in 0..100 {
+        db.insert_one(
+            "users",
+            std::collections::HashMap::from([
+                ("age".to_string(), json!(i % 50)),
+                ("name".to_string(), json!(format!("user{}", i))),
+            ]),
+        )
+        .unwrap();
+    }
+    let collection = db.collection("users").unwrap();
+    collection.create_index("age".to_string(), false).unwrap();
+
+    let indexed = collection.explain_execution(&json!({"age": 7})).unwrap();
+    assert_eq!(indexed["queryPlan"], "IndexScan");
+    let stats = &indexed["executionStats"];
+    assert_eq!(stats["indexUsed"], "users_age");
+    assert_eq!(stats["docsExamined"], 2);
+    assert_eq!(stats["nReturned"], 2);
+    assert!(stats["executionTimeMicros"].is_u64());
+
+    let scanned = collection
+        .explain_execution(&json!({"name": "user3"}))
+        .unwrap();
+    assert_eq!(scanned["queryPlan"], "CollectionScan");
+    let stats = &scanned["executionStats"];
+    assert!(stats["indexUsed"].is_null());
+    assert_eq!(stats["docsExamined"], 100);
+    assert_eq!(stats["nReturned"], 1);
+}
+
 #[test]
 fn test_hint_forces_index_usage() {
     let temp_dir = TempDir::new().unwrap();
@@ -212,3 +249,47 @@ fn test_explain_and_hint_consistency() {
     assert_eq!(results_auto.len(), results_hint.len());
     assert_eq!(results_auto.len(), 1);
 }
+
+#[test]
+fn test_suggest_indexes_ranks_hot_equality_field_first() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::open(temp_dir.path().join("advisor.mlite")).unwrap();
+    let docs = (0..200)
+        .map(|i| {
+            let region = ["eu", "us", "apac"][i % 3];
+            serde_json::from_value(json!({
+                "customer_id": i,
+                "status": if i % 2 == 0 { "open" } else { "closed" },
+                "region": region,
+                "total": i * 10,
+            }))
+            .unwrap()
+        })
+        .collect();
+    db.insert_many("orders", docs).unwrap();
+    let orders = db.collection("orders").unwrap();
+    orders.create_index("region".to_string(), false).unwrap();
+
+    let mut workload = Vec::new();
+    for i in 0..20 {
+        workload.push(json!({"customer_id": i}));
+    }
+    workload.push(json!({"status": "open"}));
+    workload.push(json!({"filter": {"status": "closed"}, "sort": {"total": -1}}));
+    workload.push(json!({"region": "eu", "total": {"$gte": 1000}}));
+
+    let suggestions = orders.suggest_indexes(&workload).unwrap();
+    assert_eq!(suggestions[0].field, "customer_id");
+    assert_eq!(suggestions[0].query_count, 20);
+    assert!(suggestions[0].estimated_selectivity < 0.01);
+    assert_eq!(
+        suggestions[0].create_index,
+        "create_index(\"customer_id\", false)"
+    );
+
+    // Already indexed fields are never suggested
+    let fields: Vec<&str> = suggestions.iter().map(|s| s.field.as_str()).collect();
+    assert!(!fields.contains(&"region"));
+    assert!(fields.contains(&"status") && fields.contains(&"total"));
+    assert!(suggestions.windows(2).all(|w| w[0].benefit >= w[1].benefit));
+}
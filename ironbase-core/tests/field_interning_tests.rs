@@ -0,0 +1,162 @@
+// Field-name interning in full scans, measured with a counting allocator
+use ironbase_core::{DatabaseCore, DatabaseOptions, StorageEngine};
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::TempDir;
+
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            let now = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(now, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Peak bytes above the baseline and number of allocations made while running `f`
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    let result = f();
+    (
+        result,
+        PEAK.load(Ordering::SeqCst) - baseline,
+        ALLOCATIONS.load(Ordering::SeqCst) - allocations,
+    )
+}
+
+const FIELDS: usize = 20;
+const DOCS: usize = 3000;
+
+fn wide_collection(db: &DatabaseCore<StorageEngine>) {
+    let docs: Vec<HashMap<String, Value>> = (0..DOCS)
+        .map(|i| {
+            (0..FIELDS)
+                .map(|f| (format!("customer_attribute_{:02}", f), json!(i * f)))
+                .chain([("group".to_string(), json!(i % 3))])
+                .collect()
+        })
+        .collect();
+    db.insert_many("wide", docs).unwrap();
+}
+
+#[test]
+fn test_interned_scan_reduces_allocations_and_peak_memory() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("wide.mlite")).unwrap();
+    wide_collection(&db);
+
+    let plain = db.collection("wide").unwrap();
+    let interned = db.collection("wide").unwrap().with_field_interning(1024);
+
+    let (plain_docs, plain_peak, plain_allocs) =
+        measure(|| plain.scan_interned(&json!({})).unwrap());
+    drop(plain_docs);
+    let (interned_docs, interned_peak, interned_allocs) =
+        measure(|| interned.scan_interned(&json!({})).unwrap());
+
+    assert_eq!(interned_docs.len(), DOCS);
+    assert!(
+        interned_allocs * 2 < plain_allocs,
+        "interned {} allocations vs plain {}",
+        interned_allocs,
+        plain_allocs
+    );
+    assert!(
+        interned_peak * 10 < plain_peak * 8,
+        "interned peak {} vs plain {}",
+        interned_peak,
+        plain_peak
+    );
+
+    // Savings are reported: every key but the first use of each name is a pool hit
+    let stats = interned.field_interning_stats();
+    assert!(stats.enabled);
+    assert!(stats.distinct_names > FIELDS);
+    assert!(stats.keys >= (DOCS * stats.distinct_names) as u64);
+    assert_eq!(stats.hits, stats.keys - stats.distinct_names as u64);
+    assert!(stats.bytes_saved > (DOCS * FIELDS) as u64);
+    assert!(!plain.field_interning_stats().enabled);
+}
+
+#[test]
+fn test_interned_scan_matches_find() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("wide.mlite")).unwrap();
+    wide_collection(&db);
+    db.delete_one("wide", &json!({"customer_attribute_01": 3}))
+        .unwrap();
+
+    let collection = db.collection("wide").unwrap().with_field_interning(64);
+    let query = json!({"group": 0});
+
+    let mut scanned: Vec<Value> = collection
+        .scan_interned(&query)
+        .unwrap()
+        .iter()
+        .map(|doc| doc.to_value())
+        .collect();
+    let mut found = collection.find(&query).unwrap();
+
+    let by_id = |doc: &Value| doc["_id"].as_i64().unwrap();
+    scanned.sort_by_key(by_id);
+    found.sort_by_key(by_id);
+    assert_eq!(scanned.len(), DOCS / 3 - 1);
+    assert_eq!(scanned, found);
+}
+
+#[test]
+fn test_database_option_interns_for_every_handle() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("wide.mlite"),
+        DatabaseOptions::new().with_field_interning(1024),
+    )
+    .unwrap();
+    wide_collection(&db);
+
+    let first = db
+        .collection("wide")
+        .unwrap()
+        .scan_interned(&json!({"group": 1}))
+        .unwrap();
+    let second = db
+        .collection("wide")
+        .unwrap()
+        .scan_interned(&json!({}))
+        .unwrap();
+    assert_eq!(first.len(), DOCS / 3);
+    assert_eq!(second.len(), DOCS);
+
+    // Both handles draw on one pool, so the second scan only hits
+    let stats = db.collection("wide").unwrap().field_interning_stats();
+    assert!(stats.enabled);
+    assert_eq!(stats.hits, stats.keys - stats.distinct_names as u64);
+    assert!(
+        !db.collection("wide")
+            .unwrap()
+            .with_field_interning(0)
+            .field_interning_stats()
+            .enabled
+    );
+}
@@ -371,3 +371,627 @@ fn test_update_one_changes_indexed_value() {
         result2.err()
     );
 }
+
+#[test]
+fn test_old_version_idx_file_is_rebuilt_on_open() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        let collection = db.collection("users").unwrap();
+        collection.create_index("age".to_string(), false).unwrap();
+
+        for (name, age) in [("Alice", 30), ("Bob", 25), ("Carol", 30)] {
+            let mut fields = std::collections::HashMap::new();
+            fields.insert("name".to_string(), json!(name));
+            fields.insert("age".to_string(), json!(age));
+            db.insert_one("users", fields).unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    // Rewrite the persisted index in the pre-versioning layout (no header)
+    let idx_path = std::fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "idx"))
+        .expect("index file should be persisted");
+    let current = std::fs::read(&idx_path).unwrap();
+    assert_eq!(&current[0..8], b"IRONIDX\0");
+    std::fs::write(&idx_path, &current[24..]).unwrap();
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("users").unwrap();
+    assert!(collection.list_indexes().contains(&"users_age".to_string()));
+
+    let mut names: Vec<String> = collection
+        .find(&json!({"age": 30}))
+        .unwrap()
+        .iter()
+        .map(|doc| doc["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["Alice", "Carol"]);
+    assert_eq!(collection.find(&json!({"age": 25})).unwrap().len(), 1);
+
+    // The file was migrated to the current format
+    let migrated = std::fs::read(&idx_path).unwrap();
+    assert_eq!(&migrated[0..8], b"IRONIDX\0");
+}
+
+#[test]
+fn test_index_on_array_of_objects_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let orders = [
+        json!([{"price": 50}, {"price": 150}]),
+        json!([{"price": 20}]),
+        json!([{"price": 300}, {"price": 300}]),
+    ];
+    for (i, items) in orders.iter().enumerate() {
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("order".to_string(), json!(i));
+        fields.insert("items".to_string(), items.clone());
+        db.insert_one("orders", fields).unwrap();
+    }
+
+    let collection = db.collection("orders").unwrap();
+    collection
+        .create_index("items.price".to_string(), false)
+        .unwrap();
+
+    let order_numbers = |query: serde_json::Value| {
+        let mut found: Vec<i64> = db
+            .collection("orders")
+            .unwrap()
+            .find(&query)
+            .unwrap()
+            .iter()
+            .map(|d| d["order"].as_i64().unwrap())
+            .collect();
+        found.sort();
+        found
+    };
+
+    // Each matching document is returned once, even with several matching elements
+    assert_eq!(
+        order_numbers(json!({"items.price": {"$gt": 100}})),
+        vec![0, 2]
+    );
+    assert_eq!(order_numbers(json!({"items.price": 20})), vec![1]);
+    assert!(order_numbers(json!({"items.price": {"$gt": 1000}})).is_empty());
+
+    db.update_one(
+        "orders",
+        &json!({"order": 0}),
+        &json!({"$set": {"items": [{"price": 10}]}}),
+    )
+    .unwrap();
+    db.delete_one("orders", &json!({"order": 2})).unwrap();
+
+    assert!(order_numbers(json!({"items.price": {"$gt": 100}})).is_empty());
+    assert_eq!(
+        order_numbers(json!({"items.price": {"$lt": 50}})),
+        vec![0, 1]
+    );
+}
+
+#[test]
+fn test_bloom_filter_survives_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        db.collection("users")
+            .unwrap()
+            .create_index("email".to_string(), true)
+            .unwrap();
+        for i in 0..200 {
+            let mut fields = std::collections::HashMap::new();
+            fields.insert("email".to_string(), json!(format!("u{}@x", i)));
+            db.insert_one("users", fields).unwrap();
+        }
+        let collection = db.collection("users").unwrap();
+        collection.enable_bloom_filter("users_email").unwrap();
+        assert!(collection.enable_bloom_filter("users_missing").is_err());
+
+        assert!(collection
+            .index_contains("users_email", &json!("u5@x"))
+            .unwrap());
+        assert!(!collection
+            .index_contains("users_email", &json!("nobody@x"))
+            .unwrap());
+        db.flush().unwrap();
+    }
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("users").unwrap();
+    for i in 0..200 {
+        let email = json!(format!("u{}@x", i));
+        assert!(collection.index_contains("users_email", &email).unwrap());
+        assert_eq!(collection.find(&json!({"email": email})).unwrap().len(), 1);
+    }
+    assert!(collection
+        .find(&json!({"email": "nobody@x"}))
+        .unwrap()
+        .is_empty());
+
+    // Unique checks still see every existing key
+    let mut duplicate = std::collections::HashMap::new();
+    duplicate.insert("email".to_string(), json!("u42@x"));
+    assert!(db.insert_one("users", duplicate).is_err());
+}
+
+#[test]
+fn test_index_lookup_by_field() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+    // Collection name containing an underscore, which index-name parsing got wrong
+    let collection = db.collection("user_events").unwrap();
+
+    collection.create_index("age".to_string(), true).unwrap();
+    collection
+        .create_compound_index(vec!["country".to_string(), "city".to_string()], false)
+        .unwrap();
+
+    let age = collection.has_index_on("age").expect("age is indexed");
+    assert_eq!(age.name, "user_events_age");
+    assert_eq!(age.fields, vec!["age".to_string()]);
+    assert!(age.unique);
+    assert!(!age.is_compound());
+
+    let country = collection
+        .has_index_on("country")
+        .expect("leads a compound index");
+    assert_eq!(country.name, "user_events_country_city");
+    assert_eq!(
+        country.fields,
+        vec!["country".to_string(), "city".to_string()]
+    );
+    assert!(country.is_compound());
+    assert!(!country.unique);
+
+    assert!(collection.has_index_on("city").is_none());
+    assert!(collection.has_index_on("name").is_none());
+    assert_eq!(
+        collection.indexed_fields(),
+        vec!["_id", "age", "city", "country"]
+    );
+
+    // A hint on the compound index plans against its leading field
+    db.insert_one(
+        "user_events",
+        std::collections::HashMap::from([
+            ("age".to_string(), json!(30)),
+            ("country".to_string(), json!("HU")),
+            ("city".to_string(), json!("Pécs")),
+        ]),
+    )
+    .unwrap();
+    let collection = db.collection("user_events").unwrap();
+    let found = collection
+        .find_with_hint(&json!({"country": "HU"}), "user_events_country_city")
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
+
+#[test]
+fn test_index_distinguishes_null_from_missing() {
+    let docs = [
+        json!({"n": 1, "v": 3}),
+        json!({"n": 2}),
+        json!({"n": 3, "v": null}),
+        json!({"n": 4, "v": 1}),
+        json!({"n": 5, "v": null}),
+        json!({"n": 6}),
+    ];
+    let queries = [
+        json!({"v": null}),
+        json!({"v": {"$exists": false}}),
+        json!({"v": {"$exists": true}}),
+        json!({"v": {"$ne": null}}),
+    ];
+
+    let mut results = Vec::new();
+    for indexed in [false, true] {
+        let temp_dir = TempDir::new().unwrap();
+        let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+        let collection = db.collection("items").unwrap();
+        if indexed {
+            collection.create_index("v".to_string(), false).unwrap();
+        }
+        for doc in &docs {
+            db.insert_one(
+                "items",
+                doc.as_object().unwrap().clone().into_iter().collect(),
+            )
+            .unwrap();
+        }
+
+        let collection = db.collection("items").unwrap();
+        let found: Vec<Vec<i64>> = queries
+            .iter()
+            .map(|query| {
+                let mut ns: Vec<i64> = collection
+                    .find(query)
+                    .unwrap()
+                    .iter()
+                    .map(|d| d["n"].as_i64().unwrap())
+                    .collect();
+                ns.sort();
+                ns
+            })
+            .collect();
+        assert_eq!(collection.count_documents(&json!({"v": null})).unwrap(), 2);
+        results.push(found);
+    }
+
+    // Equality on null matches explicit nulls only, with or without the index
+    assert_eq!(results[0][0], [3, 5]);
+    assert_eq!(results[0][1], [2, 6]);
+    assert_eq!(results[0][2], [1, 3, 4, 5]);
+    assert_eq!(results[0][3], [1, 2, 4, 6]);
+    assert_eq!(results[0], results[1]);
+}
+
+#[test]
+fn test_exists_query_uses_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+    let collection = db.collection("users").unwrap();
+    collection.create_index("email".to_string(), false).unwrap();
+    for n in 0..20 {
+        let doc = match n % 4 {
+            0 => json!({"n": n, "email": format!("u{}@example.com", n)}),
+            1 => json!({"n": n, "email": null}),
+            _ => json!({"n": n}),
+        };
+        db.insert_one(
+            "users",
+            doc.as_object().unwrap().clone().into_iter().collect(),
+        )
+        .unwrap();
+    }
+
+    let collection = db.collection("users").unwrap();
+    let query = json!({"email": {"$exists": true}});
+    let before = db.metrics();
+    let mut ns: Vec<i64> = collection
+        .find(&query)
+        .unwrap()
+        .iter()
+        .map(|d| d["n"].as_i64().unwrap())
+        .collect();
+    ns.sort();
+    assert_eq!(collection.count_documents(&query).unwrap(), 10);
+    let after = db.metrics();
+    assert_eq!(ns, (0..20).filter(|n| n % 4 < 2).collect::<Vec<i64>>());
+    assert_eq!(after.collection_scans, before.collection_scans);
+    assert_eq!(after.index_hits - before.index_hits, 2);
+
+    let plan = collection.explain(&query).unwrap();
+    assert_eq!(plan["queryPlan"], "IndexExistsScan");
+    assert_eq!(plan["indexUsed"], "users_email");
+
+    // $exists: false matches exactly what the index leaves out, so it scans
+    let missing = collection
+        .find(&json!({"email": {"$exists": false}}))
+        .unwrap();
+    assert_eq!(missing.len(), 10);
+    assert_eq!(db.metrics().collection_scans, after.collection_scans + 1);
+}
+
+#[test]
+fn test_compound_range_query_uses_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("events.mlite");
+    let insert =
+        |db: &DatabaseCore<ironbase_core::StorageEngine>, n: i64, tenant: &str, ts: i64| {
+            let doc = json!({"n": n, "tenant": tenant, "ts": ts});
+            db.insert_one(
+                "events",
+                doc.as_object().unwrap().clone().into_iter().collect(),
+            )
+            .unwrap();
+        };
+    let query = json!({"tenant": "t1", "ts": {"$gte": 100, "$lt": 300}});
+    let found_ns = |db: &DatabaseCore<ironbase_core::StorageEngine>| {
+        let mut ns: Vec<i64> = db
+            .collection("events")
+            .unwrap()
+            .find(&query)
+            .unwrap()
+            .iter()
+            .map(|d| d["n"].as_i64().unwrap())
+            .collect();
+        ns.sort();
+        ns
+    };
+
+    {
+        let db = DatabaseCore::open(&db_path).unwrap();
+        // Documents from before and after the index is created
+        insert(&db, 1, "t1", 50);
+        insert(&db, 2, "t1", 100);
+        db.collection("events")
+            .unwrap()
+            .create_compound_index(vec!["tenant".to_string(), "ts".to_string()], false)
+            .unwrap();
+        insert(&db, 3, "t1", 250);
+        insert(&db, 4, "t1", 300);
+        insert(&db, 5, "t2", 150);
+        insert(&db, 6, "t0", 200);
+
+        let before = db.metrics();
+        assert_eq!(found_ns(&db), [2, 3]);
+        let collection = db.collection("events").unwrap();
+        assert_eq!(collection.count_documents(&query).unwrap(), 2);
+        let after = db.metrics();
+        assert_eq!(after.index_hits - before.index_hits, 2);
+        assert_eq!(after.collection_scans, before.collection_scans);
+
+        let plan = collection.explain(&query).unwrap();
+        assert_eq!(plan["queryPlan"], "CompoundRangeScan");
+        assert_eq!(plan["indexUsed"], "events_tenant_ts");
+
+        // Updates and deletes move entries in and out of the range
+        db.update_one("events", &json!({"n": 1}), &json!({"$set": {"ts": 120}}))
+            .unwrap();
+        db.delete_one("events", &json!({"n": 2})).unwrap();
+        assert_eq!(found_ns(&db), [1, 3]);
+    }
+
+    // Rebuilt on reopen with compound keys
+    let db = DatabaseCore::open(&db_path).unwrap();
+    assert_eq!(found_ns(&db), [1, 3]);
+    let hinted = db
+        .collection("events")
+        .unwrap()
+        .find_with_hint(&json!({"tenant": "t1"}), "events_tenant_ts")
+        .unwrap();
+    assert_eq!(hinted.len(), 3);
+}
+
+#[test]
+fn test_unique_index_over_existing_duplicates_fails() {
+    use ironbase_core::MongoLiteError;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    for email in ["a@example.com", "b@example.com", "a@example.com"] {
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("email".to_string(), json!(email));
+        db.insert_one("users", fields).unwrap();
+    }
+    let collection = db.collection("users").unwrap();
+
+    let err = collection
+        .create_index("email".to_string(), true)
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::DuplicateKey(_)), "{}", err);
+    assert!(err.to_string().contains("a@example.com"), "{}", err);
+    assert!(!collection
+        .list_indexes()
+        .contains(&"users_email".to_string()));
+
+    // An insert is no longer held to the failed index
+    let mut fields = std::collections::HashMap::new();
+    fields.insert("email".to_string(), json!("b@example.com"));
+    db.insert_one("users", fields).unwrap();
+
+    // Nothing was persisted either
+    db.flush().unwrap();
+    drop(collection);
+    drop(db);
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("users").unwrap();
+    assert_eq!(collection.list_indexes(), vec!["users_id".to_string()]);
+    let index_files: Vec<_> = std::fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.contains("users_email"))
+        .collect();
+    assert!(index_files.is_empty(), "{:?}", index_files);
+
+    // A non-unique index over the same field still builds
+    collection.create_index("email".to_string(), false).unwrap();
+}
+
+#[test]
+fn test_filter_on_id_field_is_not_planned_on_id_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+    for i in 0..5 {
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("id".to_string(), json!(i));
+        db.insert_one("users", fields).unwrap();
+    }
+    let collection = db.collection("users").unwrap();
+
+    // `users_id` indexes `_id`, not `id`
+    let found = collection.find_one(&json!({"id": 0})).unwrap().unwrap();
+    assert_eq!(found["id"], 0);
+    assert_eq!(collection.find(&json!({"id": 3})).unwrap().len(), 1);
+    assert_eq!(collection.count_documents(&json!({"id": 4})).unwrap(), 1);
+}
+
+#[test]
+fn test_multi_field_sort_uses_compound_index_prefix() {
+    use ironbase_core::find_options::FindOptions;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::open(temp_dir.path().join("test.mlite")).unwrap();
+    let collection = db.collection("events").unwrap();
+    collection
+        .create_compound_index(vec!["tenant".to_string(), "ts".to_string()], false)
+        .unwrap();
+
+    for (n, tenant, ts) in [
+        (1, "t1", 300),
+        (2, "t1", 100),
+        (3, "t1", 200),
+        (4, "t1", 100),
+        (5, "t2", 100),
+        (6, "t1", 200),
+        (7, "t1", 100),
+    ] {
+        db.insert_one(
+            "events",
+            std::collections::HashMap::from([
+                ("n".to_string(), json!(n)),
+                ("tenant".to_string(), json!(tenant)),
+                ("ts".to_string(), json!(ts)),
+            ]),
+        )
+        .unwrap();
+    }
+    // A fresh handle sees the index entries written through `db`
+    let collection = db.collection("events").unwrap();
+
+    let query = json!({"tenant": "t1", "ts": {"$gte": 100}});
+    assert_eq!(
+        collection.explain(&query).unwrap()["queryPlan"],
+        "CompoundRangeScan"
+    );
+    let ns = |options: FindOptions| -> Vec<i64> {
+        collection
+            .find_with_options(&query, options)
+            .unwrap()
+            .iter()
+            .map(|d| d["n"].as_i64().unwrap())
+            .collect()
+    };
+
+    // Index order serves tenant and ts; only ts ties are sorted by n
+    let sort = |keys: &[(&str, i32)]| {
+        FindOptions::new().with_sort(keys.iter().map(|(f, d)| (f.to_string(), *d)).collect())
+    };
+    assert_eq!(
+        ns(sort(&[("tenant", 1), ("ts", 1), ("n", -1)])),
+        [7, 4, 2, 6, 3, 1]
+    );
+    assert_eq!(ns(sort(&[("ts", -1), ("n", 1)])), [1, 3, 6, 2, 4, 7]);
+    // A descending indexed key reads the index backwards
+    assert_eq!(
+        ns(sort(&[("tenant", 1), ("ts", -1), ("n", -1)])),
+        [1, 6, 3, 7, 4, 2]
+    );
+
+    // A sort the index fully serves still paginates correctly
+    let page = ns(sort(&[("tenant", 1), ("ts", 1)]).with_skip(3).with_limit(2));
+    assert_eq!(page.len(), 2);
+    let expected: std::collections::HashSet<i64> = [3, 6].into();
+    assert_eq!(
+        page.into_iter().collect::<std::collections::HashSet<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn test_text_index_ranks_and_follows_writes() {
+    use ironbase_core::{TextSearchMode, Tokenizer};
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.mlite");
+    let db = DatabaseCore::open(&db_path).unwrap();
+    for (name, description) in [
+        ("kettle", "Electric kettle with fast boil"),
+        ("charger", "Fast wireless charger, fast charging for phones"),
+        ("cable", "Braided charging cable"),
+        ("lamp", "Desk lamp"),
+    ] {
+        let fields = [
+            ("name".to_string(), json!(name)),
+            ("description".to_string(), json!(description)),
+        ];
+        db.insert_one("products", fields.into_iter().collect())
+            .unwrap();
+    }
+
+    let collection = db.collection("products").unwrap();
+    let name = collection
+        .create_text_index(vec!["description".to_string()], Tokenizer::Standard)
+        .unwrap();
+    assert_eq!(name, "products_description_text");
+    assert!(collection.list_indexes().contains(&name));
+    assert!(collection
+        .create_text_index(vec!["name".to_string()], Tokenizer::Standard)
+        .is_err());
+
+    let names = |docs: Vec<serde_json::Value>| -> Vec<String> {
+        docs.iter()
+            .map(|d| d["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let hits = collection
+        .fulltext_search("FAST charging", TextSearchMode::And, None)
+        .unwrap();
+    assert_eq!(names(hits.clone()), ["charger"]);
+    assert!(hits[0]["_score"].as_f64().unwrap() > 0.0);
+    let hits = collection
+        .fulltext_search("fast charging", TextSearchMode::Or, None)
+        .unwrap();
+    // Equal idf: the shorter cable description outranks the kettle
+    assert_eq!(names(hits.clone()), ["charger", "cable", "kettle"]);
+    assert!(hits[0]["_score"].as_f64() > hits[1]["_score"].as_f64());
+    assert_eq!(
+        collection
+            .fulltext_search("fast charging", TextSearchMode::Or, Some(1))
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // $text combines with the rest of the filter
+    let found = collection
+        .find(&json!({"$text": {"$search": "charging"}, "name": {"$ne": "charger"}}))
+        .unwrap();
+    assert_eq!(names(found), ["cable"]);
+    assert_eq!(
+        collection
+            .count_documents(&json!({"$text": {"$search": "fast charging", "$mode": "and"}}))
+            .unwrap(),
+        1
+    );
+
+    // Writes through the database show up in new handles and survive reopening
+    db.update_one(
+        "products",
+        &json!({"name": "lamp"}),
+        &json!({"$set": {"description": "Lamp with fast charging port"}}),
+    )
+    .unwrap();
+    db.delete_one("products", &json!({"name": "charger"}))
+        .unwrap();
+    let collection = db.collection("products").unwrap();
+    let hits = collection
+        .fulltext_search("fast charging", TextSearchMode::And, None)
+        .unwrap();
+    assert_eq!(names(hits), ["lamp"]);
+    drop(collection);
+    db.flush().unwrap();
+    drop(db);
+
+    let db = DatabaseCore::open(&db_path).unwrap();
+    let collection = db.collection("products").unwrap();
+    let hits = collection
+        .fulltext_search("fast charging", TextSearchMode::And, None)
+        .unwrap();
+    assert_eq!(names(hits), ["lamp"]);
+
+    collection.drop_index("products_description_text").unwrap();
+    assert!(collection
+        .fulltext_search("lamp", TextSearchMode::Or, None)
+        .is_err());
+    assert!(db
+        .collection("products")
+        .unwrap()
+        .fulltext_search("lamp", TextSearchMode::Or, None)
+        .is_err());
+}
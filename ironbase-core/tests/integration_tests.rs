@@ -1,7 +1,12 @@
 // Integration tests for MongoLite Core
-use ironbase_core::{DatabaseCore, Document, DocumentId, StorageEngine};
+use ironbase_core::{
+    BulkOp, CappedLimits, DatabaseCore, DatabaseOptions, Document, DocumentId, MongoLiteError,
+    OnConflict, PatchOp, StorageEngine,
+};
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
 // Helper to create test storage
@@ -317,6 +322,38 @@ fn test_stats_with_collections() {
     assert!(names.contains(&"posts".to_string()));
 }
 
+#[test]
+fn test_stats_report_wal_growth_and_commit_latency() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("wal.mlite")).unwrap();
+    db.checkpoint().unwrap();
+
+    let wal = db.stats()["wal"].clone();
+    assert_eq!(wal["size_bytes"], 0);
+    assert_eq!(wal["entries_since_checkpoint"], 0);
+    assert!(wal["avg_commit_latency_us"].is_null());
+
+    let mut last_size = 0;
+    for i in 0..5 {
+        db.insert_one("events", fields(json!({"n": i}))).unwrap();
+        let wal = db.stats()["wal"].clone();
+        let size = wal["size_bytes"].as_u64().unwrap();
+        assert!(size > last_size, "WAL should grow with every write");
+        last_size = size;
+    }
+
+    let wal = db.stats()["wal"].clone();
+    assert!(wal["entries_since_checkpoint"].as_u64().unwrap() >= 5);
+    assert!(wal["avg_commit_latency_us"].is_u64());
+
+    db.checkpoint().unwrap();
+    let wal = db.stats()["wal"].clone();
+    assert_eq!(wal["entries_since_checkpoint"], 0);
+    assert_eq!(wal["size_bytes"], 0);
+    // The latency average spans checkpoints
+    assert!(wal["avg_commit_latency_us"].is_u64());
+}
+
 #[test]
 fn test_schema_validation_blocks_invalid_insert() {
     let temp_dir = TempDir::new().unwrap();
@@ -958,3 +995,1269 @@ fn test_mixed_nested_and_flat_fields() {
         assert_eq!(agg.len(), 2);
     }
 }
+
+fn fields(value: Value) -> HashMap<String, Value> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_insert_one_conflict_policies() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("conflict.mlite");
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    db.collection("users")
+        .unwrap()
+        .create_index("email".to_string(), false)
+        .unwrap();
+
+    let original = json!({"_id": "u1", "name": "Alice", "email": "a@x.io", "age": 30});
+    db.insert_one("users", fields(original.clone())).unwrap();
+
+    // Error (default): duplicate _id is rejected and nothing changes
+    assert!(db
+        .insert_one_with_conflict("users", fields(json!({"_id": "u1"})), OnConflict::Error)
+        .is_err());
+
+    // Ignore: existing id returned, stored document untouched
+    let id = db
+        .insert_one_with_conflict(
+            "users",
+            fields(json!({"_id": "u1", "name": "Ignored"})),
+            OnConflict::Ignore,
+        )
+        .unwrap();
+    assert_eq!(id, DocumentId::String("u1".to_string()));
+    let stored = db.collection("users").unwrap().find_by_id(&id).unwrap();
+    assert_eq!(stored.unwrap()["name"], "Alice");
+
+    // Replace: new version wins, dropped fields are gone, indexes follow
+    db.insert_one_with_conflict(
+        "users",
+        fields(json!({"_id": "u1", "name": "Alicia", "email": "alicia@x.io"})),
+        OnConflict::Replace,
+    )
+    .unwrap();
+    let users = db.collection("users").unwrap();
+    let stored = users.find_by_id(&id).unwrap().unwrap();
+    assert_eq!(stored["name"], "Alicia");
+    assert!(stored.get("age").is_none());
+    assert_eq!(users.find(&json!({"email": "a@x.io"})).unwrap().len(), 0);
+    assert_eq!(
+        users.find(&json!({"email": "alicia@x.io"})).unwrap().len(),
+        1
+    );
+    assert_eq!(users.count_documents(&json!({})).unwrap(), 1);
+
+    // A free _id inserts normally under every policy
+    for (i, policy) in [OnConflict::Replace, OnConflict::Ignore]
+        .into_iter()
+        .enumerate()
+    {
+        db.insert_one_with_conflict("users", fields(json!({"_id": i as i64 + 10})), policy)
+            .unwrap();
+    }
+    drop(users);
+    drop(db);
+
+    // The replacement is durable
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    let users = db.collection("users").unwrap();
+    assert_eq!(users.count_documents(&json!({})).unwrap(), 3);
+    assert_eq!(users.find_by_id(&id).unwrap().unwrap()["name"], "Alicia");
+}
+
+fn patch(ops: Value) -> Vec<PatchOp> {
+    serde_json::from_value(ops).unwrap()
+}
+
+#[test]
+fn test_patch_one_json_patch() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("patch.mlite");
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    db.collection("items")
+        .unwrap()
+        .create_index("sku".to_string(), false)
+        .unwrap();
+    db.insert_one(
+        "items",
+        fields(json!({"sku": "a-1", "version": 1, "tags": ["x"], "dims": {"w": 2}})),
+    )
+    .unwrap();
+
+    let result = db
+        .patch_one(
+            "items",
+            &json!({"sku": "a-1"}),
+            &patch(json!([
+                {"op": "test", "path": "/version", "value": 1},
+                {"op": "replace", "path": "/version", "value": 2},
+                {"op": "add", "path": "/tags/0", "value": "new"},
+                {"op": "copy", "from": "/dims/w", "path": "/dims/h"},
+                {"op": "move", "from": "/sku", "path": "/code"},
+                {"op": "remove", "path": "/tags/1"},
+            ])),
+        )
+        .unwrap();
+    assert_eq!(result, (1, 1));
+
+    let items = db.collection("items").unwrap();
+    let stored = items.find_one(&json!({"code": "a-1"})).unwrap().unwrap();
+    assert_eq!(stored["version"], 2);
+    assert_eq!(stored["tags"], json!(["new"]));
+    assert_eq!(stored["dims"], json!({"w": 2, "h": 2}));
+    assert!(stored.get("sku").is_none());
+    assert!(items.find(&json!({"sku": "a-1"})).unwrap().is_empty());
+
+    // A failing test aborts the whole patch, including earlier operations
+    let err = db.patch_one(
+        "items",
+        &json!({"code": "a-1"}),
+        &patch(json!([
+            {"op": "replace", "path": "/version", "value": 99},
+            {"op": "test", "path": "/version", "value": 1},
+        ])),
+    );
+    assert!(err.is_err());
+    assert_eq!(
+        db.collection("items")
+            .unwrap()
+            .find_one(&json!({"code": "a-1"}))
+            .unwrap()
+            .unwrap(),
+        stored
+    );
+
+    assert!(db
+        .patch_one(
+            "items",
+            &json!({"code": "a-1"}),
+            &patch(json!([{"op": "replace", "path": "/_id", "value": 5}])),
+        )
+        .is_err());
+    assert_eq!(
+        db.patch_one("items", &json!({"code": "zzz"}), &patch(json!([])))
+            .unwrap(),
+        (0, 0)
+    );
+}
+
+#[test]
+fn test_union_view_across_tenant_collections() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("union.mlite")).unwrap();
+    db.collection("orders_acme")
+        .unwrap()
+        .create_index("status".to_string(), false)
+        .unwrap();
+    for (tenant, count) in [("orders_acme", 4), ("orders_globex", 3)] {
+        let docs = (0..count)
+            .map(|i| fields(json!({"n": i, "status": if i % 2 == 0 { "open" } else { "done" }})))
+            .collect();
+        db.insert_many(tenant, docs).unwrap();
+    }
+    db.insert_one("unrelated", fields(json!({"status": "open"})))
+        .unwrap();
+
+    let names = ["orders_acme", "orders_globex", "orders_acme"].map(String::from);
+    let view = db.union_view(&names).unwrap();
+    assert_eq!(view.collection_names(), ["orders_acme", "orders_globex"]);
+
+    let open = view.find(&json!({"status": "open"})).unwrap();
+    let mut tagged: Vec<(String, i64)> = open
+        .iter()
+        .map(|doc| {
+            (
+                doc["_collection"].as_str().unwrap().to_string(),
+                doc["n"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    tagged.sort();
+    assert_eq!(
+        tagged,
+        [
+            ("orders_acme".to_string(), 0),
+            ("orders_acme".to_string(), 2),
+            ("orders_globex".to_string(), 0),
+            ("orders_globex".to_string(), 2),
+        ]
+    );
+
+    assert_eq!(view.count_documents(&json!({})).unwrap(), 7);
+    assert_eq!(view.count_documents(&json!({"status": "done"})).unwrap(), 3);
+    assert!(db.union_view(&["missing".to_string()]).is_err());
+}
+
+#[test]
+fn test_collection_exists_and_explicit_creation() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("create.mlite")).unwrap();
+    assert!(!db.collection_exists("users"));
+
+    let users = db.create_collection("users").unwrap();
+    assert_eq!(users.count_documents(&json!({})).unwrap(), 0);
+    db.insert_one("users", fields(json!({"name": "Ann"})))
+        .unwrap();
+    assert!(db.collection_exists("users"));
+    assert!(matches!(
+        db.create_collection("users"),
+        Err(MongoLiteError::CollectionExists(name)) if name == "users"
+    ));
+    assert_eq!(
+        db.collection("users")
+            .unwrap()
+            .count_documents(&json!({}))
+            .unwrap(),
+        1
+    );
+
+    // No auto-create: a typo is an error and leaves no collection behind
+    assert!(matches!(
+        db.existing_collection("usres"),
+        Err(MongoLiteError::CollectionNotFound(name)) if name == "usres"
+    ));
+    assert!(!db.collection_exists("usres"));
+    assert_eq!(
+        db.existing_collection("users")
+            .unwrap()
+            .count_documents(&json!({}))
+            .unwrap(),
+        1
+    );
+
+    db.drop_collection("users").unwrap();
+    assert!(!db.collection_exists("users"));
+    assert!(db.create_collection("users").is_ok());
+}
+
+#[test]
+fn test_custom_wal_path_is_written_and_recovered() {
+    let data_dir = TempDir::new().unwrap();
+    let wal_dir = TempDir::new().unwrap();
+    let db_path = data_dir.path().join("app.mlite");
+    let options = || DatabaseOptions::new().with_wal_path(wal_dir.path());
+
+    let db = DatabaseCore::<StorageEngine>::open_with_options(&db_path, options()).unwrap();
+    for i in 0..3 {
+        db.insert_one("events", fields(json!({"seq": i}))).unwrap();
+    }
+    // Crash: skip the metadata flush on drop, leaving only the WAL
+    std::mem::forget(db);
+
+    let wal_file = wal_dir.path().join("app.wal");
+    assert!(std::fs::metadata(&wal_file).unwrap().len() > 0);
+    assert!(!data_dir.path().join("app.wal").exists());
+
+    let db = DatabaseCore::<StorageEngine>::open_with_options(&db_path, options()).unwrap();
+    let events = db.collection("events").unwrap();
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 3);
+    assert_eq!(events.find(&json!({"seq": 2})).unwrap().len(), 1);
+
+    // Missing WAL directory is rejected at open
+    let missing = DatabaseOptions::new().with_wal_path(wal_dir.path().join("nope/app.wal"));
+    assert!(DatabaseCore::<StorageEngine>::open_with_options(&db_path, missing).is_err());
+}
+
+#[test]
+fn test_metrics_count_operations() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("metrics.mlite")).unwrap();
+    db.collection("users")
+        .unwrap()
+        .create_index("email".to_string(), true)
+        .unwrap();
+    let start = db.metrics();
+
+    for i in 0..3 {
+        db.insert_one(
+            "users",
+            fields(json!({"email": format!("u{}@x.io", i), "age": 20 + i})),
+        )
+        .unwrap();
+    }
+    db.update_many(
+        "users",
+        &json!({"age": {"$gte": 21}}),
+        &json!({"$set": {"vip": true}}),
+    )
+    .unwrap();
+    db.delete_one("users", &json!({"email": "u0@x.io"}))
+        .unwrap();
+
+    let users = db.collection("users").unwrap();
+    let before_queries = db.metrics();
+    users.find(&json!({"email": "u1@x.io"})).unwrap();
+    users.find(&json!({"email": "u1@x.io"})).unwrap();
+    users.find(&json!({"age": 22})).unwrap();
+    users.count_documents(&json!({"age": 22})).unwrap();
+
+    let m = db.metrics();
+    assert_eq!(m.inserts - start.inserts, 3);
+    assert_eq!(m.updates - start.updates, 2);
+    assert_eq!(m.deletes - start.deletes, 1);
+    assert_eq!(m.wal_flushes - start.wal_flushes, 5);
+    assert_eq!(m.queries - before_queries.queries, 4);
+    // The repeated email lookup is served from the query cache
+    assert_eq!(m.cache_hits - before_queries.cache_hits, 1);
+    assert_eq!(m.cache_misses - before_queries.cache_misses, 2);
+    assert_eq!(m.index_hits - before_queries.index_hits, 1);
+    assert_eq!(m.collection_scans - before_queries.collection_scans, 2);
+
+    // Explicit transactions count when they commit
+    let tx = db.begin_transaction();
+    db.insert_one_tx("users", fields(json!({"email": "tx@x.io"})), tx)
+        .unwrap();
+    assert_eq!(db.metrics().inserts, m.inserts);
+    db.commit_transaction(tx).unwrap();
+    assert_eq!(db.metrics().inserts, m.inserts + 1);
+    assert_eq!(db.metrics().wal_flushes, m.wal_flushes + 1);
+}
+
+#[test]
+fn test_concurrent_insert_if_absent_inserts_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = std::sync::Arc::new(
+        DatabaseCore::<StorageEngine>::open(temp_dir.path().join("seed.mlite")).unwrap(),
+    );
+    let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let db = std::sync::Arc::clone(&db);
+            let barrier = std::sync::Arc::clone(&barrier);
+            std::thread::spawn(move || {
+                barrier.wait();
+                db.insert_if_absent(
+                    "settings",
+                    &json!({"key": "theme"}),
+                    fields(json!({"key": "theme", "value": "dark", "writer": i})),
+                )
+                .unwrap()
+            })
+        })
+        .collect();
+    let results: Vec<(bool, DocumentId)> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(results.iter().filter(|(inserted, _)| *inserted).count(), 1);
+    assert!(results.iter().all(|(_, id)| *id == results[0].1));
+    let settings = db.collection("settings").unwrap();
+    assert_eq!(
+        settings.count_documents(&json!({"key": "theme"})).unwrap(),
+        1
+    );
+
+    // A non-matching query inserts
+    let (inserted, _) = db
+        .insert_if_absent(
+            "settings",
+            &json!({"key": "lang"}),
+            fields(json!({"key": "lang"})),
+        )
+        .unwrap();
+    assert!(inserted);
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[test]
+fn test_run_ttl_sweep_now_expires_stale_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("ttl.mlite");
+    {
+        let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+        db.collection("sessions")
+            .unwrap()
+            .create_ttl_index("created_at".to_string(), 60)
+            .unwrap();
+
+        let now = unix_now();
+        for (user, created_at) in [("old", json!(now - 120)), ("fresh", json!(now))] {
+            db.insert_one(
+                "sessions",
+                fields(json!({"user": user, "created_at": created_at})),
+            )
+            .unwrap();
+        }
+        // Not a timestamp: never expires
+        db.insert_one(
+            "sessions",
+            fields(json!({"user": "text", "created_at": "yesterday"})),
+        )
+        .unwrap();
+
+        assert_eq!(db.run_ttl_sweep_now().unwrap(), 1);
+        assert_eq!(db.run_ttl_sweep_now().unwrap(), 0);
+    }
+
+    // The TTL setting survives a reopen, and the delete was durable
+    let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+    let sessions = db.collection("sessions").unwrap();
+    let mut users: Vec<String> = sessions
+        .find(&json!({}))
+        .unwrap()
+        .iter()
+        .map(|doc| doc["user"].as_str().unwrap().to_string())
+        .collect();
+    users.sort();
+    assert_eq!(users, ["fresh", "text"]);
+
+    db.update_one(
+        "sessions",
+        &json!({"user": "fresh"}),
+        &json!({"$set": {"created_at": unix_now() - 61}}),
+    )
+    .unwrap();
+    assert_eq!(db.run_ttl_sweep_now().unwrap(), 1);
+}
+
+#[test]
+fn test_ttl_sweep_interval_governs_background_sweeps() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = Arc::new(
+        DatabaseCore::<StorageEngine>::open(temp_dir.path().join("ttl_bg.mlite")).unwrap(),
+    );
+    db.collection("events")
+        .unwrap()
+        .create_ttl_index("at".to_string(), 1)
+        .unwrap();
+    let stale = fields(json!({"at": unix_now() - 10}));
+    db.insert_one("events", stale.clone()).unwrap();
+    let count = || {
+        db.collection("events")
+            .unwrap()
+            .count_documents(&json!({}))
+            .unwrap()
+    };
+
+    // Interval 0: no background sweeps
+    db.set_ttl_sweep_interval(0);
+    assert_eq!(db.ttl_sweep_interval(), 0);
+    std::thread::sleep(Duration::from_millis(1500));
+    assert_eq!(count(), 1);
+
+    db.set_ttl_sweep_interval(1);
+    assert_eq!(db.ttl_sweep_interval(), 1);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while count() > 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(count(), 0);
+
+    // Stopping the sweeper leaves later stale documents alone
+    db.set_ttl_sweep_interval(0);
+    db.insert_one("events", stale).unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+    assert_eq!(count(), 1);
+}
+
+#[test]
+fn test_empty_index_result_reads_no_documents() {
+    let db = DatabaseCore::open_memory().unwrap();
+    let docs: Vec<HashMap<String, Value>> = (0..50)
+        .map(|i| HashMap::from([("email".to_string(), json!(format!("u{}@x.io", i)))]))
+        .collect();
+    db.insert_many("users", docs).unwrap();
+    let users = db.collection("users").unwrap();
+    users.create_index("email".to_string(), true).unwrap();
+
+    let before = db.metrics();
+    assert!(users
+        .find(&json!({"email": "nobody@x.io"}))
+        .unwrap()
+        .is_empty());
+    assert!(users
+        .find_one(&json!({"email": "ghost@x.io"}))
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        users
+            .count_documents(&json!({"email": "none@x.io"}))
+            .unwrap(),
+        0
+    );
+    let result = db
+        .update_many(
+            "users",
+            &json!({"email": "other@x.io"}),
+            &json!({"$set": {"seen": true}}),
+        )
+        .unwrap();
+    assert_eq!((result.0, result.1), (0, 0));
+    let after = db.metrics();
+    assert_eq!(after.index_hits - before.index_hits, 4);
+    assert_eq!(after.documents_read, before.documents_read);
+    assert_eq!(after.write_locks, before.write_locks);
+
+    // A hit does read its document
+    assert_eq!(users.find(&json!({"email": "u7@x.io"})).unwrap().len(), 1);
+    assert!(db.metrics().documents_read > after.documents_read);
+}
+
+#[test]
+fn test_bulk_write_locks_and_invalidates_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("bulk.mlite");
+    let mut ops = Vec::new();
+    for i in 0..700 {
+        ops.push(json!({"insertOne": {"document": {"_id": i, "n": i, "odd": i % 2 == 1}}}));
+    }
+    for i in 0..200 {
+        ops.push(json!({"updateOne": {"filter": {"_id": i}, "update": {"$set": {"n": -i}}}}));
+    }
+    for i in 0..99 {
+        ops.push(json!({"deleteOne": {"filter": {"n": 300 + i}}}));
+    }
+    ops.push(json!({"updateMany": {"filter": {"odd": true}, "update": {"$set": {"tag": "odd"}}}}));
+    let ops: Vec<BulkOp> = serde_json::from_value(Value::Array(ops)).unwrap();
+    assert_eq!(ops.len(), 1000);
+
+    {
+        let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+        let items = db.collection("items").unwrap();
+        // Cache a query so the invalidation has something to drop
+        assert!(items.find(&json!({"odd": true})).unwrap().is_empty());
+
+        let before = db.metrics();
+        let result = db.bulk_write("items", &ops, true).unwrap();
+        let after = db.metrics();
+
+        assert!(result.write_errors.is_empty());
+        assert_eq!(result.inserted_count, 700);
+        assert_eq!(result.inserted_ids[699], DocumentId::Int(699));
+        assert_eq!(result.deleted_count, 99);
+        // 200 by _id, then the 350 odd documents minus the 49 odd ones deleted
+        assert_eq!(result.matched_count, 200 + 301);
+        assert_eq!(result.modified_count, 200 + 301);
+        assert_eq!(after.write_locks - before.write_locks, 1);
+        assert_eq!(after.cache_invalidations - before.cache_invalidations, 1);
+        assert_eq!(after.wal_flushes - before.wal_flushes, 1);
+
+        // insert_many goes through the same single pass
+        let docs: Vec<HashMap<String, Value>> = (0..1000)
+            .map(|i| HashMap::from([("k".to_string(), json!(i))]))
+            .collect();
+        db.insert_many("more", docs).unwrap();
+        let end = db.metrics();
+        assert_eq!(end.write_locks - after.write_locks, 1);
+        assert_eq!(end.cache_invalidations - after.cache_invalidations, 1);
+    }
+
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    let items = db.collection("items").unwrap();
+    assert_eq!(items.count_documents(&json!({})).unwrap(), 601);
+    assert_eq!(items.count_documents(&json!({"tag": "odd"})).unwrap(), 301);
+    assert_eq!(
+        items.find_one(&json!({"_id": 5})).unwrap().unwrap()["n"],
+        -5
+    );
+    assert!(items.find_one(&json!({"n": 300})).unwrap().is_none());
+    assert_eq!(
+        db.collection("more")
+            .unwrap()
+            .count_documents(&json!({}))
+            .unwrap(),
+        1000
+    );
+}
+
+#[test]
+fn test_bulk_write_ordered_stops_at_first_error() {
+    let db = DatabaseCore::open_memory().unwrap();
+    db.collection("users")
+        .unwrap()
+        .create_index("email".to_string(), true)
+        .unwrap();
+    let ops: Vec<BulkOp> = serde_json::from_value(json!([
+        {"insertOne": {"document": {"email": "a@x.io"}}},
+        {"insertOne": {"document": {"email": "a@x.io"}}},
+        {"insertOne": {"document": {"email": "b@x.io"}}},
+        {"updateOne": {"filter": {"email": "b@x.io"}, "update": {"$set": {"email": "a@x.io"}}}},
+    ]))
+    .unwrap();
+
+    let ordered = db.bulk_write("users", &ops, true).unwrap();
+    assert_eq!(ordered.inserted_count, 1);
+    assert_eq!(ordered.write_errors.len(), 1);
+    assert_eq!(ordered.write_errors[0].index, 1);
+
+    // Unordered keeps going; the duplicate insert and the update that would
+    // collide both fail without touching the index
+    db.delete_many("users", &json!({})).unwrap();
+    let unordered = db.bulk_write("users", &ops, false).unwrap();
+    assert_eq!(unordered.inserted_count, 2);
+    let failed: Vec<usize> = unordered.write_errors.iter().map(|e| e.index).collect();
+    assert_eq!(failed, [1, 3]);
+    assert_eq!(unordered.matched_count, 1);
+    assert_eq!(unordered.modified_count, 0);
+
+    let users = db.collection("users").unwrap();
+    assert_eq!(users.count_documents(&json!({})).unwrap(), 2);
+    assert_eq!(
+        users.count_documents(&json!({"email": "b@x.io"})).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_aggregation_cache_serves_until_collection_write() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("agg_cache.mlite"),
+        DatabaseOptions::new().with_aggregation_cache(16),
+    )
+    .unwrap();
+    for amount in [10, 20, 30] {
+        db.insert_one(
+            "orders",
+            HashMap::from([("amount".to_string(), json!(amount))]),
+        )
+        .unwrap();
+    }
+    let pipeline = json!([{"$group": {"_id": null, "total": {"$sum": "$amount"}}}]);
+
+    let first = db
+        .collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    assert_eq!(first[0]["total"], 60);
+    // A fresh handle is served from the shared cache
+    let second = db
+        .collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    assert_eq!(second, first);
+    let stats = db.aggregation_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.size), (1, 1, 1));
+
+    // Writes to another collection leave the entry valid
+    db.insert_one("audit", HashMap::from([("n".to_string(), json!(1))]))
+        .unwrap();
+    db.collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    assert_eq!(db.aggregation_cache_stats().hits, 2);
+
+    db.insert_one("orders", HashMap::from([("amount".to_string(), json!(40))]))
+        .unwrap();
+    let after_insert = db
+        .collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    assert_eq!(after_insert[0]["total"], 100);
+
+    // Transactional commits bump the version too
+    let tx_id = db.begin_transaction();
+    db.delete_one_tx("orders", &json!({"amount": 10}), tx_id)
+        .unwrap();
+    db.commit_transaction(tx_id).unwrap();
+    let after_commit = db
+        .collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    assert_eq!(after_commit[0]["total"], 90);
+
+    let stats = db.aggregation_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (2, 3));
+}
+
+#[test]
+fn test_aggregation_cache_skips_sample() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("agg_sample.mlite"),
+        DatabaseOptions::new().with_aggregation_cache(16),
+    )
+    .unwrap();
+    for n in 0..50 {
+        db.insert_one("items", HashMap::from([("n".to_string(), json!(n))]))
+            .unwrap();
+    }
+    let items = db.collection("items").unwrap();
+
+    // Fresh samples each run: 20 draws of 1 out of 50 are not all the same
+    let sample = json!([{"$sample": {"size": 1}}]);
+    let drawn: HashSet<i64> = (0..20)
+        .map(|_| items.aggregate(&sample).unwrap()[0]["n"].as_i64().unwrap())
+        .collect();
+    assert!(drawn.len() > 1);
+    // Also inside $facet
+    let faceted = json!([{"$facet": {"one": [{"$sample": {"size": 1}}]}}]);
+    items.aggregate(&faceted).unwrap();
+    items.aggregate(&faceted).unwrap();
+
+    let stats = db.aggregation_cache_stats();
+    assert_eq!((stats.hits, stats.size), (0, 0));
+}
+
+#[test]
+fn test_aggregation_cache_disabled_by_default() {
+    let db = DatabaseCore::open_memory().unwrap();
+    db.insert_one("orders", HashMap::from([("amount".to_string(), json!(5))]))
+        .unwrap();
+    let pipeline = json!([{"$match": {"amount": 5}}]);
+    let orders = db.collection("orders").unwrap();
+    orders.aggregate(&pipeline).unwrap();
+    orders.aggregate(&pipeline).unwrap();
+
+    let stats = db.aggregation_cache_stats();
+    assert_eq!((stats.capacity, stats.size, stats.hits), (0, 0, 0));
+}
+
+#[test]
+fn test_lookup_joins_other_collection() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("lookup.mlite"),
+        DatabaseOptions::new().with_aggregation_cache(16),
+    )
+    .unwrap();
+    for (id, name) in [(1, "ann"), (2, "bob"), (3, "cid")] {
+        db.insert_one(
+            "customers",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("name".to_string(), json!(name)),
+            ]),
+        )
+        .unwrap();
+    }
+    for (id, customer) in [(10, 1), (11, 2), (12, 1)] {
+        db.insert_one(
+            "orders",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("customer".to_string(), json!(customer)),
+            ]),
+        )
+        .unwrap();
+    }
+    let pipeline = json!([
+        {"$lookup": {"from": "orders", "localField": "_id", "foreignField": "customer", "as": "orders"}},
+        {"$sort": {"_id": 1}}
+    ]);
+    let order_ids = |results: &[Value]| -> Vec<Vec<i64>> {
+        results
+            .iter()
+            .map(|doc| {
+                let mut ids: Vec<i64> = doc["orders"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|o| o["_id"].as_i64().unwrap())
+                    .collect();
+                ids.sort();
+                ids
+            })
+            .collect()
+    };
+    let customers = db.collection("customers").unwrap();
+
+    // Scan join; the customer without orders gets an empty array
+    let scanned = customers.aggregate(&pipeline).unwrap();
+    assert_eq!(order_ids(&scanned), vec![vec![10, 12], vec![11], vec![]]);
+
+    // Same answer through an index on the foreign field
+    db.collection("orders")
+        .unwrap()
+        .create_index("customer".to_string(), false)
+        .unwrap();
+    let indexed = customers.aggregate(&pipeline).unwrap();
+    assert_eq!(order_ids(&indexed), order_ids(&scanned));
+
+    // Writes to the joined collection are seen, not served from the cache
+    db.insert_one(
+        "orders",
+        HashMap::from([
+            ("_id".to_string(), json!(13)),
+            ("customer".to_string(), json!(3)),
+        ]),
+    )
+    .unwrap();
+    let after_insert = customers.aggregate(&pipeline).unwrap();
+    assert_eq!(
+        order_ids(&after_insert),
+        vec![vec![10, 12], vec![11], vec![13]]
+    );
+    assert_eq!(db.aggregation_cache_stats().size, 0);
+
+    // A missing collection joins nothing and is not created
+    let missing = customers
+        .aggregate(&json!([
+            {"$lookup": {"from": "refunds", "localField": "_id", "foreignField": "customer", "as": "refunds"}}
+        ]))
+        .unwrap();
+    assert!(missing.iter().all(|doc| doc["refunds"] == json!([])));
+    assert!(!db.list_collections().contains(&"refunds".to_string()));
+}
+
+#[test]
+fn test_lookup_pipeline_with_let_filters_joined_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("lookup_let.mlite")).unwrap();
+    for (id, min) in [(1, 20), (2, 0), (3, 0)] {
+        db.insert_one(
+            "customers",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("min".to_string(), json!(min)),
+            ]),
+        )
+        .unwrap();
+    }
+    for (id, customer, total) in [(10, 1, 15), (11, 2, 40), (12, 1, 25), (13, 1, 30)] {
+        db.insert_one(
+            "orders",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("customer".to_string(), json!(customer)),
+                ("total".to_string(), json!(total)),
+            ]),
+        )
+        .unwrap();
+    }
+    let pipeline = json!([
+        {"$lookup": {
+            "from": "orders",
+            "let": {"cid": "$_id", "min": "$min"},
+            "pipeline": [
+                {"$match": {"$expr": {"$and": [
+                    {"$eq": ["$customer", "$$cid"]},
+                    {"$gt": ["$total", "$$min"]}
+                ]}}},
+                {"$sort": {"total": -1}},
+                {"$project": {"_id": 1}}
+            ],
+            "as": "orders"
+        }},
+        {"$sort": {"_id": 1}}
+    ]);
+    let order_ids = |results: &[Value]| -> Vec<Vec<i64>> {
+        results
+            .iter()
+            .map(|doc| {
+                doc["orders"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|o| o["_id"].as_i64().unwrap())
+                    .collect()
+            })
+            .collect()
+    };
+    let customers = db.collection("customers").unwrap();
+
+    let scanned = customers.aggregate(&pipeline).unwrap();
+    assert_eq!(order_ids(&scanned), vec![vec![13, 12], vec![11], vec![]]);
+
+    // With an index on the joined field each run is an index lookup
+    let orders = db.collection("orders").unwrap();
+    orders.create_index("customer".to_string(), false).unwrap();
+    let before = db.metrics().documents_read;
+    let indexed = customers.aggregate(&pipeline).unwrap();
+    assert_eq!(order_ids(&indexed), order_ids(&scanned));
+    // 3 customers plus the 4 orders they own, not 3 scans of all orders
+    assert!(db.metrics().documents_read - before <= 7);
+}
+
+#[test]
+fn test_out_replaces_target_collection() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("out.mlite"),
+        DatabaseOptions::new().with_aggregation_cache(16),
+    )
+    .unwrap();
+    for (id, city, amount) in [(1, "Pécs", 10), (2, "Győr", 5), (3, "Pécs", 7)] {
+        db.insert_one(
+            "sales",
+            HashMap::from([
+                ("_id".to_string(), json!(id)),
+                ("city".to_string(), json!(city)),
+                ("amount".to_string(), json!(amount)),
+            ]),
+        )
+        .unwrap();
+    }
+    // Stale contents of the target are dropped, not merged
+    db.insert_one(
+        "totals",
+        HashMap::from([("_id".to_string(), json!("Szeged"))]),
+    )
+    .unwrap();
+
+    let pipeline = json!([
+        {"$group": {"_id": "$city", "total": {"$sum": "$amount"}}},
+        {"$out": "totals"}
+    ]);
+    let sales = db.collection("sales").unwrap();
+    assert!(sales.aggregate(&pipeline).unwrap().is_empty());
+
+    let totals = db.collection("totals").unwrap();
+    let mut written: Vec<Value> = totals
+        .find(&json!({}))
+        .unwrap()
+        .into_iter()
+        .map(|doc| json!({"_id": doc["_id"], "total": doc["total"]}))
+        .collect();
+    written.sort_by_key(|doc| doc["_id"].to_string());
+    assert_eq!(
+        written,
+        vec![
+            json!({"_id": "Győr", "total": 5}),
+            json!({"_id": "Pécs", "total": 17}),
+        ]
+    );
+    // Pipeline _ids are kept and indexed
+    assert_eq!(totals.find(&json!({"_id": "Pécs"})).unwrap().len(), 1);
+
+    // Every run writes again instead of being served from the cache
+    db.insert_one(
+        "sales",
+        HashMap::from([
+            ("_id".to_string(), json!(4)),
+            ("city".to_string(), json!("Győr")),
+            ("amount".to_string(), json!(1)),
+        ]),
+    )
+    .unwrap();
+    db.drop_collection("totals").unwrap();
+    db.collection("sales")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap();
+    let gyor = db
+        .collection("totals")
+        .unwrap()
+        .find(&json!({"_id": "Győr"}))
+        .unwrap();
+    assert_eq!(gyor.len(), 1);
+    assert_eq!(gyor[0]["total"], json!(6));
+    assert_eq!(db.aggregation_cache_stats().size, 0);
+}
+
+#[test]
+fn test_failed_out_leaves_target_intact() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("out.mlite")).unwrap();
+    db.insert_one(
+        "orders",
+        HashMap::from([
+            ("_id".to_string(), json!(1)),
+            ("items".to_string(), json!(["a", "b"])),
+        ]),
+    )
+    .unwrap();
+    db.insert_one("report", HashMap::from([("_id".to_string(), json!("old"))]))
+        .unwrap();
+
+    // $unwind keeps the order's _id on every item
+    let pipeline = json!([{"$unwind": "$items"}, {"$out": "report"}]);
+    let through_db = db.aggregate("orders", &pipeline).unwrap_err();
+    assert!(
+        matches!(through_db, MongoLiteError::DuplicateKey(_)),
+        "{:?}",
+        through_db
+    );
+    let raw = db
+        .collection("orders")
+        .unwrap()
+        .aggregate(&pipeline)
+        .unwrap_err();
+    assert!(matches!(raw, MongoLiteError::DuplicateKey(_)), "{:?}", raw);
+
+    let report = db.collection("report").unwrap().find(&json!({})).unwrap();
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0]["_id"], json!("old"));
+}
+
+#[test]
+fn test_decimal_values_round_trip_and_order_exactly() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("decimal.mlite")).unwrap();
+    let pi = "3.14159265358979323846264338327950288";
+    let huge = "170141183460469231731687303715884105728"; // 2^127
+    for (id, price) in [
+        (1, json!({"$numberDecimal": pi})),
+        (2, json!({"$numberDecimal": huge})),
+        (3, json!(3)),
+        (4, json!(u64::MAX)),
+        (
+            5,
+            json!({"$numberDecimal": "3.1415926535897932384626433832795028"}),
+        ),
+    ] {
+        db.insert_one(
+            "prices",
+            HashMap::from([("_id".to_string(), json!(id)), ("price".to_string(), price)]),
+        )
+        .unwrap();
+    }
+    let prices = db.collection("prices").unwrap();
+    prices.create_index("price".to_string(), false).unwrap();
+
+    // Stored text comes back untouched
+    let doc = prices.find_one(&json!({"_id": 2})).unwrap().unwrap();
+    assert_eq!(doc["price"], json!({"$numberDecimal": huge}));
+
+    let ids = |docs: Vec<Value>| -> Vec<i64> {
+        docs.iter().map(|d| d["_id"].as_i64().unwrap()).collect()
+    };
+    let sorted = prices
+        .find_with_options(
+            &json!({}),
+            ironbase_core::FindOptions::new().with_sort(vec![("price".to_string(), 1)]),
+        )
+        .unwrap();
+    assert_eq!(ids(sorted), vec![3, 5, 1, 4, 2]);
+
+    // Equality and ranges against a decimal are exact, index or not
+    assert_eq!(
+        ids(prices
+            .find(&json!({"price": {"$numberDecimal": pi}}))
+            .unwrap()),
+        vec![1]
+    );
+    let mut above_pi = ids(prices
+        .find(
+            &json!({"price": {"$gt": {"$numberDecimal": "3.14159265358979323846264338327950287"}}}),
+        )
+        .unwrap());
+    above_pi.sort();
+    assert_eq!(above_pi, vec![1, 2, 4]);
+
+    // A plain-number range scan over the index finds the decimals in it
+    let mut below_four = ids(prices.find(&json!({"price": {"$lt": 4}})).unwrap());
+    below_four.sort();
+    assert_eq!(below_four, vec![1, 3, 5]);
+}
+
+#[test]
+fn test_max_documents_quota() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("quota.mlite");
+    let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+    db.set_collection_max_documents("tenants", Some(3)).unwrap();
+    let doc = |n: i64| HashMap::from([("n".to_string(), json!(n))]);
+
+    db.insert_one("tenants", doc(1)).unwrap();
+    db.insert_many("tenants", vec![doc(2), doc(3)]).unwrap();
+    let err = db.insert_one("tenants", doc(4)).unwrap_err();
+    assert!(matches!(err, MongoLiteError::QuotaExceeded(_)), "{}", err);
+    let err = db.upsert_by_key("tenants", "n", doc(5)).unwrap_err();
+    assert!(matches!(err, MongoLiteError::QuotaExceeded(_)), "{}", err);
+
+    // Updates that replace an existing document still go through
+    let (inserted, _) = db.upsert_by_key("tenants", "n", doc(3)).unwrap();
+    assert!(!inserted);
+    db.update_many("tenants", &json!({}), &json!({"$set": {"seen": true}}))
+        .unwrap();
+
+    // An over-quota batch is rejected as a whole
+    db.delete_one("tenants", &json!({"n": 1})).unwrap();
+    let err = db.insert_many("tenants", vec![doc(6), doc(7)]).unwrap_err();
+    assert!(matches!(err, MongoLiteError::QuotaExceeded(_)), "{}", err);
+    let tenants = db.collection("tenants").unwrap();
+    assert_eq!(tenants.count_documents(&json!({})).unwrap(), 2);
+    db.flush().unwrap();
+    drop(db);
+
+    // The quota is stored with the collection
+    let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+    let tenants = db.collection("tenants").unwrap();
+    assert_eq!(tenants.max_documents(), Some(3));
+    db.insert_one("tenants", doc(6)).unwrap();
+    assert!(db.insert_one("tenants", doc(7)).is_err());
+    tenants.set_max_documents(None).unwrap();
+    db.insert_one("tenants", doc(7)).unwrap();
+}
+
+#[test]
+fn test_capped_collection_evicts_oldest() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("capped.mlite");
+    let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+    let events = db.collection("events").unwrap();
+    events.create_index("n".to_string(), false).unwrap();
+    db.set_collection_capped(
+        "events",
+        Some(CappedLimits {
+            max_documents: Some(5),
+            max_bytes: None,
+        }),
+    )
+    .unwrap();
+    let doc = |n: i64| HashMap::from([("n".to_string(), json!(n))]);
+
+    for n in 0..8 {
+        db.insert_one("events", doc(n)).unwrap();
+    }
+    db.insert_many("events", vec![doc(8), doc(9)]).unwrap();
+
+    let events = db.collection("events").unwrap();
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 5);
+    let mut kept: Vec<i64> = events
+        .find(&json!({}))
+        .unwrap()
+        .iter()
+        .map(|d| d["n"].as_i64().unwrap())
+        .collect();
+    kept.sort();
+    assert_eq!(kept, [5, 6, 7, 8, 9]);
+    // Evicted documents left the index too
+    assert!(events.find(&json!({"n": 2})).unwrap().is_empty());
+    assert_eq!(events.find(&json!({"n": 7})).unwrap().len(), 1);
+
+    let err = db
+        .delete_many("events", &json!({"n": {"$gte": 0}}))
+        .unwrap_err();
+    assert!(err.to_string().contains("capped"), "{}", err);
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 5);
+
+    db.flush().unwrap();
+    drop(db);
+    let db = DatabaseCore::<StorageEngine>::open(&path).unwrap();
+    let events = db.collection("events").unwrap();
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 5);
+    assert!(events.find_one(&json!({"n": 4})).unwrap().is_none());
+    db.insert_one("events", doc(10)).unwrap();
+    assert!(events.find_one(&json!({"n": 5})).unwrap().is_none());
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 5);
+}
+
+#[test]
+fn test_capped_collection_by_bytes() {
+    let db = DatabaseCore::open_memory().unwrap();
+    db.collection("log").unwrap();
+    db.set_collection_capped(
+        "log",
+        Some(CappedLimits {
+            max_documents: None,
+            max_bytes: Some(300),
+        }),
+    )
+    .unwrap();
+    let line = |n: i64| {
+        HashMap::from([
+            ("n".to_string(), json!(n)),
+            ("msg".to_string(), json!("x".repeat(40))),
+        ])
+    };
+
+    for n in 0..20 {
+        db.insert_one("log", line(n)).unwrap();
+    }
+    let log = db.collection("log").unwrap();
+    let docs = log.find(&json!({})).unwrap();
+    let total: usize = docs.iter().map(|d| d.to_string().len()).sum();
+    assert!(total <= 300, "{} bytes", total);
+    assert!(docs.len() >= 3);
+    assert!(log.find_one(&json!({"n": 19})).unwrap().is_some());
+    assert!(log.find_one(&json!({"n": 0})).unwrap().is_none());
+
+    let huge = HashMap::from([("msg".to_string(), json!("x".repeat(400)))]);
+    let err = db.insert_one("log", huge).unwrap_err();
+    assert!(matches!(err, MongoLiteError::QuotaExceeded(_)), "{}", err);
+}
+
+#[test]
+fn test_skip_id_index_option() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("events.mlite");
+    let options = DatabaseOptions::new().with_skip_id_index(true);
+    let db = DatabaseCore::<StorageEngine>::open_with_options(&db_path, options).unwrap();
+    for n in 0..10 {
+        db.insert_one(
+            "events",
+            HashMap::from([
+                ("n".to_string(), json!(n)),
+                (
+                    "kind".to_string(),
+                    json!(if n % 2 == 0 { "even" } else { "odd" }),
+                ),
+            ]),
+        )
+        .unwrap();
+    }
+
+    let events = db.collection("events").unwrap();
+    assert!(!events.has_id_index());
+    assert!(events.list_indexes().is_empty());
+    let kind_index = events.create_index("kind".to_string(), false).unwrap();
+    assert_eq!(events.list_indexes(), vec![kind_index]);
+
+    // Scans, secondary indexes and _id lookups still work
+    assert_eq!(events.count_documents(&json!({})).unwrap(), 10);
+    assert_eq!(events.find(&json!({"kind": "odd"})).unwrap().len(), 5);
+    let first = events.find_one(&json!({"n": 3})).unwrap().unwrap();
+    let found = events.find(&json!({"_id": first["_id"].clone()})).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0]["n"], 3);
+
+    // The setting survives a reopen
+    db.flush().unwrap();
+    drop(db);
+    let db = DatabaseCore::<StorageEngine>::open(&db_path).unwrap();
+    let events = db.collection("events").unwrap();
+    assert!(!events.has_id_index());
+    assert!(!events.list_indexes().contains(&"events_id".to_string()));
+    assert_eq!(events.find(&json!({"kind": "even"})).unwrap().len(), 5);
+
+    // Collections whose options were not set keep their _id index
+    assert!(db.collection("users").unwrap().has_id_index());
+}
+
+#[test]
+fn test_set_collection_id_index() {
+    let db = DatabaseCore::open_memory().unwrap();
+    db.collection("log").unwrap();
+    db.set_collection_id_index("log", false).unwrap();
+
+    let log = db.collection("log").unwrap();
+    assert!(log.list_indexes().is_empty());
+    for n in 0..5 {
+        db.insert_one("log", HashMap::from([("_id".to_string(), json!(n))]))
+            .unwrap();
+    }
+    assert_eq!(log.find(&json!({"_id": 4})).unwrap().len(), 1);
+
+    // The catalog still rejects a duplicate explicit _id
+    let err = db
+        .insert_one(
+            "log",
+            HashMap::from([("_id".to_string(), json!(1)), ("x".to_string(), json!(1))]),
+        )
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::DuplicateKey(_)), "{}", err);
+    let err = db
+        .insert_many("log", vec![HashMap::from([("_id".to_string(), json!(3))])])
+        .unwrap_err();
+    assert!(matches!(err, MongoLiteError::DuplicateKey(_)), "{}", err);
+    let log = db.collection("log").unwrap();
+    assert_eq!(log.count_documents(&json!({})).unwrap(), 5);
+    assert_eq!(log.find(&json!({})).unwrap().len(), 5);
+    assert!(log
+        .find_one(&json!({"_id": 1}))
+        .unwrap()
+        .unwrap()
+        .get("x")
+        .is_none());
+
+    // Turning the index back on builds it from the stored documents
+    log.set_id_index(true).unwrap();
+    assert_eq!(log.list_indexes(), vec!["log_id".to_string()]);
+    let err = db
+        .insert_one("log", HashMap::from([("_id".to_string(), json!(2))]))
+        .unwrap_err();
+    assert!(err.to_string().contains("Duplicate"), "{}", err);
+}
@@ -0,0 +1,154 @@
+// Projection-aware by-id reads, measured with a counting allocator
+use ironbase_core::{DatabaseCore, StorageEngine};
+use serde_json::json;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use tempfile::TempDir;
+
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(now, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// The counters are process-wide, so tests measuring them run one at a time
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    // A failed test poisons the lock; the others still run
+    SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Peak bytes allocated on top of the current baseline while running `f`
+fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let result = f();
+    (result, PEAK.load(Ordering::SeqCst) - baseline)
+}
+
+#[test]
+fn test_projected_read_of_large_document_uses_less_memory() {
+    let _serial = serial();
+    const BLOB_SIZE: usize = 16 * 1024 * 1024;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("large.mlite")).unwrap();
+
+    let mut doc = HashMap::new();
+    doc.insert("name".to_string(), json!("small"));
+    doc.insert("blob".to_string(), json!("x".repeat(BLOB_SIZE)));
+    doc.insert("tags".to_string(), json!(vec!["t"; 1000]));
+    let id = db.insert_one("big", doc).unwrap();
+
+    let collection = db.collection("big").unwrap();
+    let projection = HashMap::from([("name".to_string(), 1)]);
+
+    let (projected, projected_peak) = peak_allocation(|| {
+        collection
+            .find_by_id_with_projection(&id, &projection)
+            .unwrap()
+    });
+    let (full, full_peak) = peak_allocation(|| collection.find_by_id(&id).unwrap());
+
+    let projected = projected.expect("document exists");
+    assert_eq!(projected, json!({"_id": 1, "name": "small"}));
+    assert_eq!(full.expect("document exists")["name"], "small");
+
+    assert!(full_peak > BLOB_SIZE, "full read peak: {} bytes", full_peak);
+    assert!(
+        projected_peak < BLOB_SIZE / 16,
+        "projected read peak: {} bytes",
+        projected_peak
+    );
+
+    // Empty projection falls back to the full document
+    let whole = collection
+        .find_by_id_with_projection(&id, &HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert_eq!(whole["blob"].as_str().map(str::len), Some(BLOB_SIZE));
+
+    db.delete_one("big", &json!({"name": "small"})).unwrap();
+    assert!(collection
+        .find_by_id_with_projection(&id, &projection)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_size_only_projection_counts_array_without_loading_it() {
+    let _serial = serial();
+    const ITEMS: usize = 200_000;
+
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("sizes.mlite")).unwrap();
+
+    let items: Vec<String> = (0..ITEMS).map(|i| format!("item-{:08}", i)).collect();
+    let mut big = HashMap::new();
+    big.insert("name".to_string(), json!("big"));
+    big.insert("items".to_string(), json!(items));
+    big.insert("meta".to_string(), json!({"tags": ["a", "b"]}));
+    let big_id = db.insert_one("arrays", big).unwrap();
+    drop(items);
+
+    let small = HashMap::from([
+        ("name".to_string(), json!("small")),
+        ("items".to_string(), json!("not an array")),
+    ]);
+    db.insert_one("arrays", small).unwrap();
+
+    let collection = db.collection("arrays").unwrap();
+    let size_only = json!([
+        {"$project": {"_id": 1, "count": {"$size": "$items"}, "tags": {"$size": "$meta.tags"}}},
+        {"$sort": {"_id": 1}}
+    ]);
+    // An extra include needs the document itself, so this takes the normal path
+    let with_name = json!([
+        {"$project": {"name": 1, "count": {"$size": "$items"}}},
+        {"$match": {"name": "big"}}
+    ]);
+
+    let (sized, sized_peak) = peak_allocation(|| collection.aggregate(&size_only).unwrap());
+    let (full, full_peak) = peak_allocation(|| collection.aggregate(&with_name).unwrap());
+
+    assert_eq!(sized.len(), 2);
+    assert_eq!(sized[0]["_id"], json!(big_id));
+    assert_eq!(sized[0]["count"], json!(ITEMS));
+    assert_eq!(sized[0]["tags"], json!(2));
+    // Non-array and missing values project as null
+    assert_eq!(sized[1]["count"], json!(null));
+    assert_eq!(sized[1]["tags"], json!(null));
+    assert_eq!(full[0]["count"], json!(ITEMS));
+
+    assert!(
+        sized_peak * 20 < full_peak,
+        "size-only peak {} bytes vs full {} bytes",
+        sized_peak,
+        full_peak
+    );
+
+    db.delete_one("arrays", &json!({"name": "small"})).unwrap();
+    let collection = db.collection("arrays").unwrap();
+    assert_eq!(collection.aggregate(&size_only).unwrap().len(), 1);
+}
@@ -0,0 +1,75 @@
+// Slow query log: full scans above the threshold suggest an index
+use ironbase_core::{set_log_hook, DatabaseCore, LogLevel, StorageEngine};
+use parking_lot::Mutex;
+use serde_json::json;
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn capture(level: LogLevel, _module: &str, message: &str) {
+    if level == LogLevel::Warn {
+        WARNINGS.lock().push(message.to_string());
+    }
+}
+
+fn slow_query_warnings(collection: &str) -> Vec<String> {
+    let prefix = format!("Slow query on '{}'", collection);
+    WARNINGS
+        .lock()
+        .iter()
+        .filter(|m| m.starts_with(&prefix))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_slow_query_log_warns_on_full_scan_only() {
+    set_log_hook(Some(capture));
+
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseCore::<StorageEngine>::open(temp_dir.path().join("slow.mlite")).unwrap();
+    for i in 0..20 {
+        let mut doc = HashMap::new();
+        doc.insert("age".to_string(), json!(i));
+        doc.insert(
+            "city".to_string(),
+            json!(if i % 2 == 0 { "NYC" } else { "LA" }),
+        );
+        db.insert_one("people", doc).unwrap();
+    }
+
+    let people = db
+        .collection("people")
+        .unwrap()
+        .with_slow_query_threshold(10);
+
+    // Unindexed filter over 20 documents: warned, naming the filtered field
+    assert_eq!(people.find(&json!({"city": "NYC"})).unwrap().len(), 10);
+    let warnings = slow_query_warnings("people");
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].contains("examined 20 documents"));
+    assert!(warnings[0].contains("consider an index on 'city'"));
+
+    // Indexed query: no new warning
+    people.create_index("age".to_string(), false).unwrap();
+    assert_eq!(people.find(&json!({"age": {"$gte": 15}})).unwrap().len(), 5);
+    assert_eq!(slow_query_warnings("people").len(), 1);
+
+    // Below the threshold: no warning
+    let relaxed = db
+        .collection("people")
+        .unwrap()
+        .with_slow_query_threshold(100);
+    relaxed.find(&json!({"city": "LA"})).unwrap();
+    assert_eq!(slow_query_warnings("people").len(), 1);
+
+    // Disabled by default
+    db.collection("people")
+        .unwrap()
+        .find(&json!({"city": {"$ne": "LA"}}))
+        .unwrap();
+    assert_eq!(slow_query_warnings("people").len(), 1);
+
+    set_log_hook(None);
+}
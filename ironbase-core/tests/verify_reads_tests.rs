@@ -0,0 +1,88 @@
+// Shadow reads: index-driven results cross-checked against full scans
+use ironbase_core::index::IndexKey;
+use ironbase_core::{
+    set_log_hook, DatabaseCore, DatabaseOptions, DocumentId, LogLevel, StorageEngine,
+};
+use parking_lot::Mutex;
+use serde_json::json;
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+static ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn capture(level: LogLevel, _module: &str, message: &str) {
+    if level == LogLevel::Error {
+        ERRORS.lock().push(message.to_string());
+    }
+}
+
+fn shadow_errors() -> Vec<String> {
+    ERRORS
+        .lock()
+        .iter()
+        .filter(|m| m.starts_with("Shadow read mismatch"))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_verify_reads_reports_index_drift() {
+    set_log_hook(Some(capture));
+
+    let temp_dir = TempDir::new().unwrap();
+    let options = DatabaseOptions::new().with_verify_reads(true);
+    let db = DatabaseCore::<StorageEngine>::open_with_options(
+        temp_dir.path().join("verify.mlite"),
+        options,
+    )
+    .unwrap();
+    for i in 0..10 {
+        db.insert_one(
+            "people",
+            HashMap::from([
+                ("_id".to_string(), json!(i)),
+                ("age".to_string(), json!(20 + i)),
+            ]),
+        )
+        .unwrap();
+    }
+    let people = db.collection("people").unwrap();
+    let index_name = people.create_index("age".to_string(), false).unwrap();
+
+    // A consistent index passes silently
+    assert_eq!(people.find(&json!({"age": {"$gte": 25}})).unwrap().len(), 5);
+    assert_eq!(people.count_documents(&json!({"age": 27})).unwrap(), 1);
+    assert!(shadow_errors().is_empty());
+
+    // Drop document 7's index entry behind the collection's back
+    people
+        .indexes
+        .write()
+        .get_btree_index_mut(&index_name)
+        .unwrap()
+        .delete(&IndexKey::Int(27), &DocumentId::Int(7))
+        .unwrap();
+
+    assert_eq!(people.find(&json!({"age": {"$gte": 24}})).unwrap().len(), 5);
+    let errors = shadow_errors();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("'people'"));
+    assert!(errors[0].contains("missed [Int(7)]"), "{}", errors[0]);
+
+    assert_eq!(
+        people
+            .count_documents(&json!({"age": {"$lte": 27}}))
+            .unwrap(),
+        7
+    );
+    let errors = shadow_errors();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[1].contains("index counted 7"), "{}", errors[1]);
+
+    // Without shadow reads the drift goes unreported
+    let people = people.with_verify_reads(false);
+    assert_eq!(people.find(&json!({"age": {"$gte": 23}})).unwrap().len(), 6);
+    assert_eq!(shadow_errors().len(), 2);
+
+    set_log_hook(None);
+}
@@ -1,7 +1,7 @@
 //! IronBase Adapter - Direct wrapper around IronBase core
 
-use crate::error::Result;
-use ironbase_core::{storage::StorageEngine, DatabaseCore};
+use crate::error::{McpError, Result};
+use ironbase_core::{storage::StorageEngine, DatabaseCore, MetricsSnapshot};
 use parking_lot::RwLock;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -77,6 +77,12 @@ impl IronBaseAdapter {
         }))
     }
 
+    /// Snapshot of the database operation counters
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let db = self.db.read();
+        db.metrics()
+    }
+
     /// Force checkpoint (flush to disk)
     pub fn checkpoint(&self) -> Result<()> {
         let db = self.db.write();
@@ -84,6 +90,13 @@ impl IronBaseAdapter {
         Ok(())
     }
 
+    /// Flush pending writes and metadata before the server exits
+    pub fn shutdown(&self) -> Result<()> {
+        let db = self.db.write();
+        db.flush()?;
+        Ok(())
+    }
+
     // ============================================================
     // Document CRUD
     // ============================================================
@@ -122,6 +135,21 @@ impl IronBaseAdapter {
         Ok(ids.iter().map(Self::doc_id_to_string).collect())
     }
 
+    /// Insert a document unless one already matches `query`
+    ///
+    /// Returns whether it was inserted and the new or existing `_id`
+    pub fn insert_if_absent(
+        &self,
+        collection: &str,
+        query: Value,
+        document: Value,
+    ) -> Result<(bool, String)> {
+        let db = self.db.read();
+        let fields = Self::value_to_hashmap(document);
+        let (inserted, id) = db.insert_if_absent(collection, &query, fields)?;
+        Ok((inserted, Self::doc_id_to_string(&id)))
+    }
+
     /// Find documents
     pub fn find(&self, collection: &str, query: Value, options: FindOptions) -> Result<Vec<Value>> {
         let db = self.db.read();
@@ -178,6 +206,45 @@ impl IronBaseAdapter {
         Ok(result)
     }
 
+    /// Fetch a single document by _id (O(1) catalog lookup)
+    ///
+    /// Numeric strings are also tried as integer ids, since `insert_one`
+    /// reports ids as strings.
+    pub fn get_by_id(&self, collection: &str, id: &Value) -> Result<Option<Value>> {
+        let db = self.db.read();
+        let coll = db.collection(collection)?;
+
+        for doc_id in Self::id_candidates(id)? {
+            if let Some(doc) = coll.find_by_id(&doc_id)? {
+                return Ok(Some(doc));
+            }
+        }
+        Ok(None)
+    }
+
+    /// DocumentId variants a JSON id may refer to, most specific first
+    fn id_candidates(id: &Value) -> Result<Vec<ironbase_core::DocumentId>> {
+        use ironbase_core::DocumentId;
+
+        match id {
+            Value::Number(n) => n.as_i64().map(|i| vec![DocumentId::Int(i)]).ok_or_else(|| {
+                McpError::InvalidParams(format!("Invalid 'id' parameter: {} is not an integer", n))
+            }),
+            Value::String(s) => {
+                let mut candidates = Vec::new();
+                if let Ok(i) = s.parse::<i64>() {
+                    candidates.push(DocumentId::Int(i));
+                }
+                candidates.push(DocumentId::String(s.clone()));
+                candidates.push(DocumentId::ObjectId(s.clone()));
+                Ok(candidates)
+            }
+            _ => Err(McpError::InvalidParams(
+                "Invalid 'id' parameter (expected string or integer)".to_string(),
+            )),
+        }
+    }
+
     /// Update a single document (with WAL durability)
     pub fn update_one(
         &self,
@@ -2,14 +2,16 @@
 
 pub mod adapter;
 pub mod error;
+pub mod metrics;
 pub mod prompts;
 pub mod tools;
 
 // Re-export main types
 pub use adapter::{FindOptions, IronBaseAdapter, UpdateResult};
 pub use error::{McpError, Result};
+pub use metrics::render_prometheus;
 pub use prompts::{get_prompt_content, get_prompts_list};
-pub use tools::{dispatch_tool, get_tools_list};
+pub use tools::{dispatch_tool, format_tool_result, get_tools_list};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -12,8 +12,10 @@ use std::io::{BufRead, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use mcp_docjl::metrics::PROMETHEUS_CONTENT_TYPE;
 use mcp_docjl::{
-    dispatch_tool, get_prompt_content, get_prompts_list, get_tools_list, IronBaseAdapter, VERSION,
+    dispatch_tool, format_tool_result, get_prompt_content, get_prompts_list, get_tools_list,
+    render_prometheus, IronBaseAdapter, VERSION,
 };
 
 #[tokio::main]
@@ -51,6 +53,14 @@ fn run_stdio_server() {
         }
     };
 
+    // SIGINT/SIGTERM interrupt the blocking stdin loop, so flush from here
+    let signal_adapter = Arc::clone(&adapter);
+    tokio::spawn(async move {
+        let signal = shutdown_signal().await;
+        shutdown_adapter(&signal_adapter, signal);
+        std::process::exit(0);
+    });
+
     eprintln!("Ready for requests...");
 
     // Read from stdin line by line
@@ -97,6 +107,36 @@ fn run_stdio_server() {
         }
         // Notifications (no id) get no response - this is correct per JSON-RPC spec
     }
+
+    shutdown_adapter(&adapter, "stdin closed");
+}
+
+/// Flush the database before exiting (stdio mode logs to stderr)
+fn shutdown_adapter(adapter: &IronBaseAdapter, reason: &str) {
+    eprintln!("Shutting down ({}), flushing database...", reason);
+    match adapter.shutdown() {
+        Ok(()) => eprintln!("Database flushed"),
+        Err(e) => eprintln!("Flush on shutdown failed: {}", e),
+    }
+}
+
+/// Resolves on SIGINT or SIGTERM (Ctrl-C elsewhere), naming the signal
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
 }
 
 fn handle_request(request: &McpRequest, adapter: &Arc<IronBaseAdapter>) -> Option<McpResponse> {
@@ -161,12 +201,12 @@ fn handle_request(request: &McpRequest, adapter: &Arc<IronBaseAdapter>) -> Optio
 
             let arguments = params.arguments.unwrap_or_else(|| serde_json::json!({}));
 
-            match dispatch_tool(&params.name, arguments, adapter) {
+            match dispatch_tool(&params.name, arguments.clone(), adapter) {
                 Ok(result) => {
                     let response = serde_json::json!({
                         "content": [{
                             "type": "text",
-                            "text": serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+                            "text": format_tool_result(&result, &arguments)
                         }]
                     });
                     Some(create_success_response(response, request.id.clone()))
@@ -269,7 +309,7 @@ async fn run_http_server() {
         routing::{get, post},
         Router,
     };
-    use tracing::info;
+    use tracing::{error, info};
 
     // Initialize tracing
     tracing_subscriber::fmt()
@@ -291,11 +331,14 @@ async fn run_http_server() {
         IronBaseAdapter::new(&config.database_path).expect("Failed to create IronBase adapter"),
     );
 
-    let app_state = Arc::new(HttpAppState { adapter });
+    let app_state = Arc::new(HttpAppState {
+        adapter: Arc::clone(&adapter),
+    });
 
     let app = Router::new()
         .route("/mcp", post(http_handle_mcp_request))
         .route("/health", get(health_check))
+        .route("/metrics", get(metrics))
         .with_state(app_state);
 
     let addr: std::net::SocketAddr = format!("{}:{}", host, port)
@@ -306,9 +349,19 @@ async fn run_http_server() {
 
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(async {
+            let signal = shutdown_signal().await;
+            info!("Received {}, stopping server", signal);
+        })
         .await
         .expect("Server error");
 
+    info!("Server stopped, flushing database");
+    match adapter.shutdown() {
+        Ok(()) => info!("Database flushed"),
+        Err(e) => error!("Flush on shutdown failed: {}", e),
+    }
+
     // HTTP request handler
     async fn http_handle_mcp_request(
         State(state): State<Arc<HttpAppState>>,
@@ -324,6 +377,15 @@ async fn run_http_server() {
         }
     }
 
+    async fn metrics(State(state): State<Arc<HttpAppState>>) -> impl IntoResponse {
+        let body = render_prometheus(&state.adapter.metrics());
+        (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+            body,
+        )
+    }
+
     async fn health_check() -> impl IntoResponse {
         (
             StatusCode::OK,
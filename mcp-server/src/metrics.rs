@@ -0,0 +1,24 @@
+//! Prometheus text rendering for IronBase operation counters
+
+use ironbase_core::MetricsSnapshot;
+use std::fmt::Write;
+
+/// Prefix for every exported metric name
+const METRIC_PREFIX: &str = "ironbase";
+
+/// Content-Type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Render a metrics snapshot in Prometheus text format
+///
+/// Each counter becomes `ironbase_<name>_total` with HELP and TYPE lines.
+pub fn render_prometheus(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+    for (name, help, value) in snapshot.counters() {
+        let metric = format!("{}_{}_total", METRIC_PREFIX, name);
+        let _ = writeln!(out, "# HELP {} {}", metric, help);
+        let _ = writeln!(out, "# TYPE {} counter", metric);
+        let _ = writeln!(out, "{} {}", metric, value);
+    }
+    out
+}
@@ -2,11 +2,12 @@
 
 use crate::adapter::{FindOptions, IronBaseAdapter};
 use crate::error::{McpError, Result};
+use ironbase_core::value_utils::to_json_output;
 use serde_json::{json, Value};
 
 /// Get the list of all available tools for MCP tools/list
 pub fn get_tools_list() -> Value {
-    json!({
+    let mut list = json!({
         "tools": [
             // Database Management
             {
@@ -112,6 +113,28 @@ pub fn get_tools_list() -> Value {
                     "required": ["collection", "documents"]
                 }
             },
+            {
+                "name": "insert_if_absent",
+                "description": "Insert a document only if no document matches the query (idempotent seeding)",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "collection": {
+                            "type": "string",
+                            "description": "Collection name"
+                        },
+                        "query": {
+                            "type": "object",
+                            "description": "Filter that must match nothing for the insert to happen"
+                        },
+                        "document": {
+                            "type": "object",
+                            "description": "Document to insert (JSON object)"
+                        }
+                    },
+                    "required": ["collection", "query", "document"]
+                }
+            },
             {
                 "name": "find",
                 "description": "Find documents matching a query with optional projection, sort, limit, skip",
@@ -164,6 +187,24 @@ pub fn get_tools_list() -> Value {
                     "required": ["collection", "query"]
                 }
             },
+            {
+                "name": "get_by_id",
+                "description": "Fetch a single document by its _id (direct lookup, no query scan)",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "collection": {
+                            "type": "string",
+                            "description": "Collection name"
+                        },
+                        "id": {
+                            "type": ["string", "integer"],
+                            "description": "Document _id. Example: 42 or \"42\" or \"user-7\""
+                        }
+                    },
+                    "required": ["collection", "id"]
+                }
+            },
             {
                 "name": "update_one",
                 "description": "Update a single document matching the filter",
@@ -380,7 +421,26 @@ pub fn get_tools_list() -> Value {
                 }
             }
         ]
-    })
+    });
+
+    // Output formatting applies to every tool (see format_tool_result)
+    for tool in list["tools"].as_array_mut().into_iter().flatten() {
+        tool["inputSchema"]["properties"]["pretty"] = json!({
+            "type": "boolean",
+            "description": "Pretty-print the JSON result (default: pretty unless the result is large)"
+        });
+    }
+    list
+}
+
+/// Render a tool result as the text content of a `tools/call` response
+///
+/// Every tool accepts an optional boolean `pretty` argument: true
+/// pretty-prints, false writes compact JSON, and leaving it out
+/// pretty-prints unless the result is large.
+pub fn format_tool_result(result: &Value, params: &Value) -> String {
+    let pretty = params.get("pretty").and_then(Value::as_bool);
+    to_json_output(result, pretty).unwrap_or_else(|_| "{}".to_string())
 }
 
 /// Dispatch a tool call to the appropriate handler
@@ -424,6 +484,13 @@ pub fn dispatch_tool(name: &str, params: Value, adapter: &IronBaseAdapter) -> Re
             let ids = adapter.insert_many(&collection, documents)?;
             Ok(json!({"inserted_ids": ids, "inserted_count": ids.len()}))
         }
+        "insert_if_absent" => {
+            let collection = get_string(&params, "collection")?;
+            let query = get_object(&params, "query")?;
+            let document = get_object(&params, "document")?;
+            let (inserted, id) = adapter.insert_if_absent(&collection, query, document)?;
+            Ok(json!({"inserted": inserted, "id": id}))
+        }
         "find" => {
             let collection = get_string(&params, "collection")?;
             let query = params.get("query").cloned().unwrap_or(json!({}));
@@ -448,6 +515,15 @@ pub fn dispatch_tool(name: &str, params: Value, adapter: &IronBaseAdapter) -> Re
             let document = adapter.find_one(&collection, query)?;
             Ok(json!({"document": document}))
         }
+        "get_by_id" => {
+            let collection = get_string(&params, "collection")?;
+            let id = params
+                .get("id")
+                .cloned()
+                .ok_or_else(|| McpError::InvalidParams("Missing 'id' parameter".to_string()))?;
+            let document = adapter.get_by_id(&collection, &id)?;
+            Ok(json!({"found": document.is_some(), "document": document}))
+        }
         "update_one" => {
             let collection = get_string(&params, "collection")?;
             let filter = get_object(&params, "filter")?;
@@ -6,7 +6,8 @@
 //! - Adapter (IronBaseAdapter CRUD operations)
 
 use mcp_docjl::{
-    dispatch_tool, get_prompt_content, get_prompts_list, get_tools_list, IronBaseAdapter,
+    dispatch_tool, format_tool_result, get_prompt_content, get_prompts_list, get_tools_list,
+    IronBaseAdapter,
 };
 use serde_json::json;
 use tempfile::TempDir;
@@ -219,6 +220,23 @@ fn test_dispatch_insert_one() {
     assert!(value.get("inserted_id").is_some());
 }
 
+#[test]
+fn test_dispatch_insert_if_absent() {
+    let (adapter, _temp) = create_test_adapter();
+    let params = json!({
+        "collection": "settings",
+        "query": {"key": "theme"},
+        "document": {"key": "theme", "value": "dark"}
+    });
+    let first = dispatch_tool("insert_if_absent", params.clone(), &adapter).unwrap();
+    let second = dispatch_tool("insert_if_absent", params, &adapter).unwrap();
+
+    assert_eq!(first["inserted"], true);
+    assert_eq!(second["inserted"], false);
+    assert_eq!(first["id"], second["id"]);
+    assert_eq!(adapter.count_documents("settings", json!({})).unwrap(), 1);
+}
+
 #[test]
 fn test_dispatch_insert_many() {
     let (adapter, _temp) = create_test_adapter();
@@ -314,6 +332,81 @@ fn test_dispatch_find_one() {
     assert!(value.get("document").is_some());
 }
 
+#[test]
+fn test_dispatch_get_by_id_existing() {
+    let (adapter, _temp) = create_test_adapter();
+
+    let inserted = dispatch_tool(
+        "insert_one",
+        json!({"collection": "users", "document": {"name": "Alice", "age": 30}}),
+        &adapter,
+    )
+    .unwrap();
+    let id = inserted.get("inserted_id").unwrap().clone();
+    dispatch_tool(
+        "insert_one",
+        json!({"collection": "users", "document": {"name": "Bob", "age": 25}}),
+        &adapter,
+    )
+    .unwrap();
+
+    // The returned id string resolves to the integer _id
+    let value = dispatch_tool(
+        "get_by_id",
+        json!({"collection": "users", "id": id}),
+        &adapter,
+    )
+    .unwrap();
+    assert_eq!(value.get("found"), Some(&json!(true)));
+    assert_eq!(value["document"]["name"], json!("Alice"));
+
+    // Integer ids work directly
+    let value = dispatch_tool(
+        "get_by_id",
+        json!({"collection": "users", "id": 2}),
+        &adapter,
+    )
+    .unwrap();
+    assert_eq!(value["document"]["name"], json!("Bob"));
+
+    // String _id values are looked up as strings
+    dispatch_tool(
+        "insert_one",
+        json!({"collection": "users", "document": {"_id": "user-7", "name": "Carol"}}),
+        &adapter,
+    )
+    .unwrap();
+    let value = dispatch_tool(
+        "get_by_id",
+        json!({"collection": "users", "id": "user-7"}),
+        &adapter,
+    )
+    .unwrap();
+    assert_eq!(value["document"]["name"], json!("Carol"));
+}
+
+#[test]
+fn test_dispatch_get_by_id_missing() {
+    let (adapter, _temp) = create_test_adapter();
+
+    dispatch_tool(
+        "insert_one",
+        json!({"collection": "users", "document": {"name": "Alice"}}),
+        &adapter,
+    )
+    .unwrap();
+
+    let result = dispatch_tool(
+        "get_by_id",
+        json!({"collection": "users", "id": 999}),
+        &adapter,
+    );
+    assert!(result.is_ok());
+    let value = result.unwrap();
+    assert_eq!(value.get("found"), Some(&json!(false)));
+    assert_eq!(value.get("document"), Some(&json!(null)));
+}
+
 #[test]
 fn test_dispatch_update_one() {
     let (adapter, _temp) = create_test_adapter();
@@ -606,3 +699,191 @@ fn test_dispatch_invalid_param_type() {
     );
     assert!(result.is_err());
 }
+
+// ============================================================
+// Output Format Tests
+// ============================================================
+
+#[test]
+fn test_tool_result_pretty_and_compact_parse_equal() {
+    let (adapter, _temp) = create_test_adapter();
+    dispatch_tool(
+        "insert_many",
+        json!({"collection": "t", "documents": [{"a": 1}, {"a": 2, "b": [1, 2]}]}),
+        &adapter,
+    )
+    .unwrap();
+
+    let params = json!({"collection": "t", "query": {}, "sort": {"a": 1}});
+    let result = dispatch_tool("find", params.clone(), &adapter).unwrap();
+
+    let mut pretty_params = params.clone();
+    pretty_params["pretty"] = json!(true);
+    let mut compact_params = params.clone();
+    compact_params["pretty"] = json!(false);
+    let pretty = format_tool_result(&result, &pretty_params);
+    let compact = format_tool_result(&result, &compact_params);
+
+    assert!(pretty.contains('\n'));
+    assert!(!compact.contains('\n'));
+    let parsed: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+    assert_eq!(
+        parsed,
+        serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+    );
+    assert_eq!(parsed, result);
+    // Small results are pretty unless asked otherwise
+    assert_eq!(format_tool_result(&result, &params), pretty);
+}
+
+#[test]
+fn test_large_tool_result_defaults_to_compact() {
+    let docs: Vec<serde_json::Value> = (0..3000)
+        .map(|i| json!({"_id": i, "payload": "abcdefghij"}))
+        .collect();
+    let result = json!({"documents": docs});
+
+    let text = format_tool_result(&result, &json!({}));
+    assert!(text.len() > ironbase_core::value_utils::COMPACT_OUTPUT_THRESHOLD);
+    assert!(!text.contains('\n'));
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+        result
+    );
+    assert!(format_tool_result(&result, &json!({"pretty": true})).contains('\n'));
+}
+
+#[test]
+fn test_every_tool_accepts_pretty_argument() {
+    let tools = get_tools_list();
+    for tool in tools["tools"].as_array().unwrap() {
+        assert_eq!(
+            tool["inputSchema"]["properties"]["pretty"]["type"], "boolean",
+            "tool {}",
+            tool["name"]
+        );
+    }
+}
+
+// ============================================================
+// Metrics Tests
+// ============================================================
+
+/// Minimal check of the Prometheus text format: comments are HELP/TYPE
+/// lines, samples are `name value` with a valid metric name and number
+fn parse_prometheus(text: &str) -> Vec<(String, f64)> {
+    let valid_name = |name: &str| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    };
+    let mut samples = Vec::new();
+    for line in text.lines() {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let parts: Vec<&str> = comment.splitn(3, ' ').collect();
+            assert!(matches!(parts[0], "HELP" | "TYPE"), "bad comment: {}", line);
+            assert!(valid_name(parts[1]), "bad name in: {}", line);
+            if parts[0] == "TYPE" {
+                assert_eq!(parts[2], "counter");
+            }
+            continue;
+        }
+        let (name, value) = line.split_once(' ').expect("sample line");
+        assert!(valid_name(name), "bad metric name: {}", name);
+        samples.push((
+            name.to_string(),
+            value.parse::<f64>().expect("numeric value"),
+        ));
+    }
+    samples
+}
+
+#[test]
+fn test_metrics_count_adapter_operations() {
+    let (adapter, _temp) = create_test_adapter();
+    adapter
+        .insert_many("users", vec![json!({"name": "a"}), json!({"name": "b"})])
+        .unwrap();
+    adapter
+        .update_one("users", json!({"name": "a"}), json!({"$set": {"age": 1}}))
+        .unwrap();
+    adapter.delete_one("users", json!({"name": "b"})).unwrap();
+    adapter
+        .find("users", json!({"name": "a"}), Default::default())
+        .unwrap();
+
+    let metrics = adapter.metrics();
+    assert_eq!(metrics.inserts, 2);
+    assert_eq!(metrics.updates, 1);
+    assert_eq!(metrics.deletes, 1);
+    assert!(metrics.queries >= 1);
+    assert!(metrics.collection_scans >= 1);
+    assert!(metrics.wal_flushes >= 3);
+}
+
+#[test]
+fn test_render_prometheus_is_valid_exposition() {
+    let (adapter, _temp) = create_test_adapter();
+    adapter.insert_one("users", json!({"name": "a"})).unwrap();
+
+    let text = mcp_docjl::render_prometheus(&adapter.metrics());
+    let samples = parse_prometheus(&text);
+    assert_eq!(samples.len(), 12);
+    assert!(samples.contains(&("ironbase_inserts_total".to_string(), 1.0)));
+    assert!(samples.contains(&("ironbase_deletes_total".to_string(), 0.0)));
+}
+
+// ============================================================
+// Stdio Server Shutdown Tests
+// ============================================================
+
+#[test]
+fn test_stdio_eof_flushes_database() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("stdio.mlite");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-ironbase-server"))
+        .arg("--stdio")
+        .env("IRONBASE_PATH", &db_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server");
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {
+            "name": "insert_many",
+            "arguments": {
+                "collection": "notes",
+                "documents": [{"title": "a"}, {"title": "b"}, {"title": "c"}]
+            }
+        }
+    });
+    // Dropping stdin after the write sends EOF
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{}", call).unwrap();
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("inserted_count"), "{}", stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Shutting down (stdin closed)"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Database flushed"), "{}", stderr);
+
+    let adapter = IronBaseAdapter::new(&db_path).unwrap();
+    assert_eq!(adapter.count_documents("notes", json!({})).unwrap(), 3);
+}
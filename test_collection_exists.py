@@ -0,0 +1,39 @@
+#!/usr/bin/env python3
+"""Test collection_exists(), create_collection() and collection(create=False)"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_collection_exists():
+    """Explicit creation and lookups that never create a collection"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "exists.mlite"))
+        assert not db.collection_exists("users")
+
+        users = db.create_collection("users")
+        users.insert_one({"name": "Ann"})
+        assert db.collection_exists("users")
+
+        try:
+            db.create_collection("users")
+            assert False, "creating an existing collection should raise"
+        except RuntimeError:
+            pass
+
+        assert db.collection("users", create=False).count_documents() == 1
+
+        try:
+            db.collection("usres", create=False)
+            assert False, "a missing collection should raise with create=False"
+        except RuntimeError:
+            pass
+        assert not db.collection_exists("usres")
+        assert "usres" not in db.list_collections()
+
+    print("✓ collection_exists / create_collection / create=False")
+
+
+if __name__ == "__main__":
+    test_collection_exists()
@@ -0,0 +1,46 @@
+#!/usr/bin/env python3
+"""Test IronBase.collections_info() admin listing"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_collections_info():
+    """collections_info() reports counts, indexes, schema flag and size per collection"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "info.mlite"))
+
+        users = db.collection("users")
+        users.insert_many([{"name": f"user{i}", "age": 20 + i} for i in range(5)])
+        users.create_index("age")
+        users.set_schema({"type": "object", "required": ["name"]})
+
+        orders = db.collection("orders")
+        orders.insert_many([{"total": i * 10} for i in range(3)])
+        orders.create_index("total", unique=False)
+        orders.delete_one({"total": 0})
+
+        info = {entry["name"]: entry for entry in db.collections_info()}
+
+        assert set(info) == {"users", "orders"}, info
+
+        assert info["users"]["count"] == 5
+        assert sorted(info["users"]["indexes"]) == ["users_age", "users_id"]
+        assert info["users"]["has_schema"] is True
+        assert info["users"]["size_bytes"] > 0
+
+        assert info["orders"]["count"] == 2
+        assert sorted(info["orders"]["indexes"]) == ["orders_id", "orders_total"]
+        assert info["orders"]["has_schema"] is False
+        assert 0 < info["orders"]["size_bytes"] < info["users"]["size_bytes"]
+
+        # list_collections() is unchanged
+        assert sorted(db.list_collections()) == ["orders", "users"]
+
+        db.close()
+    print("✓ collections_info reports accurate stats")
+
+
+if __name__ == "__main__":
+    test_collections_info()
@@ -0,0 +1,69 @@
+#!/usr/bin/env python3
+"""Test exact_numbers=True: big ints and Decimals round-trip without rounding"""
+
+import os
+import tempfile
+from decimal import Decimal
+from ironbase import IronBase
+
+BIG = 2**70
+PI = Decimal("3.14159265358979323846264338327950288")
+
+
+def test_exact_round_trip():
+    """Values beyond i64/f64 precision come back unchanged and compare exactly"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "exact.mlite"), exact_numbers=True)
+        nums = db.collection("nums")
+        nums.insert_many([
+            {"name": "big", "value": BIG},
+            {"name": "pi", "value": PI},
+            {"name": "five", "value": Decimal("5")},
+            {"name": "small", "value": 7},
+        ])
+        nums.create_index("value")
+
+        big = nums.find_one({"name": "big"})
+        assert big["value"] == BIG and isinstance(big["value"], int)
+        pi = nums.find_one({"name": "pi"})
+        assert pi["value"] == PI and isinstance(pi["value"], Decimal)
+        assert nums.find_one({"name": "five"})["value"] == Decimal("5")
+        assert nums.find_one({"name": "small"})["value"] == 7
+
+        # Exact equality: one unit in the last place is a different number
+        assert nums.count_documents({"value": BIG}) == 1
+        assert nums.count_documents({"value": BIG + 1}) == 0
+        assert nums.count_documents({"value": PI}) == 1
+        assert nums.count_documents({"value": {"$gt": Decimal("3.14159265358979323846264338327950287")}}) == 4
+
+        ordered = nums.find({}, sort=[("value", 1)])
+        assert [d["name"] for d in ordered] == ["pi", "five", "small", "big"]
+
+        try:
+            nums.insert_one({"value": Decimal("NaN")})
+            assert False, "NaN should be rejected"
+        except ValueError:
+            pass
+
+        print("✓ Big ints and Decimals round-trip exactly")
+
+
+def test_default_is_lossy():
+    """Without the option big ints are still stored as floats"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "lossy.mlite"))
+        nums = db.collection("nums")
+        nums.insert_one({"value": BIG})
+        assert isinstance(nums.find_one({})["value"], float)
+
+        # u64 values fit a JSON number exactly in either mode
+        nums.insert_one({"value": 2**64 - 1})
+        assert nums.count_documents({"value": 2**64 - 1}) == 1
+
+        print("✓ Default mode rounds big ints to float")
+
+
+if __name__ == "__main__":
+    test_exact_round_trip()
+    test_default_is_lossy()
+    print("\nAll exact number tests passed!")
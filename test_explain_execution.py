@@ -0,0 +1,42 @@
+#!/usr/bin/env python3
+"""Test explain(query, execute=True) execution stats"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_explain_execution():
+    """Indexed queries examine few documents, unindexed ones scan"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "explain.mlite"))
+        users = db.collection("users")
+        users.insert_many(
+            [{"age": i % 50, "name": f"user{i}"} for i in range(100)]
+        )
+        index_name = users.create_index("age", False)
+
+        plan = users.explain({"age": 7})
+        assert "executionStats" not in plan
+
+        report = users.explain({"age": 7}, execute=True)
+        stats = report["executionStats"]
+        assert report["queryPlan"] == "IndexScan"
+        assert stats["indexUsed"] == index_name
+        assert stats["nReturned"] == 2
+        assert stats["docsExamined"] == 2
+
+        report = users.explain({"name": "user3"}, execute=True)
+        stats = report["executionStats"]
+        assert report["queryPlan"] == "CollectionScan"
+        assert stats["indexUsed"] is None
+        assert stats["nReturned"] == 1
+        assert stats["docsExamined"] == 100
+
+        db.close()
+
+    print("✓ explain(execute=True)")
+
+
+if __name__ == "__main__":
+    test_explain_execution()
@@ -0,0 +1,29 @@
+#!/usr/bin/env python3
+"""Test collection.find_page(): one page of results plus the total match count"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_find_page():
+    """The total counts every match; the docs are the skip/limit window"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "page.mlite"))
+        items = db.collection("items")
+        items.insert_many([{"n": i, "group": "a" if i < 25 else "b"} for i in range(40)])
+
+        docs, total = items.find_page({"group": "a"}, sort=[("n", 1)], skip=20, limit=10)
+        assert total == items.count_documents({"group": "a"}) == 25
+        assert [d["n"] for d in docs] == [20, 21, 22, 23, 24]
+
+        docs, total = items.find_page(projection={"n": 1, "_id": 0}, sort=[("n", -1)], limit=3)
+        assert total == 40
+        assert docs == [{"n": 39}, {"n": 38}, {"n": 37}]
+        db.close()
+        print("✓ find_page returns the window and the total")
+
+
+if __name__ == "__main__":
+    test_find_page()
+    print("\n✓ All find_page tests passed")
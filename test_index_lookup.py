@@ -0,0 +1,45 @@
+#!/usr/bin/env python3
+"""Test Collection.has_index_on() / indexed_fields()"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_index_lookup():
+    """Indexes are reported by logical field name, not index name"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "lookup.mlite"))
+        users = db.collection("users")
+        users.insert_many([{"age": i, "country": "HU", "city": f"c{i}"} for i in range(3)])
+
+        users.create_index("age", unique=False)
+        users.create_compound_index(["country", "city"], False)
+
+        assert users.indexed_fields() == ["_id", "age", "city", "country"]
+
+        age = users.has_index_on("age")
+        assert age == {
+            "name": "users_age",
+            "fields": ["age"],
+            "unique": False,
+            "sparse": False,
+            "compound": False,
+        }, age
+
+        country = users.has_index_on("country")
+        assert country["name"] == "users_country_city"
+        assert country["fields"] == ["country", "city"]
+        assert country["compound"] is True
+
+        # Second field of a compound index cannot serve queries on its own
+        assert users.has_index_on("city") is None
+        assert users.has_index_on("missing") is None
+        assert users.has_index_on("_id")["unique"] is True
+
+        db.close()
+    print("✓ has_index_on / indexed_fields report logical fields")
+
+
+if __name__ == "__main__":
+    test_index_lookup()
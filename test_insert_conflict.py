@@ -0,0 +1,42 @@
+#!/usr/bin/env python3
+"""Test insert_one(on_conflict=...) with a duplicate _id"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_insert_conflict_policies():
+    """error raises, ignore keeps the stored document, replace overwrites it"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "conflict.mlite"))
+        users = db.collection("users")
+        users.insert_one({"_id": "u1", "name": "Alice", "age": 30})
+
+        try:
+            users.insert_one({"_id": "u1", "name": "Dup"})
+            assert False, "duplicate _id should raise by default"
+        except RuntimeError:
+            pass
+
+        result = users.insert_one({"_id": "u1", "name": "Ignored"}, on_conflict="ignore")
+        assert result["inserted_id"] == "u1"
+        assert users.find_one({"_id": "u1"})["name"] == "Alice"
+
+        users.insert_one({"_id": "u1", "name": "Alicia"}, on_conflict="replace")
+        stored = users.find_one({"_id": "u1"})
+        assert stored["name"] == "Alicia"
+        assert "age" not in stored
+        assert users.count_documents({}) == 1
+
+        try:
+            users.insert_one({"_id": "u2"}, on_conflict="upsert")
+            assert False, "unknown policy should raise"
+        except ValueError:
+            pass
+
+    print("✓ insert_one on_conflict policies")
+
+
+if __name__ == "__main__":
+    test_insert_conflict_policies()
@@ -0,0 +1,49 @@
+#!/usr/bin/env python3
+"""Test insert_if_absent: seeding inserts once, even from concurrent threads"""
+
+import os
+import tempfile
+import threading
+from ironbase import IronBase
+
+
+def test_insert_if_absent_is_idempotent():
+    """A second call with the same query returns the existing document's id"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "seed.mlite"))
+        settings = db.collection("settings")
+
+        first = settings.insert_if_absent({"key": "theme"}, {"key": "theme", "value": "dark"})
+        second = settings.insert_if_absent({"key": "theme"}, {"key": "theme", "value": "light"})
+        assert first["inserted"] is True
+        assert second["inserted"] is False
+        assert second["id"] == first["id"]
+        assert settings.find_one({"key": "theme"})["value"] == "dark"
+        print("✓ Repeated insert_if_absent inserts once")
+
+
+def test_concurrent_insert_if_absent():
+    """Threads racing on the same query produce exactly one insert"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "race.mlite"))
+        settings = db.collection("settings")
+        results = []
+
+        def seed(i):
+            results.append(settings.insert_if_absent({"key": "lang"}, {"key": "lang", "writer": i}))
+
+        threads = [threading.Thread(target=seed, args=(i,)) for i in range(8)]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+
+        assert sum(1 for r in results if r["inserted"]) == 1
+        assert settings.count_documents({"key": "lang"}) == 1
+        print("✓ Concurrent insert_if_absent inserts exactly once")
+
+
+if __name__ == "__main__":
+    test_insert_if_absent_is_idempotent()
+    test_concurrent_insert_if_absent()
+    print("\n✓ All insert_if_absent tests passed")
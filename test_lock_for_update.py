@@ -0,0 +1,41 @@
+#!/usr/bin/env python3
+"""Test lock_for_update: the second writer gets a conflict instead of a lost update"""
+
+import os
+import tempfile
+from ironbase import IronBase, TransactionError
+
+
+def test_second_locker_conflicts():
+    """Two read-modify-write transactions on one document: one commits, one conflicts"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "locks.mlite"))
+        counters = db.collection("counters")
+        doc_id = counters.insert_one({"name": "hits", "value": 0})["inserted_id"]
+
+        first = db.begin_transaction()
+        second = db.begin_transaction()
+
+        db.lock_for_update("counters", doc_id, first)
+        db.update_one_tx("counters", {"_id": doc_id}, {"name": "hits", "value": 1}, first)
+
+        try:
+            db.lock_for_update("counters", doc_id, second)
+            assert False, "second lock should conflict"
+        except TransactionError as e:
+            assert "Write conflict" in str(e)
+        db.rollback_transaction(second)
+
+        db.commit_transaction(first)
+        assert counters.find_one({"_id": doc_id})["value"] == 1
+
+        # The lock was released on commit
+        third = db.begin_transaction()
+        db.lock_for_update("counters", doc_id, third)
+        db.rollback_transaction(third)
+        print("✓ Second locker gets a write conflict")
+
+
+if __name__ == "__main__":
+    test_second_locker_conflicts()
+    print("\n✓ All lock_for_update tests passed")
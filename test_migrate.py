@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""Test collection.migrate() with a Python transform"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_migrate():
+    """Changed documents are rewritten and indexed; an exception writes nothing"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "migrate.mlite"))
+        users = db.collection("users")
+        users.insert_many([
+            {"name": "ann", "role": "admin"},
+            {"name": "bob"},
+            {"name": "cid"},
+        ])
+        users.create_index("role")
+
+        def backfill(doc):
+            doc.setdefault("role", "member")
+
+        assert users.migrate(backfill) == 2
+        assert sorted(d["name"] for d in users.find({"role": "member"})) == ["bob", "cid"]
+        assert users.migrate(backfill) == 0
+
+        # Returning a new dict replaces the document
+        assert users.migrate(lambda doc: {**doc, "active": True}) == 3
+        assert users.count_documents({"active": True}) == 3
+
+        def fail_on_cid(doc):
+            if doc["name"] == "cid":
+                raise ValueError("bad document")
+            doc["flag"] = 1
+
+        try:
+            users.migrate(fail_on_cid)
+            assert False, "transform exception should propagate"
+        except ValueError:
+            pass
+        assert users.count_documents({"flag": 1}) == 0
+
+    print("✓ migrate rewrites documents through a Python callable")
+
+
+if __name__ == "__main__":
+    test_migrate()
@@ -0,0 +1,53 @@
+#!/usr/bin/env python3
+"""Test collection.patch_one() with JSON Patch operations"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_patch_one():
+    """Every op applies in order; a failing test leaves the document unchanged"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "patch.mlite"))
+        items = db.collection("items")
+        items.insert_one({"_id": "p1", "version": 1, "tags": ["a"], "meta": {"w": 1}})
+
+        result = items.patch_one({"_id": "p1"}, [
+            {"op": "test", "path": "/version", "value": 1},
+            {"op": "replace", "path": "/version", "value": 2},
+            {"op": "add", "path": "/tags/-", "value": "b"},
+            {"op": "copy", "from": "/meta", "path": "/backup"},
+            {"op": "move", "from": "/meta/w", "path": "/width"},
+            {"op": "remove", "path": "/tags/0"},
+        ])
+        assert result["matched_count"] == 1 and result["modified_count"] == 1
+
+        doc = items.find_one({"_id": "p1"})
+        assert doc["version"] == 2
+        assert doc["tags"] == ["b"]
+        assert doc["backup"] == {"w": 1}
+        assert doc["meta"] == {}
+        assert doc["width"] == 1
+
+        try:
+            items.patch_one({"_id": "p1"}, [
+                {"op": "remove", "path": "/width"},
+                {"op": "test", "path": "/version", "value": 1},
+            ])
+            assert False, "failing test op should raise"
+        except RuntimeError:
+            pass
+        assert items.find_one({"_id": "p1"}) == doc
+
+        try:
+            items.patch_one({"_id": "p1"}, [{"op": "frobnicate", "path": "/x"}])
+            assert False, "unknown op should raise"
+        except ValueError:
+            pass
+
+    print("✓ patch_one applies JSON Patch atomically")
+
+
+if __name__ == "__main__":
+    test_patch_one()
@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+"""Test that core errors surface as specific Python exception types"""
+
+import os
+import tempfile
+import ironbase
+from ironbase import IronBase, DuplicateKeyError, ValidationError, TransactionError
+
+
+def test_duplicate_key_error():
+    """A unique-constraint violation raises DuplicateKeyError (still a RuntimeError)"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "errors.mlite"))
+        users = db.collection("users")
+        users.create_index("email", unique=True)
+        users.insert_one({"email": "alice@example.com"})
+
+        try:
+            users.insert_one({"email": "alice@example.com"})
+        except DuplicateKeyError as e:
+            assert "Duplicate key" in str(e)
+        else:
+            raise AssertionError("expected DuplicateKeyError")
+
+        # Existing `except RuntimeError` clauses keep working
+        try:
+            users.insert_one({"email": "alice@example.com"})
+        except RuntimeError as e:
+            assert isinstance(e, DuplicateKeyError)
+
+        assert users.count_documents({}) == 1
+        db.close()
+    print("✓ unique violations raise DuplicateKeyError")
+
+
+def test_other_error_types():
+    """Schema and transaction failures map to their own exception types"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "errors.mlite"))
+        users = db.collection("users")
+        users.set_schema({"type": "object", "required": ["name"]})
+
+        try:
+            users.insert_one({"age": 3})
+        except ValidationError:
+            pass
+        else:
+            raise AssertionError("expected ValidationError")
+
+        try:
+            db.commit_transaction(12345)
+        except TransactionError:
+            pass
+        else:
+            raise AssertionError("expected TransactionError")
+
+        for name in ("DuplicateKeyError", "ValidationError", "DocumentNotFoundError", "TransactionError"):
+            assert issubclass(getattr(ironbase, name), RuntimeError), name
+        db.close()
+    print("✓ schema and transaction errors have specific types")
+
+
+if __name__ == "__main__":
+    test_duplicate_key_error()
+    test_other_error_types()
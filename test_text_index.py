@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+"""Test text indexes: create_text_index(), fulltext_search() and $text in find()"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+PRODUCTS = [
+    {"name": "kettle", "description": "Electric kettle with fast boil"},
+    {"name": "charger", "description": "Fast wireless charger, fast charging for phones"},
+    {"name": "cable", "description": "Braided charging cable"},
+    {"name": "fast cable", "description": "Fast charging cable, two metres of braided nylon"},
+    {"name": "lamp", "description": "Desk lamp"},
+]
+
+
+def test_fulltext_search_and_mode():
+    """An AND search returns only documents with every term, best match first"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "text.mlite"))
+        products = db.collection("products")
+        products.insert_many(PRODUCTS)
+
+        name = products.create_text_index(["description"])
+        assert name == "products_description_text"
+        assert name in products.list_indexes()
+
+        hits = products.fulltext_search("Fast charging", mode="and")
+        assert [h["name"] for h in hits] == ["charger", "fast cable"]
+        assert hits[0]["_score"] > hits[1]["_score"] > 0
+
+        # OR mode ranks every document with either term; limit keeps the best
+        assert len(products.fulltext_search("fast charging", mode="or")) == 4
+        top = products.fulltext_search("fast charging", mode="or", limit=1)
+        assert [h["name"] for h in top] == ["charger"]
+
+        # Documents inserted after the index was created are searchable
+        products.insert_one({"name": "bank", "description": "Power bank with fast charging"})
+        names = [h["name"] for h in products.fulltext_search("fast charging")]
+        assert "bank" in names
+        db.close()
+        print("✓ fulltext_search ranks AND matches by relevance")
+
+
+def test_text_query_in_find():
+    """$text in find() narrows through the text index and combines with other filters"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "text.mlite"))
+        products = db.collection("products")
+        products.insert_many(PRODUCTS)
+        products.create_text_index(["description"], tokenizer="standard")
+
+        docs = products.find({"$text": {"$search": "braided"}})
+        assert sorted(d["name"] for d in docs) == ["cable", "fast cable"]
+
+        docs = products.find({"$text": {"$search": "charging"}, "name": {"$ne": "charger"}})
+        assert sorted(d["name"] for d in docs) == ["cable", "fast cable"]
+
+        try:
+            products.create_text_index(["description"], tokenizer="ngram")
+            assert False, "unknown tokenizer should be rejected"
+        except RuntimeError as e:
+            assert "tokenizer" in str(e)
+        db.close()
+        print("✓ $text works in find()")
+
+
+if __name__ == "__main__":
+    test_fulltext_search_and_mode()
+    test_text_query_in_find()
+    print("\n✓ All text index tests passed")
@@ -0,0 +1,77 @@
+#!/usr/bin/env python3
+"""Test db.transaction(): commit on clean exit, rollback when the block raises"""
+
+import os
+import tempfile
+from ironbase import IronBase, TransactionError
+
+
+def test_clean_block_commits():
+    """Writes inside a block that exits normally are committed and survive a reopen"""
+    with tempfile.TemporaryDirectory() as tmp:
+        path = os.path.join(tmp, "tx.mlite")
+        db = IronBase(path)
+        db.collection("accounts").insert_one({"name": "bob", "balance": 10})
+
+        with db.transaction() as tx:
+            tx.insert_one("accounts", {"name": "alice", "balance": 100})
+            tx.update_one("accounts", {"name": "bob"}, {"name": "bob", "balance": 20})
+        db.close()
+
+        db = IronBase(path)
+        accounts = db.collection("accounts")
+        assert accounts.count_documents({}) == 2
+        assert accounts.find_one({"name": "bob"})["balance"] == 20
+        db.close()
+        print("✓ Clean block commits")
+
+
+def test_exception_rolls_back():
+    """An exception inside the block rolls back and propagates"""
+    with tempfile.TemporaryDirectory() as tmp:
+        path = os.path.join(tmp, "tx_rollback.mlite")
+        db = IronBase(path)
+
+        try:
+            with db.transaction() as tx:
+                tx.insert_one("accounts", {"name": "alice", "balance": 100})
+                tx.insert_one("accounts", {"name": "carol", "balance": 5})
+                raise ValueError("boom")
+        except ValueError as e:
+            assert str(e) == "boom"
+        else:
+            assert False, "exception should propagate"
+
+        assert db.collection("accounts").count_documents({}) == 0
+        db.close()
+
+        db = IronBase(path)
+        assert db.collection("accounts").count_documents({}) == 0
+        db.close()
+        print("✓ Exception rolls back")
+
+
+def test_finished_transaction_rejects_writes():
+    """A transaction committed inside its block is not committed twice"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "tx_done.mlite"))
+
+        with db.transaction() as tx:
+            tx.insert_one("accounts", {"name": "alice"})
+            tx.commit()
+            try:
+                tx.delete_one("accounts", {"name": "alice"})
+                assert False, "write after commit should fail"
+            except TransactionError as e:
+                assert "already finished" in str(e)
+
+        assert db.collection("accounts").count_documents({}) == 1
+        db.close()
+        print("✓ Finished transaction rejects writes")
+
+
+if __name__ == "__main__":
+    test_clean_block_commits()
+    test_exception_rolls_back()
+    test_finished_transaction_rejects_writes()
+    print("\n✓ All transaction context manager tests passed")
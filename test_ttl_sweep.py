@@ -0,0 +1,46 @@
+#!/usr/bin/env python3
+"""Test TTL indexes: manual sweeps and the background sweep interval"""
+
+import os
+import tempfile
+import time
+from ironbase import IronBase
+
+
+def test_manual_ttl_sweep():
+    """run_ttl_sweep_now deletes only documents past their expiry"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "ttl.mlite"))
+        sessions = db.collection("sessions")
+        sessions.create_ttl_index("created_at", 60)
+
+        now = int(time.time())
+        sessions.insert_one({"user": "old", "created_at": now - 120})
+        sessions.insert_one({"user": "fresh", "created_at": now})
+
+        assert db.run_ttl_sweep_now() == 1
+        assert [doc["user"] for doc in sessions.find({})] == ["fresh"]
+        print("✓ Manual TTL sweep expires stale documents")
+
+
+def test_background_ttl_sweep():
+    """A background sweeper runs at the configured interval"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "ttl_bg.mlite"))
+        events = db.collection("events")
+        events.create_ttl_index("at", 1)
+        events.insert_one({"at": int(time.time()) - 10})
+
+        db.set_ttl_sweep_interval(1)
+        deadline = time.time() + 5
+        while events.count_documents({}) > 0 and time.time() < deadline:
+            time.sleep(0.1)
+        assert events.count_documents({}) == 0
+        db.set_ttl_sweep_interval(0)
+        print("✓ Background TTL sweep runs at the configured interval")
+
+
+if __name__ == "__main__":
+    test_manual_ttl_sweep()
+    test_background_ttl_sweep()
+    print("\n✓ All TTL sweep tests passed")
@@ -0,0 +1,49 @@
+#!/usr/bin/env python3
+"""Test update_one(..., upsert=True) and $setOnInsert"""
+
+import os
+import tempfile
+from ironbase import IronBase
+
+
+def test_update_upsert():
+    """Nothing matches: insert; a match: update and skip $setOnInsert"""
+    with tempfile.TemporaryDirectory() as tmp:
+        db = IronBase(os.path.join(tmp, "upsert.mlite"))
+        counters = db.collection("counters")
+        update = {"$inc": {"n": 1}, "$setOnInsert": {"created": "mon"}}
+
+        result = counters.update_one({"name": "visits"}, update)
+        assert result["matched_count"] == 0
+        assert result["upserted_id"] is None
+        assert counters.count_documents() == 0
+
+        result = counters.update_one({"name": "visits"}, update, upsert=True)
+        assert result["matched_count"] == 0
+        assert result["modified_count"] == 0
+        upserted_id = result["upserted_id"]
+        assert upserted_id is not None
+
+        doc = counters.find_one({"_id": upserted_id})
+        assert doc["name"] == "visits"
+        assert doc["n"] == 1
+        assert doc["created"] == "mon"
+
+        update = {"$inc": {"n": 1}, "$setOnInsert": {"created": "tue"}}
+        result = counters.update_one({"name": "visits"}, update, upsert=True)
+        assert result["matched_count"] == 1
+        assert result["modified_count"] == 1
+        assert result["upserted_id"] is None
+
+        doc = counters.find_one({"name": "visits"})
+        assert doc["n"] == 2
+        assert doc["created"] == "mon"
+        assert counters.count_documents() == 1
+
+        db.close()
+
+    print("✓ update_one upsert / $setOnInsert")
+
+
+if __name__ == "__main__":
+    test_update_upsert()